@@ -0,0 +1,80 @@
+//! Measures the duration of enumerating a fake `sysfs` tree with many devices.
+//!
+//! Run with:
+//!
+//! ```no_build,no_run
+//! cargo bench --bench enumerate
+//! cargo bench --bench enumerate --features rayon
+//! ```
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use udevrs::testing::{FixtureDevice, SysfsFixture};
+use udevrs::UdevEnumerate;
+
+const SUBSYSTEMS: usize = 32;
+const DEVICES_PER_SUBSYSTEM: usize = 256;
+const ITERATIONS: u32 = 10;
+
+// Lays out `SUBSYSTEMS` classes with `DEVICES_PER_SUBSYSTEM` devices each.
+fn fake_sys_tree() -> udevrs::Result<SysfsFixture> {
+    SysfsFixture::new()?.with_devices((0..SUBSYSTEMS).flat_map(|s| {
+        let subsystem = format!("mock{s}");
+        (0..DEVICES_PER_SUBSYSTEM).map(move |d| {
+            let sysname = format!("{subsystem}dev{d}");
+            FixtureDevice::new(
+                format!("/devices/virtual/{subsystem}/{sysname}"),
+                &subsystem,
+            )
+            .with_devname(&sysname)
+        })
+    }))
+}
+
+fn bench<F: FnMut() -> udevrs::Result<usize>>(name: &str, mut f: F) -> udevrs::Result<()> {
+    let mut found = 0;
+    let mut elapsed = Duration::ZERO;
+
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        found = f()?;
+        elapsed += start.elapsed();
+    }
+
+    println!("{name}: {found} devices, {:?}/scan", elapsed / ITERATIONS);
+
+    Ok(())
+}
+
+fn main() -> udevrs::Result<()> {
+    let fixture = fake_sys_tree()?;
+    let udev = fixture.udev();
+
+    bench("scan_devices (all)", || {
+        let mut enumerate = UdevEnumerate::new(Arc::clone(&udev));
+        enumerate.scan_devices()?;
+        Ok(enumerate.devices().len())
+    })?;
+
+    bench("scan_devices (subsystem)", || {
+        let mut enumerate = UdevEnumerate::new(Arc::clone(&udev));
+        enumerate.add_match_subsystem("mock7")?;
+        enumerate.scan_devices()?;
+        Ok(enumerate.devices().len())
+    })?;
+
+    bench("scan_devices (sysname)", || {
+        let mut enumerate = UdevEnumerate::new(Arc::clone(&udev));
+        enumerate.add_match_sysname("*dev7")?;
+        enumerate.scan_devices()?;
+        Ok(enumerate.devices().len())
+    })?;
+
+    bench("scan_iter (all)", || {
+        let enumerate = UdevEnumerate::new(Arc::clone(&udev));
+        Ok(enumerate.scan_iter().filter(|dev| dev.is_ok()).count())
+    })?;
+
+    Ok(())
+}
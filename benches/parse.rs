@@ -0,0 +1,106 @@
+//! Measures the throughput of parsing netlink event properties into [UdevDevice]s.
+//!
+//! Run with:
+//!
+//! ```no_build,no_run
+//! cargo bench --bench parse
+//! ```
+
+use std::hint::black_box;
+use std::sync::Arc;
+use std::time::Instant;
+
+use udevrs::{Udev, UdevDevice, UdevList};
+
+const ITERATIONS: u32 = 100_000;
+
+const PROPERTIES: [&str; 24] = [
+    "ACTION=add",
+    "DEVPATH=/devices/pci0000:00/0000:00:14.0/usb1/1-2/1-2:1.0/input/input42/event7",
+    "SUBSYSTEM=input",
+    "DEVNAME=/dev/input/event7",
+    "SEQNUM=4242",
+    "USEC_INITIALIZED=123456789",
+    "MAJOR=13",
+    "MINOR=71",
+    "ID_INPUT=1",
+    "ID_INPUT_KEY=1",
+    "ID_INPUT_KEYBOARD=1",
+    "ID_VENDOR=Mock",
+    "ID_VENDOR_ENC=Mock",
+    "ID_VENDOR_ID=1234",
+    "ID_MODEL=Keyboard",
+    "ID_MODEL_ENC=Keyboard",
+    "ID_MODEL_ID=5678",
+    "ID_REVISION=0100",
+    "ID_SERIAL=Mock_Keyboard",
+    "ID_TYPE=hid",
+    "ID_BUS=usb",
+    "ID_USB_INTERFACES=:030101:030000:",
+    "ID_USB_INTERFACE_NUM=00",
+    "ID_PATH=pci-0000:00:14.0-usb-0:2:1.0",
+];
+
+fn main() -> udevrs::Result<()> {
+    let udev = Arc::new(Udev::new());
+
+    let mut buf = Vec::new();
+    for prop in PROPERTIES {
+        buf.extend_from_slice(prop.as_bytes());
+        buf.push(0);
+    }
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(UdevDevice::new_from_nulstr(Arc::clone(&udev), &buf)?);
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "new_from_nulstr: {:.0} events/s, {:?}/event",
+        f64::from(ITERATIONS) / elapsed.as_secs_f64(),
+        elapsed / ITERATIONS
+    );
+
+    let device = UdevDevice::new_from_nulstr(Arc::clone(&udev), &buf)?;
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        for prop in PROPERTIES {
+            let (key, _) = prop.split_once('=').unwrap_or_default();
+            black_box(device.get_property_value(key));
+        }
+    }
+    let elapsed = start.elapsed();
+    let lookups = ITERATIONS * PROPERTIES.len() as u32;
+
+    println!(
+        "get_property_value: {:.0} lookups/s, {:?}/lookup",
+        f64::from(lookups) / elapsed.as_secs_f64(),
+        elapsed / lookups
+    );
+
+    // large lists, e.g. sys attributes, or hwdb properties
+    let mut list = UdevList::new(Arc::clone(&udev));
+    let names: Vec<String> = (0..512).map(|i| format!("ATTR_{i}")).collect();
+    for name in names.iter() {
+        list.add_entry(name, "1");
+    }
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS / 100 {
+        for name in names.iter() {
+            black_box(list.entry_by_name(name));
+        }
+    }
+    let elapsed = start.elapsed();
+    let lookups = ITERATIONS / 100 * names.len() as u32;
+
+    println!(
+        "entry_by_name (512 entries): {:.0} lookups/s, {:?}/lookup",
+        f64::from(lookups) / elapsed.as_secs_f64(),
+        elapsed / lookups
+    );
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+//! Selects the default paths compiled into the library.
+//!
+//! Downstreams with a different filesystem layout, e.g. Android, override the defaults with
+//! environment variables at build time:
+//!
+//! - `UDEVRS_RUN_DIR`: root runtime directory, default: `/run`
+//! - `UDEVRS_SYS_DIR`: `sysfs` mount point, default: `/sys`
+//! - `UDEVRS_LIBEXEC_DIR`: `udev` library directory with the `hwdb.bin`, default: `/usr/lib/udev`
+//! - `UDEVRS_SYSCONF_DIR`: system configuration directory with the `udev/udev.conf`, default: `/etc`
+
+use std::env;
+
+const PATHS: [(&str, &str); 4] = [
+    ("UDEVRS_RUN_DIR", "/run"),
+    ("UDEVRS_SYS_DIR", "/sys"),
+    ("UDEVRS_LIBEXEC_DIR", "/usr/lib/udev"),
+    ("UDEVRS_SYSCONF_DIR", "/etc"),
+];
+
+fn main() {
+    for (var, default) in PATHS {
+        println!("cargo:rerun-if-env-changed={var}");
+
+        let val = env::var(var).unwrap_or(default.into());
+        let path = val.trim_end_matches('/');
+
+        if !path.starts_with('/') || path.contains("/../") || path.ends_with("/..") {
+            panic!("{var} must be an absolute path without `..` components, have: {val}");
+        }
+
+        println!("cargo:rustc-env={var}={path}");
+
+        if var == "UDEVRS_RUN_DIR" {
+            println!("cargo:rustc-env=UDEVRS_UDEV_RUN_DIR={path}/udev");
+        }
+
+        if var == "UDEVRS_SYSCONF_DIR" {
+            println!("cargo:rustc-env=UDEVRS_UDEV_CONF={path}/udev/udev.conf");
+        }
+    }
+}
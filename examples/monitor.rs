@@ -1,6 +1,8 @@
 use std::sync::Arc;
 use std::{thread, time};
 
+use udevrs::DeviceAction;
+
 fn main() -> udevrs::Result<()> {
     env_logger::init();
     let udev = Arc::new(udevrs::Udev::new());
@@ -9,7 +11,14 @@ fn main() -> udevrs::Result<()> {
     monitor.enable_receiving()?;
 
     loop {
-        println!("{:?}", monitor.receive_device());
+        match monitor.receive_device() {
+            Ok(device) => match device.action_type() {
+                DeviceAction::Add => println!("added: {}", device.syspath()),
+                DeviceAction::Remove => println!("removed: {}", device.syspath()),
+                action => println!("{action}: {}", device.syspath()),
+            },
+            Err(err) => println!("{err:?}"),
+        }
 
         thread::sleep(time::Duration::from_secs(1));
     }
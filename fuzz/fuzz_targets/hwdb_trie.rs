@@ -0,0 +1,40 @@
+//! Fuzzes the hardware database trie parser with corrupt `hwdb.bin` contents.
+//!
+//! Run with `cargo fuzz run hwdb_trie`, seeding the corpus with `tests/hwdb.bin`, e.g.:
+//!
+//! ```no_build,no_run
+//! mkdir -p fuzz/corpus/hwdb_trie && cp tests/hwdb.bin fuzz/corpus/hwdb_trie/
+//! cargo +nightly fuzz run hwdb_trie -- -max_len=16777216
+//! ```
+
+#![no_main]
+
+use std::sync::Arc;
+
+use libfuzzer_sys::fuzz_target;
+use udevrs::{TrieEntry, TrieReader, Udev, UdevHwdb};
+
+const MODALIASES: [&str; 4] = [
+    "usb:v1D6Bp0002d0606dc09dsc00dp01ic09isc00ip00in00",
+    "pci:v00008086d00001C2D*",
+    "input:b0003v046Dp C52Be0111",
+    "",
+];
+
+fuzz_target!(|data: &[u8]| {
+    // raw entries, and strings, at arbitrary offsets
+    let reader = TrieReader::new(data);
+    let _ = TrieEntry::try_from(data);
+    let _ = reader.read_entry(data.len() / 2);
+    let _ = reader.read_string(data.len() / 2);
+
+    let udev = Arc::new(Udev::new());
+
+    if let Ok(mut hwdb) = UdevHwdb::new_from_bytes(udev, data.to_vec()) {
+        for modalias in MODALIASES {
+            let _ = hwdb.query(modalias);
+        }
+
+        let _ = UdevHwdb::parse_nodes(hwdb.header(), hwdb.as_bytes()).count();
+    }
+});
@@ -0,0 +1,153 @@
+use std::collections::VecDeque;
+
+use udevrs::{Error, Result};
+
+/// Command line argument, either an option, or a positional argument.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Arg {
+    /// Option name without the leading dashes, and its inline value, e.g. `--action=add`.
+    Opt(String, Option<String>),
+    /// Positional argument.
+    Pos(String),
+}
+
+/// Minimal `getopt_long`-style argument parser.
+///
+/// Supports `--name`, `--name=value`, `--name value`, `-n`, `-nvalue`, `-n value`, and bundled
+/// short flags, e.g. `-vn`. Whether an option takes a value is decided by the subcommand, see
+/// [set_values](Self::set_values), and [value](Self::value).
+pub struct Args {
+    args: VecDeque<String>,
+    // remaining flags of a bundled short option argument
+    bundle: String,
+    values: &'static str,
+}
+
+impl Args {
+    /// Creates a new [Args] from the process arguments, skipping the program name.
+    pub fn from_env() -> Self {
+        Self::new(std::env::args().skip(1))
+    }
+
+    /// Creates a new [Args] from a list of arguments.
+    pub fn new<I: IntoIterator<Item = String>>(args: I) -> Self {
+        Self {
+            args: args.into_iter().collect(),
+            bundle: String::new(),
+            values: "",
+        }
+    }
+
+    /// Sets the short options that take a value, like the `getopt` option string without the
+    /// `:` separators, e.g. `"st"`.
+    ///
+    /// The rest of a short option argument is the value of these options, e.g. `-sblock`, and
+    /// further flags for all other options, e.g. `-vn`.
+    pub fn set_values(&mut self, values: &'static str) {
+        self.values = values;
+    }
+
+    /// Gets the next [Arg].
+    pub fn next_arg(&mut self) -> Option<Arg> {
+        if !self.bundle.is_empty() {
+            let bundle = std::mem::take(&mut self.bundle);
+            return Some(self.short_opt(&bundle));
+        }
+
+        let arg = self.args.pop_front()?;
+
+        if arg == "--" || arg == "-" || !arg.starts_with('-') {
+            Some(Arg::Pos(arg))
+        } else if let Some(long) = arg.strip_prefix("--") {
+            match long.split_once('=') {
+                Some((name, value)) => Some(Arg::Opt(name.into(), Some(value.into()))),
+                None => Some(Arg::Opt(long.into(), None)),
+            }
+        } else {
+            Some(self.short_opt(&arg[1..]))
+        }
+    }
+
+    // Splits the first flag off the short option argument, keeping the rest as its value, or as
+    // the next bundled flags.
+    fn short_opt(&mut self, short: &str) -> Arg {
+        let split = short.chars().next().map(char::len_utf8).unwrap_or(0);
+        let (name, rest) = short.split_at(split);
+
+        if rest.is_empty() {
+            Arg::Opt(name.into(), None)
+        } else if self.values.contains(name) {
+            Arg::Opt(name.into(), Some(rest.into()))
+        } else {
+            self.bundle = rest.into();
+            Arg::Opt(name.into(), None)
+        }
+    }
+
+    /// Gets the value of the option `name`, either the inline value, or the next argument.
+    ///
+    /// Returns: `Ok(String)` on success, `Err(Error)` if the value is missing.
+    pub fn value(&mut self, name: &str, inline: Option<String>) -> Result<String> {
+        inline
+            .or_else(|| self.args.pop_front())
+            .ok_or(Error::Udev(format!("option '{name}' requires an argument")))
+    }
+}
+
+/// Creates the [Error] for an unknown option, or argument.
+pub fn unknown(arg: Arg) -> Error {
+    match arg {
+        Arg::Opt(name, _) if name.len() == 1 => Error::Udev(format!("invalid option -- '{name}'")),
+        Arg::Opt(name, _) => Error::Udev(format!("unrecognized option '--{name}'")),
+        Arg::Pos(arg) => Error::Udev(format!("unexpected argument '{arg}'")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args() -> Result<()> {
+        let mut args = Args::new(
+            [
+                "--action=add",
+                "-s",
+                "block",
+                "-qall",
+                "-vn",
+                "-vsnet",
+                "--name",
+                "sda",
+                "/dev/sdb",
+            ]
+            .map(String::from),
+        );
+        args.set_values("sq");
+
+        assert_eq!(
+            args.next_arg(),
+            Some(Arg::Opt("action".into(), Some("add".into())))
+        );
+        assert_eq!(args.next_arg(), Some(Arg::Opt("s".into(), None)));
+        assert_eq!(args.value("s", None)?, "block");
+        assert_eq!(
+            args.next_arg(),
+            Some(Arg::Opt("q".into(), Some("all".into())))
+        );
+        assert_eq!(args.next_arg(), Some(Arg::Opt("v".into(), None)));
+        assert_eq!(args.next_arg(), Some(Arg::Opt("n".into(), None)));
+        assert_eq!(args.next_arg(), Some(Arg::Opt("v".into(), None)));
+        assert_eq!(
+            args.next_arg(),
+            Some(Arg::Opt("s".into(), Some("net".into())))
+        );
+        assert_eq!(args.next_arg(), Some(Arg::Opt("name".into(), None)));
+        assert_eq!(args.value("name", None)?, "sda");
+        assert_eq!(args.next_arg(), Some(Arg::Pos("/dev/sdb".into())));
+        assert_eq!(args.next_arg(), None);
+        assert!(args.value("name", None).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use udevrs::{Error, Result, Udev, UdevHwdb};
+
+use crate::args::{self, Arg, Args};
+
+const USAGE: &str = "\
+udevrs hwdb [OPTIONS] query MODALIAS
+
+Query the hardware database.
+
+Options:
+  -h --help           Show this help
+  -t --test=MODALIAS  Query the database, like 'query MODALIAS'";
+
+/// Runs the `hwdb` subcommand.
+pub fn run(udev: Arc<Udev>, mut args: Args) -> Result<ExitCode> {
+    args.set_values("t");
+
+    let mut modalias = None;
+
+    while let Some(arg) = args.next_arg() {
+        match arg {
+            Arg::Opt(name, value) if name == "t" || name == "test" => {
+                modalias = Some(args.value(&name, value)?);
+            }
+            Arg::Opt(name, _) if name == "h" || name == "help" => {
+                println!("{USAGE}");
+                return Ok(ExitCode::SUCCESS);
+            }
+            Arg::Pos(cmd) if cmd == "query" && modalias.is_none() => {
+                modalias = Some(args.value("query", None)?);
+            }
+            arg => return Err(args::unknown(arg)),
+        }
+    }
+
+    let modalias = modalias.ok_or(Error::Udev("missing MODALIAS, see --help".into()))?;
+
+    let mut hwdb = UdevHwdb::new(udev)?;
+    if let Some(properties) = hwdb.query(modalias.as_str()) {
+        for entry in properties.iter() {
+            println!("{}={}", entry.name(), entry.value());
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
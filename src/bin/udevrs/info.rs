@@ -0,0 +1,304 @@
+use std::fs;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use udevrs::{Error, Result, SysPath, Udev, UdevDevice, UdevEnumerate};
+
+use crate::args::{self, Arg, Args};
+
+const USAGE: &str = "\
+udevrs info [OPTIONS] [DEVPATH|FILE]
+
+Query sysfs or the udev database.
+
+Options:
+  -h --help             Show this help
+  -q --query=TYPE       Query device information:
+       name               Name of device node
+       symlink            Pointing to node
+       path               sysfs device path
+       property           The device properties
+       all                All values
+  -p --path=SYSPATH     sysfs device path used for query or attribute walk
+  -n --name=NAME        Node or symlink name used for query or attribute walk
+  -r --root             Prepend dev directory to path names
+  -a --attribute-walk   Print all key matches walking along the chain
+                        of parent devices
+  -e --export-db        Export the content of the udev database";
+
+/// Device information selected with `--query`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Query {
+    Name,
+    Symlink,
+    Path,
+    Property,
+    All,
+}
+
+/// Runs the `info` subcommand.
+pub fn run(udev: Arc<Udev>, mut args: Args) -> Result<ExitCode> {
+    args.set_values("qpn");
+
+    let mut query = Query::All;
+    let mut root = false;
+    let mut walk = false;
+    let mut devices = Vec::new();
+
+    while let Some(arg) = args.next_arg() {
+        match arg {
+            Arg::Opt(name, value) if name == "q" || name == "query" => {
+                query = match args.value(&name, value)?.as_str() {
+                    "name" => Query::Name,
+                    "symlink" => Query::Symlink,
+                    "path" => Query::Path,
+                    "property" | "env" => Query::Property,
+                    "all" => Query::All,
+                    q => return Err(Error::Udev(format!("unknown query type '{q}'"))),
+                };
+            }
+            Arg::Opt(name, value)
+                if name == "p" || name == "path" || name == "n" || name == "name" =>
+            {
+                devices.push(args.value(&name, value)?);
+            }
+            Arg::Opt(name, _) if name == "r" || name == "root" => root = true,
+            Arg::Opt(name, _) if name == "a" || name == "attribute-walk" => walk = true,
+            Arg::Opt(name, _) if name == "e" || name == "export-db" => {
+                return export_db(udev);
+            }
+            Arg::Opt(name, _) if name == "h" || name == "help" => {
+                println!("{USAGE}");
+                return Ok(ExitCode::SUCCESS);
+            }
+            Arg::Pos(device) => devices.push(device),
+            arg => return Err(args::unknown(arg)),
+        }
+    }
+
+    if devices.is_empty() {
+        return Err(Error::Udev("a device name or path is required".into()));
+    }
+
+    for (i, arg) in devices.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+
+        let mut device = device_from_arg(Arc::clone(&udev), arg)?;
+
+        if walk {
+            attribute_walk(&mut device)?;
+        } else {
+            print_query(&mut device, query, root);
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+// Looks up the device by `/dev` node, `/sys` path, or devpath, like `udevadm info`.
+fn device_from_arg(udev: Arc<Udev>, arg: &str) -> Result<UdevDevice> {
+    let sys = udev.get_sys_path().to_owned();
+    let dev = udev.get_dev_path().to_owned();
+
+    if arg.starts_with(sys.as_str()) {
+        UdevDevice::new_from_syspath(Arc::clone(&udev), &SysPath::new_in(sys.as_str(), arg)?)
+    } else if arg.starts_with("/devices/") {
+        let syspath = format!("{sys}{arg}");
+        UdevDevice::new_from_syspath(Arc::clone(&udev), &SysPath::new_in(sys.as_str(), syspath)?)
+    } else {
+        let node = if arg.starts_with('/') {
+            arg.to_owned()
+        } else {
+            format!("{dev}/{arg}")
+        };
+
+        let meta = fs::metadata(node.as_str())
+            .map_err(|err| Error::Udev(format!("unable to access {node}: {err}")))?;
+        let devtype = if meta.file_type().is_block_device() {
+            "b"
+        } else if meta.file_type().is_char_device() {
+            "c"
+        } else {
+            return Err(Error::Udev(format!("not a device node: {node}")));
+        };
+
+        UdevDevice::new_from_devnum(udev, devtype, meta.rdev())
+    }
+}
+
+// Collects the device properties, like the `E:` lines of `udevadm info`.
+fn properties(device: &mut UdevDevice) -> Vec<(String, String)> {
+    device.read_uevent_file().ok();
+    if let Err(err) = device.read_db() {
+        log::debug!("no database entry for {}: {err}", device.syspath());
+    }
+
+    let mut props = vec![("DEVPATH".to_owned(), device.devpath().to_owned())];
+
+    for (key, value) in [
+        ("SUBSYSTEM", device.get_subsystem().to_owned()),
+        ("DEVTYPE", device.get_devtype().to_owned()),
+        ("DRIVER", device.get_driver().unwrap_or_default().to_owned()),
+        ("DEVNAME", devnode(device).unwrap_or_default()),
+    ] {
+        if !value.is_empty() {
+            props.push((key.into(), value));
+        }
+    }
+
+    let devnum = device.get_devnum();
+    if devnum > 0 {
+        props.push(("MAJOR".into(), libc::major(devnum).to_string()));
+        props.push(("MINOR".into(), libc::minor(devnum).to_string()));
+    }
+
+    if device.get_ifindex() > 0 {
+        props.push(("IFINDEX".into(), device.get_ifindex().to_string()));
+    }
+
+    for entry in device.properties_list().iter() {
+        if !props.iter().any(|(key, _)| key == entry.name()) {
+            props.push((entry.name().into(), entry.value().into()));
+        }
+    }
+
+    props
+}
+
+fn print_query(device: &mut UdevDevice, query: Query, root: bool) {
+    let dev = device.udev().get_dev_path().to_owned();
+    let strip = |path: &str| -> String {
+        if root {
+            path.to_owned()
+        } else {
+            path.strip_prefix(dev.as_str())
+                .map(|p| p.trim_start_matches('/'))
+                .unwrap_or(path)
+                .to_owned()
+        }
+    };
+
+    let props = properties(device);
+    let devnode = devnode(device).as_deref().map(strip);
+    let links: Vec<String> = device
+        .devlinks_list()
+        .iter()
+        .map(|l| strip(l.name()))
+        .collect();
+
+    match query {
+        Query::Name => {
+            if let Some(node) = devnode {
+                println!("{node}");
+            }
+        }
+        Query::Symlink => println!("{}", links.join(" ")),
+        Query::Path => println!("{}", device.devpath()),
+        Query::Property => props.iter().for_each(|(k, v)| println!("{k}={v}")),
+        Query::All => {
+            println!("P: {}", device.devpath());
+            println!("M: {}", sysname(device.syspath()));
+            println!("U: {}", device.get_subsystem());
+            if !device.get_devtype().is_empty() {
+                println!("T: {}", device.get_devtype());
+            }
+            if let Some(node) = devnode {
+                println!("N: {node}");
+            }
+            if device.devlink_priority() != 0 {
+                println!("L: {}", device.devlink_priority());
+            }
+            links.iter().for_each(|link| println!("S: {link}"));
+            props.iter().for_each(|(k, v)| println!("E: {k}={v}"));
+        }
+    }
+}
+
+fn export_db(udev: Arc<Udev>) -> Result<ExitCode> {
+    let mut enumerate = UdevEnumerate::new(Arc::clone(&udev));
+    enumerate.scan_devices()?;
+
+    for dev in enumerate.devices() {
+        let syspath = SysPath::new_in(udev.get_sys_path(), dev.syspath())?;
+        if let Ok(mut device) = UdevDevice::new_from_syspath(Arc::clone(&udev), &syspath) {
+            print_query(&mut device, Query::All, false);
+            println!();
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn attribute_walk(device: &mut UdevDevice) -> Result<()> {
+    println!(
+        "
+Udevadm info starts with the device specified by the devpath and then
+walks up the chain of parent devices. It prints for every device
+found, all possible attributes in the udev rules key format.
+A rule to match, can be composed by the attributes of the device
+and the attributes from one single parent device.
+"
+    );
+
+    println!("  looking at device '{}':", device.devpath());
+    print_attributes(device, "")?;
+
+    // the `/devices` root is not a device
+    for mut parent in device.ancestors().take_while(|p| p.devpath() != "/devices") {
+        println!("  looking at parent device '{}':", parent.devpath());
+        print_attributes(&mut parent, "S")?;
+    }
+
+    Ok(())
+}
+
+fn print_attributes(device: &mut UdevDevice, suffix: &str) -> Result<()> {
+    println!("    KERNEL{suffix}==\"{}\"", sysname(device.syspath()));
+    println!("    SUBSYSTEM{suffix}==\"{}\"", device.get_subsystem());
+    println!(
+        "    DRIVER{suffix}==\"{}\"",
+        device.get_driver().unwrap_or_default()
+    );
+
+    device.get_sysattr_list_read()?;
+    let mut names: Vec<String> = device
+        .sysattr_list()
+        .iter()
+        .map(|e| e.name().to_owned())
+        .filter(|name| name != "uevent" && name != "descriptors")
+        .collect();
+    names.sort();
+
+    for name in names {
+        let Some(value) = device.get_sysattr_value(name.as_str()) else {
+            continue;
+        };
+        let value = value.trim_end_matches('\n');
+
+        // skip binary, and multi-line values, like udevadm
+        if value.len() <= 4096 && value.chars().all(|c| !c.is_control() || c == '\t') {
+            println!("    ATTR{}{{{name}}}==\"{value}\"", suffix);
+        }
+    }
+    println!();
+
+    Ok(())
+}
+
+// Gets the absolute device node path, `uevent` files have the name relative to `/dev`.
+fn devnode(device: &UdevDevice) -> Option<String> {
+    device.get_devnode().map(|node| {
+        if node.starts_with('/') {
+            node.to_owned()
+        } else {
+            format!("{}/{node}", device.udev().get_dev_path())
+        }
+    })
+}
+
+fn sysname(syspath: &str) -> &str {
+    syspath.rsplit('/').next().unwrap_or_default()
+}
@@ -0,0 +1,76 @@
+//! `udevadm`-compatible diagnostic tool built on the `udevrs` library.
+//!
+//! Implements the `info`, `monitor`, `trigger`, `settle`, and `hwdb` subcommands, to compare the
+//! library view of the system against `udevadm`.
+
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use udevrs::{Result, Udev};
+
+mod args;
+mod hwdb;
+mod info;
+mod monitor;
+mod settle;
+mod trigger;
+
+use args::{Arg, Args};
+
+const USAGE: &str = "\
+udevrs [--help] [--version] COMMAND [OPTIONS]
+
+Send control commands or test the device manager.
+
+Commands:
+  info          Query sysfs or the udev database
+  monitor       Listen to kernel and udev events
+  trigger       Request events from the kernel
+  settle        Wait for pending udev events
+  hwdb          Query the hardware database
+
+Run 'udevrs COMMAND --help' for the command options.";
+
+fn main() -> ExitCode {
+    env_logger::init();
+
+    // exit quietly when the output is closed, e.g. piped to `head`, like `udevadm`
+    // SAFETY: restoring the default `SIGPIPE` action has no preconditions.
+    unsafe { libc::signal(libc::SIGPIPE, libc::SIG_DFL) };
+
+    match run(Args::from_env()) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("udevrs: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(mut args: Args) -> Result<ExitCode> {
+    let udev = Arc::new(Udev::new());
+
+    match args.next_arg() {
+        Some(Arg::Pos(cmd)) => match cmd.as_str() {
+            "info" => info::run(udev, args),
+            "monitor" => monitor::run(udev, args),
+            "trigger" => trigger::run(udev, args),
+            "settle" => settle::run(udev, args),
+            "hwdb" => hwdb::run(udev, args),
+            _ => Err(udevrs::Error::Udev(format!("unknown command '{cmd}'"))),
+        },
+        Some(Arg::Opt(name, _)) if name == "V" || name == "version" => {
+            println!("{}", env!("CARGO_PKG_VERSION"));
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(Arg::Opt(name, _)) if name == "h" || name == "help" => {
+            println!("{USAGE}");
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(arg) => Err(args::unknown(arg)),
+        None => {
+            eprintln!("{USAGE}");
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::io::{self, Write};
+use std::os::fd::AsRawFd;
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use udevrs::{Error, ErrorKind, Result, Udev, UdevDevice, UdevMonitor};
+
+use crate::args::{self, Arg, Args};
+
+const USAGE: &str = "\
+udevrs monitor [OPTIONS]
+
+Listen to kernel and udev events.
+
+Options:
+  -h --help                                Show this help
+  -p --property                            Print the event properties
+  -k --kernel                              Print kernel uevents
+  -u --udev                                Print udev events
+  -s --subsystem-match=SUBSYSTEM[/DEVTYPE] Filter events by subsystem
+  -t --tag-match=TAG                       Filter events by tag";
+
+/// Runs the `monitor` subcommand.
+pub fn run(udev: Arc<Udev>, mut args: Args) -> Result<ExitCode> {
+    args.set_values("st");
+
+    let mut print_kernel = false;
+    let mut print_udev = false;
+    let mut properties = false;
+    let mut subsystems = Vec::new();
+    let mut tags = Vec::new();
+
+    while let Some(arg) = args.next_arg() {
+        match arg {
+            Arg::Opt(name, _) if name == "p" || name == "property" || name == "e" => {
+                properties = true
+            }
+            Arg::Opt(name, _) if name == "k" || name == "kernel" => print_kernel = true,
+            Arg::Opt(name, _) if name == "u" || name == "udev" => print_udev = true,
+            Arg::Opt(name, value) if name == "s" || name == "subsystem-match" => {
+                subsystems.push(args.value(&name, value)?);
+            }
+            Arg::Opt(name, value) if name == "t" || name == "tag-match" => {
+                tags.push(args.value(&name, value)?);
+            }
+            Arg::Opt(name, _) if name == "h" || name == "help" => {
+                println!("{USAGE}");
+                return Ok(ExitCode::SUCCESS);
+            }
+            arg => return Err(args::unknown(arg)),
+        }
+    }
+
+    if !print_kernel && !print_udev {
+        print_kernel = true;
+        print_udev = true;
+    }
+
+    let mut monitors = Vec::with_capacity(2);
+    for (source, enabled) in [("KERNEL", print_kernel), ("UDEV", print_udev)] {
+        if !enabled {
+            continue;
+        }
+
+        let group = if source == "KERNEL" { "kernel" } else { "udev" };
+        let mut monitor = UdevMonitor::new_from_netlink(Arc::clone(&udev), group)?;
+
+        for subsystem in subsystems.iter() {
+            let (subsystem, devtype) = subsystem.split_once('/').unwrap_or((subsystem, ""));
+            monitor.filter_add_match_subsystem_devtype(subsystem, devtype)?;
+        }
+        for tag in tags.iter() {
+            monitor.filter_add_match_tag(tag)?;
+        }
+
+        monitor.set_receive_buffer_size(128 * 1024 * 1024).ok();
+        monitor.enable_receiving()?;
+
+        monitors.push((source, monitor));
+    }
+
+    println!("monitor will print the received events for:");
+    for (source, _) in monitors.iter() {
+        match *source {
+            "KERNEL" => println!("KERNEL - the kernel uevent"),
+            _ => println!("UDEV - the event which udev sends out after rule processing"),
+        }
+    }
+    println!();
+
+    let mut pfd: Vec<libc::pollfd> = monitors
+        .iter()
+        .map(|(_, monitor)| libc::pollfd {
+            fd: monitor.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        })
+        .collect();
+
+    loop {
+        // SAFETY: `pfd` is a valid, initialized list, and the length matches.
+        let ret = unsafe { libc::poll(pfd.as_mut_ptr(), pfd.len() as libc::nfds_t, -1) };
+        if ret < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(err.into());
+        }
+
+        for (pfd, (source, monitor)) in pfd.iter_mut().zip(monitors.iter_mut()) {
+            if pfd.revents & libc::POLLIN == 0 {
+                continue;
+            }
+            pfd.revents = 0;
+
+            match monitor.receive_device() {
+                Ok(device) => print_device(source, &device, properties),
+                Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::Filtered) => {}
+                Err(err @ Error::Overflow(_)) => eprintln!("udevrs: {err}"),
+                Err(err) => return Err(err),
+            }
+        }
+
+        io::stdout().flush().ok();
+    }
+}
+
+fn print_device(source: &str, device: &UdevDevice, properties: bool) {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `ts` is a valid, initialized `timespec`.
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+
+    println!(
+        "{source:<6}[{}.{:06}] {:<8} {} ({})",
+        ts.tv_sec,
+        ts.tv_nsec / 1000,
+        device.action(),
+        device.devpath(),
+        device.subsystem()
+    );
+
+    if properties {
+        for entry in device.properties_list().iter() {
+            println!("{}={}", entry.name(), entry.value());
+        }
+        println!();
+    }
+}
@@ -0,0 +1,70 @@
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::{path, time};
+
+use udevrs::{Error, Result, Udev, UdevQueue};
+
+use crate::args::{self, Arg, Args};
+
+const USAGE: &str = "\
+udevrs settle [OPTIONS]
+
+Wait for pending udev events.
+
+Options:
+  -h --help                 Show this help
+  -t --timeout=SEC          Maximum time to wait for events (default: 120)
+  -E --exit-if-exists=FILE  Stop waiting if FILE exists";
+
+/// Runs the `settle` subcommand.
+pub fn run(udev: Arc<Udev>, mut args: Args) -> Result<ExitCode> {
+    args.set_values("tE");
+
+    let mut timeout = time::Duration::from_secs(120);
+    let mut exit_if_exists = None;
+
+    while let Some(arg) = args.next_arg() {
+        match arg {
+            Arg::Opt(name, value) if name == "t" || name == "timeout" => {
+                let secs = args.value(&name, value)?;
+                timeout = secs
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|s| s.is_finite() && *s >= 0.0)
+                    .map(time::Duration::from_secs_f64)
+                    .ok_or(Error::Udev(format!("invalid timeout value '{secs}'")))?;
+            }
+            Arg::Opt(name, value) if name == "E" || name == "exit-if-exists" => {
+                exit_if_exists = Some(args.value(&name, value)?);
+            }
+            Arg::Opt(name, _) if name == "h" || name == "help" => {
+                println!("{USAGE}");
+                return Ok(ExitCode::SUCCESS);
+            }
+            arg => return Err(args::unknown(arg)),
+        }
+    }
+
+    let mut queue = UdevQueue::new(udev);
+    let deadline = time::Instant::now() + timeout;
+
+    loop {
+        if exit_if_exists
+            .as_ref()
+            .is_some_and(|file| path::Path::new(file).exists())
+        {
+            return Ok(ExitCode::SUCCESS);
+        }
+
+        // wake up at least every second to check the exit file
+        let remaining = deadline.saturating_duration_since(time::Instant::now());
+        if queue.settle(remaining.min(time::Duration::from_secs(1)))? {
+            return Ok(ExitCode::SUCCESS);
+        }
+
+        if time::Instant::now() >= deadline {
+            eprintln!("udevrs: timeout waiting for the udev event queue");
+            return Ok(ExitCode::FAILURE);
+        }
+    }
+}
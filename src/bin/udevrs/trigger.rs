@@ -0,0 +1,134 @@
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use udevrs::{Error, Result, Udev, UdevEnumerate};
+
+use crate::args::{self, Arg, Args};
+
+const USAGE: &str = "\
+udevrs trigger [OPTIONS] [DEVPATH...]
+
+Request events from the kernel.
+
+Options:
+  -h --help                         Show this help
+  -v --verbose                      Print the list of devices while running
+  -n --dry-run                      Do not actually trigger the events
+  -t --type=TYPE                    Type of events to trigger: devices, or subsystems
+  -c --action=ACTION                Event action value, default: change
+  -s --subsystem-match=SUBSYSTEM    Trigger devices from a matching subsystem
+  -S --subsystem-nomatch=SUBSYSTEM  Exclude devices from a matching subsystem
+  -a --attr-match=FILE[=VALUE]      Trigger devices with a matching attribute
+  -A --attr-nomatch=FILE[=VALUE]    Exclude devices with a matching attribute
+  -p --property-match=KEY=VALUE     Trigger devices with a matching property
+  -g --tag-match=TAG                Trigger devices with a matching tag
+  -y --sysname-match=NAME           Trigger devices with this /sys path";
+
+/// Runs the `trigger` subcommand.
+pub fn run(udev: Arc<Udev>, mut args: Args) -> Result<ExitCode> {
+    args.set_values("tcsSaApgy");
+
+    let mut enumerate = UdevEnumerate::new(Arc::clone(&udev));
+    let mut action = String::from("change");
+    let mut subsystems = false;
+    let mut verbose = false;
+    let mut dry_run = false;
+    let mut devpaths = Vec::new();
+
+    while let Some(arg) = args.next_arg() {
+        match arg {
+            Arg::Opt(name, _) if name == "v" || name == "verbose" => verbose = true,
+            Arg::Opt(name, _) if name == "n" || name == "dry-run" => dry_run = true,
+            Arg::Opt(name, value) if name == "t" || name == "type" => {
+                subsystems = match args.value(&name, value)?.as_str() {
+                    "devices" => false,
+                    "subsystems" => true,
+                    ty => return Err(Error::Udev(format!("unknown type --type={ty}"))),
+                };
+            }
+            Arg::Opt(name, value) if name == "c" || name == "action" => {
+                action = args.value(&name, value)?;
+            }
+            Arg::Opt(name, value) if name == "s" || name == "subsystem-match" => {
+                enumerate.add_match_subsystem(args.value(&name, value)?.as_str())?;
+            }
+            Arg::Opt(name, value) if name == "S" || name == "subsystem-nomatch" => {
+                enumerate.add_nomatch_subsystem(args.value(&name, value)?.as_str())?;
+            }
+            Arg::Opt(name, value) if name == "a" || name == "attr-match" => {
+                let attr = args.value(&name, value)?;
+                let (file, val) = attr
+                    .split_once('=')
+                    .map_or((attr.as_str(), None), |(file, val)| (file, Some(val)));
+                enumerate.add_match_sysattr(file, val)?;
+            }
+            Arg::Opt(name, value) if name == "A" || name == "attr-nomatch" => {
+                let attr = args.value(&name, value)?;
+                let (file, val) = attr
+                    .split_once('=')
+                    .map_or((attr.as_str(), None), |(file, val)| (file, Some(val)));
+                enumerate.add_nomatch_sysattr(file, val)?;
+            }
+            Arg::Opt(name, value) if name == "p" || name == "property-match" => {
+                let property = args.value(&name, value)?;
+                let (key, val) = property.split_once('=').ok_or(Error::Udev(format!(
+                    "invalid property match '{property}', expected KEY=VALUE"
+                )))?;
+                enumerate.add_match_property(key, val)?;
+            }
+            Arg::Opt(name, value) if name == "g" || name == "tag-match" => {
+                enumerate.add_match_tag(args.value(&name, value)?.as_str())?;
+            }
+            Arg::Opt(name, value) if name == "y" || name == "sysname-match" => {
+                enumerate.add_match_sysname(args.value(&name, value)?.as_str())?;
+            }
+            Arg::Opt(name, _) if name == "h" || name == "help" => {
+                println!("{USAGE}");
+                return Ok(ExitCode::SUCCESS);
+            }
+            Arg::Pos(devpath) => devpaths.push(devpath),
+            arg => return Err(args::unknown(arg)),
+        }
+    }
+
+    if devpaths.is_empty() {
+        if subsystems {
+            enumerate.scan_subsystems()?;
+        } else {
+            enumerate.scan_devices()?;
+        }
+    } else {
+        let sys = udev.get_sys_path();
+        for devpath in devpaths {
+            let syspath = if devpath.starts_with(sys) {
+                devpath
+            } else {
+                format!("{sys}{devpath}")
+            };
+            enumerate.add_syspath(syspath.as_str())?;
+        }
+    }
+
+    if dry_run {
+        if verbose {
+            enumerate
+                .devices()
+                .iter()
+                .for_each(|dev| println!("{}", dev.syspath()));
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let mut code = ExitCode::SUCCESS;
+    for (syspath, res) in enumerate.trigger_all(action.as_str()) {
+        if verbose {
+            println!("{syspath}");
+        }
+        if let Err(err) = res {
+            eprintln!("udevrs: failed to trigger {syspath}: {err}");
+            code = ExitCode::FAILURE;
+        }
+    }
+
+    Ok(code)
+}
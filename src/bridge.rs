@@ -0,0 +1,593 @@
+//! Event bridge over an abstract Unix socket, for consumers without netlink access.
+//!
+//! Hardened sandboxes often block `NETLINK_KOBJECT_UEVENT` sockets. A privileged
+//! [EventBridgeServer] receives the events of a [UdevMonitor], and forwards them over an abstract
+//! Unix socket. Sandboxed processes connect with an [EventBridgeClient], which receives devices
+//! like a [UdevMonitor]:
+//!
+//! ```no_run
+//! # use std::sync::Arc;
+//! # use udevrs::bridge::{EventBridgeClient, EventBridgeServer, DEFAULT_BRIDGE_NAME};
+//! # use udevrs::{Udev, UdevMonitor};
+//! # fn main() -> udevrs::Result<()> {
+//! let udev = Arc::new(Udev::new());
+//!
+//! // privileged broker
+//! let mut monitor = UdevMonitor::new_from_netlink(Arc::clone(&udev), "udev")?;
+//! monitor.enable_receiving()?;
+//! let mut server = EventBridgeServer::bind(monitor, DEFAULT_BRIDGE_NAME)?;
+//! std::thread::spawn(move || server.run());
+//!
+//! // sandboxed consumer
+//! let mut client = EventBridgeClient::connect(udev, DEFAULT_BRIDGE_NAME)?;
+//! let device = client.receive_device()?;
+//! println!("{} {}", device.action(), device.devpath());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Every message is a [UdevMonitorNetlinkHeader], followed by the device properties, the same
+//! format `udev` sends over netlink. Apply the monitor filters on the server, clients receive
+//! every forwarded device.
+//!
+//! Abstract sockets are reachable by every process in the network namespace of the server, and
+//! have no file permissions, so both ends check the `SO_PEERCRED` credentials of their peer:
+//!
+//! - the server only accepts clients running as `root`, its own user, or one of the
+//!   [allowed users](EventBridgeServer::set_allowed_uids), up to a
+//!   [client limit](EventBridgeServer::set_max_clients)
+//! - clients only trust servers running as `root`, their own user, or one of the users passed
+//!   to [connect_with_allowed_uids](EventBridgeClient::connect_with_allowed_uids), so a process
+//!   squatting the name cannot forge events
+//!
+//! Events are queued per client, and sent without blocking. A client that does not read its
+//! messages drops events, and never stalls the server.
+
+use std::io::{self, Read};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::net::{SocketAddr, UnixListener, UnixStream};
+use std::sync::Arc;
+use std::time;
+
+use crate::peer::{peer_allowed, peer_cred, PeerQueue};
+use crate::{
+    Error, ErrorKind, ErrorScope, Result, Udev, UdevDevice, UdevMonitor, UdevMonitorNetlinkHeader,
+};
+
+/// Default abstract socket name of the event bridge.
+pub const DEFAULT_BRIDGE_NAME: &str = "udevrs-bridge";
+/// Default maximum number of clients connected to an [EventBridgeServer].
+pub const DEFAULT_MAX_CLIENTS: usize = 64;
+/// Maximum length of the device properties in a bridge message.
+const PROPERTIES_MAX: usize = 8192;
+/// Maximum length of the messages queued for a client, before new events are dropped.
+const CLIENT_QUEUE_LIMIT: usize = 256 * 1024;
+
+/// Forwards the events of a [UdevMonitor] to [EventBridgeClient]s, over an abstract Unix socket.
+pub struct EventBridgeServer {
+    monitor: UdevMonitor,
+    listener: UnixListener,
+    clients: Vec<PeerQueue>,
+    name: String,
+    allowed_uids: Vec<libc::uid_t>,
+    max_clients: usize,
+    dropped_events: u64,
+}
+
+impl EventBridgeServer {
+    /// Creates a new [EventBridgeServer], listening on the abstract socket `name`.
+    ///
+    /// The `monitor` should already be [receiving](UdevMonitor::enable_receiving).
+    ///
+    /// Returns: `Ok(EventBridgeServer)` on success, `Err(Error)` if the socket cannot be bound,
+    /// e.g. because another server uses the name.
+    pub fn bind(monitor: UdevMonitor, name: &str) -> Result<Self> {
+        let listener = SocketAddr::from_abstract_name(name)
+            .and_then(|addr| UnixListener::bind_addr(&addr))
+            .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+            .map_err(|err| {
+                Error::os(
+                    ErrorScope::Monitor,
+                    format!("unable to bind event bridge {name}: {err}"),
+                    err,
+                )
+            })?;
+
+        Ok(Self {
+            monitor,
+            listener,
+            clients: Vec::new(),
+            name: name.into(),
+            allowed_uids: Vec::new(),
+            max_clients: DEFAULT_MAX_CLIENTS,
+            dropped_events: 0,
+        })
+    }
+
+    /// Gets the abstract socket name.
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Gets a reference to the [UdevMonitor] receiving the forwarded events.
+    pub const fn monitor(&self) -> &UdevMonitor {
+        &self.monitor
+    }
+
+    /// Gets a mutable reference to the [UdevMonitor], e.g. to update its filters.
+    pub fn monitor_mut(&mut self) -> &mut UdevMonitor {
+        &mut self.monitor
+    }
+
+    /// Gets the user IDs of clients accepted in addition to `root`, and the server user.
+    pub fn allowed_uids(&self) -> &[libc::uid_t] {
+        self.allowed_uids.as_ref()
+    }
+
+    /// Sets the user IDs of clients accepted in addition to `root`, and the server user.
+    ///
+    /// Clients are identified by their `SO_PEERCRED` credentials, connections of other users
+    /// are closed.
+    pub fn set_allowed_uids(&mut self, uids: &[libc::uid_t]) {
+        self.allowed_uids = uids.to_vec();
+    }
+
+    /// Builder function that sets the user IDs of clients accepted in addition to `root`, and
+    /// the server user.
+    pub fn with_allowed_uids(mut self, uids: &[libc::uid_t]) -> Self {
+        self.set_allowed_uids(uids);
+        self
+    }
+
+    /// Gets the maximum number of connected clients.
+    pub const fn max_clients(&self) -> usize {
+        self.max_clients
+    }
+
+    /// Sets the maximum number of connected clients, further connections are closed.
+    ///
+    /// Defaults to [DEFAULT_MAX_CLIENTS].
+    pub fn set_max_clients(&mut self, max: usize) {
+        self.max_clients = max;
+    }
+
+    /// Builder function that sets the maximum number of connected clients.
+    pub fn with_max_clients(mut self, max: usize) -> Self {
+        self.set_max_clients(max);
+        self
+    }
+
+    /// Gets the number of connected clients.
+    pub fn clients(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Gets the number of events dropped, because a client did not read its messages.
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped_events
+    }
+
+    /// Accepts the pending client connections, without blocking.
+    ///
+    /// Connections of users not [allowed](Self::set_allowed_uids), and connections above the
+    /// [client limit](Self::set_max_clients), are closed.
+    ///
+    /// Returns: `Ok(usize)` with the number of connected clients on success, `Err(Error)`
+    /// otherwise.
+    pub fn accept_clients(&mut self) -> Result<usize> {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    let name = self.name.as_str();
+                    let (pid, uid) = match peer_cred(&stream) {
+                        Ok(cred) => cred,
+                        Err(err) => {
+                            log::debug!("event bridge {name}: rejecting client: {err}");
+                            continue;
+                        }
+                    };
+
+                    if !peer_allowed(uid, self.allowed_uids.as_ref()) {
+                        log::warn!("event bridge {name}: rejecting client pid={pid}, uid={uid}");
+                    } else if self.clients.len() >= self.max_clients {
+                        log::warn!(
+                            "event bridge {name}: rejecting client pid={pid}, too many clients"
+                        );
+                    } else {
+                        log::debug!("event bridge {name}: client pid={pid}, uid={uid} connected");
+                        self.clients
+                            .push(PeerQueue::new(stream, CLIENT_QUEUE_LIMIT)?);
+                    }
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    return Ok(self.clients.len())
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                Err(err) => {
+                    return Err(Error::os(
+                        ErrorScope::Monitor,
+                        format!("unable to accept event bridge client: {err}"),
+                        err,
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Queues the [UdevDevice] for every connected client, and sends it without blocking.
+    ///
+    /// Clients that do not read their messages drop the device once their queue is full, see
+    /// [dropped_events](Self::dropped_events). Clients that disconnected are dropped.
+    ///
+    /// Returns: the number of clients the device was queued for.
+    pub fn forward(&mut self, device: &mut UdevDevice) -> usize {
+        let header = UdevMonitorNetlinkHeader::for_device(device).to_bytes();
+        let mut msg = Vec::with_capacity(header.len() + device.monitor_buf().len());
+        msg.extend_from_slice(header.as_ref());
+        msg.extend_from_slice(device.get_properties_monitor_buf().as_bytes());
+
+        let name = self.name.as_str();
+        let dropped_events = &mut self.dropped_events;
+        let mut queued = 0;
+
+        self.clients.retain_mut(|client| {
+            let pushed = client.try_push(msg.clone());
+            if !pushed {
+                *dropped_events = dropped_events.saturating_add(1);
+            }
+
+            match client.flush() {
+                Ok(()) => {
+                    queued += usize::from(pushed);
+                    true
+                }
+                Err(err) => {
+                    log::debug!("event bridge {name}: dropping client: {err}");
+                    false
+                }
+            }
+        });
+
+        queued
+    }
+
+    /// Accepts pending clients, then receives the next device from the [UdevMonitor], and
+    /// forwards it.
+    ///
+    /// Returns: `Ok(usize)` with the number of clients the device was queued for on success,
+    /// `Err(Error)` from [receive_device](UdevMonitor::receive_device) otherwise.
+    pub fn forward_next(&mut self) -> Result<usize> {
+        self.accept_clients()?;
+
+        let mut device = self.monitor.receive_device()?;
+
+        Ok(self.forward(&mut device))
+    }
+
+    /// Forwards the events of the [UdevMonitor], and accepts new clients, until an error occurs.
+    ///
+    /// Devices rejected by the monitor filter, and interrupted waits, do not stop the bridge.
+    ///
+    /// Returns: `Err(Error)` if waiting, accepting a client, or receiving a device fails.
+    pub fn run(&mut self) -> Result<()> {
+        loop {
+            let pollfd = |fd, events| libc::pollfd {
+                fd,
+                events,
+                revents: 0,
+            };
+
+            let mut pfd = vec![
+                pollfd(self.listener.as_raw_fd(), libc::POLLIN),
+                pollfd(self.monitor.sock(), libc::POLLIN),
+            ];
+            pfd.extend(
+                self.clients
+                    .iter()
+                    .map(|client| pollfd(client.stream().as_raw_fd(), client.poll_events())),
+            );
+
+            // SAFETY: `pollfd` is properly initialized, and the pointer references valid memory.
+            let ret = unsafe { libc::poll(pfd.as_mut_ptr(), pfd.len() as libc::nfds_t, -1) };
+            if ret < 0 {
+                let errno = io::Error::last_os_error();
+                if errno.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(Error::os(
+                    ErrorScope::Monitor,
+                    format!("error waiting for event bridge: {errno}"),
+                    errno,
+                ));
+            }
+
+            if pfd[2..].iter().any(|p| p.revents != 0) {
+                self.flush_clients();
+            }
+
+            if pfd[0].revents != 0 {
+                self.accept_clients()?;
+            }
+
+            if pfd[1].revents != 0 {
+                match self.monitor.receive_device() {
+                    Ok(mut device) => {
+                        self.forward(&mut device);
+                    }
+                    Err(err)
+                        if matches!(
+                            err.kind(),
+                            ErrorKind::Filtered | ErrorKind::WouldBlock | ErrorKind::Interrupted
+                        ) =>
+                    {
+                        log::trace!("event bridge {}: {err}", self.name);
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+    }
+
+    // Sends the queued messages without blocking, and drops disconnected clients.
+    fn flush_clients(&mut self) {
+        let name = self.name.as_str();
+        self.clients.retain_mut(|client| match client.flush() {
+            Ok(()) => true,
+            Err(err) => {
+                log::debug!("event bridge {name}: dropping client: {err}");
+                false
+            }
+        });
+    }
+}
+
+/// Receives the events forwarded by an [EventBridgeServer].
+///
+/// Devices are marked initialized if they have a `USEC_INITIALIZED` property, i.e. were
+/// processed by the `udev` daemon.
+#[derive(Debug)]
+pub struct EventBridgeClient {
+    udev: Arc<Udev>,
+    stream: UnixStream,
+    name: String,
+    server_pid: libc::pid_t,
+    server_uid: libc::uid_t,
+}
+
+impl EventBridgeClient {
+    /// Connects to the [EventBridgeServer] listening on the abstract socket `name`.
+    ///
+    /// The server must run as `root`, or the user of this process, see
+    /// [connect_with_allowed_uids](Self::connect_with_allowed_uids).
+    ///
+    /// Returns: `Ok(EventBridgeClient)` on success, `Err(Error)` otherwise.
+    pub fn connect(udev: Arc<Udev>, name: &str) -> Result<Self> {
+        Self::connect_with_allowed_uids(udev, name, &[])
+    }
+
+    /// Connects to the [EventBridgeServer] listening on the abstract socket `name`, also
+    /// trusting servers running as one of the `allowed_uids`.
+    ///
+    /// Abstract names are first-come-first-served, so the server is identified by its
+    /// `SO_PEERCRED` credentials, before any event is received.
+    ///
+    /// Returns: `Ok(EventBridgeClient)` on success, `Err(Error)` if the connection fails, or the
+    /// server user is not trusted.
+    pub fn connect_with_allowed_uids(
+        udev: Arc<Udev>,
+        name: &str,
+        allowed_uids: &[libc::uid_t],
+    ) -> Result<Self> {
+        let stream = SocketAddr::from_abstract_name(name)
+            .and_then(|addr| UnixStream::connect_addr(&addr))
+            .map_err(|err| {
+                Error::os(
+                    ErrorScope::Monitor,
+                    format!("unable to connect to event bridge {name}: {err}"),
+                    err,
+                )
+            })?;
+
+        let (server_pid, server_uid) = peer_cred(&stream).map_err(|err| {
+            Error::os(
+                ErrorScope::Monitor,
+                format!("unable to get event bridge {name} credentials: {err}"),
+                err,
+            )
+        })?;
+
+        if server_pid <= 0 || !peer_allowed(server_uid, allowed_uids) {
+            return Err(Error::UdevMonitor(format!(
+                "untrusted event bridge {name}, pid={server_pid}, uid={server_uid}"
+            )));
+        }
+
+        Ok(Self {
+            udev,
+            stream,
+            name: name.into(),
+            server_pid,
+            server_uid,
+        })
+    }
+
+    /// Gets the abstract socket name.
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Gets the process ID of the server, when the connection was established.
+    pub const fn server_pid(&self) -> libc::pid_t {
+        self.server_pid
+    }
+
+    /// Gets the user ID of the server, when the connection was established.
+    pub const fn server_uid(&self) -> libc::uid_t {
+        self.server_uid
+    }
+
+    /// Receives the next forwarded device, blocking until one arrives.
+    ///
+    /// Returns: `Ok(UdevDevice)` on success, `Err(Error)` if the server disconnected, or sent an
+    /// invalid message.
+    pub fn receive_device(&mut self) -> Result<UdevDevice> {
+        let mut header = [0u8; UdevMonitorNetlinkHeader::LEN];
+        self.read_exact(header.as_mut())?;
+
+        let nlh = UdevMonitorNetlinkHeader::try_from(header.as_ref())?;
+        let properties_len = nlh.properties_len() as usize;
+
+        if nlh.properties_off() as usize != UdevMonitorNetlinkHeader::LEN
+            || properties_len > PROPERTIES_MAX
+        {
+            return Err(Error::UdevMonitor(format!(
+                "invalid event bridge message, properties offset: {}, length: {properties_len}",
+                nlh.properties_off()
+            )));
+        }
+
+        let mut properties = vec![0u8; properties_len];
+        self.read_exact(properties.as_mut())?;
+
+        let mut device =
+            UdevDevice::new_from_nulstr(Arc::clone(&self.udev), properties.as_ref())
+                .map_err(|err| Error::UdevMonitor(format!("could not create device: {err}")))?;
+
+        if device.usec_initialized() > 0 {
+            device.set_is_initialized(true);
+        }
+
+        Ok(device)
+    }
+
+    /// Receives the next forwarded device, waiting up to `timeout` for one to arrive.
+    ///
+    /// Returns:
+    ///
+    /// - `Ok(Some(UdevDevice))` if a device was received
+    /// - `Ok(None)` if the timeout elapsed
+    /// - `Err(Error)` otherwise
+    pub fn receive_device_timeout(
+        &mut self,
+        timeout: time::Duration,
+    ) -> Result<Option<UdevDevice>> {
+        let deadline = time::Instant::now() + timeout;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(time::Instant::now());
+            let mut pfd = [libc::pollfd {
+                fd: self.stream.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            }];
+            let timeout_ms = remaining.as_millis().min(i32::MAX as u128) as libc::c_int;
+
+            // SAFETY: `pollfd` is properly initialized, and the pointer references valid memory.
+            let ret =
+                unsafe { libc::poll(pfd.as_mut_ptr(), pfd.len() as libc::nfds_t, timeout_ms) };
+
+            match ret {
+                0 => return Ok(None),
+                r if r > 0 => return self.receive_device().map(Some),
+                _ => {
+                    let errno = io::Error::last_os_error();
+                    if errno.kind() != io::ErrorKind::Interrupted {
+                        return Err(Error::os(
+                            ErrorScope::Monitor,
+                            format!("error waiting for event bridge device: {errno}"),
+                            errno,
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.stream.read_exact(buf).map_err(|err| {
+            let msg = if err.kind() == io::ErrorKind::UnexpectedEof {
+                format!("event bridge {} closed", self.name)
+            } else {
+                format!("unable to read from event bridge {}: {err}", self.name)
+            };
+            Error::os(ErrorScope::Monitor, msg, err)
+        })
+    }
+}
+
+impl AsRawFd for EventBridgeClient {
+    fn as_raw_fd(&self) -> RawFd {
+        self.stream.as_raw_fd()
+    }
+}
+
+impl AsFd for EventBridgeClient {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.stream.as_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_bridge() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+        let name = format!("udevrs-bridge-test-{}", std::process::id());
+
+        let (mut sender, receiver) = UdevMonitor::new_loopback(Arc::clone(&udev))?;
+        let mut server = EventBridgeServer::bind(receiver, name.as_str())?;
+        assert_eq!(server.name(), name);
+        assert!(EventBridgeServer::bind(UdevMonitor::new(Arc::clone(&udev))?, &name).is_err());
+
+        let mut client = EventBridgeClient::connect(Arc::clone(&udev), name.as_str())?;
+        assert_eq!(server.accept_clients()?, 1);
+        assert!(client
+            .receive_device_timeout(time::Duration::from_millis(10))?
+            .is_none());
+
+        let mut device = UdevDevice::builder(Arc::clone(&udev))
+            .devpath("/devices/virtual/mock/mock0")
+            .subsystem("mock")
+            .action("add")
+            .usec_initialized(1_000)
+            .tag("seat")
+            .build()?;
+        sender.send_device(None, &mut device)?;
+        assert_eq!(server.forward_next()?, 1);
+
+        let mut received = client
+            .receive_device_timeout(time::Duration::from_secs(1))?
+            .expect("forwarded device");
+        assert_eq!(received.devpath(), "/devices/virtual/mock/mock0");
+        assert_eq!(received.action(), "add");
+        assert!(received.is_initialized());
+        assert!(received.has_tag("seat"));
+
+        // the server runs as the user of the client
+        assert_eq!(client.server_pid(), std::process::id() as libc::pid_t);
+
+        // clients above the limit are closed
+        server.set_max_clients(1);
+        let mut extra = EventBridgeClient::connect(Arc::clone(&udev), name.as_str())?;
+        assert_eq!(server.accept_clients()?, 1);
+        assert!(extra.receive_device().is_err());
+
+        // clients that do not read drop events, without blocking the server
+        let mut forwarded = 0;
+        while server.dropped_events() == 0 {
+            forwarded += server.forward(&mut device);
+            assert!(forwarded < 1_000_000);
+        }
+        assert_eq!(server.clients(), 1);
+
+        // disconnected clients are dropped
+        drop(client);
+        assert_eq!(server.forward(&mut device), 0);
+        assert_eq!(server.clients(), 0);
+
+        Ok(())
+    }
+}
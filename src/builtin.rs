@@ -0,0 +1,232 @@
+//! Built-in device identification commands.
+//!
+//! Equivalents of the `udev` built-in commands, run from `IMPORT{builtin}` and `RUN{builtin}`
+//! rules. Each built-in inspects a [UdevDevice], and adds the properties it computes to the
+//! device.
+//!
+//! Built-ins run in-process, and only need the `sysfs` access already provided by [UdevDevice].
+
+use std::fmt;
+
+use crate::{Error, Result, UdevDevice};
+
+mod input_id;
+mod path_id;
+mod usb_id;
+
+pub use input_id::*;
+pub use path_id::*;
+pub use usb_id::*;
+
+/// Built-in command that adds properties to a [UdevDevice].
+pub trait UdevBuiltin: Send + Sync {
+    /// Gets the name of the built-in, used to look it up in the [UdevBuiltinRegistry].
+    fn name(&self) -> &str;
+
+    /// Gets a short description of the built-in.
+    fn help(&self) -> &str;
+
+    /// Gets whether the built-in only needs to run once per device event.
+    fn run_once(&self) -> bool {
+        false
+    }
+
+    /// Runs the built-in against the [UdevDevice], adding the computed properties to the device.
+    ///
+    /// The `args` do not include the built-in name.
+    ///
+    /// Returns: `Ok(())` on success, `Err(Error)` if the device is not supported by the built-in.
+    fn run(&self, dev: &mut UdevDevice, args: &[&str]) -> Result<()>;
+}
+
+/// Registry of [UdevBuiltin] commands.
+pub struct UdevBuiltinRegistry {
+    builtins: Vec<Box<dyn UdevBuiltin>>,
+}
+
+impl UdevBuiltinRegistry {
+    /// Creates a new [UdevBuiltinRegistry] with the built-ins provided by this crate.
+    pub fn new() -> Self {
+        Self::new_empty()
+            .with_builtin(InputId)
+            .with_builtin(PathId)
+            .with_builtin(UsbId)
+    }
+
+    /// Creates a new [UdevBuiltinRegistry] without any built-ins.
+    pub fn new_empty() -> Self {
+        Self {
+            builtins: Vec::new(),
+        }
+    }
+
+    /// Registers the [UdevBuiltin], replacing any built-in with the same name.
+    pub fn register<B: UdevBuiltin + 'static>(&mut self, builtin: B) {
+        self.builtins.retain(|b| b.name() != builtin.name());
+        self.builtins.push(Box::new(builtin));
+    }
+
+    /// Builder function that registers the [UdevBuiltin].
+    pub fn with_builtin<B: UdevBuiltin + 'static>(mut self, builtin: B) -> Self {
+        self.register(builtin);
+        self
+    }
+
+    /// Gets the names of the registered built-ins.
+    pub fn names(&self) -> Vec<&str> {
+        self.builtins.iter().map(|b| b.name()).collect()
+    }
+
+    /// Gets the number of registered built-ins.
+    pub fn len(&self) -> usize {
+        self.builtins.len()
+    }
+
+    /// Gets whether the registry has no built-ins.
+    pub fn is_empty(&self) -> bool {
+        self.builtins.is_empty()
+    }
+
+    /// Looks up a registered built-in by name.
+    pub fn get(&self, name: &str) -> Option<&dyn UdevBuiltin> {
+        self.builtins
+            .iter()
+            .find(|b| b.name() == name)
+            .map(|b| b.as_ref())
+    }
+
+    /// Runs a built-in `command` against the [UdevDevice].
+    ///
+    /// The `command` is the built-in name, followed by whitespace-separated arguments, as used
+    /// in `IMPORT{builtin}` rules, e.g. `path_id`, or `usb_id`.
+    ///
+    /// Returns: `Ok(())` on success, `Err(Error)` if the built-in is unknown, or fails.
+    pub fn run(&self, dev: &mut UdevDevice, command: &str) -> Result<()> {
+        let mut args = command.split_whitespace();
+        let name = args
+            .next()
+            .ok_or(Error::UdevBuiltin("empty built-in command".into()))?;
+        let args: Vec<&str> = args.collect();
+
+        let builtin = self
+            .get(name)
+            .ok_or(Error::UdevBuiltin(format!("unknown built-in: {name}")))?;
+
+        log::debug!("running built-in '{command}' for {dev}");
+
+        builtin.run(dev, args.as_ref())
+    }
+}
+
+impl Default for UdevBuiltinRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for UdevBuiltinRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UdevBuiltinRegistry")
+            .field("builtins", &self.names())
+            .finish()
+    }
+}
+
+/// Adds a property computed by a built-in to the [UdevDevice].
+///
+/// Empty values are not added.
+pub fn builtin_add_property(dev: &mut UdevDevice, key: &str, value: &str) {
+    if !value.is_empty() {
+        log::trace!("built-in property: {key}={value}");
+        dev.add_property(key, value);
+    }
+}
+
+// Gets the sysname of the device, falling back to the last syspath component.
+pub(crate) fn builtin_sysname(dev: &UdevDevice) -> String {
+    if dev.sysname().is_empty() {
+        dev.syspath().rsplit('/').next().unwrap_or("").to_owned()
+    } else {
+        dev.sysname().to_owned()
+    }
+}
+
+// Gets the sys attribute value of the device, without the trailing newline.
+pub(crate) fn builtin_sysattr(dev: &UdevDevice, attr: &str) -> Option<String> {
+    dev.get_sysattr_value(attr)
+        .map(|v| v.trim_end().to_owned())
+        .filter(|v| !v.is_empty())
+}
+
+// Gets the closest parent device with the `subsystem` and `devtype`.
+pub(crate) fn builtin_parent(
+    dev: &UdevDevice,
+    subsystem: &str,
+    devtype: &str,
+) -> Option<UdevDevice> {
+    let mut parent = dev.new_from_parent().ok();
+
+    while let Some(p) = parent {
+        if p.get_subsystem() == subsystem && p.get_devtype() == devtype {
+            return Some(p);
+        }
+        parent = p.new_from_parent().ok();
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Udev;
+    use std::sync::Arc;
+
+    struct Constant;
+
+    impl UdevBuiltin for Constant {
+        fn name(&self) -> &str {
+            "constant"
+        }
+
+        fn help(&self) -> &str {
+            "Adds a constant property"
+        }
+
+        fn run(&self, dev: &mut UdevDevice, args: &[&str]) -> Result<()> {
+            builtin_add_property(dev, "CONSTANT", args.first().copied().unwrap_or("1"));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_builtin_registry() -> Result<()> {
+        let mut registry = UdevBuiltinRegistry::new();
+        assert_eq!(registry.names(), ["input_id", "path_id", "usb_id"]);
+
+        registry.register(Constant);
+        assert_eq!(registry.len(), 4);
+        assert_eq!(
+            registry.get("constant").map(|b| b.help()),
+            Some("Adds a constant property")
+        );
+
+        let mut dev = UdevDevice::new(Arc::new(Udev::new()));
+
+        registry.run(&mut dev, "constant")?;
+        assert_eq!(dev.get_property_value("CONSTANT"), Some("1"));
+
+        registry.run(&mut dev, "constant  two")?;
+        assert_eq!(dev.get_property_value("CONSTANT"), Some("two"));
+
+        assert!(registry.run(&mut dev, "").is_err());
+        assert!(registry.run(&mut dev, "unknown").is_err());
+
+        // devices without a sysfs path are not supported by the crate built-ins
+        assert!(registry.run(&mut dev, "path_id").is_err());
+        assert!(registry.run(&mut dev, "usb_id").is_err());
+        assert!(registry.run(&mut dev, "input_id").is_err());
+
+        Ok(())
+    }
+}
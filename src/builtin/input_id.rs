@@ -0,0 +1,439 @@
+use super::{builtin_add_property, UdevBuiltin};
+use crate::{Error, Result, UdevDevice};
+
+// Event types, from `linux/input-event-codes.h`.
+const EV_KEY: usize = 0x01;
+const EV_REL: usize = 0x02;
+const EV_SW: usize = 0x05;
+
+// Absolute axes.
+const ABS_X: usize = 0x00;
+const ABS_Y: usize = 0x01;
+const ABS_Z: usize = 0x02;
+const ABS_RX: usize = 0x03;
+const ABS_PRESSURE: usize = 0x18;
+const ABS_MT_SLOT: usize = 0x2f;
+const ABS_MT_POSITION_X: usize = 0x35;
+const ABS_MT_POSITION_Y: usize = 0x36;
+
+// Relative axes.
+const REL_X: usize = 0x00;
+const REL_Y: usize = 0x01;
+const REL_HWHEEL: usize = 0x06;
+const REL_WHEEL: usize = 0x08;
+
+// Keys, and buttons.
+const BTN_MISC: usize = 0x100;
+const BTN_MOUSE: usize = 0x110;
+const BTN_JOYSTICK: usize = 0x120;
+const BTN_DIGI: usize = 0x140;
+const BTN_TOOL_PEN: usize = 0x140;
+const BTN_TOOL_FINGER: usize = 0x145;
+const BTN_TOUCH: usize = 0x14a;
+const BTN_STYLUS: usize = 0x14b;
+const KEY_OK: usize = 0x160;
+const BTN_DPAD_UP: usize = 0x220;
+const BTN_DPAD_RIGHT: usize = 0x223;
+const BTN_TRIGGER_HAPPY1: usize = 0x2c0;
+const BTN_TRIGGER_HAPPY40: usize = 0x2e7;
+
+// Device properties.
+const INPUT_PROP_DIRECT: usize = 0x01;
+const INPUT_PROP_POINTING_STICK: usize = 0x05;
+const INPUT_PROP_ACCELEROMETER: usize = 0x06;
+
+// Bits in a word of the capability bitmaps, the size of the kernel `unsigned long`.
+const BITS_PER_WORD: usize = usize::BITS as usize;
+
+/// Input device classes, computed from the capability bitmaps in `sys`.
+///
+/// Each class corresponds to an `ID_INPUT_*` property set by the [InputId] built-in.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct InputClasses(u32);
+
+bitflags! {
+    impl InputClasses: u32 {
+        /// Input device with capabilities, sets `ID_INPUT`.
+        const INPUT = 1 << 0;
+        /// Device with keys, sets `ID_INPUT_KEY`.
+        const KEY = 1 << 1;
+        /// Full keyboard, sets `ID_INPUT_KEYBOARD`.
+        const KEYBOARD = 1 << 2;
+        /// Mouse, sets `ID_INPUT_MOUSE`.
+        const MOUSE = 1 << 3;
+        /// Touchpad, sets `ID_INPUT_TOUCHPAD`.
+        const TOUCHPAD = 1 << 4;
+        /// Touchscreen, sets `ID_INPUT_TOUCHSCREEN`.
+        const TOUCHSCREEN = 1 << 5;
+        /// Joystick, or gamepad, sets `ID_INPUT_JOYSTICK`.
+        const JOYSTICK = 1 << 6;
+        /// Graphics tablet, sets `ID_INPUT_TABLET`.
+        const TABLET = 1 << 7;
+        /// Accelerometer, sets `ID_INPUT_ACCELEROMETER`.
+        const ACCELEROMETER = 1 << 8;
+        /// Pointing stick, sets `ID_INPUT_POINTINGSTICK`.
+        const POINTINGSTICK = 1 << 9;
+        /// Switch, e.g. a lid switch, sets `ID_INPUT_SWITCH`.
+        const SWITCH = 1 << 10;
+        /// No input class, e.g. a device without capabilities, sets no properties.
+        const NONE = 0;
+    }
+}
+
+impl InputClasses {
+    /// Gets the `ID_INPUT_*` property names of the set classes.
+    pub fn properties(&self) -> impl Iterator<Item = &'static str> + '_ {
+        [
+            (Self::INPUT, "ID_INPUT"),
+            (Self::KEY, "ID_INPUT_KEY"),
+            (Self::KEYBOARD, "ID_INPUT_KEYBOARD"),
+            (Self::MOUSE, "ID_INPUT_MOUSE"),
+            (Self::TOUCHPAD, "ID_INPUT_TOUCHPAD"),
+            (Self::TOUCHSCREEN, "ID_INPUT_TOUCHSCREEN"),
+            (Self::JOYSTICK, "ID_INPUT_JOYSTICK"),
+            (Self::TABLET, "ID_INPUT_TABLET"),
+            (Self::ACCELEROMETER, "ID_INPUT_ACCELEROMETER"),
+            (Self::POINTINGSTICK, "ID_INPUT_POINTINGSTICK"),
+            (Self::SWITCH, "ID_INPUT_SWITCH"),
+        ]
+        .into_iter()
+        .filter_map(|(class, name)| self.contains(class).then_some(name))
+    }
+}
+
+/// Built-in that classifies input devices, e.g. keyboards, mice, and touchpads.
+///
+/// Adds the properties of the [InputClasses] computed by [UdevDevice::input_classes], e.g.:
+///
+/// - `ID_INPUT`: set for every input device
+/// - `ID_INPUT_KEY`: device with keys
+/// - `ID_INPUT_KEYBOARD`: full keyboard
+/// - `ID_INPUT_MOUSE`, `ID_INPUT_TOUCHPAD`, `ID_INPUT_TOUCHSCREEN`, `ID_INPUT_TABLET`: pointers
+/// - `ID_INPUT_JOYSTICK`: joystick, or gamepad
+/// - `ID_INPUT_ACCELEROMETER`: accelerometer
+/// - `ID_INPUT_SWITCH`: switch
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct InputId;
+
+impl UdevBuiltin for InputId {
+    fn name(&self) -> &str {
+        "input_id"
+    }
+
+    fn help(&self) -> &str {
+        "Input device properties"
+    }
+
+    fn run(&self, dev: &mut UdevDevice, _args: &[&str]) -> Result<()> {
+        let classes = dev.input_classes();
+
+        if classes.is_empty() {
+            return Err(Error::UdevBuiltin(format!(
+                "no input device found for: {}",
+                dev.syspath()
+            )));
+        }
+
+        for property in classes.properties() {
+            builtin_add_property(dev, property, "1");
+        }
+
+        Ok(())
+    }
+}
+
+impl UdevDevice {
+    /// Gets the [InputClasses] of an input device.
+    ///
+    /// The capabilities are read from the closest `input` device with a `capabilities/ev` sys
+    /// attribute, so event devices, e.g. `event3`, are classified by their parent input device.
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{InputClasses, Udev, UdevDevice};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let udev = Arc::new(Udev::new());
+    /// let event = UdevDevice::new_from_subsystem_sysname(udev, "input", "event0")?;
+    ///
+    /// if event.input_classes().contains(InputClasses::KEYBOARD) {
+    ///     println!("{} is a keyboard", event.syspath());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns: the classes of the input device, [InputClasses::NONE] for other devices.
+    pub fn input_classes(&self) -> InputClasses {
+        let caps = if self.get_sysattr_value("capabilities/ev").is_some() {
+            Capabilities::read(self)
+        } else {
+            match self.ancestors().find(|parent| {
+                parent.get_subsystem() == "input"
+                    && parent.get_sysattr_value("capabilities/ev").is_some()
+            }) {
+                Some(parent) => Capabilities::read(&parent),
+                None => return InputClasses::NONE,
+            }
+        };
+
+        caps.classes()
+    }
+}
+
+// Capability bitmap, from the space-separated hexadecimal words in `sys`.
+//
+// The most significant word is printed first, so word `0` is the last in the attribute.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Bitmap(Vec<usize>);
+
+impl Bitmap {
+    fn parse(value: &str) -> Self {
+        Self(
+            value
+                .split_whitespace()
+                .rev()
+                .map(|word| usize::from_str_radix(word, 16).unwrap_or(0))
+                .collect(),
+        )
+    }
+
+    fn word(&self, idx: usize) -> usize {
+        self.0.get(idx).copied().unwrap_or(0)
+    }
+
+    fn test(&self, bit: usize) -> bool {
+        self.word(bit / BITS_PER_WORD) & (1 << (bit % BITS_PER_WORD)) != 0
+    }
+
+    fn any(&self, bits: impl IntoIterator<Item = usize>) -> bool {
+        bits.into_iter().any(|bit| self.test(bit))
+    }
+}
+
+// Capability bitmaps of an input device.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Capabilities {
+    ev: Bitmap,
+    abs: Bitmap,
+    rel: Bitmap,
+    key: Bitmap,
+    props: Bitmap,
+}
+
+impl Capabilities {
+    fn read(dev: &UdevDevice) -> Self {
+        let bitmap =
+            |attr: &str| Bitmap::parse(dev.get_sysattr_value(attr).as_deref().unwrap_or(""));
+
+        Self {
+            ev: bitmap("capabilities/ev"),
+            abs: bitmap("capabilities/abs"),
+            rel: bitmap("capabilities/rel"),
+            key: bitmap("capabilities/key"),
+            props: bitmap("properties"),
+        }
+    }
+
+    fn classes(&self) -> InputClasses {
+        let mut classes = InputClasses::INPUT;
+
+        let pointer = self.pointer_classes();
+        let key = self.key_classes();
+
+        // some event devices only have a scroll wheel
+        if pointer.is_empty()
+            && key.is_empty()
+            && self.ev.test(EV_REL)
+            && self.rel.any([REL_WHEEL, REL_HWHEEL])
+        {
+            classes |= InputClasses::KEY;
+        }
+
+        if self.ev.test(EV_SW) {
+            classes |= InputClasses::SWITCH;
+        }
+
+        classes | pointer | key
+    }
+
+    fn pointer_classes(&self) -> InputClasses {
+        let has_keys = self.ev.test(EV_KEY);
+        let has_abs_coordinates = self.abs.test(ABS_X) && self.abs.test(ABS_Y);
+        let has_3d_coordinates = has_abs_coordinates && self.abs.test(ABS_Z);
+
+        if self.props.test(INPUT_PROP_ACCELEROMETER) || (!has_keys && has_3d_coordinates) {
+            return InputClasses::ACCELEROMETER;
+        }
+
+        let stylus_or_pen = self.key.any([BTN_STYLUS, BTN_TOOL_PEN]);
+        let finger_but_no_pen = self.key.test(BTN_TOOL_FINGER) && !self.key.test(BTN_TOOL_PEN);
+        let has_mouse_button = self.key.any(BTN_MOUSE..BTN_JOYSTICK);
+        let has_rel_coordinates =
+            self.ev.test(EV_REL) && self.rel.test(REL_X) && self.rel.test(REL_Y);
+        // devices claiming all absolute axes do not have real multi-touch coordinates
+        let has_mt_coordinates = self.abs.test(ABS_MT_POSITION_X)
+            && self.abs.test(ABS_MT_POSITION_Y)
+            && !(self.abs.test(ABS_MT_SLOT) && self.abs.test(ABS_MT_SLOT - 1));
+        let is_direct = self.props.test(INPUT_PROP_DIRECT);
+        let has_touch = self.key.test(BTN_TOUCH);
+        // joysticks do not necessarily have buttons, e.g. rudders, and pedals only have axes
+        let has_joystick_axes_or_buttons = self.key.any(BTN_JOYSTICK..BTN_DIGI)
+            || self.key.any(BTN_TRIGGER_HAPPY1..=BTN_TRIGGER_HAPPY40)
+            || self.key.any(BTN_DPAD_UP..=BTN_DPAD_RIGHT)
+            || self.abs.any(ABS_RX..ABS_PRESSURE);
+
+        let mut classes = InputClasses::NONE;
+
+        if self.props.test(INPUT_PROP_POINTING_STICK) {
+            classes |= InputClasses::POINTINGSTICK;
+        }
+
+        if has_abs_coordinates {
+            if stylus_or_pen {
+                classes |= InputClasses::TABLET;
+            } else if finger_but_no_pen && !is_direct {
+                classes |= InputClasses::TOUCHPAD;
+            } else if has_mouse_button {
+                // absolute mice without touch buttons, e.g. virtual machine pointers
+                classes |= InputClasses::MOUSE;
+            } else if has_touch || is_direct {
+                classes |= InputClasses::TOUCHSCREEN;
+            } else if has_joystick_axes_or_buttons {
+                classes |= InputClasses::JOYSTICK;
+            }
+        } else if has_joystick_axes_or_buttons {
+            classes |= InputClasses::JOYSTICK;
+        }
+
+        if has_mt_coordinates {
+            if stylus_or_pen {
+                classes |= InputClasses::TABLET;
+            } else if finger_but_no_pen && !is_direct {
+                classes |= InputClasses::TOUCHPAD;
+            } else if has_touch || is_direct {
+                classes |= InputClasses::TOUCHSCREEN;
+            }
+        }
+
+        if has_rel_coordinates && has_mouse_button {
+            classes |= InputClasses::MOUSE;
+        }
+
+        classes
+    }
+
+    fn key_classes(&self) -> InputClasses {
+        if !self.ev.test(EV_KEY) {
+            return InputClasses::NONE;
+        }
+
+        let mut classes = InputClasses::NONE;
+
+        // only consider KEY_* codes, not BTN_* codes
+        let has_keys = (0..BTN_MISC / BITS_PER_WORD).any(|idx| self.key.word(idx) != 0)
+            || self.key.any(KEY_OK..BTN_TRIGGER_HAPPY1);
+
+        if has_keys {
+            classes |= InputClasses::KEY;
+        }
+
+        // the first 32 codes are ESC, numbers, and Q to D, ignoring KEY_RESERVED
+        let mask = 0xffff_fffe;
+        if self.key.word(0) & mask == mask {
+            classes |= InputClasses::KEYBOARD;
+        }
+
+        classes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classes(ev: &str, abs: &str, rel: &str, key: &str, props: &str) -> InputClasses {
+        Capabilities {
+            ev: Bitmap::parse(ev),
+            abs: Bitmap::parse(abs),
+            rel: Bitmap::parse(rel),
+            key: Bitmap::parse(key),
+            props: Bitmap::parse(props),
+        }
+        .classes()
+    }
+
+    // Formats a key bitmap with the given codes set, as printed by the kernel.
+    fn keys(codes: &[usize]) -> String {
+        let mut words = [0usize; 0x300 / BITS_PER_WORD];
+        for &code in codes {
+            words[code / BITS_PER_WORD] |= 1 << (code % BITS_PER_WORD);
+        }
+        words
+            .iter()
+            .rev()
+            .map(|w| format!("{w:x}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn test_input_id() {
+        let keyboard = keys(&(1..32).collect::<Vec<_>>());
+        assert_eq!(
+            classes("120013", "0", "0", keyboard.as_str(), "0"),
+            InputClasses::INPUT | InputClasses::KEY | InputClasses::KEYBOARD
+        );
+
+        let mouse = keys(&[BTN_MOUSE, BTN_MOUSE + 1, BTN_MOUSE + 2]);
+        assert_eq!(
+            classes("17", "0", "143", mouse.as_str(), "0"),
+            InputClasses::INPUT | InputClasses::MOUSE
+        );
+
+        let touchpad = keys(&[BTN_MOUSE, BTN_TOOL_FINGER, BTN_TOUCH]);
+        assert_eq!(
+            classes("b", "660800000000003", "0", touchpad.as_str(), "1"),
+            InputClasses::INPUT | InputClasses::TOUCHPAD
+        );
+
+        let touchscreen = keys(&[BTN_TOUCH]);
+        assert_eq!(
+            classes("b", "3", "0", touchscreen.as_str(), "2"),
+            InputClasses::INPUT | InputClasses::TOUCHSCREEN
+        );
+
+        let joystick = keys(&[BTN_JOYSTICK, BTN_JOYSTICK + 1]);
+        assert_eq!(
+            classes("1b", "3", "0", joystick.as_str(), "0"),
+            InputClasses::INPUT | InputClasses::JOYSTICK
+        );
+
+        let tablet = keys(&[BTN_TOOL_PEN, BTN_TOUCH, BTN_STYLUS]);
+        assert_eq!(
+            classes("1b", "1000003", "0", tablet.as_str(), "0"),
+            InputClasses::INPUT | InputClasses::TABLET
+        );
+
+        assert_eq!(
+            classes("9", "7", "0", "0", "0"),
+            InputClasses::INPUT | InputClasses::ACCELEROMETER
+        );
+        assert_eq!(
+            classes("21", "0", "0", "0", "0"),
+            InputClasses::INPUT | InputClasses::SWITCH
+        );
+        assert_eq!(
+            classes("5", "0", "100", "0", "0"),
+            InputClasses::INPUT | InputClasses::KEY
+        );
+
+        let props: Vec<&str> = (InputClasses::INPUT | InputClasses::MOUSE)
+            .properties()
+            .collect();
+        assert_eq!(props, ["ID_INPUT", "ID_INPUT_MOUSE"]);
+
+        // devices without capabilities are not input devices
+        assert_eq!(UdevDevice::default().input_classes(), InputClasses::NONE);
+    }
+}
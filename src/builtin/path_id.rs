@@ -0,0 +1,531 @@
+use std::fs;
+
+use super::{builtin_add_property, builtin_parent, builtin_sysattr, builtin_sysname, UdevBuiltin};
+use crate::{Error, Result, UdevDevice};
+
+/// Built-in that composes the persistent device path, e.g. `pci-0000:00:1f.2-ata-1`.
+///
+/// Adds the properties:
+///
+/// - `ID_PATH`: the device path, composed from the parent devices
+/// - `ID_PATH_TAG`: the device path, with characters invalid in tags replaced by `_`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PathId;
+
+impl UdevBuiltin for PathId {
+    fn name(&self) -> &str {
+        "path_id"
+    }
+
+    fn help(&self) -> &str {
+        "Compose persistent device path"
+    }
+
+    fn run(&self, dev: &mut UdevDevice, _args: &[&str]) -> Result<()> {
+        let path = dev.path_id().ok_or(Error::UdevBuiltin(format!(
+            "no persistent path for device: {}",
+            dev.syspath()
+        )))?;
+        let tag = path_tag(path.as_str());
+
+        builtin_add_property(dev, "ID_PATH", path.as_str());
+        builtin_add_property(dev, "ID_PATH_TAG", tag.as_str());
+
+        Ok(())
+    }
+}
+
+impl UdevDevice {
+    /// Gets the persistent device path, e.g. `pci-0000:00:14.0-usb-0:2:1.0-scsi-0:0:0:0`.
+    ///
+    /// The path is composed from the parent devices, the way the `path_id` built-in computes
+    /// `ID_PATH`, and names the `/dev/disk/by-path` links:
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{Udev, UdevDevice};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let udev = Arc::new(Udev::new());
+    /// let disk = UdevDevice::new_from_subsystem_sysname(udev, "block", "sda")?;
+    ///
+    /// if let Some(path) = disk.path_id() {
+    ///     println!("/dev/disk/by-path/{path}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns: `Some(String)` for devices on a supported bus, `None` for devices with an unknown
+    /// parent device type, and block devices without a well-known transport.
+    pub fn path_id(&self) -> Option<String> {
+        compose_path(self)
+    }
+}
+
+fn compose_path(dev: &UdevDevice) -> Option<String> {
+    if dev.syspath().is_empty() {
+        return None;
+    }
+
+    let mut path = String::new();
+    let mut supported_transport = false;
+    let mut supported_parent = false;
+
+    let mut parent = Some(dev.clone());
+
+    while let Some(cur) = parent {
+        let subsystem = cur.get_subsystem().to_owned();
+        let sysname = builtin_sysname(&cur);
+
+        let next = match subsystem.as_str() {
+            "scsi" => {
+                supported_transport = true;
+                handle_scsi(cur, &mut path, &mut supported_parent)
+            }
+            "usb" => {
+                supported_transport = true;
+                Some(handle_usb(cur, &mut path))
+            }
+            "bcma" => {
+                supported_parent = true;
+                handle_bcma(cur, &mut path)
+            }
+            "serio" => {
+                let sysnum = sysname.trim_start_matches(|c: char| !c.is_ascii_digit());
+                path_prepend(&mut path, format!("serio-{sysnum}").as_str());
+                Some(skip_subsystem(cur, "serio"))
+            }
+            "pci" => {
+                path_prepend(&mut path, format!("pci-{sysname}").as_str());
+                supported_parent = true;
+                Some(skip_subsystem(cur, "pci"))
+            }
+            "platform" => {
+                path_prepend(&mut path, format!("platform-{sysname}").as_str());
+                supported_transport = true;
+                supported_parent = true;
+                Some(skip_subsystem(cur, "platform"))
+            }
+            "acpi" => {
+                path_prepend(&mut path, format!("acpi-{sysname}").as_str());
+                supported_parent = true;
+                Some(skip_subsystem(cur, "acpi"))
+            }
+            "xen" => {
+                path_prepend(&mut path, format!("xen-{sysname}").as_str());
+                supported_parent = true;
+                Some(skip_subsystem(cur, "xen"))
+            }
+            "virtio" => {
+                let mut virtio_parent = Some(cur);
+                while let Some(p) = virtio_parent.take() {
+                    if p.get_subsystem() == "virtio" {
+                        virtio_parent = p.new_from_parent().ok();
+                    } else {
+                        virtio_parent = Some(p);
+                        break;
+                    }
+                }
+                supported_transport = true;
+                supported_parent = true;
+
+                virtio_parent.inspect(|p| {
+                    path_prepend(
+                        &mut path,
+                        format!("virtio-pci-{}", builtin_sysname(p)).as_str(),
+                    )
+                })
+            }
+            "scm" | "ccw" | "ccwgroup" | "iucv" => {
+                path_prepend(&mut path, format!("{subsystem}-{sysname}").as_str());
+                supported_transport = true;
+                supported_parent = true;
+                Some(skip_subsystem(cur, subsystem.as_str()))
+            }
+            _ => Some(cur),
+        };
+
+        parent = next.and_then(|p| p.new_from_parent().ok());
+    }
+
+    // From the `path_id` documentation:
+    //
+    // Do not return devices with an unknown parent device type. They
+    // might produce conflicting IDs if the parent does not provide a
+    // unique and predictable name.
+    //
+    // Do not return block devices without a well-known transport. Some
+    // devices do not expose their buses and do not provide a unique
+    // and predictable name that way.
+    if path.is_empty()
+        || !supported_parent
+        || (dev.get_subsystem() == "block" && !supported_transport)
+    {
+        None
+    } else {
+        Some(path)
+    }
+}
+
+fn path_prepend(path: &mut String, prefix: &str) {
+    if path.is_empty() {
+        path.push_str(prefix);
+    } else {
+        path.insert(0, '-');
+        path.insert_str(0, prefix);
+    }
+}
+
+/// Composes a valid tag name from the `path`.
+///
+/// Characters other than alphanumerics and `-` are replaced with a single `_`, with leading and
+/// trailing `_` removed.
+fn path_tag(path: &str) -> String {
+    let mut tag = String::with_capacity(path.len());
+
+    for c in path.chars() {
+        if c.is_ascii_alphanumeric() || c == '-' {
+            tag.push(c);
+        } else if !tag.is_empty() && !tag.ends_with('_') {
+            tag.push('_');
+        }
+    }
+
+    tag.trim_end_matches('_').into()
+}
+
+// Gets the last parent device in the `subsystem`, starting from `dev`.
+fn skip_subsystem(dev: UdevDevice, subsystem: &str) -> UdevDevice {
+    let mut last = dev;
+
+    while let Ok(parent) = last.new_from_parent() {
+        if parent.get_subsystem() != subsystem {
+            break;
+        }
+        last = parent;
+    }
+
+    last
+}
+
+fn handle_usb(dev: UdevDevice, path: &mut String) -> UdevDevice {
+    let devtype = dev.get_devtype().to_owned();
+    if devtype != "usb_interface" && devtype != "usb_device" {
+        return dev;
+    }
+
+    let sysname = builtin_sysname(&dev);
+    match sysname.split_once('-') {
+        Some((_, port)) => {
+            path_prepend(path, format!("usb-0:{port}").as_str());
+            skip_subsystem(dev, "usb")
+        }
+        None => dev,
+    }
+}
+
+fn handle_scsi(
+    dev: UdevDevice,
+    path: &mut String,
+    supported_parent: &mut bool,
+) -> Option<UdevDevice> {
+    if dev.get_devtype() != "scsi_device" {
+        return Some(dev);
+    }
+
+    // firewire
+    if let Some(id) = builtin_sysattr(&dev, "ieee1394_id") {
+        path_prepend(path, format!("ieee1394-0x{id}").as_str());
+        *supported_parent = true;
+        return Some(skip_subsystem(dev, "scsi"));
+    }
+
+    // the SCSI transport has no subsystem, and is only identified by the syspath
+    let syspath = dev.syspath().to_owned();
+
+    if syspath.contains("/rport-") {
+        *supported_parent = true;
+        handle_scsi_fibre_channel(dev, path)
+    } else if syspath.contains("/end_device-") {
+        *supported_parent = true;
+        handle_scsi_sas(dev, path)
+    } else if syspath.contains("/session") {
+        *supported_parent = true;
+        handle_scsi_iscsi(dev, path)
+    } else if syspath.contains("/ata") {
+        handle_scsi_ata(dev, path)
+    } else if syspath.contains("/vmbus_") {
+        handle_scsi_hyperv(dev, path)
+    } else {
+        handle_scsi_default(dev, path)
+    }
+}
+
+fn handle_scsi_fibre_channel(dev: UdevDevice, path: &mut String) -> Option<UdevDevice> {
+    let target = builtin_parent(&dev, "scsi", "scsi_target")?;
+    let fc = UdevDevice::new_from_subsystem_sysname(
+        dev.udev_cloned(),
+        "fc_transport",
+        builtin_sysname(&target).as_str(),
+    )
+    .ok()?;
+    let port = builtin_sysattr(&fc, "port_name")?;
+
+    path_prepend(path, format!("fc-{port}-{}", lun_number(&dev)).as_str());
+
+    Some(dev)
+}
+
+fn handle_scsi_sas(dev: UdevDevice, path: &mut String) -> Option<UdevDevice> {
+    let target = builtin_parent(&dev, "scsi", "scsi_target")?;
+    let target_parent = target.new_from_parent().ok()?;
+    let sas = UdevDevice::new_from_subsystem_sysname(
+        dev.udev_cloned(),
+        "sas_device",
+        builtin_sysname(&target_parent).as_str(),
+    )
+    .ok()?;
+    let address = builtin_sysattr(&sas, "sas_address")?;
+
+    path_prepend(path, format!("sas-{address}-{}", lun_number(&dev)).as_str());
+
+    Some(dev)
+}
+
+fn handle_scsi_iscsi(dev: UdevDevice, path: &mut String) -> Option<UdevDevice> {
+    let session = dev
+        .ancestors()
+        .find(|p| builtin_sysname(p).starts_with("session"))?;
+    let udev = dev.udev_cloned();
+
+    let session_dev = UdevDevice::new_from_subsystem_sysname(
+        udev.clone(),
+        "iscsi_session",
+        builtin_sysname(&session).as_str(),
+    )
+    .ok()?;
+    let target = builtin_sysattr(&session_dev, "targetname")?;
+
+    let connection = format!("connection{}:0", sysnum(&session));
+    let connection_dev =
+        UdevDevice::new_from_subsystem_sysname(udev, "iscsi_connection", connection.as_str())
+            .ok()?;
+    let addr = builtin_sysattr(&connection_dev, "persistent_address")?;
+    let port = builtin_sysattr(&connection_dev, "persistent_port")?;
+
+    path_prepend(
+        path,
+        format!("ip-{addr}:{port}-iscsi-{target}-{}", lun_number(&dev)).as_str(),
+    );
+
+    Some(dev)
+}
+
+fn handle_scsi_hyperv(dev: UdevDevice, path: &mut String) -> Option<UdevDevice> {
+    let host = builtin_parent(&dev, "scsi", "scsi_host")?;
+    let vmbus = host.new_from_parent().ok()?;
+    let guid = vmbus_guid(builtin_sysattr(&vmbus, "device_id")?.as_str())?;
+
+    path_prepend(path, format!("vmbus-{guid}-{}", lun_number(&dev)).as_str());
+
+    Some(dev)
+}
+
+fn handle_bcma(dev: UdevDevice, path: &mut String) -> Option<UdevDevice> {
+    // sysname format: `bcma<bus>:<core>`
+    let core = builtin_sysname(&dev)
+        .strip_prefix("bcma")?
+        .split_once(':')?
+        .1
+        .parse::<u32>()
+        .ok()?;
+
+    path_prepend(path, format!("bcma-{core}").as_str());
+
+    Some(dev)
+}
+
+// Gets the trailing number of the device sysname, e.g. `3` for `session3`.
+fn sysnum(dev: &UdevDevice) -> String {
+    let sysname = builtin_sysname(dev);
+    let start = sysname
+        .rfind(|c: char| !c.is_ascii_digit())
+        .map_or(0, |i| i + 1);
+
+    sysname[start..].to_owned()
+}
+
+/// Formats the LUN of the SCSI device, from the sysnum.
+fn lun_number(dev: &UdevDevice) -> String {
+    format_lun(sysnum(dev).parse::<u64>().unwrap_or(0))
+}
+
+/// Formats the `lun` path component.
+///
+/// Small LUNs use peripheral addressing, larger LUNs use flat 64-bit addressing.
+fn format_lun(lun: u64) -> String {
+    if lun < 256 {
+        format!("lun-{lun}")
+    } else {
+        format!(
+            "lun-0x{:04x}{:04x}00000000",
+            lun & 0xffff,
+            (lun >> 16) & 0xffff
+        )
+    }
+}
+
+/// Strips the braces and dashes from the Hyper-V VMBus device GUID.
+///
+/// Returns: the GUID digits, e.g. `f8b3781a1e824818a1c363d806ec15bb`, `None` for malformed
+/// GUIDs.
+fn vmbus_guid(guid: &str) -> Option<String> {
+    let inner = guid.strip_prefix('{')?.strip_suffix('}')?;
+
+    (inner.len() == 36).then(|| inner.chars().filter(|&c| c != '-').collect())
+}
+
+fn handle_scsi_ata(dev: UdevDevice, path: &mut String) -> Option<UdevDevice> {
+    let host = builtin_parent(&dev, "scsi", "scsi_host")?;
+    let ata = host.new_from_parent().ok()?;
+
+    let port = UdevDevice::new_from_subsystem_sysname(
+        dev.udev_cloned(),
+        "ata_port",
+        builtin_sysname(&ata).as_str(),
+    )
+    .ok()?;
+    let port_no = builtin_sysattr(&port, "port_no")?;
+
+    path_prepend(path, format!("ata-{port_no}").as_str());
+
+    Some(host)
+}
+
+fn handle_scsi_default(dev: UdevDevice, path: &mut String) -> Option<UdevDevice> {
+    let sysname = builtin_sysname(&dev);
+    let hctl: Vec<u32> = sysname
+        .split(':')
+        .filter_map(|n| n.parse::<u32>().ok())
+        .collect();
+    let [host, bus, target, lun] = hctl[..] else {
+        return None;
+    };
+
+    let host_dev = builtin_parent(&dev, "scsi", "scsi_host")?;
+
+    // From the `path_id` documentation:
+    //
+    // Rebase host offset to get the local relative number
+    //
+    // Note: This is by definition racy, unreliable and too simple.
+    // Please do not copy this model anywhere. It's just a left-over
+    // from the time we had no idea how things should look like in
+    // the end.
+    let base = host_dev
+        .syspath()
+        .rsplit_once('/')
+        .map(|(base, _)| base.to_owned())?;
+
+    let basenum = fs::read_dir(base.as_str())
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            e.file_name()
+                .to_str()
+                .and_then(|n| n.strip_prefix("host"))
+                .and_then(|n| n.parse::<u32>().ok())
+        })
+        .min();
+
+    if let Some(basenum) = basenum {
+        let host = host.saturating_sub(basenum);
+        path_prepend(path, format!("scsi-{host}:{bus}:{target}:{lun}").as_str());
+    }
+
+    Some(host_dev)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FixtureDevice, SysfsFixture};
+
+    const PCI: &str = "/devices/pci0000:00/0000:00:14.0";
+
+    // USB storage disk behind a PCI controller, a platform serial port, and a virtual disk.
+    fn fixture() -> Result<SysfsFixture> {
+        let interface = format!("{PCI}/usb1/1-2/1-2:1.0");
+        let scsi = format!("{interface}/host6/target6:0:0/6:0:0:0");
+        let device = |devpath: &str, subsystem: &str, devtype: &str| {
+            FixtureDevice::new(devpath, subsystem).with_devtype(devtype)
+        };
+
+        SysfsFixture::new()?.with_devices([
+            FixtureDevice::new(PCI, "pci"),
+            device(&format!("{PCI}/usb1"), "usb", "usb_device"),
+            device(&format!("{PCI}/usb1/1-2"), "usb", "usb_device"),
+            device(&interface, "usb", "usb_interface"),
+            device(&format!("{interface}/host6"), "scsi", "scsi_host"),
+            device(
+                &format!("{interface}/host6/target6:0:0"),
+                "scsi",
+                "scsi_target",
+            ),
+            device(&scsi, "scsi", "scsi_device"),
+            device(&format!("{scsi}/block/sdb"), "block", "disk"),
+            FixtureDevice::new("/devices/platform/serial8250", "platform"),
+            FixtureDevice::new("/devices/platform/serial8250/tty/ttyS0", "tty"),
+            FixtureDevice::new("/devices/virtual/block/loop0", "block"),
+        ])
+    }
+
+    #[test]
+    fn test_path_id() {
+        let mut path = String::new();
+        path_prepend(&mut path, "usb-0:1:1.0");
+        path_prepend(&mut path, "pci-0000:00:14.0");
+        assert_eq!(path, "pci-0000:00:14.0-usb-0:1:1.0");
+        assert_eq!(path_tag(path.as_str()), "pci-0000_00_14_0-usb-0_1_1_0");
+        assert_eq!(path_tag("_platform-a:b:_"), "platform-a_b");
+
+        assert_eq!(format_lun(3), "lun-3");
+        assert_eq!(format_lun(0x4001_0203), "lun-0x0203400100000000");
+        assert_eq!(
+            vmbus_guid("{f8b3781a-1e82-4818-a1c3-63d806ec15bb}").as_deref(),
+            Some("f8b3781a1e824818a1c363d806ec15bb")
+        );
+        assert_eq!(vmbus_guid("f8b3781a-1e82-4818-a1c3-63d806ec15bb"), None);
+    }
+
+    #[test]
+    fn test_path_id_fixture() -> Result<()> {
+        let fixture = fixture()?;
+        let scsi = format!("{PCI}/usb1/1-2/1-2:1.0/host6/target6:0:0/6:0:0:0");
+
+        let mut pci = fixture.device(PCI)?;
+        PathId.run(&mut pci, &[])?;
+        assert_eq!(pci.get_property_value("ID_PATH"), Some("pci-0000:00:14.0"));
+
+        let mut disk = fixture.device(format!("{scsi}/block/sdb").as_str())?;
+        assert_eq!(
+            disk.path_id().as_deref(),
+            Some("pci-0000:00:14.0-usb-0:2:1.0-scsi-0:0:0:0")
+        );
+
+        PathId.run(&mut disk, &[])?;
+        assert_eq!(
+            disk.get_property_value("ID_PATH_TAG"),
+            Some("pci-0000_00_14_0-usb-0_2_1_0-scsi-0_0_0_0")
+        );
+
+        let tty = fixture.device("/devices/platform/serial8250/tty/ttyS0")?;
+        assert_eq!(tty.path_id().as_deref(), Some("platform-serial8250"));
+
+        // virtual devices have no persistent path
+        let loop0 = fixture.device("/devices/virtual/block/loop0")?;
+        assert_eq!(loop0.path_id(), None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,536 @@
+use super::{builtin_add_property, builtin_parent, builtin_sysattr, builtin_sysname, UdevBuiltin};
+use crate::{util, Error, Result, UdevDevice};
+
+/// USB interface descriptor type.
+const USB_DT_INTERFACE: u8 = 0x04;
+/// Length of the USB interface descriptor.
+const USB_DT_INTERFACE_SIZE: usize = 9;
+
+/// USB device identification, computed from the `usb_device` and `usb_interface` parents.
+///
+/// Created by [UdevDevice::usb_info], and used by the [UsbId] built-in.
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UsbInfo {
+    vendor: String,
+    vendor_enc: String,
+    vendor_id: String,
+    model: String,
+    model_enc: String,
+    model_id: String,
+    revision: String,
+    serial: String,
+    serial_short: String,
+    id_type: String,
+    instance: String,
+    interfaces: String,
+    interface_num: String,
+    interface_class: Option<u8>,
+    interface_subclass: Option<u8>,
+    interface_protocol: Option<u8>,
+    driver: String,
+}
+
+impl UsbInfo {
+    /// Reads the [UsbInfo] of the device, from the closest `usb_device` parent.
+    ///
+    /// Devices below a `usb_interface` also get the interface information, and SCSI devices on
+    /// USB mass storage interfaces use the SCSI vendor, model, and revision.
+    ///
+    /// Returns: `Ok(UsbInfo)` on success, `Err(Error)` if the device is not a USB device.
+    pub fn read(dev: &UdevDevice) -> Result<Self> {
+        if dev.syspath().is_empty() {
+            return Err(Error::UdevBuiltin("usb_id: device has no syspath".into()));
+        }
+
+        let mut info = Self::default();
+        let parent;
+
+        let usb_dev = if dev.get_devtype() == "usb_device" {
+            dev
+        } else {
+            // find the interface the device is attached to
+            let parent_if;
+            let interface = if dev.get_subsystem() == "usb" && dev.get_devtype() == "usb_interface"
+            {
+                dev
+            } else {
+                parent_if = builtin_parent(dev, "usb", "usb_interface").ok_or(
+                    Error::UdevBuiltin(format!(
+                        "usb_id: unable to access usb_interface device of: {}",
+                        dev.syspath()
+                    )),
+                )?;
+                &parent_if
+            };
+
+            info.read_interface(dev, interface);
+
+            parent = builtin_parent(interface, "usb", "usb_device").ok_or(Error::UdevBuiltin(
+                format!(
+                    "usb_id: unable to find parent usb_device of: {}",
+                    dev.syspath()
+                ),
+            ))?;
+            &parent
+        };
+
+        info.read_device(usb_dev);
+
+        Ok(info)
+    }
+
+    fn read_interface(&mut self, dev: &UdevDevice, interface: &UdevDevice) {
+        let hex_attr = |attr: &str| {
+            builtin_sysattr(interface, attr).and_then(|c| u8::from_str_radix(c.as_str(), 16).ok())
+        };
+
+        self.interface_num = builtin_sysattr(interface, "bInterfaceNumber").unwrap_or_default();
+        self.driver = builtin_sysattr(interface, "driver").unwrap_or_default();
+        self.interface_class = hex_attr("bInterfaceClass");
+        self.interface_subclass = hex_attr("bInterfaceSubClass");
+        self.interface_protocol = hex_attr("bInterfaceProtocol");
+
+        let if_class = self.interface_class.unwrap_or(0);
+        self.id_type = usb_iftype(if_class).into();
+
+        if if_class != 8 {
+            return;
+        }
+
+        let if_subclass = self.interface_subclass.unwrap_or(0);
+        self.id_type = usb_mass_storage_ifsubtype(if_subclass).into();
+
+        // mass storage: SCSI or ATAPI
+        if if_subclass != 6 && if_subclass != 2 {
+            return;
+        }
+
+        let scsi = if dev.get_devtype() == "scsi_device" {
+            Some(dev.clone())
+        } else {
+            builtin_parent(dev, "scsi", "scsi_device")
+        };
+
+        if let Some(scsi) = scsi {
+            let sysname = builtin_sysname(&scsi);
+            let hctl: Vec<u32> = sysname
+                .split(':')
+                .filter_map(|n| n.parse::<u32>().ok())
+                .collect();
+
+            if let [_, _, target, lun] = hctl[..] {
+                if let Some(val) = builtin_sysattr(&scsi, "vendor") {
+                    self.vendor_enc = util::encode_string(val.as_str()).unwrap_or_default();
+                    self.vendor = sanitize(val.as_str());
+                }
+                if let Some(val) = builtin_sysattr(&scsi, "model") {
+                    self.model_enc = util::encode_string(val.as_str()).unwrap_or_default();
+                    self.model = sanitize(val.as_str());
+                }
+                if let Some(val) = builtin_sysattr(&scsi, "type") {
+                    self.id_type = scsi_type(val.as_str()).into();
+                }
+                if let Some(val) = builtin_sysattr(&scsi, "rev") {
+                    self.revision = sanitize(val.as_str());
+                }
+
+                // From the `usb_id` documentation:
+                //
+                // some broken devices have the same identifiers
+                // for all luns, export the target:lun number
+                self.instance = format!("{target}:{lun}");
+            }
+        }
+    }
+
+    fn read_device(&mut self, usb_dev: &UdevDevice) {
+        self.interfaces = usb_packed_interfaces(usb_dev);
+        self.vendor_id = builtin_sysattr(usb_dev, "idVendor").unwrap_or_default();
+        self.model_id = builtin_sysattr(usb_dev, "idProduct").unwrap_or_default();
+
+        // fallback to USB vendor & device
+        if self.vendor.is_empty() {
+            let val =
+                builtin_sysattr(usb_dev, "manufacturer").unwrap_or_else(|| self.vendor_id.clone());
+            self.vendor_enc = util::encode_string(val.as_str()).unwrap_or_default();
+            self.vendor = sanitize(val.as_str());
+        }
+
+        if self.model.is_empty() {
+            let val = builtin_sysattr(usb_dev, "product").unwrap_or_else(|| self.model_id.clone());
+            self.model_enc = util::encode_string(val.as_str()).unwrap_or_default();
+            self.model = sanitize(val.as_str());
+        }
+
+        if self.revision.is_empty() {
+            self.revision = builtin_sysattr(usb_dev, "bcdDevice")
+                .map(|v| sanitize(v.as_str()))
+                .unwrap_or_default();
+        }
+
+        // serial numbers with control, non-ASCII, or ',' characters are ignored
+        self.serial_short = builtin_sysattr(usb_dev, "serial")
+            .filter(|s| s.bytes().all(|b| (0x20..=0x7f).contains(&b) && b != b','))
+            .map(|s| sanitize(s.as_str()))
+            .unwrap_or_default();
+
+        self.serial = [self.vendor.as_str(), self.model.as_str()].join("_");
+        if !self.serial_short.is_empty() {
+            self.serial.push('_');
+            self.serial.push_str(self.serial_short.as_str());
+        }
+        if !self.instance.is_empty() {
+            self.serial.push('-');
+            self.serial.push_str(self.instance.as_str());
+        }
+    }
+
+    /// Gets the vendor name, e.g. `SanDisk`, used for `ID_VENDOR`.
+    pub fn vendor(&self) -> &str {
+        self.vendor.as_str()
+    }
+
+    /// Gets the encoded vendor name, used for `ID_VENDOR_ENC`.
+    pub fn vendor_enc(&self) -> &str {
+        self.vendor_enc.as_str()
+    }
+
+    /// Gets the `idVendor`, e.g. `0781`, used for `ID_VENDOR_ID`.
+    pub fn vendor_id(&self) -> &str {
+        self.vendor_id.as_str()
+    }
+
+    /// Gets the model name, e.g. `Cruzer_Blade`, used for `ID_MODEL`.
+    pub fn model(&self) -> &str {
+        self.model.as_str()
+    }
+
+    /// Gets the encoded model name, used for `ID_MODEL_ENC`.
+    pub fn model_enc(&self) -> &str {
+        self.model_enc.as_str()
+    }
+
+    /// Gets the `idProduct`, e.g. `5567`, used for `ID_MODEL_ID`.
+    pub fn model_id(&self) -> &str {
+        self.model_id.as_str()
+    }
+
+    /// Gets the revision, from `bcdDevice`, or the SCSI `rev`, used for `ID_REVISION`.
+    pub fn revision(&self) -> &str {
+        self.revision.as_str()
+    }
+
+    /// Gets the composed serial, e.g. `SanDisk_Cruzer_Blade_4C53`, used for `ID_SERIAL`.
+    pub fn serial(&self) -> &str {
+        self.serial.as_str()
+    }
+
+    /// Gets the device `serial`, used for `ID_SERIAL_SHORT`.
+    pub fn serial_short(&self) -> &str {
+        self.serial_short.as_str()
+    }
+
+    /// Gets the device type, e.g. `disk`, used for `ID_TYPE`.
+    pub fn id_type(&self) -> &str {
+        self.id_type.as_str()
+    }
+
+    /// Gets the SCSI `target:lun` instance, used for `ID_INSTANCE`.
+    pub fn instance(&self) -> &str {
+        self.instance.as_str()
+    }
+
+    /// Gets the packed interfaces, e.g. `:080650:`, used for `ID_USB_INTERFACES`.
+    pub fn interfaces(&self) -> &str {
+        self.interfaces.as_str()
+    }
+
+    /// Gets the `bInterfaceNumber`, used for `ID_USB_INTERFACE_NUM`.
+    pub fn interface_num(&self) -> &str {
+        self.interface_num.as_str()
+    }
+
+    /// Gets the `bInterfaceClass`.
+    pub const fn interface_class(&self) -> Option<u8> {
+        self.interface_class
+    }
+
+    /// Gets the `bInterfaceSubClass`.
+    pub const fn interface_subclass(&self) -> Option<u8> {
+        self.interface_subclass
+    }
+
+    /// Gets the `bInterfaceProtocol`.
+    pub const fn interface_protocol(&self) -> Option<u8> {
+        self.interface_protocol
+    }
+
+    /// Gets the interface driver, used for `ID_USB_DRIVER`.
+    pub fn driver(&self) -> &str {
+        self.driver.as_str()
+    }
+
+    /// Gets the `ID_*` properties set by the [UsbId] built-in.
+    ///
+    /// Properties with empty values are included, and skipped by [builtin_add_property].
+    pub fn properties(&self) -> [(&'static str, &str); 15] {
+        [
+            ("ID_VENDOR", self.vendor()),
+            ("ID_VENDOR_ENC", self.vendor_enc()),
+            ("ID_VENDOR_ID", self.vendor_id()),
+            ("ID_MODEL", self.model()),
+            ("ID_MODEL_ENC", self.model_enc()),
+            ("ID_MODEL_ID", self.model_id()),
+            ("ID_REVISION", self.revision()),
+            ("ID_SERIAL", self.serial()),
+            ("ID_SERIAL_SHORT", self.serial_short()),
+            ("ID_TYPE", self.id_type()),
+            ("ID_INSTANCE", self.instance()),
+            ("ID_BUS", "usb"),
+            ("ID_USB_INTERFACES", self.interfaces()),
+            ("ID_USB_INTERFACE_NUM", self.interface_num()),
+            ("ID_USB_DRIVER", self.driver()),
+        ]
+    }
+}
+
+impl UdevDevice {
+    /// Gets the USB identification of the device, the way the `usb_id` built-in computes it.
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{Udev, UdevDevice};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let udev = Arc::new(Udev::new());
+    /// let disk = UdevDevice::new_from_subsystem_sysname(udev, "block", "sdb")?;
+    ///
+    /// if let Some(usb) = disk.usb_info() {
+    ///     println!("{}:{} {}", usb.vendor_id(), usb.model_id(), usb.serial());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns: `Some(UsbInfo)` for USB devices, and devices below a USB interface, `None`
+    /// otherwise.
+    pub fn usb_info(&self) -> Option<UsbInfo> {
+        UsbInfo::read(self).ok()
+    }
+}
+
+/// Built-in that identifies USB devices.
+///
+/// Adds the properties of the [UsbInfo]:
+///
+/// - `ID_VENDOR`, `ID_VENDOR_ENC`, `ID_VENDOR_ID`
+/// - `ID_MODEL`, `ID_MODEL_ENC`, `ID_MODEL_ID`
+/// - `ID_REVISION`
+/// - `ID_SERIAL`, `ID_SERIAL_SHORT`
+/// - `ID_TYPE`, `ID_INSTANCE`
+/// - `ID_BUS=usb`
+/// - `ID_USB_INTERFACES`, `ID_USB_INTERFACE_NUM`, `ID_USB_DRIVER`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct UsbId;
+
+impl UdevBuiltin for UsbId {
+    fn name(&self) -> &str {
+        "usb_id"
+    }
+
+    fn help(&self) -> &str {
+        "USB device properties"
+    }
+
+    fn run(&self, dev: &mut UdevDevice, _args: &[&str]) -> Result<()> {
+        let info = UsbInfo::read(dev)?;
+
+        for (key, value) in info.properties() {
+            builtin_add_property(dev, key, value);
+        }
+
+        Ok(())
+    }
+}
+
+fn sanitize(val: &str) -> String {
+    util::replace_chars(util::replace_whitespace(val).as_str(), "")
+}
+
+/// Gets the `ID_TYPE` for the USB interface class.
+fn usb_iftype(if_class: u8) -> &'static str {
+    match if_class {
+        0x01 => "audio",
+        0x06 => "media",
+        0x07 => "printer",
+        0x08 => "storage",
+        0x0e => "video",
+        // HID devices are handled by the input layer
+        _ => "generic",
+    }
+}
+
+/// Gets the `ID_TYPE` for the USB mass storage interface subclass.
+fn usb_mass_storage_ifsubtype(if_subclass: u8) -> &'static str {
+    match if_subclass {
+        // RBC devices
+        1 => "rbc",
+        2 => "atapi",
+        3 => "tape",
+        // UFI
+        4 => "floppy",
+        // Transparent SPC-2 devices
+        6 => "scsi",
+        _ => "generic",
+    }
+}
+
+/// Gets the `ID_TYPE` for the SCSI peripheral device `type` attribute.
+fn scsi_type(scsi_type: &str) -> &'static str {
+    match scsi_type.trim().parse::<u8>() {
+        Ok(0) | Ok(0xe) => "disk",
+        Ok(1) => "tape",
+        Ok(4) | Ok(7) | Ok(0xf) => "optical",
+        Ok(5) => "cd",
+        _ => "generic",
+    }
+}
+
+fn usb_packed_interfaces(usb_dev: &UdevDevice) -> String {
+    std::fs::read(format!("{}/descriptors", usb_dev.syspath()))
+        .map(|desc| packed_interfaces(desc.as_ref()))
+        .unwrap_or_default()
+}
+
+/// Packs the class, subclass and protocol of the unique interfaces in the USB `descriptors`.
+///
+/// Returns: the packed interfaces, e.g. `:080650:`, or an empty string if there are none.
+fn packed_interfaces(descriptors: &[u8]) -> String {
+    let mut packed = String::new();
+    let mut pos = 0usize;
+
+    while pos + USB_DT_INTERFACE_SIZE < descriptors.len() {
+        let desc = &descriptors[pos..];
+        let len = desc[0] as usize;
+        if len < 3 {
+            break;
+        }
+        pos += len;
+
+        if desc[1] != USB_DT_INTERFACE {
+            continue;
+        }
+
+        let interface = format!(":{:02x}{:02x}{:02x}", desc[5], desc[6], desc[7]);
+        if !packed.contains(interface.as_str()) {
+            packed.push_str(interface.as_str());
+        }
+    }
+
+    if !packed.is_empty() {
+        packed.push(':');
+    }
+
+    packed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FixtureDevice, SysfsFixture};
+
+    const USB_PATH: &str = "/devices/pci0000:00/0000:00:14.0/usb1/1-2";
+
+    // USB storage device, with its interface, and SCSI host, and a non-USB device.
+    fn fixture() -> Result<SysfsFixture> {
+        SysfsFixture::new()?.with_devices([
+            FixtureDevice::new(USB_PATH, "usb")
+                .with_devtype("usb_device")
+                .with_sysattr("idVendor", "0781\n")
+                .with_sysattr("idProduct", "5567\n")
+                .with_sysattr("bcdDevice", "0100\n")
+                .with_sysattr("manufacturer", "SanDisk\n")
+                .with_sysattr("product", "Cruzer Blade\n")
+                .with_sysattr("serial", "4C53\n"),
+            FixtureDevice::new(format!("{USB_PATH}/1-2:1.0"), "usb")
+                .with_devtype("usb_interface")
+                .with_driver("usb-storage")
+                .with_sysattr("bInterfaceNumber", "00\n")
+                .with_sysattr("bInterfaceClass", "08\n")
+                .with_sysattr("bInterfaceSubClass", "06\n")
+                .with_sysattr("bInterfaceProtocol", "50\n"),
+            FixtureDevice::new(format!("{USB_PATH}/1-2:1.0/host6"), "scsi")
+                .with_devtype("scsi_host"),
+            FixtureDevice::new("/devices/virtual/mem/null", "mem"),
+        ])
+    }
+
+    #[test]
+    fn test_usb_id() {
+        assert_eq!(usb_iftype(0x08), "storage");
+        assert_eq!(usb_iftype(0x03), "generic");
+        assert_eq!(usb_mass_storage_ifsubtype(6), "scsi");
+        assert_eq!(scsi_type("5"), "cd");
+        assert_eq!(scsi_type("0\n"), "disk");
+        assert_eq!(scsi_type("bogus"), "generic");
+
+        // device descriptor, configuration descriptor, two identical interfaces, and a HID
+        // interface with an endpoint
+        let descriptors = [
+            0x12, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x40, 0x6b, 0x1d, 0x02, 0x00, 0x06, 0x06,
+            0x03, 0x02, 0x01, 0x01, //
+            0x09, 0x02, 0x19, 0x00, 0x01, 0x01, 0x00, 0xe0, 0x00, //
+            0x09, 0x04, 0x00, 0x00, 0x02, 0x08, 0x06, 0x50, 0x00, //
+            0x09, 0x04, 0x01, 0x00, 0x02, 0x08, 0x06, 0x50, 0x00, //
+            0x09, 0x04, 0x02, 0x00, 0x01, 0x03, 0x01, 0x02, 0x00, //
+            0x07, 0x05, 0x81, 0x03, 0x04, 0x00, 0x0c,
+        ];
+
+        assert_eq!(packed_interfaces(&descriptors), ":080650:030102:");
+        assert_eq!(packed_interfaces(&descriptors[..18]), "");
+    }
+
+    #[test]
+    fn test_usb_info() -> Result<()> {
+        let fixture = fixture()?;
+
+        let mut host = fixture.device(format!("{USB_PATH}/1-2:1.0/host6").as_str())?;
+        let info = host
+            .usb_info()
+            .ok_or(Error::UdevBuiltin("no usb info".into()))?;
+
+        assert_eq!(info.vendor(), "SanDisk");
+        assert_eq!(info.vendor_id(), "0781");
+        assert_eq!(info.model(), "Cruzer_Blade");
+        assert_eq!(info.model_id(), "5567");
+        assert_eq!(info.revision(), "0100");
+        assert_eq!(info.serial(), "SanDisk_Cruzer_Blade_4C53");
+        assert_eq!(info.serial_short(), "4C53");
+        assert_eq!(info.id_type(), "scsi");
+        assert_eq!(info.interface_num(), "00");
+        assert_eq!(info.interface_class(), Some(0x08));
+        assert_eq!(info.interface_subclass(), Some(0x06));
+        assert_eq!(info.interface_protocol(), Some(0x50));
+        assert_eq!(info.driver(), "usb-storage");
+
+        UsbId.run(&mut host, &[])?;
+        assert_eq!(host.get_property_value("ID_SERIAL"), Some(info.serial()));
+        assert_eq!(host.get_property_value("ID_BUS"), Some("usb"));
+        assert_eq!(host.get_property_value("ID_INSTANCE"), None);
+
+        // the usb_device itself has no interface information
+        let usb = fixture.device(USB_PATH)?.usb_info();
+        assert!(usb.as_ref().is_some_and(|u| u.interface_class().is_none()));
+        assert_eq!(
+            usb.map(|u| u.serial().to_owned()),
+            Some(info.serial().to_owned())
+        );
+
+        assert!(fixture
+            .device("/devices/virtual/mem/null")?
+            .usb_info()
+            .is_none());
+
+        Ok(())
+    }
+}
@@ -1,13 +1,85 @@
-use std::{cmp, fmt, fs, io, sync::Arc};
+use std::sync::{Arc, OnceLock};
+use std::{cmp, env, fmt, fs, io};
 
-use crate::{file_handle, name_to_handle_at, Error, LogPriority, Result, UdevEntryList, UdevList};
+use crate::log::{register_log_fn, unregister_log_fn};
+use crate::{
+    file_handle, name_to_handle_at, Error, LogFn, LogPriority, Result, UdevEntryList, UdevList,
+    SYS_MOUNT_POINT,
+};
+
+mod cache;
+mod config;
+
+pub use cache::*;
+pub use config::*;
 
 pub const RULES_PATH_LEN: usize = 4;
 
+/// Default `udev` run path, used when the [Udev] context does not set one.
+///
+/// The `udev` directory in [UDEV_ROOT_RUN](crate::UDEV_ROOT_RUN).
+pub const DEFAULT_RUN_PATH: &str = env!("UDEVRS_UDEV_RUN_DIR");
+/// Default device node path, used when the [Udev] context does not set one.
+pub const DEFAULT_DEV_PATH: &str = "/dev";
+/// Default `udev` library path, used when the [Udev] context does not set one.
+///
+/// Set with the `UDEVRS_LIBEXEC_DIR` environment variable at build time.
+pub const DEFAULT_LIBEXEC_PATH: &str = env!("UDEVRS_LIBEXEC_DIR");
+
+/// Runtime environment variable that overrides the default `sysfs` mount point.
+pub const SYS_PATH_ENV: &str = "UDEVRS_SYS_PATH";
+/// Runtime environment variable that overrides the default device node path.
+pub const DEV_PATH_ENV: &str = "UDEVRS_DEV_PATH";
+/// Runtime environment variable that overrides the default `udev` run path.
+pub const RUN_PATH_ENV: &str = "UDEV_RUN_PATH";
+/// Alternative name of [RUN_PATH_ENV], with the `UDEVRS_` prefix of the other path overrides.
+///
+/// Read if [RUN_PATH_ENV] is unset.
+pub const RUN_PATH_ENV_ALT: &str = "UDEVRS_RUN_PATH";
+/// Runtime environment variable that overrides the default `udev` library path.
+pub const LIBEXEC_PATH_ENV: &str = "UDEV_LIBEXEC_DIR";
+/// Alternative name of [LIBEXEC_PATH_ENV], with the `UDEVRS_` prefix of the other path
+/// overrides.
+///
+/// Read if [LIBEXEC_PATH_ENV] is unset.
+pub const LIBEXEC_PATH_ENV_ALT: &str = "UDEVRS_LIBEXEC_PATH";
+
+static SYS_PATH_OVERRIDE: OnceLock<Option<String>> = OnceLock::new();
+static DEV_PATH_OVERRIDE: OnceLock<Option<String>> = OnceLock::new();
+static RUN_PATH_OVERRIDE: OnceLock<Option<String>> = OnceLock::new();
+static LIBEXEC_PATH_OVERRIDE: OnceLock<Option<String>> = OnceLock::new();
+
+// Reads an absolute path override from the environment, once per process.
+fn env_override(vars: &[&str], cell: &'static OnceLock<Option<String>>) -> Option<&'static str> {
+    cell.get_or_init(|| path_override(vars, |var| env::var(var).ok()))
+        .as_deref()
+}
+
+// Gets the first absolute path of the variables, in order, read with `lookup`.
+fn path_override<F: Fn(&str) -> Option<String>>(vars: &[&str], lookup: F) -> Option<String> {
+    vars.iter().find_map(|var| {
+        let path = lookup(var)?;
+        let trimmed = path.trim_end_matches('/');
+
+        if trimmed.starts_with('/') {
+            log::debug!("{var} overrides the default path: {trimmed}");
+            Some(trimmed.into())
+        } else {
+            log::warn!("ignoring {var}, the path is not absolute: {path}");
+            None
+        }
+    })
+}
+
 /// libudev context
 ///
-/// The context contains the default values read from the udev config file,
-/// and is passed to all library operations.
+/// The context contains the default values read from the udev config file, see
+/// [new_from_config](Self::new_from_config), and is passed to all library operations.
+///
+/// The `sys`, `dev`, `run`, and `libexec` paths select the filesystem trees used by the library,
+/// e.g. a fake `sysfs` tree in tests, or bind-mounted roots in containers. Unset paths fall back
+/// to the [SYS_PATH_ENV], [DEV_PATH_ENV], [RUN_PATH_ENV], and [LIBEXEC_PATH_ENV] environment
+/// variables, then to the defaults selected at build time.
 #[repr(C)]
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Udev {
@@ -16,8 +88,14 @@ pub struct Udev {
     rules_path: [String; RULES_PATH_LEN],
     rules_path_ts: [u64; RULES_PATH_LEN],
     run_path: String,
+    libexec_path: String,
     properties_list: Option<UdevList>,
     log_priority: LogPriority,
+    children_max: u32,
+    exec_delay: u32,
+    resolve_names: ResolveNames,
+    log_fn: Option<LogFn>,
+    device_cache: WeakDeviceCache,
 }
 
 impl Udev {
@@ -29,26 +107,65 @@ impl Udev {
             rules_path: [""; RULES_PATH_LEN].map(String::from),
             rules_path_ts: [0; RULES_PATH_LEN],
             run_path: String::new(),
+            libexec_path: String::new(),
             properties_list: None,
             log_priority: LogPriority::new(),
+            children_max: 0,
+            exec_delay: 0,
+            resolve_names: ResolveNames::new(),
+            log_fn: None,
+            device_cache: WeakDeviceCache::default(),
         }
     }
 
     /// Convenience function for `udev` log messages.
+    ///
+    /// Messages above the [LogPriority] of the context are dropped. Other messages are passed to
+    /// the [LogFn], if set, or the `log` crate, at the matching [`Level`](log::Level).
     pub fn log<M: fmt::Display>(&self, priority: LogPriority, msg: M) {
         if priority <= self.log_priority {
-            match priority {
-                LogPriority::Emergency
-                | LogPriority::Alert
-                | LogPriority::Critical
-                | LogPriority::Error => log::error!("{priority}: {msg}"),
-                LogPriority::Warning => log::warn!("{priority}: {msg}"),
-                LogPriority::Notice | LogPriority::Info => log::info!("{priority}: {msg}"),
-                LogPriority::Debug => log::debug!("{priority}: {msg}"),
+            match self.log_fn.as_ref() {
+                Some(log_fn) => log_fn.call(priority, msg.to_string().as_str()),
+                None => log::log!(log::Level::from(priority), "{priority}: {msg}"),
             }
         }
     }
 
+    /// Gets the logging callback [LogFn].
+    pub fn log_fn(&self) -> Option<&LogFn> {
+        self.log_fn.as_ref()
+    }
+
+    /// Sets the logging callback, like `udev_set_log_fn` in `libudev`.
+    ///
+    /// Routes the [log](Self::log) messages of the context, and the `log` crate records of the
+    /// library, up to the [LogPriority] of the context, e.g. to a `journald`, or `tracing`,
+    /// pipeline of the embedding application.
+    ///
+    /// `log` records carry no context, so the library records go to the callback set last, by
+    /// any context. The callback is installed as the `log` crate logger of the process, unless
+    /// the application already installed one, which then keeps receiving the library records.
+    pub fn set_log_fn<F: Fn(LogPriority, &str) + Send + Sync + 'static>(&mut self, f: F) {
+        let log_fn = LogFn::new(f);
+        register_log_fn(log_fn.clone(), self.log_priority);
+        self.log_fn = Some(log_fn);
+    }
+
+    /// Builder function that sets the logging callback.
+    ///
+    /// See [set_log_fn](Self::set_log_fn).
+    pub fn with_log_fn<F: Fn(LogPriority, &str) + Send + Sync + 'static>(mut self, f: F) -> Self {
+        self.set_log_fn(f);
+        self
+    }
+
+    /// Clears the logging callback, messages go to the `log` crate again.
+    pub fn clear_log_fn(&mut self) {
+        if let Some(log_fn) = self.log_fn.take() {
+            unregister_log_fn(&log_fn);
+        }
+    }
+
     /// Gets the system path.
     pub fn sys_path(&self) -> &str {
         self.sys_path.as_str()
@@ -65,6 +182,16 @@ impl Udev {
         self
     }
 
+    /// Gets the system path, falling back to [SYS_PATH_ENV], then the `sysfs` mount point if the
+    /// system path is unset.
+    pub fn get_sys_path(&self) -> &str {
+        if self.sys_path.is_empty() {
+            env_override(&[SYS_PATH_ENV], &SYS_PATH_OVERRIDE).unwrap_or(SYS_MOUNT_POINT)
+        } else {
+            self.sys_path.as_str()
+        }
+    }
+
     /// Gets the device path.
     pub fn dev_path(&self) -> &str {
         self.dev_path.as_str()
@@ -81,6 +208,16 @@ impl Udev {
         self
     }
 
+    /// Gets the device path, falling back to [DEV_PATH_ENV], then [DEFAULT_DEV_PATH] if the
+    /// device path is unset.
+    pub fn get_dev_path(&self) -> &str {
+        if self.dev_path.is_empty() {
+            env_override(&[DEV_PATH_ENV], &DEV_PATH_OVERRIDE).unwrap_or(DEFAULT_DEV_PATH)
+        } else {
+            self.dev_path.as_str()
+        }
+    }
+
     /// Gets a reference to the list of rules paths.
     pub fn rules_path(&self) -> &[String] {
         let len = self.rules_path_count();
@@ -159,6 +296,51 @@ impl Udev {
         self
     }
 
+    /// Gets the run path, falling back to [RUN_PATH_ENV], [RUN_PATH_ENV_ALT], then
+    /// [DEFAULT_RUN_PATH] if the run path is unset.
+    ///
+    /// The run path holds the device database (`data`), and the tags reverse index (`tags`).
+    pub fn get_run_path(&self) -> &str {
+        if self.run_path.is_empty() {
+            env_override(&[RUN_PATH_ENV, RUN_PATH_ENV_ALT], &RUN_PATH_OVERRIDE)
+                .unwrap_or(DEFAULT_RUN_PATH)
+        } else {
+            self.run_path.as_str()
+        }
+    }
+
+    /// Gets the `udev` library path.
+    pub fn libexec_path(&self) -> &str {
+        self.libexec_path.as_str()
+    }
+
+    /// Sets the `udev` library path.
+    pub fn set_libexec_path<P: Into<String>>(&mut self, path: P) {
+        self.libexec_path = path.into();
+    }
+
+    /// Builder function that sets the `udev` library path.
+    pub fn with_libexec_path<P: Into<String>>(mut self, path: P) -> Self {
+        self.set_libexec_path(path);
+        self
+    }
+
+    /// Gets the `udev` library path, falling back to [LIBEXEC_PATH_ENV], [LIBEXEC_PATH_ENV_ALT],
+    /// then [DEFAULT_LIBEXEC_PATH] if the library path is unset.
+    ///
+    /// The library path holds the vendor hardware database (`hwdb.bin`).
+    pub fn get_libexec_path(&self) -> &str {
+        if self.libexec_path.is_empty() {
+            env_override(
+                &[LIBEXEC_PATH_ENV, LIBEXEC_PATH_ENV_ALT],
+                &LIBEXEC_PATH_OVERRIDE,
+            )
+            .unwrap_or(DEFAULT_LIBEXEC_PATH)
+        } else {
+            self.libexec_path.as_str()
+        }
+    }
+
     /// Gets a reference to the properties list [UdevList].
     ///
     /// **NOTE** User is responsible for initializing the [`properties_list`](UdevList) before calling.
@@ -210,7 +392,7 @@ impl Udev {
         self
     }
 
-    /// Gets whether `/dev` is mounted on `devtmpfs`.
+    /// Gets whether the device path, e.g. `/dev`, is mounted on `devtmpfs`.
     pub fn has_devtmpfs(&self) -> bool {
         use io::BufRead;
 
@@ -221,7 +403,13 @@ impl Udev {
             fs::OpenOptions::new()
                 .read(true)
                 .open("/proc/self/mountinfo"),
-            name_to_handle_at(libc::AT_FDCWD, "/dev", &mut handle, &mut mount_id, 0),
+            name_to_handle_at(
+                libc::AT_FDCWD,
+                self.get_dev_path(),
+                &mut handle,
+                &mut mount_id,
+                0,
+            ),
         ) {
             let mut reader = io::BufReader::new(f);
             let mut line = String::new();
@@ -258,6 +446,67 @@ impl Udev {
 mod tests {
     use super::*;
     use crate::UdevEntry;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_path_overrides() {
+        let lookup = |vars: &[(&str, &str)], names: &[&str]| {
+            let vars: HashMap<&str, &str> = vars.iter().copied().collect();
+            path_override(names, |var| vars.get(var).map(|v| v.to_string()))
+        };
+        let run = [RUN_PATH_ENV, RUN_PATH_ENV_ALT];
+        let libexec = [LIBEXEC_PATH_ENV, LIBEXEC_PATH_ENV_ALT];
+
+        assert_eq!(lookup(&[], &run), None);
+        assert_eq!(
+            lookup(&[("UDEV_RUN_PATH", "/tmp/run/udev/")], &run).as_deref(),
+            Some("/tmp/run/udev")
+        );
+        assert_eq!(
+            lookup(&[("UDEVRS_RUN_PATH", "/tmp/alt")], &run).as_deref(),
+            Some("/tmp/alt")
+        );
+        // the requested names come first, relative paths are ignored
+        assert_eq!(
+            lookup(
+                &[
+                    ("UDEV_RUN_PATH", "/tmp/run"),
+                    ("UDEVRS_RUN_PATH", "/tmp/alt")
+                ],
+                &run
+            )
+            .as_deref(),
+            Some("/tmp/run")
+        );
+        assert_eq!(
+            lookup(
+                &[("UDEV_RUN_PATH", "run"), ("UDEVRS_RUN_PATH", "/tmp/alt")],
+                &run
+            )
+            .as_deref(),
+            Some("/tmp/alt")
+        );
+
+        assert_eq!(
+            lookup(&[("UDEV_LIBEXEC_DIR", "/tmp/lib/udev")], &libexec).as_deref(),
+            Some("/tmp/lib/udev")
+        );
+        assert_eq!(
+            lookup(&[("UDEVRS_LIBEXEC_PATH", "/tmp/alt")], &libexec).as_deref(),
+            Some("/tmp/alt")
+        );
+        assert_eq!(
+            lookup(
+                &[
+                    ("UDEV_LIBEXEC_DIR", "/tmp/lib/udev"),
+                    ("UDEVRS_LIBEXEC_PATH", "/tmp/alt")
+                ],
+                &libexec
+            )
+            .as_deref(),
+            Some("/tmp/lib/udev")
+        );
+    }
 
     #[test]
     fn test_udev() -> Result<()> {
@@ -269,6 +518,7 @@ mod tests {
             ["test_rules0", "test_rules1", "test_rules2", "test_rules3"].map(String::from);
         let exp_rules_ts = [17092390, 17092391, 17092392, 17092393];
         let exp_run_path = "test_run_path";
+        let exp_libexec_path = "test_libexec_path";
         let exp_prop_list = [UdevEntry::new().with_name("test_entry_name")];
         let exp_log_prio = LogPriority::Debug;
 
@@ -278,6 +528,7 @@ mod tests {
             .with_rules_path(&exp_rules_path)
             .with_rules_path_ts(&exp_rules_ts)
             .with_run_path(exp_run_path)
+            .with_libexec_path(exp_libexec_path)
             .with_log_priority(exp_log_prio);
 
         assert_eq!(null_udev.sys_path(), "");
@@ -285,6 +536,7 @@ mod tests {
         assert!(null_udev.rules_path().is_empty());
         assert!(null_udev.rules_path_ts().is_empty());
         assert_eq!(null_udev.run_path(), "");
+        assert_eq!(null_udev.libexec_path(), "");
         assert!(null_udev.properties_list().is_err());
         assert_eq!(null_udev.log_priority(), LogPriority::new());
 
@@ -293,6 +545,8 @@ mod tests {
         assert_eq!(exp_udev.rules_path(), exp_rules_path.as_ref());
         assert_eq!(exp_udev.rules_path_ts(), exp_rules_ts.as_ref());
         assert_eq!(exp_udev.run_path(), exp_run_path);
+        assert_eq!(exp_udev.libexec_path(), exp_libexec_path);
+        assert_eq!(exp_udev.get_libexec_path(), exp_libexec_path);
 
         assert_eq!(exp_udev.log_priority(), exp_log_prio);
 
@@ -311,6 +565,9 @@ mod tests {
         null_udev.set_run_path(exp_run_path);
         assert_eq!(null_udev.run_path(), exp_run_path);
 
+        null_udev.set_libexec_path(exp_libexec_path);
+        assert_eq!(null_udev.libexec_path(), exp_libexec_path);
+
         null_udev.set_log_priority(exp_log_prio);
         assert_eq!(null_udev.log_priority(), exp_log_prio);
 
@@ -343,8 +600,7 @@ mod tests {
             assert_eq!(prop, exp_prop);
         }
 
-        let exp_udev =
-            Udev::with_properties_list(Arc::clone(&mut null_udev), exp_prop_list.clone());
+        let exp_udev = Udev::with_properties_list(Arc::clone(&null_udev), exp_prop_list.clone());
 
         for (prop, exp_prop) in exp_udev
             .properties_list()
@@ -359,4 +615,55 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_udev_log_fn() {
+        use std::sync::Mutex;
+
+        let logged = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&logged);
+
+        let mut udev = Udev::new()
+            .with_log_priority(LogPriority::Info)
+            .with_log_fn(move |priority, msg| {
+                if let Ok(mut l) = sink.lock() {
+                    l.push((priority, msg.to_owned()));
+                }
+            });
+        assert!(udev.log_fn().is_some());
+        assert_eq!(udev.clone(), udev);
+
+        udev.log(
+            LogPriority::Error,
+            "device /devices/virtual/mem/null failed",
+        );
+        // above the context priority
+        udev.log(LogPriority::Debug, "dropped");
+        // library records go to the callback too
+        log::warn!("library warning");
+        log::debug!("library debug");
+
+        udev.clear_log_fn();
+        assert!(udev.log_fn().is_none());
+        udev.log(LogPriority::Error, "to the log crate");
+        log::warn!("after clear");
+
+        // other tests may log concurrently
+        let logged = logged.lock().map(|l| l.clone()).unwrap_or_default();
+        let has = |priority, msg: &str| logged.iter().any(|(p, m)| *p == priority && m == msg);
+
+        assert!(has(
+            LogPriority::Error,
+            "device /devices/virtual/mem/null failed"
+        ));
+        assert!(has(LogPriority::Warning, "library warning"));
+        for msg in [
+            "dropped",
+            "library debug",
+            "to the log crate",
+            "after clear",
+        ] {
+            assert!(!logged.iter().any(|(_, m)| m == msg));
+        }
+    }
 }
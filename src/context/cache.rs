@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, RwLock, Weak};
+
+use super::Udev;
+use crate::{DeviceAction, LoadFlags, Result, SysPath, UdevDevice};
+
+type DeviceMap = HashMap<String, Arc<UdevDevice>>;
+
+/// Thread-safe cache of [UdevDevice]s, keyed by `syspath`.
+///
+/// Attached to a [Udev] context with [set_device_cache](Udev::set_device_cache), and used by
+/// [device_from_syspath_cached](Udev::device_from_syspath_cached). Clones share the same cache.
+///
+/// Monitors on the context invalidate devices on `remove`, `change`, and `move` events, see
+/// [invalidate](Self::invalidate).
+///
+/// The application owns the cache, the context only holds a weak reference. Dropping the last
+/// clone detaches the cache from the context, and releases the cached devices.
+///
+/// Cached devices are snapshots, loaded with their `uevent` file, database entry, and sys
+/// attribute names. The getters of a clone, e.g. [has_tag](UdevDevice::has_tag), read from the
+/// snapshot, and do not access `sys`, or the database again.
+#[derive(Clone, Default)]
+pub struct DeviceCache(Arc<RwLock<DeviceMap>>);
+
+impl DeviceCache {
+    /// Creates a new, empty [DeviceCache].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the number of cached devices.
+    pub fn len(&self) -> usize {
+        self.0.read().map(|c| c.len()).unwrap_or(0)
+    }
+
+    /// Gets whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Gets the cached device with the `syspath`.
+    pub fn get(&self, syspath: &str) -> Option<Arc<UdevDevice>> {
+        self.0.read().ok()?.get(syspath).map(Arc::clone)
+    }
+
+    /// Adds the device to the cache, replacing any device with the same `syspath`.
+    pub fn insert(&self, device: Arc<UdevDevice>) {
+        if let Ok(mut cache) = self.0.write() {
+            cache.insert(device.syspath().into(), device);
+        }
+    }
+
+    /// Removes the device with the `syspath` from the cache.
+    ///
+    /// Returns: the removed device, if it was cached.
+    pub fn remove(&self, syspath: &str) -> Option<Arc<UdevDevice>> {
+        self.0.write().ok()?.remove(syspath)
+    }
+
+    /// Removes all devices from the cache.
+    pub fn clear(&self) {
+        if let Ok(mut cache) = self.0.write() {
+            cache.clear();
+        }
+    }
+
+    /// Invalidates the cached device for a device event.
+    ///
+    /// `remove`, and `change` events remove the device with the event `syspath`. `move` events
+    /// also remove the device with the old `syspath`. Other events leave the cache unchanged.
+    ///
+    /// Returns: `true` if a cached device was removed.
+    pub fn invalidate(&self, event: &UdevDevice) -> bool {
+        match event.action_type() {
+            DeviceAction::Remove | DeviceAction::Change => self.remove(event.syspath()).is_some(),
+            DeviceAction::Move => {
+                let old = format!("{}{}", event.udev().get_sys_path(), event.devpath_old());
+                let old_removed = self.remove(old.as_str()).is_some();
+                self.remove(event.syspath()).is_some() || old_removed
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Debug for DeviceCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeviceCache")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl PartialEq for DeviceCache {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Weak reference to a [DeviceCache], held by the [Udev] context.
+///
+/// Cached devices hold a reference to the context, so a strong reference would keep the context,
+/// and the cache, alive forever.
+#[derive(Clone, Default)]
+pub(crate) struct WeakDeviceCache(Weak<RwLock<DeviceMap>>);
+
+impl WeakDeviceCache {
+    // Gets the cache, if the application still holds it.
+    fn upgrade(&self) -> Option<DeviceCache> {
+        self.0.upgrade().map(DeviceCache)
+    }
+}
+
+impl fmt::Debug for WeakDeviceCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WeakDeviceCache")
+            .field("attached", &(self.0.strong_count() > 0))
+            .finish()
+    }
+}
+
+impl PartialEq for WeakDeviceCache {
+    fn eq(&self, other: &Self) -> bool {
+        Weak::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Udev {
+    /// Gets the [DeviceCache], if one is attached, and the application still holds it.
+    pub fn device_cache(&self) -> Option<DeviceCache> {
+        self.device_cache.upgrade()
+    }
+
+    /// Attaches a [DeviceCache] to the context.
+    ///
+    /// Useful for applications receiving many events for the same devices, to avoid reading
+    /// the device from `sys` for every event.
+    ///
+    /// The context holds a weak reference, the cache stays attached while the application holds
+    /// a clone of it.
+    pub fn set_device_cache(&mut self, cache: &DeviceCache) {
+        self.device_cache = WeakDeviceCache(Arc::downgrade(&cache.0));
+    }
+
+    /// Builder function that attaches a [DeviceCache] to the context.
+    pub fn with_device_cache(mut self, cache: &DeviceCache) -> Self {
+        self.set_device_cache(cache);
+        self
+    }
+
+    /// Detaches the [DeviceCache] from the context.
+    pub fn clear_device_cache(&mut self) {
+        self.device_cache = WeakDeviceCache::default();
+    }
+
+    /// Gets the device with the `syspath`, from the [DeviceCache], if attached.
+    ///
+    /// Devices missing from the cache are read from `sys`, like
+    /// [UdevDevice::new_from_syspath], loaded with [LoadFlags::all], and added to the cache.
+    /// Without a cache, the device is always read from `sys`.
+    ///
+    /// Returns: `Ok(Arc<UdevDevice>)` on success, `Err(Error)` if the device does not exist.
+    pub fn device_from_syspath_cached(
+        self: &Arc<Self>,
+        syspath: &SysPath,
+    ) -> Result<Arc<UdevDevice>> {
+        let syspath = syspath.canonicalize()?;
+        let cache = self.device_cache();
+
+        if let Some(device) = cache.as_ref().and_then(|c| c.get(syspath.as_str())) {
+            return Ok(device);
+        }
+
+        let mut device = UdevDevice::new_from_syspath(Arc::clone(self), &syspath)?;
+        // devices without a database entry are still valid snapshots
+        device.load(LoadFlags::all());
+        let device = Arc::new(device);
+
+        if let Some(cache) = cache {
+            cache.insert(Arc::clone(&device));
+        }
+
+        Ok(device)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FixtureDevice, SysfsFixture};
+    use std::thread;
+
+    // memory device with a device node, e.g. `/sys/devices/virtual/mem/null`
+    fn fixture() -> Result<SysfsFixture> {
+        SysfsFixture::new()?
+            .with_device(FixtureDevice::new("/devices/virtual/mem/null", "mem").with_devnum(1, 3))
+    }
+
+    #[test]
+    fn test_device_cache() -> Result<()> {
+        let fixture = fixture()?;
+        let cache = DeviceCache::new();
+        let udev = Arc::new(
+            Udev::new()
+                .with_sys_path(fixture.sys_path())
+                .with_run_path(fixture.run_path())
+                .with_device_cache(&cache),
+        );
+
+        assert!(cache.is_empty());
+        assert_eq!(udev.device_cache(), Some(cache.clone()));
+        assert_ne!(&DeviceCache::new(), &cache);
+
+        let sys = udev.get_sys_path();
+
+        let syspath = SysPath::new_in(sys, format!("{sys}/devices/virtual/mem/null"))?;
+        let device = udev.device_from_syspath_cached(&syspath)?;
+        assert_eq!(cache.len(), 1);
+        assert_eq!(device.get_devnum(), libc::makedev(1, 3));
+        assert!(Arc::ptr_eq(
+            &device,
+            &udev.device_from_syspath_cached(&syspath)?
+        ));
+
+        // lookups from other threads share the cache
+        let shared = Arc::clone(&udev);
+        let path = syspath.clone();
+        let other = thread::spawn(move || shared.device_from_syspath_cached(&path))
+            .join()
+            .expect("lookup thread panicked")?;
+        assert!(Arc::ptr_eq(&device, &other));
+
+        let event = |action: &str, syspath: &str| {
+            let mut dev = UdevDevice::new(Arc::clone(&udev)).with_syspath(syspath);
+            dev.set_action(action);
+            dev
+        };
+
+        let (a, b, c) = (
+            format!("{sys}/devices/a"),
+            format!("{sys}/devices/b"),
+            format!("{sys}/devices/c"),
+        );
+        for path in [&a, &b, &c] {
+            cache.insert(Arc::new(event("", path.as_str())));
+        }
+        let len = cache.len();
+
+        assert!(!cache.invalidate(&event("add", a.as_str())));
+        assert!(cache.invalidate(&event("change", a.as_str())));
+        assert!(!cache.invalidate(&event("remove", a.as_str())));
+        assert!(cache.get(a.as_str()).is_none());
+
+        // moves invalidate the old syspath
+        let moved =
+            event("move", format!("{sys}/devices/d").as_str()).with_devpath_old("/devices/b");
+        assert!(cache.invalidate(&moved));
+        assert!(cache.get(b.as_str()).is_none());
+        assert!(cache.get(c.as_str()).is_some());
+        assert_eq!(cache.len(), len - 2);
+
+        cache.clear();
+        assert!(cache.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_device_cache_release() {
+        let cache = DeviceCache::new();
+        let udev = Arc::new(Udev::new().with_device_cache(&cache));
+
+        cache.insert(Arc::new(
+            UdevDevice::new(Arc::clone(&udev)).with_syspath("/sys/devices/a"),
+        ));
+        assert!(Arc::strong_count(&udev) > 1);
+
+        // dropping the cache detaches it, and releases the devices holding the context
+        drop(cache);
+        assert!(udev.device_cache().is_none());
+        assert_eq!(Arc::strong_count(&udev), 1);
+    }
+}
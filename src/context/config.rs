@@ -0,0 +1,264 @@
+use std::{env, fmt, fs};
+
+use super::Udev;
+use crate::{Error, LogPriority, Result};
+
+/// Default `udev` configuration file path.
+///
+/// The `udev/udev.conf` file in the `UDEVRS_SYSCONF_DIR` set at build time.
+pub const UDEV_CONF_PATH: &str = env!("UDEVRS_UDEV_CONF");
+/// Runtime environment variable that overrides the `udev_log` setting.
+pub const UDEV_LOG_ENV: &str = "UDEV_LOG";
+
+/// Represents when `udev` resolves user, and group, names in rules.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ResolveNames {
+    /// Resolve names when the rules are parsed.
+    #[default]
+    Early,
+    /// Resolve names when the rules are applied to an event.
+    Late,
+    /// Never resolve names, only numeric IDs are accepted.
+    Never,
+}
+
+impl ResolveNames {
+    /// Creates a new [ResolveNames].
+    pub const fn new() -> Self {
+        Self::Early
+    }
+}
+
+impl TryFrom<&str> for ResolveNames {
+    type Error = Error;
+
+    fn try_from(val: &str) -> Result<Self> {
+        match val.trim() {
+            "early" => Ok(Self::Early),
+            "late" => Ok(Self::Late),
+            "never" => Ok(Self::Never),
+            _ => Err(Error::Udev(format!("invalid resolve_names value: {val}"))),
+        }
+    }
+}
+
+impl From<&ResolveNames> for &'static str {
+    fn from(val: &ResolveNames) -> Self {
+        match val {
+            ResolveNames::Early => "early",
+            ResolveNames::Late => "late",
+            ResolveNames::Never => "never",
+        }
+    }
+}
+
+impl From<ResolveNames> for &'static str {
+    fn from(val: ResolveNames) -> Self {
+        (&val).into()
+    }
+}
+
+impl fmt::Display for ResolveNames {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", <&str>::from(self))
+    }
+}
+
+impl Udev {
+    /// Creates a new [Udev] from the `udev` configuration file, like `udev_new` in `libudev`.
+    ///
+    /// Reads the settings from [UDEV_CONF_PATH], see [load_config](Self::load_config). A missing,
+    /// or unreadable, configuration file leaves the defaults.
+    ///
+    /// The [UDEV_LOG_ENV] environment variable overrides the `udev_log` setting.
+    pub fn new_from_config() -> Self {
+        let mut udev = Self::new();
+
+        if let Err(err) = udev.load_config(UDEV_CONF_PATH) {
+            log::debug!("unable to load {UDEV_CONF_PATH}: {err}");
+        }
+
+        udev.apply_log_env(env::var(UDEV_LOG_ENV).ok().as_deref());
+
+        udev
+    }
+
+    /// Loads the settings from the `udev` configuration file at `path`.
+    ///
+    /// The file contains `key=value` lines, with optionally quoted values. Empty lines, and lines
+    /// starting with `#`, are ignored. The supported settings are:
+    ///
+    /// - `udev_log`: the [LogPriority], as a number, or a name, e.g. `err`
+    /// - `children_max`: the maximum number of concurrent event workers
+    /// - `exec_delay`: the delay, in seconds, before running `RUN` programs
+    /// - `resolve_names`: when user, and group, names are resolved, see [ResolveNames]
+    ///
+    /// Unknown keys, and invalid values, are logged, and skipped, like `libudev`.
+    ///
+    /// Returns: `Ok(())` on success, `Err(Error)` if the file cannot be read.
+    pub fn load_config(&mut self, path: &str) -> Result<()> {
+        let contents = fs::read_to_string(path)?;
+        self.parse_config(contents.as_str());
+        Ok(())
+    }
+
+    /// Builder function that loads the settings from the `udev` configuration file at `path`.
+    ///
+    /// See [load_config](Self::load_config).
+    pub fn with_config(mut self, path: &str) -> Result<Self> {
+        self.load_config(path)?;
+        Ok(self)
+    }
+
+    /// Gets the maximum number of concurrent event workers, `0` if unset.
+    pub const fn children_max(&self) -> u32 {
+        self.children_max
+    }
+
+    /// Sets the maximum number of concurrent event workers.
+    pub fn set_children_max(&mut self, val: u32) {
+        self.children_max = val;
+    }
+
+    /// Builder function that sets the maximum number of concurrent event workers.
+    pub fn with_children_max(mut self, val: u32) -> Self {
+        self.set_children_max(val);
+        self
+    }
+
+    /// Gets the delay, in seconds, before running `RUN` programs.
+    pub const fn exec_delay(&self) -> u32 {
+        self.exec_delay
+    }
+
+    /// Sets the delay, in seconds, before running `RUN` programs.
+    pub fn set_exec_delay(&mut self, val: u32) {
+        self.exec_delay = val;
+    }
+
+    /// Builder function that sets the delay, in seconds, before running `RUN` programs.
+    pub fn with_exec_delay(mut self, val: u32) -> Self {
+        self.set_exec_delay(val);
+        self
+    }
+
+    /// Gets the [ResolveNames] setting.
+    pub const fn resolve_names(&self) -> ResolveNames {
+        self.resolve_names
+    }
+
+    /// Sets the [ResolveNames] setting.
+    pub fn set_resolve_names(&mut self, val: ResolveNames) {
+        self.resolve_names = val;
+    }
+
+    /// Builder function that sets the [ResolveNames] setting.
+    pub fn with_resolve_names(mut self, val: ResolveNames) -> Self {
+        self.set_resolve_names(val);
+        self
+    }
+
+    fn parse_config(&mut self, contents: &str) {
+        for (num, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                log::debug!("invalid udev.conf line {}: {line}", num + 1);
+                continue;
+            };
+
+            let (key, value) = (key.trim(), unquote(value.trim()));
+
+            let res = match key {
+                "udev_log" => LogPriority::try_from(value).map(|p| self.set_log_priority(p)),
+                "children_max" => parse_u32(key, value).map(|v| self.set_children_max(v)),
+                "exec_delay" => parse_u32(key, value).map(|v| self.set_exec_delay(v)),
+                "resolve_names" => ResolveNames::try_from(value).map(|v| self.set_resolve_names(v)),
+                _ => {
+                    log::debug!("unknown udev.conf key: {key}");
+                    Ok(())
+                }
+            };
+
+            if let Err(err) = res {
+                log::warn!("ignoring udev.conf line {}: {err}", num + 1);
+            }
+        }
+    }
+
+    fn apply_log_env(&mut self, val: Option<&str>) {
+        if let Some(val) = val {
+            match LogPriority::try_from(val) {
+                Ok(priority) => self.set_log_priority(priority),
+                Err(err) => log::warn!("ignoring {UDEV_LOG_ENV}: {err}"),
+            }
+        }
+    }
+}
+
+// Strips matching single, or double, quotes around the value.
+fn unquote(value: &str) -> &str {
+    ['"', '\'']
+        .iter()
+        .find_map(|&q| value.strip_prefix(q).and_then(|v| v.strip_suffix(q)))
+        .unwrap_or(value)
+}
+
+fn parse_u32(key: &str, value: &str) -> Result<u32> {
+    value
+        .parse::<u32>()
+        .map_err(|err| Error::Udev(format!("invalid {key} value: {value}, {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::SysfsFixture;
+
+    #[test]
+    fn test_udev_config() -> Result<()> {
+        let fixture = SysfsFixture::new()?;
+        let path = fixture.root().join("udev.conf");
+        let path = path.to_str().unwrap_or_default();
+
+        fs::write(
+            path,
+            "# comment\n\
+             \n\
+             udev_log=\"debug\"\n\
+             children_max = 8\n\
+             exec_delay='2'\n\
+             resolve_names=late\n\
+             event_timeout=180\n\
+             invalid line\n",
+        )?;
+
+        let udev = Udev::new().with_config(path)?;
+
+        assert_eq!(udev.log_priority(), LogPriority::Debug);
+        assert_eq!(udev.children_max(), 8);
+        assert_eq!(udev.exec_delay(), 2);
+        assert_eq!(udev.resolve_names(), ResolveNames::Late);
+
+        // invalid values leave the previous settings
+        let mut udev = udev;
+        udev.parse_config("children_max=-1\nresolve_names=sometimes\nudev_log=loud\n");
+        assert_eq!(udev.children_max(), 8);
+        assert_eq!(udev.resolve_names(), ResolveNames::Late);
+        assert_eq!(udev.log_priority(), LogPriority::Debug);
+
+        udev.apply_log_env(Some("err"));
+        assert_eq!(udev.log_priority(), LogPriority::Error);
+        udev.apply_log_env(Some("loud"));
+        udev.apply_log_env(None);
+        assert_eq!(udev.log_priority(), LogPriority::Error);
+
+        assert!(Udev::new().load_config("/nonexistent/udev.conf").is_err());
+
+        Ok(())
+    }
+}
@@ -1,11 +1,52 @@
-use std::io::{self, BufRead, Read};
+use std::io::{self, Write};
 use std::os::linux::fs::MetadataExt;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::{cmp, env, ffi, fmt, fs, mem, time};
 
-use super::{Error, Mode, Result, Udev, UdevEntry, UdevEntryList, UdevList};
+use super::{
+    Error, ErrorContext, ErrorScope, Mode, Result, SysPath, Udev, UdevEntry, UdevEntryList,
+    UdevList,
+};
 use crate::util;
 
+mod action;
+#[cfg(feature = "block")]
+mod block;
+mod builder;
+mod children;
+mod export;
+mod extensions;
+mod id;
+mod info;
+mod lazy;
+mod load;
+#[cfg(feature = "net")]
+mod net;
+mod parent;
+mod seat;
+mod snapshot;
+mod sysattrs;
+
+pub use action::*;
+#[cfg(feature = "block")]
+pub use block::*;
+pub use builder::*;
+pub use children::*;
+pub use export::*;
+pub use extensions::*;
+pub use id::*;
+pub use info::*;
+pub use load::*;
+#[cfg(feature = "net")]
+pub use net::*;
+pub use parent::*;
+pub use seat::*;
+
+pub(crate) use lazy::is_valid_sysattr;
+use lazy::{LazyFields, UeventInfo};
+
 /// Maximum number of ENVP entries
 pub const ENVP_LEN: usize = 128;
 
@@ -16,7 +57,7 @@ pub const UEVENT_FILE_LIMIT: usize = 0x1000;
 
 /// Represents one kernel `sys` device.
 #[repr(C)]
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub struct UdevDevice {
     udev: Arc<Udev>,
     parent_device: Option<Arc<Self>>,
@@ -41,6 +82,7 @@ pub struct UdevDevice {
     sysattr_value_list: UdevList,
     sysattr_list: UdevList,
     tags_list: UdevList,
+    current_tags_list: UdevList,
     seqnum: u64,
     usec_initialized: u64,
     devlink_priority: i32,
@@ -58,6 +100,9 @@ pub struct UdevDevice {
     is_initialized: bool,
     sysattr_list_read: bool,
     db_persist: bool,
+    db_version: u32,
+    extensions: UdevExtensions,
+    lazy: LazyFields,
 }
 
 impl UdevDevice {
@@ -68,6 +113,7 @@ impl UdevDevice {
         let sysattr_value_list = UdevList::new(Arc::clone(&udev));
         let sysattr_list = UdevList::new(Arc::clone(&udev));
         let tags_list = UdevList::new(Arc::clone(&udev));
+        let current_tags_list = UdevList::new(Arc::clone(&udev));
 
         Self {
             udev,
@@ -93,6 +139,7 @@ impl UdevDevice {
             sysattr_value_list,
             sysattr_list,
             tags_list,
+            current_tags_list,
             seqnum: 0,
             usec_initialized: 0,
             devlink_priority: 0,
@@ -110,6 +157,9 @@ impl UdevDevice {
             is_initialized: false,
             sysattr_list_read: false,
             db_persist: false,
+            db_version: 0,
+            extensions: UdevExtensions::new(),
+            lazy: LazyFields::default(),
         }
     }
 
@@ -134,7 +184,7 @@ impl UdevDevice {
                 break;
             }
 
-            device.add_property_from_string(std::str::from_utf8(key).unwrap_or(""));
+            device.add_property_from_bytes_parse(key)?;
         }
 
         device.add_property_from_string_parse_finish()?;
@@ -147,32 +197,28 @@ impl UdevDevice {
     ///
     /// The `syspath` is the absolute path to the device, including the sys mount point.
     ///
-    /// The initial refcount is 1, and needs to be decremented to release the resources of the udev device.
+    /// Symlinks are resolved, so devices created from `/sys/class`, `/sys/bus`, or `/sys/dev`
+    /// paths have the canonical syspath, and compare equal to the device created from it.
+    ///
+    /// Devices under `/sys/devices` must have a `uevent` file.
     ///
     /// Returns: a new [UdevDevice], or `Error`, if it does not exist
-    pub fn new_from_syspath(udev: Arc<Udev>, syspath: &str) -> Result<Self> {
-        if syspath.is_empty() {
-            Err(Error::UdevDevice("empty syspath".into()))
-        } else if !syspath.starts_with("/sys") {
-            Err(Error::UdevDevice(format!("not in sys: {syspath}")))
-        } else if let Some(path) = syspath.strip_prefix("/sys/") {
-            let fullpath = if path.starts_with("/devices/") {
-                let uevent_path = format!("{syspath}/uevent");
-                let _ = fs::File::open(uevent_path.as_str()).map_err(|err| {
-                    Error::UdevDevice(format!("unable to open syspath uevent file: {err}"))
-                })?;
-                uevent_path
-            } else {
-                syspath.into()
-            };
-
-            let dev = Self::new(udev).with_syspath(fullpath);
-            log::trace!("device {dev} has devpath: {}", dev.devpath());
+    pub fn new_from_syspath(udev: Arc<Udev>, syspath: &SysPath) -> Result<Self> {
+        let syspath = &syspath.canonicalize()?;
 
-            Ok(dev)
-        } else {
-            Err(Error::UdevDevice("empty syspath subdir".into()))
+        if syspath.subdir().starts_with("devices/") {
+            let uevent_path = format!("{syspath}/uevent");
+            fs::metadata(uevent_path.as_str()).map_err(|err| {
+                Error::UdevDevice(format!("unable to open syspath uevent file: {err}"))
+            })?;
         }
+
+        let dev = Self::new(udev)
+            .with_syspath(syspath.as_str())
+            .with_devpath(syspath.devpath());
+        log::trace!("device {dev} has devpath: {}", dev.devpath());
+
+        Ok(dev)
     }
 
     /// Creates new [UdevDevice].
@@ -201,11 +247,15 @@ impl UdevDevice {
         }?;
 
         // use /sys/dev/{block,char}/<maj>:<min> link
-        let path = format!(
-            "/sys/dev/{type_str}/{}:{}",
-            util::major(devnum),
-            util::minor(devnum)
-        );
+        let sys = udev.get_sys_path();
+        let path = SysPath::new_in(
+            sys,
+            format!(
+                "{sys}/dev/{type_str}/{}:{}",
+                util::major(devnum),
+                util::minor(devnum)
+            ),
+        )?;
         Self::new_from_syspath(udev, &path)
     }
 
@@ -231,10 +281,11 @@ impl UdevDevice {
         subsystem: &str,
         sysname: &str,
     ) -> Result<Self> {
+        let sys = udev.get_sys_path();
         let path = if subsystem == "subsystem" {
-            let sub_path = format!("/sys/subsystem/{sysname}");
-            let bus_path = format!("/sys/bus/{sysname}");
-            let class_path = format!("/sys/class/{sysname}");
+            let sub_path = format!("{sys}/subsystem/{sysname}");
+            let bus_path = format!("{sys}/bus/{sysname}");
+            let class_path = format!("{sys}/class/{sysname}");
 
             if fs::metadata(sub_path.as_str()).is_ok() {
                 Ok(sub_path)
@@ -248,7 +299,7 @@ impl UdevDevice {
                 )))
             }
         } else if subsystem == "module" {
-            let path = format!("/sys/module/{sysname}");
+            let path = format!("{sys}/module/{sysname}");
             if fs::metadata(path.as_str()).is_ok() {
                 Ok(path)
             } else {
@@ -258,8 +309,8 @@ impl UdevDevice {
             }
         } else if subsystem == "drivers" {
             if let Some(driver) = sysname.split(':').nth(2) {
-                let sub_path = format!("/sys/subsystem/{sysname}/drivers/{driver}");
-                let bus_path = format!("/sys/bus/{sysname}/drivers/{driver}");
+                let sub_path = format!("{sys}/subsystem/{sysname}/drivers/{driver}");
+                let bus_path = format!("{sys}/bus/{sysname}/drivers/{driver}");
 
                 if fs::metadata(sub_path.as_str()).is_ok() {
                     Ok(sub_path)
@@ -276,9 +327,9 @@ impl UdevDevice {
                 )))
             }
         } else {
-            let sub_path = format!("/sys/subsystem/{subsystem}/devices/{sysname}");
-            let bus_path = format!("/sys/bus/{subsystem}/devices/{sysname}");
-            let class_path = format!("/sys/class/{subsystem}/{sysname}");
+            let sub_path = format!("{sys}/subsystem/{subsystem}/devices/{sysname}");
+            let bus_path = format!("{sys}/bus/{subsystem}/devices/{sysname}");
+            let class_path = format!("{sys}/class/{subsystem}/{sysname}");
 
             if fs::metadata(sub_path.as_str()).is_ok() {
                 Ok(sub_path)
@@ -290,6 +341,7 @@ impl UdevDevice {
                 Err(Error::UdevDevice(format!("no device found for: {sysname}")))
             }
         }?;
+        let path = SysPath::new_in(sys, path)?;
 
         Self::new_from_syspath(udev, &path)
     }
@@ -343,9 +395,11 @@ impl UdevDevice {
                 let sock = unsafe { libc::socket(libc::PF_INET, libc::SOCK_DGRAM, 0) };
                 if sock < 0 {
                     let errno = io::Error::last_os_error();
-                    return Err(Error::UdevDevice(format!(
-                        "unable to create a socket: {errno}"
-                    )));
+                    return Err(Error::os(
+                        ErrorScope::Device,
+                        format!("unable to create a socket: {errno}"),
+                        errno,
+                    ));
                 }
 
                 // SAFETY: zeroed memory initializes `libc::ifreq` to a valid state.
@@ -356,9 +410,11 @@ impl UdevDevice {
                     let errno = io::Error::last_os_error();
                     // SAFETY: `sock` is a valid socket file descriptor returned by the kernel
                     unsafe { libc::close(sock) };
-                    return Err(Error::UdevDevice(format!(
-                        "invalid interface name: {errno}"
-                    )));
+                    return Err(Error::os(
+                        ErrorScope::Device,
+                        format!("invalid interface name: {errno}"),
+                        errno,
+                    ));
                 }
                 // SAFETY: `sock` is a valid socket file descriptor returned by the kernel
                 unsafe { libc::close(sock) };
@@ -370,7 +426,7 @@ impl UdevDevice {
                     Error::UdevDevice(format!("unable to convert ifr_name to Rust string: {err}"))
                 })?;
 
-                let mut dev = Self::new_from_subsystem_sysname(udev, "net", ifr_name)?;
+                let dev = Self::new_from_subsystem_sysname(udev, "net", ifr_name)?;
                 if dev.get_ifindex() == ifindex {
                     Ok(dev)
                 } else {
@@ -426,31 +482,7 @@ impl UdevDevice {
     ///
     /// Returns an `Err` if no parent is found.
     pub fn new_from_parent(&self) -> Result<Self> {
-        let path = self.syspath();
-        let subdir = path
-            .strip_prefix("/sys/")
-            .ok_or(Error::UdevDevice(format!("invalid syspath: {path}")))?;
-
-        let mut pos = subdir.len();
-        let syslen = "/sys/".len();
-        loop {
-            // do a reverse search for the next parent directory on the syspath
-            pos = subdir[..pos]
-                .rfind('/')
-                .ok_or(Error::UdevDevice("no syspath subdirectory".into()))?;
-            if pos < 2 {
-                break;
-            }
-            if let Ok(dev) =
-                Self::new_from_syspath(self.udev_cloned(), &path[..syslen.saturating_add(pos)])
-            {
-                return Ok(dev);
-            }
-        }
-
-        Err(Error::UdevDevice(format!(
-            "no parent device found for syspath: {path}"
-        )))
+        self.parent()
     }
 
     /// Gets the next parent [UdevDevice].
@@ -519,7 +551,7 @@ impl UdevDevice {
         let mut ret = Err(Error::UdevDevice("no matching parent device found".into()));
         let mut parent_res = self.new_from_parent();
 
-        while let Ok(mut parent) = parent_res {
+        while let Ok(parent) = parent_res {
             let parent_subsystem = parent.get_subsystem().to_owned();
             let parent_devtype = parent.get_devtype().to_owned();
 
@@ -554,9 +586,41 @@ impl UdevDevice {
         self.syspath.as_str()
     }
 
+    /// Gets the canonical [SysPath] of the [UdevDevice], with symlinks resolved.
+    ///
+    /// Devices created with [new_from_syspath](Self::new_from_syspath) already have the
+    /// canonical syspath, devices with a syspath set manually may not.
+    ///
+    /// Returns: `Ok(SysPath)` on success, `Err(Error)` if the syspath is invalid, or does not
+    /// exist.
+    pub fn canonical_syspath(&self) -> Result<SysPath> {
+        SysPath::new_in(self.udev.get_sys_path(), self.syspath())?.canonicalize()
+    }
+
     /// Sets the [UdevDevice] syspath.
+    ///
+    /// Like `libudev`, also sets the derived fields:
+    ///
+    /// - the devpath, if the syspath is under the `sys` path of the [Udev] context
+    /// - the sysname, the last path component, with `!` translated to `/`, e.g. `cciss/c0d0`
+    /// - the sysnum, the trailing number of the sysname, e.g. `1` for `sda1`, if any
     pub fn set_syspath<P: Into<String>>(&mut self, syspath: P) {
         self.syspath = syspath.into();
+
+        if let Some(devpath) = self
+            .syspath
+            .strip_prefix(self.udev.get_sys_path())
+            .filter(|p| p.starts_with('/'))
+        {
+            self.devpath = devpath.into();
+        }
+
+        let (sysname, sysnum) = split_sysname(self.syspath.as_str());
+        self.sysname = sysname;
+        self.sysnum = sysnum;
+
+        // cached values belong to the old syspath
+        self.lazy = LazyFields::default();
     }
 
     /// Builder function that sets the [UdevDevice] syspath.
@@ -573,6 +637,7 @@ impl UdevDevice {
     /// Sets the [UdevDevice] devpath.
     pub fn set_devpath<P: Into<String>>(&mut self, devpath: P) {
         self.devpath = devpath.into();
+        self.lazy.invalidate_id_filename();
     }
 
     /// Builder function that sets the [UdevDevice] devpath.
@@ -613,6 +678,13 @@ impl UdevDevice {
         self
     }
 
+    /// Gets the instance number of the [UdevDevice], e.g. `0` for `sda0`.
+    ///
+    /// Returns: the sysnum, or `None` if the sysname does not end with a number.
+    pub fn get_sysnum(&self) -> Option<&str> {
+        non_empty(self.sysnum.as_str())
+    }
+
     /// Gets the [UdevDevice] devnode.
     pub fn devnode(&self) -> &str {
         self.devnode.as_str()
@@ -638,12 +710,13 @@ impl UdevDevice {
     /// The path is an absolute path, and starts with the device directory.
     /// ```
     ///
-    /// Returns: the device node file name of the [UdevDevice], or an empty string if none exists.
-    pub fn get_devnode(&mut self) -> &str {
-        if self.devnode.is_empty() && !self.info_loaded {
-            self.read_uevent_file().ok();
+    /// Returns: the device node file name of the [UdevDevice], or `None` if none exists.
+    pub fn get_devnode(&self) -> Option<&str> {
+        if self.devnode.is_empty() && !self.info_loaded && !self.uevent_loaded {
+            non_empty(self.uevent_info().devnode.as_str())
+        } else {
+            non_empty(self.devnode.as_str())
         }
-        self.devnode.as_str()
     }
 
     /// Gets the [UdevDevice] devnode [Mode].
@@ -702,6 +775,7 @@ impl UdevDevice {
     /// Sets the [UdevDevice] subsystem.
     pub fn set_subsystem<P: Into<String>>(&mut self, subsystem: P) {
         self.subsystem = subsystem.into();
+        self.lazy.invalidate_id_filename();
     }
 
     /// Builder function that sets the [UdevDevice] subsystem.
@@ -718,23 +792,27 @@ impl UdevDevice {
     ///
     /// - name of the subsystem of the `udev` device.
     /// - empty string if the subsystem can not be determined.
-    pub fn get_subsystem(&mut self) -> &str {
-        if self.subsystem.is_empty() {
+    pub fn get_subsystem(&self) -> &str {
+        if !self.subsystem.is_empty() {
+            return self.subsystem();
+        }
+
+        self.lazy.subsystem.get_or_init(|| {
             if let Ok(subsystem) = Udev::get_sys_core_link_value("subsystem", self.syspath()) {
-                self.set_subsystem(subsystem);
+                subsystem
             } else if self.devpath.starts_with("/module/")
                 || self.devpath.rfind("/drivers/").is_some()
             {
-                self.set_subsystem("module");
+                "module".into()
             } else if self.devpath.starts_with("/subsystem/")
                 || self.devpath.starts_with("/class/")
                 || self.devpath.starts_with("/bus/")
             {
-                self.set_subsystem("subsystem");
+                "subsystem".into()
+            } else {
+                String::new()
             }
-        }
-
-        self.subsystem()
+        })
     }
 
     /// Gets the [UdevDevice] devtype.
@@ -756,11 +834,12 @@ impl UdevDevice {
     /// Gets the devtypes string of the [UdevDevice].
     ///
     /// If the devtype is unset, attempts to read properties from the syspath file.
-    pub fn get_devtype(&mut self) -> &str {
-        if self.devtype.is_empty() {
-            self.read_uevent_file().ok();
+    pub fn get_devtype(&self) -> &str {
+        if self.devtype.is_empty() && !self.uevent_loaded {
+            self.uevent_info().devtype.as_str()
+        } else {
+            self.devtype.as_str()
         }
-        self.devtype.as_str()
     }
 
     /// Gets the [UdevDevice] driver.
@@ -782,11 +861,15 @@ impl UdevDevice {
     /// Gets the kernel driver name.
     ///
     /// Returns: the kernel driver name, or `None`  if none is attached.
-    pub fn get_driver(&mut self) -> Option<&str> {
+    pub fn get_driver(&self) -> Option<&str> {
         if self.driver.is_empty() {
-            self.driver = Udev::get_sys_core_link_value("driver", self.syspath.as_str()).ok()?;
+            self.lazy
+                .driver
+                .get_or_init(|| Udev::get_sys_core_link_value("driver", self.syspath()).ok())
+                .as_deref()
+        } else {
+            Some(self.driver.as_str())
         }
-        Some(self.driver.as_str())
     }
 
     /// Gets the [UdevDevice] action.
@@ -805,6 +888,21 @@ impl UdevDevice {
         self
     }
 
+    /// Gets the kernel action of the [UdevDevice].
+    ///
+    /// From the `libudev` documentation:
+    ///
+    /// ```no_build,no_run
+    /// This is only valid if the device was received through a monitor. Devices read from
+    /// sys do not have an action string. Usual actions are: add, remove, change, online,
+    /// offline.
+    /// ```
+    ///
+    /// Returns: the kernel action, or `None` if the device has no action.
+    pub fn get_action(&self) -> Option<&str> {
+        non_empty(self.action.as_str())
+    }
+
     /// Gets the [UdevDevice] devpath_old.
     pub fn devpath_old(&self) -> &str {
         self.devpath_old.as_str()
@@ -839,11 +937,16 @@ impl UdevDevice {
 
     /// Gets the ID filename.
     ///
-    /// If the filename is empty, attempts to set it based on subsystem information.
+    /// If the filename is empty, attempts to set it based on subsystem information. The derived
+    /// filename is cached until the devpath, subsystem, devnum, or ifindex changes.
     ///
     /// Returns: the ID filename, or an empty string if none can be constructed.
-    pub fn get_id_filename(&mut self) -> &str {
-        let id_filename = if self.id_filename.is_empty() {
+    pub fn get_id_filename(&self) -> &str {
+        if !self.id_filename.is_empty() {
+            return self.id_filename();
+        }
+
+        self.lazy.id_filename.get_or_init(|| {
             if self.get_subsystem().is_empty() {
                 String::new()
             } else if util::major(self.get_devnum()) > 0 {
@@ -855,7 +958,7 @@ impl UdevDevice {
                 } else {
                     'c'
                 };
-                let devnum = self.devnum();
+                let devnum = self.get_devnum();
                 let major = util::major(devnum);
                 let minor = util::minor(devnum);
                 format!("{devtype}{major}:{minor}")
@@ -863,8 +966,8 @@ impl UdevDevice {
                 // From `libudev` documentation:
                 //
                 // use netdev ifindex: <type><ifindex>, e.g. n3
-                format!("n{}", self.ifindex)
-            } else if let Some(sysname) = self.devpath.clone().rsplit('/').next() {
+                format!("n{}", self.get_ifindex())
+            } else if let Some(sysname) = self.devpath.rsplit('/').next() {
                 // From `libudev` documentation:
                 //
                 // use $subsys:$sysname, e.g. pci:0000:00:1f.2
@@ -873,16 +976,7 @@ impl UdevDevice {
             } else {
                 String::new()
             }
-        } else {
-            String::new()
-        };
-
-        if id_filename.is_empty() {
-            ""
-        } else {
-            self.id_filename = id_filename;
-            self.id_filename()
-        }
+        })
     }
 
     /// Gets a reference to the list of `envp` arguments.
@@ -949,24 +1043,53 @@ impl UdevDevice {
     }
 
     /// Updates the `envp` and monitor buffer from the properties list.
+    ///
+    /// Like `libudev`, the devlinks, tags, and current tags are sent as the `DEVLINKS`, `TAGS`,
+    /// and `CURRENT_TAGS` properties. The values are built from the current lists, replacing any
+    /// properties with the same names, and are not added to the properties list.
     pub fn update_envp_monitor_buf(&mut self) {
-        self.monitor_buf.clear();
-        self.clear_envp();
+        let join = |list: &UdevList, sep: &str| {
+            list.iter()
+                .map(|e| e.name())
+                .collect::<Vec<&str>>()
+                .join(sep)
+        };
+        let wrap_tags = |tags: String| {
+            if tags.is_empty() {
+                tags
+            } else {
+                format!(":{tags}:")
+            }
+        };
+        let exported = [
+            ("DEVLINKS", join(&self.devlinks_list, " ")),
+            ("TAGS", wrap_tags(join(&self.tags_list, ":"))),
+            (
+                "CURRENT_TAGS",
+                wrap_tags(join(&self.current_tags_list, ":")),
+            ),
+        ];
 
         // add at most `ENVP_LEN` properties, skipping private entries
-        for (i, list_entry) in self
+        let envp = self
             .properties_list
             .iter()
             .filter(|e| !e.name().starts_with('.'))
-            .enumerate()
+            .filter(|e| !exported.iter().any(|(key, _)| *key == e.name()))
+            .map(|e| format!("{}={}", e.name(), e.value()))
+            .chain(
+                exported
+                    .iter()
+                    .filter(|(_, value)| !value.is_empty())
+                    .map(|(key, value)| format!("{key}={value}")),
+            )
             .take(ENVP_LEN)
-        {
-            let key = list_entry.name();
-            let value = list_entry.value();
+            .collect::<Vec<String>>();
 
-            // add string to envp
-            let envp_str = format!("{key}={value}");
+        self.monitor_buf.clear();
+        self.clear_envp();
 
+        for (i, envp_str) in envp.into_iter().enumerate() {
             self.monitor_buf += envp_str.as_str();
             self.monitor_buf += "\0";
 
@@ -983,6 +1106,7 @@ impl UdevDevice {
 
     /// Gets a mutable reference to the [UdevDevice] `devlinks_list` [UdevList].
     pub fn devlinks_list_mut(&mut self) -> &mut UdevList {
+        self.set_envp_uptodate(false);
         &mut self.devlinks_list
     }
 
@@ -1015,6 +1139,7 @@ impl UdevDevice {
 
     /// Sets the [UdevDevice] `devlinks_list` [UdevList].
     pub fn set_devlinks_list<U: Into<UdevEntryList>>(&mut self, devlinks_list: U) {
+        self.set_envp_uptodate(false);
         self.devlinks_list.set_list(devlinks_list);
     }
 
@@ -1027,6 +1152,7 @@ impl UdevDevice {
     /// Adds an [UdevEntry] to the devlinks list.
     pub fn add_devlink(&mut self, devlink: &str) {
         self.set_devlinks_uptodate(false);
+        self.set_envp_uptodate(false);
         self.devlinks_list.add_entry(devlink, "");
     }
 
@@ -1056,6 +1182,16 @@ impl UdevDevice {
         self.properties_list.entry_by_name(key).map(|e| e.value())
     }
 
+    /// Gets the raw bytes of the value of a given property.
+    ///
+    /// Differs from [get_property_value](Self::get_property_value) only for values with
+    /// non-UTF-8 bytes, which are replaced in the string value.
+    pub fn get_property_value_bytes(&self, key: &str) -> Option<&[u8]> {
+        self.properties_list
+            .entry_by_name(key)
+            .map(|e| e.value_bytes())
+    }
+
     /// Gets a reference to the [UdevDevice] `sysattr_value_list` [UdevList].
     pub const fn sysattr_value_list(&self) -> &UdevList {
         &self.sysattr_value_list
@@ -1134,52 +1270,144 @@ impl UdevDevice {
     /// value and not open the attribute again.
     /// ```
     ///
+    /// Use [refresh_sysattr](Self::refresh_sysattr), or
+    /// [get_sysattr_value_uncached](Self::get_sysattr_value_uncached), for values changing at
+    /// runtime.
+    ///
     /// Returns: the content of a sys attribute file, or `None` if there is no sys attribute value.
-    pub fn get_sysattr_value(&mut self, sysattr: &str) -> Option<String> {
-        match self.sysattr_value_list.entry_by_name(sysattr).cloned() {
+    pub fn get_sysattr_value(&self, sysattr: &str) -> Option<String> {
+        match self.sysattr_value_list.entry_by_name(sysattr) {
             Some(entry) => Some(entry.value().to_owned()),
-            None => {
-                let path = format!("{}/{sysattr}", self.syspath);
-                match fs::metadata(path.as_str()) {
-                    Ok(metadata) => {
-                        if metadata.is_symlink() {
-                            if sysattr == "driver" || sysattr == "subsystem" || sysattr == "module"
-                            {
-                                let value =
-                                    Udev::get_sys_core_link_value(sysattr, self.syspath.as_str())
-                                        .ok()?;
-                                Some(
-                                    self.sysattr_value_list
-                                        .add_entry(sysattr, value.as_str())?
-                                        .value()
-                                        .to_owned(),
-                                )
-                            } else {
-                                None
-                            }
-                        } else if metadata.is_dir()
-                            || metadata.len() == 0
-                            || metadata.st_mode() & libc::S_IRUSR == 0
-                        {
-                            None
-                        } else {
-                            let mut file = fs::File::open(path.as_str()).ok()?;
-                            let mut value = [0u8; 4096];
-                            let read = file.read(&mut value).ok()?;
-                            let value_str =
-                                std::str::from_utf8(value[..read].as_ref()).unwrap_or("");
-                            let entry = self.sysattr_value_list.add_entry(sysattr, value_str)?;
-
-                            Some(entry.value().to_owned())
-                        }
-                    }
-                    Err(_) => {
-                        self.sysattr_value_list.add_entry(sysattr, "");
-                        None
-                    }
-                }
-            }
+            None => self.lazy.sysattr(self.syspath(), sysattr),
+        }
+    }
+
+    /// Writes the sys attribute file value.
+    ///
+    /// The `value` is written as-is, in a single write, e.g. `1` to an LED `brightness`, or a
+    /// device name to a driver `unbind` attribute. The cached value is invalidated, so the next
+    /// [get_sysattr_value](Self::get_sysattr_value) reads the value stored by the kernel.
+    ///
+    /// Returns: `Ok(())` on success, `Err(Error)` if the attribute is not a writable regular file,
+    /// or the kernel rejects the value.
+    pub fn set_sysattr_value(&mut self, sysattr: &str, value: &str) -> Result<()> {
+        if self.syspath.is_empty() {
+            return Err(Error::UdevDevice("device has no syspath".into()));
+        }
+
+        if !lazy::is_valid_sysattr(sysattr) {
+            return Err(Error::UdevDevice(format!("invalid sysattr: {sysattr}")));
+        }
+
+        let path = format!("{}/{sysattr}", self.syspath);
+        let metadata = fs::symlink_metadata(path.as_str()).map_err(|err| {
+            Error::os(
+                ErrorScope::Device,
+                format!("unable to access sysattr: {err}"),
+                err,
+            )
+            .with_context(
+                self.error_context("set_sysattr_value")
+                    .with_path(path.as_str()),
+            )
+        })?;
+
+        if !metadata.is_file() {
+            return Err(
+                Error::UdevDevice("sysattr is not a regular file".into()).with_context(
+                    self.error_context("set_sysattr_value")
+                        .with_path(path.as_str()),
+                ),
+            );
+        }
+
+        if metadata.st_mode() & libc::S_IWUSR == 0 {
+            return Err(
+                Error::UdevDevice("sysattr is not writable".into()).with_context(
+                    self.error_context("set_sysattr_value")
+                        .with_path(path.as_str()),
+                ),
+            );
+        }
+
+        // invalidate the cached value, also on failure, since the kernel may have applied a
+        // partial write
+        self.sysattr_value_list.remove_entry(sysattr);
+        self.lazy.invalidate_sysattr(sysattr);
+
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .custom_flags(libc::O_CLOEXEC | libc::O_NOFOLLOW)
+            .open(path.as_str())
+            .map_err(|err| {
+                Error::os(
+                    ErrorScope::Device,
+                    format!("unable to open sysattr: {err}"),
+                    err,
+                )
+                .with_context(
+                    self.error_context("set_sysattr_value")
+                        .with_path(path.as_str()),
+                )
+            })?;
+
+        let written = file.write(value.as_bytes()).map_err(|err| {
+            Error::os(
+                ErrorScope::Device,
+                format!("unable to write sysattr: {err}"),
+                err,
+            )
+            .with_context(
+                self.error_context("set_sysattr_value")
+                    .with_path(path.as_str()),
+            )
+        })?;
+
+        if written == value.len() {
+            log::trace!("set sysattr {path}: {value}");
+            Ok(())
+        } else {
+            Err(Error::UdevDevice(format!(
+                "short write to sysattr {path}, wrote: {written}, expected: {}",
+                value.len()
+            )))
+        }
+    }
+
+    /// Triggers a synthetic kernel event for the [UdevDevice], like `udevadm trigger`.
+    ///
+    /// Writes the `action` to the device `uevent` file, and the kernel emits an event with the
+    /// action for the device.
+    ///
+    /// Returns: `Ok(())` on success, `Err(Error)` if the action is invalid, or the `uevent` file
+    /// cannot be written.
+    pub fn trigger(&mut self, action: &str) -> Result<()> {
+        validate_trigger_action(action)?;
+        self.set_sysattr_value("uevent", action)
+    }
+
+    /// Triggers a synthetic kernel event for the [UdevDevice], tagged with a `uuid`.
+    ///
+    /// The kernel adds the `uuid` to the event as the `SYNTH_UUID` property, so the caller can
+    /// match the events it triggered, e.g. with a [UdevMonitor](crate::UdevMonitor).
+    ///
+    /// Returns: `Ok(())` on success, `Err(Error)` if the action or UUID is invalid, or the
+    /// `uevent` file cannot be written.
+    pub fn trigger_with_uuid(&mut self, action: &str, uuid: &str) -> Result<()> {
+        validate_trigger_action(action)?;
+
+        let is_uuid = uuid.len() == 36
+            && uuid.char_indices().all(|(i, c)| match i {
+                8 | 13 | 18 | 23 => c == '-',
+                _ => c.is_ascii_hexdigit(),
+            });
+
+        if !is_uuid {
+            return Err(Error::UdevDevice(format!("invalid trigger UUID: {uuid}")));
         }
+
+        self.set_sysattr_value("uevent", format!("{action} {uuid}").as_str())
     }
 
     /// Gets a reference to the [UdevDevice] `tags_list` [UdevList].
@@ -1189,11 +1417,13 @@ impl UdevDevice {
 
     /// Gets a mutable reference to the [UdevDevice] `tags_list` [UdevList].
     pub fn tags_list_mut(&mut self) -> &mut UdevList {
+        self.set_envp_uptodate(false);
         &mut self.tags_list
     }
 
     /// Sets the [UdevDevice] `tags_list` [UdevList].
     pub fn set_tags_list<U: Into<UdevEntryList>>(&mut self, tags_list: U) {
+        self.set_envp_uptodate(false);
         self.tags_list.set_list(tags_list);
     }
 
@@ -1227,7 +1457,33 @@ impl UdevDevice {
         self.tags_list_entry()
     }
 
-    /// Gets the current tags list entry in the [UdevDevice].
+    /// Gets a reference to the [UdevDevice] `current_tags_list` [UdevList].
+    ///
+    /// Current tags are the tags set on the device by the last event, see
+    /// [get_current_tags_list_entry](Self::get_current_tags_list_entry).
+    pub const fn current_tags_list(&self) -> &UdevList {
+        &self.current_tags_list
+    }
+
+    /// Gets a mutable reference to the [UdevDevice] `current_tags_list` [UdevList].
+    pub fn current_tags_list_mut(&mut self) -> &mut UdevList {
+        self.set_envp_uptodate(false);
+        &mut self.current_tags_list
+    }
+
+    /// Sets the [UdevDevice] `current_tags_list` [UdevList].
+    pub fn set_current_tags_list<U: Into<UdevEntryList>>(&mut self, current_tags_list: U) {
+        self.set_envp_uptodate(false);
+        self.current_tags_list.set_list(current_tags_list);
+    }
+
+    /// Builder function sets the [UdevDevice] `current_tags_list` [UdevList].
+    pub fn with_current_tags_list<U: Into<UdevEntryList>>(mut self, current_tags_list: U) -> Self {
+        self.set_current_tags_list(current_tags_list);
+        self
+    }
+
+    /// Gets the first current tags list entry in the [UdevDevice].
     ///
     /// From the `libudev` documentation:
     ///
@@ -1238,12 +1494,48 @@ impl UdevDevice {
     /// can be retrieved from the list entry by udev_list_entry_get_name().
     /// ```
     ///
-    /// Returns: the current entry of the tag list
+    /// Tags stay in the [tags list](Self::get_tags_list_entry) after they are removed from the
+    /// device, current tags only hold the tags set by the last event. Databases written before
+    /// current tags were introduced, see [db_version](Self::db_version), have no current tags, so
+    /// all tags are current.
+    ///
+    /// Returns: the first entry of the current tag list
     pub fn get_current_tags_list_entry(&mut self) -> Option<&UdevEntry> {
         if !self.info_loaded {
             self.read_db().ok();
         }
-        self.tags_list.next_entry()
+        if self.has_current_tags() {
+            self.current_tags_list.entry()
+        } else {
+            self.tags_list.entry()
+        }
+    }
+
+    /// Adds an [UdevEntry] to the current tags list, and the tags list.
+    pub fn add_current_tag(&mut self, tag: &str) -> Result<()> {
+        self.add_tag(tag)?;
+        self.current_tags_list.add_entry(tag, "");
+
+        Ok(())
+    }
+
+    /// Gets whether the [UdevDevice] has the provided `tag` currently set.
+    ///
+    /// See [get_current_tags_list_entry](Self::get_current_tags_list_entry).
+    pub fn has_current_tag(&mut self, tag: &str) -> bool {
+        if !self.info_loaded {
+            self.read_db().ok();
+        }
+        if self.has_current_tags() {
+            self.current_tags_list.entry_by_name(tag).is_some()
+        } else {
+            self.tags_list.entry_by_name(tag).is_some()
+        }
+    }
+
+    // Gets whether the device information tells current tags apart from all tags.
+    fn has_current_tags(&self) -> bool {
+        self.db_version >= 1 || !self.current_tags_list.is_empty()
     }
 
     /// Adds an [UdevEntry] to the tags list.
@@ -1251,6 +1543,7 @@ impl UdevDevice {
         Self::is_valid_tag(tag)?;
 
         self.set_tags_uptodate(false);
+        self.set_envp_uptodate(false);
         self.tags_list.add_entry(tag, "");
 
         Ok(())
@@ -1354,6 +1647,20 @@ impl UdevDevice {
         self
     }
 
+    /// Gets the priority of the device claiming its `devlinks`, e.g. the `L:` record of the
+    /// `udev` database, set by `OPTIONS+="link_priority=N"` rules.
+    ///
+    /// Reads the `udev` database, if the device information is not loaded.
+    ///
+    /// Returns: the devlink priority, `0` by default, higher priorities own shared symlinks, see
+    /// [select_highest_priority](crate::node::select_highest_priority).
+    pub fn link_priority(&mut self) -> i32 {
+        if !self.info_loaded {
+            self.read_db().ok();
+        }
+        self.devlink_priority
+    }
+
     /// Gets the [UdevDevice] devnum.
     pub const fn devnum(&self) -> u64 {
         self.devnum
@@ -1362,6 +1669,7 @@ impl UdevDevice {
     /// Sets the [UdevDevice] devnum.
     pub fn set_devnum(&mut self, devnum: u64) {
         self.devnum = devnum;
+        self.lazy.invalidate_id_filename();
     }
 
     /// Builder function sets the [UdevDevice] devnum.
@@ -1371,11 +1679,12 @@ impl UdevDevice {
     }
 
     /// Gets the device major/minor number.
-    pub fn get_devnum(&mut self) -> u64 {
-        if !self.info_loaded {
-            self.read_uevent_file().ok();
+    pub fn get_devnum(&self) -> u64 {
+        if self.devnum == 0 && !self.info_loaded && !self.uevent_loaded {
+            self.uevent_info().devnum
+        } else {
+            self.devnum
         }
-        self.devnum
     }
 
     /// Gets the [UdevDevice] ifindex.
@@ -1386,6 +1695,7 @@ impl UdevDevice {
     /// Sets the [UdevDevice] ifindex.
     pub fn set_ifindex(&mut self, ifindex: i32) {
         self.ifindex = ifindex;
+        self.lazy.invalidate_id_filename();
     }
 
     /// Builder function sets the [UdevDevice] ifindex.
@@ -1397,11 +1707,19 @@ impl UdevDevice {
     /// Gets the [UdevDevice] ifindex.
     ///
     /// If the information file is not loaded, reads and parses the file from disk.
-    pub fn get_ifindex(&mut self) -> i32 {
-        if !self.info_loaded {
-            self.read_uevent_file().ok();
+    pub fn get_ifindex(&self) -> i32 {
+        if self.ifindex == 0 && !self.info_loaded && !self.uevent_loaded {
+            self.uevent_info().ifindex
+        } else {
+            self.ifindex
         }
-        self.ifindex
+    }
+
+    // Gets the `uevent` file information, reading it on the first call.
+    fn uevent_info(&self) -> &UeventInfo {
+        self.lazy
+            .uevent
+            .get_or_init(|| UeventInfo::read(self.syspath()))
     }
 
     /// Gets the [UdevDevice] watch handle.
@@ -1508,6 +1826,7 @@ impl UdevDevice {
     /// Sets the [UdevDevice] info loaded.
     pub fn set_info_loaded(&mut self, info_loaded: bool) {
         self.info_loaded = info_loaded;
+        self.lazy.invalidate_id_filename();
     }
 
     /// Builder function sets the [UdevDevice] info loaded.
@@ -1540,6 +1859,7 @@ impl UdevDevice {
     /// Sets the [UdevDevice] uevent loaded.
     pub fn set_uevent_loaded(&mut self, uevent_loaded: bool) {
         self.uevent_loaded = uevent_loaded;
+        self.lazy.invalidate_id_filename();
     }
 
     /// Builder function sets the [UdevDevice] uevent loaded.
@@ -1632,7 +1952,8 @@ impl UdevDevice {
                     Ok(m) => m,
                     Err(_) => continue,
                 };
-                if metadata.st_mode() & libc::S_IRUSR != 0 {
+                // skip attributes that are not readable
+                if metadata.st_mode() & libc::S_IRUSR == 0 {
                     continue;
                 }
 
@@ -1662,6 +1983,29 @@ impl UdevDevice {
         self
     }
 
+    /// Gets the version of the database record format, read from the `V:` record.
+    ///
+    /// Records written by older `udev` versions have no `V:` record, and version `0`.
+    pub const fn db_version(&self) -> u32 {
+        self.db_version
+    }
+
+    /// Sets the version of the database record format.
+    pub fn set_db_version(&mut self, db_version: u32) {
+        self.db_version = db_version;
+    }
+
+    /// Builder function that sets the version of the database record format.
+    pub fn with_db_version(mut self, db_version: u32) -> Self {
+        self.set_db_version(db_version);
+        self
+    }
+
+    // Creates the [ErrorContext] of a failed operation on the device.
+    fn error_context(&self, operation: &str) -> ErrorContext {
+        ErrorContext::new(operation).with_syspath(self.syspath.as_str())
+    }
+
     /// Reads [UdevDevice] information from the persistent database file.
     ///
     /// Returns: `Ok(())` on success, `Err(Error)` otherwise
@@ -1672,52 +2016,24 @@ impl UdevDevice {
         } else if id.is_empty() {
             Err(Error::UdevDevice("unable to retrieve ID filename".into()))
         } else {
-            let filename = format!("/udev/data/{id}");
+            let filename = self.db_filename();
             let file = fs::File::open(filename.as_str()).map_err(|err| {
-                Error::UdevDevice(format!("unable to open DB file: {filename}, error: {err}"))
+                Error::os(
+                    ErrorScope::Device,
+                    format!("unable to open DB file: {err}"),
+                    err,
+                )
+                .with_context(self.error_context("read_db").with_path(filename.as_str()))
             })?;
 
             // devices with a database entry are initialized
             self.is_initialized = true;
+            self.db_loaded = true;
 
-            let mut line = String::with_capacity(util::LINE_SIZE);
-            let mut reader = io::BufReader::new(file);
-
-            while let Ok(len) = reader.read_line(&mut line) {
-                if len < 4 {
-                    break;
-                }
-                let val = &line[2..];
-                match val.chars().next() {
-                    Some('S') => {
-                        self.add_devlink(format!("/dev/{val}").as_str());
-                        break;
-                    }
-                    Some('L') => {
-                        self.set_devlink_priority(val.parse::<i32>().unwrap_or(0));
-                        break;
-                    }
-                    Some('E') => {
-                        if self.add_property_from_string(val).is_some() {
-                            if let Some(entry) = self.properties_list.list_mut().back_mut() {
-                                entry.set_num(1);
-                            }
-                        }
-                        break;
-                    }
-                    Some('G') => {
-                        self.add_tag(val)?;
-                        break;
-                    }
-                    Some('W') => {
-                        self.set_watch_handle(val.parse::<i32>().unwrap_or(0));
-                        break;
-                    }
-                    Some('I') => {
-                        self.set_usec_initialized(val.parse::<u64>().unwrap_or(0));
-                        break;
-                    }
-                    _ => (),
+            // skip malformed records, like `libudev`, instead of dropping the rest of the file
+            for line in util::read_lines(io::BufReader::new(file)) {
+                if let Err(err) = self.parse_db_record(line.as_str()) {
+                    log::debug!("device {self}: {err}");
                 }
             }
 
@@ -1727,23 +2043,314 @@ impl UdevDevice {
         }
     }
 
+    /// Creates a new [UdevDevice] from the text of a database record.
+    ///
+    /// The record uses the `udev` database format, one `<type>:<value>` record per line:
+    ///
+    /// - `S:` device node symlink, relative to `/dev`
+    /// - `L:` device node symlink priority
+    /// - `W:` inotify watch handle
+    /// - `E:` persistent property, in `KEY=value` format
+    /// - `G:` tag
+    /// - `I:` timestamp of device initialization, in microseconds
+    /// - `Q:` current tag, written by newer `udev` versions
+    /// - `V:` version of the record format, written by newer `udev` versions
+    ///
+    /// `udev` writes the tags currently set on the device as `Q:` records, and all tags ever set
+    /// as `G:` records, see [get_current_tags_list_entry](Self::get_current_tags_list_entry).
+    ///
+    /// Empty lines and unknown record types are ignored.
+    ///
+    /// The inverse of [to_db_string](Self::to_db_string).
+    ///
+    /// Returns: `Ok(UdevDevice)` on success, `Err(Error)` if a record is malformed.
+    pub fn from_db_string(udev: Arc<Udev>, db: &str) -> Result<Self> {
+        let mut device = Self::new(udev);
+
+        for line in db.lines() {
+            device.parse_db_record(line)?;
+        }
+
+        // devices with a database entry are initialized
+        device.set_is_initialized(true);
+        device.set_db_loaded(true);
+
+        Ok(device)
+    }
+
+    fn parse_db_record(&mut self, line: &str) -> Result<()> {
+        if line.is_empty() {
+            return Ok(());
+        }
+
+        let (key, val) = line
+            .split_once(':')
+            .filter(|(key, _)| key.len() == 1)
+            .ok_or(Error::UdevDevice(format!("invalid DB record: {line}")))?;
+
+        let parse_err =
+            |err: &dyn fmt::Display| Error::UdevDevice(format!("invalid DB record: {line}, {err}"));
+
+        match key {
+            "S" => {
+                let dev_path = self.udev.get_dev_path().to_owned();
+                self.add_devlink(format!("{dev_path}/{val}").as_str())
+            }
+            "L" => self.set_devlink_priority(val.parse::<i32>().map_err(|e| parse_err(&e))?),
+            "W" => self.set_watch_handle(val.parse::<i32>().map_err(|e| parse_err(&e))?),
+            "I" => self.set_usec_initialized(val.parse::<u64>().map_err(|e| parse_err(&e))?),
+            "G" => self.add_tag(val)?,
+            "Q" => self.add_current_tag(val)?,
+            "V" => self.set_db_version(val.parse::<u32>().map_err(|e| parse_err(&e))?),
+            "E" => {
+                let (name, value) = val
+                    .split_once('=')
+                    .ok_or(parse_err(&"missing property value"))?;
+                if let Some(entry) = self.add_property_internal(name, value) {
+                    let name = entry.name().to_owned();
+                    if let Some(entry) = self.properties_list.entry_by_name_mut(name.as_str()) {
+                        entry.set_num(1);
+                    }
+                }
+            }
+            _ => log::trace!("ignoring unknown DB record: {line}"),
+        }
+
+        Ok(())
+    }
+
+    /// Converts the [UdevDevice] into the text of a database record.
+    ///
+    /// Only properties marked as persistent (non-zero [`num`](UdevEntry::num)) are written,
+    /// matching the properties read from a database record.
+    ///
+    /// See [from_db_string](Self::from_db_string) for the record format.
+    pub fn to_db_string(&self) -> String {
+        let mut db = String::new();
+
+        let dev_path = self.udev.get_dev_path();
+        for link in self.devlinks_list.iter() {
+            let name = link.name();
+            let rel = name
+                .strip_prefix(dev_path)
+                .and_then(|n| n.strip_prefix('/'))
+                .unwrap_or(name);
+            db.push_str(format!("S:{rel}\n").as_str());
+        }
+
+        if self.devlink_priority != 0 {
+            db.push_str(format!("L:{}\n", self.devlink_priority).as_str());
+        }
+
+        if self.watch_handle > 0 {
+            db.push_str(format!("W:{}\n", self.watch_handle).as_str());
+        }
+
+        for property in self.properties_list.iter().filter(|e| e.num() != 0) {
+            db.push_str(format!("E:{}={}\n", property.name(), property.value()).as_str());
+        }
+
+        for tag in self.tags_list.iter() {
+            db.push_str(format!("G:{}\n", tag.name()).as_str());
+        }
+
+        for tag in self.current_tags_list.iter() {
+            db.push_str(format!("Q:{}\n", tag.name()).as_str());
+        }
+
+        if self.usec_initialized > 0 {
+            db.push_str(format!("I:{}\n", self.usec_initialized).as_str());
+        }
+
+        // records with current tags use the newer format
+        if self.has_current_tags() {
+            db.push_str(format!("V:{}\n", self.db_version.max(1)).as_str());
+        }
+
+        db
+    }
+
+    /// Gets the path of the persistent database file for the [UdevDevice].
+    ///
+    /// The database lives under the [Udev] run path, see [Udev::get_run_path].
+    ///
+    /// Returns: the database file path, or an empty string if no ID filename can be constructed.
+    pub fn db_filename(&mut self) -> String {
+        let id = self.get_id_filename().to_owned();
+        if id.is_empty() {
+            String::new()
+        } else {
+            format!("{}/data/{id}", self.udev.get_run_path())
+        }
+    }
+
+    /// Gets the path of the persistent database file consulted for the [UdevDevice].
+    ///
+    /// Useful for diagnostics, e.g. to log which file was read when properties are missing.
+    ///
+    /// Returns: `Some(PathBuf)` with the database file path, `None` if no ID filename can be
+    /// constructed, e.g. for devices without a subsystem.
+    pub fn db_path(&mut self) -> Option<PathBuf> {
+        Some(self.db_filename())
+            .filter(|f| !f.is_empty())
+            .map(PathBuf::from)
+    }
+
+    /// Gets whether the persistent database file for the [UdevDevice] exists.
+    ///
+    /// Devices without a database entry have not been processed by the `udev` daemon, or the
+    /// daemon did not store any information about them.
+    pub fn has_db_entry(&mut self) -> bool {
+        self.db_path().is_some_and(|path| path.is_file())
+    }
+
+    /// Writes the [UdevDevice] information to the persistent database file.
+    ///
+    /// The record is written to a temporary file, and renamed over the database file, so readers
+    /// never observe a partial record. See [to_db_string](Self::to_db_string) for the format.
+    ///
+    /// From the `libudev` documentation:
+    ///
+    /// ```no_build,no_run
+    /// do not store anything for otherwise empty devices
+    /// ```
+    ///
+    /// Devices without database information, device number, or interface index have their
+    /// database file removed instead.
+    ///
+    /// If [db_persist](Self::db_persist) is set, the file is marked with the sticky bit, so the
+    /// record survives the database cleanup on daemon restart.
+    ///
+    /// Returns: `Ok(())` on success, `Err(Error)` otherwise
+    pub fn update_db(&mut self) -> Result<()> {
+        let filename = self.db_filename();
+        if filename.is_empty() {
+            return Err(Error::UdevDevice("unable to retrieve ID filename".into()));
+        }
+
+        let db = self.to_db_string();
+
+        if db.is_empty() && util::major(self.get_devnum()) == 0 && self.get_ifindex() == 0 {
+            return self.delete_db();
+        }
+
+        let path = std::path::Path::new(filename.as_str());
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| {
+                Error::os(
+                    ErrorScope::Device,
+                    format!("unable to create DB directory: {err}"),
+                    err,
+                )
+                .with_context(self.error_context("update_db").with_path(dir))
+            })?;
+        }
+
+        let tmp_filename = format!("{filename}.tmp");
+        let mode = if self.db_persist { 0o1644 } else { 0o644 };
+
+        let write_tmp = || -> io::Result<()> {
+            let mut file = fs::File::create(tmp_filename.as_str())?;
+            file.set_permissions(fs::Permissions::from_mode(mode))?;
+            file.write_all(db.as_bytes())?;
+            file.sync_data()
+        };
+
+        if let Err(err) = write_tmp().and_then(|_| fs::rename(tmp_filename.as_str(), path)) {
+            fs::remove_file(tmp_filename.as_str()).ok();
+            return Err(Error::os(
+                ErrorScope::Device,
+                format!("unable to write DB file: {err}"),
+                err,
+            )
+            .with_context(self.error_context("update_db").with_path(filename.as_str())));
+        }
+
+        log::debug!("created DB file '{filename}' for '{}'", self.devpath());
+
+        Ok(())
+    }
+
+    /// Removes the persistent database file of the [UdevDevice].
+    ///
+    /// A missing database file is not an error.
+    ///
+    /// Returns: `Ok(())` on success, `Err(Error)` otherwise
+    pub fn delete_db(&mut self) -> Result<()> {
+        let filename = self.db_filename();
+        if filename.is_empty() {
+            return Err(Error::UdevDevice("unable to retrieve ID filename".into()));
+        }
+
+        match fs::remove_file(filename.as_str()) {
+            Ok(_) => {
+                log::debug!("removed DB file '{filename}' for '{}'", self.devpath());
+                Ok(())
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(Error::os(
+                ErrorScope::Device,
+                format!("unable to remove DB file: {err}"),
+                err,
+            )
+            .with_context(self.error_context("delete_db").with_path(filename.as_str()))),
+        }
+    }
+
+    /// Updates the tags reverse index for the [UdevDevice].
+    ///
+    /// Each tag has a directory under `<run_path>/tags/<tag>`, holding one empty file per tagged
+    /// device, named after the [ID filename](Self::get_id_filename). The index allows
+    /// [UdevEnumerate](crate::UdevEnumerate) to find tagged devices without scanning `/sys`.
+    ///
+    /// Tags of `device_old` that are no longer set on the device are removed from the index.
+    /// The current tags of the device are added to the index if `add` is `true`, and removed
+    /// otherwise, e.g. when the device is removed.
+    ///
+    /// Returns: `Ok(())` on success, `Err(Error)` otherwise
+    pub fn tag_index_update(&mut self, device_old: Option<&UdevDevice>, add: bool) -> Result<()> {
+        let id = self.get_id_filename().to_owned();
+        if id.is_empty() {
+            return Err(Error::UdevDevice("unable to retrieve ID filename".into()));
+        }
+
+        let tags_path = format!("{}/tags", self.udev.get_run_path());
+
+        if let Some(old) = device_old {
+            for tag in old
+                .tags_list()
+                .iter()
+                .filter(|t| self.tags_list.entry_by_name(t.name()).is_none())
+            {
+                tag_index_remove(format!("{tags_path}/{}/{id}", tag.name()).as_str())?;
+            }
+        }
+
+        for tag in self.tags_list.iter() {
+            let filename = format!("{tags_path}/{}/{id}", tag.name());
+            if add {
+                tag_index_add(filename.as_str())?;
+            } else {
+                tag_index_remove(filename.as_str())?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Reads properties from the `uevent` file.
     pub fn read_uevent_file(&mut self) -> Result<()> {
-        if !self.uevent_loaded {
+        if self.uevent_loaded {
             Ok(())
         } else {
-            let filename = format!("{}/uevent", self.syspath());
-            let f = fs::OpenOptions::new().read(true).open(filename)?;
-
-            self.uevent_loaded = true;
+            let lines = lazy::read_uevent_lines(self.syspath())?;
 
-            let mut reader = io::BufReader::new(f.take(UEVENT_FILE_LIMIT as u64));
-            let mut line = String::new();
+            self.set_uevent_loaded(true);
 
             let (mut maj, mut min) = (0u32, 0u32);
 
-            while let Ok(read) = reader.read_line(&mut line) {
-                let tline = line.trim_end_matches('\n');
+            for line in lines {
+                let tline = line.as_str();
 
                 if let Some(devtype) = tline.strip_prefix("DEVTYPE=") {
                     self.set_devtype(devtype);
@@ -1767,13 +2374,11 @@ impl UdevDevice {
                 }
 
                 self.add_property_from_string(tline);
-
-                if read == 0 {
-                    break;
-                }
             }
 
-            self.set_devnum(libc::makedev(maj, min));
+            if maj > 0 {
+                self.set_devnum(libc::makedev(maj, min));
+            }
 
             Ok(())
         }
@@ -1781,14 +2386,36 @@ impl UdevDevice {
 
     /// Parses the `property` string, and adds an [UdevEntry] to the properties list.
     pub fn add_property_from_string(&mut self, property: &str) -> Option<&UdevEntry> {
-        let mut pit = property.split('=').take(2);
-
-        let name = pit.next().unwrap_or("");
-        let value = pit.next().unwrap_or("");
+        // values may contain `=`, only split at the first one
+        let (name, value) = property.split_once('=').unwrap_or((property, ""));
 
         self.add_property_internal(name, value)
     }
 
+    /// Adds a property with the `key` and `value` to the properties list.
+    ///
+    /// An empty `value` removes the property.
+    pub fn add_property(&mut self, key: &str, value: &str) -> Option<&UdevEntry> {
+        self.add_property_internal(key, value)
+    }
+
+    /// Adds a property with the `key` and raw `value` bytes to the properties list.
+    ///
+    /// Non-UTF-8 bytes are replaced in the string value, and kept in the raw value, see
+    /// [get_property_value_bytes](Self::get_property_value_bytes).
+    ///
+    /// An empty `value` removes the property.
+    pub fn add_property_bytes(&mut self, key: &str, value: &[u8]) -> Option<&UdevEntry> {
+        if key.is_empty() {
+            None
+        } else if value.is_empty() {
+            self.add_property_internal(key, "")
+        } else {
+            self.set_envp_uptodate(false);
+            self.properties_list_mut().add_entry_bytes(key, value)
+        }
+    }
+
     fn add_property_internal(&mut self, key: &str, value: &str) -> Option<&UdevEntry> {
         if key.is_empty() {
             None
@@ -1815,7 +2442,9 @@ impl UdevDevice {
     /// to use a device without a `DEVPATH` set.
     pub fn add_property_from_string_parse(&mut self, property: &str) -> Result<()> {
         if let Some(path) = property.strip_prefix("DEVPATH=") {
-            self.set_syspath(path);
+            let syspath = format!("{}{path}", self.udev.get_sys_path());
+            self.set_syspath(syspath);
+            self.set_devpath(path);
         } else if let Some(path) = property.strip_prefix("SUBSYSTEM=") {
             self.set_subsystem(path);
         } else if let Some(devtype) = property.strip_prefix("DEVTYPE=") {
@@ -1823,46 +2452,79 @@ impl UdevDevice {
         } else if let Some(devname) = property.strip_prefix("DEVNAME=") {
             self.set_devnode(devname);
         } else if let Some(devlinks) = property.strip_prefix("DEVLINKS=") {
-            for link in devlinks.split(|s| s == ' ') {
+            for link in devlinks.split(' ') {
                 if !link.is_empty() && !link.starts_with('\0') {
                     self.add_devlink(link);
                 }
             }
         } else if let Some(tags) = property.strip_prefix("TAGS=") {
-            for tag in tags.split(|s| s == ':') {
+            for tag in tags.split(':') {
                 if !tag.is_empty() && !tag.starts_with('\0') {
                     self.add_tag(tag)?;
                 }
             }
+        } else if let Some(tags) = property.strip_prefix("CURRENT_TAGS=") {
+            for tag in tags.split(':') {
+                if !tag.is_empty() && !tag.starts_with('\0') {
+                    self.add_current_tag(tag)?;
+                }
+            }
         } else if let Some(usec_init) = property.strip_prefix("USEC_INITIALIZED=") {
-            self.set_usec_initialized(usec_init.parse::<u64>().unwrap_or(0));
+            self.set_usec_initialized(parse_property("USEC_INITIALIZED", usec_init));
         } else if let Some(driver) = property.strip_prefix("DRIVER=") {
             self.set_driver(driver);
         } else if let Some(action) = property.strip_prefix("ACTION=") {
             self.set_action(action);
         } else if let Some(major) = property.strip_prefix("MAJOR=") {
-            self.set_maj(major.parse::<i32>().unwrap_or(0));
+            self.set_maj(parse_property("MAJOR", major));
         } else if let Some(minor) = property.strip_prefix("MINOR=") {
-            self.set_min(minor.parse::<i32>().unwrap_or(0));
+            self.set_min(parse_property("MINOR", minor));
         } else if let Some(devpath_old) = property.strip_prefix("DEVPATH_OLD=") {
             self.set_devpath_old(devpath_old);
         } else if let Some(seqnum) = property.strip_prefix("SEQNUM=") {
-            self.set_seqnum(seqnum.parse::<u64>().unwrap_or(0));
+            self.set_seqnum(parse_property("SEQNUM", seqnum));
         } else if let Some(ifindex) = property.strip_prefix("IFINDEX=") {
-            self.set_ifindex(ifindex.parse::<i32>().unwrap_or(0));
+            self.set_ifindex(parse_property("IFINDEX", ifindex));
         } else if let Some(devmode) = property.strip_prefix("DEVMODE=") {
-            self.set_devnode_mode(devmode.parse::<u32>().unwrap_or(0).into());
+            self.set_devnode_mode(parse_property::<u32>("DEVMODE", devmode).into());
         } else if let Some(devuid) = property.strip_prefix("DEVUID=") {
-            self.set_devnode_uid(devuid.parse::<u32>().unwrap_or(0));
+            self.set_devnode_uid(parse_property("DEVUID", devuid));
         } else if let Some(devgid) = property.strip_prefix("DEVGID=") {
-            self.set_devnode_gid(devgid.parse::<u32>().unwrap_or(0));
-        } else {
-            self.add_property_from_string(property);
+            self.set_devnode_gid(parse_property("DEVGID", devgid));
         }
 
+        // like the `libudev` setters, keep every key in the properties list
+        self.add_property_from_string(property);
+
         Ok(())
     }
 
+    /// Parses a raw `KEY=value` property, and if needed, updates internal values accordingly.
+    ///
+    /// Like [add_property_from_string_parse](Self::add_property_from_string_parse), but keeps
+    /// values with non-UTF-8 bytes, e.g. firmware strings in kernel events, instead of dropping
+    /// them. Internal values use the lossy UTF-8 conversion.
+    pub fn add_property_from_bytes_parse(&mut self, property: &[u8]) -> Result<()> {
+        match std::str::from_utf8(property) {
+            Ok(prop) => self.add_property_from_string_parse(prop),
+            Err(_) => {
+                self.add_property_from_string_parse(&String::from_utf8_lossy(property))?;
+
+                // keep the raw value bytes in the properties list
+                if let Some(pos) = property.iter().position(|&b| b == b'=') {
+                    match std::str::from_utf8(&property[..pos]) {
+                        Ok(key) => {
+                            self.add_property_bytes(key, &property[pos + 1..]);
+                        }
+                        Err(_) => log::debug!("device: property key is not valid UTF-8"),
+                    }
+                }
+
+                Ok(())
+            }
+        }
+    }
+
     /// Finishes adding property information after parsing configuration string.
     ///
     /// **NOTE** users should call this function after the final call to
@@ -1875,6 +2537,18 @@ impl UdevDevice {
         self.set_maj(0);
         self.set_min(0);
 
+        // the kernel only sends the new `INTERFACE` of renamed network interfaces, like
+        // `udevd`, keep the old name for the rules, and consumers of the event
+        if self.action() == "move"
+            && self.subsystem() == "net"
+            && self.get_property_value("INTERFACE_OLD").is_none()
+        {
+            let (interface_old, _) = split_sysname(self.devpath_old());
+            if !interface_old.is_empty() {
+                self.add_property_internal("INTERFACE_OLD", interface_old.as_str());
+            }
+        }
+
         if self.devpath().is_empty() || self.subsystem().is_empty() {
             log::debug!("device: empty devpath and/or subsystem");
         }
@@ -1883,6 +2557,55 @@ impl UdevDevice {
     }
 }
 
+/// Kernel actions accepted by the `uevent` sys attribute.
+pub const TRIGGER_ACTIONS: [&str; 8] = [
+    "add", "remove", "change", "move", "online", "offline", "bind", "unbind",
+];
+
+fn validate_trigger_action(action: &str) -> Result<()> {
+    if !DeviceAction::from(action).is_unknown() {
+        Ok(())
+    } else {
+        Err(Error::UdevDevice(format!(
+            "invalid trigger action: {action}"
+        )))
+    }
+}
+
+// Gets the sysname, and sysnum, from the last component of a syspath, or devpath.
+//
+// Like `libudev`, the sysnum is empty if the sysname has no trailing number, or is all digits.
+fn split_sysname(path: &str) -> (String, String) {
+    let sysname = path
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .replace('!', "/");
+
+    let digits = sysname
+        .bytes()
+        .rev()
+        .take_while(|b| b.is_ascii_digit())
+        .count();
+    let sysnum = if digits < sysname.len() {
+        sysname[sysname.len() - digits..].to_owned()
+    } else {
+        String::new()
+    };
+
+    (sysname, sysnum)
+}
+
+// Maps unset string fields to `None`, matching the `NULL` returns of `libudev`.
+fn non_empty(val: &str) -> Option<&str> {
+    if val.is_empty() {
+        None
+    } else {
+        Some(val)
+    }
+}
+
 impl Default for UdevDevice {
     fn default() -> Self {
         Self::new(Arc::new(Udev::new()))
@@ -1919,10 +2642,55 @@ impl fmt::Display for UdevDevice {
     }
 }
 
+fn tag_index_add(filename: &str) -> Result<()> {
+    let path = std::path::Path::new(filename);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o444)
+        .custom_flags(libc::O_CLOEXEC | libc::O_NOFOLLOW)
+        .open(path)
+        .map_err(|err| {
+            Error::UdevDevice(format!(
+                "unable to create tag index file: {filename}, error: {err}"
+            ))
+        })?;
+
+    Ok(())
+}
+
+fn tag_index_remove(filename: &str) -> Result<()> {
+    match fs::remove_file(filename) {
+        Ok(_) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(Error::UdevDevice(format!(
+            "unable to remove tag index file: {filename}, error: {err}"
+        ))),
+    }
+}
+
+// Parses a numeric property value, invalid values are logged, and read as zero.
+fn parse_property<T: std::str::FromStr + Default>(key: &str, value: &str) -> T
+where
+    T::Err: fmt::Display,
+{
+    crate::list::parse_value(key, value).unwrap_or_else(|err| {
+        log::debug!("{err}");
+        T::default()
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::{FixtureDevice, SysfsFixture};
     use crate::UdevEntry;
+    use std::path::Path;
 
     #[test]
     fn test_udev_device() {
@@ -2015,6 +2783,8 @@ mod tests {
         assert_eq!(null_dev.devnode_mode(), Mode::NONE);
         assert_eq!(null_dev.subsystem(), "");
         assert_eq!(null_dev.devtype(), "");
+        assert_eq!(null_dev.get_sysnum(), None);
+        assert_eq!(null_dev.get_action(), None);
         assert_eq!(null_dev.driver(), "");
         assert_eq!(null_dev.action(), "");
         assert_eq!(null_dev.devpath_old(), "");
@@ -2054,6 +2824,8 @@ mod tests {
         assert_eq!(exp_dev.devtype(), exp_devtype);
         assert_eq!(exp_dev.driver(), exp_driver);
         assert_eq!(exp_dev.action(), exp_action);
+        assert_eq!(exp_dev.get_sysnum(), Some(exp_sysnum));
+        assert_eq!(exp_dev.get_action(), Some(exp_action));
         assert_eq!(exp_dev.devpath_old(), exp_devpath_old);
         assert_eq!(exp_dev.id_filename(), exp_id_filename);
         assert_eq!(exp_dev.envp(), exp_envp);
@@ -2138,6 +2910,170 @@ mod tests {
         null_dev.set_sysattr_list_read(exp_sysattr_list_read);
         null_dev.set_db_persist(exp_db_persist);
 
+        // devices compare by syspath, compare every field
         assert_eq!(null_dev, exp_dev);
+        assert_eq!(format!("{null_dev:?}"), format!("{exp_dev:?}"));
+    }
+
+    #[test]
+    fn test_set_sysattr_value() -> Result<()> {
+        let devpath = "/devices/virtual/leds/led0";
+        let fixture = SysfsFixture::new()?.with_device(
+            FixtureDevice::new(devpath, "leds")
+                .with_sysattr("brightness", "0\n")
+                .with_sysattr("max_brightness", "255\n")
+                .with_sysattr("power/control", "auto\n"),
+        )?;
+        let dir = Path::new(fixture.sys_path()).join(&devpath[1..]);
+        fs::set_permissions(
+            dir.join("max_brightness"),
+            fs::Permissions::from_mode(0o444),
+        )?;
+
+        let mut dev = fixture.device(devpath)?;
+
+        assert_eq!(dev.get_sysattr_value("brightness").as_deref(), Some("0\n"));
+
+        dev.set_sysattr_value("brightness", "1")?;
+        assert_eq!(dev.get_sysattr_value("brightness").as_deref(), Some("1"));
+
+        for invalid in ["", "/brightness", "../brightness", "power", "missing"] {
+            assert!(
+                dev.set_sysattr_value(invalid, "1").is_err(),
+                "expected error for: {invalid}"
+            );
+        }
+
+        dev.trigger("change")?;
+        assert_eq!(fs::read_to_string(dir.join("uevent"))?, "change");
+
+        let uuid = "fc73cb8c-9b1e-4d3b-a7f2-3f0d0e5b4c21";
+        dev.trigger_with_uuid("add", uuid)?;
+        assert_eq!(
+            fs::read_to_string(dir.join("uevent"))?,
+            format!("add {uuid}")
+        );
+
+        assert!(dev.trigger("explode").is_err());
+        assert!(dev.trigger_with_uuid("add", "not-a-uuid").is_err());
+
+        // root can write to read-only files, so only check the mode is rejected
+        assert!(dev.set_sysattr_value("max_brightness", "1").is_err());
+        assert_eq!(fs::read_to_string(dir.join("max_brightness"))?, "255\n");
+
+        Ok(())
+    }
+    #[test]
+    fn test_new_from_nulstr_raw_bytes() -> Result<()> {
+        let buf = b"ACTION=add\0\
+            DEVPATH=/devices/virtual/mock/mock0\0\
+            SUBSYSTEM=mock\0\
+            ID_VENDOR=Acme \xff\xfe\0\
+            ID_OPTIONS=a=1 b=2\0\
+            TAGS=:systemd:seat:\0\
+            CURRENT_TAGS=:seat:\0\0";
+
+        let mut dev = UdevDevice::new_from_nulstr(Arc::new(Udev::new()), buf)?;
+
+        assert!(dev.has_tag("systemd"));
+        assert!(!dev.has_current_tag("systemd"));
+        assert!(dev.has_current_tag("seat"));
+
+        assert_eq!(dev.action(), "add");
+        assert_eq!(dev.subsystem(), "mock");
+        assert_eq!(
+            dev.get_property_value("ID_VENDOR"),
+            Some("Acme \u{fffd}\u{fffd}")
+        );
+        assert_eq!(
+            dev.get_property_value_bytes("ID_VENDOR"),
+            Some(b"Acme \xff\xfe".as_ref())
+        );
+        assert_eq!(dev.get_property_value("ID_OPTIONS"), Some("a=1 b=2"));
+        assert_eq!(
+            dev.get_property_value_bytes("ID_OPTIONS"),
+            Some(b"a=1 b=2".as_ref())
+        );
+
+        // current tags are sent from the current list, and do not linger after changes
+        let len = dev.properties_list().len();
+        assert!(dev
+            .get_properties_monitor_buf()
+            .contains("\0CURRENT_TAGS=:seat:\0"));
+        assert_eq!(dev.properties_list().len(), len);
+
+        dev.current_tags_list_mut().remove_entry("seat");
+        dev.add_current_tag("uaccess")?;
+        let buf = dev.get_properties_monitor_buf();
+        assert!(buf.contains("\0CURRENT_TAGS=:uaccess:\0"));
+        assert!(buf.contains("\0TAGS=:systemd:seat:uaccess:\0"));
+        assert_eq!(buf.matches("CURRENT_TAGS=").count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_move_interface_old() -> Result<()> {
+        let buf = b"ACTION=move\0\
+            DEVPATH=/devices/virtual/net/enp3s0\0\
+            DEVPATH_OLD=/devices/virtual/net/eth0\0\
+            SUBSYSTEM=net\0\
+            INTERFACE=enp3s0\0\
+            IFINDEX=2\0\0";
+
+        let dev = UdevDevice::new_from_nulstr(Arc::new(Udev::new()), buf)?;
+        assert_eq!(dev.devpath_old(), "/devices/virtual/net/eth0");
+        assert_eq!(dev.get_property_value("INTERFACE"), Some("enp3s0"));
+        assert_eq!(dev.get_property_value("INTERFACE_OLD"), Some("eth0"));
+
+        // only renamed network interfaces get the old name
+        let buf = b"ACTION=move\0\
+            DEVPATH=/devices/virtual/mock/mock1\0\
+            DEVPATH_OLD=/devices/virtual/mock/mock0\0\
+            SUBSYSTEM=mock\0\0";
+
+        let dev = UdevDevice::new_from_nulstr(Arc::new(Udev::new()), buf)?;
+        assert_eq!(dev.devpath_old(), "/devices/virtual/mock/mock0");
+        assert!(dev.get_property_value("INTERFACE_OLD").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_syspath_derived_fields() {
+        let udev = Arc::new(Udev::new());
+        let sys = udev.get_sys_path().to_owned();
+
+        let mut dev = UdevDevice::new(Arc::clone(&udev));
+        for (devpath, sysname, sysnum) in [
+            (
+                "/devices/pci0000:00/0000:00:1f.2/ata1/host0/target0:0:0/0:0:0:0/block/sda/sda1",
+                "sda1",
+                "1",
+            ),
+            ("/devices/virtual/block/sda", "sda", ""),
+            ("/devices/virtual/tty/tty!ttyS0", "tty/ttyS0", "0"),
+            (
+                "/devices/pci0000:00/0000:00:1c.0/cciss0/block/cciss!c0d0",
+                "cciss/c0d0",
+                "0",
+            ),
+            ("/devices/virtual/mem/null", "null", ""),
+            ("/devices/system/cpu/cpu12", "cpu12", "12"),
+            ("/devices/system/node/0", "0", ""),
+        ] {
+            dev.set_syspath(format!("{sys}{devpath}"));
+            assert_eq!(dev.devpath(), devpath);
+            assert_eq!(dev.sysname(), sysname);
+            assert_eq!(dev.sysnum(), sysnum);
+        }
+
+        // paths outside `sys` keep the devpath, but still derive the sysname
+        let dev = UdevDevice::new(udev)
+            .with_devpath("/devices/mock")
+            .with_syspath("/tmp/mock/input3");
+        assert_eq!(dev.devpath(), "/devices/mock");
+        assert_eq!(dev.sysname(), "input3");
+        assert_eq!(dev.get_sysnum(), Some("3"));
     }
 }
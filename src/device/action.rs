@@ -0,0 +1,131 @@
+use std::fmt;
+
+use super::UdevDevice;
+
+/// Represents the kernel action of a device event.
+///
+/// See [UdevDevice::action_type].
+#[repr(C)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum DeviceAction {
+    /// Device was added.
+    Add,
+    /// Device was removed.
+    Remove,
+    /// Device, or its properties, changed.
+    Change,
+    /// Device was renamed, or moved to a new parent.
+    Move,
+    /// Device came online, e.g. a hot-plugged CPU.
+    Online,
+    /// Device went offline.
+    Offline,
+    /// Driver was bound to the device.
+    Bind,
+    /// Driver was unbound from the device.
+    Unbind,
+    /// Action unknown to this library, or no action, e.g. for devices read from `sys`.
+    Unknown(String),
+}
+
+impl DeviceAction {
+    /// Gets the [DeviceAction] as the kernel action string, e.g. `add`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Add => "add",
+            Self::Remove => "remove",
+            Self::Change => "change",
+            Self::Move => "move",
+            Self::Online => "online",
+            Self::Offline => "offline",
+            Self::Bind => "bind",
+            Self::Unbind => "unbind",
+            Self::Unknown(action) => action.as_str(),
+        }
+    }
+
+    /// Gets whether the action is [Unknown](Self::Unknown).
+    pub const fn is_unknown(&self) -> bool {
+        matches!(self, Self::Unknown(_))
+    }
+}
+
+impl Default for DeviceAction {
+    fn default() -> Self {
+        Self::Unknown(String::new())
+    }
+}
+
+impl From<&str> for DeviceAction {
+    fn from(val: &str) -> Self {
+        match val {
+            "add" => Self::Add,
+            "remove" => Self::Remove,
+            "change" => Self::Change,
+            "move" => Self::Move,
+            "online" => Self::Online,
+            "offline" => Self::Offline,
+            "bind" => Self::Bind,
+            "unbind" => Self::Unbind,
+            _ => Self::Unknown(val.into()),
+        }
+    }
+}
+
+impl fmt::Display for DeviceAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl UdevDevice {
+    /// Gets the kernel action as a [DeviceAction].
+    ///
+    /// Typed alternative to [action](Self::action), for exhaustive `match` statements:
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{DeviceAction, Udev, UdevMonitor};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let mut monitor = UdevMonitor::new_from_netlink(Arc::new(Udev::new()), "udev")?;
+    /// monitor.enable_receiving()?;
+    ///
+    /// let device = monitor.receive_device()?;
+    /// match device.action_type() {
+    ///     DeviceAction::Add => println!("added: {}", device.syspath()),
+    ///     DeviceAction::Remove => println!("removed: {}", device.syspath()),
+    ///     action => println!("{action}: {}", device.syspath()),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn action_type(&self) -> DeviceAction {
+        self.action().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TRIGGER_ACTIONS;
+
+    #[test]
+    fn test_device_action() {
+        for action in TRIGGER_ACTIONS {
+            let typed = DeviceAction::from(action);
+            assert!(!typed.is_unknown());
+            assert_eq!(typed.as_str(), action);
+            assert_eq!(typed.to_string(), action);
+        }
+
+        assert_eq!(
+            DeviceAction::from("explode"),
+            DeviceAction::Unknown("explode".into())
+        );
+        assert_eq!(DeviceAction::default().as_str(), "");
+
+        let dev = UdevDevice::default();
+        assert_eq!(dev.action_type(), DeviceAction::Unknown(String::new()));
+        assert_eq!(dev.with_action("bind").action_type(), DeviceAction::Bind);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{Error, Result};
+
+use super::{Children, UdevDevice};
+
+/// Size of the sectors counted by the `size` sys attribute, independent of the logical block
+/// size of the device.
+pub const SECTOR_SIZE: u64 = 512;
+
+/// Iterator over the partitions of a whole-disk [UdevDevice].
+///
+/// Created by [UdevDevice::partitions].
+#[derive(Clone, Debug)]
+pub struct Partitions {
+    children: Children,
+}
+
+impl Iterator for Partitions {
+    type Item = UdevDevice;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.children.find(|dev| dev.is_partition())
+    }
+}
+
+impl UdevDevice {
+    /// Gets whether the [UdevDevice] is a `block` device.
+    pub fn is_block(&self) -> bool {
+        self.get_subsystem() == "block"
+    }
+
+    /// Gets whether the [UdevDevice] is a partition of a `block` device, e.g. `sda1`.
+    pub fn is_partition(&self) -> bool {
+        self.is_block()
+            && (self.get_devtype() == "partition" || self.get_sysattr_value("partition").is_some())
+    }
+
+    /// Gets the partition number, e.g. `1` for `sda1`.
+    ///
+    /// Returns: `Some(u32)` for partitions, `None` otherwise.
+    pub fn partition_number(&self) -> Option<u32> {
+        if self.is_partition() {
+            self.get_sysattr_value("partition")?
+                .trim()
+                .parse::<u32>()
+                .ok()
+        } else {
+            None
+        }
+    }
+
+    /// Gets the whole-disk device of a `block` device.
+    ///
+    /// Partitions resolve to the parent disk, whole disks resolve to themselves:
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{Udev, UdevDevice};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let udev = Arc::new(Udev::new());
+    /// let part = UdevDevice::new_from_subsystem_sysname(udev, "block", "sda1")?;
+    ///
+    /// let disk = part.whole_disk()?;
+    /// println!("{} is on {}", part.syspath(), disk.syspath());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns: `Ok(UdevDevice)` on success, `Err(Error)` if the device is not a `block` device,
+    /// or the partition has no parent disk.
+    pub fn whole_disk(&self) -> Result<Self> {
+        if !self.is_block() {
+            Err(Error::UdevDevice(format!(
+                "not a block device: {}",
+                self.syspath()
+            )))
+        } else if self.is_partition() {
+            self.parent_with_subsystem_devtype("block", "disk")
+        } else {
+            Ok(self.clone())
+        }
+    }
+
+    /// Gets the size of a `block` device in bytes.
+    ///
+    /// The `size` sys attribute counts 512-byte sectors, see [SECTOR_SIZE].
+    ///
+    /// Returns: `Some(u64)` for `block` devices with a valid `size` attribute, `None` otherwise.
+    pub fn size_bytes(&self) -> Option<u64> {
+        if self.is_block() {
+            self.get_sysattr_value("size")?
+                .trim()
+                .parse::<u64>()
+                .ok()?
+                .checked_mul(SECTOR_SIZE)
+        } else {
+            None
+        }
+    }
+
+    /// Gets an [Iterator] over the partitions of a whole-disk device.
+    ///
+    /// Partitions, and devices that are not `block` devices, have no partitions.
+    pub fn partitions(&self) -> Partitions {
+        Partitions {
+            children: self.children(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FixtureDevice, SysfsFixture};
+
+    const DISK_PATH: &str = "/devices/virtual/block/mock0";
+
+    // Disk with two partitions, added out of order, and a character device.
+    fn fixture() -> Result<SysfsFixture> {
+        let partition = |number: u32, size: &str| {
+            FixtureDevice::new(format!("{DISK_PATH}/mock0p{number}"), "block")
+                .with_devnum(240, number)
+                .with_devtype("partition")
+                .with_sysattr("partition", format!("{number}\n"))
+                .with_sysattr("size", size)
+        };
+
+        SysfsFixture::new()?.with_devices([
+            FixtureDevice::new(DISK_PATH, "block")
+                .with_devnum(240, 0)
+                .with_devtype("disk")
+                .with_sysattr("size", "2048\n"),
+            partition(2, "1024\n"),
+            partition(1, "512\n"),
+            FixtureDevice::new("/devices/virtual/mem/null", "mem"),
+        ])
+    }
+
+    #[test]
+    fn test_block_helpers() -> Result<()> {
+        let fixture = fixture()?;
+
+        let disk = fixture.device(DISK_PATH)?;
+        assert!(disk.is_block());
+        assert!(!disk.is_partition());
+        assert_eq!(disk.partition_number(), None);
+        assert_eq!(disk.size_bytes(), Some(2048 * SECTOR_SIZE));
+        assert_eq!(disk.whole_disk()?, disk);
+
+        let parts: Vec<UdevDevice> = disk.partitions().collect();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].partition_number(), Some(1));
+        assert_eq!(parts[1].partition_number(), Some(2));
+        assert_eq!(parts[1].size_bytes(), Some(1024 * SECTOR_SIZE));
+
+        for part in parts.iter() {
+            assert!(part.is_partition());
+            assert_eq!(part.whole_disk()?.syspath(), disk.syspath());
+            assert_eq!(part.partitions().count(), 0);
+        }
+
+        let null = fixture.device("/devices/virtual/mem/null")?;
+        assert!(!null.is_block());
+        assert!(null.whole_disk().is_err());
+        assert_eq!(null.size_bytes(), None);
+        assert_eq!(null.partitions().count(), 0);
+
+        Ok(())
+    }
+}
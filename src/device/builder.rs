@@ -0,0 +1,358 @@
+use std::sync::Arc;
+
+use super::UdevDevice;
+use crate::{Error, Result, Udev};
+
+/// Builder of synthetic [UdevDevice]s, e.g. for tests, and simulators.
+///
+/// Unlike the `with_*` functions of [UdevDevice], [build](Self::build) validates the device, and
+/// keeps the derived fields, and properties, consistent:
+///
+/// - the syspath is the `sys` path of the [Udev] context, followed by the devpath
+/// - the sysname, and sysnum, are derived from the devpath, see
+///   [set_syspath](UdevDevice::set_syspath)
+/// - the devnum, and the `MAJOR`, and `MINOR` properties, are set from the same numbers
+/// - the properties are written to the monitor buffer, ready for
+///   [send_device](crate::UdevMonitor::send_device)
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use udevrs::{Udev, UdevDevice};
+/// # fn main() -> udevrs::Result<()> {
+/// let device = UdevDevice::builder(Arc::new(Udev::new()))
+///     .devpath("/devices/virtual/block/loop0")
+///     .subsystem("block")
+///     .devtype("disk")
+///     .devnum(7, 0)
+///     .action("add")
+///     .property("ID_MOCK", "1")
+///     .build()?;
+///
+/// assert_eq!(device.sysname(), "loop0");
+/// assert_eq!(device.sysnum(), "0");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UdevDeviceBuilder {
+    udev: Arc<Udev>,
+    devpath: String,
+    subsystem: String,
+    devtype: String,
+    driver: String,
+    action: String,
+    devnode: String,
+    devnum: Option<(u32, u32)>,
+    ifindex: i32,
+    seqnum: u64,
+    usec_initialized: u64,
+    properties: Vec<(String, String)>,
+    tags: Vec<String>,
+    devlinks: Vec<String>,
+    is_initialized: bool,
+}
+
+impl UdevDeviceBuilder {
+    /// Creates a new [UdevDeviceBuilder].
+    pub fn new(udev: Arc<Udev>) -> Self {
+        Self {
+            udev,
+            devpath: String::new(),
+            subsystem: String::new(),
+            devtype: String::new(),
+            driver: String::new(),
+            action: String::new(),
+            devnode: String::new(),
+            devnum: None,
+            ifindex: 0,
+            seqnum: 0,
+            usec_initialized: 0,
+            properties: Vec::new(),
+            tags: Vec::new(),
+            devlinks: Vec::new(),
+            is_initialized: false,
+        }
+    }
+
+    /// Sets the devpath, e.g. `/devices/virtual/block/loop0`, required.
+    pub fn devpath<S: Into<String>>(mut self, devpath: S) -> Self {
+        self.devpath = devpath.into();
+        self
+    }
+
+    /// Sets the subsystem, required.
+    pub fn subsystem<S: Into<String>>(mut self, subsystem: S) -> Self {
+        self.subsystem = subsystem.into();
+        self
+    }
+
+    /// Sets the devtype.
+    pub fn devtype<S: Into<String>>(mut self, devtype: S) -> Self {
+        self.devtype = devtype.into();
+        self
+    }
+
+    /// Sets the driver.
+    pub fn driver<S: Into<String>>(mut self, driver: S) -> Self {
+        self.driver = driver.into();
+        self
+    }
+
+    /// Sets the kernel action, e.g. `add`.
+    pub fn action<S: Into<String>>(mut self, action: S) -> Self {
+        self.action = action.into();
+        self
+    }
+
+    /// Sets the device node, e.g. `/dev/loop0`, requires a [devnum](Self::devnum).
+    pub fn devnode<S: Into<String>>(mut self, devnode: S) -> Self {
+        self.devnode = devnode.into();
+        self
+    }
+
+    /// Sets the device number from the `major`, and `minor` numbers.
+    pub fn devnum(mut self, major: u32, minor: u32) -> Self {
+        self.devnum = Some((major, minor));
+        self
+    }
+
+    /// Sets the network interface index, only valid for the `net` subsystem.
+    pub fn ifindex(mut self, ifindex: i32) -> Self {
+        self.ifindex = ifindex;
+        self
+    }
+
+    /// Sets the event sequence number.
+    pub fn seqnum(mut self, seqnum: u64) -> Self {
+        self.seqnum = seqnum;
+        self
+    }
+
+    /// Sets the initialization time, in microseconds, and marks the device initialized.
+    pub fn usec_initialized(mut self, usec: u64) -> Self {
+        self.usec_initialized = usec;
+        self.is_initialized = true;
+        self
+    }
+
+    /// Sets whether the device was initialized by `udev`.
+    pub fn initialized(mut self, val: bool) -> Self {
+        self.is_initialized = val;
+        self
+    }
+
+    /// Adds a property.
+    ///
+    /// Properties derived from the other builder fields, e.g. `DEVPATH`, or `MAJOR`, are
+    /// rejected by [build](Self::build), set them through the builder functions instead.
+    pub fn property<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.properties.push((key.into(), value.into()));
+        self
+    }
+
+    /// Adds a tag.
+    pub fn tag<S: Into<String>>(mut self, tag: S) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Adds a devlink, e.g. `/dev/disk/by-id/mock`.
+    pub fn devlink<S: Into<String>>(mut self, devlink: S) -> Self {
+        self.devlinks.push(devlink.into());
+        self
+    }
+
+    /// Builds the [UdevDevice].
+    ///
+    /// Returns: `Ok(UdevDevice)` on success, `Err(Error)` if:
+    ///
+    /// - the devpath is empty, relative, or has empty, `.`, or `..` components
+    /// - the subsystem is empty, or contains a `/`
+    /// - a device node is set without a device number
+    /// - an interface index is set for a subsystem other than `net`
+    /// - a property sets a field derived from the builder, or a tag is invalid
+    pub fn build(self) -> Result<UdevDevice> {
+        let invalid = |msg: String| Error::UdevDevice(format!("invalid synthetic device: {msg}"));
+
+        let devpath = self.devpath.as_str();
+        if !devpath.starts_with('/')
+            || devpath[1..]
+                .split('/')
+                .any(|c| c.is_empty() || c == "." || c == "..")
+        {
+            return Err(invalid(format!("devpath: {devpath}")));
+        }
+
+        let subsystem = self.subsystem.as_str();
+        if subsystem.is_empty() || subsystem.contains('/') {
+            return Err(invalid(format!("subsystem: {subsystem}")));
+        }
+
+        if !self.devnode.is_empty() && self.devnum.is_none() {
+            return Err(invalid(format!("devnode without devnum: {}", self.devnode)));
+        }
+
+        if self.ifindex != 0 && subsystem != "net" {
+            return Err(invalid(format!("ifindex for subsystem: {subsystem}")));
+        }
+
+        if let Some((key, _)) = self
+            .properties
+            .iter()
+            .find(|(key, _)| DERIVED_PROPERTIES.contains(&key.as_str()))
+        {
+            return Err(invalid(format!("derived property: {key}")));
+        }
+
+        let mut device = UdevDevice::new(Arc::clone(&self.udev));
+
+        let mut props = vec![
+            format!("DEVPATH={devpath}"),
+            format!("SUBSYSTEM={subsystem}"),
+        ];
+        for (key, value) in [
+            ("DEVTYPE", self.devtype.as_str()),
+            ("DRIVER", self.driver.as_str()),
+            ("ACTION", self.action.as_str()),
+            ("DEVNAME", self.devnode.as_str()),
+        ] {
+            if !value.is_empty() {
+                props.push(format!("{key}={value}"));
+            }
+        }
+        if let Some((major, minor)) = self.devnum {
+            props.push(format!("MAJOR={major}"));
+            props.push(format!("MINOR={minor}"));
+        }
+        if self.ifindex != 0 {
+            props.push(format!("IFINDEX={}", self.ifindex));
+        }
+        if self.seqnum != 0 {
+            props.push(format!("SEQNUM={}", self.seqnum));
+        }
+        if self.usec_initialized != 0 {
+            props.push(format!("USEC_INITIALIZED={}", self.usec_initialized));
+        }
+
+        for prop in props.iter() {
+            device.add_property_from_string_parse(prop)?;
+        }
+        for (key, value) in self.properties.iter() {
+            device.add_property(key, value);
+        }
+        device.add_property_from_string_parse_finish()?;
+
+        // keep the major, and minor numbers in sync with the devnum
+        if let Some((major, minor)) = self.devnum {
+            device.set_maj(major as i32);
+            device.set_min(minor as i32);
+        }
+
+        for tag in self.tags.iter() {
+            if tag.is_empty() {
+                return Err(invalid("empty tag".into()));
+            }
+            device.add_tag(tag)?;
+        }
+        for devlink in self.devlinks.iter() {
+            device.add_devlink(devlink);
+        }
+
+        device.set_is_initialized(self.is_initialized);
+        // all information is set, getters must not read from `sys`
+        device.set_info_loaded(true);
+        device.update_envp_monitor_buf();
+
+        Ok(device)
+    }
+}
+
+impl UdevDevice {
+    /// Creates a [UdevDeviceBuilder] for a synthetic device.
+    pub fn builder(udev: Arc<Udev>) -> UdevDeviceBuilder {
+        UdevDeviceBuilder::new(udev)
+    }
+}
+
+// Properties set from the builder fields.
+const DERIVED_PROPERTIES: [&str; 11] = [
+    "DEVPATH",
+    "SUBSYSTEM",
+    "DEVTYPE",
+    "DRIVER",
+    "ACTION",
+    "DEVNAME",
+    "MAJOR",
+    "MINOR",
+    "IFINDEX",
+    "SEQNUM",
+    "USEC_INITIALIZED",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_device_builder() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+
+        let mut device = UdevDevice::builder(Arc::clone(&udev))
+            .devpath(
+                "/devices/pci0000:00/0000:00:1f.2/ata1/host0/target0:0:0/0:0:0:0/block/sda/sda1",
+            )
+            .subsystem("block")
+            .devtype("partition")
+            .devnode("/dev/sda1")
+            .devnum(8, 1)
+            .action("add")
+            .seqnum(42)
+            .usec_initialized(1_000)
+            .property("ID_FS_TYPE", "ext4")
+            .tag("systemd")
+            .devlink("/dev/disk/by-label/root")
+            .build()?;
+
+        let sys = udev.get_sys_path();
+        assert_eq!(
+            device.syspath(),
+            format!("{sys}{}", device.devpath()).as_str()
+        );
+        assert_eq!(device.sysname(), "sda1");
+        assert_eq!(device.sysnum(), "1");
+        assert_eq!(device.subsystem(), "block");
+        assert_eq!(device.devtype(), "partition");
+        assert_eq!(device.devnum(), libc::makedev(8, 1));
+        assert_eq!((device.maj(), device.min()), (8, 1));
+        assert_eq!(device.seqnum(), 42);
+        assert!(device.is_initialized());
+        assert!(device.has_tag("systemd"));
+        assert_eq!(device.get_property_value("MAJOR"), Some("8"));
+        assert_eq!(device.get_property_value("ID_FS_TYPE"), Some("ext4"));
+
+        // the monitor buffer round trips through the nulstr parser
+        let buf = device.get_properties_monitor_buf().as_bytes().to_vec();
+        assert!(buf.ends_with(b"\0"));
+        let parsed = UdevDevice::new_from_nulstr(Arc::clone(&udev), &buf)?;
+        assert_eq!(parsed.devnum(), device.devnum());
+        assert_eq!(parsed.devpath(), device.devpath());
+
+        let base = || {
+            UdevDevice::builder(Arc::clone(&udev))
+                .devpath("/devices/virtual/tty/ttyS0")
+                .subsystem("tty")
+        };
+        assert!(base().build().is_ok());
+        assert!(base().devpath("").build().is_err());
+        assert!(base().devpath("devices/tty").build().is_err());
+        assert!(base().devpath("/devices//tty").build().is_err());
+        assert!(base().devpath("/devices/../tty").build().is_err());
+        assert!(base().subsystem("").build().is_err());
+        assert!(base().devnode("/dev/ttyS0").build().is_err());
+        assert!(base().ifindex(2).build().is_err());
+        assert!(base().property("MAJOR", "4").build().is_err());
+        assert!(base().tag("").build().is_err());
+
+        Ok(())
+    }
+}
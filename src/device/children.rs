@@ -0,0 +1,176 @@
+use std::fs;
+use std::sync::Arc;
+
+use crate::{SysPath, Udev};
+
+use super::UdevDevice;
+
+/// Iterator over the child devices of an [UdevDevice].
+///
+/// Created by [UdevDevice::children], and [UdevDevice::descendants].
+///
+/// Child devices are the closest devices below the syspath, i.e. directories with a `uevent`
+/// file. Directories that are not devices, e.g. `net` or `block`, are searched for devices.
+/// Symlinks, e.g. `subsystem` or `driver`, are not followed.
+///
+/// Directories are read lazily, when the iterator advances.
+#[derive(Clone, Debug)]
+pub struct Children {
+    udev: Arc<Udev>,
+    root: Option<SysPath>,
+    stack: Vec<SysPath>,
+    recursive: bool,
+}
+
+impl Children {
+    fn new(device: &UdevDevice, recursive: bool) -> Self {
+        let udev = device.udev_cloned();
+        let root = SysPath::new_in(udev.get_sys_path(), device.syspath()).ok();
+
+        Self {
+            udev,
+            root,
+            stack: Vec::new(),
+            recursive,
+        }
+    }
+
+    // Pushes the subdirectories of `dir` to the stack, in reverse order, so they are visited
+    // sorted by name.
+    fn push_subdirs(&mut self, dir: &SysPath) {
+        let Ok(entries) = fs::read_dir(dir.as_str()) else {
+            return;
+        };
+
+        let mut names: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+            .filter_map(|e| e.file_name().into_string().ok())
+            .collect();
+        names.sort_unstable_by(|a, b| b.cmp(a));
+
+        self.stack
+            .extend(names.iter().filter_map(|name| dir.join(name).ok()));
+    }
+}
+
+impl Iterator for Children {
+    type Item = UdevDevice;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(root) = self.root.take() {
+            self.push_subdirs(&root);
+        }
+
+        while let Some(path) = self.stack.pop() {
+            let is_device = fs::metadata(format!("{path}/uevent")).is_ok();
+
+            if !is_device || self.recursive {
+                self.push_subdirs(&path);
+            }
+
+            if is_device {
+                match UdevDevice::new_from_syspath(Arc::clone(&self.udev), &path) {
+                    Ok(dev) => return Some(dev),
+                    // devices can disappear during the walk
+                    Err(err) => log::debug!("skipping child device {path}: {err}"),
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl UdevDevice {
+    /// Gets an [Iterator] over the direct child devices.
+    ///
+    /// Complements [parent](Self::parent): the parent of every child device is this device.
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{Udev, UdevDevice};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let udev = Arc::new(Udev::new());
+    /// let device = UdevDevice::new_from_subsystem_sysname(udev, "block", "sda")?;
+    ///
+    /// for partition in device.children() {
+    ///     println!("{}", partition.syspath());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn children(&self) -> Children {
+        Children::new(self, false)
+    }
+
+    /// Gets an [Iterator] over all devices in the subtree below the syspath.
+    ///
+    /// Devices are returned depth-first, every device before its own children.
+    pub fn descendants(&self) -> Children {
+        Children::new(self, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::SysfsFixture;
+    use crate::Result;
+    use std::path;
+
+    #[test]
+    fn test_children() -> Result<()> {
+        let fixture = SysfsFixture::new()?;
+        let sys_path = fixture.sys_path();
+        let host = path::Path::new(sys_path).join("devices/host0");
+
+        for dir in [
+            "",
+            "port0",
+            "port0/disk/sda",
+            "port0/disk/sda/sda1",
+            "port1",
+            "power",
+        ] {
+            let dir = host.join(dir);
+            fs::create_dir_all(dir.as_path())?;
+            if !dir.ends_with("disk") && !dir.ends_with("power") {
+                fs::write(dir.join("uevent"), "")?;
+            }
+        }
+        std::os::unix::fs::symlink(host.join("port1"), host.join("port0/device"))?;
+
+        let host = UdevDevice::new_from_syspath(
+            fixture.udev(),
+            &SysPath::new_in(sys_path, host.to_str().unwrap_or_default())?,
+        )?;
+
+        let names = |iter: Children| -> Vec<String> {
+            iter.map(|d| {
+                let syspath = d.syspath().to_owned();
+                path::Path::new(syspath.as_str())
+                    .strip_prefix(host.syspath())
+                    .map(|p| p.display().to_string())
+                    .unwrap_or(syspath)
+            })
+            .collect()
+        };
+
+        assert_eq!(names(host.children()), ["port0", "port1"]);
+        assert_eq!(
+            names(host.descendants()),
+            ["port0", "port0/disk/sda", "port0/disk/sda/sda1", "port1"]
+        );
+
+        // every child has the device as parent
+        for child in host.children() {
+            assert_eq!(child.parent()?.syspath(), host.syspath());
+        }
+
+        let leaf = host.children().last();
+        assert!(leaf.is_some_and(|d| d.children().next().is_none()));
+
+        Ok(())
+    }
+}
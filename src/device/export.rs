@@ -0,0 +1,154 @@
+use std::fmt;
+use std::io::Write;
+
+use super::UdevDevice;
+use crate::Result;
+
+/// Represents the output formats of [UdevDevice::export_properties].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ExportFormat {
+    /// Environment file lines, as printed by `udevadm info --query=property`: `KEY=value`
+    #[default]
+    Env,
+    /// Shell variable assignments, as printed by `udevadm info --query=property --export`:
+    /// `KEY='value'`
+    ///
+    /// Single quotes in values are escaped, so the output can be `eval`ed.
+    Shell,
+    /// JSON object of the properties, on a single line: `{"KEY":"value"}`
+    Json,
+}
+
+impl From<&ExportFormat> for &'static str {
+    fn from(val: &ExportFormat) -> Self {
+        match val {
+            ExportFormat::Env => "env",
+            ExportFormat::Shell => "shell",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+impl From<ExportFormat> for &'static str {
+    fn from(val: ExportFormat) -> Self {
+        (&val).into()
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", <&str>::from(self))
+    }
+}
+
+impl UdevDevice {
+    /// Writes the [UdevDevice] properties in the [ExportFormat] to the `writer`.
+    ///
+    /// Loads the `uevent` file, if it has not been loaded yet, like
+    /// [print_info](Self::print_info). Properties are sorted by name.
+    ///
+    /// Useful to pass device properties to other programs, e.g. the output of an
+    /// `IMPORT{program}` helper, or a debugging tool.
+    ///
+    /// Returns: `Ok(())` on success, `Err(Error)` if writing fails.
+    pub fn export_properties<W: Write>(
+        &mut self,
+        format: ExportFormat,
+        mut writer: W,
+    ) -> Result<()> {
+        // missing files only leave the properties empty
+        if !self.info_loaded() {
+            self.read_uevent_file().ok();
+        }
+
+        let props = self.properties_list().sorted_iter();
+
+        match format {
+            ExportFormat::Env => {
+                for prop in props {
+                    writeln!(writer, "{}={}", prop.name(), prop.value())?;
+                }
+            }
+            ExportFormat::Shell => {
+                for prop in props {
+                    writeln!(writer, "{}={}", prop.name(), shell_quote(prop.value()))?;
+                }
+            }
+            ExportFormat::Json => {
+                let fields: Vec<String> = props
+                    .map(|prop| {
+                        format!("{}:{}", json_string(prop.name()), json_string(prop.value()))
+                    })
+                    .collect();
+
+                writeln!(writer, "{{{}}}", fields.join(","))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Quotes the value in single quotes, escaping embedded single quotes as `'\''`.
+pub(super) fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+// Quotes the value as a JSON string.
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(format!("\\u{:04x}", c as u32).as_str()),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_properties() -> Result<()> {
+        let mut dev = UdevDevice::default().with_info_loaded(true);
+
+        dev.add_property("SUBSYSTEM", "block");
+        dev.add_property("ID_FS_LABEL", "it's \"root\"\\\n");
+        dev.add_property("DEVNAME", "/dev/loop0");
+
+        let export = |dev: &mut UdevDevice, format| -> Result<String> {
+            let mut out = Vec::new();
+            dev.export_properties(format, &mut out)?;
+            Ok(String::from_utf8(out).unwrap_or_default())
+        };
+
+        assert_eq!(
+            export(&mut dev, ExportFormat::Env)?,
+            "DEVNAME=/dev/loop0\nID_FS_LABEL=it's \"root\"\\\n\nSUBSYSTEM=block\n"
+        );
+        assert_eq!(
+            export(&mut dev, ExportFormat::Shell)?,
+            "DEVNAME='/dev/loop0'\nID_FS_LABEL='it'\\''s \"root\"\\\n'\nSUBSYSTEM='block'\n"
+        );
+        assert_eq!(
+            export(&mut dev, ExportFormat::Json)?,
+            "{\"DEVNAME\":\"/dev/loop0\",\"ID_FS_LABEL\":\"it's \\\"root\\\"\\\\\\n\",\"SUBSYSTEM\":\"block\"}\n"
+        );
+
+        assert_eq!(json_string("\u{1}"), "\"\\u0001\"");
+
+        Ok(())
+    }
+}
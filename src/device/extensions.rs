@@ -0,0 +1,184 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::{fmt, sync::Arc};
+
+use super::UdevDevice;
+
+/// Typed storage for user data attached to a [UdevDevice].
+///
+/// Holds at most one value per type. Values are reference-counted, so cloning the
+/// [UdevExtensions], e.g. when cloning the [UdevDevice], shares the values between the clones.
+///
+/// Extensions do not take part in [UdevDevice] comparisons.
+#[derive(Clone, Default)]
+pub struct UdevExtensions {
+    map: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+}
+
+impl UdevExtensions {
+    /// Creates a new, empty [UdevExtensions].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a value, replacing any value of the same type.
+    ///
+    /// Returns: `Some(Arc<T>)` with the replaced value, `None` otherwise.
+    pub fn insert<T: Any + Send + Sync>(&mut self, val: T) -> Option<Arc<T>> {
+        self.map
+            .insert(TypeId::of::<T>(), Arc::new(val))
+            .and_then(|old| old.downcast::<T>().ok())
+    }
+
+    /// Gets a reference to the value of type `T`.
+    pub fn get<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.map
+            .get(&TypeId::of::<T>())
+            .and_then(|val| val.downcast_ref::<T>())
+    }
+
+    /// Gets a shared reference-counted handle to the value of type `T`.
+    pub fn get_arc<T: Any + Send + Sync>(&self) -> Option<Arc<T>> {
+        self.map
+            .get(&TypeId::of::<T>())
+            .and_then(|val| Arc::clone(val).downcast::<T>().ok())
+    }
+
+    /// Gets a mutable reference to the value of type `T`.
+    ///
+    /// Returns: `Some(&mut T)` if the value is not shared with a cloned [UdevExtensions], `None`
+    /// otherwise.
+    pub fn get_mut<T: Any + Send + Sync>(&mut self) -> Option<&mut T> {
+        self.map
+            .get_mut(&TypeId::of::<T>())
+            .and_then(Arc::get_mut)
+            .and_then(|val| val.downcast_mut::<T>())
+    }
+
+    /// Gets whether a value of type `T` is stored.
+    pub fn contains<T: Any + Send + Sync>(&self) -> bool {
+        self.map.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Removes the value of type `T`.
+    ///
+    /// Returns: `Some(Arc<T>)` with the removed value, `None` if no value was stored.
+    pub fn remove<T: Any + Send + Sync>(&mut self) -> Option<Arc<T>> {
+        self.map
+            .remove(&TypeId::of::<T>())
+            .and_then(|val| val.downcast::<T>().ok())
+    }
+
+    /// Gets the number of stored values.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Gets whether no values are stored.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Removes all stored values.
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+}
+
+impl PartialEq for UdevExtensions {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl fmt::Debug for UdevExtensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UdevExtensions")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl UdevDevice {
+    /// Gets a reference to the [UdevExtensions] attached to the [UdevDevice].
+    pub const fn extensions(&self) -> &UdevExtensions {
+        &self.extensions
+    }
+
+    /// Gets a mutable reference to the [UdevExtensions] attached to the [UdevDevice].
+    pub fn extensions_mut(&mut self) -> &mut UdevExtensions {
+        &mut self.extensions
+    }
+
+    /// Attaches a value of type `T` to the [UdevDevice], replacing any value of the same type.
+    ///
+    /// Useful for carrying state with the device, e.g. an open file descriptor, instead of
+    /// keeping a separate map keyed by the syspath.
+    ///
+    /// Returns: `Some(Arc<T>)` with the replaced value, `None` otherwise.
+    pub fn insert_ext<T: Any + Send + Sync>(&mut self, val: T) -> Option<Arc<T>> {
+        self.extensions.insert(val)
+    }
+
+    /// Builder function that attaches a value of type `T` to the [UdevDevice].
+    pub fn with_ext<T: Any + Send + Sync>(mut self, val: T) -> Self {
+        self.insert_ext(val);
+        self
+    }
+
+    /// Gets a reference to the attached value of type `T`.
+    pub fn get_ext<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.extensions.get::<T>()
+    }
+
+    /// Gets a mutable reference to the attached value of type `T`.
+    ///
+    /// Returns: `Some(&mut T)` if the value is not shared with a cloned [UdevDevice], `None`
+    /// otherwise.
+    pub fn get_ext_mut<T: Any + Send + Sync>(&mut self) -> Option<&mut T> {
+        self.extensions.get_mut::<T>()
+    }
+
+    /// Detaches the value of type `T` from the [UdevDevice].
+    ///
+    /// Returns: `Some(Arc<T>)` with the removed value, `None` if no value was attached.
+    pub fn remove_ext<T: Any + Send + Sync>(&mut self) -> Option<Arc<T>> {
+        self.extensions.remove::<T>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Handle(i32);
+
+    #[test]
+    fn test_device_extensions() {
+        let mut dev = UdevDevice::default().with_ext(Handle(3));
+
+        assert_eq!(dev.get_ext::<Handle>(), Some(&Handle(3)));
+        assert_eq!(dev.get_ext::<u32>(), None);
+
+        assert_eq!(dev.insert_ext(7u32), None);
+        assert_eq!(dev.insert_ext(8u32).as_deref(), Some(&7));
+        assert_eq!(dev.extensions().len(), 2);
+
+        if let Some(handle) = dev.get_ext_mut::<Handle>() {
+            handle.0 = 4;
+        }
+        assert_eq!(dev.get_ext::<Handle>(), Some(&Handle(4)));
+
+        // clones share the values, and ignore extensions in comparisons
+        let clone = dev.clone();
+        assert_eq!(clone.get_ext::<Handle>(), Some(&Handle(4)));
+        assert!(dev.get_ext_mut::<Handle>().is_none());
+        assert_eq!(clone, UdevDevice::new(clone.udev_cloned()));
+
+        drop(clone);
+        assert_eq!(dev.remove_ext::<Handle>().as_deref(), Some(&Handle(4)));
+        assert!(!dev.extensions().contains::<Handle>());
+        assert_eq!(dev.extensions().len(), 1);
+    }
+}
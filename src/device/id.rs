@@ -0,0 +1,185 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use super::UdevDevice;
+use crate::{util, DevnumType};
+
+/// Represents the identity of a [UdevDevice], as reported to other processes.
+///
+/// Devices with a device node are identified by the device number, and type, which are unique
+/// while the device exists, even if it moves in the device tree. Other devices are identified by
+/// their subsystem, and syspath.
+///
+/// The identity is computed from the device state when requested, and may read the device
+/// number, and subsystem, from `sys`. A device whose device number is not known yet, e.g. a
+/// device created from a syspath before reading its `uevent` file, has a `Syspath` identity until
+/// it is loaded. Compute the identity once, and keep it, to use it as a set, or map key.
+#[repr(C)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum DeviceId {
+    /// Device with a device node.
+    Devnum {
+        /// Device number type, from the subsystem.
+        kind: DevnumType,
+        /// Major device number.
+        major: u32,
+        /// Minor device number.
+        minor: u32,
+    },
+    /// Device without a device node.
+    Syspath {
+        /// Device subsystem.
+        subsystem: String,
+        /// Absolute path of the device in `sys`.
+        syspath: String,
+    },
+}
+
+impl DeviceId {
+    /// Creates the [DeviceId] of a [UdevDevice].
+    pub fn from_device(dev: &UdevDevice) -> Self {
+        let devnum = dev.get_devnum();
+        let major = util::major(devnum);
+
+        if major > 0 {
+            Self::Devnum {
+                kind: DevnumType::from_device(dev),
+                major: major.into(),
+                minor: util::minor(devnum).into(),
+            }
+        } else {
+            Self::Syspath {
+                subsystem: dev.get_subsystem().into(),
+                syspath: dev.syspath().into(),
+            }
+        }
+    }
+}
+
+impl fmt::Display for DeviceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Devnum {
+                kind: DevnumType::Block,
+                major,
+                minor,
+            } => write!(f, "b{major}:{minor}"),
+            Self::Devnum {
+                kind: DevnumType::Char,
+                major,
+                minor,
+            } => write!(f, "c{major}:{minor}"),
+            Self::Syspath { subsystem, syspath } => write!(f, "+{subsystem}:{syspath}"),
+        }
+    }
+}
+
+impl UdevDevice {
+    /// Gets the [DeviceId] of the [UdevDevice].
+    ///
+    /// See [DeviceId] for when the identity changes.
+    pub fn id(&self) -> DeviceId {
+        DeviceId::from_device(self)
+    }
+}
+
+// Devices compare, and hash, by their syspath, which does not change when the device
+// information is loaded.
+impl PartialEq for UdevDevice {
+    fn eq(&self, other: &Self) -> bool {
+        self.syspath() == other.syspath()
+    }
+}
+
+impl Eq for UdevDevice {}
+
+impl Hash for UdevDevice {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.syspath().hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Result, Udev};
+    use std::collections::HashSet;
+    use std::hash::{BuildHasher, RandomState};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_device_id() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+
+        let disk = || {
+            UdevDevice::builder(Arc::clone(&udev))
+                .devpath("/devices/virtual/block/loop0")
+                .subsystem("block")
+                .devnum(7, 0)
+        };
+
+        let id = disk().build()?.id();
+        assert_eq!(
+            id,
+            DeviceId::Devnum {
+                kind: DevnumType::Block,
+                major: 7,
+                minor: 0
+            }
+        );
+        assert_eq!(id.to_string(), "b7:0");
+
+        // the cached state does not change the identity
+        let plain = disk().build()?;
+        let loaded = disk()
+            .property("ID_FS_TYPE", "ext4")
+            .tag("systemd")
+            .build()?;
+        assert_eq!(plain, loaded);
+
+        let tty = UdevDevice::builder(Arc::clone(&udev))
+            .devpath("/devices/virtual/tty/tty0")
+            .subsystem("tty")
+            .devnum(4, 0)
+            .build()?;
+        assert_eq!(tty.id().to_string(), "c4:0");
+        assert_ne!(tty, plain);
+
+        let net = UdevDevice::builder(Arc::clone(&udev))
+            .devpath("/devices/virtual/net/lo")
+            .subsystem("net")
+            .build()?;
+        assert_eq!(
+            net.id().to_string(),
+            format!("+net:{}/devices/virtual/net/lo", udev.get_sys_path())
+        );
+
+        let set: HashSet<DeviceId> = [&plain, &loaded, &tty, &net]
+            .into_iter()
+            .map(UdevDevice::id)
+            .collect();
+        assert_eq!(set.len(), 3);
+
+        // loading the device number changes the identity, but not the device key
+        let mut lazy = UdevDevice::new(Arc::clone(&udev))
+            .with_syspath(format!(
+                "{}/devices/virtual/block/loop0",
+                udev.get_sys_path()
+            ))
+            .with_subsystem("block");
+        lazy.set_info_loaded(true);
+        let before = lazy.id();
+
+        let state = RandomState::new();
+        let key = state.hash_one(&lazy);
+
+        lazy.set_devnum(libc::makedev(7, 0));
+        assert_ne!(lazy.id(), before);
+        assert_eq!(lazy.id(), plain.id());
+        assert_eq!(state.hash_one(&lazy), key);
+        assert_eq!(state.hash_one(&plain), key);
+        assert_eq!(lazy, plain);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+use std::fmt::{self, Write};
+
+use super::{export::shell_quote, UdevDevice};
+
+/// Represents the output formats of `udevadm info`.
+///
+/// See [UdevDevice::print_info].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum InfoFormat {
+    /// Device record, as printed by `udevadm info --query=all`:
+    ///
+    /// ```no_build,no_run
+    /// P: /devices/virtual/block/loop0
+    /// N: loop0
+    /// S: disk/by-label/root
+    /// E: DEVNAME=/dev/loop0
+    /// ```
+    #[default]
+    Record,
+    /// Device properties, as printed by `udevadm info --query=property`: `KEY=value`
+    Property,
+    /// Device properties, as printed by `udevadm info --query=property --export`: `KEY='value'`
+    Export,
+}
+
+impl From<&InfoFormat> for &'static str {
+    fn from(val: &InfoFormat) -> Self {
+        match val {
+            InfoFormat::Record => "record",
+            InfoFormat::Property => "property",
+            InfoFormat::Export => "export",
+        }
+    }
+}
+
+impl From<InfoFormat> for &'static str {
+    fn from(val: InfoFormat) -> Self {
+        (&val).into()
+    }
+}
+
+impl fmt::Display for InfoFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", <&str>::from(self))
+    }
+}
+
+impl UdevDevice {
+    /// Formats the [UdevDevice] information in the [InfoFormat] used by `udevadm info`.
+    ///
+    /// Loads the `uevent` file, and the database entry, if they have not been loaded yet.
+    ///
+    /// Device node, and device link paths in the [InfoFormat::Record] are relative to the device
+    /// directory, and the record ends with an empty line.
+    ///
+    /// Device links, and properties, are sorted by name, like the `libudev` lists.
+    ///
+    /// Returns: the formatted device information.
+    pub fn print_info(&mut self, format: InfoFormat) -> String {
+        // load lazily read information, missing files only leave the fields empty
+        if !self.info_loaded() {
+            self.read_uevent_file().ok();
+        }
+        self.get_devlinks_list_entry();
+
+        let mut out = String::new();
+
+        // writing to a `String` cannot fail
+        match format {
+            InfoFormat::Record => {
+                let dev_path = self.udev().get_dev_path();
+
+                writeln!(out, "P: {}", self.devpath()).ok();
+
+                if !self.devnode().is_empty() {
+                    writeln!(out, "N: {}", strip_dev_path(self.devnode(), dev_path)).ok();
+                }
+
+                if self.devlink_priority() != 0 {
+                    writeln!(out, "L: {}", self.devlink_priority()).ok();
+                }
+
+                for link in self.devlinks_list().sorted_iter() {
+                    writeln!(out, "S: {}", strip_dev_path(link.name(), dev_path)).ok();
+                }
+
+                for prop in self.properties_list().sorted_iter() {
+                    writeln!(out, "E: {}={}", prop.name(), prop.value()).ok();
+                }
+
+                out.push('\n');
+            }
+            InfoFormat::Property => {
+                for prop in self.properties_list().sorted_iter() {
+                    writeln!(out, "{}={}", prop.name(), prop.value()).ok();
+                }
+            }
+            InfoFormat::Export => {
+                for prop in self.properties_list().sorted_iter() {
+                    writeln!(out, "{}={}", prop.name(), shell_quote(prop.value())).ok();
+                }
+            }
+        }
+
+        out
+    }
+}
+
+fn strip_dev_path<'a>(path: &'a str, dev_path: &str) -> &'a str {
+    path.strip_prefix(dev_path)
+        .and_then(|p| p.strip_prefix('/'))
+        .unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_print_info() {
+        let mut dev = UdevDevice::default()
+            .with_devpath("/devices/virtual/block/loop0")
+            .with_devnode("/dev/loop0")
+            .with_info_loaded(true);
+
+        dev.add_devlink("/dev/disk/by-label/root");
+        dev.add_property("DEVNAME", "/dev/loop0");
+        dev.add_property("SUBSYSTEM", "block");
+
+        assert_eq!(
+            dev.print_info(InfoFormat::Record),
+            "P: /devices/virtual/block/loop0\n\
+             N: loop0\n\
+             S: disk/by-label/root\n\
+             E: DEVNAME=/dev/loop0\n\
+             E: SUBSYSTEM=block\n\n"
+        );
+        assert_eq!(
+            dev.print_info(InfoFormat::Property),
+            "DEVNAME=/dev/loop0\nSUBSYSTEM=block\n"
+        );
+        assert_eq!(
+            dev.print_info(InfoFormat::Export),
+            "DEVNAME='/dev/loop0'\nSUBSYSTEM='block'\n"
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::os::linux::fs::MetadataExt;
+use std::sync::{OnceLock, RwLock};
+use std::{fmt, fs};
+
+use crate::metrics::{Metric, MetricsTimer};
+use crate::{util, Udev};
+
+use super::UEVENT_FILE_LIMIT;
+
+/// Values read lazily by the [UdevDevice](super::UdevDevice) getters taking `&self`.
+///
+/// Explicitly set fields take precedence over the cached values, so the caches only hold
+/// information read from `sys`.
+#[derive(Default)]
+pub(crate) struct LazyFields {
+    pub(crate) subsystem: OnceLock<String>,
+    pub(crate) driver: OnceLock<Option<String>>,
+    pub(crate) uevent: OnceLock<UeventInfo>,
+    pub(crate) id_filename: OnceLock<String>,
+    pub(crate) sysattrs: RwLock<HashMap<String, Option<String>>>,
+}
+
+impl LazyFields {
+    /// Gets the cached value of a sys attribute, reading it on the first call.
+    pub(crate) fn sysattr(&self, syspath: &str, sysattr: &str) -> Option<String> {
+        if let Some(value) = self
+            .sysattrs
+            .read()
+            .ok()
+            .and_then(|cache| cache.get(sysattr).cloned())
+        {
+            return value;
+        }
+
+        let value = read_sysattr(syspath, sysattr);
+
+        // a poisoned lock only disables caching
+        if let Ok(mut cache) = self.sysattrs.write() {
+            cache.insert(sysattr.into(), value.clone());
+        }
+
+        value
+    }
+
+    /// Removes the cached ID filename, after a field it is derived from changed.
+    pub(crate) fn invalidate_id_filename(&mut self) {
+        self.id_filename.take();
+    }
+
+    /// Removes the cached value of a sys attribute.
+    pub(crate) fn invalidate_sysattr(&mut self, sysattr: &str) {
+        if let Ok(cache) = self.sysattrs.get_mut() {
+            cache.remove(sysattr);
+        }
+    }
+
+    /// Removes the cached values of all sys attributes.
+    pub(crate) fn clear_sysattrs(&mut self) {
+        if let Ok(cache) = self.sysattrs.get_mut() {
+            cache.clear();
+        }
+    }
+}
+
+impl Clone for LazyFields {
+    fn clone(&self) -> Self {
+        Self {
+            subsystem: self.subsystem.clone(),
+            driver: self.driver.clone(),
+            uevent: self.uevent.clone(),
+            id_filename: self.id_filename.clone(),
+            sysattrs: RwLock::new(
+                self.sysattrs
+                    .read()
+                    .map(|cache| cache.clone())
+                    .unwrap_or_default(),
+            ),
+        }
+    }
+}
+
+impl fmt::Debug for LazyFields {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazyFields")
+            .field("subsystem", &self.subsystem)
+            .field("driver", &self.driver)
+            .field("uevent", &self.uevent)
+            .field("id_filename", &self.id_filename)
+            .finish_non_exhaustive()
+    }
+}
+
+// caches hold information derived from the syspath, and do not change the device identity
+impl PartialEq for LazyFields {
+    fn eq(&self, _oth: &Self) -> bool {
+        true
+    }
+}
+
+/// Device information from the `uevent` file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct UeventInfo {
+    pub(crate) devtype: String,
+    pub(crate) devnode: String,
+    pub(crate) devnum: u64,
+    pub(crate) ifindex: i32,
+}
+
+impl UeventInfo {
+    /// Reads the `uevent` file of the device at `syspath`.
+    ///
+    /// A missing, or unreadable, file leaves the information empty.
+    pub(crate) fn read(syspath: &str) -> Self {
+        let mut info = Self::default();
+        let (mut maj, mut min) = (0u32, 0u32);
+
+        for line in read_uevent_lines(syspath).unwrap_or_default() {
+            if let Some(devtype) = line.strip_prefix("DEVTYPE=") {
+                info.devtype = devtype.into();
+            } else if let Some(devname) = line.strip_prefix("DEVNAME=") {
+                info.devnode = devname.into();
+            } else if let Some(ifindex) = line.strip_prefix("IFINDEX=") {
+                info.ifindex = ifindex.parse::<i32>().unwrap_or(0);
+            } else if let Some(major) = line.strip_prefix("MAJOR=") {
+                maj = major.parse::<u32>().unwrap_or(0);
+            } else if let Some(minor) = line.strip_prefix("MINOR=") {
+                min = minor.parse::<u32>().unwrap_or(0);
+            }
+        }
+
+        if maj > 0 {
+            info.devnum = libc::makedev(maj, min);
+        }
+
+        info
+    }
+}
+
+/// Reads the lines of the `uevent` file of the device at `syspath`.
+pub(crate) fn read_uevent_lines(syspath: &str) -> io::Result<Vec<String>> {
+    let file = fs::File::open(format!("{syspath}/uevent"))?;
+    let reader = io::BufReader::new(file.take(UEVENT_FILE_LIMIT as u64));
+
+    Ok(util::read_lines(reader).collect())
+}
+
+/// Checks whether a sys attribute name stays below the device directory.
+///
+/// Names are relative paths, e.g. `queue/rotational`, without empty, `.`, or `..` components.
+pub(crate) fn is_valid_sysattr(sysattr: &str) -> bool {
+    !sysattr.is_empty()
+        && !sysattr.starts_with('/')
+        && !sysattr
+            .split('/')
+            .any(|c| c.is_empty() || c == "." || c == "..")
+}
+
+/// Reads a sys attribute value, core links are resolved to the basename of the link target.
+pub(crate) fn read_sysattr(syspath: &str, sysattr: &str) -> Option<String> {
+    if !is_valid_sysattr(sysattr) {
+        return None;
+    }
+
+    let path = format!("{syspath}/{sysattr}");
+    let metadata = fs::symlink_metadata(path.as_str()).ok()?;
+
+    if metadata.is_symlink() {
+        if matches!(sysattr, "driver" | "subsystem" | "module") {
+            Udev::get_sys_core_link_value(sysattr, syspath).ok()
+        } else {
+            None
+        }
+    } else if metadata.is_dir() || metadata.len() == 0 || metadata.st_mode() & libc::S_IRUSR == 0 {
+        None
+    } else {
+        let timer = MetricsTimer::start(Metric::SysattrReadDuration);
+        let mut file = fs::File::open(path.as_str()).ok()?;
+        let mut value = [0u8; 4096];
+        let read = file.read(&mut value).ok()?;
+        timer.stop();
+
+        Some(String::from_utf8_lossy(&value[..read]).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::SysfsFixture;
+    use crate::UdevDevice;
+    use std::{sync::Arc, thread};
+
+    #[test]
+    fn test_lazy_getters() -> crate::Result<()> {
+        let fixture = SysfsFixture::new()?;
+        let dir = fixture.root().join("loop0");
+        fs::create_dir_all(dir.as_path())?;
+        fs::write(
+            dir.join("uevent"),
+            "MAJOR=7\nMINOR=0\nDEVNAME=loop0\nDEVTYPE=disk\n",
+        )?;
+        fs::write(dir.join("size"), "0\n")?;
+
+        let dev = Arc::new(UdevDevice::default().with_syspath(dir.to_str().unwrap_or_default()));
+
+        // getters only need a shared reference, also from other threads
+        let shared = Arc::clone(&dev);
+        thread::spawn(move || {
+            assert_eq!(shared.get_devtype(), "disk");
+            assert_eq!(shared.get_sysattr_value("size").as_deref(), Some("0\n"));
+        })
+        .join()
+        .ok();
+
+        assert_eq!(dev.get_devtype(), "disk");
+        assert_eq!(dev.get_devnode(), Some("loop0"));
+        assert_eq!(dev.get_devnum(), libc::makedev(7, 0));
+        assert_eq!(dev.get_ifindex(), 0);
+        assert_eq!(dev.get_driver(), None);
+        assert_eq!(dev.get_sysattr_value("missing"), None);
+
+        // names leaving the device directory are never read
+        let name = dir.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        assert_eq!(dev.get_sysattr_value(&format!("../{name}/size")), None);
+        assert_eq!(dev.get_sysattr_value("./size"), None);
+        assert_eq!(
+            dev.get_sysattr_value(&format!("{}/size", dir.display())),
+            None
+        );
+
+        // cached values survive file changes
+        fs::write(dir.join("size"), "8\n")?;
+        assert_eq!(dev.get_sysattr_value("size").as_deref(), Some("0\n"));
+
+        // explicitly set fields take precedence, and a new syspath drops the caches
+        let mut dev = (*dev).clone().with_devtype("partition");
+        assert_eq!(dev.get_devtype(), "partition");
+        assert_eq!(dev.get_sysattr_value("size").as_deref(), Some("0\n"));
+
+        dev.set_syspath(dir.to_str().unwrap_or_default());
+        assert_eq!(dev.get_sysattr_value("size").as_deref(), Some("8\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_id_filename_invalidation() {
+        let mut dev = UdevDevice::new(Arc::new(Udev::new()))
+            .with_devpath("/devices/virtual/mock/mock0")
+            .with_subsystem("mock")
+            .with_info_loaded(true);
+        assert_eq!(dev.get_id_filename(), "+mock:mock0");
+
+        dev.set_devnum(libc::makedev(7, 0));
+        assert_eq!(dev.get_id_filename(), "c7:0");
+
+        dev.set_subsystem("block");
+        assert_eq!(dev.get_id_filename(), "b7:0");
+
+        dev.set_devnum(0);
+        dev.set_ifindex(3);
+        assert_eq!(dev.get_id_filename(), "n3");
+
+        dev.set_ifindex(0);
+        dev.set_devpath("/devices/virtual/mock/mock1");
+        assert_eq!(dev.get_id_filename(), "+block:mock1");
+
+        dev.set_info_loaded(false);
+        dev.set_uevent_loaded(true);
+        assert_eq!(dev.get_id_filename(), "+block:mock1");
+    }
+}
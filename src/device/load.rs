@@ -0,0 +1,155 @@
+use crate::Result;
+
+use super::UdevDevice;
+
+/// Selects the device information sources read by [UdevDevice::load].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct LoadFlags(u32);
+
+bitflags! {
+    impl LoadFlags: u32 {
+        /// Properties from the sys device `uevent` file.
+        const UEVENT = 1 << 0;
+        /// Properties, devlinks, and tags from the `udev` database.
+        const DB = 1 << 1;
+        /// Names of the sys device attributes.
+        const SYSATTRS = 1 << 2;
+        /// No sources, [UdevDevice::load] reads nothing.
+        const NONE = 0;
+    }
+}
+
+/// Per-source results of [UdevDevice::load].
+///
+/// Sources that were not requested are `None`.
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LoadSummary {
+    uevent: Option<Result<()>>,
+    db: Option<Result<()>>,
+    sysattrs: Option<Result<()>>,
+}
+
+impl LoadSummary {
+    /// Creates a new [LoadSummary].
+    pub const fn new() -> Self {
+        Self {
+            uevent: None,
+            db: None,
+            sysattrs: None,
+        }
+    }
+
+    /// Gets the result of reading the `uevent` file, if requested.
+    pub fn uevent(&self) -> Option<&Result<()>> {
+        self.uevent.as_ref()
+    }
+
+    /// Gets the result of reading the `udev` database, if requested.
+    pub fn db(&self) -> Option<&Result<()>> {
+        self.db.as_ref()
+    }
+
+    /// Gets the result of reading the sys attribute names, if requested.
+    pub fn sysattrs(&self) -> Option<&Result<()>> {
+        self.sysattrs.as_ref()
+    }
+
+    /// Gets whether all requested sources were read successfully.
+    pub fn is_ok(&self) -> bool {
+        [&self.uevent, &self.db, &self.sysattrs]
+            .into_iter()
+            .flatten()
+            .all(|r| r.is_ok())
+    }
+}
+
+impl UdevDevice {
+    /// Reads the requested device information sources eagerly.
+    ///
+    /// Getters read missing information lazily, e.g. [get_devnode](Self::get_devnode) reads
+    /// the `uevent` file, which may block. Loading up front, e.g. in a worker thread, moves all
+    /// the I/O to a known point:
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{LoadFlags, Udev, UdevDevice};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let udev = Arc::new(Udev::new());
+    /// let mut device = UdevDevice::new_from_subsystem_sysname(udev, "block", "loop0")?;
+    ///
+    /// let summary = device.load(LoadFlags::UEVENT | LoadFlags::DB | LoadFlags::SYSATTRS);
+    /// if let Some(Err(err)) = summary.db() {
+    ///     eprintln!("no database entry: {err}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Sources are read in order: `uevent` file, database, then sys attributes. The database
+    /// filename depends on the device number, or interface index, from the `uevent` file.
+    ///
+    /// Sources already read are not read again, and report success.
+    ///
+    /// Returns: [LoadSummary] with the result of every requested source.
+    pub fn load(&mut self, flags: LoadFlags) -> LoadSummary {
+        let mut summary = LoadSummary::new();
+
+        if flags.contains(LoadFlags::UEVENT) {
+            summary.uevent = Some(self.read_uevent_file());
+        }
+
+        if flags.contains(LoadFlags::DB) {
+            summary.db = Some(self.read_db());
+        }
+
+        if flags.contains(LoadFlags::SYSATTRS) {
+            summary.sysattrs = Some(self.get_sysattr_list_read().map(|_| ()));
+        }
+
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::SysfsFixture;
+    use std::fs;
+
+    #[test]
+    fn test_load() -> Result<()> {
+        let fixture = SysfsFixture::new()?;
+        let dir = fixture.root().join("loop0");
+        fs::create_dir_all(dir.as_path())?;
+        fs::write(dir.join("uevent"), "DEVTYPE=disk\nDEVNAME=loop0\n")?;
+        fs::write(dir.join("size"), "0\n")?;
+
+        let mut dev = UdevDevice::default().with_syspath(dir.to_str().unwrap_or_default());
+
+        let summary = dev.load(LoadFlags::NONE);
+        assert_eq!(summary, LoadSummary::new());
+        assert!(summary.is_ok());
+        assert!(!dev.uevent_loaded());
+
+        let summary = dev.load(LoadFlags::UEVENT | LoadFlags::SYSATTRS);
+        assert_eq!(summary.uevent(), Some(&Ok(())));
+        assert_eq!(summary.sysattrs(), Some(&Ok(())));
+        assert!(summary.db().is_none());
+        assert!(summary.is_ok());
+
+        assert!(dev.uevent_loaded());
+        assert!(dev.sysattr_list_read());
+        assert_eq!(dev.devtype(), "disk");
+        assert!(dev.sysattr_list().entry_by_name("size").is_some());
+
+        // the device has no database entry
+        let summary = dev.load(LoadFlags::all());
+        assert_eq!(summary.uevent(), Some(&Ok(())));
+        assert!(matches!(summary.db(), Some(Err(_))));
+        assert!(!summary.is_ok());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,371 @@
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::{fmt, fs, io, mem};
+
+use super::UdevDevice;
+use crate::{Error, ErrorScope, Result};
+
+/// Length of an Ethernet MAC address in bytes.
+pub const MAC_ADDRESS_LEN: usize = 6;
+
+/// Maximum length of a network interface name, excluding the nul terminator.
+pub const IFNAME_MAX_LEN: usize = libc::IFNAMSIZ - 1;
+
+/// Represents the operational state of a network interface, from the `operstate` sys attribute.
+///
+/// Values follow RFC 2863, see [UdevDevice::operstate].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum OperState {
+    /// State unknown, e.g. for interfaces without carrier detection.
+    #[default]
+    Unknown,
+    /// Interface is missing a component, typically hardware.
+    NotPresent,
+    /// Interface is down.
+    Down,
+    /// Interface is down, because a lower layer interface is down.
+    LowerLayerDown,
+    /// Interface is in test mode.
+    Testing,
+    /// Interface is waiting for an external event, e.g. `802.1X` authentication.
+    Dormant,
+    /// Interface is up, and can pass packets.
+    Up,
+}
+
+impl OperState {
+    /// Gets the [OperState] as the sys attribute string, e.g. `up`.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::NotPresent => "notpresent",
+            Self::Down => "down",
+            Self::LowerLayerDown => "lowerlayerdown",
+            Self::Testing => "testing",
+            Self::Dormant => "dormant",
+            Self::Up => "up",
+        }
+    }
+}
+
+impl From<&str> for OperState {
+    fn from(val: &str) -> Self {
+        match val.trim() {
+            "notpresent" => Self::NotPresent,
+            "down" => Self::Down,
+            "lowerlayerdown" => Self::LowerLayerDown,
+            "testing" => Self::Testing,
+            "dormant" => Self::Dormant,
+            "up" => Self::Up,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for OperState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl UdevDevice {
+    /// Gets whether the [UdevDevice] is a network interface, i.e. in the `net` subsystem.
+    pub fn is_net(&self) -> bool {
+        self.get_subsystem() == "net"
+    }
+
+    /// Gets the interface index of a network interface.
+    ///
+    /// Unlike [get_ifindex](Self::get_ifindex), falls back to the `ifindex` sys attribute, and
+    /// is only set for `net` devices.
+    ///
+    /// Returns: `Some(u32)` for network interfaces, `None` otherwise.
+    pub fn net_ifindex(&self) -> Option<u32> {
+        if !self.is_net() {
+            None
+        } else if self.get_ifindex() > 0 {
+            u32::try_from(self.get_ifindex()).ok()
+        } else {
+            self.get_sysattr_value("ifindex")?
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|&idx| idx > 0)
+        }
+    }
+
+    /// Gets the MAC address of a network interface, from the `address` sys attribute.
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{Udev, UdevDevice};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let udev = Arc::new(Udev::new());
+    /// let eth = UdevDevice::new_from_subsystem_sysname(udev, "net", "eth0")?;
+    ///
+    /// if let Some([a, b, c, d, e, f]) = eth.mac_address() {
+    ///     println!("eth0: {a:02x}:{b:02x}:{c:02x}:{d:02x}:{e:02x}:{f:02x}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns: `Some([u8; 6])` for network interfaces with an Ethernet-style address, `None`
+    /// otherwise, e.g. for InfiniBand, or tunnel interfaces.
+    pub fn mac_address(&self) -> Option<[u8; MAC_ADDRESS_LEN]> {
+        if !self.is_net() {
+            return None;
+        }
+
+        let address = self.get_sysattr_value("address")?;
+        let mut mac = [0u8; MAC_ADDRESS_LEN];
+        let mut octets = address.trim().split(':');
+
+        for byte in mac.iter_mut() {
+            let octet = octets.next().filter(|o| o.len() == 2)?;
+            *byte = u8::from_str_radix(octet, 16).ok()?;
+        }
+
+        octets.next().is_none().then_some(mac)
+    }
+
+    /// Gets the operational state of a network interface, from the `operstate` sys attribute.
+    ///
+    /// Returns: `Some(OperState)` for network interfaces with an `operstate`, `None` otherwise.
+    pub fn operstate(&self) -> Option<OperState> {
+        if self.is_net() {
+            self.get_sysattr_value("operstate")
+                .map(|state| OperState::from(state.as_str()))
+        } else {
+            None
+        }
+    }
+
+    /// Gets whether the [UdevDevice] is a wireless network interface.
+    ///
+    /// Wireless interfaces have the `wlan` devtype, a `wireless` directory, or a `phy80211`
+    /// link in `sys`.
+    pub fn is_wireless(&self) -> bool {
+        self.is_net()
+            && (self.get_devtype() == "wlan"
+                || ["wireless", "phy80211"]
+                    .iter()
+                    .any(|name| fs::symlink_metadata(format!("{}/{name}", self.syspath())).is_ok()))
+    }
+
+    /// Renames a network interface with the `SIOCSIFNAME` ioctl, like `udevd` applying a `NAME`
+    /// rule, e.g. for predictable interface names.
+    ///
+    /// Requires `CAP_NET_ADMIN`, and the interface to be down. On success, the syspath, devpath,
+    /// and sysname follow the new name, the old devpath is kept in
+    /// [devpath_old](Self::devpath_old), and the `DEVPATH`, `DEVPATH_OLD`, `INTERFACE`, and
+    /// `INTERFACE_OLD` properties are set, like in the kernel `move` event of the rename.
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{Udev, UdevDevice};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let udev = Arc::new(Udev::new());
+    /// let mut eth = UdevDevice::new_from_subsystem_sysname(udev, "net", "eth0")?;
+    ///
+    /// eth.rename_netif("enp3s0")?;
+    /// assert_eq!(eth.get_property_value("INTERFACE_OLD"), Some("eth0"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns: `Ok(())` on success, or if the interface already has the name, `Err(Error)` if
+    /// the device is not a network interface, the name is invalid, or the rename fails.
+    pub fn rename_netif(&mut self, new_name: &str) -> Result<()> {
+        if !self.is_net() {
+            return Err(Error::UdevDevice(format!(
+                "not a network interface: {}",
+                self.syspath()
+            )));
+        }
+
+        let old_name = self.sysname().to_owned();
+        validate_netif_name(old_name.as_str())?;
+        validate_netif_name(new_name)?;
+
+        if old_name == new_name {
+            return Ok(());
+        }
+
+        // SAFETY: all arguments are valid, and the return value is checked before use.
+        let sock = unsafe { libc::socket(libc::PF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+        if sock < 0 {
+            let errno = io::Error::last_os_error();
+            return Err(Error::os(
+                ErrorScope::Device,
+                format!("unable to create a socket: {errno}"),
+                errno,
+            )
+            .with_context(self.error_context("rename_netif")));
+        }
+        // SAFETY: `socket` returned a new file descriptor, owned by nothing else.
+        let sock = unsafe { OwnedFd::from_raw_fd(sock) };
+
+        // SAFETY: zeroed memory initializes `libc::ifreq` to a valid state.
+        let mut ifr: libc::ifreq = unsafe { mem::zeroed() };
+        copy_netif_name(&mut ifr.ifr_name, old_name.as_str());
+        // SAFETY: `ifru_newname` is the union field read by `SIOCSIFNAME`, and all fields are
+        // plain bytes.
+        copy_netif_name(unsafe { &mut ifr.ifr_ifru.ifru_newname }, new_name);
+
+        // SAFETY: all arguments are valid, pointers reference valid memory, and `SIOCSIFNAME` is
+        // a valid ioctl
+        if unsafe { libc::ioctl(sock.as_raw_fd(), libc::SIOCSIFNAME, &mut ifr) } != 0 {
+            let errno = io::Error::last_os_error();
+            let err_msg =
+                format!("unable to rename network interface {old_name} to {new_name}: {errno}");
+            log::error!("{err_msg}");
+            return Err(Error::os(ErrorScope::Device, err_msg, errno)
+                .with_context(self.error_context("rename_netif")));
+        }
+
+        log::debug!("renamed network interface {old_name} to {new_name}");
+        self.set_netif_name(new_name);
+
+        Ok(())
+    }
+
+    // Updates the paths, and properties, after renaming the network interface.
+    fn set_netif_name(&mut self, new_name: &str) {
+        let old_name = self.sysname().to_owned();
+        let devpath_old = self.devpath().to_owned();
+        let devpath = devpath_old
+            .rsplit_once('/')
+            .map_or(format!("/{new_name}"), |(parent, _)| {
+                format!("{parent}/{new_name}")
+            });
+
+        self.set_syspath(format!("{}{devpath}", self.udev.get_sys_path()));
+        self.set_devpath(devpath.as_str());
+        self.set_devpath_old(devpath_old.as_str());
+        // cached values were read from the old syspath
+        self.clear_sysattr_cache();
+
+        self.add_property("DEVPATH", devpath.as_str());
+        self.add_property("DEVPATH_OLD", devpath_old.as_str());
+        self.add_property("INTERFACE", new_name);
+        self.add_property("INTERFACE_OLD", old_name.as_str());
+    }
+}
+
+// Validates a network interface name, like the kernel `dev_valid_name`.
+fn validate_netif_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || name.len() > IFNAME_MAX_LEN
+        || name == "."
+        || name == ".."
+        || name
+            .chars()
+            .any(|c| c == '/' || c == ':' || c.is_whitespace())
+    {
+        Err(Error::UdevDevice(format!(
+            "invalid network interface name: {name}"
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+// Copies a validated interface name into a zeroed, nul-terminated `ifreq` name buffer.
+fn copy_netif_name(dst: &mut [libc::c_char; libc::IFNAMSIZ], name: &str) {
+    for (dst, &src) in dst.iter_mut().zip(name.as_bytes()) {
+        *dst = src as libc::c_char;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FixtureDevice, SysfsFixture};
+    use crate::Result;
+
+    // Ethernet, and wireless interfaces, and a non-network device.
+    fn fixture() -> Result<SysfsFixture> {
+        SysfsFixture::new()?.with_devices([
+            FixtureDevice::new("/devices/virtual/net/eth0", "net")
+                .with_ifindex(2)
+                .with_sysattr("address", "02:42:ac:11:00:02\n")
+                .with_sysattr("operstate", "up\n"),
+            FixtureDevice::new("/devices/virtual/net/wlan0", "net")
+                .with_sysattr("ifindex", "3\n")
+                .with_sysattr("address", "20:00:00:00:00:00:00:00:00:00\n")
+                .with_sysattr("operstate", "dormant\n")
+                .with_sysattr("phy80211/name", "phy0\n"),
+            FixtureDevice::new("/devices/virtual/mem/null", "mem"),
+        ])
+    }
+
+    #[test]
+    fn test_net_helpers() -> Result<()> {
+        let fixture = fixture()?;
+
+        let eth = fixture.device("/devices/virtual/net/eth0")?;
+        assert!(eth.is_net());
+        assert_eq!(eth.net_ifindex(), Some(2));
+        assert_eq!(
+            eth.mac_address(),
+            Some([0x02, 0x42, 0xac, 0x11, 0x00, 0x02])
+        );
+        assert_eq!(eth.operstate(), Some(OperState::Up));
+        assert!(!eth.is_wireless());
+
+        let wlan = fixture.device("/devices/virtual/net/wlan0")?;
+        assert_eq!(wlan.net_ifindex(), Some(3));
+        assert_eq!(wlan.mac_address(), None);
+        assert_eq!(wlan.operstate(), Some(OperState::Dormant));
+        assert!(wlan.is_wireless());
+
+        let mut null = fixture.device("/devices/virtual/mem/null")?;
+        assert!(!null.is_net());
+        assert_eq!(null.net_ifindex(), None);
+        assert_eq!(null.operstate(), None);
+        assert!(null.rename_netif("null0").is_err());
+
+        let mut eth = fixture.device("/devices/virtual/net/eth0")?;
+        for name in [
+            "",
+            ".",
+            "..",
+            "eth/0",
+            "eth:0",
+            "eth 0",
+            "enx0242ac110002ab",
+        ] {
+            assert!(eth.rename_netif(name).is_err(), "{name}");
+        }
+        // already named
+        eth.rename_netif("eth0")?;
+        assert!(eth.devpath_old().is_empty());
+
+        eth.set_netif_name("enp3s0");
+        assert_eq!(eth.sysname(), "enp3s0");
+        assert_eq!(eth.devpath(), "/devices/virtual/net/enp3s0");
+        assert!(eth.syspath().ends_with("/devices/virtual/net/enp3s0"));
+        assert_eq!(eth.devpath_old(), "/devices/virtual/net/eth0");
+        assert_eq!(
+            eth.get_property_value("DEVPATH_OLD"),
+            Some("/devices/virtual/net/eth0")
+        );
+        assert_eq!(eth.get_property_value("INTERFACE"), Some("enp3s0"));
+        assert_eq!(eth.get_property_value("INTERFACE_OLD"), Some("eth0"));
+
+        for state in [
+            "notpresent",
+            "down",
+            "lowerlayerdown",
+            "testing",
+            "dormant",
+            "up",
+        ] {
+            assert_eq!(OperState::from(state).as_str(), state);
+        }
+        assert_eq!(OperState::from("bogus"), OperState::Unknown);
+
+        Ok(())
+    }
+}
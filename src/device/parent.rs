@@ -0,0 +1,162 @@
+use std::sync::Arc;
+
+use crate::{Error, Result, SysPath, Udev};
+
+use super::UdevDevice;
+
+/// Iterator over the parent devices of an [UdevDevice], from the closest to the root.
+///
+/// Created by [UdevDevice::ancestors].
+///
+/// Parent devices are created lazily, when the iterator advances. Directories in the syspath
+/// that are not devices are skipped.
+#[derive(Clone, Debug)]
+pub struct Ancestors {
+    udev: Arc<Udev>,
+    next: Option<SysPath>,
+}
+
+impl Iterator for Ancestors {
+    type Item = UdevDevice;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(path) = self.next.take() {
+            self.next = path.parent();
+
+            if let Ok(dev) = UdevDevice::new_from_syspath(Arc::clone(&self.udev), &path) {
+                return Some(dev);
+            }
+        }
+
+        None
+    }
+}
+
+impl UdevDevice {
+    /// Gets an [Iterator] over the parent devices, walking up the syspath.
+    ///
+    /// Unlike [get_parent](Self::get_parent), only needs a shared reference, and does not cache
+    /// the parent devices:
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{Udev, UdevDevice};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let udev = Arc::new(Udev::new());
+    /// let device = UdevDevice::new_from_subsystem_sysname(udev, "block", "sda1")?;
+    ///
+    /// for parent in device.ancestors() {
+    ///     println!("{}", parent.syspath());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn ancestors(&self) -> Ancestors {
+        Ancestors {
+            udev: self.udev_cloned(),
+            next: SysPath::new_in(self.udev().get_sys_path(), self.syspath())
+                .ok()
+                .and_then(|path| path.parent()),
+        }
+    }
+
+    /// Gets the next parent [UdevDevice], without caching it.
+    ///
+    /// Like `libudev`, `/sys/devices` itself is never a parent device.
+    ///
+    /// Returns: `Ok(UdevDevice)` on success, `Err(Error)` if no parent exists, e.g. for top-level
+    /// devices like `/sys/devices/virtual`.
+    pub fn parent(&self) -> Result<Self> {
+        self.ancestors().next().ok_or(Error::UdevDevice(format!(
+            "no parent device found for syspath: {}",
+            self.syspath()
+        )))
+    }
+
+    /// Gets the next parent [UdevDevice] with a matching `subsystem` and `devtype`, without
+    /// caching it.
+    ///
+    /// An empty `devtype` matches any devtype.
+    ///
+    /// Returns: `Ok(UdevDevice)` on success, `Err(Error)` if no matching parent exists.
+    pub fn parent_with_subsystem_devtype(&self, subsystem: &str, devtype: &str) -> Result<Self> {
+        self.ancestors()
+            .find_map(|parent| {
+                let matches = parent.get_subsystem() == subsystem
+                    && (devtype.is_empty() || parent.get_devtype() == devtype);
+                matches.then_some(parent)
+            })
+            .ok_or(Error::UdevDevice(format!(
+                "no parent device found with subsystem: {subsystem}, devtype: {devtype}"
+            )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::SysfsFixture;
+    use crate::SYS_MOUNT_POINT;
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn test_ancestors() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+
+        // the sandbox may not expose any network interfaces, `test_ancestors_boundary` always
+        // walks a temporary sys tree
+        let net = fs::read_dir(format!("{SYS_MOUNT_POINT}/class/net"))
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.ok()?.path().to_str().map(String::from));
+
+        for path in net {
+            let dev = UdevDevice::new_from_syspath(Arc::clone(&udev), &SysPath::new(path)?)?;
+
+            let mut child = dev.syspath().to_owned();
+            for parent in dev.ancestors() {
+                assert!(child.starts_with(&format!("{}/", parent.syspath())));
+                child = parent.syspath().into();
+            }
+
+            match dev.ancestors().next() {
+                Some(first) => assert_eq!(dev.parent()?, first),
+                None => assert!(dev.parent().is_err()),
+            }
+
+            assert!(dev.parent_with_subsystem_devtype("net", "").is_err());
+        }
+
+        let dev = UdevDevice::new(udev).with_syspath("not-in-sys");
+        assert_eq!(dev.ancestors().count(), 0);
+        assert!(dev.parent().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ancestors_boundary() -> Result<()> {
+        let fixture = SysfsFixture::new()?;
+        let sys = fixture.sys_path();
+        let dir = Path::new(sys);
+        let child = dir.join("devices").join("top").join("child");
+        fs::create_dir_all(&child)?;
+        fs::write(dir.join("devices").join("uevent"), "")?;
+        fs::write(dir.join("devices").join("top").join("uevent"), "")?;
+        fs::write(child.join("uevent"), "")?;
+
+        let syspath = SysPath::new_in(sys, format!("{sys}/devices/top/child"))?;
+        let dev = UdevDevice::new_from_syspath(fixture.udev(), &syspath)?;
+
+        // the walk stops below `/sys/devices`, even if it looks like a device
+        let parents: Vec<String> = dev.ancestors().map(|p| p.syspath().into()).collect();
+        assert_eq!(parents, [format!("{sys}/devices/top")]);
+
+        let top = dev.parent()?;
+        assert!(top.parent().is_err());
+        assert!(top.ancestors().next().is_none());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,318 @@
+use std::{fs, path};
+
+use super::UdevDevice;
+use crate::{Error, Result, Udev};
+
+/// Name of the default seat, of devices without an `ID_SEAT` property.
+pub const DEFAULT_SEAT: &str = "seat0";
+
+/// Tag of devices the active session user gets access to, e.g. by `systemd-logind` ACLs.
+pub const UACCESS_TAG: &str = "uaccess";
+/// Tag of devices assigned to a seat.
+pub const SEAT_TAG: &str = "seat";
+/// Tag of devices that create a seat when plugged in, e.g. graphics cards.
+pub const MASTER_OF_SEAT_TAG: &str = "master-of-seat";
+
+/// Gets whether the seat name is valid, like `systemd-logind`: `seat`, followed by ASCII
+/// alphanumerics, `_`, or `-`.
+pub fn is_valid_seat_name(name: &str) -> bool {
+    name.len() <= 255
+        && name.strip_prefix("seat").is_some_and(|suffix| {
+            suffix
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+        })
+}
+
+impl UdevDevice {
+    /// Gets the `ID_SEAT` property, the seat the device is assigned to.
+    ///
+    /// Reads the `udev` database, if the device information is not loaded.
+    pub fn id_seat(&mut self) -> Option<&str> {
+        if !self.info_loaded {
+            self.read_db().ok();
+        }
+        self.get_property_value("ID_SEAT")
+            .filter(|seat| !seat.is_empty())
+    }
+
+    /// Gets the seat of the device, the `ID_SEAT` property, or [DEFAULT_SEAT].
+    pub fn seat(&mut self) -> &str {
+        self.id_seat().unwrap_or(DEFAULT_SEAT)
+    }
+
+    /// Gets whether the device currently has the [UACCESS_TAG], i.e. the user of the active
+    /// session on its seat gets access to the device node.
+    ///
+    /// Like `logind`, tags removed by a later event do not count, see
+    /// [has_current_tag](Self::has_current_tag).
+    pub fn has_uaccess(&mut self) -> bool {
+        self.has_current_tag(UACCESS_TAG)
+    }
+
+    /// Gets whether the device currently has the [SEAT_TAG], i.e. is assigned to a seat.
+    pub fn is_seat_device(&mut self) -> bool {
+        self.has_current_tag(SEAT_TAG)
+    }
+
+    /// Gets whether the device currently has the [MASTER_OF_SEAT_TAG], i.e. creates its seat.
+    pub fn is_master_of_seat(&mut self) -> bool {
+        self.has_current_tag(MASTER_OF_SEAT_TAG)
+    }
+}
+
+/// Represents the state of a seat, as written by `systemd-logind` to the `seats` directory.
+///
+/// The `seats` directory is `systemd/seats`, next to the `udev` run path of the [Udev]
+/// context, i.e. `/run/systemd/seats` by default, see [seats_path](Self::seats_path).
+#[repr(C)]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct UdevSeat {
+    name: String,
+    is_seat0: bool,
+    can_multi_session: bool,
+    can_tty: bool,
+    can_graphical: bool,
+    active_session: String,
+    active_uid: Option<u32>,
+    sessions: Vec<String>,
+    uids: Vec<u32>,
+}
+
+impl UdevSeat {
+    /// Creates a new [UdevSeat].
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        let name = name.into();
+
+        Self {
+            is_seat0: name == DEFAULT_SEAT,
+            name,
+            can_multi_session: false,
+            can_tty: false,
+            can_graphical: false,
+            active_session: String::new(),
+            active_uid: None,
+            sessions: Vec::new(),
+            uids: Vec::new(),
+        }
+    }
+
+    /// Gets the seat name, e.g. `seat0`.
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Gets whether this is the default seat.
+    pub const fn is_seat0(&self) -> bool {
+        self.is_seat0
+    }
+
+    /// Gets whether the seat supports multiple sessions.
+    pub const fn can_multi_session(&self) -> bool {
+        self.can_multi_session
+    }
+
+    /// Gets whether the seat has text terminals.
+    pub const fn can_tty(&self) -> bool {
+        self.can_tty
+    }
+
+    /// Gets whether the seat has a graphics device.
+    pub const fn can_graphical(&self) -> bool {
+        self.can_graphical
+    }
+
+    /// Gets the ID of the active session, if any.
+    pub fn active_session(&self) -> Option<&str> {
+        Some(self.active_session.as_str()).filter(|s| !s.is_empty())
+    }
+
+    /// Gets the user ID of the active session, if any.
+    pub const fn active_uid(&self) -> Option<u32> {
+        self.active_uid
+    }
+
+    /// Gets the IDs of the sessions on the seat.
+    pub fn sessions(&self) -> &[String] {
+        self.sessions.as_ref()
+    }
+
+    /// Gets the user IDs of the sessions on the seat.
+    pub fn uids(&self) -> &[u32] {
+        self.uids.as_ref()
+    }
+
+    /// Gets whether the device is assigned to this seat, see [UdevDevice::seat].
+    pub fn has_device(&self, device: &mut UdevDevice) -> bool {
+        device.seat() == self.name
+    }
+
+    /// Gets whether the user of the active session gets access to the device, i.e. the device
+    /// is assigned to this seat, and has the [UACCESS_TAG].
+    pub fn grants_access(&self, device: &mut UdevDevice, uid: u32) -> bool {
+        self.active_uid == Some(uid) && self.has_device(device) && device.has_uaccess()
+    }
+
+    /// Gets the path of the `seats` directory for the [Udev] context.
+    pub fn seats_path(udev: &Udev) -> path::PathBuf {
+        let run = path::Path::new(udev.get_run_path());
+
+        run.parent().unwrap_or(run).join("systemd").join("seats")
+    }
+
+    /// Parses the state file contents of the seat `name`.
+    ///
+    /// Returns: `Ok(UdevSeat)` on success, `Err(Error)` if the name is invalid.
+    pub fn parse(name: &str, contents: &str) -> Result<Self> {
+        if !is_valid_seat_name(name) {
+            return Err(Error::UdevDevice(format!("invalid seat name: {name}")));
+        }
+
+        let mut seat = Self::new(name);
+        let flag = |value: &str| value == "1" || value == "yes";
+
+        for (key, value) in contents
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| line.split_once('='))
+        {
+            match key {
+                "IS_SEAT0" => seat.is_seat0 = flag(value),
+                "CAN_MULTI_SESSION" => seat.can_multi_session = flag(value),
+                "CAN_TTY" => seat.can_tty = flag(value),
+                "CAN_GRAPHICAL" => seat.can_graphical = flag(value),
+                "ACTIVE" => seat.active_session = value.into(),
+                "ACTIVE_UID" => seat.active_uid = value.parse().ok(),
+                "SESSIONS" => {
+                    seat.sessions = value.split_whitespace().map(String::from).collect();
+                }
+                "UIDS" => {
+                    seat.uids = value
+                        .split_whitespace()
+                        .filter_map(|uid| uid.parse().ok())
+                        .collect();
+                }
+                _ => log::trace!("unknown seat key: {key}"),
+            }
+        }
+
+        Ok(seat)
+    }
+
+    /// Reads the state of the seat `name` from the `seats` directory.
+    ///
+    /// Returns: `Ok(UdevSeat)` on success, `Err(Error)` if the seat does not exist.
+    pub fn read(udev: &Udev, name: &str) -> Result<Self> {
+        if !is_valid_seat_name(name) {
+            return Err(Error::UdevDevice(format!("invalid seat name: {name}")));
+        }
+
+        let path = Self::seats_path(udev).join(name);
+        let contents = fs::read_to_string(&path).map_err(|err| {
+            Error::UdevDevice(format!("unable to read seat {}: {err}", path.display()))
+        })?;
+
+        Self::parse(name, contents.as_str())
+    }
+
+    /// Reads the state of all seats from the `seats` directory.
+    ///
+    /// Returns: the seats sorted by name, or an empty list if there is no `seats` directory,
+    /// e.g. without `systemd-logind`.
+    pub fn list(udev: &Udev) -> Vec<Self> {
+        let mut seats: Vec<Self> = fs::read_dir(Self::seats_path(udev))
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter(|name| is_valid_seat_name(name))
+            .filter_map(|name| {
+                Self::read(udev, name.as_str())
+                    .inspect_err(|err| log::debug!("{err}"))
+                    .ok()
+            })
+            .collect();
+
+        seats.sort_by(|a, b| a.name.cmp(&b.name));
+        seats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::SysfsFixture;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_seats() -> Result<()> {
+        assert!(is_valid_seat_name("seat0"));
+        assert!(is_valid_seat_name("seat-usb_1"));
+        assert!(!is_valid_seat_name("seat/0"));
+        assert!(!is_valid_seat_name("usb0"));
+
+        let fixture = SysfsFixture::new()?;
+        let seats = fixture.root().join("run/systemd/seats");
+        fs::create_dir_all(&seats)?;
+        fs::write(
+            seats.join("seat0"),
+            "# This is private data. Do not parse.\nIS_SEAT0=1\nCAN_MULTI_SESSION=1\nCAN_TTY=1\nCAN_GRAPHICAL=1\nACTIVE=2\nACTIVE_UID=1000\nSESSIONS=2 c1\nUIDS=1000 42\n",
+        )?;
+        fs::write(seats.join("seat1"), "CAN_GRAPHICAL=0\n")?;
+        fs::write(seats.join("invalid"), "IS_SEAT0=1\n")?;
+
+        let udev = Arc::new(Udev::new().with_run_path(fixture.run_path()));
+        assert_eq!(UdevSeat::seats_path(&udev), seats);
+
+        let list = UdevSeat::list(&udev);
+        assert_eq!(list.len(), 2);
+
+        let seat0 = &list[0];
+        assert_eq!(seat0.name(), "seat0");
+        assert!(seat0.is_seat0() && seat0.can_multi_session() && seat0.can_graphical());
+        assert_eq!(seat0.active_session(), Some("2"));
+        assert_eq!(seat0.active_uid(), Some(1000));
+        assert_eq!(seat0.sessions(), ["2", "c1"]);
+        assert_eq!(seat0.uids(), [1000, 42]);
+
+        let seat1 = &list[1];
+        assert!(!seat1.is_seat0() && !seat1.can_graphical());
+        assert_eq!(seat1.active_session(), None);
+
+        let mut mouse = UdevDevice::builder(Arc::clone(&udev))
+            .devpath("/devices/virtual/input/mouse0")
+            .subsystem("input")
+            .tag(SEAT_TAG)
+            .tag(UACCESS_TAG)
+            .build()?;
+        assert_eq!(mouse.id_seat(), None);
+        assert_eq!(mouse.seat(), DEFAULT_SEAT);
+        assert!(mouse.has_uaccess() && mouse.is_seat_device() && !mouse.is_master_of_seat());
+        assert!(seat0.has_device(&mut mouse));
+        assert!(seat0.grants_access(&mut mouse, 1000));
+        assert!(!seat0.grants_access(&mut mouse, 42));
+
+        // the `uaccess` tag was removed by a later event
+        let mut revoked = UdevDevice::builder(Arc::clone(&udev))
+            .devpath("/devices/virtual/input/mouse1")
+            .subsystem("input")
+            .tag(UACCESS_TAG)
+            .build()?;
+        revoked.add_current_tag(SEAT_TAG)?;
+        assert!(revoked.has_tag(UACCESS_TAG));
+        assert!(!revoked.has_uaccess() && revoked.is_seat_device());
+        assert!(!seat0.grants_access(&mut revoked, 1000));
+
+        let mut card = UdevDevice::builder(udev)
+            .devpath("/devices/virtual/drm/card1")
+            .subsystem("drm")
+            .property("ID_SEAT", "seat1")
+            .tag(MASTER_OF_SEAT_TAG)
+            .build()?;
+        assert_eq!(card.seat(), "seat1");
+        assert!(card.is_master_of_seat() && !card.has_uaccess());
+        assert!(seat1.has_device(&mut card));
+        assert!(!seat0.has_device(&mut card));
+
+        Ok(())
+    }
+}
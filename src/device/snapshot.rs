@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+
+use super::UdevDevice;
+
+impl UdevDevice {
+    /// Gets a snapshot of the device symlinks, e.g. `/dev/disk/by-label/root`.
+    ///
+    /// Reads the `udev` database, if the device information is not loaded, like
+    /// [get_devlinks_list_entry](Self::get_devlinks_list_entry).
+    ///
+    /// Returns: the symlinks in the `devlinks_list` order.
+    pub fn devlinks(&mut self) -> Vec<String> {
+        self.get_devlinks_list_entry();
+        self.devlinks_list()
+            .iter()
+            .map(|entry| entry.name().to_owned())
+            .collect()
+    }
+
+    /// Gets a snapshot of the device properties.
+    ///
+    /// Reads the `uevent` file, and the `udev` database, if the device information is not
+    /// loaded, like `udev_device_get_properties_list_entry`.
+    ///
+    /// Returns: the properties by name.
+    pub fn properties(&mut self) -> BTreeMap<String, String> {
+        if !self.info_loaded() {
+            // missing files only leave the properties empty
+            self.read_uevent_file().ok();
+            self.read_db().ok();
+        }
+
+        self.properties_list()
+            .iter()
+            .map(|entry| (entry.name().to_owned(), entry.value().to_owned()))
+            .collect()
+    }
+
+    /// Gets a snapshot of the tags ever set on the device, see [has_tag](Self::has_tag).
+    ///
+    /// Reads the `udev` database, if the device information is not loaded, like
+    /// [get_tags_list_entry](Self::get_tags_list_entry).
+    ///
+    /// Returns: the tags in the `tags_list` order.
+    pub fn tags(&mut self) -> Vec<String> {
+        self.get_tags_list_entry();
+        self.tags_list()
+            .iter()
+            .map(|entry| entry.name().to_owned())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{FixtureDevice, SysfsFixture};
+    use crate::Result;
+
+    // Loop disk with database properties, devlinks, and tags.
+    fn fixture() -> Result<SysfsFixture> {
+        SysfsFixture::new()?.with_devices([FixtureDevice::new(
+            "/devices/virtual/block/loop0",
+            "block",
+        )
+        .with_devnum(7, 0)
+        .with_devtype("disk")
+        .with_db_property("ID_FS_TYPE", "ext4")
+        .with_devlink("disk/by-label/root")
+        .with_devlink("disk/by-uuid/1234")
+        .with_tag("systemd")
+        .with_tag("seat")])
+    }
+
+    #[test]
+    fn test_snapshots() -> Result<()> {
+        let fixture = fixture()?;
+
+        let mut device = fixture.device("/devices/virtual/block/loop0")?;
+
+        let props = device.properties();
+        assert_eq!(props.get("ID_FS_TYPE").map(String::as_str), Some("ext4"));
+        assert_eq!(props.get("MAJOR").map(String::as_str), Some("7"));
+        assert_eq!(props.get("MINOR").map(String::as_str), Some("0"));
+
+        let devlinks = device.devlinks();
+        assert_eq!(devlinks.len(), 2);
+        assert!(devlinks.iter().all(|link| link.contains("/disk/by-")));
+
+        assert_eq!(device.tags(), ["systemd", "seat"]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+use std::fs;
+use std::io::Read;
+use std::os::unix::fs::OpenOptionsExt;
+
+use crate::metrics::{Metric, MetricsTimer};
+use crate::{Error, Result, Udev, UdevList};
+
+use super::{lazy, UdevDevice};
+
+impl UdevDevice {
+    /// Reads the values of all readable sys attributes of the device in one pass.
+    ///
+    /// Intended for inventory tools taking a snapshot of a device, instead of calling
+    /// [get_sysattr_value](Self::get_sysattr_value) for each entry of the
+    /// [sysattr_list](Self::sysattr_list).
+    ///
+    /// Attributes are skipped if they are:
+    ///
+    /// - directories, or symlinks other than the `driver`, `subsystem`, and `module` links
+    /// - not readable, or fail to read, e.g. write-only, or unsupported attributes
+    /// - larger than `max_size` bytes
+    /// - binary, i.e. values containing `NUL` bytes, or invalid UTF-8
+    ///
+    /// Values are stored as-is, including the trailing newline, and cached, so later calls to
+    /// [get_sysattr_value](Self::get_sysattr_value) return the snapshot value.
+    ///
+    /// Returns: `Ok(&UdevList)` of attribute names, and values on success, `Err(Error)` if the
+    /// device has no syspath, or the syspath cannot be read.
+    pub fn read_all_sysattrs(&mut self, max_size: usize) -> Result<&UdevList> {
+        if self.syspath().is_empty() {
+            return Err(Error::UdevDevice("device has no syspath".into()));
+        }
+
+        self.get_sysattr_list_read()?;
+
+        let syspath = self.syspath().to_owned();
+        let names: Vec<String> = self
+            .sysattr_list()
+            .iter()
+            .map(|entry| entry.name().to_owned())
+            .collect();
+
+        for name in names {
+            match read_sysattr_snapshot(syspath.as_str(), name.as_str(), max_size) {
+                Some(value) => {
+                    self.sysattr_value_list_mut()
+                        .add_entry(name.as_str(), value.as_str());
+                }
+                None => log::trace!("skipping sysattr {syspath}/{name}"),
+            }
+        }
+
+        Ok(self.sysattr_value_list())
+    }
+
+    /// Reads the sys attribute file value, bypassing the cache.
+    ///
+    /// Unlike [get_sysattr_value](Self::get_sysattr_value), the value is read from `sys` on every
+    /// call, and the cached value is left unchanged, e.g. to poll attributes like `operstate`.
+    ///
+    /// Returns: the content of a sys attribute file, or `None` if there is no sys attribute value.
+    pub fn get_sysattr_value_uncached(&self, sysattr: &str) -> Option<String> {
+        lazy::read_sysattr(self.syspath(), sysattr)
+    }
+
+    /// Re-reads the sys attribute file value, and replaces the cached value.
+    ///
+    /// Intended for monitoring tools, after a `change` event of the device, for attributes
+    /// changing at runtime, e.g. the `size` of a block device, or the `online` state of a CPU.
+    ///
+    /// Returns: the content of a sys attribute file, or `None` if there is no sys attribute value.
+    pub fn refresh_sysattr(&mut self, sysattr: &str) -> Option<String> {
+        self.sysattr_value_list.remove_entry(sysattr);
+        self.lazy.invalidate_sysattr(sysattr);
+        self.lazy.sysattr(self.syspath(), sysattr)
+    }
+
+    /// Clears the cached values of all sys attributes.
+    ///
+    /// Removes the values read by [get_sysattr_value](Self::get_sysattr_value), and
+    /// [read_all_sysattrs](Self::read_all_sysattrs), or explicitly set in the
+    /// [sysattr_value_list](Self::sysattr_value_list), so the next calls read the values from
+    /// `sys` again.
+    pub fn clear_sysattr_cache(&mut self) {
+        self.sysattr_value_list.clear();
+        self.lazy.clear_sysattrs();
+    }
+}
+
+// Reads a sys attribute value, if it is a text value of at most `max_size` bytes.
+fn read_sysattr_snapshot(syspath: &str, sysattr: &str, max_size: usize) -> Option<String> {
+    let path = format!("{syspath}/{sysattr}");
+    let metadata = fs::symlink_metadata(path.as_str()).ok()?;
+
+    if metadata.is_symlink() {
+        if matches!(sysattr, "driver" | "subsystem" | "module") {
+            Udev::get_sys_core_link_value(sysattr, syspath).ok()
+        } else {
+            None
+        }
+    } else if metadata.is_file() {
+        let timer = MetricsTimer::start(Metric::SysattrReadDuration);
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_CLOEXEC | libc::O_NOFOLLOW)
+            .open(path.as_str())
+            .ok()?;
+
+        // `sys` reports the page size for most attributes, so the size is only known after reading
+        let mut value = Vec::new();
+        file.take(max_size.saturating_add(1) as u64)
+            .read_to_end(&mut value)
+            .ok()?;
+        timer.stop();
+
+        if value.len() > max_size || value.contains(&0) {
+            None
+        } else {
+            String::from_utf8(value).ok()
+        }
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FixtureDevice, SysfsFixture};
+
+    // Disk with text attributes, an attribute over the size limit, and a binary attribute.
+    fn fixture() -> Result<SysfsFixture> {
+        SysfsFixture::new()?.with_devices([FixtureDevice::new(
+            "/devices/virtual/block/sdz",
+            "block",
+        )
+        .with_devtype("disk")
+        .with_driver("sd")
+        .with_sysattr("size", "1024\n")
+        .with_sysattr("model", "a very long model name\n")
+        .with_sysattr("descriptors", "\x12\x01\0\x02")])
+    }
+
+    #[test]
+    fn test_read_all_sysattrs() -> Result<()> {
+        let fixture = fixture()?;
+
+        let mut device = fixture.device("/devices/virtual/block/sdz")?;
+        let snapshot = device.read_all_sysattrs(16)?;
+
+        let value = |name: &str| snapshot.entry_by_name(name).map(|e| e.value().to_owned());
+        assert_eq!(value("size").as_deref(), Some("1024\n"));
+        assert_eq!(value("driver").as_deref(), Some("sd"));
+        assert_eq!(value("subsystem").as_deref(), Some("block"));
+        // larger than the maximum size
+        assert_eq!(value("model"), None);
+        // binary
+        assert_eq!(value("descriptors"), None);
+
+        assert_eq!(device.get_sysattr_value("size").as_deref(), Some("1024\n"));
+
+        // cached values are kept, until refreshed, or cleared
+        let size = format!("{}/size", device.syspath());
+        fs::write(size.as_str(), "2048\n")?;
+        assert_eq!(device.get_sysattr_value("size").as_deref(), Some("1024\n"));
+        assert_eq!(
+            device.get_sysattr_value_uncached("size").as_deref(),
+            Some("2048\n")
+        );
+        assert_eq!(device.get_sysattr_value("size").as_deref(), Some("1024\n"));
+
+        assert_eq!(device.refresh_sysattr("size").as_deref(), Some("2048\n"));
+        assert_eq!(device.get_sysattr_value("size").as_deref(), Some("2048\n"));
+
+        fs::write(size.as_str(), "4096\n")?;
+        device.clear_sysattr_cache();
+        assert!(device.sysattr_value_list().is_empty());
+        assert_eq!(device.get_sysattr_value("size").as_deref(), Some("4096\n"));
+
+        Ok(())
+    }
+}
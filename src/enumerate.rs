@@ -7,9 +7,17 @@
 
 use std::{fs, sync::Arc};
 
+use crate::device::is_valid_sysattr;
+use crate::metrics::{Metric, MetricsTimer};
+use crate::pattern::{MatchCache, SysattrMatch};
 use crate::util;
-use crate::UDEV_ROOT_RUN;
-use crate::{Error, Result, Udev, UdevDevice, UdevEntry, UdevEntryList, UdevList};
+use crate::{Error, Result, SysPath, Udev, UdevDevice, UdevEntry, UdevEntryList, UdevList};
+
+mod devnum;
+mod iter;
+
+pub use devnum::*;
+pub use iter::*;
 
 const LOG_PREFIX: &str = "udev enumerate:";
 
@@ -57,6 +65,8 @@ pub struct UdevEnumerate {
     sysname_match_list: UdevList,
     properties_match_list: UdevList,
     tags_match_list: UdevList,
+    devtype_match_list: UdevList,
+    devnum_match_list: Vec<DevnumMatch>,
     devices_list: UdevList,
     parent: Option<Arc<UdevDevice>>,
     devices: Vec<Syspath>,
@@ -64,18 +74,21 @@ pub struct UdevEnumerate {
     devices_max: usize,
     devices_uptodate: bool,
     match_is_initialized: bool,
+    match_cache: MatchCache,
 }
 
 impl UdevEnumerate {
     /// Creates a new [UdevEnumerate].
     pub fn new(udev: Arc<Udev>) -> Self {
-        let sysattr_match_list = UdevList::new(Arc::clone(&udev));
-        let sysattr_nomatch_list = UdevList::new(Arc::clone(&udev));
+        // several values of one attribute all have to match, e.g. a numeric range
+        let sysattr_match_list = UdevList::new(Arc::clone(&udev)).with_unique(false);
+        let sysattr_nomatch_list = UdevList::new(Arc::clone(&udev)).with_unique(false);
         let subsystem_match_list = UdevList::new(Arc::clone(&udev));
         let subsystem_nomatch_list = UdevList::new(Arc::clone(&udev));
         let sysname_match_list = UdevList::new(Arc::clone(&udev));
         let properties_match_list = UdevList::new(Arc::clone(&udev));
         let tags_match_list = UdevList::new(Arc::clone(&udev));
+        let devtype_match_list = UdevList::new(Arc::clone(&udev));
         let devices_list = UdevList::new(Arc::clone(&udev));
 
         Self {
@@ -87,6 +100,8 @@ impl UdevEnumerate {
             sysname_match_list,
             properties_match_list,
             tags_match_list,
+            devtype_match_list,
+            devnum_match_list: Vec::new(),
             devices_list,
             parent: None,
             devices: Vec::new(),
@@ -94,6 +109,7 @@ impl UdevEnumerate {
             devices_max: 0,
             devices_uptodate: false,
             match_is_initialized: false,
+            match_cache: MatchCache::default(),
         }
     }
 
@@ -115,6 +131,11 @@ impl UdevEnumerate {
     /// Sets the sysattr match list [UdevList].
     pub fn set_sysattr_match_list<L: Into<UdevEntryList>>(&mut self, list: L) {
         self.sysattr_match_list.set_list(list);
+
+        for entry in self.sysattr_match_list.iter() {
+            // invalid comparisons never match
+            self.match_cache.add_sysattr(entry.value()).ok();
+        }
     }
 
     /// Builder function that sets the sysattr match list [UdevList].
@@ -131,13 +152,40 @@ impl UdevEnumerate {
     /// Match only devices with a given /sys device attribute.
     /// ```
     ///
-    /// Returns `Ok(UdevEntry)` on success, `Err(Error)` otherwise.
-    pub fn add_match_sysattr(&mut self, sysattr: &str) -> Result<&UdevEntry> {
+    /// The optional `value` is a glob pattern, e.g. `1*`, matched without the trailing newline
+    /// of the attribute. Devices with the attribute match any value, if `None`, or empty.
+    ///
+    /// As an extension, the numeric comparisons `>=N`, and `<=N` match integer attributes, e.g.
+    /// `queue/rotational`, or `size`. Devices have to match every value added for an attribute,
+    /// so a `>=`, and a `<=` comparison match a range:
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{Udev, UdevEnumerate};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let mut enumerate = UdevEnumerate::new(Arc::new(Udev::new()));
+    /// enumerate.add_match_subsystem("block")?;
+    /// enumerate.add_match_sysattr("queue/rotational", Some("0"))?;
+    /// enumerate.add_match_sysattr("size", Some(">=2097152"))?;
+    /// enumerate.add_match_sysattr("size", Some("<=4194304"))?;
+    /// enumerate.scan_devices()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns `Ok(UdevEntry)` on success, `Err(Error)` if the sysattr is empty, leaves the
+    /// device directory, e.g. `../uevent`, or the value is an invalid numeric comparison.
+    pub fn add_match_sysattr(&mut self, sysattr: &str, value: Option<&str>) -> Result<&UdevEntry> {
         if sysattr.is_empty() {
             Err(Error::UdevEnumerate("sysattr is null".into()))
+        } else if !is_valid_sysattr(sysattr) {
+            Err(Error::UdevEnumerate(format!("invalid sysattr: {sysattr}")))
         } else {
+            let value = value.unwrap_or_default();
+            self.match_cache.add_sysattr(value)?;
+
             self.sysattr_match_list
-                .add_entry(sysattr, "")
+                .add_entry(sysattr, value)
                 .ok_or(Error::UdevEnumerate(
                     "unable to add match sysattr entry".into(),
                 ))
@@ -157,6 +205,11 @@ impl UdevEnumerate {
     /// Sets the sysattr nomatch list [UdevList].
     pub fn set_sysattr_nomatch_list<L: Into<UdevEntryList>>(&mut self, list: L) {
         self.sysattr_nomatch_list.set_list(list);
+
+        for entry in self.sysattr_nomatch_list.iter() {
+            // invalid comparisons never match
+            self.match_cache.add_sysattr(entry.value()).ok();
+        }
     }
 
     /// Builder function that sets the sysattr nomatch list [UdevList].
@@ -173,13 +226,25 @@ impl UdevEnumerate {
     /// Match only devices without a given /sys device attribute.
     /// ```
     ///
-    /// Returns `Ok(UdevEntry)` on success, `Err(Error)` otherwise.
-    pub fn add_nomatch_sysattr(&mut self, sysattr: &str) -> Result<&UdevEntry> {
+    /// The optional `value` is matched like [add_match_sysattr](Self::add_match_sysattr).
+    ///
+    /// Returns `Ok(UdevEntry)` on success, `Err(Error)` if the sysattr is empty, leaves the
+    /// device directory, e.g. `../uevent`, or the value is an invalid numeric comparison.
+    pub fn add_nomatch_sysattr(
+        &mut self,
+        sysattr: &str,
+        value: Option<&str>,
+    ) -> Result<&UdevEntry> {
         if sysattr.is_empty() {
             Err(Error::UdevEnumerate("sysattr is null".into()))
+        } else if !is_valid_sysattr(sysattr) {
+            Err(Error::UdevEnumerate(format!("invalid sysattr: {sysattr}")))
         } else {
+            let value = value.unwrap_or_default();
+            self.match_cache.add_sysattr(value)?;
+
             self.sysattr_nomatch_list
-                .add_entry(sysattr, "")
+                .add_entry(sysattr, value)
                 .ok_or(Error::UdevEnumerate(
                     "unable to add no-match sysattr entry".into(),
                 ))
@@ -199,6 +264,10 @@ impl UdevEnumerate {
     /// Sets the subsystem match list [UdevList].
     pub fn set_subsystem_match_list<L: Into<UdevEntryList>>(&mut self, list: L) {
         self.subsystem_match_list.set_list(list);
+
+        for entry in self.subsystem_match_list.iter() {
+            self.match_cache.add_glob(entry.name());
+        }
     }
 
     /// Builder function that sets the subsystem match list [UdevList].
@@ -215,11 +284,15 @@ impl UdevEnumerate {
     /// Match only devices belonging to a certain kernel subsystem.
     /// ```
     ///
+    /// The subsystem may be a `fnmatch` style pattern, e.g. `tty*`.
+    ///
     /// Returns `Ok(UdevEntry)` on success, `Err(Error)` otherwise.
     pub fn add_match_subsystem(&mut self, subsystem: &str) -> Result<&UdevEntry> {
         if subsystem.is_empty() {
             Err(Error::UdevEnumerate("subsystem is null".into()))
         } else {
+            self.match_cache.add_glob(subsystem);
+
             self.subsystem_match_list
                 .add_entry(subsystem, "")
                 .ok_or(Error::UdevEnumerate(
@@ -241,6 +314,10 @@ impl UdevEnumerate {
     /// Sets the subsystem nomatch list [UdevList].
     pub fn set_subsystem_nomatch_list<L: Into<UdevEntryList>>(&mut self, list: L) {
         self.subsystem_nomatch_list.set_list(list);
+
+        for entry in self.subsystem_nomatch_list.iter() {
+            self.match_cache.add_glob(entry.name());
+        }
     }
 
     /// Builder function that sets the subsystem nomatch list [UdevList].
@@ -257,11 +334,15 @@ impl UdevEnumerate {
     /// Match only devices not belonging to a certain kernel subsystem.
     /// ```
     ///
+    /// The subsystem may be a `fnmatch` style pattern, e.g. `tty*`.
+    ///
     /// Returns `Ok(UdevEntry)` on success, `Err(Error)` otherwise.
     pub fn add_nomatch_subsystem(&mut self, subsystem: &str) -> Result<&UdevEntry> {
         if subsystem.is_empty() {
             Err(Error::UdevEnumerate("subsystem is null".into()))
         } else {
+            self.match_cache.add_glob(subsystem);
+
             self.subsystem_nomatch_list
                 .add_entry(subsystem, "")
                 .ok_or(Error::UdevEnumerate(
@@ -283,6 +364,10 @@ impl UdevEnumerate {
     /// Sets the sysname match list [UdevList].
     pub fn set_sysname_match_list<L: Into<UdevEntryList>>(&mut self, list: L) {
         self.sysname_match_list.set_list(list);
+
+        for entry in self.sysname_match_list.iter() {
+            self.match_cache.add_glob(entry.name());
+        }
     }
 
     /// Builder function that sets the sysname match list [UdevList].
@@ -299,11 +384,15 @@ impl UdevEnumerate {
     /// Match only devices with a given /sys device name.
     /// ```
     ///
+    /// The sysname may be a `fnmatch` style pattern, e.g. `sd*`, or `tty[0-9]*`.
+    ///
     /// Returns `Ok(UdevEntry)` on success, `Err(Error)` otherwise.
     pub fn add_match_sysname(&mut self, sysname: &str) -> Result<&UdevEntry> {
         if sysname.is_empty() {
             Err(Error::UdevEnumerate("sysname is null".into()))
         } else {
+            self.match_cache.add_glob(sysname);
+
             self.sysname_match_list
                 .add_entry(sysname, "")
                 .ok_or(Error::UdevEnumerate(
@@ -394,6 +483,45 @@ impl UdevEnumerate {
         }
     }
 
+    /// Gets a reference to the devtype match list [UdevList].
+    pub const fn devtype_match_list(&self) -> &UdevList {
+        &self.devtype_match_list
+    }
+
+    /// Gets a mutable reference to the devtype match list [UdevList].
+    pub fn devtype_match_list_mut(&mut self) -> &mut UdevList {
+        &mut self.devtype_match_list
+    }
+
+    /// Sets the devtype match list [UdevList].
+    pub fn set_devtype_match_list<L: Into<UdevEntryList>>(&mut self, list: L) {
+        self.devtype_match_list.set_list(list);
+    }
+
+    /// Builder function that sets the devtype match list [UdevList].
+    pub fn with_devtype_match_list<L: Into<UdevEntryList>>(mut self, list: L) -> Self {
+        self.set_devtype_match_list(list);
+        self
+    }
+
+    /// Adds an entry to the match devtype [UdevEntry] list.
+    ///
+    /// Match only devices with a certain devtype, e.g. `disk`, or `partition` block devices.
+    /// Devices matching any of the devtypes are included.
+    ///
+    /// Returns `Ok(UdevEntry)` on success, `Err(Error)` otherwise.
+    pub fn add_match_devtype(&mut self, devtype: &str) -> Result<&UdevEntry> {
+        if devtype.is_empty() {
+            Err(Error::UdevEnumerate("devtype is null".into()))
+        } else {
+            self.devtype_match_list
+                .add_entry(devtype, "")
+                .ok_or(Error::UdevEnumerate(
+                    "unable to add match devtype entry".into(),
+                ))
+        }
+    }
+
     /// Gets a reference to the devices list [UdevList].
     pub const fn devices_list(&self) -> &UdevList {
         &self.devices_list
@@ -540,7 +668,8 @@ impl UdevEnumerate {
         if syspath.is_empty() {
             Err(Error::UdevEnumerate("empty syspath".into()))
         } else {
-            let dev = UdevDevice::new_from_syspath(Arc::clone(&self.udev), syspath)?;
+            let syspath = SysPath::new_in(self.udev.get_sys_path(), syspath)?;
+            let dev = UdevDevice::new_from_syspath(Arc::clone(&self.udev), &syspath)?;
             self.syspath_add(dev.syspath())
         }
     }
@@ -559,6 +688,30 @@ impl UdevEnumerate {
         Ok(())
     }
 
+    /// Triggers a synthetic kernel event for every enumerated device, like `udevadm trigger`.
+    ///
+    /// Uses the devices found by [scan_devices](Self::scan_devices), or added with
+    /// [add_syspath](Self::add_syspath). A failure for one device does not stop the others.
+    ///
+    /// Returns: the syspath, and the trigger result, of every enumerated device.
+    pub fn trigger_all(&self, action: &str) -> Vec<(String, Result<()>)> {
+        self.devices
+            .iter()
+            .map(|dev| {
+                let syspath = dev.syspath().to_owned();
+                let res = UdevDevice::new(Arc::clone(&self.udev))
+                    .with_syspath(syspath.as_str())
+                    .trigger(action);
+
+                if let Err(err) = res.as_ref() {
+                    log::debug!("unable to trigger {action} for {syspath}: {err}");
+                }
+
+                (syspath, res)
+            })
+            .collect()
+    }
+
     /// Scan `/sys` for devices which match the given filters.
     ///
     /// From the `libudev` documentation:
@@ -570,6 +723,8 @@ impl UdevEnumerate {
     ///
     /// Returns: `Ok(())` on success, `Err(Error)` otherwise.
     pub fn scan_devices(&mut self) -> Result<()> {
+        let _timer = MetricsTimer::start(Metric::EnumerateDuration);
+
         if self.tags_match_list.entry().is_some() {
             self.scan_devices_tags()
         } else if self.parent.is_some() {
@@ -580,53 +735,88 @@ impl UdevEnumerate {
     }
 
     fn scan_devices_tags(&mut self) -> Result<()> {
-        // scan only tagged devices.
-        // use tags reverse-index, instead of searching all deivces in /sys
-        let mut add_syspaths: Vec<String> = Vec::new();
-
-        for list_entry in self.tags_match_list.iter() {
-            let tag_name = list_entry.name();
-            let path = format!("{UDEV_ROOT_RUN}/udev/tags/{tag_name}");
-
-            for dir_entry in fs::read_dir(path.as_str())
-                .map_err(|err| Error::UdevEnumerate(format!("unable to open tags path: {err}")))?
-                .filter(|e| e.is_ok())
-            {
-                let d_name = dir_entry?
-                    .file_name()
-                    .into_string()
-                    .unwrap_or(String::new());
-                if d_name.is_empty() {
-                    log::trace!("{LOG_PREFIX} empty/invalid entry");
-                } else if d_name.starts_with('.') {
-                    log::trace!("{LOG_PREFIX} private entry");
-                } else {
-                    let mut dev =
-                        UdevDevice::new_from_device_id(Arc::clone(&self.udev), d_name.as_str())?;
-                    let dev_syspath = dev.syspath().to_owned();
-
-                    if !self.match_subsystem(dev.subsystem()) {
-                        log::trace!("{LOG_PREFIX} no subsystem match");
-                    } else if !self.match_sysname(dev.sysname()) {
-                        log::trace!("{LOG_PREFIX} no sysname match");
-                    } else if !self.match_parent(&dev) {
-                        log::trace!("{LOG_PREFIX} no parent match");
-                    } else if !self.match_property(&dev) {
-                        log::trace!("{LOG_PREFIX} no property match");
-                    } else if !self.match_sysattr(&mut dev) {
-                        log::trace!("{LOG_PREFIX} no sys attribute match");
-                    } else {
-                        add_syspaths.push(dev_syspath);
-                    }
-                }
+        // stream the matches into the device list, instead of collecting the syspaths first
+        let mut devices = std::mem::take(&mut self.devices);
+        let len = devices.len();
+
+        let res = self.scan_iter().try_for_each(|dev| {
+            devices.push(Syspath::new().with_syspath(dev?.syspath()));
+            Ok(())
+        });
+
+        let added = devices.len().saturating_sub(len);
+        self.devices = devices;
+
+        if added > 0 {
+            self.devices_cur = self.devices_cur.saturating_add(added);
+            if self.devices_cur > self.devices_max {
+                self.devices_max = self.devices_cur.next_multiple_of(1024);
             }
+            self.devices_uptodate = false;
         }
 
-        for path in add_syspaths.iter() {
-            self.syspath_add(path)?;
+        res
+    }
+
+    // Filters a device found through the tags reverse-index.
+    fn match_tagged_device(&self, dev: &mut UdevDevice) -> bool {
+        if !self.match_subsystem(dev.get_subsystem()) {
+            log::trace!("{LOG_PREFIX} no subsystem match");
+        } else if !self.match_sysname(dev.sysname()) {
+            log::trace!("{LOG_PREFIX} no sysname match");
+        } else if !self.match_devtype(dev) {
+            log::trace!("{LOG_PREFIX} no devtype match");
+        } else if !self.match_devnum(dev) {
+            log::trace!("{LOG_PREFIX} no devnum match");
+        } else if !self.match_parent(dev) {
+            log::trace!("{LOG_PREFIX} no parent match");
+        } else if !self.match_property(dev) {
+            log::trace!("{LOG_PREFIX} no property match");
+        } else if !self.match_sysattr(dev) {
+            log::trace!("{LOG_PREFIX} no sys attribute match");
+        } else {
+            return true;
         }
 
-        Ok(())
+        false
+    }
+
+    // Filters a device found in a `/sys` subsystem directory, the sysname is already matched.
+    fn match_scanned_device(&self, dev: &mut UdevDevice) -> bool {
+        if self.match_is_initialized
+            && !dev.get_is_initialized()
+            && (util::major(dev.devnum()) > 0 || dev.get_ifindex() > 0)
+        {
+            // From `libudev` documentation:
+            //
+            // ```
+            // All devices with a device node or network interfaces
+            // possibly need udev to adjust the device node permission
+            // or context, or rename the interface before it can be
+            // reliably used from other processes.
+            //
+            // For now, we can only check these types of devices, we
+            // might not store a database, and have no way to find out
+            // for all other types of devices.
+            // ```
+            log::trace!("{LOG_PREFIX} device not initialized");
+        } else if !self.match_parent(dev) {
+            log::trace!("{LOG_PREFIX} no parent match");
+        } else if !self.match_devtype(dev) {
+            log::trace!("{LOG_PREFIX} no devtype match");
+        } else if !self.match_devnum(dev) {
+            log::trace!("{LOG_PREFIX} no devnum match");
+        } else if !self.match_tag(dev) {
+            log::trace!("{LOG_PREFIX} no tag match");
+        } else if !self.match_property(dev) {
+            log::trace!("{LOG_PREFIX} no property match");
+        } else if !self.match_sysattr(dev) {
+            log::trace!("{LOG_PREFIX} no /sys attribute match");
+        } else {
+            return true;
+        }
+
+        false
     }
 
     fn match_subsystem(&self, subsystem: &str) -> bool {
@@ -634,18 +824,35 @@ impl UdevEnumerate {
             && !self
                 .subsystem_nomatch_list
                 .iter()
-                .any(|f| f.name() == subsystem)
+                .any(|f| self.match_glob(f.name(), subsystem))
             && (self.subsystem_match_list.is_empty()
                 || self
                     .subsystem_match_list
                     .iter()
-                    .any(|f| f.name() == subsystem))
+                    .any(|f| self.match_glob(f.name(), subsystem)))
     }
 
     fn match_sysname(&self, sysname: &str) -> bool {
         !sysname.is_empty()
             && (self.sysname_match_list.is_empty()
-                || self.sysname_match_list.iter().any(|f| f.name() == sysname))
+                || self
+                    .sysname_match_list
+                    .iter()
+                    .any(|f| self.match_glob(f.name(), sysname)))
+    }
+
+    // Matches the value against a `fnmatch` style pattern, like `libudev`, compiled when the
+    // match was added.
+    fn match_glob(&self, pattern: &str, value: &str) -> bool {
+        self.match_cache.glob(pattern).matches(value)
+    }
+
+    fn match_devtype(&self, dev: &UdevDevice) -> bool {
+        self.devtype_match_list.is_empty()
+            || self
+                .devtype_match_list
+                .iter()
+                .any(|f| f.name() == dev.get_devtype())
     }
 
     fn match_parent(&self, dev: &UdevDevice) -> bool {
@@ -705,20 +912,15 @@ impl UdevEnumerate {
     }
 
     fn match_sysattr(&self, dev: &mut UdevDevice) -> bool {
+        // skip list, then include list (no matches always match)
         !self
             .sysattr_nomatch_list
             .iter()
-            .any(|f| dev.match_sysattr_value(f.name(), f.value()))
+            .any(|f| dev.match_sysattr_value(f.name(), &self.match_cache.sysattr(f.value())))
             && self
                 .sysattr_match_list
                 .iter()
-                .filter(|f| !dev.match_sysattr_value(f.name(), f.value()))
-                .count()
-                == 0
-            && self
-                .sysattr_match_list
-                .iter()
-                .any(|f| dev.match_sysattr_value(f.name(), f.value()))
+                .all(|f| dev.match_sysattr_value(f.name(), &self.match_cache.sysattr(f.value())))
     }
 
     fn scan_devices_children(&mut self) -> Result<()> {
@@ -726,7 +928,13 @@ impl UdevEnumerate {
     }
 
     fn scan_devices_all(&mut self) -> Result<()> {
-        Err(Error::UdevEnumerate("unimplemented".into()))
+        if fs::metadata(format!("{}/subsystem", self.udev.get_sys_path())).is_ok() {
+            // we have `/subsystem/`, forget all the old stuff
+            self.scan_dir("subsystem", "devices", "")
+        } else {
+            self.scan_dir("bus", "devices", "")?;
+            self.scan_dir("class", "", "")
+        }
     }
 
     /// Scans `/sys` for all kernel subsystems.
@@ -739,12 +947,14 @@ impl UdevEnumerate {
     ///
     /// Returns: `Ok(())` on success, `Err(Error)` otherwise.
     pub fn scan_subsystems(&mut self) -> Result<()> {
+        let _timer = MetricsTimer::start(Metric::EnumerateDuration);
+
         // all kernel modules
         if self.match_subsystem("module") {
             self.scan_dir_and_add_devices("module", "", "")?;
         }
 
-        let subsysdir = if fs::metadata("/sys/subsystem").is_ok() {
+        let subsysdir = if fs::metadata(format!("{}/subsystem", self.udev.get_sys_path())).is_ok() {
             "subsystem"
         } else {
             "bus"
@@ -769,25 +979,36 @@ impl UdevEnumerate {
         subdir1: &str,
         subdir2: &str,
     ) -> Result<()> {
+        let sys = self.udev.get_sys_path();
         let path = if !subdir1.is_empty() && !subdir2.is_empty() {
-            format!("/sys/{basedir}/{subdir1}/{subdir2}")
+            format!("{sys}/{basedir}/{subdir1}/{subdir2}")
         } else if !subdir1.is_empty() {
-            format!("/sys/{basedir}/{subdir1}")
+            format!("{sys}/{basedir}/{subdir1}")
         } else if !subdir2.is_empty() {
-            format!("/sys/{basedir}/{subdir2}")
+            format!("{sys}/{basedir}/{subdir2}")
         } else {
-            format!("/sys/{basedir}")
+            format!("{sys}/{basedir}")
         };
 
-        let mut add_syspaths: Vec<String> = Vec::new();
+        for syspath in self.scan_dir_syspaths(path.as_str())?.iter() {
+            self.syspath_add(syspath)?;
+        }
 
-        for dir_entry in fs::read_dir(path.as_str())
+        Ok(())
+    }
+
+    // Scans one directory of devices, and returns the canonical syspaths of the matches.
+    //
+    // Entries are filtered by name, and file type from the directory entry, before a device is
+    // created, so rejected entries cost no `stat` calls.
+    fn scan_dir_syspaths(&self, path: &str) -> Result<Vec<String>> {
+        let mut syspaths = Vec::new();
+
+        for dir_entry in fs::read_dir(path)
             .map_err(|err| Error::UdevEnumerate(format!("unable to open {path} path: {err}")))?
         {
-            let d_name = dir_entry?
-                .file_name()
-                .into_string()
-                .unwrap_or(String::new());
+            let dir_entry = dir_entry?;
+            let d_name = dir_entry.file_name().into_string().unwrap_or_default();
 
             if d_name.is_empty() {
                 log::trace!("{LOG_PREFIX} empty/invalid entry");
@@ -795,80 +1016,77 @@ impl UdevEnumerate {
                 log::trace!("{LOG_PREFIX} private entry");
             } else if !self.match_sysname(d_name.as_str()) {
                 log::trace!("{LOG_PREFIX} no /sys name match");
-            } else {
-                let syspath = format!("{path}/{d_name}");
-                if let Ok(mut dev) =
-                    UdevDevice::new_from_syspath(Arc::clone(&self.udev), syspath.as_str())
-                {
-                    if self.match_is_initialized {
-                        // From `libudev` documentation:
-                        //
-                        // ```
-                        // All devices with a device node or network interfaces
-                        // possibly need udev to adjust the device node permission
-                        // or context, or rename the interface before it can be
-                        // reliably used from other processes.
-                        //
-                        // For now, we can only check these types of devices, we
-                        // might not store a database, and have no way to find out
-                        // for all other types of devices.
-                        // ```
-                        if dev.get_is_initialized()
-                            && (util::major(dev.devnum()) > 0 || dev.get_ifindex() > 0)
-                        {
-                            break;
-                        }
-                    }
-                    let dev_syspath = dev.syspath().to_owned();
-                    if !self.match_parent(&dev) {
-                        log::trace!("{LOG_PREFIX} no parent match");
-                        break;
-                    } else if !self.match_tag(&mut dev) {
-                        log::trace!("{LOG_PREFIX} no tag match");
-                        break;
-                    } else if !self.match_property(&dev) {
-                        log::trace!("{LOG_PREFIX} no property match");
-                        break;
-                    } else if !self.match_sysattr(&mut dev) {
-                        log::trace!("{LOG_PREFIX} no /sys attribute match");
-                        break;
-                    } else {
-                        add_syspaths.push(dev_syspath);
-                    }
+            } else if is_regular_file(&dir_entry) {
+                // devices are directories, or symlinks to them
+                log::trace!("{LOG_PREFIX} regular file entry");
+            } else if let Ok(mut dev) =
+                SysPath::new_in(self.udev.get_sys_path(), format!("{path}/{d_name}")).and_then(
+                    |syspath| UdevDevice::new_from_syspath(Arc::clone(&self.udev), &syspath),
+                )
+            {
+                if self.match_scanned_device(&mut dev) {
+                    syspaths.push(dev.syspath().to_owned());
                 }
             }
         }
 
-        for syspath in add_syspaths.iter() {
-            self.add_syspath(syspath)?;
-        }
-
-        Ok(())
+        Ok(syspaths)
     }
 
     fn scan_dir(&mut self, basedir: &str, subdir: &str, subsystem: &str) -> Result<()> {
-        let path = format!("/sys/{basedir}");
+        let path = format!("{}/{basedir}", self.udev.get_sys_path());
+        let mut subsystems: Vec<String> = Vec::new();
 
         for dir_entry in fs::read_dir(path.as_str())
             .map_err(|err| Error::UdevEnumerate(format!("unable to open {path} path: {err}")))?
         {
-            let d_name = dir_entry?
-                .file_name()
-                .into_string()
-                .unwrap_or(String::new());
+            let dir_entry = dir_entry?;
+            let d_name = dir_entry.file_name().into_string().unwrap_or_default();
 
             if d_name.is_empty() {
                 log::trace!("{LOG_PREFIX} empty/invalid entry");
             } else if d_name.starts_with('.') {
                 log::trace!("{LOG_PREFIX} private entry");
-            } else if !self.match_sysname(if subsystem.is_empty() {
+            } else if !self.match_subsystem(if subsystem.is_empty() {
                 d_name.as_str()
             } else {
                 subsystem
             }) {
-                log::trace!("{LOG_PREFIX} no /sys subsystem name match");
+                log::trace!("{LOG_PREFIX} no /sys subsystem match");
+            } else if is_regular_file(&dir_entry) {
+                log::trace!("{LOG_PREFIX} regular file entry");
             } else {
-                self.scan_dir_and_add_devices(basedir, d_name.as_str(), subdir)?;
+                subsystems.push(d_name);
+            }
+        }
+
+        let scan = |d_name: &String| {
+            if subdir.is_empty() {
+                self.scan_dir_syspaths(format!("{path}/{d_name}").as_str())
+            } else {
+                self.scan_dir_syspaths(format!("{path}/{d_name}/{subdir}").as_str())
+            }
+        };
+
+        // the subsystem directories are independent, scan them in parallel if enabled,
+        // and add the devices in directory order
+        #[cfg(feature = "rayon")]
+        let results: Vec<Result<Vec<String>>> = {
+            use rayon::prelude::*;
+            subsystems.par_iter().map(scan).collect()
+        };
+        #[cfg(not(feature = "rayon"))]
+        let results: Vec<Result<Vec<String>>> = subsystems.iter().map(scan).collect();
+
+        for res in results {
+            match res {
+                Ok(syspaths) => {
+                    for syspath in syspaths.iter() {
+                        self.syspath_add(syspath)?;
+                    }
+                }
+                // devices can disappear during the scan, keep going with the rest
+                Err(err) => log::debug!("{LOG_PREFIX} {err}"),
             }
         }
 
@@ -876,33 +1094,99 @@ impl UdevEnumerate {
     }
 }
 
+// Gets whether the directory entry is a regular file, from the entry type returned by
+// `getdents`, without a `stat` call on file systems reporting it, like `sysfs`.
+fn is_regular_file(dir_entry: &fs::DirEntry) -> bool {
+    dir_entry.file_type().is_ok_and(|t| t.is_file())
+}
+
+impl Udev {
+    /// Enumerates all block devices, e.g. disks and partitions.
+    ///
+    /// Convenience function for an [UdevEnumerate] matching the `block` subsystem.
+    ///
+    /// Returns: `Ok(Vec<UdevDevice>)` sorted by `syspath` on success, `Err(Error)` otherwise.
+    pub fn block_devices(self: &Arc<Self>) -> Result<Vec<UdevDevice>> {
+        self.subsystem_devices("block")
+    }
+
+    /// Enumerates all network interfaces.
+    ///
+    /// Convenience function for an [UdevEnumerate] matching the `net` subsystem.
+    ///
+    /// Returns: `Ok(Vec<UdevDevice>)` sorted by `syspath` on success, `Err(Error)` otherwise.
+    pub fn net_interfaces(self: &Arc<Self>) -> Result<Vec<UdevDevice>> {
+        self.subsystem_devices("net")
+    }
+
+    /// Enumerates all input devices, e.g. keyboards, mice, and their `event` nodes.
+    ///
+    /// Convenience function for an [UdevEnumerate] matching the `input` subsystem.
+    ///
+    /// Returns: `Ok(Vec<UdevDevice>)` sorted by `syspath` on success, `Err(Error)` otherwise.
+    pub fn input_devices(self: &Arc<Self>) -> Result<Vec<UdevDevice>> {
+        self.subsystem_devices("input")
+    }
+
+    fn subsystem_devices(self: &Arc<Self>, subsystem: &str) -> Result<Vec<UdevDevice>> {
+        let mut enumerate = UdevEnumerate::new(Arc::clone(self));
+
+        enumerate.add_match_subsystem(subsystem)?;
+        enumerate.scan_devices()?;
+
+        let mut syspaths: Vec<&str> = enumerate.devices().iter().map(|d| d.syspath()).collect();
+        syspaths.sort_unstable();
+        syspaths.dedup();
+
+        Ok(syspaths
+            .into_iter()
+            .filter_map(|syspath| {
+                // devices can disappear between the scan, and creating the device
+                SysPath::new_in(self.get_sys_path(), syspath)
+                    .and_then(|syspath| UdevDevice::new_from_syspath(Arc::clone(self), &syspath))
+                    .map(|dev| dev.with_subsystem(subsystem))
+                    .map_err(|err| log::debug!("{LOG_PREFIX} {err}"))
+                    .ok()
+            })
+            .collect())
+    }
+}
+
 impl UdevDevice {
-    pub(crate) fn match_sysattr_value(&mut self, sysattr: &str, match_val: &str) -> bool {
-        match self.get_sysattr_value(sysattr) {
-            Some(val) => {
-                if match_val.is_empty() {
-                    true
-                } else if let Ok(pattern) = glob::Pattern::new(match_val) {
-                    pattern.matches(val.as_str())
-                } else {
-                    false
-                }
-            }
-            None => false,
-        }
+    pub(crate) fn match_sysattr_value(&mut self, sysattr: &str, matcher: &SysattrMatch) -> bool {
+        // `sys` values end with a newline, like `libudev`, match without it
+        self.get_sysattr_value(sysattr)
+            .is_some_and(|val| matcher.matches(val.trim_end_matches('\n')))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::{FixtureDevice, SysfsFixture};
     use crate::UdevEntry;
 
+    // Solid state, and rotational disks, with size attributes, and a disk without attributes.
+    fn fixture() -> Result<SysfsFixture> {
+        let disk = |name: &str, rotational: &str, size: &str| {
+            FixtureDevice::new(format!("/devices/virtual/block/{name}"), "block")
+                .with_sysattr("queue/rotational", rotational)
+                .with_sysattr("size", size)
+        };
+
+        SysfsFixture::new()?.with_devices([
+            disk("ssd0", "0\n", "4194304\n"),
+            disk("hdd0", "1\n", "1048576\n"),
+            FixtureDevice::new("/devices/virtual/block/ram0", "block"),
+        ])
+    }
+
     #[test]
     fn test_udev_enumerate() {
         let udev = Arc::new(Udev::new());
 
         let mut null_enum = UdevEnumerate::new(Arc::clone(&udev));
+        assert!(null_enum.trigger_all("change").is_empty());
 
         assert_eq!(null_enum.udev(), &udev);
         assert!(null_enum.sysattr_match_list().is_empty());
@@ -912,6 +1196,8 @@ mod tests {
         assert!(null_enum.sysname_match_list().is_empty());
         assert!(null_enum.properties_match_list().is_empty());
         assert!(null_enum.tags_match_list().is_empty());
+        assert!(null_enum.devtype_match_list().is_empty());
+        assert!(null_enum.devnum_match_list().is_empty());
         assert!(null_enum.devices_list().is_empty());
         assert!(null_enum.parent().is_none());
         assert!(null_enum.devices().is_empty());
@@ -919,6 +1205,10 @@ mod tests {
         assert_eq!(null_enum.devices_max(), 0);
         assert!(!null_enum.devices_uptodate());
         assert!(!null_enum.match_is_initialized());
+        assert!(null_enum
+            .add_match_sysattr("../../../../../etc/passwd", Some("root:*"))
+            .is_err());
+        assert!(null_enum.sysattr_match_list().is_empty());
 
         let exp_sysattr_match_list = [UdevEntry::new().with_name("test_sysattr_match_list")];
         let exp_sysattr_nomatch_list = [UdevEntry::new().with_name("test_sysattr_nomatch_list")];
@@ -928,6 +1218,8 @@ mod tests {
         let exp_sysname_match_list = [UdevEntry::new().with_name("test_sysname_match_list")];
         let exp_properties_match_list = [UdevEntry::new().with_name("test_properties_match_list")];
         let exp_tags_match_list = [UdevEntry::new().with_name("test_tags_match_list")];
+        let exp_devtype_match_list = [UdevEntry::new().with_name("test_devtype_match_list")];
+        let exp_devnum_match_list = [DevnumMatch::new(DevnumType::Block, 8, 0, 15)];
         let exp_devices_list = [UdevEntry::new().with_name("test_devices_list")];
         let exp_parent = Arc::new(UdevDevice::new(Arc::clone(&udev)));
         let exp_devices = [Syspath::new(), Syspath::new()];
@@ -944,6 +1236,8 @@ mod tests {
             .with_sysname_match_list(exp_sysname_match_list.clone())
             .with_properties_match_list(exp_properties_match_list.clone())
             .with_tags_match_list(exp_tags_match_list.clone())
+            .with_devtype_match_list(exp_devtype_match_list.clone())
+            .with_devnum_match_list(exp_devnum_match_list)
             .with_devices_list(exp_devices_list.clone())
             .with_parent(exp_parent.clone())
             .with_devices(exp_devices.clone())
@@ -983,6 +1277,10 @@ mod tests {
                 .tags_match_list()
                 .iter()
                 .zip(exp_tags_match_list.iter()),
+            exp_enum
+                .devtype_match_list()
+                .iter()
+                .zip(exp_devtype_match_list.iter()),
             exp_enum.devices_list().iter().zip(exp_devices_list.iter()),
         ] {
             for (entry, exp_entry) in iter {
@@ -990,6 +1288,7 @@ mod tests {
             }
         }
 
+        assert_eq!(exp_enum.devnum_match_list(), exp_devnum_match_list.as_ref());
         assert_eq!(exp_enum.parent(), Some(&exp_parent));
         assert_eq!(exp_enum.devices(), exp_devices.as_ref());
         assert_eq!(exp_enum.devices_cur(), exp_devices_cur);
@@ -1004,6 +1303,8 @@ mod tests {
         null_enum.set_sysname_match_list(exp_sysname_match_list.clone());
         null_enum.set_properties_match_list(exp_properties_match_list.clone());
         null_enum.set_tags_match_list(exp_tags_match_list.clone());
+        null_enum.set_devtype_match_list(exp_devtype_match_list.clone());
+        null_enum.set_devnum_match_list(exp_devnum_match_list);
         null_enum.set_devices_list(exp_devices_list.clone());
         null_enum.set_parent(exp_parent.clone());
         null_enum.set_devices(exp_devices.clone());
@@ -1014,4 +1315,95 @@ mod tests {
 
         assert_eq!(null_enum, exp_enum);
     }
+
+    #[test]
+    fn test_match_glob() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+        let mut enumerate = UdevEnumerate::new(udev);
+
+        enumerate.add_match_sysname("sda*")?;
+        enumerate.add_match_sysname("tty[0-9]*")?;
+
+        for sysname in ["sda", "sda1", "tty0", "tty63"] {
+            assert!(enumerate.match_sysname(sysname), "{sysname}");
+        }
+        for sysname in ["sdb", "ttyS0", "tty", ""] {
+            assert!(!enumerate.match_sysname(sysname), "{sysname}");
+        }
+
+        enumerate.add_match_subsystem("tty*")?;
+        enumerate.add_nomatch_subsystem("*serial")?;
+        assert!(enumerate.match_subsystem("tty"));
+        assert!(!enumerate.match_subsystem("ttyserial"));
+        assert!(!enumerate.match_subsystem("block"));
+
+        // invalid patterns only match literally
+        enumerate.add_match_sysname("[sd")?;
+        assert!(enumerate.match_sysname("[sd"));
+        assert!(!enumerate.match_sysname("sd"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_match_sysattr() -> Result<()> {
+        let fixture = fixture()?;
+
+        let sysnames = |enumerate: &mut UdevEnumerate| -> Result<Vec<String>> {
+            enumerate.scan_devices()?;
+            let mut names: Vec<String> = enumerate
+                .devices()
+                .iter()
+                .filter_map(|d| d.syspath().rsplit('/').next().map(String::from))
+                .collect();
+            names.sort();
+            Ok(names)
+        };
+
+        let mut enumerate = UdevEnumerate::new(fixture.udev());
+        enumerate.add_match_sysattr("size", None)?;
+        assert_eq!(sysnames(&mut enumerate)?, ["hdd0", "ssd0"]);
+
+        let mut enumerate = UdevEnumerate::new(fixture.udev());
+        enumerate.add_match_sysattr("queue/rotational", Some("0"))?;
+        assert_eq!(sysnames(&mut enumerate)?, ["ssd0"]);
+
+        let mut enumerate = UdevEnumerate::new(fixture.udev());
+        enumerate.add_match_sysattr("size", Some(">=2097152"))?;
+        assert_eq!(sysnames(&mut enumerate)?, ["ssd0"]);
+
+        // several values of one attribute all have to match
+        let mut enumerate = UdevEnumerate::new(fixture.udev());
+        enumerate.add_match_sysattr("size", Some(">=1048576"))?;
+        enumerate.add_match_sysattr("size", Some("<=2097152"))?;
+        assert_eq!(enumerate.sysattr_match_list().len(), 2);
+        assert_eq!(sysnames(&mut enumerate)?, ["hdd0"]);
+
+        let mut enumerate = UdevEnumerate::new(fixture.udev());
+        enumerate.add_match_sysattr("size", Some(">=2097152"))?;
+        enumerate.add_match_sysattr("size", Some("<=4194304"))?;
+        assert_eq!(sysnames(&mut enumerate)?, ["ssd0"]);
+
+        let mut enumerate = UdevEnumerate::new(fixture.udev());
+        enumerate.add_match_sysattr("size", Some(">=2097152"))?;
+        enumerate.add_match_sysattr("size", Some("<=2097152"))?;
+        assert!(sysnames(&mut enumerate)?.is_empty());
+
+        let mut enumerate = UdevEnumerate::new(fixture.udev());
+        enumerate.add_match_sysattr("size", Some("<= 2097152"))?;
+        enumerate.add_nomatch_sysattr("queue/rotational", Some("0*"))?;
+        assert_eq!(sysnames(&mut enumerate)?, ["hdd0"]);
+
+        assert!(enumerate.add_match_sysattr("size", Some(">=big")).is_err());
+        assert!(enumerate.add_match_sysattr("", None).is_err());
+        assert!(enumerate
+            .add_match_sysattr("../../../../../etc/passwd", Some("root:*"))
+            .is_err());
+        assert!(enumerate.add_nomatch_sysattr("/etc/passwd", None).is_err());
+        assert!(enumerate
+            .add_match_sysattr("queue/./rotational", None)
+            .is_err());
+
+        Ok(())
+    }
 }
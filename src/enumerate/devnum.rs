@@ -0,0 +1,262 @@
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+
+use super::UdevEnumerate;
+use crate::{util, Error, Result, UdevDevice};
+
+/// Represents the type of a device number, device numbers are not unique across the types.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum DevnumType {
+    /// Block device, e.g. disks and partitions.
+    Block,
+    /// Character device, e.g. terminals and input devices.
+    #[default]
+    Char,
+}
+
+impl DevnumType {
+    /// Creates a new [DevnumType].
+    pub const fn new() -> Self {
+        Self::Char
+    }
+
+    /// Gets the [DevnumType] of a [UdevDevice], from its subsystem.
+    pub fn from_device(dev: &UdevDevice) -> Self {
+        if dev.get_subsystem() == "block" {
+            Self::Block
+        } else {
+            Self::Char
+        }
+    }
+}
+
+impl TryFrom<&str> for DevnumType {
+    type Error = Error;
+
+    fn try_from(val: &str) -> Result<Self> {
+        // like `UdevDevice::new_from_devnum`, accepts `b`, `block`, `c`, `char`, etc.
+        match val {
+            t if t.starts_with('b') => Ok(Self::Block),
+            t if t.starts_with('c') => Ok(Self::Char),
+            _ => Err(Error::UdevEnumerate(format!("invalid device type: {val}"))),
+        }
+    }
+}
+
+impl From<&DevnumType> for &'static str {
+    fn from(val: &DevnumType) -> Self {
+        match val {
+            DevnumType::Block => "block",
+            DevnumType::Char => "char",
+        }
+    }
+}
+
+impl From<DevnumType> for &'static str {
+    fn from(val: DevnumType) -> Self {
+        (&val).into()
+    }
+}
+
+impl fmt::Display for DevnumType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", <&str>::from(self))
+    }
+}
+
+/// Represents a device number match of [UdevEnumerate]: a major number, and a range of minors.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DevnumMatch {
+    devnum_type: DevnumType,
+    major: u32,
+    minor_first: u32,
+    minor_last: u32,
+}
+
+impl DevnumMatch {
+    /// Creates a new [DevnumMatch] for the minors `minor_first` through `minor_last`, inclusive.
+    pub const fn new(
+        devnum_type: DevnumType,
+        major: u32,
+        minor_first: u32,
+        minor_last: u32,
+    ) -> Self {
+        Self {
+            devnum_type,
+            major,
+            minor_first,
+            minor_last,
+        }
+    }
+
+    /// Gets the [DevnumType].
+    pub const fn devnum_type(&self) -> DevnumType {
+        self.devnum_type
+    }
+
+    /// Gets the major number.
+    pub const fn major(&self) -> u32 {
+        self.major
+    }
+
+    /// Gets the first minor number of the range.
+    pub const fn minor_first(&self) -> u32 {
+        self.minor_first
+    }
+
+    /// Gets the last minor number of the range, inclusive.
+    pub const fn minor_last(&self) -> u32 {
+        self.minor_last
+    }
+
+    /// Gets whether the [UdevDevice] has a device number of the type, major, and minor range.
+    ///
+    /// Devices without a device number never match.
+    pub fn matches(&self, dev: &UdevDevice) -> bool {
+        let devnum = dev.get_devnum();
+        let minor = u32::from(util::minor(devnum));
+
+        devnum != 0
+            && DevnumType::from_device(dev) == self.devnum_type
+            && u32::from(util::major(devnum)) == self.major
+            && (self.minor_first..=self.minor_last).contains(&minor)
+    }
+}
+
+impl UdevEnumerate {
+    /// Gets the list of [DevnumMatch] filters.
+    pub fn devnum_match_list(&self) -> &[DevnumMatch] {
+        self.devnum_match_list.as_ref()
+    }
+
+    /// Sets the list of [DevnumMatch] filters.
+    pub fn set_devnum_match_list<M: IntoIterator<Item = DevnumMatch>>(&mut self, list: M) {
+        self.devnum_match_list = list.into_iter().collect();
+    }
+
+    /// Builder function that sets the list of [DevnumMatch] filters.
+    pub fn with_devnum_match_list<M: IntoIterator<Item = DevnumMatch>>(mut self, list: M) -> Self {
+        self.set_devnum_match_list(list);
+        self
+    }
+
+    /// Adds a device number match.
+    ///
+    /// Match only devices of the `devnum_type`, `block` or `char`, with the `major` number, and
+    /// a minor number in the `minors` range. Devices matching any of the device numbers are
+    /// included.
+    ///
+    /// For example, the partitions of the first SCSI disk, `8:0`:
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{Udev, UdevEnumerate};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let mut enumerate = UdevEnumerate::new(Arc::new(Udev::new()));
+    ///
+    /// enumerate.add_match_devtype("partition")?;
+    /// enumerate.add_match_devnum("block", 8, 1..16)?;
+    /// enumerate.scan_devices()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns `Ok(DevnumMatch)` on success, `Err(Error)` if the type is invalid, or the range
+    /// is empty.
+    pub fn add_match_devnum<R: RangeBounds<u32>>(
+        &mut self,
+        devnum_type: &str,
+        major: u32,
+        minors: R,
+    ) -> Result<&DevnumMatch> {
+        let devnum_type = DevnumType::try_from(devnum_type)?;
+
+        let first = match minors.start_bound() {
+            Bound::Included(&m) => Some(m),
+            Bound::Excluded(&m) => m.checked_add(1),
+            Bound::Unbounded => Some(0),
+        };
+        let last = match minors.end_bound() {
+            Bound::Included(&m) => Some(m),
+            Bound::Excluded(&m) => m.checked_sub(1),
+            Bound::Unbounded => Some(u32::MAX),
+        };
+
+        match (first, last) {
+            (Some(first), Some(last)) if first <= last => {
+                self.devnum_match_list
+                    .push(DevnumMatch::new(devnum_type, major, first, last));
+                self.devnum_match_list.last().ok_or(Error::UdevEnumerate(
+                    "unable to add match devnum entry".into(),
+                ))
+            }
+            _ => Err(Error::UdevEnumerate(format!(
+                "empty minor range for devnum match: {devnum_type} {major}"
+            ))),
+        }
+    }
+
+    pub(super) fn match_devnum(&self, dev: &UdevDevice) -> bool {
+        self.devnum_match_list.is_empty() || self.devnum_match_list.iter().any(|m| m.matches(dev))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Udev;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_match_devnum() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+        let mut enumerate = UdevEnumerate::new(Arc::clone(&udev));
+
+        assert_eq!(DevnumType::try_from("b")?, DevnumType::Block);
+        assert_eq!(DevnumType::try_from("char")?, DevnumType::Char);
+        assert!(DevnumType::try_from("net").is_err());
+
+        assert!(enumerate.add_match_devnum("block", 8, 4..4).is_err());
+        assert!(enumerate.add_match_devnum("disk", 8, ..).is_err());
+
+        assert_eq!(
+            enumerate.add_match_devnum("block", 8, 1..16)?,
+            &DevnumMatch::new(DevnumType::Block, 8, 1, 15)
+        );
+
+        let partition = UdevDevice::new(Arc::clone(&udev))
+            .with_subsystem("block")
+            .with_devnum(libc::makedev(8, 3));
+        let disk = partition.clone().with_devnum(libc::makedev(8, 0));
+        let other = partition.clone().with_devnum(libc::makedev(8, 16));
+        let chardev = partition.clone().with_subsystem("tty");
+        let no_devnum = partition.clone().with_devnum(0).with_info_loaded(true);
+
+        assert!(enumerate.match_devnum(&partition));
+        assert!(!enumerate.match_devnum(&disk));
+        assert!(!enumerate.match_devnum(&other));
+        assert!(!enumerate.match_devnum(&chardev));
+        assert!(!enumerate.match_devnum(&no_devnum));
+
+        enumerate.add_match_devnum("c", 4, ..=63)?;
+        assert!(enumerate.match_devnum(&chardev.with_devnum(libc::makedev(4, 1))));
+
+        // scan only disks on the live system, if there are any
+        let mut enumerate = UdevEnumerate::new(udev);
+        enumerate.add_match_subsystem("block")?;
+        enumerate.add_match_devtype("disk")?;
+        if enumerate.scan_devices().is_ok() {
+            for entry in enumerate.devices_list().iter() {
+                let dev = UdevDevice::new_from_syspath(
+                    Arc::clone(enumerate.udev()),
+                    &crate::SysPath::new(entry.name())?,
+                )?;
+                assert_eq!(dev.get_devtype(), "disk");
+            }
+        }
+
+        Ok(())
+    }
+}
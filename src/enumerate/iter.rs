@@ -0,0 +1,271 @@
+use std::{collections::VecDeque, fs, sync::Arc};
+
+use super::{UdevEnumerate, LOG_PREFIX};
+use crate::{Error, Result, SysPath, UdevDevice};
+
+// Directory visited by a [ScanIter].
+#[derive(Clone, Debug)]
+enum ScanDir {
+    // `<run>/tags/<tag>`, entries are device IDs
+    Tag(String),
+    // `<sys>/<basedir>`, entries are subsystems, with their devices in `<subsystem>/<subdir>`
+    Subsystems { path: String, subdir: String },
+    // `<sys>/<basedir>/<subsystem>[/<subdir>]`, entries are devices
+    Devices(String),
+}
+
+impl ScanDir {
+    fn path(&self) -> &str {
+        match self {
+            Self::Tag(path) | Self::Subsystems { path, .. } | Self::Devices(path) => path,
+        }
+    }
+
+    fn open(&self) -> Result<fs::ReadDir> {
+        let path = self.path();
+        fs::read_dir(path)
+            .map_err(|err| Error::UdevEnumerate(format!("unable to open {path} path: {err}")))
+    }
+}
+
+/// Lazy iterator over the devices matching the filters of a [UdevEnumerate].
+///
+/// Created by [scan_iter](UdevEnumerate::scan_iter). Directories are read while iterating, and
+/// only the open directory handles are kept in memory, instead of every matching syspath.
+///
+/// Errors stopping [scan_devices](UdevEnumerate::scan_devices) are yielded as `Err` items, the
+/// iteration may continue with the next directory.
+pub struct ScanIter<'e> {
+    enumerate: &'e UdevEnumerate,
+    pending: VecDeque<ScanDir>,
+    open: Vec<(ScanDir, fs::ReadDir)>,
+    error: Option<Error>,
+}
+
+impl<'e> ScanIter<'e> {
+    pub(super) fn new(enumerate: &'e UdevEnumerate) -> Self {
+        let mut pending = VecDeque::new();
+        let mut error = None;
+
+        let sys = enumerate.udev.get_sys_path();
+        let subsystems = |basedir: &str, subdir: &str| ScanDir::Subsystems {
+            path: format!("{sys}/{basedir}"),
+            subdir: subdir.into(),
+        };
+
+        if enumerate.tags_match_list.entry().is_some() {
+            // use the tags reverse-index, instead of searching all devices in `/sys`
+            let run = enumerate.udev.get_run_path();
+            pending.extend(
+                enumerate
+                    .tags_match_list
+                    .iter()
+                    .map(|tag| ScanDir::Tag(format!("{run}/tags/{}", tag.name()))),
+            );
+        } else if enumerate.parent.is_some() {
+            error = Some(Error::UdevEnumerate("unimplemented".into()));
+        } else if fs::metadata(format!("{sys}/subsystem")).is_ok() {
+            pending.push_back(subsystems("subsystem", "devices"));
+        } else {
+            pending.push_back(subsystems("bus", "devices"));
+            pending.push_back(subsystems("class", ""));
+        }
+
+        Self {
+            enumerate,
+            pending,
+            open: Vec::new(),
+            error,
+        }
+    }
+
+    fn tagged_device(&self, id: &str) -> Result<Option<UdevDevice>> {
+        let mut dev = UdevDevice::new_from_device_id(Arc::clone(&self.enumerate.udev), id)?;
+
+        Ok(self.enumerate.match_tagged_device(&mut dev).then_some(dev))
+    }
+
+    fn scanned_device(&self, path: &str, d_name: &str) -> Option<UdevDevice> {
+        if !self.enumerate.match_sysname(d_name) {
+            log::trace!("{LOG_PREFIX} no /sys name match");
+            return None;
+        }
+
+        let udev = &self.enumerate.udev;
+        let mut dev = SysPath::new_in(udev.get_sys_path(), format!("{path}/{d_name}"))
+            .and_then(|syspath| UdevDevice::new_from_syspath(Arc::clone(udev), &syspath))
+            .ok()?;
+
+        self.enumerate.match_scanned_device(&mut dev).then_some(dev)
+    }
+}
+
+impl Iterator for ScanIter<'_> {
+    type Item = Result<UdevDevice>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.error.take() {
+            return Some(Err(err));
+        }
+
+        loop {
+            let Some((dir, entries)) = self.open.last_mut() else {
+                let dir = self.pending.pop_front()?;
+                match dir.open() {
+                    Ok(entries) => self.open.push((dir, entries)),
+                    Err(err) => return Some(Err(err)),
+                }
+                continue;
+            };
+
+            let d_name = match entries.next() {
+                // devices, and subsystems are directories, or symlinks to them
+                Some(Ok(entry))
+                    if !matches!(dir, ScanDir::Tag(_)) && super::is_regular_file(&entry) =>
+                {
+                    continue
+                }
+                Some(Ok(entry)) => entry.file_name().into_string().unwrap_or_default(),
+                Some(Err(err)) => {
+                    if let ScanDir::Devices(path) = dir {
+                        // devices can disappear during the scan, keep going with the rest
+                        log::debug!("{LOG_PREFIX} unable to read {path} entry: {err}");
+                        continue;
+                    }
+                    return Some(Err(err.into()));
+                }
+                None => {
+                    self.open.pop();
+                    continue;
+                }
+            };
+
+            if d_name.is_empty() {
+                log::trace!("{LOG_PREFIX} empty/invalid entry");
+                continue;
+            } else if d_name.starts_with('.') {
+                log::trace!("{LOG_PREFIX} private entry");
+                continue;
+            }
+
+            match dir.clone() {
+                ScanDir::Tag(_) => match self.tagged_device(d_name.as_str()) {
+                    Ok(Some(dev)) => return Some(Ok(dev)),
+                    Ok(None) => (),
+                    Err(err) => return Some(Err(err)),
+                },
+                ScanDir::Subsystems { path, subdir } => {
+                    if !self.enumerate.match_subsystem(d_name.as_str()) {
+                        log::trace!("{LOG_PREFIX} no /sys subsystem match");
+                        continue;
+                    }
+
+                    let devices = if subdir.is_empty() {
+                        ScanDir::Devices(format!("{path}/{d_name}"))
+                    } else {
+                        ScanDir::Devices(format!("{path}/{d_name}/{subdir}"))
+                    };
+                    // descend right away, to keep the order of `scan_devices`
+                    match devices.open() {
+                        Ok(entries) => self.open.push((devices, entries)),
+                        Err(err) => log::debug!("{LOG_PREFIX} {err}"),
+                    }
+                }
+                ScanDir::Devices(path) => {
+                    if let Some(dev) = self.scanned_device(path.as_str(), d_name.as_str()) {
+                        return Some(Ok(dev));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl UdevEnumerate {
+    /// Scans `/sys` for devices which match the given filters, lazily.
+    ///
+    /// Yields the same devices as [scan_devices](Self::scan_devices), while walking the
+    /// directories, without collecting the syspaths first, or adding them to the
+    /// [devices](Self::devices) list. Bounds memory use on systems with many devices:
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{Udev, UdevEnumerate};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let mut enumerate = UdevEnumerate::new(Arc::new(Udev::new()));
+    /// enumerate.add_match_subsystem("block")?;
+    ///
+    /// for dev in enumerate.scan_iter() {
+    ///     println!("{}", dev?.syspath());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns: a [ScanIter] over the matching devices.
+    pub fn scan_iter(&self) -> ScanIter<'_> {
+        ScanIter::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FixtureDevice, SysfsFixture};
+
+    // Two loop disks, one tagged, and a serial port.
+    fn fixture() -> Result<SysfsFixture> {
+        SysfsFixture::new()?.with_devices([
+            FixtureDevice::new("/devices/virtual/block/loop0", "block")
+                .with_devnum(7, 0)
+                .with_devtype("disk")
+                .with_tag("systemd"),
+            FixtureDevice::new("/devices/virtual/block/loop1", "block")
+                .with_devnum(7, 1)
+                .with_devtype("disk"),
+            FixtureDevice::new("/devices/virtual/tty/ttyS0", "tty"),
+        ])
+    }
+
+    #[test]
+    fn test_scan_iter() -> Result<()> {
+        let fixture = fixture()?;
+
+        let syspaths = |enumerate: &UdevEnumerate| -> Result<Vec<String>> {
+            let mut paths = enumerate
+                .scan_iter()
+                .map(|dev| dev.map(|dev| dev.syspath().to_owned()))
+                .collect::<Result<Vec<String>>>()?;
+            paths.sort();
+            Ok(paths)
+        };
+        let scanned = |enumerate: &mut UdevEnumerate| -> Result<Vec<String>> {
+            enumerate.scan_devices()?;
+            let mut paths: Vec<String> = enumerate
+                .devices()
+                .iter()
+                .map(|d| d.syspath().to_owned())
+                .collect();
+            paths.sort();
+            Ok(paths)
+        };
+
+        let mut enumerate = UdevEnumerate::new(fixture.udev());
+        assert_eq!(syspaths(&enumerate)?.len(), 3);
+
+        enumerate.add_match_subsystem("block")?;
+        let block = syspaths(&enumerate)?;
+        assert_eq!(block.len(), 2);
+        assert!(enumerate.devices().is_empty());
+        assert_eq!(block, scanned(&mut enumerate)?);
+
+        let mut tagged = UdevEnumerate::new(fixture.udev());
+        tagged.add_match_tag("systemd")?;
+        let systemd = syspaths(&tagged)?;
+        assert_eq!(systemd.len(), 1);
+        assert!(systemd[0].ends_with("/loop0"));
+        assert_eq!(systemd, scanned(&mut tagged)?);
+
+        Ok(())
+    }
+}
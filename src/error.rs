@@ -1,11 +1,35 @@
-use std::fmt;
+use std::{fmt, io, path, sync::Arc};
 
 /// Convenience alias for the `udev` library `Result` type.
 pub type Result<T> = std::result::Result<T, Error>;
 
 /// Error types for the `udev` library.
+///
+/// Use [kind](Self::kind) to distinguish error conditions, e.g. a non-blocking receive with no
+/// pending messages, from a permission failure:
+///
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use udevrs::{ErrorKind, Udev, UdevMonitor};
+/// # fn main() -> udevrs::Result<()> {
+/// let udev = Arc::new(Udev::new());
+/// let mut monitor = UdevMonitor::new_from_netlink(udev, "udev")?;
+/// monitor.enable_receiving()?;
+///
+/// match monitor.receive_device() {
+///     Ok(device) => println!("{} {}", device.action(), device.devpath()),
+///     Err(err) if err.is_would_block() => println!("no pending devices"),
+///     Err(err) if err.kind() == ErrorKind::Filtered => println!("device filtered"),
+///     Err(err) => return Err(err),
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// New variants may be added in minor releases, matches need a wildcard arm.
 #[repr(C)]
 #[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum Error {
     InvalidLen(usize),
     Udev(String),
@@ -15,12 +39,130 @@ pub enum Error {
     UdevEnumerate(String),
     UdevQueue(String),
     UdevUtil(String),
+    UdevBuiltin(String),
+    UdevNode(String),
     Io(String),
+    /// The monitor receive buffer overflowed (`ENOBUFS`), and an unknown number of events was
+    /// lost.
+    ///
+    /// The monitor stays usable, but the state tracked from events may be stale. Re-enumerate
+    /// the devices, or set an [OverflowStrategy](crate::OverflowStrategy) to resync
+    /// automatically.
+    Overflow(String),
+    /// A system call, or I/O operation failed, see [OsError].
+    Os(OsError),
+    /// The received device did not pass the monitor filter.
+    Filtered(String),
+    /// An [Error] with the [ErrorContext] of the failed operation, see
+    /// [with_context](Self::with_context).
+    Context(ErrorContext, Box<Error>),
 }
 
-impl From<std::io::Error> for Error {
-    fn from(err: std::io::Error) -> Self {
-        Self::Io(format!("{err}"))
+impl Error {
+    /// Creates an [Error::Os] from the failed operation message, and the underlying
+    /// [io::Error].
+    ///
+    /// The message is displayed as-is after the `scope` prefix, so it should already describe
+    /// the `err`.
+    pub fn os<M: Into<String>>(scope: ErrorScope, msg: M, err: io::Error) -> Self {
+        Self::Os(OsError::new(scope, msg, err))
+    }
+
+    /// Attaches the [ErrorContext] of the failed operation, e.g. the device, and file path.
+    ///
+    /// The [kind](Self::kind), [errno](Self::errno), and [io_error](Self::io_error) of the
+    /// [Error] are preserved.
+    pub fn with_context(self, context: ErrorContext) -> Self {
+        Self::Context(context, Box::new(self))
+    }
+
+    /// Gets the outermost [ErrorContext] attached to the [Error].
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Self::Context(context, _) => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Gets the [Error] without any attached [ErrorContext].
+    pub fn without_context(&self) -> &Self {
+        match self {
+            Self::Context(_, err) => err.without_context(),
+            err => err,
+        }
+    }
+
+    /// Gets the path of the file the failed operation accessed, e.g. the database file.
+    ///
+    /// Returns: the innermost path of the attached [ErrorContext]s, `None` if there is none.
+    pub fn path(&self) -> Option<&path::Path> {
+        match self {
+            Self::Context(context, err) => err.path().or(context.path()),
+            _ => None,
+        }
+    }
+
+    /// Gets the syspath of the device the failed operation accessed.
+    ///
+    /// Returns: the innermost syspath of the attached [ErrorContext]s, `None` if there is none.
+    pub fn syspath(&self) -> Option<&str> {
+        match self {
+            Self::Context(context, err) => err.syspath().or(context.syspath()),
+            _ => None,
+        }
+    }
+
+    /// Gets the [ErrorKind] of the [Error].
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Context(_, err) => err.kind(),
+            Self::InvalidLen(_) => ErrorKind::InvalidInput,
+            Self::Io(_) => ErrorKind::Io,
+            Self::Overflow(_) => ErrorKind::Overflow,
+            Self::Os(err) => err.kind(),
+            Self::Filtered(_) => ErrorKind::Filtered,
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// Gets the `errno` of the failed system call.
+    ///
+    /// Returns: `Some(i32)` for errors from a system call, `None` otherwise.
+    pub fn errno(&self) -> Option<i32> {
+        match self {
+            Self::Context(_, err) => err.errno(),
+            Self::Os(err) => err.errno(),
+            Self::Overflow(_) => Some(libc::ENOBUFS),
+            _ => None,
+        }
+    }
+
+    /// Gets the underlying [io::Error].
+    ///
+    /// Returns: `Some(&io::Error)` for [Error::Os], `None` otherwise.
+    pub fn io_error(&self) -> Option<&io::Error> {
+        match self {
+            Self::Context(_, err) => err.io_error(),
+            Self::Os(err) => Some(err.io_error()),
+            _ => None,
+        }
+    }
+
+    /// Gets whether the operation would block, e.g. a non-blocking receive with no pending
+    /// messages (`EAGAIN`).
+    pub fn is_would_block(&self) -> bool {
+        self.kind() == ErrorKind::WouldBlock
+    }
+
+    /// Gets whether the operation was not permitted (`EPERM`, or `EACCES`).
+    pub fn is_permission(&self) -> bool {
+        self.kind() == ErrorKind::PermissionDenied
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::os(ErrorScope::Io, format!("{err}"), err)
     }
 }
 
@@ -53,7 +195,392 @@ impl fmt::Display for Error {
             Self::UdevEnumerate(err) => write!(f, "udev enumerate: {err}"),
             Self::UdevQueue(err) => write!(f, "udev queue: {err}"),
             Self::UdevUtil(err) => write!(f, "udev util: {err}"),
+            Self::UdevBuiltin(err) => write!(f, "udev builtin: {err}"),
+            Self::UdevNode(err) => write!(f, "udev node: {err}"),
             Self::Io(err) => write!(f, "I/O: {err}"),
+            Self::Overflow(err) => write!(f, "udev monitor overflow: {err}"),
+            Self::Os(err) => write!(f, "{err}"),
+            Self::Filtered(err) => write!(f, "udev monitor: {err}"),
+            Self::Context(context, err) => write!(f, "{err} ({context})"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Context(_, err) => Some(err.as_ref()),
+            Self::Os(err) => Some(err.io_error()),
+            _ => None,
+        }
+    }
+}
+
+/// Represents the category of an [Error].
+///
+/// New kinds may be added in minor releases, matches need a wildcard arm.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// Error without a more specific kind, e.g. invalid data from `sys`.
+    #[default]
+    Other,
+    /// Invalid argument (`EINVAL`), or length.
+    InvalidInput,
+    /// Entity not found (`ENOENT`, `ENODEV`, or `ENXIO`).
+    NotFound,
+    /// Operation not permitted (`EPERM`, or `EACCES`).
+    PermissionDenied,
+    /// Operation would block (`EAGAIN`).
+    WouldBlock,
+    /// Operation interrupted by a signal (`EINTR`).
+    Interrupted,
+    /// Operation timed out (`ETIMEDOUT`).
+    TimedOut,
+    /// Received device did not pass the monitor filter.
+    Filtered,
+    /// Monitor receive buffer overflowed (`ENOBUFS`).
+    Overflow,
+    /// Other I/O failure.
+    Io,
+}
+
+impl ErrorKind {
+    /// Gets the [ErrorKind] of an `errno` value.
+    pub const fn from_errno(errno: i32) -> Self {
+        match errno {
+            libc::EINVAL => Self::InvalidInput,
+            libc::ENOENT | libc::ENODEV | libc::ENXIO => Self::NotFound,
+            libc::EPERM | libc::EACCES => Self::PermissionDenied,
+            libc::EAGAIN => Self::WouldBlock,
+            libc::EINTR => Self::Interrupted,
+            libc::ETIMEDOUT => Self::TimedOut,
+            libc::ENOBUFS => Self::Overflow,
+            _ => Self::Io,
+        }
+    }
+
+    /// Gets the [ErrorKind] name.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Other => "other",
+            Self::InvalidInput => "invalid input",
+            Self::NotFound => "not found",
+            Self::PermissionDenied => "permission denied",
+            Self::WouldBlock => "would block",
+            Self::Interrupted => "interrupted",
+            Self::TimedOut => "timed out",
+            Self::Filtered => "filtered",
+            Self::Overflow => "overflow",
+            Self::Io => "I/O",
+        }
+    }
+}
+
+impl From<&io::Error> for ErrorKind {
+    fn from(err: &io::Error) -> Self {
+        match err.raw_os_error() {
+            Some(errno) => Self::from_errno(errno),
+            None => match err.kind() {
+                io::ErrorKind::InvalidInput | io::ErrorKind::InvalidData => Self::InvalidInput,
+                io::ErrorKind::NotFound => Self::NotFound,
+                io::ErrorKind::PermissionDenied => Self::PermissionDenied,
+                io::ErrorKind::WouldBlock => Self::WouldBlock,
+                io::ErrorKind::Interrupted => Self::Interrupted,
+                io::ErrorKind::TimedOut => Self::TimedOut,
+                _ => Self::Io,
+            },
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Represents the part of the library an [OsError] originated from.
+///
+/// Selects the [Display](fmt::Display) prefix, matching the other [Error] variants.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorScope {
+    Udev,
+    Device,
+    Hwdb,
+    Monitor,
+    Enumerate,
+    Queue,
+    Util,
+    Builtin,
+    Node,
+    #[default]
+    Io,
+}
+
+impl ErrorScope {
+    /// Gets the [Display](fmt::Display) prefix of the [ErrorScope].
+    pub const fn prefix(&self) -> &'static str {
+        match self {
+            Self::Udev => "udev: ",
+            Self::Device => "udev device: ",
+            Self::Hwdb => "udev hwdb: ",
+            Self::Monitor => "udev monitor: ",
+            Self::Enumerate => "udev enumerate: ",
+            Self::Queue => "udev queue: ",
+            Self::Util => "udev util: ",
+            Self::Builtin => "udev builtin: ",
+            Self::Node => "udev node: ",
+            Self::Io => "I/O: ",
         }
     }
 }
+
+/// Failed system call, or I/O operation, preserving the underlying [io::Error].
+#[derive(Clone, Debug)]
+pub struct OsError {
+    scope: ErrorScope,
+    msg: String,
+    source: Arc<io::Error>,
+}
+
+impl OsError {
+    /// Creates a new [OsError].
+    pub fn new<M: Into<String>>(scope: ErrorScope, msg: M, err: io::Error) -> Self {
+        Self {
+            scope,
+            msg: msg.into(),
+            source: Arc::new(err),
+        }
+    }
+
+    /// Gets the [ErrorScope] of the [OsError].
+    pub const fn scope(&self) -> ErrorScope {
+        self.scope
+    }
+
+    /// Gets the message of the [OsError].
+    pub fn message(&self) -> &str {
+        self.msg.as_str()
+    }
+
+    /// Gets the underlying [io::Error].
+    pub fn io_error(&self) -> &io::Error {
+        self.source.as_ref()
+    }
+
+    /// Gets the `errno` of the failed system call, if any.
+    pub fn errno(&self) -> Option<i32> {
+        self.source.raw_os_error()
+    }
+
+    /// Gets the [ErrorKind] of the [OsError].
+    pub fn kind(&self) -> ErrorKind {
+        ErrorKind::from(self.io_error())
+    }
+}
+
+impl PartialEq for OsError {
+    fn eq(&self, oth: &Self) -> bool {
+        self.scope == oth.scope
+            && self.msg == oth.msg
+            && self.source.kind() == oth.source.kind()
+            && self.errno() == oth.errno()
+    }
+}
+
+impl fmt::Display for OsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.scope.prefix(), self.msg)
+    }
+}
+
+/// Represents the operation, file path, and device of a failed operation.
+///
+/// Attached to an [Error] with [with_context](Error::with_context), so failures can be grouped
+/// by device, or file, e.g. in log aggregation, without parsing the messages.
+#[repr(C)]
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ErrorContext {
+    operation: String,
+    path: Option<path::PathBuf>,
+    syspath: Option<String>,
+}
+
+impl ErrorContext {
+    /// Creates a new [ErrorContext] for the `operation`, e.g. `read_db`.
+    pub fn new<O: Into<String>>(operation: O) -> Self {
+        Self {
+            operation: operation.into(),
+            path: None,
+            syspath: None,
+        }
+    }
+
+    /// Gets the name of the failed operation.
+    pub fn operation(&self) -> &str {
+        self.operation.as_str()
+    }
+
+    /// Gets the path of the accessed file, if any.
+    pub fn path(&self) -> Option<&path::Path> {
+        self.path.as_deref()
+    }
+
+    /// Sets the path of the accessed file.
+    pub fn set_path<P: Into<path::PathBuf>>(&mut self, path: P) {
+        self.path = Some(path.into());
+    }
+
+    /// Builder function that sets the path of the accessed file.
+    pub fn with_path<P: Into<path::PathBuf>>(mut self, path: P) -> Self {
+        self.set_path(path);
+        self
+    }
+
+    /// Gets the syspath of the accessed device, if any.
+    pub fn syspath(&self) -> Option<&str> {
+        self.syspath.as_deref()
+    }
+
+    /// Sets the syspath of the accessed device, an empty syspath is ignored.
+    pub fn set_syspath<S: Into<String>>(&mut self, syspath: S) {
+        self.syspath = Some(syspath.into()).filter(|s| !s.is_empty());
+    }
+
+    /// Builder function that sets the syspath of the accessed device.
+    pub fn with_syspath<S: Into<String>>(mut self, syspath: S) -> Self {
+        self.set_syspath(syspath);
+        self
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "operation: {}", self.operation)?;
+        if let Some(path) = self.path() {
+            write!(f, ", path: {}", path.display())?;
+        }
+        if let Some(syspath) = self.syspath() {
+            write!(f, ", syspath: {syspath}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_kind() {
+        let again = io::Error::from_raw_os_error(libc::EAGAIN);
+        let err = Error::os(
+            ErrorScope::Monitor,
+            format!("unable to receive message: {again}"),
+            again,
+        );
+
+        assert!(err.is_would_block());
+        assert!(!err.is_permission());
+        assert_eq!(err.errno(), Some(libc::EAGAIN));
+        assert_eq!(
+            err.io_error().and_then(|e| e.raw_os_error()),
+            Some(libc::EAGAIN)
+        );
+        assert!(std::error::Error::source(&err).is_some());
+        assert!(err
+            .to_string()
+            .starts_with("udev monitor: unable to receive message: "));
+
+        for (errno, kind) in [
+            (libc::EPERM, ErrorKind::PermissionDenied),
+            (libc::EACCES, ErrorKind::PermissionDenied),
+            (libc::ENODEV, ErrorKind::NotFound),
+            (libc::EINTR, ErrorKind::Interrupted),
+            (libc::ENOBUFS, ErrorKind::Overflow),
+            (libc::EIO, ErrorKind::Io),
+        ] {
+            let err = Error::from(io::Error::from_raw_os_error(errno));
+            assert_eq!(err.kind(), kind);
+            assert_eq!(err.errno(), Some(errno));
+        }
+
+        let eof = Error::from(io::Error::from(io::ErrorKind::UnexpectedEof));
+        assert_eq!(eof.kind(), ErrorKind::Io);
+        assert_eq!(eof.errno(), None);
+        assert!(eof.to_string().starts_with("I/O: "));
+
+        let filtered = Error::Filtered("device did not pass filter".into());
+        assert_eq!(filtered.kind(), ErrorKind::Filtered);
+        assert_eq!(
+            filtered.to_string(),
+            "udev monitor: device did not pass filter"
+        );
+
+        assert_eq!(Error::InvalidLen(1).kind(), ErrorKind::InvalidInput);
+        assert_eq!(Error::UdevDevice("bad".into()).kind(), ErrorKind::Other);
+        assert_eq!(Error::UdevDevice("bad".into()).errno(), None);
+    }
+
+    #[test]
+    fn test_error_context() {
+        let noent = io::Error::from_raw_os_error(libc::ENOENT);
+        let err = Error::os(ErrorScope::Device, "unable to open DB file", noent)
+            .with_context(ErrorContext::new("read_db").with_path("/run/udev/data/b8:0"))
+            .with_context(ErrorContext::new("load").with_syspath("/sys/devices/virtual/block/sda"));
+
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert_eq!(err.errno(), Some(libc::ENOENT));
+        assert_eq!(err.path(), Some(path::Path::new("/run/udev/data/b8:0")));
+        assert_eq!(err.syspath(), Some("/sys/devices/virtual/block/sda"));
+        assert_eq!(err.context().map(|c| c.operation()), Some("load"));
+        assert!(matches!(err.without_context(), Error::Os(_)));
+        assert_eq!(
+            err.to_string(),
+            "udev device: unable to open DB file (operation: read_db, path: /run/udev/data/b8:0) (operation: load, syspath: /sys/devices/virtual/block/sda)"
+        );
+
+        let source = std::error::Error::source(&err).map(|e| e.to_string());
+        assert!(
+            source.is_some_and(|s| s.ends_with("(operation: read_db, path: /run/udev/data/b8:0)"))
+        );
+
+        let fixture = crate::testing::SysfsFixture::new().unwrap();
+        let run = path::Path::new(fixture.run_path());
+        let mut dev = crate::UdevDevice::new(fixture.udev())
+            .with_syspath("/sys/devices/virtual/mem/null")
+            .with_id_filename("c1:3");
+
+        let err = dev.read_db().err();
+        assert_eq!(err.as_ref().map(Error::kind), Some(ErrorKind::NotFound));
+        assert_eq!(
+            err.as_ref().and_then(Error::path),
+            Some(run.join("data/c1:3").as_path())
+        );
+        assert_eq!(
+            err.as_ref().and_then(Error::syspath),
+            Some("/sys/devices/virtual/mem/null")
+        );
+        // the path is only displayed by the context
+        let msg = err.as_ref().map(Error::to_string).unwrap_or_default();
+        assert_eq!(msg.matches("data/c1:3").count(), 1, "{msg}");
+
+        let hwdb = run.join("hwdb.bin");
+        let err = crate::UdevHwdb::open_path(
+            Arc::new(crate::Udev::new()),
+            hwdb.to_str().unwrap_or_default(),
+        )
+        .err();
+        assert_eq!(err.as_ref().map(Error::kind), Some(ErrorKind::NotFound));
+        assert_eq!(err.as_ref().and_then(Error::path), Some(hwdb.as_path()));
+
+        let plain = Error::UdevDevice("bad".into());
+        assert_eq!(plain.path(), None);
+        assert_eq!(plain.context(), None);
+        assert_eq!(plain.without_context(), &plain);
+    }
+}
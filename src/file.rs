@@ -1,6 +1,6 @@
 use std::{ffi, io};
 
-use crate::{Error, Result};
+use crate::{Error, ErrorContext, ErrorScope, Result};
 
 /// Represents an FFI type from `fcntl.h` for a `file_handle`.
 #[repr(C)]
@@ -75,6 +75,7 @@ pub fn name_to_handle_at(
 
         log::warn!("{errmsg}");
 
-        Err(Error::Io(errmsg))
+        Err(Error::os(ErrorScope::Io, errmsg, errno)
+            .with_context(ErrorContext::new("name_to_handle_at").with_path(path)))
     }
 }
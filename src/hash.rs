@@ -0,0 +1,124 @@
+//! Hash functions of the `libudev` monitor protocol.
+//!
+//! `libudev` monitors send the hashes of the device subsystem, and devtype, and a bloom filter
+//! of the device tags, in the [UdevMonitorNetlinkHeader](crate::UdevMonitorNetlinkHeader), so
+//! receivers can drop messages in a socket filter program, see
+//! [build_filter](crate::build_filter).
+//!
+//! The functions return the same values as `libudev` on the same host, so they can be used to
+//! build custom socket filters, or to match headers sent by other `libudev` implementations.
+//!
+//! Like `libudev`, [murmur_hash2] reads the input in native byte order, so the hashes differ
+//! between little-endian, and big-endian hosts.
+
+/// Computes the 32-bit `MurmurHash2` of the `key`, with the `seed`.
+///
+/// MurmurHash2 was written by Austin Appleby, and is placed in the public domain. The author
+/// hereby disclaims copyright to this source code.
+///
+/// Produces the same results as the `MurmurHash2.c` in `libudev`. The function does not work
+/// incrementally.
+pub fn murmur_hash2(key: &[u8], seed: u32) -> u32 {
+    // 'm' and 'r' are mixing constants generated offline.
+    // They're not really 'magic', they just happen to work well.
+    const M: u32 = 0x5bd1_e995;
+    const R: u32 = 24;
+
+    // initialize the hash to a 'random' value
+    let mut h = seed ^ (key.len() as u32);
+
+    // mix 4 bytes at a time into the hash
+    let mut chunks = key.chunks_exact(4);
+    for data in chunks.by_ref() {
+        let mut k = u32::from_ne_bytes([data[0], data[1], data[2], data[3]]);
+
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+
+        h = h.wrapping_mul(M);
+        h ^= k;
+    }
+
+    // handle the last few bytes of the input array
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        for (i, &b) in tail.iter().enumerate().rev() {
+            h ^= u32::from(b) << (8 * i);
+        }
+        h = h.wrapping_mul(M);
+    }
+
+    // do a few final mixes of the hash to ensure the last few bytes are well-incorporated
+    h ^= h >> 13;
+    h = h.wrapping_mul(M);
+    h ^= h >> 15;
+
+    h
+}
+
+/// Computes the hash of a string, as used for the subsystem, and devtype, header fields.
+pub fn string_hash32(s: &str) -> u32 {
+    murmur_hash2(s.as_bytes(), 0)
+}
+
+/// Computes the bloom filter bits of a string, as used for the tag header fields.
+///
+/// Sets four bits, selected by 6-bit slices of the [string_hash32] of the string.
+pub fn string_bloom64(s: &str) -> u64 {
+    let hash = string_hash32(s);
+
+    (1u64 << (hash & 63))
+        | (1u64 << ((hash >> 6) & 63))
+        | (1u64 << ((hash >> 12) & 63))
+        | (1u64 << ((hash >> 18) & 63))
+}
+
+/// Computes the tag bloom filter of a device with the `tags`.
+///
+/// Combines the [string_bloom64] bits of all tags, like the header sent by `libudev`.
+pub fn tag_bloom_bits(tags: &[&str]) -> u64 {
+    tags.iter().fold(0, |bits, tag| bits | string_bloom64(tag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash() {
+        // generated with the `MurmurHash2.c`, and `util_string_bloom64` from `eudev`
+        let vectors = [
+            ("", 0x0000_0000, 0x0000_0000_0000_0001),
+            ("a", 0x9268_5f5e, 0x2000_0000_4400_0020),
+            ("ab", 0x1aa1_4063, 0x0000_0108_0010_0002),
+            ("abc", 0x1357_7c9b, 0x0084_0000_0820_0000),
+            ("abcd", 0x2687_3021, 0x0008_0002_0000_0001),
+            ("block", 0xf003_1db7, 0x00c2_0000_0000_0001),
+            ("disk", 0x7bcb_c5ee, 0x1004_4000_0080_0000),
+            ("net", 0xa74d_3cc8, 0x0008_0000_0008_0100),
+            ("usb_device", 0x27f8_f50c, 0x4000_0000_0010_9000),
+            ("systemd", 0xa75f_972a, 0x0200_0400_1080_0000),
+            ("uaccess", 0xe88e_d0cc, 0x0000_2008_0000_1008),
+            ("seat", 0x435b_3e40, 0x0208_0000_0040_0001),
+        ];
+
+        for (s, hash, bloom) in vectors {
+            if cfg!(target_endian = "little") {
+                assert_eq!(string_hash32(s), hash, "{s}");
+                assert_eq!(string_bloom64(s), bloom, "{s}");
+            }
+            assert!(string_bloom64(s).count_ones() <= 4);
+        }
+
+        if cfg!(target_endian = "little") {
+            assert_eq!(murmur_hash2(b"hello", 0x9747_b28c), 0x7f1d_dbbd);
+            assert_eq!(
+                tag_bloom_bits(&["systemd", "uaccess", "seat"]),
+                0x0208_2408_10c0_1009
+            );
+        }
+
+        assert_eq!(tag_bloom_bits(&[]), 0);
+    }
+}
@@ -1,63 +1,88 @@
-use std::io::{self, Read};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::{env, fs, mem};
+use std::{env, mem};
 
-use crate::{Error, Result, Udev, UdevEntry, UdevList};
+use crate::metrics::{Metric, MetricsTimer};
+use crate::{Error, Result, Udev, UdevDevice, UdevEntry, UdevList};
 
+mod file;
 mod line;
+mod map;
+mod stack;
+mod stats;
 mod trie;
 
+pub use file::*;
 pub use line::*;
+pub use stack::*;
+pub use stats::*;
 pub use trie::*;
 
-static NODE_SIZE: AtomicUsize = AtomicUsize::new(24);
-static CHILD_ENTRY_SIZE: AtomicUsize = AtomicUsize::new(16);
-static VALUE_ENTRY_SIZE: AtomicUsize = AtomicUsize::new(32);
+use map::HwdbBuf;
 
-/// Gets the [Node](TrieNode) size loaded from the [TrieHeader].
-pub fn node_size() -> usize {
-    NODE_SIZE.load(Ordering::Relaxed)
-}
+/// Runtime environment variable with the path of a `hwdb.bin` file, searched before the
+/// default locations, e.g. in tests.
+pub const HWDB_BIN_ENV: &str = "UDEV_HWDB_BIN";
 
-pub(crate) fn set_node_size(val: usize) {
-    NODE_SIZE.store(val, Ordering::SeqCst);
-}
+/// Path of the `hwdb.bin` file relative to `$HOME`, searched after [HWDB_BIN_ENV].
+pub const HWDB_HOME_PATH: &str = ".config/systemd/hwdb/hwdb.bin";
 
-/// Gets the [ChildEntry](TrieChildEntry) size loaded from the [TrieHeader].
-pub fn child_entry_size() -> usize {
-    CHILD_ENTRY_SIZE.load(Ordering::Relaxed)
+/// Gets the `hwdb.bin` locations of the [Udev] context, in search order.
+///
+/// The locations are the overrides for tests, followed by the `sd-hwdb` locations:
+///
+/// - the [HWDB_BIN_ENV] environment variable, if set
+/// - [HWDB_HOME_PATH] in `$HOME`, if set, e.g. to a scratch directory in tests
+/// - `/etc/systemd/hwdb/hwdb.bin`, and `/etc/udev/hwdb.bin`, in the `UDEVRS_SYSCONF_DIR` set at
+///   build time
+/// - `/usr/lib/systemd/hwdb/hwdb.bin`
+/// - `hwdb.bin` in the [libexec path](Udev::get_libexec_path)
+///
+/// Earlier locations take precedence: [UdevHwdb::open] uses the first existing file, and
+/// [HwdbStack::open] merges all of them.
+pub fn hwdb_bin_paths(udev: &Udev) -> Vec<String> {
+    hwdb_bin_paths_with(udev, |var| env::var(var).ok())
 }
 
-pub(crate) fn set_child_entry_size(val: usize) {
-    CHILD_ENTRY_SIZE.store(val, Ordering::SeqCst);
+// Gets the `hwdb.bin` locations, reading the environment variables with `lookup`.
+fn hwdb_bin_paths_with<F: Fn(&str) -> Option<String>>(udev: &Udev, lookup: F) -> Vec<String> {
+    let sysconf = env!("UDEVRS_SYSCONF_DIR");
+
+    let bin_env = lookup(HWDB_BIN_ENV).filter(|path| !path.is_empty());
+    let home = lookup("HOME")
+        .filter(|home| !home.is_empty())
+        .map(|home| format!("{}/{HWDB_HOME_PATH}", home.trim_end_matches('/')));
+
+    let mut paths = Vec::new();
+
+    for path in bin_env.into_iter().chain(home).chain([
+        format!("{sysconf}/systemd/hwdb/hwdb.bin"),
+        format!("{sysconf}/udev/hwdb.bin"),
+        "/usr/lib/systemd/hwdb/hwdb.bin".into(),
+        format!("{}/hwdb.bin", udev.get_libexec_path()),
+    ]) {
+        // the libexec path may be one of the fixed locations
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+
+    paths
 }
 
-/// Gets the [ValueEntry](TrieValueEntry) size loaded from the [TrieHeader].
-pub fn value_entry_size() -> usize {
-    VALUE_ENTRY_SIZE.load(Ordering::Relaxed)
+fn get_hwdb_bin_paths(udev: &Udev) -> String {
+    hwdb_bin_paths(udev).join("\0")
 }
 
-pub(crate) fn set_value_entry_size(val: usize) {
-    VALUE_ENTRY_SIZE.store(val, Ordering::SeqCst);
+fn sysattr_value(dev: &mut UdevDevice, attr: &str) -> Option<String> {
+    dev.get_sysattr_value(attr)
+        .map(|v| v.trim_end().to_owned())
+        .filter(|v| !v.is_empty())
 }
 
-#[cfg(target_os = "linux")]
-const UDEV_LIBEXEC_DIR: &str = "/usr/lib/udev";
-// FIXME: add udev libexec dirs for other OSes
-
-fn get_hwdb_bin_paths() -> String {
-    const DEFAULT_LOCATIONS: [&str; 2] = ["/etc/udev", UDEV_LIBEXEC_DIR];
-
-    if let Ok(by_env) = env::var("UDEV_HWDB_BIN") {
-        DEFAULT_LOCATIONS
-            .iter()
-            .fold(by_env, |path, loc| format!("{path}\0{loc}/hwdb.bin"))
-    } else {
-        DEFAULT_LOCATIONS
-            .iter()
-            .fold(String::new(), |path, loc| format!("{path}\0{loc}/hwdb.bin"))
-    }
+fn sysattr_hex(dev: &mut UdevDevice, attr: &str) -> Option<u32> {
+    let val = sysattr_value(dev, attr)?;
+    u32::from_str_radix(val.trim_start_matches("0x"), 16).ok()
 }
 
 /// Represents the on-disk hardware database.
@@ -68,82 +93,17 @@ pub struct UdevHwdb {
     udev: Arc<Udev>,
     bin_paths: String,
     hwdb_path: String,
+    map: HwdbBuf,
     head: TrieHeader,
     properties_list: UdevList,
 }
 
 impl UdevHwdb {
     /// Creates a new [UdevHwdb].
+    ///
+    /// Convenience for [open](Self::open), followed by [load_mmap](UdevHwdbFile::load_mmap).
     pub fn new(udev: Arc<Udev>) -> Result<Self> {
-        let mut hwdb_path = String::new();
-        let bin_paths = get_hwdb_bin_paths();
-
-        let (head, metadata) = {
-            // In the original `libudev`, they `mmap` the entire on-disk database into a `const char *`
-            // union, which leads to inherently unsafe access in Rust.
-            //
-            // Instead, we'll just parse the header for now, which advances the `File` struct's internal
-            // cursor, and delay further parsing for subsequent calls to the various node entry, and value calls.
-            //
-            // Alternatively, we could parse the properties list now, and avoid keeping the file
-            // struct, file metadata, and `TrieHeader` in the `UdevHwdb` struct. Instead, we would just
-            // keep the parsed `properties_list`.
-            //
-            // TBD.
-
-            let mut bin_file: Option<fs::File> = None;
-
-            for path in bin_paths.split('\0') {
-                if let Ok(f) = fs::OpenOptions::new().read(true).open(path) {
-                    bin_file = Some(f);
-                    path.clone_into(&mut hwdb_path);
-                    break;
-                }
-                let errno = io::Error::last_os_error();
-                if errno.raw_os_error() == Some(libc::ENOENT) {
-                    Ok(())
-                } else {
-                    Err(Error::UdevHwdb(format!(
-                        "error reading {path}, errno: {errno}"
-                    )))
-                }?;
-            }
-
-            let mut file = bin_file.ok_or(Error::UdevHwdb(
-                "unable to find hwdb.bin database file".into(),
-            ))?;
-
-            let metadata = file.metadata()?;
-            let mut hwdb_head_buf = [0u8; mem::size_of::<TrieHeader>()];
-
-            file.read_exact(&mut hwdb_head_buf)?;
-
-            (TrieHeader::try_from(hwdb_head_buf.as_ref())?, metadata)
-        };
-
-        let properties_list = UdevList::new(Arc::clone(&udev));
-
-        set_node_size(head.node_size() as usize);
-        set_child_entry_size(head.child_entry_size() as usize);
-        set_value_entry_size(head.value_entry_size() as usize);
-
-        log::debug!("=== trie on-disk ===");
-        log::debug!("tool version:           {}", head.tool_version());
-        log::debug!("file size:         {:8} bytes", metadata.len());
-        log::debug!("header size:       {:8} bytes", head.header_size());
-        log::debug!("node size:         {:8} bytes", head.node_size());
-        log::debug!("child size:        {:8} bytes", head.child_entry_size());
-        log::debug!("value size:        {:8} bytes", head.value_entry_size());
-        log::debug!("strings:           {:8} bytes", head.strings_len());
-        log::debug!("nodes:             {:8} bytes", head.nodes_len());
-
-        Ok(Self {
-            udev,
-            bin_paths,
-            hwdb_path,
-            head,
-            properties_list,
-        })
+        Self::open(udev)?.load_mmap()
     }
 
     /// Gets a reference to the [TrieHeader].
@@ -151,6 +111,18 @@ impl UdevHwdb {
         &self.head
     }
 
+    /// Gets the path of the loaded `hwdb.bin` file.
+    pub fn hwdb_path(&self) -> &str {
+        self.hwdb_path.as_str()
+    }
+
+    /// Gets the contents of the `hwdb.bin` file, memory-mapped, or read into memory.
+    ///
+    /// Can be used with [parse_nodes](Self::parse_nodes) to walk the trie without copying.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.map
+    }
+
     /// Looks up a matching device in the hardware database.
     ///
     /// Parameters:
@@ -168,50 +140,15 @@ impl UdevHwdb {
     ///
     /// Returns: an optional reference to an [UdevEntry].
     pub fn get_properties_list_entry(&mut self, modalias: &str, _flags: u32) -> Option<&UdevEntry> {
-        // For now, do the naive thing, and read the entire HWDB into memory (12M+!!!)
-        //
-        // Using the BufReader to jump around to all the various offsets will probably be
-        // more efficient, but harder to follow. BufReader only supports relative `Seek`ing.
-        //
-        // Nodes are also not sequential in the on-disk format, which would make parsing
-        // easier, but lose some of the structure of the HWDB. According to the man page
-        // (`man 7 hwdb`), entries later in the HWDB have higher priority, which some tools
-        // may rely on.
+        // According to the man page (`man 7 hwdb`), entries later in the HWDB have higher
+        // priority, which some tools may rely on.
         //
         // `libudev` does not appear to track priority.
-        //
-        // Loading everything into memory at one time also avoids some other tool updating the
-        // HWDB while we are parsing it.
-        let file = fs::OpenOptions::new()
-            .read(true)
-            .open(&self.hwdb_path)
-            .map_err(|err| {
-                log::warn!("unable to open HWDB file: {err}");
-            })
-            .ok()?;
-
-        let metadata = file
-            .metadata()
-            .map_err(|err| {
-                log::warn!("unable to get HWDB metadata: {err}");
-            })
-            .ok()?;
-
-        let file_len = metadata.len() as usize;
-
-        let mut reader = io::BufReader::new(file);
-        let mut hwdb_buf = Vec::with_capacity(file_len);
-
-        reader
-            .read_to_end(&mut hwdb_buf)
-            .map_err(|err| {
-                log::warn!("error reading HWDB into memory: {err}");
-            })
-            .ok()?;
+        let _timer = MetricsTimer::start(Metric::HwdbLookupDuration);
 
         self.properties_list.clear();
 
-        Self::trie_search(&mut self.properties_list, &self.head, &hwdb_buf, modalias)
+        Self::trie_search(&mut self.properties_list, &self.head, &self.map, modalias)
             .map_err(|err| {
                 log::warn!("error looking up property list UdevEntry: {err}");
             })
@@ -226,21 +163,121 @@ impl UdevHwdb {
         Some(self.properties_list())
     }
 
+    /// Looks up the properties of a [UdevDevice] in the hardware database.
+    ///
+    /// Like `udev-builtin-hwdb`, the device and its parents are searched for a modalias (see
+    /// [compose_modalias](Self::compose_modalias)), and the first modalias with matching
+    /// properties is used. The search stops at USB devices, since their parents are usually just
+    /// a USB hub.
+    ///
+    /// Returns: an optional reference to the matched [properties list](UdevList).
+    pub fn get_properties_for_device(&mut self, dev: &mut UdevDevice) -> Option<&UdevList> {
+        let mut modaliases = Vec::new();
+
+        let (modalias, last) = Self::device_modalias(dev);
+        modaliases.extend(modalias);
+
+        let mut parent = if last {
+            None
+        } else {
+            dev.new_from_parent().ok()
+        };
+
+        while let Some(mut dev) = parent {
+            let (modalias, last) = Self::device_modalias(&mut dev);
+            modaliases.extend(modalias);
+
+            parent = if last {
+                None
+            } else {
+                dev.new_from_parent().ok()
+            };
+        }
+
+        for modalias in modaliases {
+            log::trace!("Searching HWDB for device modalias: {modalias}");
+            if self
+                .get_properties_list_entry(modalias.as_str(), 0)
+                .is_some()
+            {
+                return Some(self.properties_list());
+            }
+        }
+
+        None
+    }
+
+    // Gets the device modalias, and whether to stop searching parent devices.
+    fn device_modalias(dev: &mut UdevDevice) -> (Option<String>, bool) {
+        let last = dev.get_subsystem() == "usb" && dev.get_devtype() == "usb_device";
+        (Self::compose_modalias(dev), last)
+    }
+
+    /// Gets the modalias of a [UdevDevice].
+    ///
+    /// Uses the `MODALIAS` property, or the `modalias` sys attribute, if available. Otherwise,
+    /// composes the modalias from the device identification attributes:
+    ///
+    /// - USB devices: `usb:v<idVendor>p<idProduct>:<product>`
+    /// - PCI devices: `pci:v<vendor>d<device>`
+    /// - input devices: `input:b<bustype>v<vendor>p<product>e<version>`
+    ///
+    /// Returns: `Some(String)` with the modalias, `None` if no modalias could be found.
+    pub fn compose_modalias(dev: &mut UdevDevice) -> Option<String> {
+        if let Some(modalias) = dev.get_property_value("MODALIAS") {
+            return Some(modalias.to_owned());
+        }
+
+        if dev.syspath().is_empty() {
+            return None;
+        }
+
+        if let Some(modalias) = sysattr_value(dev, "modalias") {
+            return Some(modalias);
+        }
+
+        match dev.get_subsystem() {
+            "usb" => {
+                let vendor = sysattr_hex(dev, "idVendor")?;
+                let product = sysattr_hex(dev, "idProduct")?;
+                let name = sysattr_value(dev, "product").unwrap_or_default();
+
+                Some(format!("usb:v{vendor:04X}p{product:04X}:{name}"))
+            }
+            "pci" => {
+                let vendor = sysattr_hex(dev, "vendor")?;
+                let device = sysattr_hex(dev, "device")?;
+
+                Some(format!("pci:v{vendor:08X}d{device:08X}"))
+            }
+            "input" => {
+                let bustype = sysattr_hex(dev, "id/bustype")?;
+                let vendor = sysattr_hex(dev, "id/vendor")?;
+                let product = sysattr_hex(dev, "id/product")?;
+                let version = sysattr_hex(dev, "id/version")?;
+
+                Some(format!(
+                    "input:b{bustype:04X}v{vendor:04X}p{product:04X}e{version:04X}"
+                ))
+            }
+            _ => None,
+        }
+    }
+
     /// Gets a reference to the [properties list](UdevList).
     pub const fn properties_list(&self) -> &UdevList {
         &self.properties_list
     }
 
     /// Adds a key-value pair to the property list.
+    ///
+    /// Only keys starting with a space are added, without the space, like the database
+    /// properties. An existing value is replaced, there is no source priority to compare, see
+    /// [query](Self::query) for the priority handling of database lookups.
     pub fn add_property(&mut self, key: &str, value: &str) -> Result<()> {
-        Self::_add_property(&mut self.properties_list, key, value)
-    }
-
-    pub(crate) fn _add_property(list: &mut UdevList, key: &str, value: &str) -> Result<()> {
         if let Some(nkey) = key.strip_prefix(' ') {
-            // TODO - should check priority if existing: https://github.com/systemd/systemd/blob/main/src/libsystemd/sd-hwdb/sd-hwdb.c#L134
-            // add_entry if UdevList.unique (default) will replace currently
-            list.add_entry(nkey, value)
+            self.properties_list
+                .add_entry(nkey, value)
                 .map(|_| ())
                 .ok_or(Error::UdevHwdb("unable to add property".into()))
         } else {
@@ -251,24 +288,24 @@ impl UdevHwdb {
     }
 
     /// Parses all [TrieEntry] nodes from an in-memory HWDB buffer.
+    ///
+    /// Stops at the end of the nodes section, or at the first invalid [TrieEntry].
     pub fn parse_nodes<'a>(
         head: &'a TrieHeader,
         hwdb_buf: &'a [u8],
     ) -> impl Iterator<Item = TrieEntry> + 'a {
-        let nodes_len = head.nodes_len() as usize;
+        let reader = TrieReader::new(hwdb_buf).with_sizes(head.into());
         let node_start = mem::size_of::<TrieHeader>();
-        let node_end = node_start.saturating_add(nodes_len);
-
-        let buf_len = hwdb_buf.len();
+        let node_end = node_start
+            .saturating_add(head.nodes_len() as usize)
+            .min(reader.len());
 
         let mut idx = node_start;
 
         std::iter::from_fn(move || {
-            if (0..buf_len).contains(&node_start)
-                && (0..buf_len).contains(&node_end)
-                && idx < nodes_len
-            {
-                TrieEntry::try_from(&hwdb_buf[idx..])
+            if idx < node_end {
+                reader
+                    .read_entry(idx)
                     .inspect(|entry| {
                         idx = idx.saturating_add(entry.len());
                     })
@@ -282,6 +319,44 @@ impl UdevHwdb {
         })
     }
 
+    /// Adds the property of a matching [TrieValueEntry] to the property list.
+    ///
+    /// Like `sd-hwdb`, duplicate keys keep the value with the highest priority, ordered by source
+    /// file priority and line number. `sources` tracks the [TrieValueEntry] of every added key.
+    pub(crate) fn add_value_entry(
+        list: &mut UdevList,
+        sources: &mut HashMap<String, TrieValueEntry>,
+        hwdb_buf: &[u8],
+        sizes: TrieSizes,
+        value: &TrieValueEntry,
+    ) -> Result<()> {
+        let key = trie_string(hwdb_buf, value.key_off() as usize)?;
+
+        // Silently ignore all properties which do not start with a
+        // space; future extensions might use additional prefixes.
+        let Some(key) = key.strip_prefix(' ') else {
+            return Ok(());
+        };
+
+        if sizes.has_value_sources() {
+            if let Some(old) = sources.get(key) {
+                if value.is_lower_priority(old) {
+                    log::trace!("Skipping lower priority property: {key}");
+                    return Ok(());
+                }
+            }
+        }
+
+        let val = trie_string(hwdb_buf, value.value_off() as usize)?;
+
+        log::trace!("Matching property, key: {key}, value: {val}");
+        list.add_entry(key, val)
+            .ok_or(Error::UdevHwdb("unable to add property".into()))?;
+        sources.insert(key.into(), *value);
+
+        Ok(())
+    }
+
     fn trie_search(
         list: &mut UdevList,
         head: &TrieHeader,
@@ -289,59 +364,110 @@ impl UdevHwdb {
         search: &str,
     ) -> Result<()> {
         let mut line_buf = LineBuf::new();
+        let mut sources = HashMap::new();
+        let search_bytes = search.as_bytes();
+        let search_at = |idx: usize| search_bytes.get(idx).copied().unwrap_or(0);
+        let search_from = |idx: usize| search.get(idx..).unwrap_or("");
+
         let mut i = 0usize;
-        let nodes_root_off = head.nodes_root_off() as usize;
 
-        let mut node = if nodes_root_off < hwdb_buf.len() {
-            TrieEntry::try_from(&hwdb_buf[nodes_root_off..]).ok()
-        } else {
-            None
-        };
+        let mut node = Some(
+            TrieReader::new(hwdb_buf)
+                .with_sizes(head.into())
+                .root_entry(head)?,
+        );
 
         log::trace!("Search term: {search}");
 
         while let Some(n) = node {
             if n.node().prefix_off() > 0 {
-                let prefix_off = n.node().prefix_off() as usize;
-                let ts = trie_string(hwdb_buf, prefix_off)?;
-
-                for (p, c) in ts.chars().enumerate() {
-                    if c == '*' || c == '?' || c == '[' {
-                        return line_buf.trie_fnmatch(list, hwdb_buf, &n, p, &search[i + p..]);
+                let prefix = trie_string(hwdb_buf, n.node().prefix_off() as usize)?;
+
+                for (p, c) in prefix.bytes().enumerate() {
+                    if c == b'*' || c == b'?' || c == b'[' {
+                        return line_buf.trie_fnmatch(
+                            list,
+                            &mut sources,
+                            hwdb_buf,
+                            &n,
+                            p,
+                            search_from(i + p),
+                        );
                     }
 
-                    if search.chars().nth(i + p) != Some(c) {
+                    if search_at(i + p) != c {
                         return Ok(());
                     }
                 }
 
-                i = i.saturating_add(ts.chars().count());
+                i = i.saturating_add(prefix.len());
             }
 
             for wildcard in [b'*', b'?', b'['] {
                 if let Some(child) = n.lookup_child(hwdb_buf, wildcard) {
                     line_buf.add_char(wildcard)?;
-                    log::trace!("wildcard ({wildcard:?}) child match: child: {child:?}");
-                    line_buf.trie_fnmatch(list, hwdb_buf, &child, 0, &search[i..])?;
+                    log::trace!("wildcard ({}) child match", wildcard as char);
+                    line_buf.trie_fnmatch(
+                        list,
+                        &mut sources,
+                        hwdb_buf,
+                        &child,
+                        0,
+                        search_from(i),
+                    )?;
                     line_buf.remove_char();
                 }
             }
 
-            if search.chars().nth(i) == Some('\0') {
+            if i >= search_bytes.len() {
                 for value in n.values().iter() {
-                    let key_str = trie_string(hwdb_buf, value.key_off() as usize)?;
-                    let val_str = trie_string(hwdb_buf, value.value_off() as usize)?;
-
-                    log::trace!("Matching property, key: {key_str}, value: {val_str}");
-                    Self::_add_property(list, key_str, val_str)?;
+                    Self::add_value_entry(list, &mut sources, hwdb_buf, n.sizes(), value)?;
                 }
+                return Ok(());
             }
 
-            node = n.lookup_child(hwdb_buf, *search.as_bytes().get(i).unwrap_or(&0));
+            node = n.lookup_child(hwdb_buf, search_at(i));
             i = i.saturating_add(1);
-            log::trace!("No match found, searching next child[{i}]: {node:?}");
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hwdb_bin_paths() {
+        let udev = Udev::new().with_libexec_path("/opt/udev/lib");
+        let paths = get_hwdb_bin_paths(&udev);
+
+        assert!(paths.ends_with(
+            "/etc/systemd/hwdb/hwdb.bin\0/etc/udev/hwdb.bin\0/usr/lib/systemd/hwdb/hwdb.bin\0/opt/udev/lib/hwdb.bin"
+        ));
+
+        // the default libexec path is searched once
+        let udev = Udev::new().with_libexec_path("/usr/lib/systemd/hwdb");
+        assert!(hwdb_bin_paths(&udev).ends_with(&["/usr/lib/systemd/hwdb/hwdb.bin".into()]));
+
+        let paths = get_hwdb_bin_paths(&Udev::new());
+        assert!(paths.ends_with(format!("\0{}/hwdb.bin", Udev::new().get_libexec_path()).as_str()));
+
+        let udev = Udev::new().with_libexec_path("/opt/udev/lib");
+        let lookup = |vars: &[(&str, &str)]| {
+            let vars: HashMap<&str, &str> = vars.iter().copied().collect();
+            hwdb_bin_paths_with(&udev, |var| vars.get(var).map(|v| v.to_string()))
+        };
+
+        assert_eq!(lookup(&[]).len(), 4);
+        assert_eq!(lookup(&[("HOME", "")]).len(), 4);
+
+        // the environment variable comes first, then the home directory
+        let paths = lookup(&[(HWDB_BIN_ENV, "/tmp/hwdb.bin"), ("HOME", "/home/test/")]);
+        assert_eq!(paths.len(), 6);
+        assert_eq!(paths[0], "/tmp/hwdb.bin");
+        assert_eq!(paths[1], "/home/test/.config/systemd/hwdb/hwdb.bin");
+        assert_eq!(paths[5], "/opt/udev/lib/hwdb.bin");
+    }
+}
@@ -0,0 +1,295 @@
+use std::io::{self, Read};
+use std::sync::Arc;
+use std::{fs, mem};
+
+use super::map::{HwdbBuf, HwdbMap};
+use super::{
+    get_hwdb_bin_paths, TrieHeader, TrieNode, UdevHwdb, MIN_CHILD_ENTRY_SIZE, MIN_VALUE_ENTRY_SIZE,
+};
+use crate::{Error, ErrorContext, ErrorScope, Result, Udev, UdevList};
+
+/// Opened, but not yet loaded, on-disk hardware database.
+///
+/// Created by [UdevHwdb::open], or [UdevHwdb::open_path]. Opening only locates the `hwdb.bin`
+/// file, and reads and validates the [TrieHeader]. The heavy work of reading, or mapping, the
+/// database is done by [load](Self::load), or [load_mmap](Self::load_mmap), e.g. on a blocking
+/// thread pool in async applications.
+#[derive(Debug)]
+pub struct UdevHwdbFile {
+    udev: Arc<Udev>,
+    bin_paths: String,
+    path: String,
+    file: fs::File,
+    len: usize,
+    head: TrieHeader,
+}
+
+impl UdevHwdb {
+    /// Opens the on-disk hardware database.
+    ///
+    /// Searches the [hwdb_bin_paths](super::hwdb_bin_paths) of the context, e.g. the
+    /// `UDEV_HWDB_BIN` environment variable, `/etc/udev/hwdb.bin`, and `hwdb.bin` in the
+    /// [libexec path](Udev::get_libexec_path), in order, and opens the first existing file.
+    ///
+    /// Returns: `Ok(UdevHwdbFile)` on success, `Err(Error)` if no database file is found, or the
+    /// header is invalid.
+    pub fn open(udev: Arc<Udev>) -> Result<UdevHwdbFile> {
+        let bin_paths = get_hwdb_bin_paths(&udev);
+
+        for path in bin_paths.split('\0').filter(|p| !p.is_empty()) {
+            match fs::File::open(path) {
+                Ok(file) => {
+                    return UdevHwdbFile::new(udev, bin_paths.clone(), path, file)
+                        .map_err(|err| err.with_context(hwdb_context("open_hwdb", path)))
+                }
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => {
+                    return Err(read_error(err).with_context(hwdb_context("open_hwdb", path)))
+                }
+            }
+        }
+
+        Err(Error::UdevHwdb(
+            "unable to find hwdb.bin database file".into(),
+        ))
+    }
+
+    /// Opens the on-disk hardware database at `path`, without searching the default locations.
+    ///
+    /// Returns: `Ok(UdevHwdbFile)` on success, `Err(Error)` if the file cannot be opened, or the
+    /// header is invalid.
+    pub fn open_path(udev: Arc<Udev>, path: &str) -> Result<UdevHwdbFile> {
+        let context = || hwdb_context("open_hwdb", path);
+        let file = fs::File::open(path).map_err(|err| read_error(err).with_context(context()))?;
+
+        UdevHwdbFile::new(udev, path.into(), path, file).map_err(|err| err.with_context(context()))
+    }
+
+    /// Creates a new [UdevHwdb] from the contents of a `hwdb.bin` file held in memory.
+    ///
+    /// The [TrieHeader] is validated like [open_path](Self::open_path), and the trie is read
+    /// with bounds checks on lookup, so corrupt, or untrusted, contents return errors instead of
+    /// panicking, e.g. in fuzz targets.
+    ///
+    /// Returns: `Ok(UdevHwdb)` on success, `Err(Error)` if the header is invalid.
+    pub fn new_from_bytes(udev: Arc<Udev>, buf: Vec<u8>) -> Result<Self> {
+        let head = TrieHeader::try_from(buf.as_slice())?;
+        validate_header(&head, buf.len())?;
+
+        Ok(Self::from_buf(
+            udev,
+            String::new(),
+            String::new(),
+            head,
+            HwdbBuf::Owned(buf),
+        ))
+    }
+
+    fn from_buf(
+        udev: Arc<Udev>,
+        bin_paths: String,
+        hwdb_path: String,
+        head: TrieHeader,
+        buf: HwdbBuf,
+    ) -> Self {
+        log::trace!("=== trie on-disk ===");
+        log::trace!("tool version:           {}", head.tool_version());
+        log::trace!("file size:         {:8} bytes", buf.len());
+        log::trace!("header size:       {:8} bytes", head.header_size());
+        log::trace!("node size:         {:8} bytes", head.node_size());
+        log::trace!("child size:        {:8} bytes", head.child_entry_size());
+        log::trace!("value size:        {:8} bytes", head.value_entry_size());
+        log::trace!("strings:           {:8} bytes", head.strings_len());
+        log::trace!("nodes:             {:8} bytes", head.nodes_len());
+
+        let properties_list = UdevList::new(Arc::clone(&udev));
+
+        Self {
+            udev,
+            bin_paths,
+            hwdb_path,
+            map: buf,
+            head,
+            properties_list,
+        }
+    }
+
+    /// Creates a new [UdevHwdb] from the database file at `path`.
+    ///
+    /// Convenience for [open_path](Self::open_path), followed by
+    /// [load_mmap](UdevHwdbFile::load_mmap).
+    pub fn new_from_path(udev: Arc<Udev>, path: &str) -> Result<Self> {
+        Self::open_path(udev, path)?.load_mmap()
+    }
+}
+
+impl UdevHwdbFile {
+    fn new(udev: Arc<Udev>, bin_paths: String, path: &str, mut file: fs::File) -> Result<Self> {
+        let len = file.metadata()?.len() as usize;
+        let head_len = mem::size_of::<TrieHeader>();
+
+        if len < head_len {
+            return Err(Error::UdevHwdb(format!(
+                "invalid hwdb.bin file size: {len}, expected at least: {head_len}"
+            )));
+        }
+
+        let mut head_buf = [0u8; mem::size_of::<TrieHeader>()];
+        file.read_exact(&mut head_buf)?;
+
+        let head = TrieHeader::try_from(head_buf.as_ref())?;
+        validate_header(&head, len)?;
+
+        log::debug!("opened hwdb: {path}, size: {len} bytes");
+
+        Ok(Self {
+            udev,
+            bin_paths,
+            path: path.into(),
+            file,
+            len,
+            head,
+        })
+    }
+
+    /// Gets the path of the opened `hwdb.bin` file.
+    pub fn path(&self) -> &str {
+        self.path.as_str()
+    }
+
+    /// Gets the size of the opened `hwdb.bin` file.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Gets whether the opened `hwdb.bin` file is empty.
+    ///
+    /// Always `false`, since files smaller than the [TrieHeader] are rejected.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Gets a reference to the validated [TrieHeader].
+    pub const fn header(&self) -> &TrieHeader {
+        &self.head
+    }
+
+    /// Loads the database by reading the entire file into memory.
+    ///
+    /// Unlike [load_mmap](Self::load_mmap), the loaded database is not affected by later changes
+    /// to the file.
+    ///
+    /// Returns: `Ok(UdevHwdb)` on success, `Err(Error)` if the file cannot be read, or changed
+    /// size since it was opened.
+    pub fn load(mut self) -> Result<UdevHwdb> {
+        let context = hwdb_context("load_hwdb", self.path());
+        let mut buf = Vec::with_capacity(self.len);
+        io::Seek::rewind(&mut self.file)
+            .and_then(|_| self.file.read_to_end(&mut buf))
+            .map_err(|err| read_error(err).with_context(context.clone()))?;
+
+        self.into_hwdb(HwdbBuf::Owned(buf))
+            .map_err(|err| err.with_context(context))
+    }
+
+    /// Loads the database by memory-mapping the file, like the original `libudev`.
+    ///
+    /// Returns: `Ok(UdevHwdb)` on success, `Err(Error)` if the file cannot be mapped, or changed
+    /// size since it was opened.
+    pub fn load_mmap(self) -> Result<UdevHwdb> {
+        let context = hwdb_context("load_hwdb", self.path());
+        let map = HwdbMap::new(&self.file).map_err(|err| err.with_context(context.clone()))?;
+
+        self.into_hwdb(HwdbBuf::Mapped(map))
+            .map_err(|err| err.with_context(context))
+    }
+
+    fn into_hwdb(self, buf: HwdbBuf) -> Result<UdevHwdb> {
+        if buf.len() != self.len {
+            return Err(Error::UdevHwdb(format!(
+                "hwdb.bin file changed size while loading, have: {}, expected: {}",
+                buf.len(),
+                self.len
+            )));
+        }
+
+        Ok(UdevHwdb::from_buf(
+            self.udev,
+            self.bin_paths,
+            self.path,
+            self.head,
+            buf,
+        ))
+    }
+}
+
+// Gets the [ErrorContext] of a failed operation on the database file at `path`.
+pub(super) fn hwdb_context(operation: &str, path: &str) -> ErrorContext {
+    ErrorContext::new(operation).with_path(path)
+}
+
+// Converts an error reading a database file, the path is carried by the [ErrorContext].
+pub(super) fn read_error(err: io::Error) -> Error {
+    Error::os(
+        ErrorScope::Hwdb,
+        format!("error reading database: {err}"),
+        err,
+    )
+}
+
+/// Validates the sizes and offsets in the [TrieHeader] against the database file size.
+///
+/// Returns: `Ok(())` if all sections fit in the file, `Err(Error)` otherwise.
+pub fn validate_header(head: &TrieHeader, file_len: usize) -> Result<()> {
+    let file_len = file_len as u64;
+    let head_len = mem::size_of::<TrieHeader>() as u64;
+
+    let invalid = |msg: String| Err(Error::UdevHwdb(format!("invalid hwdb.bin header: {msg}")));
+
+    if head.file_size() != file_len {
+        return invalid(format!(
+            "file size: {}, actual: {file_len}",
+            head.file_size()
+        ));
+    }
+
+    if head.header_size() < head_len || head.header_size() > file_len {
+        return invalid(format!("header size: {}", head.header_size()));
+    }
+
+    if head.node_size() < mem::size_of::<TrieNode>() as u64
+        || head.child_entry_size() < MIN_CHILD_ENTRY_SIZE as u64
+        || head.value_entry_size() < MIN_VALUE_ENTRY_SIZE as u64
+    {
+        return invalid(format!(
+            "entry sizes, node: {}, child: {}, value: {}",
+            head.node_size(),
+            head.child_entry_size(),
+            head.value_entry_size()
+        ));
+    }
+
+    let sections_end = head
+        .header_size()
+        .checked_add(head.nodes_len())
+        .and_then(|end| end.checked_add(head.strings_len()));
+
+    match sections_end {
+        Some(end) if end <= file_len => (),
+        _ => {
+            return invalid(format!(
+                "nodes: {}, strings: {}, exceed file size: {file_len}",
+                head.nodes_len(),
+                head.strings_len()
+            ))
+        }
+    }
+
+    if head.nodes_root_off() < head.header_size()
+        || head.nodes_root_off().saturating_add(head.node_size()) > file_len
+    {
+        return invalid(format!("root node offset: {}", head.nodes_root_off()));
+    }
+
+    Ok(())
+}
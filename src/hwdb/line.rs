@@ -1,5 +1,8 @@
-use super::trie_string;
-use crate::{Error, Result, TrieEntry, UdevHwdb, UdevList};
+use std::collections::HashMap;
+use std::ffi;
+
+use super::{trie_string, TrieReader};
+use crate::{Error, Result, TrieEntry, TrieValueEntry, UdevHwdb, UdevList};
 use heapless::Vec;
 
 /// Maximum length for a file line.
@@ -61,68 +64,65 @@ impl LineBuf {
 
     /// Searches the [LineBuf] for a matching property.
     ///
-    /// If a property is found, it is added to [`list`](UdevList).
+    /// Appends the `entry` prefix (starting at `p`), and recursively every child, to the
+    /// [LineBuf]. If the assembled line is a glob pattern matching `search`, the values of the
+    /// entry are added to [`list`](UdevList), with `sources` tracking the value entry of each
+    /// added property for priority ordering.
     pub fn trie_fnmatch(
         &mut self,
         list: &mut UdevList,
+        sources: &mut HashMap<String, TrieValueEntry>,
         hwdb_buf: &[u8],
         entry: &TrieEntry,
         p: usize,
         search: &str,
     ) -> Result<()> {
-        let prefix_off = entry.node().prefix_off() as usize;
-        let prefix = trie_string(hwdb_buf, prefix_off)?;
-        let prefix_len = prefix.len();
+        let prefix = match entry.node().prefix_off() as usize {
+            0 => "",
+            off => trie_string(hwdb_buf, off)?.get(p..).unwrap_or(""),
+        };
 
         log::trace!(
             "Entering fnmatch, prefix: {prefix}, glob: {}, search: {search}",
             self.get()
         );
 
-        let (start, end) = (
-            p,
-            prefix
-                .as_bytes()
-                .iter()
-                .skip(p)
-                .position(|c| *c == b'\0')
-                .unwrap_or(prefix_len),
-        );
+        self.add(prefix)?;
 
-        // the logic of add only if within bounds but always remove is odd but the linebuf_add return is not checked in https://github.com/cr8t/udev/issues/25#L187
-        // so behavior should match
-        if (start..=end).contains(&prefix_len) {
-            self.add(&prefix[start..end])?;
-        }
+        let reader = TrieReader::new(hwdb_buf).with_sizes(entry.sizes());
 
         for child in entry.children().iter() {
-            let child_off = child.child_off() as usize;
-            if child_off < hwdb_buf.len() {
-                self.add_char(child.c())?;
-                self.trie_fnmatch(
-                    list,
-                    hwdb_buf,
-                    &TrieEntry::try_from(&hwdb_buf[child_off..])?,
-                    0,
-                    search,
-                )?;
-                self.remove_char();
-            }
+            let child_entry = reader.read_entry(child.child_off() as usize)?;
+
+            self.add_char(child.c())?;
+            self.trie_fnmatch(list, sources, hwdb_buf, &child_entry, 0, search)?;
+            self.remove_char();
         }
 
-        if glob::Pattern::new(self.get())?.matches(search) {
-            log::trace!("Found matching entry, entry: {entry:?}, search: {search}");
+        if !entry.values().is_empty() && fnmatch(self.get(), search) {
+            log::trace!(
+                "Found matching entry, glob: {}, search: {search}",
+                self.get()
+            );
             for value in entry.values().iter() {
-                UdevHwdb::_add_property(
-                    list,
-                    trie_string(hwdb_buf, value.key_off() as usize)?,
-                    trie_string(hwdb_buf, value.value_off() as usize)?,
-                )?;
+                UdevHwdb::add_value_entry(list, sources, hwdb_buf, entry.sizes(), value)?;
             }
         }
 
-        self.remove(end.saturating_sub(start));
+        self.remove(prefix.len());
 
         Ok(())
     }
 }
+
+/// Matches `string` against the shell wildcard `pattern`, with the same semantics as
+/// [`fnmatch(3)`](https://man7.org/linux/man-pages/man3/fnmatch.3.html) without flags.
+pub fn fnmatch(pattern: &str, string: &str) -> bool {
+    match (ffi::CString::new(pattern), ffi::CString::new(string)) {
+        // SAFETY: both arguments are valid, nul-terminated C strings.
+        (Ok(pattern), Ok(string)) => unsafe {
+            libc::fnmatch(pattern.as_ptr(), string.as_ptr(), 0) == 0
+        },
+        _ => false,
+    }
+}
@@ -0,0 +1,105 @@
+use std::ffi::c_void;
+use std::num::NonZeroUsize;
+use std::ptr::NonNull;
+use std::{fmt, fs, ops};
+
+use nix::sys::mman::{self, MapFlags, ProtFlags};
+
+use crate::{Error, Result};
+
+/// Read-only memory mapping of the on-disk hardware database.
+///
+/// The mapping is created once, and all trie traversal is done against the mapped slice.
+///
+/// **NOTE**: like the original `libudev`, the file is mapped `MAP_PRIVATE`. Tools updating the
+/// database replace the file with a rename, so the mapped contents remain valid. Truncating the
+/// file in-place while it is mapped results in `SIGBUS` on access.
+pub(crate) struct HwdbMap {
+    addr: NonNull<c_void>,
+    len: usize,
+}
+
+// SAFETY: the mapping is read-only, and never mutated after creation, so it can be shared across
+// threads like a `&[u8]`.
+unsafe impl Send for HwdbMap {}
+// SAFETY: see `Send` implementation above.
+unsafe impl Sync for HwdbMap {}
+
+impl HwdbMap {
+    /// Maps the entire `file` into memory.
+    ///
+    /// Returns: `Ok(HwdbMap)` on success, `Err(Error)` otherwise.
+    pub fn new(file: &fs::File) -> Result<Self> {
+        let len = file.metadata()?.len() as usize;
+        let length = NonZeroUsize::new(len)
+            .ok_or(Error::UdevHwdb("unable to map empty hwdb.bin file".into()))?;
+
+        // SAFETY: the file is opened for reading, the length matches the file size, and the
+        // result is checked before use. The mapping does not alias any Rust-owned memory.
+        let addr = unsafe {
+            mman::mmap(
+                None,
+                length,
+                ProtFlags::PROT_READ,
+                MapFlags::MAP_PRIVATE,
+                file,
+                0,
+            )
+        }
+        .map_err(|err| Error::UdevHwdb(format!("unable to map hwdb.bin file, errno: {err}")))?;
+
+        Ok(Self { addr, len })
+    }
+
+    /// Gets the mapped file contents.
+    pub fn as_slice(&self) -> &[u8] {
+        // SAFETY: `addr` points to a valid, readable mapping of `len` bytes, that lives until the
+        // `HwdbMap` is dropped.
+        unsafe { std::slice::from_raw_parts(self.addr.as_ptr() as *const u8, self.len) }
+    }
+}
+
+impl ops::Deref for HwdbMap {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl Drop for HwdbMap {
+    fn drop(&mut self) {
+        // SAFETY: `addr` and `len` describe a mapping created in `HwdbMap::new`, and no
+        // references to the mapped slice outlive `self`.
+        if let Err(err) = unsafe { mman::munmap(self.addr, self.len) } {
+            log::warn!("unable to unmap hwdb.bin file, errno: {err}");
+        }
+    }
+}
+
+impl fmt::Debug for HwdbMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HwdbMap")
+            .field("addr", &self.addr)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+/// Contents of the on-disk hardware database, either memory-mapped, or read into memory.
+#[derive(Debug)]
+pub(crate) enum HwdbBuf {
+    Mapped(HwdbMap),
+    Owned(Vec<u8>),
+}
+
+impl ops::Deref for HwdbBuf {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Mapped(map) => map.as_slice(),
+            Self::Owned(buf) => buf.as_slice(),
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use std::sync::Arc;
+use std::{fs, io};
+
+use super::file::{hwdb_context, read_error};
+use super::{hwdb_bin_paths, UdevHwdb};
+use crate::{Error, Result, Udev, UdevEntry, UdevList};
+
+/// Stack of hardware databases, queried together, e.g. a vendor `hwdb.bin`, and local overrides.
+///
+/// Databases are ordered by increasing precedence: properties found in several databases take
+/// the value from the last database in the stack. Within one database, duplicate properties are
+/// resolved like [UdevHwdb::query].
+///
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use udevrs::{HwdbStack, Udev};
+/// # fn main() -> udevrs::Result<()> {
+/// let mut stack = HwdbStack::open_dir(Arc::new(Udev::new()), "/usr/lib/udev/hwdb.d")?;
+///
+/// if let Some(props) = stack.query("usb:v1D6Bp0003") {
+///     for prop in props.iter() {
+///         println!("{}={}", prop.name(), prop.value());
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[repr(C)]
+pub struct HwdbStack {
+    hwdbs: Vec<UdevHwdb>,
+    properties_list: UdevList,
+}
+
+impl HwdbStack {
+    /// Creates a new, empty [HwdbStack].
+    pub fn new(udev: Arc<Udev>) -> Self {
+        Self {
+            hwdbs: Vec::new(),
+            properties_list: UdevList::new(udev),
+        }
+    }
+
+    /// Opens all existing databases in the [hwdb_bin_paths] of the [Udev] context.
+    ///
+    /// Earlier search locations take precedence, e.g. `/etc/udev/hwdb.bin` over the vendor
+    /// `hwdb.bin` in the [libexec path](Udev::get_libexec_path), so they are stacked last.
+    ///
+    /// Returns: `Ok(HwdbStack)` on success, `Err(Error)` if no database file is found, or a file
+    /// cannot be loaded.
+    pub fn open(udev: Arc<Udev>) -> Result<Self> {
+        let mut paths = hwdb_bin_paths(&udev);
+        paths.reverse();
+
+        Self::open_paths(udev, paths)
+    }
+
+    /// Opens all `*.bin` databases in the directory `dir`, e.g. a `hwdb.d` directory.
+    ///
+    /// Files are stacked in lexicographic order of their names, so later files take precedence,
+    /// e.g. `60-local.bin` over `20-vendor.bin`.
+    ///
+    /// Returns: `Ok(HwdbStack)` on success, `Err(Error)` if the directory cannot be read, has no
+    /// database files, or a file cannot be loaded.
+    pub fn open_dir(udev: Arc<Udev>, dir: &str) -> Result<Self> {
+        let mut paths: Vec<String> = fs::read_dir(dir)
+            .map_err(|err| read_error(err).with_context(hwdb_context("open_hwdb_dir", dir)))?
+            .filter_map(|entry| entry.ok()?.path().to_str().map(String::from))
+            .filter(|path| path.ends_with(".bin"))
+            .collect();
+        paths.sort();
+
+        Self::open_paths(udev, paths)
+    }
+
+    // Loads the existing files in `paths`, in order of increasing precedence.
+    fn open_paths(udev: Arc<Udev>, paths: Vec<String>) -> Result<Self> {
+        let mut stack = Self::new(Arc::clone(&udev));
+
+        for path in paths {
+            match fs::metadata(path.as_str()) {
+                Ok(_) => stack.push(UdevHwdb::new_from_path(Arc::clone(&udev), path.as_str())?),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => {
+                    return Err(read_error(err).with_context(hwdb_context("open_hwdb", &path)))
+                }
+            }
+        }
+
+        if stack.is_empty() {
+            Err(Error::UdevHwdb(
+                "unable to find hwdb.bin database file".into(),
+            ))
+        } else {
+            Ok(stack)
+        }
+    }
+
+    /// Adds a database to the top of the [HwdbStack], taking precedence over the others.
+    pub fn push(&mut self, hwdb: UdevHwdb) {
+        log::debug!("stacking hwdb: {}", hwdb.hwdb_path());
+        self.hwdbs.push(hwdb);
+    }
+
+    /// Builder function that adds a database to the top of the [HwdbStack].
+    pub fn with_hwdb(mut self, hwdb: UdevHwdb) -> Self {
+        self.push(hwdb);
+        self
+    }
+
+    /// Gets the databases, in order of increasing precedence.
+    pub fn hwdbs(&self) -> &[UdevHwdb] {
+        self.hwdbs.as_ref()
+    }
+
+    /// Gets the number of databases.
+    pub fn len(&self) -> usize {
+        self.hwdbs.len()
+    }
+
+    /// Gets whether the [HwdbStack] has no databases.
+    pub fn is_empty(&self) -> bool {
+        self.hwdbs.is_empty()
+    }
+
+    /// Gets a reference to the merged [properties list](UdevList) of the last query.
+    pub const fn properties_list(&self) -> &UdevList {
+        &self.properties_list
+    }
+
+    /// Looks up a matching device in all databases, and merges the properties.
+    ///
+    /// See [UdevHwdb::get_properties_list_entry] for the `modalias` format, `flags` are unused.
+    ///
+    /// Returns: an optional reference to the first [UdevEntry] of the merged properties.
+    pub fn get_properties_list_entry(&mut self, modalias: &str, _flags: u32) -> Option<&UdevEntry> {
+        self.properties_list.clear();
+
+        for hwdb in self.hwdbs.iter_mut() {
+            let Some(props) = hwdb.query(modalias) else {
+                continue;
+            };
+
+            // later databases replace the values of earlier ones
+            for prop in props.iter() {
+                self.properties_list.add_entry(prop.name(), prop.value());
+            }
+
+            log::trace!("hwdb {} matched: {modalias}", hwdb.hwdb_path());
+        }
+
+        self.properties_list.entry()
+    }
+
+    /// Looks up a matching device modalias in all databases, and returns the merged properties.
+    pub fn query(&mut self, modalias: &str) -> Option<&UdevList> {
+        self.get_properties_list_entry(modalias, 0)?;
+        Some(self.properties_list())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::SysfsFixture;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn test_hwdb_stack() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+        let bin = std::env::current_dir()?.join("tests/hwdb.bin");
+
+        let fixture = SysfsFixture::new()?;
+        let dir = fixture.root().join("hwdb.d");
+        fs::create_dir_all(&dir)?;
+        symlink(&bin, dir.join("60-local.bin"))?;
+        symlink(&bin, dir.join("20-vendor.bin"))?;
+        fs::write(dir.join("README"), "not a database")?;
+
+        let mut stack = HwdbStack::open_dir(Arc::clone(&udev), dir.to_str().unwrap_or_default())?;
+        assert_eq!(stack.len(), 2);
+        assert!(stack.hwdbs()[0].hwdb_path().ends_with("/20-vendor.bin"));
+        assert!(stack.hwdbs()[1].hwdb_path().ends_with("/60-local.bin"));
+
+        let vendor = stack
+            .query("usb:v1D6B")
+            .and_then(|props| props.entry_by_name("ID_VENDOR_FROM_DATABASE"))
+            .map(|e| e.value().to_owned());
+        assert_eq!(vendor.as_deref(), Some("Linux Foundation"));
+        assert!(stack.query("null:v1D6B").is_none());
+
+        let empty = fixture.root().join("empty");
+        fs::create_dir_all(&empty)?;
+        assert!(HwdbStack::open_dir(udev, empty.to_str().unwrap_or_default()).is_err());
+
+        Ok(())
+    }
+}
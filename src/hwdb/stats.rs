@@ -0,0 +1,239 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use super::{TrieReader, TrieSizes, UdevHwdb};
+use crate::{Error, Result};
+
+/// Statistics of the hardware database trie, see [UdevHwdb::stats].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct HwdbStats {
+    node_count: usize,
+    value_count: usize,
+    max_depth: usize,
+    nodes_len: usize,
+    nodes_used: usize,
+    strings_len: usize,
+    strings_used: usize,
+}
+
+impl HwdbStats {
+    /// Creates a new [HwdbStats].
+    pub const fn new() -> Self {
+        Self {
+            node_count: 0,
+            value_count: 0,
+            max_depth: 0,
+            nodes_len: 0,
+            nodes_used: 0,
+            strings_len: 0,
+            strings_used: 0,
+        }
+    }
+
+    /// Gets the number of trie nodes reachable from the root node.
+    pub const fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    /// Gets the number of property values of all trie nodes.
+    pub const fn value_count(&self) -> usize {
+        self.value_count
+    }
+
+    /// Gets the maximum depth of the trie, the root node has depth `0`.
+    pub const fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Gets the size of the nodes section, from the [TrieHeader](super::TrieHeader).
+    pub const fn nodes_len(&self) -> usize {
+        self.nodes_len
+    }
+
+    /// Gets the number of bytes of the nodes section used by reachable nodes.
+    pub const fn nodes_used(&self) -> usize {
+        self.nodes_used
+    }
+
+    /// Gets the size of the strings section, from the [TrieHeader](super::TrieHeader).
+    pub const fn strings_len(&self) -> usize {
+        self.strings_len
+    }
+
+    /// Gets the number of bytes of the strings section referenced by reachable nodes.
+    ///
+    /// Strings sharing a suffix are stored once, so overlapping strings are counted once.
+    pub const fn strings_used(&self) -> usize {
+        self.strings_used
+    }
+
+    /// Gets the fraction of the strings section referenced by reachable nodes.
+    ///
+    /// Returns: a value between `0.0` and `1.0`, or `0.0` if the strings section is empty.
+    pub fn strings_utilization(&self) -> f64 {
+        if self.strings_len == 0 {
+            0.0
+        } else {
+            self.strings_used as f64 / self.strings_len as f64
+        }
+    }
+}
+
+impl fmt::Display for HwdbStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "nodes:      {:8}", self.node_count)?;
+        writeln!(f, "values:     {:8}", self.value_count)?;
+        writeln!(f, "max depth:  {:8}", self.max_depth)?;
+        writeln!(
+            f,
+            "node bytes: {:8} of {:8} bytes",
+            self.nodes_used, self.nodes_len
+        )?;
+        write!(
+            f,
+            "strings:    {:8} of {:8} bytes ({:.1}%)",
+            self.strings_used,
+            self.strings_len,
+            self.strings_utilization() * 100.0
+        )
+    }
+}
+
+impl UdevHwdb {
+    /// Validates the entire hardware database trie.
+    ///
+    /// Walks every node reachable from the root, and checks that:
+    ///
+    /// - nodes, and their children, and values arrays, are inside the nodes section
+    /// - no node is reachable more than once, e.g. through a cycle
+    /// - children are sorted by their character, without duplicates
+    /// - prefix, key, value, and source file name strings are inside the strings section, and
+    ///   `NUL`-terminated, UTF-8 strings
+    ///
+    /// Useful to diagnose lookups returning wrong, or no, properties, see also
+    /// [stats](Self::stats).
+    ///
+    /// Returns: `Ok(())` if the trie is valid, `Err(Error)` describing the first problem found.
+    pub fn validate(&self) -> Result<()> {
+        self.walk_trie().map(|_| ())
+    }
+
+    /// Gets the [HwdbStats] of the hardware database trie.
+    ///
+    /// Walks the trie like [validate](Self::validate).
+    ///
+    /// Returns: `Ok(HwdbStats)` if the trie is valid, `Err(Error)` otherwise.
+    pub fn stats(&self) -> Result<HwdbStats> {
+        self.walk_trie()
+    }
+
+    fn walk_trie(&self) -> Result<HwdbStats> {
+        let head = self.header();
+        let sizes = TrieSizes::from_header(head);
+        let reader = TrieReader::new(self.as_bytes()).with_sizes(sizes);
+
+        let nodes_start = head.header_size() as usize;
+        let nodes_end = nodes_start.saturating_add(head.nodes_len() as usize);
+        let strings_end = nodes_end.saturating_add(head.strings_len() as usize);
+
+        let invalid = |msg: String| Error::UdevHwdb(format!("invalid hwdb trie: {msg}"));
+
+        let mut stats = HwdbStats::new();
+        stats.nodes_len = head.nodes_len() as usize;
+        stats.strings_len = head.strings_len() as usize;
+
+        let mut strings = Vec::new();
+        let mut check_string = |off: u64, what: &str| -> Result<()> {
+            let off = off as usize;
+            if !(nodes_end..strings_end).contains(&off) {
+                return Err(invalid(format!(
+                    "{what} string offset outside strings: {off}"
+                )));
+            }
+
+            let len = reader
+                .slice(off, strings_end - off)?
+                .iter()
+                .position(|c| c == &b'\0')
+                .ok_or_else(|| invalid(format!("{what} string not NUL-terminated: {off}")))?;
+
+            std::str::from_utf8(reader.slice(off, len)?)
+                .map_err(|_| invalid(format!("{what} string not valid UTF-8: {off}")))?;
+
+            strings.push((off, off + len + 1));
+            Ok(())
+        };
+
+        let mut visited = HashSet::new();
+        let mut stack = vec![(head.nodes_root_off() as usize, 0usize)];
+
+        while let Some((off, depth)) = stack.pop() {
+            if !(nodes_start..nodes_end).contains(&off) {
+                return Err(invalid(format!("node offset outside nodes: {off}")));
+            }
+            if !visited.insert(off) {
+                return Err(invalid(format!("node reachable more than once: {off}")));
+            }
+
+            let entry = reader.read_entry(off)?;
+            let entry_len = entry.len();
+            let entry_end = off.saturating_add(entry_len);
+
+            if entry.children().len() != entry.node().children_count() as usize
+                || entry.values().len() != entry.node().values_count() as usize
+                || entry_end > nodes_end
+            {
+                return Err(invalid(format!("node arrays exceed nodes section: {off}")));
+            }
+
+            // children are sorted when parsed, so check the on-disk order
+            let children_off = off.saturating_add(sizes.node());
+            let mut last = None;
+            for i in 0..entry.children().len() {
+                let c = reader.read_u8(children_off.saturating_add(i * sizes.child_entry()))?;
+                if last.is_some_and(|l| l >= c) {
+                    return Err(invalid(format!("node children not sorted: {off}")));
+                }
+                last = Some(c);
+            }
+
+            if entry.node().prefix_off() > 0 {
+                check_string(entry.node().prefix_off(), "prefix")?;
+            }
+
+            for value in entry.values() {
+                check_string(value.key_off(), "key")?;
+                check_string(value.value_off(), "value")?;
+                if sizes.has_value_sources() {
+                    check_string(value.filename_off(), "file name")?;
+                }
+            }
+
+            stats.node_count += 1;
+            stats.value_count += entry.values().len();
+            stats.max_depth = stats.max_depth.max(depth);
+            stats.nodes_used += entry_len;
+
+            stack.extend(
+                entry
+                    .children()
+                    .iter()
+                    .map(|child| (child.child_off() as usize, depth + 1)),
+            );
+        }
+
+        // overlapping strings, e.g. shared suffixes, are counted once
+        strings.sort_unstable();
+        let mut covered_end = 0;
+        for (start, end) in strings {
+            let start = start.max(covered_end);
+            if end > start {
+                stats.strings_used += end - start;
+                covered_end = end;
+            }
+        }
+
+        Ok(stats)
+    }
+}
@@ -1,6 +1,8 @@
-use std::cmp;
+use std::{cmp, mem};
 
-use crate::{hwdb, Error, Result};
+use crate::{Error, Result};
+
+use super::TrieReader;
 
 /// Trie child entry in the hardware database.
 ///
@@ -23,9 +25,11 @@ impl TrieChildEntry {
         }
     }
 
-    /// Gets the length of the encoded [TrieChildEntry].
-    pub fn len(&self) -> usize {
-        hwdb::child_entry_size()
+    /// Gets the length of the encoded [TrieChildEntry], in the current `hwdb.bin` format.
+    ///
+    /// Databases may use longer entries, see [TrieSizes](super::TrieSizes).
+    pub const fn len(&self) -> usize {
+        mem::size_of::<Self>()
     }
 
     /// Gets whether the [TrieChildEntry] is empty.
@@ -70,20 +74,15 @@ impl TryFrom<&[u8]> for TrieChildEntry {
     type Error = Error;
 
     fn try_from(val: &[u8]) -> Result<Self> {
-        if val.len() < hwdb::child_entry_size() {
+        if val.len() < mem::size_of::<Self>() {
             Err(Error::InvalidLen(val.len()))
         } else {
-            // TODO: parse use get ranges and offsets
-            let mut idx = 0usize;
+            let reader = TrieReader::new(val);
 
-            let c = val[idx];
+            let c = reader.read_u8(0)?;
             let _padding = [0u8; 7];
-
             // skip `c` index + padding
-            idx += 8;
-
-            let child_off =
-                u64::from_le_bytes(val[idx..idx + std::mem::size_of::<u64>()].try_into()?);
+            let child_off = reader.read_u64(8)?;
 
             Ok(Self {
                 c,
@@ -102,6 +101,6 @@ impl Ord for TrieChildEntry {
 
 impl PartialOrd for TrieChildEntry {
     fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
-        Some(self.c.cmp(&other.c))
+        Some(self.cmp(other))
     }
 }
@@ -1,8 +1,13 @@
 use std::mem;
 
-use crate::{hwdb, Error, Result};
+use crate::{Error, Result};
 
-use super::{TrieChildEntry, TrieNode, TrieValueEntry};
+use super::{TrieChildEntry, TrieNode, TrieReader, TrieSizes, TrieValueEntry};
+
+/// Minimum length of an encoded [TrieChildEntry].
+pub const MIN_CHILD_ENTRY_SIZE: usize = mem::size_of::<TrieChildEntry>();
+/// Minimum length of an encoded [TrieValueEntry], without the version 2 source fields.
+pub const MIN_VALUE_ENTRY_SIZE: usize = 2 * mem::size_of::<u64>();
 
 /// Represents the full Trie entry in the HWDB.
 #[repr(C)]
@@ -11,6 +16,7 @@ pub struct TrieEntry {
     node: TrieNode,
     children: Vec<TrieChildEntry>,
     values: Vec<TrieValueEntry>,
+    sizes: TrieSizes,
 }
 
 impl TrieEntry {
@@ -20,9 +26,60 @@ impl TrieEntry {
             node: TrieNode::new(),
             children: Vec::new(),
             values: Vec::new(),
+            sizes: TrieSizes::new(),
         }
     }
 
+    /// Parses a [TrieEntry] with the [TrieSizes] of its database.
+    ///
+    /// Returns: `Ok(TrieEntry)` on success, `Err(Error)` if the sizes are invalid, or the
+    /// children, and values arrays, exceed the buffer.
+    pub fn from_bytes(val: &[u8], sizes: TrieSizes) -> Result<Self> {
+        let child_len = sizes.child_entry();
+        let value_len = sizes.value_entry();
+
+        if sizes.node() < mem::size_of::<TrieNode>()
+            || child_len < MIN_CHILD_ENTRY_SIZE
+            || value_len < MIN_VALUE_ENTRY_SIZE
+        {
+            return Err(Error::UdevHwdb(format!(
+                "invalid trie entry sizes, node: {}, child: {child_len}, value: {value_len}",
+                sizes.node()
+            )));
+        }
+
+        let node = TrieNode::try_from(val)?;
+        let reader = TrieReader::new(val);
+
+        let child_count = node.children_count() as usize;
+        let child_off = sizes.node();
+        let child_buf = reader.slice(child_off, child_count.saturating_mul(child_len))?;
+
+        let mut children = child_buf
+            .chunks_exact(child_len)
+            .map(TrieChildEntry::try_from)
+            .collect::<Result<Vec<TrieChildEntry>>>()?;
+
+        children.sort();
+
+        let value_count = node.values_count() as usize;
+        let value_off = child_off.saturating_add(child_buf.len());
+        let value_buf = reader.slice(value_off, value_count.saturating_mul(value_len))?;
+
+        // the chunk length tells version 1, and version 2 value entries apart
+        let values = value_buf
+            .chunks_exact(value_len)
+            .map(TrieValueEntry::try_from)
+            .collect::<Result<Vec<TrieValueEntry>>>()?;
+
+        Ok(Self {
+            node,
+            children,
+            values,
+            sizes,
+        })
+    }
+
     /// Gets a reference to the [TrieNode].
     pub const fn node(&self) -> &TrieNode {
         &self.node
@@ -38,12 +95,18 @@ impl TrieEntry {
         self.values.as_ref()
     }
 
-    /// Gets the total length of the [TrieEntry].
+    /// Gets the [TrieSizes] the [TrieEntry] was parsed with.
+    pub const fn sizes(&self) -> TrieSizes {
+        self.sizes
+    }
+
+    /// Gets the total length of the encoded [TrieEntry].
     pub fn len(&self) -> usize {
-        let children_len = self.children.len().saturating_mul(hwdb::child_entry_size());
-        let values_len = self.values.len().saturating_mul(hwdb::value_entry_size());
+        let children_len = self.children.len().saturating_mul(self.sizes.child_entry());
+        let values_len = self.values.len().saturating_mul(self.sizes.value_entry());
 
-        mem::size_of::<TrieNode>()
+        self.sizes
+            .node()
             .saturating_add(children_len)
             .saturating_add(values_len)
     }
@@ -63,7 +126,6 @@ impl TrieEntry {
     /// Returns [Some(TrieNode)](TrieNode) on success, [`None`] otherwise.
     pub fn lookup_child(&self, hwdb_buf: &[u8], c: u8) -> Option<Self> {
         let search = TrieChildEntry::new().with_c(c);
-        let buf_len = hwdb_buf.len();
 
         // assuming children are sorted (done in initialisation), perform a binary search instead like C hwdb
         let child = self
@@ -72,14 +134,11 @@ impl TrieEntry {
             .ok()
             .and_then(|idx| self.children.get(idx))?;
 
-        let child_off = child.child_off() as usize;
-
-        // if the child offset is in range, attempt to construct a `TrieNode` at that offset
-        if (0..buf_len).contains(&child_off) {
-            Self::try_from(&hwdb_buf[child_off..]).ok()
-        } else {
-            None
-        }
+        TrieReader::new(hwdb_buf)
+            .with_sizes(self.sizes)
+            .read_entry(child.child_off() as usize)
+            .map_err(|err| log::debug!("invalid child entry: {err}"))
+            .ok()
     }
 }
 
@@ -89,46 +148,11 @@ impl Default for TrieEntry {
     }
 }
 
+// Parses the entry with the sizes of the current `hwdb.bin` format, see `from_bytes`.
 impl TryFrom<&[u8]> for TrieEntry {
     type Error = Error;
 
     fn try_from(val: &[u8]) -> Result<Self> {
-        let node = TrieNode::try_from(val)?;
-
-        let mut idx = hwdb::node_size();
-
-        let val_end = val.len();
-        let child_len = hwdb::child_entry_size();
-        let child_count = node.children_count() as usize;
-        let child_end = idx.saturating_add(child_count.saturating_mul(child_len));
-
-        let mut children: Vec<TrieChildEntry> = Vec::with_capacity(child_count);
-
-        if (idx..val_end).contains(&child_end) && child_count > 0 {
-            for c in val[idx..].chunks_exact(child_len).take(child_count) {
-                children.push(c.try_into()?);
-                idx = idx.saturating_add(child_len);
-            }
-        }
-
-        children.sort();
-
-        let value_len = hwdb::value_entry_size();
-        let value_count = node.values_count() as usize;
-        let value_end = idx.saturating_add(value_count.saturating_mul(value_len));
-
-        let mut values: Vec<TrieValueEntry> = Vec::with_capacity(value_count);
-
-        if (idx..val_end).contains(&value_end) && value_count > 0 {
-            for c in val[idx..].chunks_exact(value_len).take(value_count) {
-                values.push(c.try_into()?);
-            }
-        }
-
-        Ok(Self {
-            node,
-            children,
-            values,
-        })
+        Self::from_bytes(val, TrieSizes::new())
     }
 }
@@ -1,6 +1,8 @@
 use std::mem;
 
-use crate::{hwdb, Error, Result};
+use crate::{Error, Result};
+
+use super::TrieReader;
 
 /// Trie node in the hardware database.
 #[repr(C, packed(8))]
@@ -23,9 +25,11 @@ impl TrieNode {
         }
     }
 
-    /// Gets the length of the encoded [TrieNode].
-    pub fn len(&self) -> usize {
-        hwdb::node_size()
+    /// Gets the length of the encoded [TrieNode], in the current `hwdb.bin` format.
+    ///
+    /// Databases may use longer nodes, see [TrieSizes](super::TrieSizes).
+    pub const fn len(&self) -> usize {
+        mem::size_of::<Self>()
     }
 
     /// Gets whether the [TrieNode] is empty.
@@ -89,22 +93,13 @@ impl TryFrom<&[u8]> for TrieNode {
         if val.len() < mem::size_of::<Self>() {
             Err(Error::InvalidLen(val.len()))
         } else {
-            let mut idx = 0usize;
-
-            let prefix_off = u64::from_le_bytes(val[idx..idx + 8].try_into()?);
-            idx += mem::size_of::<u64>();
+            let reader = TrieReader::new(val);
 
-            let children_count = val[idx];
+            let prefix_off = reader.read_u64(0)?;
+            let children_count = reader.read_u8(8)?;
             let _padding = [0u8; 7];
-
             // skip past the children count + padding
-            idx += 8;
-
-            let values_count = u64::from_le_bytes(
-                val.get(idx..idx + 8)
-                    .ok_or(Error::InvalidLen(val.len()))?
-                    .try_into()?,
-            );
+            let values_count = reader.read_u64(16)?;
 
             if values_count > 64 {
                 Err(Error::InvalidLen(values_count as usize))
@@ -0,0 +1,154 @@
+use crate::{Error, Result};
+
+use super::{TrieEntry, TrieHeader, TrieSizes};
+
+/// Bounds-checked reader over the in-memory HWDB buffer.
+///
+/// Offsets in the `hwdb.bin` file are not trusted: every read checks the offset, and length,
+/// against the buffer, and returns an error instead of panicking on a corrupt database.
+///
+/// Entries are read with the [TrieSizes] of the reader, the current `hwdb.bin` format by
+/// default, see [with_sizes](Self::with_sizes).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrieReader<'a> {
+    buf: &'a [u8],
+    sizes: TrieSizes,
+}
+
+impl<'a> TrieReader<'a> {
+    /// Creates a new [TrieReader] over the HWDB buffer.
+    pub const fn new(buf: &'a [u8]) -> Self {
+        Self {
+            buf,
+            sizes: TrieSizes::new(),
+        }
+    }
+
+    /// Builder function that sets the [TrieSizes] of the database, e.g. from its [TrieHeader].
+    pub const fn with_sizes(mut self, sizes: TrieSizes) -> Self {
+        self.sizes = sizes;
+        self
+    }
+
+    /// Gets the [TrieSizes] used to read entries.
+    pub const fn sizes(&self) -> TrieSizes {
+        self.sizes
+    }
+
+    /// Gets the underlying HWDB buffer.
+    pub const fn as_bytes(&self) -> &'a [u8] {
+        self.buf
+    }
+
+    /// Gets the length of the HWDB buffer.
+    pub const fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Gets whether the HWDB buffer is empty.
+    pub const fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// Gets `len` bytes at the offset `off`.
+    ///
+    /// Returns: `Ok(&[u8])` on success, `Err(Error)` if the range exceeds the buffer.
+    pub fn slice(&self, off: usize, len: usize) -> Result<&'a [u8]> {
+        off.checked_add(len)
+            .and_then(|end| self.buf.get(off..end))
+            .ok_or_else(|| self.out_of_range(off, len))
+    }
+
+    /// Gets the bytes from the offset `off` to the end of the buffer.
+    ///
+    /// Returns: `Ok(&[u8])` on success, `Err(Error)` if the offset exceeds the buffer.
+    pub fn tail(&self, off: usize) -> Result<&'a [u8]> {
+        self.buf.get(off..).ok_or_else(|| self.out_of_range(off, 0))
+    }
+
+    /// Reads a byte at the offset `off`.
+    pub fn read_u8(&self, off: usize) -> Result<u8> {
+        Ok(self.slice(off, 1)?[0])
+    }
+
+    /// Reads a little-endian [`u16`] at the offset `off`.
+    pub fn read_u16(&self, off: usize) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.slice(off, 2)?.try_into()?))
+    }
+
+    /// Reads a little-endian [`u32`] at the offset `off`.
+    pub fn read_u32(&self, off: usize) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.slice(off, 4)?.try_into()?))
+    }
+
+    /// Reads a little-endian [`u64`] at the offset `off`.
+    pub fn read_u64(&self, off: usize) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.slice(off, 8)?.try_into()?))
+    }
+
+    /// Reads a string at the offset `off`, terminated by a `NUL`, or newline, character.
+    ///
+    /// Returns: `Ok(&str)` on success, `Err(Error)` if the offset exceeds the buffer, or the
+    /// string is not valid UTF-8.
+    pub fn read_string(&self, off: usize) -> Result<&'a str> {
+        let buf = self
+            .buf
+            .get(off..)
+            .filter(|b| !b.is_empty())
+            .ok_or_else(|| Error::UdevHwdb(format!("invalid trie_string offset: {off}")))?;
+
+        let end = buf
+            .iter()
+            .position(|c| c == &b'\0' || c == &b'\n')
+            .unwrap_or(buf.len());
+
+        std::str::from_utf8(&buf[..end])
+            .map_err(|_| Error::UdevHwdb("failed to parse utf-8 trie_string".to_string()))
+    }
+
+    /// Reads the [TrieEntry] at the offset `off`.
+    ///
+    /// Returns: `Ok(TrieEntry)` on success, `Err(Error)` if the entry, or its children, and
+    /// values arrays, exceed the buffer.
+    pub fn read_entry(&self, off: usize) -> Result<TrieEntry> {
+        TrieEntry::from_bytes(self.tail(off)?, self.sizes)
+    }
+
+    /// Reads the root [TrieEntry] at the offset from the [TrieHeader].
+    pub fn root_entry(&self, head: &TrieHeader) -> Result<TrieEntry> {
+        self.read_entry(head.nodes_root_off() as usize)
+    }
+
+    fn out_of_range(&self, off: usize, len: usize) -> Error {
+        Error::UdevHwdb(format!(
+            "trie offset out of range: {off}, length: {len}, buffer length: {}",
+            self.buf.len()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trie_reader() -> Result<()> {
+        let buf = [1u8, 0, 0, 0, 0, 0, 0, 0, b'a', b'b', 0, b'c'];
+        let reader = TrieReader::new(&buf);
+
+        assert_eq!(reader.read_u64(0)?, 1);
+        assert_eq!(reader.read_u32(0)?, 1);
+        assert_eq!(reader.read_u16(0)?, 1);
+        assert_eq!(reader.read_u8(8)?, b'a');
+        assert_eq!(reader.read_string(8)?, "ab");
+        assert_eq!(reader.read_string(11)?, "c");
+
+        assert!(reader.read_u64(5).is_err());
+        assert!(reader.read_u8(buf.len()).is_err());
+        assert!(reader.slice(usize::MAX, 2).is_err());
+        assert!(reader.read_string(buf.len()).is_err());
+        assert!(reader.read_entry(4).is_err());
+
+        Ok(())
+    }
+}
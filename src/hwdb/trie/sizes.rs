@@ -0,0 +1,111 @@
+use std::mem;
+
+use super::{TrieChildEntry, TrieHeader, TrieNode, TrieValueEntry, VALUE_ENTRY2_SIZE};
+
+/// Encoded sizes of the records in the hardware database trie.
+///
+/// The sizes are stored in the [TrieHeader] of each database, and may differ between `hwdb.bin`
+/// files written by different tools, so every [TrieReader](super::TrieReader) carries the sizes
+/// of the database it reads.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TrieSizes {
+    node: usize,
+    child_entry: usize,
+    value_entry: usize,
+}
+
+impl TrieSizes {
+    /// Creates a new [TrieSizes] with the sizes of the current `hwdb.bin` format.
+    pub const fn new() -> Self {
+        Self {
+            node: mem::size_of::<TrieNode>(),
+            child_entry: mem::size_of::<TrieChildEntry>(),
+            value_entry: mem::size_of::<TrieValueEntry>(),
+        }
+    }
+
+    /// Creates a new [TrieSizes] from the sizes in the [TrieHeader].
+    pub const fn from_header(head: &TrieHeader) -> Self {
+        Self {
+            node: head.node_size() as usize,
+            child_entry: head.child_entry_size() as usize,
+            value_entry: head.value_entry_size() as usize,
+        }
+    }
+
+    /// Gets the [TrieNode] size.
+    pub const fn node(&self) -> usize {
+        self.node
+    }
+
+    /// Gets the [TrieChildEntry] size.
+    pub const fn child_entry(&self) -> usize {
+        self.child_entry
+    }
+
+    /// Gets the [TrieValueEntry] size.
+    pub const fn value_entry(&self) -> usize {
+        self.value_entry
+    }
+
+    /// Gets whether the [TrieValueEntry] records are version 2 entries, with the source of the
+    /// value.
+    pub const fn has_value_sources(&self) -> bool {
+        self.value_entry >= VALUE_ENTRY2_SIZE
+    }
+}
+
+impl Default for TrieSizes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<&TrieHeader> for TrieSizes {
+    fn from(val: &TrieHeader) -> Self {
+        Self::from_header(val)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hwdb::{TrieEntry, TrieReader};
+    use crate::Result;
+
+    #[test]
+    fn test_trie_sizes() -> Result<()> {
+        let head = TrieHeader::new()
+            .with_node_size(32)
+            .with_child_entry_size(16)
+            .with_value_entry_size(16);
+        let v1 = TrieSizes::from(&head);
+        assert!(!v1.has_value_sources());
+        assert!(TrieSizes::new().has_value_sources());
+
+        // node with one child, and one version 1 value, padded to the node size
+        let mut buf = vec![0u8; 32 + 16 + 16];
+        buf[0..8].copy_from_slice(&7u64.to_le_bytes());
+        buf[8] = 1;
+        buf[16..24].copy_from_slice(&1u64.to_le_bytes());
+        buf[32] = b'a';
+        buf[40..48].copy_from_slice(&64u64.to_le_bytes());
+        buf[48..56].copy_from_slice(&3u64.to_le_bytes());
+        buf[56..64].copy_from_slice(&5u64.to_le_bytes());
+
+        let entry = TrieReader::new(&buf).with_sizes(v1).read_entry(0)?;
+        assert_eq!(entry.sizes(), v1);
+        assert_eq!(entry.len(), buf.len());
+        assert_eq!(entry.children()[0].c(), b'a');
+        assert_eq!(entry.children()[0].child_off(), 64);
+        assert_eq!(entry.values()[0].key_off(), 3);
+        assert_eq!(entry.values()[0].value_off(), 5);
+
+        // the default sizes of another database do not change this one
+        assert!(TrieEntry::try_from(buf.as_slice()).is_err());
+        assert!(TrieEntry::from_bytes(&buf, TrieSizes::from(&TrieHeader::new())).is_err());
+
+        Ok(())
+    }
+}
@@ -1,29 +1,47 @@
 use std::mem;
 
-use crate::{hwdb, Error, Result};
+use crate::{Error, Result};
+
+use super::{TrieReader, MIN_VALUE_ENTRY_SIZE};
 
 /// Trie value entry in the hardware database.
 ///
 /// Array of value entries that directly follows the node record.
+///
+/// Version 2 entries ([VALUE_ENTRY2_SIZE] bytes) also record the source of the value, used to
+/// order duplicate keys by file priority and line number.
 #[repr(C, packed(8))]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct TrieValueEntry {
     key_off: u64,
     value_off: u64,
+    filename_off: u64,
+    line_number: u32,
+    file_priority: u16,
+    _padding: u16,
 }
 
+/// Length of the encoded version 2 [TrieValueEntry].
+pub const VALUE_ENTRY2_SIZE: usize = 32;
+
 impl TrieValueEntry {
     /// Creates a new [TrieValueEntry].
     pub const fn new() -> Self {
         Self {
             key_off: 0,
             value_off: 0,
+            filename_off: 0,
+            line_number: 0,
+            file_priority: 0,
+            _padding: 0,
         }
     }
 
-    /// Gets the length of the encoded [TrieValueEntry].
-    pub fn len(&self) -> usize {
-        hwdb::value_entry_size()
+    /// Gets the length of the encoded [TrieValueEntry], in the current `hwdb.bin` format.
+    ///
+    /// Version 1 databases use shorter entries, see [TrieSizes](super::TrieSizes).
+    pub const fn len(&self) -> usize {
+        mem::size_of::<Self>()
     }
 
     /// Gets whether the [TrieValueEntry] is empty.
@@ -62,26 +80,108 @@ impl TrieValueEntry {
         self.set_value_off(val);
         self
     }
+
+    /// Gets source filename offset.
+    ///
+    /// **NOTE**: only set for version 2 entries.
+    pub const fn filename_off(&self) -> u64 {
+        self.filename_off
+    }
+
+    /// Sets source filename offset.
+    pub fn set_filename_off(&mut self, val: u64) {
+        self.filename_off = val;
+    }
+
+    /// Builder function that sets the source filename offset.
+    pub fn with_filename_off(mut self, val: u64) -> Self {
+        self.set_filename_off(val);
+        self
+    }
+
+    /// Gets source line number.
+    ///
+    /// **NOTE**: only set for version 2 entries.
+    pub const fn line_number(&self) -> u32 {
+        self.line_number
+    }
+
+    /// Sets source line number.
+    pub fn set_line_number(&mut self, val: u32) {
+        self.line_number = val;
+    }
+
+    /// Builder function that sets the source line number.
+    pub fn with_line_number(mut self, val: u32) -> Self {
+        self.set_line_number(val);
+        self
+    }
+
+    /// Gets source file priority.
+    ///
+    /// **NOTE**: only set for version 3 databases, `0` otherwise.
+    pub const fn file_priority(&self) -> u16 {
+        self.file_priority
+    }
+
+    /// Sets source file priority.
+    pub fn set_file_priority(&mut self, val: u16) {
+        self.file_priority = val;
+    }
+
+    /// Builder function that sets the source file priority.
+    pub fn with_file_priority(mut self, val: u16) -> Self {
+        self.set_file_priority(val);
+        self
+    }
+
+    /// Gets whether this entry has lower priority than the `other` entry for the same key.
+    ///
+    /// From the `sd-hwdb` documentation:
+    ///
+    /// ```no_build,no_run
+    /// On duplicates, we order by filename priority and line-number.
+    ///
+    /// For v3 data: we compare the priority (of the source file) and the line number.
+    ///
+    /// For v2 data: we rely on the fact that the filenames in the hwdb are added in the order
+    /// of priority (higher later), because they are *processed* in the order of priority.
+    /// ```
+    pub const fn is_lower_priority(&self, other: &Self) -> bool {
+        let (line, other_line) = (self.line_number, other.line_number);
+
+        if self.file_priority == 0 {
+            let (off, other_off) = (self.filename_off, other.filename_off);
+            off < other_off || (off == other_off && line < other_line)
+        } else {
+            let (prio, other_prio) = (self.file_priority, other.file_priority);
+            prio < other_prio || (prio == other_prio && line < other_line)
+        }
+    }
 }
 
+// Entries of at least `VALUE_ENTRY2_SIZE` bytes are parsed as version 2 entries.
 impl TryFrom<&[u8]> for TrieValueEntry {
     type Error = Error;
 
     fn try_from(val: &[u8]) -> Result<Self> {
-        if val.len() < hwdb::value_entry_size() {
+        if val.len() < MIN_VALUE_ENTRY_SIZE {
             Err(Error::InvalidLen(val.len()))
         } else {
-            // TODO: parse use get ranges and offsets
-            let mut idx = 0usize;
-            let mut idx_end = mem::size_of::<u64>();
+            let reader = TrieReader::new(val);
+
+            let key_off = reader.read_u64(0)?;
+            let value_off = reader.read_u64(8)?;
 
-            let key_off = u64::from_le_bytes(val[idx..idx_end].try_into()?);
-            idx += idx_end;
-            idx_end += idx_end;
+            let mut entry = Self::new().with_key_off(key_off).with_value_off(value_off);
 
-            let value_off = u64::from_le_bytes(val[idx..idx_end].try_into()?);
+            if val.len() >= VALUE_ENTRY2_SIZE {
+                entry.set_filename_off(reader.read_u64(16)?);
+                entry.set_line_number(reader.read_u32(24)?);
+                entry.set_file_priority(reader.read_u16(28)?);
+            }
 
-            Ok(Self { key_off, value_off })
+            Ok(entry)
         }
     }
 }
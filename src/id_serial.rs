@@ -0,0 +1,122 @@
+//! Composition of the `ID_SERIAL` device property.
+//!
+//! Follows the `vendor_model_serial` composition rules of the `udev` identification helpers
+//! (`ata_id`, `scsi_id`, `usb_id`), so tools can predict the `/dev/disk/by-id` symlink names for
+//! devices before the `udev` database is populated.
+
+use crate::{util, UdevDevice};
+
+/// Composes the `ID_SERIAL` value for the [UdevDevice].
+///
+/// Each component is read from the device properties, falling back to the `sysfs` attributes
+/// of the device:
+///
+/// - vendor: `ID_VENDOR`, `vendor`, or `manufacturer`
+/// - model: `ID_MODEL`, `model`, or `product`
+/// - serial: `ID_SERIAL_SHORT`, or `serial`
+///
+/// Components are sanitized with [replace_whitespace](util::replace_whitespace) and
+/// [replace_chars](util::replace_chars), and joined with `_`. ATA devices (`ID_BUS=ata`) do not
+/// include the vendor, like `ata_id`.
+///
+/// Returns: `Some(String)` with the composed `ID_SERIAL`, `None` if the device has no model.
+pub fn compose_id_serial(device: &mut UdevDevice) -> Option<String> {
+    let is_ata = device.get_property_value("ID_BUS") == Some("ata");
+
+    let vendor = if is_ata {
+        String::new()
+    } else {
+        id_component(device, "ID_VENDOR", &["vendor", "manufacturer"])
+    };
+    let model = id_component(device, "ID_MODEL", &["model", "product"]);
+    let serial = id_component(device, "ID_SERIAL_SHORT", &["serial"]);
+
+    if model.is_empty() {
+        None
+    } else {
+        Some(
+            [vendor, model, serial]
+                .into_iter()
+                .filter(|c| !c.is_empty())
+                .collect::<Vec<String>>()
+                .join("_"),
+        )
+    }
+}
+
+/// Verifies the `ID_SERIAL` property of the [UdevDevice] against the composed value.
+///
+/// Returns: `true` if the device has an `ID_SERIAL` property equal to [compose_id_serial].
+pub fn verify_id_serial(device: &mut UdevDevice) -> bool {
+    match compose_id_serial(device) {
+        Some(serial) => device.get_property_value("ID_SERIAL") == Some(serial.as_str()),
+        None => false,
+    }
+}
+
+fn id_component(device: &mut UdevDevice, property: &str, sysattrs: &[&str]) -> String {
+    let value = match device.get_property_value(property) {
+        Some(val) if !val.is_empty() => val.to_owned(),
+        _ if device.syspath().is_empty() => String::new(),
+        _ => sysattrs
+            .iter()
+            .filter_map(|attr| device.get_sysattr_value(attr))
+            .find(|val| !val.trim().is_empty())
+            .unwrap_or_default(),
+    };
+
+    util::replace_chars(util::replace_whitespace(value.as_str()).as_str(), "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Udev;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_compose_id_serial() {
+        let udev = Arc::new(Udev::new());
+
+        let mut usb = UdevDevice::new(Arc::clone(&udev));
+        usb.add_property_from_string("ID_VENDOR=SanDisk");
+        usb.add_property_from_string("ID_MODEL=Ultra  Fit ");
+        usb.add_property_from_string("ID_SERIAL_SHORT=4C53(0001)");
+
+        assert_eq!(
+            compose_id_serial(&mut usb).as_deref(),
+            Some("SanDisk_Ultra_Fit_4C53_0001_")
+        );
+        assert!(!verify_id_serial(&mut usb));
+
+        usb.add_property_from_string("ID_SERIAL=SanDisk_Ultra_Fit_4C53_0001_");
+        assert!(verify_id_serial(&mut usb));
+
+        // ATA devices do not include the vendor
+        let mut ata = UdevDevice::new(Arc::clone(&udev));
+        ata.add_property_from_string("ID_BUS=ata");
+        ata.add_property_from_string("ID_VENDOR=ATA");
+        ata.add_property_from_string("ID_MODEL=Samsung SSD 870 EVO 1TB");
+        ata.add_property_from_string("ID_SERIAL_SHORT=S6PTNX0T123456");
+
+        assert_eq!(
+            compose_id_serial(&mut ata).as_deref(),
+            Some("Samsung_SSD_870_EVO_1TB_S6PTNX0T123456")
+        );
+
+        // no serial
+        let mut no_serial = UdevDevice::new(Arc::clone(&udev));
+        no_serial.add_property_from_string("ID_VENDOR=QEMU");
+        no_serial.add_property_from_string("ID_MODEL=QEMU HARDDISK");
+
+        assert_eq!(
+            compose_id_serial(&mut no_serial).as_deref(),
+            Some("QEMU_QEMU_HARDDISK")
+        );
+
+        // no model
+        let mut no_model = UdevDevice::new(udev);
+        assert!(compose_id_serial(&mut no_model).is_none());
+        assert!(!verify_id_serial(&mut no_model));
+    }
+}
@@ -0,0 +1,69 @@
+//! Non-blocking reads of `inotify` events.
+
+use std::os::fd::RawFd;
+use std::{cmp, io, mem};
+
+/// Size of the buffer used to read `inotify` events.
+const EVENT_BUF_LEN: usize = 4096;
+
+/// Event read from an `inotify` file descriptor.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct InotifyEvent {
+    pub(crate) wd: i32,
+    pub(crate) mask: u32,
+    /// Name of the file in a watched directory, empty for events of the watched file itself.
+    pub(crate) name: String,
+}
+
+/// Reads the pending events of the non-blocking `inotify` file descriptor, calling `f` for each
+/// event, until the read would block.
+///
+/// Returns: `Ok(())` once all pending events are read, `Err(io::Error)` if the read fails.
+pub(crate) fn read_events<F: FnMut(InotifyEvent)>(fd: RawFd, mut f: F) -> io::Result<()> {
+    let mut buf = [0u8; EVENT_BUF_LEN];
+    let header_len = mem::size_of::<libc::inotify_event>();
+
+    loop {
+        // SAFETY: the file descriptor is valid, and the buffer references valid memory of the
+        // provided length.
+        let read = unsafe { libc::read(fd, buf.as_mut_ptr() as *mut _, EVENT_BUF_LEN) };
+
+        if read < 0 {
+            let errno = io::Error::last_os_error();
+            return match errno.kind() {
+                io::ErrorKind::WouldBlock => Ok(()),
+                io::ErrorKind::Interrupted => continue,
+                _ => Err(errno),
+            };
+        }
+
+        let read = read as usize;
+        if read == 0 {
+            return Ok(());
+        }
+
+        let mut idx = 0usize;
+        while idx + header_len <= read {
+            // SAFETY: the buffer contains at least one full event header at `idx`, and
+            // `read_unaligned` does not require alignment.
+            let event =
+                unsafe { (buf.as_ptr().add(idx) as *const libc::inotify_event).read_unaligned() };
+
+            let name_start = idx + header_len;
+            let name_end = cmp::min(name_start + event.len as usize, read);
+            let name = buf[name_start..name_end]
+                .split(|&c| c == 0)
+                .next()
+                .map(|n| String::from_utf8_lossy(n).into_owned())
+                .unwrap_or_default();
+
+            f(InotifyEvent {
+                wd: event.wd,
+                mask: event.mask,
+                name,
+            });
+
+            idx = name_start + event.len as usize;
+        }
+    }
+}
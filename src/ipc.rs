@@ -0,0 +1,1079 @@
+//! Device queries, and events over [varlink](https://varlink.org), for non-Rust clients.
+//!
+//! An [IpcServer] serves the `org.freedesktop.UdevRs` interface, see [INTERFACE_DESCRIPTION],
+//! on a Unix socket, so clients in any language can share one privileged daemon:
+//!
+//! - `ListDevices`: enumerates the devices matching a filter, like [UdevEnumerate]
+//! - `GetDevice`: gets one device by syspath
+//! - `Monitor`: streams the events of a [UdevMonitor], called with the varlink `more` flag
+//!
+//! ```no_run
+//! # use std::sync::Arc;
+//! # use udevrs::ipc::IpcServer;
+//! # use udevrs::{Udev, UdevMonitor};
+//! # fn main() -> udevrs::Result<()> {
+//! let udev = Arc::new(Udev::new());
+//!
+//! let mut monitor = UdevMonitor::new_from_netlink(Arc::clone(&udev), "udev")?;
+//! monitor.enable_receiving()?;
+//!
+//! IpcServer::bind(udev, "/run/udevrs/io.udevrs")?
+//!     .with_monitor(monitor)
+//!     .run()
+//! # }
+//! ```
+//!
+//! The devices can then be queried with any varlink client, e.g.
+//! `varlinkctl call /run/udevrs/io.udevrs org.freedesktop.UdevRs.ListDevices '{}'`.
+//!
+//! Only clients running as `root`, the server user, or one of the
+//! [allowed users](IpcServer::set_allowed_uids) may query devices, since the server may read
+//! attributes, and databases, the clients cannot.
+//!
+//! Requires the `ipc` feature.
+
+use std::io::{self, Read, Write};
+use std::os::fd::AsRawFd;
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::net::{SocketAddr, UnixListener, UnixStream};
+use std::sync::{mpsc, Arc};
+use std::{thread, time};
+
+use serde_json::{json, Map, Value};
+
+use crate::peer::{peer_allowed, peer_cred, PeerQueue};
+
+use crate::{
+    Error, ErrorKind, ErrorScope, Result, SysPath, Udev, UdevDevice, UdevEnumerate, UdevList,
+    UdevMonitor,
+};
+
+/// Name of the varlink interface served by the [IpcServer].
+pub const INTERFACE_NAME: &str = "org.freedesktop.UdevRs";
+
+/// Varlink interface description served by the [IpcServer].
+pub const INTERFACE_DESCRIPTION: &str = "\
+# Device queries, and events of a udevrs daemon.
+interface org.freedesktop.UdevRs
+
+# A device, with its udev properties, tags, and devlinks.
+type Device (
+  syspath: string,
+  devpath: string,
+  subsystem: string,
+  sysname: string,
+  devtype: ?string,
+  devnode: ?string,
+  driver: ?string,
+  action: ?string,
+  seqnum: int,
+  properties: [string]string,
+  tags: []string,
+  devlinks: []string
+)
+
+# Device filters, with the semantics of the libudev enumerate matches.
+type DeviceFilter (
+  subsystems: ?[]string,
+  nomatch_subsystems: ?[]string,
+  sysnames: ?[]string,
+  devtypes: ?[]string,
+  tags: ?[]string,
+  sysattrs: ?[]string,
+  properties: ?[string]string,
+  is_initialized: ?bool
+)
+
+# Lists the devices matching the filter.
+method ListDevices(filter: ?DeviceFilter) -> (devices: []Device)
+
+# Gets the device with the syspath.
+method GetDevice(syspath: string) -> (device: Device)
+
+# Streams the events of devices in any of the subsystems, with all of the tags.
+# Requires the `more` flag.
+method Monitor(subsystems: ?[]string, tags: ?[]string) -> (device: Device)
+
+error NoSuchDevice (syspath: string)
+error MonitorUnavailable ()
+";
+
+// Interface description of the varlink service interface.
+const SERVICE_DESCRIPTION: &str = "\
+interface org.varlink.service
+
+method GetInfo() -> (
+  vendor: string,
+  product: string,
+  version: string,
+  url: string,
+  interfaces: []string
+)
+
+method GetInterfaceDescription(interface: string) -> (description: string)
+
+error InterfaceNotFound (interface: string)
+error MethodNotFound (method: string)
+error MethodNotImplemented (method: string)
+error InvalidParameter (parameter: string)
+error PermissionDenied ()
+error ExpectedMore ()
+";
+
+const SERVICE_NAME: &str = "org.varlink.service";
+/// Default maximum number of clients connected to an [IpcServer].
+pub const DEFAULT_MAX_CONNECTIONS: usize = 64;
+/// Maximum length of a pending request.
+const REQUEST_MAX: usize = 64 * 1024;
+/// Maximum length of the events queued for a client, before new events are dropped.
+const CLIENT_QUEUE_LIMIT: usize = 1024 * 1024;
+
+// Event subscription of a `Monitor` call.
+#[derive(Clone, Debug, Default)]
+struct IpcSubscription {
+    subsystems: Vec<String>,
+    tags: Vec<String>,
+}
+
+impl IpcSubscription {
+    fn matches(&self, device: &mut UdevDevice) -> bool {
+        (self.subsystems.is_empty()
+            || self
+                .subsystems
+                .iter()
+                .any(|s| s.as_str() == device.get_subsystem()))
+            && self.tags.iter().all(|tag| device.has_tag(tag))
+    }
+}
+
+// Reply to a varlink call.
+enum IpcReply {
+    Parameters(Value),
+    Error(String, Value),
+    Subscribe(IpcSubscription),
+    ListDevices(Box<UdevEnumerate>),
+}
+
+// State shared by the connections of the [IpcServer] while answering calls.
+struct IpcContext<'a> {
+    udev: &'a Arc<Udev>,
+    has_monitor: bool,
+    workers: &'a IpcWorkers,
+}
+
+// Runs `ListDevices` calls on worker threads, so enumerating `sys` never blocks the server.
+struct IpcWorkers {
+    sender: mpsc::Sender<(u64, Vec<u8>)>,
+    replies: mpsc::Receiver<(u64, Vec<u8>)>,
+    // written by the workers to wake the server, when a reply is ready
+    waker: Arc<UnixStream>,
+    wakeup: UnixStream,
+}
+
+impl IpcWorkers {
+    fn new() -> io::Result<Self> {
+        let (sender, replies) = mpsc::channel();
+        let (wakeup, waker) = UnixStream::pair()?;
+        wakeup.set_nonblocking(true)?;
+        waker.set_nonblocking(true)?;
+
+        Ok(Self {
+            sender,
+            replies,
+            waker: Arc::new(waker),
+            wakeup,
+        })
+    }
+
+    // Enumerates the devices on a worker thread, the reply is sent to the connection `id`.
+    fn list_devices(&self, id: u64, enumerate: UdevEnumerate) -> io::Result<()> {
+        let sender = self.sender.clone();
+        let waker = Arc::clone(&self.waker);
+
+        thread::Builder::new()
+            .name("udevrs-ipc".into())
+            .spawn(move || {
+                let reply = json!({ "parameters": { "devices": list_devices(enumerate) } });
+
+                if sender.send((id, encode(&reply))).is_ok() {
+                    // a full wakeup buffer already wakes the server
+                    (&*waker).write_all(&[0]).ok();
+                }
+            })
+            .map(|_| ())
+    }
+
+    // Drains the wakeup socket, and collects the finished replies.
+    fn finished(&self) -> Vec<(u64, Vec<u8>)> {
+        let mut buf = [0u8; 64];
+        while matches!((&self.wakeup).read(buf.as_mut()), Ok(count) if count > 0) {}
+
+        self.replies.try_iter().collect()
+    }
+}
+
+// Client connection of the [IpcServer].
+struct IpcConnection {
+    id: u64,
+    queue: PeerQueue,
+    buf: Vec<u8>,
+    subscription: Option<IpcSubscription>,
+    // whether the peer user may call the `org.freedesktop.UdevRs` methods
+    authorized: bool,
+    // whether a `ListDevices` call runs on a worker, later calls wait for its reply
+    listing: bool,
+}
+
+impl IpcConnection {
+    // Whether new calls are answered: no call is running, and the previous replies were sent.
+    fn wants_calls(&self) -> bool {
+        !self.listing && self.queue.is_empty()
+    }
+
+    fn poll_events(&self) -> libc::c_short {
+        let read = if self.wants_calls() { libc::POLLIN } else { 0 };
+
+        read | self.queue.poll_events()
+    }
+
+    // Sends queued messages, reads, and answers the pending calls, returns `false` if the
+    // connection is closed.
+    fn process(&mut self, revents: libc::c_short, ctx: &IpcContext) -> bool {
+        if !self.flush() {
+            return false;
+        }
+
+        if revents & libc::POLLIN != 0 {
+            if !self.read() {
+                return false;
+            }
+        } else if revents & (libc::POLLHUP | libc::POLLERR) != 0 {
+            log::debug!("ipc: closing disconnected connection");
+            return false;
+        }
+
+        self.handle_calls(ctx)
+    }
+
+    // Reads the pending calls, returns `false` if the connection is closed.
+    fn read(&mut self) -> bool {
+        let mut chunk = [0u8; 4096];
+
+        match self.queue.stream().read(chunk.as_mut()) {
+            Ok(0) => false,
+            Ok(count) => {
+                self.buf.extend_from_slice(&chunk[..count]);
+
+                if self.buf.len() > REQUEST_MAX {
+                    log::debug!("ipc: closing connection with an oversized call");
+                    false
+                } else {
+                    true
+                }
+            }
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+                ) =>
+            {
+                true
+            }
+            Err(err) => {
+                log::debug!("ipc: closing connection: {err}");
+                false
+            }
+        }
+    }
+
+    // Answers the buffered calls, one at a time, returns `false` if the connection is closed.
+    fn handle_calls(&mut self, ctx: &IpcContext) -> bool {
+        while self.wants_calls() {
+            let Some(end) = self.buf.iter().position(|&b| b == 0) else {
+                break;
+            };
+            let msg: Vec<u8> = self.buf.drain(..=end).collect();
+
+            let Ok(call) = serde_json::from_slice::<Value>(&msg[..end]) else {
+                log::debug!("ipc: closing connection on invalid call");
+                return false;
+            };
+            let oneway = call["oneway"].as_bool().unwrap_or(false);
+
+            let reply = match handle_call(ctx.udev, ctx.has_monitor, self.authorized, &call) {
+                IpcReply::Parameters(parameters) => json!({ "parameters": parameters }),
+                IpcReply::Error(error, parameters) => {
+                    json!({ "error": error, "parameters": parameters })
+                }
+                IpcReply::Subscribe(subscription) => {
+                    self.subscription = Some(subscription);
+                    continue;
+                }
+                IpcReply::ListDevices(_) if oneway => continue,
+                IpcReply::ListDevices(enumerate) => {
+                    if let Err(err) = ctx.workers.list_devices(self.id, *enumerate) {
+                        log::warn!("ipc: closing connection, unable to list devices: {err}");
+                        return false;
+                    }
+                    self.listing = true;
+                    continue;
+                }
+            };
+
+            if oneway {
+                continue;
+            }
+
+            self.queue.push(encode(&reply));
+            if !self.flush() {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    // Queues the reply of a finished `ListDevices` call, then answers the calls waiting for it.
+    fn finish_list(&mut self, reply: Vec<u8>, ctx: &IpcContext) -> bool {
+        self.listing = false;
+        self.queue.push(reply);
+
+        self.flush() && self.handle_calls(ctx)
+    }
+
+    // Queues an event, dropped if the client does not read its messages, returns `false` if the
+    // connection is closed.
+    fn queue_event(&mut self, msg: &[u8], dropped_events: &mut u64) -> bool {
+        if !self.queue.try_push(msg.to_vec()) {
+            *dropped_events = dropped_events.saturating_add(1);
+        }
+
+        self.flush()
+    }
+
+    fn flush(&mut self) -> bool {
+        self.queue
+            .flush()
+            .inspect_err(|err| log::debug!("ipc: closing connection: {err}"))
+            .is_ok()
+    }
+}
+
+/// Serves device queries, and events over the `org.freedesktop.UdevRs` varlink interface.
+///
+/// Clients are identified by their `SO_PEERCRED` credentials. Clients running as `root`, the
+/// server user, or one of the [allowed users](Self::set_allowed_uids) may call the
+/// `org.freedesktop.UdevRs` methods, other users get the `PermissionDenied` error.
+///
+/// The server never blocks on a client: `ListDevices` calls enumerate on worker threads, and
+/// messages are queued per client, and sent without blocking. A client that does not read its
+/// messages drops events, see [dropped_events](Self::dropped_events).
+pub struct IpcServer {
+    udev: Arc<Udev>,
+    listener: UnixListener,
+    monitor: Option<UdevMonitor>,
+    connections: Vec<IpcConnection>,
+    address: String,
+    allowed_uids: Vec<libc::uid_t>,
+    max_connections: usize,
+    next_id: u64,
+    dropped_events: u64,
+    workers: IpcWorkers,
+}
+
+impl IpcServer {
+    /// Creates a new [IpcServer], listening on the Unix socket `address`.
+    ///
+    /// Addresses starting with `@` are abstract socket names, others are file system paths,
+    /// which must not exist.
+    ///
+    /// Returns: `Ok(IpcServer)` on success, `Err(Error)` if the socket cannot be bound.
+    pub fn bind(udev: Arc<Udev>, address: &str) -> Result<Self> {
+        let listener = match address.strip_prefix('@') {
+            Some(name) => {
+                SocketAddr::from_abstract_name(name).and_then(|addr| UnixListener::bind_addr(&addr))
+            }
+            None => UnixListener::bind(address),
+        }
+        .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+        .map_err(|err| {
+            Error::os(
+                ErrorScope::Udev,
+                format!("unable to bind ipc socket {address}: {err}"),
+                err,
+            )
+        })?;
+
+        let workers = IpcWorkers::new().map_err(|err| {
+            Error::os(
+                ErrorScope::Udev,
+                format!("unable to create ipc workers: {err}"),
+                err,
+            )
+        })?;
+
+        Ok(Self {
+            udev,
+            listener,
+            monitor: None,
+            connections: Vec::new(),
+            address: address.into(),
+            allowed_uids: Vec::new(),
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            next_id: 0,
+            dropped_events: 0,
+            workers,
+        })
+    }
+
+    /// Gets the socket address.
+    pub fn address(&self) -> &str {
+        self.address.as_str()
+    }
+
+    /// Gets a reference to the [UdevMonitor] serving `Monitor` calls, if set.
+    pub const fn monitor(&self) -> Option<&UdevMonitor> {
+        self.monitor.as_ref()
+    }
+
+    /// Sets the [UdevMonitor] serving `Monitor` calls.
+    ///
+    /// The `monitor` should already be [receiving](UdevMonitor::enable_receiving). Without a
+    /// monitor, `Monitor` calls fail with the `MonitorUnavailable` error.
+    pub fn set_monitor(&mut self, monitor: UdevMonitor) {
+        self.monitor = Some(monitor);
+    }
+
+    /// Builder function that sets the [UdevMonitor] serving `Monitor` calls.
+    pub fn with_monitor(mut self, monitor: UdevMonitor) -> Self {
+        self.set_monitor(monitor);
+        self
+    }
+
+    /// Gets the user IDs of clients authorized in addition to `root`, and the server user.
+    pub fn allowed_uids(&self) -> &[libc::uid_t] {
+        self.allowed_uids.as_ref()
+    }
+
+    /// Sets the user IDs of clients authorized in addition to `root`, and the server user.
+    ///
+    /// Applies to clients connecting afterwards.
+    pub fn set_allowed_uids(&mut self, uids: &[libc::uid_t]) {
+        self.allowed_uids = uids.to_vec();
+    }
+
+    /// Builder function that sets the user IDs of clients authorized in addition to `root`, and
+    /// the server user.
+    pub fn with_allowed_uids(mut self, uids: &[libc::uid_t]) -> Self {
+        self.set_allowed_uids(uids);
+        self
+    }
+
+    /// Gets the maximum number of connected clients.
+    pub const fn max_connections(&self) -> usize {
+        self.max_connections
+    }
+
+    /// Sets the maximum number of connected clients, further connections are closed.
+    ///
+    /// Defaults to [DEFAULT_MAX_CONNECTIONS].
+    pub fn set_max_connections(&mut self, max: usize) {
+        self.max_connections = max;
+    }
+
+    /// Builder function that sets the maximum number of connected clients.
+    pub fn with_max_connections(mut self, max: usize) -> Self {
+        self.set_max_connections(max);
+        self
+    }
+
+    /// Gets the number of connected clients.
+    pub fn connections(&self) -> usize {
+        self.connections.len()
+    }
+
+    /// Gets the number of events dropped, because a client did not read its messages.
+    pub const fn dropped_events(&self) -> u64 {
+        self.dropped_events
+    }
+
+    /// Waits up to `timeout`, or forever for `None`, then answers the pending calls, accepts new
+    /// clients, and streams a received event to the subscribed clients.
+    ///
+    /// Returns: `Ok(())` on success, `Err(Error)` if waiting, accepting a client, or receiving
+    /// an event fails.
+    pub fn dispatch(&mut self, timeout: Option<time::Duration>) -> Result<()> {
+        let pollfd = |fd, events| libc::pollfd {
+            fd,
+            events,
+            revents: 0,
+        };
+
+        // negative file descriptors are ignored by `poll`
+        let mut pfd = vec![
+            pollfd(self.listener.as_raw_fd(), libc::POLLIN),
+            pollfd(
+                self.monitor.as_ref().map(|m| m.sock()).unwrap_or(-1),
+                libc::POLLIN,
+            ),
+            pollfd(self.workers.wakeup.as_raw_fd(), libc::POLLIN),
+        ];
+        pfd.extend(
+            self.connections
+                .iter()
+                .map(|conn| pollfd(conn.queue.stream().as_raw_fd(), conn.poll_events())),
+        );
+
+        let timeout_ms = timeout
+            .map(|t| t.as_millis().min(i32::MAX as u128) as libc::c_int)
+            .unwrap_or(-1);
+
+        // SAFETY: `pollfd` is properly initialized, and the pointer references valid memory.
+        let ret = unsafe { libc::poll(pfd.as_mut_ptr(), pfd.len() as libc::nfds_t, timeout_ms) };
+        if ret < 0 {
+            let errno = io::Error::last_os_error();
+            return if errno.kind() == io::ErrorKind::Interrupted {
+                Ok(())
+            } else {
+                Err(Error::os(
+                    ErrorScope::Udev,
+                    format!("error waiting for ipc clients: {errno}"),
+                    errno,
+                ))
+            };
+        }
+
+        let ctx = IpcContext {
+            udev: &self.udev,
+            has_monitor: self.monitor.is_some(),
+            workers: &self.workers,
+        };
+
+        let mut revents = pfd[3..].iter().map(|p| p.revents);
+        self.connections.retain_mut(|conn| {
+            let revents = revents.next().unwrap_or(0);
+            revents == 0 || conn.process(revents, &ctx)
+        });
+
+        if pfd[2].revents != 0 {
+            let mut finished = self.workers.finished();
+
+            self.connections.retain_mut(|conn| {
+                match finished.iter().position(|(id, _)| *id == conn.id) {
+                    Some(pos) => conn.finish_list(finished.swap_remove(pos).1, &ctx),
+                    None => true,
+                }
+            });
+        }
+
+        if pfd[0].revents != 0 {
+            self.accept_connections()?;
+        }
+
+        if pfd[1].revents != 0 {
+            self.forward_event()?;
+        }
+
+        Ok(())
+    }
+
+    /// Serves the clients until an error occurs.
+    ///
+    /// Returns: `Err(Error)` if [dispatch](Self::dispatch) fails.
+    pub fn run(&mut self) -> Result<()> {
+        loop {
+            self.dispatch(None)?;
+        }
+    }
+
+    fn accept_connections(&mut self) -> Result<()> {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    let address = self.address.as_str();
+                    let (pid, uid) = match peer_cred(&stream) {
+                        Ok(cred) => cred,
+                        Err(err) => {
+                            log::debug!("ipc {address}: rejecting client: {err}");
+                            continue;
+                        }
+                    };
+
+                    if self.connections.len() >= self.max_connections {
+                        log::warn!("ipc {address}: rejecting client pid={pid}, too many clients");
+                        continue;
+                    }
+
+                    let authorized = peer_allowed(uid, self.allowed_uids.as_ref());
+                    log::debug!(
+                        "ipc {address}: client pid={pid}, uid={uid} connected, authorized: {authorized}"
+                    );
+
+                    self.next_id = self.next_id.wrapping_add(1);
+                    self.connections.push(IpcConnection {
+                        id: self.next_id,
+                        queue: PeerQueue::new(stream, CLIENT_QUEUE_LIMIT)?,
+                        buf: Vec::new(),
+                        subscription: None,
+                        authorized,
+                        listing: false,
+                    });
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                Err(err) => {
+                    return Err(Error::os(
+                        ErrorScope::Udev,
+                        format!("unable to accept ipc client: {err}"),
+                        err,
+                    ))
+                }
+            }
+        }
+    }
+
+    fn forward_event(&mut self) -> Result<()> {
+        let Some(monitor) = self.monitor.as_mut() else {
+            return Ok(());
+        };
+
+        let mut device = match monitor.receive_device() {
+            Ok(device) => device,
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    ErrorKind::Filtered | ErrorKind::WouldBlock | ErrorKind::Interrupted
+                ) =>
+            {
+                log::trace!("ipc {}: {err}", self.address);
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+
+        let msg = encode(&json!({
+            "parameters": { "device": device_json(&mut device) },
+            "continues": true,
+        }));
+
+        let dropped_events = &mut self.dropped_events;
+        self.connections
+            .retain_mut(|conn| match conn.subscription.as_ref() {
+                Some(subscription) if subscription.matches(&mut device) => {
+                    conn.queue_event(msg.as_ref(), dropped_events)
+                }
+                _ => true,
+            });
+
+        Ok(())
+    }
+}
+
+// Encodes a varlink message, terminated by a NUL byte.
+fn encode(msg: &Value) -> Vec<u8> {
+    let mut buf = msg.to_string().into_bytes();
+    buf.push(0);
+    buf
+}
+
+fn invalid_parameter(parameter: &str) -> IpcReply {
+    IpcReply::Error(
+        format!("{SERVICE_NAME}.InvalidParameter"),
+        json!({ "parameter": parameter }),
+    )
+}
+
+fn handle_call(udev: &Arc<Udev>, has_monitor: bool, authorized: bool, call: &Value) -> IpcReply {
+    let method = call["method"].as_str().unwrap_or_default();
+    let parameters = &call["parameters"];
+    let more = call["more"].as_bool().unwrap_or(false);
+
+    let Some((interface, name)) = method.rsplit_once('.') else {
+        return invalid_parameter("method");
+    };
+
+    if interface == INTERFACE_NAME && !authorized {
+        return IpcReply::Error(format!("{SERVICE_NAME}.PermissionDenied"), json!({}));
+    }
+
+    match (interface, name) {
+        (SERVICE_NAME, "GetInfo") => IpcReply::Parameters(json!({
+            "vendor": env!("CARGO_PKG_AUTHORS"),
+            "product": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+            "url": env!("CARGO_PKG_REPOSITORY"),
+            "interfaces": [SERVICE_NAME, INTERFACE_NAME],
+        })),
+        (SERVICE_NAME, "GetInterfaceDescription") => match parameters["interface"].as_str() {
+            Some(SERVICE_NAME) => {
+                IpcReply::Parameters(json!({ "description": SERVICE_DESCRIPTION }))
+            }
+            Some(INTERFACE_NAME) => {
+                IpcReply::Parameters(json!({ "description": INTERFACE_DESCRIPTION }))
+            }
+            Some(other) => IpcReply::Error(
+                format!("{SERVICE_NAME}.InterfaceNotFound"),
+                json!({ "interface": other }),
+            ),
+            None => invalid_parameter("interface"),
+        },
+        (INTERFACE_NAME, "ListDevices") => match enumerate_filter(udev, &parameters["filter"]) {
+            Some(enumerate) => IpcReply::ListDevices(Box::new(enumerate)),
+            None => invalid_parameter("filter"),
+        },
+        (INTERFACE_NAME, "GetDevice") => {
+            let Some(syspath) = parameters["syspath"].as_str() else {
+                return invalid_parameter("syspath");
+            };
+
+            match SysPath::new_in(udev.get_sys_path(), syspath)
+                .and_then(|path| UdevDevice::new_from_syspath(Arc::clone(udev), &path))
+            {
+                Ok(mut dev) => {
+                    IpcReply::Parameters(json!({ "device": loaded_device_json(&mut dev) }))
+                }
+                Err(err) => {
+                    log::debug!("ipc: {err}");
+                    IpcReply::Error(
+                        format!("{INTERFACE_NAME}.NoSuchDevice"),
+                        json!({ "syspath": syspath }),
+                    )
+                }
+            }
+        }
+        (INTERFACE_NAME, "Monitor") => {
+            if !more {
+                IpcReply::Error(format!("{SERVICE_NAME}.ExpectedMore"), json!({}))
+            } else if !has_monitor {
+                IpcReply::Error(format!("{INTERFACE_NAME}.MonitorUnavailable"), json!({}))
+            } else {
+                match (
+                    string_list(&parameters["subsystems"]),
+                    string_list(&parameters["tags"]),
+                ) {
+                    (Some(subsystems), Some(tags)) => {
+                        IpcReply::Subscribe(IpcSubscription { subsystems, tags })
+                    }
+                    (None, _) => invalid_parameter("subsystems"),
+                    (_, None) => invalid_parameter("tags"),
+                }
+            }
+        }
+        (SERVICE_NAME, _) | (INTERFACE_NAME, _) => IpcReply::Error(
+            format!("{SERVICE_NAME}.MethodNotFound"),
+            json!({ "method": method }),
+        ),
+        (interface, _) => IpcReply::Error(
+            format!("{SERVICE_NAME}.InterfaceNotFound"),
+            json!({ "interface": interface }),
+        ),
+    }
+}
+
+// Parses an optional list of strings, `None` if the value is invalid.
+fn string_list(value: &Value) -> Option<Vec<String>> {
+    match value {
+        Value::Null => Some(Vec::new()),
+        Value::Array(list) => list.iter().map(|v| v.as_str().map(String::from)).collect(),
+        _ => None,
+    }
+}
+
+// Creates an enumerator with the matches of a `DeviceFilter`, `None` if the filter is invalid.
+fn enumerate_filter(udev: &Arc<Udev>, filter: &Value) -> Option<UdevEnumerate> {
+    let mut enumerate = UdevEnumerate::new(Arc::clone(udev));
+
+    if filter.is_null() {
+        return Some(enumerate);
+    } else if !filter.is_object() {
+        return None;
+    }
+
+    for subsystem in string_list(&filter["subsystems"])? {
+        enumerate.add_match_subsystem(&subsystem).ok()?;
+    }
+    for subsystem in string_list(&filter["nomatch_subsystems"])? {
+        enumerate.add_nomatch_subsystem(&subsystem).ok()?;
+    }
+    for sysname in string_list(&filter["sysnames"])? {
+        enumerate.add_match_sysname(&sysname).ok()?;
+    }
+    for devtype in string_list(&filter["devtypes"])? {
+        enumerate.add_match_devtype(&devtype).ok()?;
+    }
+    for tag in string_list(&filter["tags"])? {
+        enumerate.add_match_tag(&tag).ok()?;
+    }
+    for sysattr in string_list(&filter["sysattrs"])? {
+        let (name, value) = sysattr
+            .split_once('=')
+            .map_or((sysattr.as_str(), None), |(name, value)| {
+                (name, Some(value))
+            });
+        enumerate.add_match_sysattr(name, value).ok()?;
+    }
+
+    match &filter["properties"] {
+        Value::Null => (),
+        Value::Object(properties) => {
+            for (key, value) in properties.iter() {
+                enumerate.add_match_property(key, value.as_str()?).ok()?;
+            }
+        }
+        _ => return None,
+    }
+
+    match &filter["is_initialized"] {
+        Value::Null => (),
+        Value::Bool(val) => enumerate.set_match_is_initialized(*val),
+        _ => return None,
+    }
+
+    Some(enumerate)
+}
+
+// Enumerates, and encodes the devices, run on a worker thread.
+fn list_devices(enumerate: UdevEnumerate) -> Vec<Value> {
+    enumerate
+        .scan_iter()
+        .filter_map(|dev| {
+            dev.inspect_err(|err| log::debug!("ipc: {err}"))
+                .ok()
+                .map(|mut dev| loaded_device_json(&mut dev))
+        })
+        .collect()
+}
+
+// Reads the `uevent` file, and database of a `sys` device, then encodes it.
+fn loaded_device_json(device: &mut UdevDevice) -> Value {
+    device.read_uevent_file().ok();
+    if let Err(err) = device.read_db() {
+        log::trace!("ipc: no database for {}: {err}", device.syspath());
+    }
+
+    device_json(device)
+}
+
+// Encodes a `Device` of the varlink interface.
+fn device_json(device: &mut UdevDevice) -> Value {
+    let names = |list: &UdevList| -> Vec<String> {
+        list.iter().map(|entry| entry.name().to_owned()).collect()
+    };
+
+    let properties: Map<String, Value> = device
+        .properties_list()
+        .iter()
+        .map(|entry| (entry.name().to_owned(), Value::from(entry.value())))
+        .collect();
+
+    let mut obj = Map::new();
+    obj.insert("syspath".into(), device.syspath().into());
+    obj.insert("devpath".into(), device.devpath().into());
+    obj.insert("subsystem".into(), device.get_subsystem().into());
+    obj.insert("sysname".into(), device.sysname().into());
+
+    for (key, value) in [
+        (
+            "devtype",
+            Some(device.get_devtype()).filter(|d| !d.is_empty()),
+        ),
+        ("devnode", device.get_devnode()),
+        ("driver", device.get_driver()),
+        ("action", device.get_action()),
+    ] {
+        if let Some(value) = value {
+            obj.insert(key.into(), value.into());
+        }
+    }
+
+    obj.insert("seqnum".into(), device.seqnum().into());
+    obj.insert("properties".into(), properties.into());
+    obj.insert("tags".into(), names(device.tags_list()).into());
+    obj.insert("devlinks".into(), names(device.devlinks_list()).into());
+
+    obj.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn call(server: &mut IpcServer, client: &mut UnixStream, request: Value) -> Result<Value> {
+        client.write_all(encode(&request).as_ref())?;
+        wait_reply(server, client)
+    }
+
+    // Dispatches until the client can read a reply, e.g. from a `ListDevices` worker.
+    fn wait_reply(server: &mut IpcServer, client: &mut UnixStream) -> Result<Value> {
+        for _ in 0..500 {
+            server.dispatch(Some(time::Duration::from_millis(10)))?;
+
+            let mut pfd = [libc::pollfd {
+                fd: client.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            }];
+            // SAFETY: `pollfd` is properly initialized, and the pointer references valid memory.
+            if unsafe { libc::poll(pfd.as_mut_ptr(), 1, 0) } > 0 {
+                return reply(client);
+            }
+        }
+
+        Err(Error::Udev("no ipc reply".into()))
+    }
+
+    fn reply(client: &mut UnixStream) -> Result<Value> {
+        let mut buf = Vec::new();
+        let mut byte = [0u8; 1];
+
+        while client.read(byte.as_mut())? == 1 && byte[0] != 0 {
+            buf.push(byte[0]);
+        }
+
+        serde_json::from_slice(&buf).map_err(|err| Error::Udev(format!("invalid reply: {err}")))
+    }
+
+    #[test]
+    fn test_ipc_server() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+        let address = format!("@udevrs-ipc-test-{}", std::process::id());
+
+        let (mut sender, receiver) = UdevMonitor::new_loopback(Arc::clone(&udev))?;
+        let mut server = IpcServer::bind(Arc::clone(&udev), &address)?.with_monitor(receiver);
+        assert_eq!(server.address(), address);
+        assert!(server.monitor().is_some());
+
+        let mut client = SocketAddr::from_abstract_name(&address[1..])
+            .and_then(|addr| UnixStream::connect_addr(&addr))?;
+        client.set_read_timeout(Some(time::Duration::from_secs(1)))?;
+        server.dispatch(Some(time::Duration::from_secs(1)))?;
+        assert_eq!(server.connections(), 1);
+
+        let info = call(
+            &mut server,
+            &mut client,
+            json!({ "method": "org.varlink.service.GetInfo" }),
+        )?;
+        assert_eq!(info["parameters"]["product"], "udevrs");
+        assert_eq!(info["parameters"]["interfaces"][1], INTERFACE_NAME);
+
+        let desc = call(
+            &mut server,
+            &mut client,
+            json!({
+                "method": "org.varlink.service.GetInterfaceDescription",
+                "parameters": { "interface": INTERFACE_NAME },
+            }),
+        )?;
+        assert_eq!(desc["parameters"]["description"], INTERFACE_DESCRIPTION);
+
+        let missing = call(
+            &mut server,
+            &mut client,
+            json!({
+                "method": "org.freedesktop.UdevRs.GetDevice",
+                "parameters": { "syspath": "/devices/virtual/mock/missing" },
+            }),
+        )?;
+        assert_eq!(missing["error"], "org.freedesktop.UdevRs.NoSuchDevice");
+
+        let none = call(
+            &mut server,
+            &mut client,
+            json!({
+                "method": "org.freedesktop.UdevRs.ListDevices",
+                "parameters": { "filter": { "subsystems": ["udevrs-missing"] } },
+            }),
+        )?;
+        assert_eq!(none["parameters"]["devices"], json!([]));
+
+        let invalid = call(
+            &mut server,
+            &mut client,
+            json!({
+                "method": "org.freedesktop.UdevRs.ListDevices",
+                "parameters": { "filter": { "subsystems": 1 } },
+            }),
+        )?;
+        assert_eq!(invalid["error"], "org.varlink.service.InvalidParameter");
+
+        // attribute names cannot leave the device directory
+        let traversal = call(
+            &mut server,
+            &mut client,
+            json!({
+                "method": "org.freedesktop.UdevRs.ListDevices",
+                "parameters": { "filter": { "sysattrs": ["../../../../../etc/passwd=root:*"] } },
+            }),
+        )?;
+        assert_eq!(traversal["error"], "org.varlink.service.InvalidParameter");
+
+        // calls are answered in order, also behind a running `ListDevices`
+        client.write_all(
+            encode(&json!({
+                "method": "org.freedesktop.UdevRs.ListDevices",
+                "parameters": { "filter": { "subsystems": ["udevrs-missing"] } },
+            }))
+            .as_ref(),
+        )?;
+        let listed = call(
+            &mut server,
+            &mut client,
+            json!({ "method": "org.varlink.service.GetInfo" }),
+        )?;
+        assert_eq!(listed["parameters"]["devices"], json!([]));
+        let info = wait_reply(&mut server, &mut client)?;
+        assert_eq!(info["parameters"]["product"], "udevrs");
+
+        let no_more = call(
+            &mut server,
+            &mut client,
+            json!({ "method": "org.freedesktop.UdevRs.Monitor" }),
+        )?;
+        assert_eq!(no_more["error"], "org.varlink.service.ExpectedMore");
+
+        // subscribe, then stream an event from the monitor
+        client.write_all(
+            encode(&json!({
+                "method": "org.freedesktop.UdevRs.Monitor",
+                "parameters": { "subsystems": ["mock"] },
+                "more": true,
+            }))
+            .as_ref(),
+        )?;
+        server.dispatch(Some(time::Duration::from_secs(1)))?;
+
+        let mut device = UdevDevice::builder(Arc::clone(&udev))
+            .devpath("/devices/virtual/mock/mock0")
+            .subsystem("mock")
+            .action("add")
+            .tag("seat")
+            .build()?;
+        sender.send_device(None, &mut device)?;
+        server.dispatch(Some(time::Duration::from_secs(1)))?;
+
+        let event = reply(&mut client)?;
+        assert_eq!(event["continues"], true);
+        assert_eq!(
+            event["parameters"]["device"]["devpath"],
+            "/devices/virtual/mock/mock0"
+        );
+        assert_eq!(event["parameters"]["device"]["action"], "add");
+        assert_eq!(event["parameters"]["device"]["tags"], json!(["seat"]));
+
+        // clients above the limit are closed
+        server.set_max_connections(1);
+        let mut extra = SocketAddr::from_abstract_name(&address[1..])
+            .and_then(|addr| UnixStream::connect_addr(&addr))?;
+        server.dispatch(Some(time::Duration::from_secs(1)))?;
+        assert_eq!(server.connections(), 1);
+        assert_eq!(extra.read(&mut [0u8; 1])?, 0);
+
+        drop(client);
+        server.dispatch(Some(time::Duration::from_secs(1)))?;
+        assert_eq!(server.connections(), 0);
+
+        Ok(())
+    }
+}
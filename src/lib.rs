@@ -7,39 +7,64 @@ use std::sync::Arc;
 #[macro_use]
 extern crate bitflags;
 
+pub mod bridge;
+mod builtin;
 mod context;
 mod device;
 mod enumerate;
 mod error;
 mod file;
+pub mod hash;
 mod hwdb;
+mod id_serial;
+mod inotify;
+#[cfg(feature = "ipc")]
+pub mod ipc;
+pub mod links;
 mod list;
 mod log;
+mod metrics;
 mod mode;
 mod monitor;
-mod murmur_hash;
+pub mod node;
+mod path;
+mod pattern;
+mod peer;
 mod queue;
 mod socket;
+mod sysattr_watch;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod usb;
 mod util;
+pub mod watch;
 
+pub use builtin::*;
 pub use context::*;
 pub use device::*;
 pub use enumerate::*;
 pub use error::*;
 pub use file::*;
+pub use hash::{murmur_hash2, string_bloom64, string_hash32};
 pub use hwdb::*;
+pub use id_serial::*;
 pub use list::*;
 pub use log::*;
+#[cfg(feature = "metrics")]
+pub use metrics::*;
 pub use mode::*;
 pub use monitor::*;
-pub use murmur_hash::*;
+pub use path::*;
 pub use queue::*;
 pub use socket::*;
+pub use sysattr_watch::*;
 pub use util::*;
 
-/// Creates a new [Udev] context.
+/// Creates a new [Udev] context, with the settings from the `udev` configuration file.
+///
+/// See [Udev::new_from_config].
 pub fn udev_new() -> Arc<Udev> {
-    Arc::new(Udev::new())
+    Arc::new(Udev::new_from_config())
 }
 
 /// Gets the [LogPriority] for the [Udev] context.
@@ -52,6 +77,11 @@ pub fn udev_set_log_priority(udev: &mut Udev, val: LogPriority) {
     udev.set_log_priority(val);
 }
 
+/// Sets the logging callback for the [Udev] context.
+pub fn udev_set_log_fn<F: Fn(LogPriority, &str) + Send + Sync + 'static>(udev: &mut Udev, f: F) {
+    udev.set_log_fn(f);
+}
+
 /// Gets a reference to the next entry in a [UdevList].
 ///
 /// Breaks with the original `libudev` API by requiring a reference to the list, instead of a list
@@ -129,7 +159,8 @@ pub fn udev_device_get_udev_cloned(device: &UdevDevice) -> Arc<Udev> {
 ///
 /// Returns: a new [UdevDevice], or `Error`, if it does not exist
 pub fn udev_device_new_from_syspath(udev: Arc<Udev>, syspath: &str) -> Result<UdevDevice> {
-    UdevDevice::new_from_syspath(udev, syspath)
+    let syspath = SysPath::new_in(udev.get_sys_path(), syspath)?;
+    UdevDevice::new_from_syspath(udev, &syspath)
 }
 
 /// Creates new [UdevDevice].
@@ -336,8 +367,10 @@ pub fn udev_device_get_sysname(dev: &UdevDevice) -> &str {
 /// ```no_build,no_run
 /// Get the instance number of the device.
 /// ```
-pub fn udev_device_get_sysnum(dev: &UdevDevice) -> &str {
-    dev.sysnum()
+///
+/// Returns: the instance number, or `None` if the device has none.
+pub fn udev_device_get_sysnum(dev: &UdevDevice) -> Option<&str> {
+    dev.get_sysnum()
 }
 
 /// Gets the [UdevDevice] `devnode`.
@@ -349,8 +382,8 @@ pub fn udev_device_get_sysnum(dev: &UdevDevice) -> &str {
 /// The path is an absolute path, and starts with the device directory.
 /// ```
 ///
-/// Returns: the device node file name of the [UdevDevice], or an empty string if none exists.
-pub fn udev_device_get_devnode(dev: &mut UdevDevice) -> &str {
+/// Returns: the device node file name of the [UdevDevice], or `None` if none exists.
+pub fn udev_device_get_devnode(dev: &UdevDevice) -> Option<&str> {
     dev.get_devnode()
 }
 
@@ -416,7 +449,7 @@ pub fn udev_device_get_tags_list_entry(dev: &mut UdevDevice) -> Option<&UdevEntr
 /// can be retrieved from the list entry by udev_list_entry_get_name().
 /// ```
 ///
-/// Returns: the current entry of the tag list
+/// Returns: the first entry of the current tag list
 pub fn udev_device_get_current_tags_list_entry(dev: &mut UdevDevice) -> Option<&UdevEntry> {
     dev.get_current_tags_list_entry()
 }
@@ -444,12 +477,12 @@ pub fn udev_device_get_property_value<'d>(dev: &'d UdevDevice, key: &str) -> Opt
 /// Gets the kernel driver name.
 ///
 /// Returns: the kernel driver name, or `None`  if none is attached.
-pub fn udev_device_get_driver(dev: &mut UdevDevice) -> Option<&str> {
+pub fn udev_device_get_driver(dev: &UdevDevice) -> Option<&str> {
     dev.get_driver()
 }
 
 /// Gets the device major/minor number.
-pub fn udev_device_get_devnum(dev: &mut UdevDevice) -> u64 {
+pub fn udev_device_get_devnum(dev: &UdevDevice) -> u64 {
     dev.get_devnum()
 }
 
@@ -464,8 +497,8 @@ pub fn udev_device_get_devnum(dev: &mut UdevDevice) -> u64 {
 /// ```
 ///
 /// Returns the kernel action value, or `None` if there is no action value available.
-pub fn udev_device_get_action(dev: &UdevDevice) -> &str {
-    dev.action()
+pub fn udev_device_get_action(dev: &UdevDevice) -> Option<&str> {
+    dev.get_action()
 }
 
 /// Gets the device event sequence number.
@@ -509,7 +542,7 @@ pub fn udev_device_get_usec_since_initialized(dev: &mut UdevDevice) -> u64 {
 /// ```
 ///
 /// Returns: the content of a sys attribute file, or `None` if there is no sys attribute value.
-pub fn udev_device_get_sysattr_value(dev: &mut UdevDevice, sysattr: &str) -> Option<String> {
+pub fn udev_device_get_sysattr_value(dev: &UdevDevice, sysattr: &str) -> Option<String> {
     dev.get_sysattr_value(sysattr)
 }
 
@@ -531,10 +564,8 @@ pub fn udev_device_has_tag(dev: &mut UdevDevice, tag: &str) -> bool {
 /// ```no_build,no_run
 /// Check if a given device has a certain tag associated.
 /// ```
-///
-/// TODO: `eudev` does not database does not support current tags, implement in this library.
 pub fn udev_device_has_current_tag(dev: &mut UdevDevice, tag: &str) -> bool {
-    dev.has_tag(tag)
+    dev.has_current_tag(tag)
 }
 
 /// Creates a new [UdevMonitor] from the provided parameters.
@@ -733,8 +764,9 @@ pub fn udev_enumerate_add_nomatch_subsystem<'e>(
 pub fn udev_enumerate_add_match_sysattr<'e>(
     enumerate: &'e mut UdevEnumerate,
     sysattr: &str,
+    value: Option<&str>,
 ) -> Result<&'e UdevEntry> {
-    enumerate.add_match_sysattr(sysattr)
+    enumerate.add_match_sysattr(sysattr, value)
 }
 
 /// Adds an entry to the no-match sysattr [UdevEntry] list.
@@ -749,8 +781,9 @@ pub fn udev_enumerate_add_match_sysattr<'e>(
 pub fn udev_enumerate_add_nomatch_sysattr<'e>(
     enumerate: &'e mut UdevEnumerate,
     sysattr: &str,
+    value: Option<&str>,
 ) -> Result<&'e UdevEntry> {
-    enumerate.add_nomatch_sysattr(sysattr)
+    enumerate.add_nomatch_sysattr(sysattr, value)
 }
 
 /// Adds an entry to the match properties [UdevEntry] list.
@@ -910,6 +943,35 @@ pub fn udev_queue_get_queue_is_empty(queue: &UdevQueue) -> bool {
     queue.queue_is_empty()
 }
 
+/// Gets the current kernel event sequence number.
+///
+/// Returns: `Ok(u64)` on success, `Err(Error)` if the kernel sequence number cannot be read.
+pub fn udev_queue_get_kernel_seqnum(queue: &UdevQueue) -> Result<u64> {
+    queue.get_kernel_seqnum()
+}
+
+/// Gets the last event sequence number queued by the `udev` daemon.
+pub fn udev_queue_get_udev_seqnum(queue: &UdevQueue) -> u64 {
+    queue.get_udev_seqnum()
+}
+
+/// Gets whether the event with `seqnum` has been processed by the `udev` daemon.
+pub fn udev_queue_get_seqnum_is_finished(queue: &UdevQueue, seqnum: u64) -> bool {
+    queue.get_seqnum_is_finished(seqnum)
+}
+
+/// Gets whether all events in the range `start..=end` have been processed by the `udev` daemon.
+pub fn udev_queue_get_seqnum_sequence_is_finished(queue: &UdevQueue, start: u64, end: u64) -> bool {
+    queue.get_seqnum_sequence_is_finished(start, end)
+}
+
+/// Gets the first entry of the list of events currently queued by the `udev` daemon.
+///
+/// Entry names are the device syspaths, and entry values are the event sequence numbers.
+pub fn udev_queue_get_queued_list_entry(queue: &mut UdevQueue) -> Option<&UdevEntry> {
+    queue.get_queued_list_entry()
+}
+
 /// Gets a file descriptor to watch for a queue to become empty.
 pub fn udev_queue_get_fd(queue: &mut UdevQueue) -> Result<i32> {
     queue.get_fd()
@@ -917,11 +979,6 @@ pub fn udev_queue_get_fd(queue: &mut UdevQueue) -> Result<i32> {
 
 /// Clears the watched file descriptor for queue changes.
 ///
-/// # Safety
-///
-/// Users must ensure that every [UdevQueue] has a unique file descriptor, if the descriptor is
-/// non-negative.
-///
 /// Returns: `Ok(())` on success, `Err(Error)` otherwise.
 pub fn udev_queue_flush(queue: &mut UdevQueue) -> Result<()> {
     queue.flush()
@@ -0,0 +1,177 @@
+//! Resolution of device node symlinks, e.g. `/dev/disk/by-uuid/...`.
+//!
+//! Persistent device names are symlinks to the device node, created by `udev` rules. The helpers
+//! map a symlink to the [UdevDevice] of the device node, and a [UdevDevice] back to the symlinks
+//! pointing to its device node.
+//!
+//! ```no_run
+//! # use std::sync::Arc;
+//! # use udevrs::{links, Udev};
+//! # fn main() -> udevrs::Result<()> {
+//! let udev = Arc::new(Udev::new());
+//!
+//! let dev = links::resolve_devlink(udev, "/dev/disk/by-label/root")?;
+//! println!("syspath: {}", dev.syspath());
+//!
+//! for link in links::find_devlinks_for(&dev)? {
+//!     println!("link: {link}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fs;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::sync::Arc;
+
+use crate::{Error, Result, Udev, UdevDevice};
+
+/// Maximum directory depth searched below the device link directory, e.g. `by-id/<link>`.
+const DEVLINK_DEPTH: usize = 4;
+
+/// Creates the [UdevDevice] of the device node a symlink points to.
+///
+/// The `path` is followed to the device node, e.g. `/dev/disk/by-uuid/<uuid>` to `/dev/sda1`,
+/// and the device is created from the device number of the node. Device node paths are also
+/// accepted.
+///
+/// Returns: `Ok(UdevDevice)` on success, `Err(Error)` if the `path` cannot be resolved, or does
+/// not point to a device node.
+pub fn resolve_devlink(udev: Arc<Udev>, path: &str) -> Result<UdevDevice> {
+    let metadata = fs::metadata(path)
+        .map_err(|err| Error::UdevDevice(format!("unable to resolve devlink {path}: {err}")))?;
+
+    let file_type = metadata.file_type();
+    let devtype = if file_type.is_block_device() {
+        "block"
+    } else if file_type.is_char_device() {
+        "char"
+    } else {
+        return Err(Error::UdevDevice(format!(
+            "devlink does not point to a device node: {path}"
+        )));
+    };
+
+    UdevDevice::new_from_devnum(udev, devtype, metadata.rdev())
+}
+
+/// Finds the symlinks below `/dev/disk` pointing to the device node of the [UdevDevice].
+///
+/// The `/dev/disk` hierarchies, e.g. `by-id`, `by-uuid`, `by-path`, are scanned, instead of
+/// reading the `udev` database, so links are found also for devices without a database entry.
+///
+/// Returns: `Ok(Vec<String>)` of the sorted symlink paths on success, `Err(Error)` if the device
+/// has no device number.
+pub fn find_devlinks_for(device: &UdevDevice) -> Result<Vec<String>> {
+    let dir = format!("{}/disk", device.udev().get_dev_path());
+    find_devlinks_in(device, dir.as_str())
+}
+
+/// Finds the symlinks below the directory `dir` pointing to the device node of the [UdevDevice].
+///
+/// See [find_devlinks_for].
+///
+/// Returns: `Ok(Vec<String>)` of the sorted symlink paths on success, `Err(Error)` if the device
+/// has no device number.
+pub fn find_devlinks_in(device: &UdevDevice, dir: &str) -> Result<Vec<String>> {
+    let devnum = device.get_devnum();
+    if devnum == 0 {
+        return Err(Error::UdevDevice(format!(
+            "device has no device number: {}",
+            device.syspath()
+        )));
+    }
+
+    let block = device.get_subsystem() == "block";
+    let mut links = Vec::new();
+
+    scan_devlinks(dir, DEVLINK_DEPTH, &mut |path| {
+        // dangling links, e.g. to removed devices, are skipped
+        let matched = fs::metadata(path).is_ok_and(|m| {
+            let file_type = m.file_type();
+            m.rdev() == devnum
+                && if block {
+                    file_type.is_block_device()
+                } else {
+                    file_type.is_char_device()
+                }
+        });
+
+        if matched {
+            links.push(path.to_owned());
+        }
+    });
+
+    links.sort();
+
+    Ok(links)
+}
+
+// Calls `f` with the path of each symlink below `dir`, without following symlinked directories.
+fn scan_devlinks(dir: &str, depth: usize, f: &mut dyn FnMut(&str)) {
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(err) => {
+            log::debug!("unable to read devlink directory {dir}: {err}");
+            return;
+        }
+    };
+
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = match entry.path().to_str() {
+            Some(p) => p.to_owned(),
+            None => continue,
+        };
+
+        match entry.file_type() {
+            Ok(t) if t.is_symlink() => f(path.as_str()),
+            Ok(t) if t.is_dir() && depth > 0 => scan_devlinks(path.as_str(), depth - 1, f),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FixtureDevice, SysfsFixture};
+    use std::os::unix::fs::symlink;
+    use std::path::Path;
+
+    // memory device of the `/dev/null` device node
+    fn fixture() -> Result<SysfsFixture> {
+        SysfsFixture::new()?
+            .with_device(FixtureDevice::new("/devices/virtual/mem/null", "mem").with_devnum(1, 3))
+    }
+
+    #[test]
+    fn test_devlinks() -> Result<()> {
+        let fixture = fixture()?;
+        let udev = fixture.udev();
+
+        let dir = Path::new(fixture.dev_path());
+        let by_id = dir.join("disk/by-id");
+        fs::create_dir_all(by_id.as_path())?;
+        symlink("/dev/null", by_id.join("null-link"))?;
+        symlink("/dev/zero", by_id.join("zero-link"))?;
+        symlink("/dev/udevrs-missing", by_id.join("dangling"))?;
+
+        let link = by_id.join("null-link");
+        let link = link.to_str().unwrap_or_default();
+
+        assert!(resolve_devlink(Arc::clone(&udev), "/").is_err());
+
+        let dev = resolve_devlink(Arc::clone(&udev), link)?;
+        assert_eq!(dev.get_devnum(), libc::makedev(1, 3));
+        assert_eq!(
+            dev.syspath(),
+            fixture.device("/devices/virtual/mem/null")?.syspath()
+        );
+
+        assert_eq!(find_devlinks_for(&dev)?, [link]);
+
+        assert!(find_devlinks_in(&UdevDevice::new(udev), "/dev").is_err());
+
+        Ok(())
+    }
+}
@@ -1,20 +1,26 @@
-use std::collections::{
-    linked_list::{Iter, IterMut},
-    LinkedList,
-};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{BuildHasherDefault, Hasher};
+use std::slice::{Iter, IterMut};
+use std::str::FromStr;
 use std::sync::Arc;
 
-use crate::{Udev, UdevDevice};
+use crate::{Error, Result, Udev, UdevDevice};
 
-/// Convenience alias for a [LinkedList] of [UdevEntry].
-pub type UdevEntryList = LinkedList<UdevEntry>;
+/// Convenience alias for a [Vec] of [UdevEntry].
+pub type UdevEntryList = Vec<UdevEntry>;
 
 /// Represents a list wrapper around a [UdevEntryList].
+///
+/// Entries keep their insertion order, and are indexed by a hash of the `name` for constant-time
+/// lookups, without allocating index keys.
 #[repr(C)]
-#[derive(Clone, Debug, Default, PartialEq)]
+#[derive(Clone, Debug, Default)]
 pub struct UdevList {
     udev: Arc<Udev>,
     list: UdevEntryList,
+    index: NameIndex,
+    indexed: bool,
     entries_cur: usize,
     entries_max: usize,
     unique: bool,
@@ -22,37 +28,47 @@ pub struct UdevList {
 
 impl UdevList {
     /// Creates a new [UdevList].
-    pub const fn new(udev: Arc<Udev>) -> Self {
-        Self {
-            udev,
-            list: LinkedList::new(),
-            entries_cur: 0,
-            entries_max: 0,
-            unique: true,
-        }
+    pub fn new(udev: Arc<Udev>) -> Self {
+        Self::create(udev, UdevEntryList::new())
     }
 
     /// Creates a new [UdevList] from the provided parameters.
-    pub const fn create(udev: Arc<Udev>, list: UdevEntryList) -> Self {
-        Self {
+    pub fn create(udev: Arc<Udev>, list: UdevEntryList) -> Self {
+        let mut ret = Self {
             udev,
             list,
+            index: NameIndex::default(),
+            indexed: false,
             entries_cur: 0,
             entries_max: 0,
             unique: true,
-        }
+        };
+        ret.reindex();
+        ret
     }
 
     /// Gets an [`Iterator`] over [UdevEntry] items.
-    pub fn iter(&self) -> Iter<UdevEntry> {
+    pub fn iter(&self) -> Iter<'_, UdevEntry> {
         self.list.iter()
     }
 
     /// Gets an [`Iterator`] over [UdevEntry] items.
-    pub fn iter_mut(&mut self) -> IterMut<UdevEntry> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, UdevEntry> {
+        // entry names may change, rebuild the index on the next update
+        self.indexed = false;
         self.list.iter_mut()
     }
 
+    /// Gets an [`Iterator`] over [UdevEntry] items, sorted by `name`.
+    ///
+    /// `libudev` keeps lists sorted, e.g. for the properties export of `udevadm info`. Entries
+    /// with the same `name` keep their insertion order.
+    pub fn sorted_iter(&self) -> std::vec::IntoIter<&UdevEntry> {
+        let mut entries: Vec<&UdevEntry> = self.list.iter().collect();
+        entries.sort_by(|a, b| a.name().cmp(b.name()));
+        entries.into_iter()
+    }
+
     /// Gets a reference to the [UdevEntryList].
     pub fn list(&self) -> &UdevEntryList {
         &self.list
@@ -60,12 +76,14 @@ impl UdevList {
 
     /// Gets a mutable reference to the [UdevEntryList].
     pub fn list_mut(&mut self) -> &mut UdevEntryList {
+        self.indexed = false;
         &mut self.list
     }
 
     /// Sets the [UdevEntryList].
     pub fn set_list<L: Into<UdevEntryList>>(&mut self, list: L) {
         self.list = list.into();
+        self.reindex();
     }
 
     /// Builder function that sets the [UdevEntryList].
@@ -87,36 +105,83 @@ impl UdevList {
     /// Clears the list of all entries.
     pub fn clear(&mut self) {
         self.list.clear();
+        self.index.clear();
+        self.indexed = true;
     }
 
     /// Gets an optional reference to the first [UdevEntry] in the [UdevEntryList].
     pub fn entry(&self) -> Option<&UdevEntry> {
-        self.list.front()
+        self.list.first()
     }
 
     /// Gets an optional mutable reference to the first [UdevEntry] in the [UdevEntryList].
     pub fn entry_mut(&mut self) -> Option<&mut UdevEntry> {
-        self.list.front_mut()
+        self.indexed = false;
+        self.list.first_mut()
     }
 
     /// Gets an optional reference to an [UdevEntry] with a matching `name`.
+    ///
+    /// For non-unique lists, the first matching entry is returned.
     pub fn entry_by_name(&self, name: &str) -> Option<&UdevEntry> {
-        self.list.iter().find(|e| e.name() == name)
+        self.position(name).map(|pos| &self.list[pos])
+    }
+
+    /// Gets the value of the [UdevEntry] with a matching `name`, parsed as `T`.
+    ///
+    /// See [UdevEntry::value_as].
+    ///
+    /// Returns: `Ok(T)` on success, `Err(Error)` if there is no matching entry, or the value
+    /// cannot be parsed.
+    pub fn get_as<T>(&self, name: &str) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.entry_by_name(name)
+            .ok_or_else(|| Error::Udev(format!("no entry with name: {name}")))?
+            .value_as()
+    }
+
+    /// Gets the value of the [UdevEntry] with a matching `name` as an unsigned number, e.g.
+    /// `USEC_INITIALIZED`, or `SEQNUM`.
+    ///
+    /// Returns: `Ok(u64)` on success, `Err(Error)` if there is no matching entry, or the value
+    /// is not a number.
+    pub fn get_u64(&self, name: &str) -> Result<u64> {
+        self.get_as(name)
+    }
+
+    /// Gets the value of the [UdevEntry] with a matching `name` as a boolean.
+    ///
+    /// See [UdevEntry::value_bool].
+    ///
+    /// Returns: `Ok(bool)` on success, `Err(Error)` if there is no matching entry, or the value
+    /// is not a boolean.
+    pub fn get_bool(&self, name: &str) -> Result<bool> {
+        self.entry_by_name(name)
+            .ok_or_else(|| Error::Udev(format!("no entry with name: {name}")))?
+            .value_bool()
     }
 
     /// Gets an optional mutable reference to an [UdevEntry] with a matching `name`.
+    ///
+    /// For non-unique lists, the first matching entry is returned.
     pub fn entry_by_name_mut(&mut self, name: &str) -> Option<&mut UdevEntry> {
-        self.list.iter_mut().find(|e| e.name() == name)
+        let pos = self.position(name)?;
+        self.indexed = false;
+        self.list.get_mut(pos)
     }
 
     /// Gets the next [UdevEntry] in the list.
     pub fn next_entry(&self) -> Option<&UdevEntry> {
-        self.list.iter().nth(self.entries_cur)
+        self.list.get(self.entries_cur)
     }
 
     /// Gets the next [UdevEntry] in the list.
     pub fn next_entry_mut(&mut self) -> Option<&mut UdevEntry> {
-        self.list.iter_mut().nth(self.entries_cur)
+        self.indexed = false;
+        self.list.get_mut(self.entries_cur)
     }
 
     /// Adds an entry to the list.
@@ -125,31 +190,95 @@ impl UdevList {
     ///
     /// If `value` is empty, the entry value with be empty.
     pub fn add_entry(&mut self, name: &str, value: &str) -> Option<&UdevEntry> {
-        if self.unique() && self.entry_by_name(name).is_some() {
-            let existing = self.entry_by_name_mut(name).unwrap();
-            log::trace!("Updating property, {name}: {} => {value}", existing.value());
-            existing.set_value(value);
-            Some(existing)
+        if !self.indexed {
+            self.reindex();
+        }
+
+        match self.position(name) {
+            Some(pos) if self.unique() => {
+                let existing = &mut self.list[pos];
+                log::trace!("Updating property, {name}: {} => {value}", existing.value());
+                existing.set_value(value);
+                Some(&*existing)
+            }
+            _ => {
+                log::trace!("Adding property, {name}: {value}");
+                self.index.entry(name_hash(name)).or_insert(self.list.len());
+                self.list
+                    .push(UdevEntry::new().with_name(name).with_value(value));
+                self.list.last()
+            }
+        }
+    }
+
+    /// Adds an entry to the list, with a `value` that may contain non-UTF-8 bytes.
+    ///
+    /// The entry value is the lossy UTF-8 conversion, and the raw bytes are kept for
+    /// [UdevEntry::value_bytes].
+    ///
+    /// If an [UdevEntry] with the same `name` exists, the `value` will be updated.
+    pub fn add_entry_bytes(&mut self, name: &str, value: &[u8]) -> Option<&UdevEntry> {
+        self.add_entry(name, &String::from_utf8_lossy(value))?;
+
+        // unique lists update the existing entry, others append a new one
+        let pos = if self.unique() {
+            self.position(name)?
         } else {
-            log::trace!("Adding property, {name}: {value}");
-            self.list
-                .push_back(UdevEntry::new().with_name(name).with_value(value));
-            self.list.back()
+            self.list.len().checked_sub(1)?
+        };
+
+        let entry = &mut self.list[pos];
+        entry.set_value_bytes(value);
+
+        Some(entry)
+    }
+
+    /// Sets the number of the [UdevEntry] with a matching `name`.
+    ///
+    /// Returns: `true` if a matching entry exists, `false` otherwise.
+    pub fn set_entry_num(&mut self, name: &str, num: i32) -> bool {
+        match self.position(name) {
+            Some(pos) => {
+                self.list[pos].set_num(num);
+                true
+            }
+            None => false,
         }
     }
 
     /// Removes an [UdevEntry] if an entry exists with a matching `name`.
     pub fn remove_entry(&mut self, name: &str) {
-        if let Some(pos) = self.list.iter().position(|e| e.name() == name) {
-            let mut ext = self.list.split_off(pos);
+        if let Some(pos) = self.position(name) {
+            self.list.remove(pos);
+            // positions after the removed entry shift, and duplicates may move up
+            self.reindex();
+        }
+    }
 
-            if ext.len() > 1 {
-                ext.pop_front();
-                self.list.append(&mut ext);
+    // Gets the position of the first entry with a matching `name`.
+    //
+    // Falls back to a linear search while the index is stale, or on hash collisions.
+    fn position(&self, name: &str) -> Option<usize> {
+        if self.indexed {
+            match self.index.get(&name_hash(name)).copied() {
+                Some(pos) if self.list[pos].name() == name => Some(pos),
+                Some(_) => self.list.iter().position(|e| e.name() == name),
+                None => None,
             }
+        } else {
+            self.list.iter().position(|e| e.name() == name)
         }
     }
 
+    // Rebuilds the name index from the entry list.
+    fn reindex(&mut self) {
+        self.index.clear();
+        for (pos, entry) in self.list.iter().enumerate() {
+            self.index.entry(name_hash(entry.name())).or_insert(pos);
+        }
+        self.indexed = true;
+    }
+
     /// Gets the current [UdevEntry].
     pub const fn entries_cur(&self) -> usize {
         self.entries_cur
@@ -165,17 +294,69 @@ impl UdevList {
         self.unique
     }
 
+    /// Sets whether the [UdevList] is unique.
+    ///
+    /// Unique lists update the value of an existing [UdevEntry] with the same `name`, instead of
+    /// adding a new entry.
+    pub fn set_unique(&mut self, unique: bool) {
+        self.unique = unique;
+    }
+
+    /// Builder function that sets whether the [UdevList] is unique.
+    pub fn with_unique(mut self, unique: bool) -> Self {
+        self.set_unique(unique);
+        self
+    }
+
     /// Gets whether the [UdevDevice] matches an [UdevEntry] in the list.
     pub fn has_tag(&self, device: &UdevDevice) -> bool {
         if self.is_empty() {
             true
         } else {
             self.iter()
-                .filter(|e| device.tags_list().entry_by_name(e.name()).is_some())
-                .count()
-                != 0
+                .any(|e| device.tags_list().entry_by_name(e.name()).is_some())
+        }
+    }
+}
+
+impl PartialEq for UdevList {
+    fn eq(&self, oth: &Self) -> bool {
+        self.udev == oth.udev
+            && self.list == oth.list
+            && self.entries_cur == oth.entries_cur
+            && self.entries_max == oth.entries_max
+            && self.unique == oth.unique
+    }
+}
+
+// Maps the hash of an entry name to the position of the first entry with that hash.
+type NameIndex = HashMap<u64, usize, BuildHasherDefault<NameHasher>>;
+
+// FNV-1a, cheap for the short property names, collisions fall back to a linear search.
+fn name_hash(name: &str) -> u64 {
+    name.bytes().fold(0xcbf2_9ce4_8422_2325, |h, b| {
+        (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// Passes the already hashed entry names through to the index.
+#[derive(Default)]
+struct NameHasher(u64);
+
+impl Hasher for NameHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 << 8) | u64::from(b);
         }
     }
+
+    fn write_u64(&mut self, val: u64) {
+        self.0 = val;
+    }
 }
 
 /// UDEV list entry.
@@ -186,6 +367,7 @@ impl UdevList {
 pub struct UdevEntry {
     name: String,
     value: String,
+    raw_value: Option<Box<[u8]>>,
     num: i32,
 }
 
@@ -195,6 +377,7 @@ impl UdevEntry {
         Self {
             name: String::new(),
             value: String::new(),
+            raw_value: None,
             num: 0,
         }
     }
@@ -223,6 +406,7 @@ impl UdevEntry {
     /// Sets the [UdevEntry] value.
     pub fn set_value<N: Into<String>>(&mut self, value: N) {
         self.value = value.into();
+        self.raw_value = None;
     }
 
     /// Builder function that sets the [UdevEntry] value.
@@ -231,6 +415,68 @@ impl UdevEntry {
         self
     }
 
+    /// Gets the raw bytes of the [UdevEntry] value.
+    ///
+    /// Differs from the [value](Self::value) bytes only if the value was set from non-UTF-8
+    /// bytes, e.g. firmware strings in kernel events.
+    pub fn value_bytes(&self) -> &[u8] {
+        match self.raw_value.as_deref() {
+            Some(raw) => raw,
+            None => self.value.as_bytes(),
+        }
+    }
+
+    /// Sets the [UdevEntry] value from raw bytes.
+    ///
+    /// The [value](Self::value) is the lossy UTF-8 conversion of the bytes, and the raw bytes are
+    /// kept if they are not valid UTF-8.
+    pub fn set_value_bytes(&mut self, value: &[u8]) {
+        match std::str::from_utf8(value) {
+            Ok(val) => self.set_value(val),
+            Err(_) => {
+                self.value = String::from_utf8_lossy(value).into_owned();
+                self.raw_value = Some(value.into());
+            }
+        }
+    }
+
+    /// Builder function that sets the [UdevEntry] value from raw bytes.
+    pub fn with_value_bytes(mut self, value: &[u8]) -> Self {
+        self.set_value_bytes(value);
+        self
+    }
+
+    /// Gets the [UdevEntry] value parsed as `T`, e.g. a number.
+    ///
+    /// Surrounding whitespace is ignored.
+    ///
+    /// Returns: `Ok(T)` on success, `Err(Error)` if the value cannot be parsed.
+    pub fn value_as<T>(&self) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        parse_value(self.name(), self.value())
+    }
+
+    /// Gets the [UdevEntry] value as a boolean, like `udev` rules, and `systemd`.
+    ///
+    /// Accepts `1`, `yes`, `y`, `true`, `t`, and `on` as `true`, and `0`, `no`, `n`, `false`,
+    /// `f`, and `off` as `false`, ignoring case.
+    ///
+    /// Returns: `Ok(bool)` on success, `Err(Error)` for other values.
+    pub fn value_bool(&self) -> Result<bool> {
+        match self.value().trim().to_ascii_lowercase().as_str() {
+            "1" | "yes" | "y" | "true" | "t" | "on" => Ok(true),
+            "0" | "no" | "n" | "false" | "f" | "off" => Ok(false),
+            _ => Err(Error::Udev(format!(
+                "invalid boolean value for {}: {}",
+                self.name(),
+                self.value()
+            ))),
+        }
+    }
+
     /// Gets the [UdevEntry] number.
     pub const fn num(&self) -> i32 {
         self.num
@@ -248,6 +494,18 @@ impl UdevEntry {
     }
 }
 
+// Parses a `name` entry `value`, with an error naming the entry.
+pub(crate) fn parse_value<T>(name: &str, value: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    value
+        .trim()
+        .parse::<T>()
+        .map_err(|err| Error::Udev(format!("invalid value for {name}: {value}, {err}")))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -283,5 +541,121 @@ mod tests {
         assert_eq!(null_entry.num(), exp_num);
 
         assert_eq!(null_entry, exp_entry);
+
+        let raw = b"Vendor \xff";
+        let mut raw_entry = UdevEntry::new().with_name(exp_name).with_value_bytes(raw);
+        assert_eq!(raw_entry.value(), "Vendor \u{fffd}");
+        assert_eq!(raw_entry.value_bytes(), raw);
+
+        raw_entry.set_value(exp_value);
+        assert_eq!(raw_entry.value_bytes(), exp_value.as_bytes());
+    }
+
+    #[test]
+    fn test_udev_entry_typed() -> Result<()> {
+        let mut list = UdevList::new(Arc::new(Udev::new()));
+
+        list.add_entry("IFINDEX", "3");
+        list.add_entry("USEC_INITIALIZED", " 12345678\n");
+        list.add_entry("USEC_INITIALIZED", "12345678");
+        list.add_entry("ID_BUS", "usb");
+        list.add_entry("ID_MOCK", "Yes");
+        list.add_entry("ID_OTHER", "off");
+
+        assert_eq!(list.get_as::<i32>("IFINDEX")?, 3);
+        assert_eq!(list.get_u64("USEC_INITIALIZED")?, 12_345_678);
+        assert!(list.get_bool("ID_MOCK")?);
+        assert!(!list.get_bool("ID_OTHER")?);
+
+        assert!(list.get_u64("ID_BUS").is_err());
+        assert!(list.get_bool("ID_BUS").is_err());
+        assert!(list.get_u64("MISSING").is_err());
+        assert!(list.get_bool("MISSING").is_err());
+
+        let entry = UdevEntry::new().with_name("MAJOR").with_value(" 8 ");
+        assert_eq!(entry.value_as::<u32>()?, 8);
+        assert!(entry.value_as::<i8>().is_ok());
+        assert!(entry.with_value("-1").value_as::<u32>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_udev_list_index() {
+        let mut list = UdevList::new(Arc::new(Udev::new()));
+
+        list.add_entry("ACTION", "add");
+        list.add_entry("DEVPATH", "/devices/virtual/mock/mock0");
+        list.add_entry("SUBSYSTEM", "mock");
+        list.add_entry("ACTION", "change");
+
+        // updates keep the insertion order
+        let names: Vec<&str> = list.iter().map(|e| e.name()).collect();
+        assert_eq!(names, ["ACTION", "DEVPATH", "SUBSYSTEM"]);
+        assert_eq!(
+            list.entry_by_name("ACTION").map(|e| e.value()),
+            Some("change")
+        );
+
+        list.remove_entry("DEVPATH");
+        assert!(list.entry_by_name("DEVPATH").is_none());
+        assert_eq!(
+            list.entry_by_name("SUBSYSTEM").map(|e| e.value()),
+            Some("mock")
+        );
+
+        // renames through mutable references are picked up by the next lookup
+        if let Some(entry) = list.entry_by_name_mut("SUBSYSTEM") {
+            entry.set_name("DEVTYPE");
+        }
+        assert!(list.entry_by_name("SUBSYSTEM").is_none());
+        assert!(list.entry_by_name("DEVTYPE").is_some());
+
+        list.add_entry("SUBSYSTEM", "mock");
+        assert_eq!(list.len(), 3);
+        assert!(list.set_entry_num("SUBSYSTEM", 1));
+        assert_eq!(list.entry_by_name("SUBSYSTEM").map(|e| e.num()), Some(1));
+
+        // non-unique lists keep duplicates, and lookups return the first match
+        let mut list = UdevList::new(Arc::new(Udev::new())).with_unique(false);
+        list.add_entry("block", "disk");
+        list.add_entry("block", "partition");
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.entry_by_name("block").map(|e| e.value()), Some("disk"));
+
+        list.remove_entry("block");
+        assert_eq!(
+            list.entry_by_name("block").map(|e| e.value()),
+            Some("partition")
+        );
+        // removing the only entry leaves an empty list
+        list.remove_entry("block");
+        assert!(list.is_empty());
+        assert!(list.entry_by_name("block").is_none());
+        list.remove_entry("block");
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn test_udev_list_sorted_iter() {
+        let mut list = UdevList::new(Arc::new(Udev::new())).with_unique(false);
+        list.add_entry("SUBSYSTEM", "block");
+        list.add_entry("DEVNAME", "/dev/sda");
+        list.add_entry("ACTION", "add");
+        list.add_entry("DEVNAME", "/dev/sdb");
+
+        let sorted: Vec<(&str, &str)> = list.sorted_iter().map(|e| (e.name(), e.value())).collect();
+        assert_eq!(
+            sorted,
+            [
+                ("ACTION", "add"),
+                ("DEVNAME", "/dev/sda"),
+                ("DEVNAME", "/dev/sdb"),
+                ("SUBSYSTEM", "block"),
+            ]
+        );
+
+        // insertion order is kept
+        assert_eq!(list.entry().map(|e| e.name()), Some("SUBSYSTEM"));
     }
 }
@@ -1,4 +1,7 @@
 use std::fmt;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::{Error, Result};
 
 pub const LOG_EMERG: i32 = 0;
 pub const LOG_ALERT: i32 = 1;
@@ -54,6 +57,36 @@ impl From<i32> for LogPriority {
     }
 }
 
+impl TryFrom<&str> for LogPriority {
+    type Error = Error;
+
+    /// Parses a [LogPriority] from a number, or a `syslog` priority name, e.g. `err`, or `debug`,
+    /// like the `udev_log` setting in `udev.conf`.
+    fn try_from(val: &str) -> Result<Self> {
+        let val = val.trim();
+
+        if let Ok(num) = val.parse::<i32>() {
+            return if (LOG_EMERG..=LOG_DEBUG).contains(&num) {
+                Ok(num.into())
+            } else {
+                Err(Error::Udev(format!("invalid log priority: {val}")))
+            };
+        }
+
+        match val.to_ascii_lowercase().as_str() {
+            "emerg" => Ok(Self::Emergency),
+            "alert" => Ok(Self::Alert),
+            "crit" | "critical" => Ok(Self::Critical),
+            "err" | "error" => Ok(Self::Error),
+            "warning" | "warn" => Ok(Self::Warning),
+            "notice" => Ok(Self::Notice),
+            "info" => Ok(Self::Info),
+            "debug" => Ok(Self::Debug),
+            _ => Err(Error::Udev(format!("invalid log priority: {val}"))),
+        }
+    }
+}
+
 impl From<&LogPriority> for i32 {
     fn from(val: &LogPriority) -> Self {
         (*val).into()
@@ -66,6 +99,31 @@ impl From<LogPriority> for i32 {
     }
 }
 
+impl From<LogPriority> for log::Level {
+    fn from(val: LogPriority) -> Self {
+        match val {
+            LogPriority::Emergency
+            | LogPriority::Alert
+            | LogPriority::Critical
+            | LogPriority::Error => Self::Error,
+            LogPriority::Warning => Self::Warn,
+            LogPriority::Notice | LogPriority::Info => Self::Info,
+            LogPriority::Debug => Self::Debug,
+        }
+    }
+}
+
+impl From<log::Level> for LogPriority {
+    fn from(val: log::Level) -> Self {
+        match val {
+            log::Level::Error => Self::Error,
+            log::Level::Warn => Self::Warning,
+            log::Level::Info => Self::Info,
+            log::Level::Debug | log::Level::Trace => Self::Debug,
+        }
+    }
+}
+
 impl From<&LogPriority> for &'static str {
     fn from(val: &LogPriority) -> Self {
         match val {
@@ -93,6 +151,100 @@ impl fmt::Display for LogPriority {
     }
 }
 
+/// Logging callback of a [Udev](crate::Udev) context, see [Udev::set_log_fn](crate::Udev::set_log_fn).
+///
+/// Receives the [LogPriority], and the formatted message.
+#[derive(Clone)]
+pub struct LogFn(Arc<LogCallback>);
+
+type LogCallback = dyn Fn(LogPriority, &str) + Send + Sync;
+
+impl LogFn {
+    /// Creates a new [LogFn] from the callback.
+    pub fn new<F: Fn(LogPriority, &str) + Send + Sync + 'static>(f: F) -> Self {
+        Self(Arc::new(f))
+    }
+
+    /// Calls the logging callback.
+    pub fn call(&self, priority: LogPriority, msg: &str) {
+        (self.0)(priority, msg)
+    }
+}
+
+impl fmt::Debug for LogFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LogFn").finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for LogFn {
+    fn eq(&self, oth: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &oth.0)
+    }
+}
+
+// Callback of the latest `Udev::set_log_fn` call, and the most verbose priority it receives.
+static REGISTERED: RwLock<Option<(LogFn, LogPriority)>> = RwLock::new(None);
+// Whether the forwarding logger is the `log` crate logger of the process.
+static INSTALLED: OnceLock<bool> = OnceLock::new();
+static LOGGER: LogFnLogger = LogFnLogger;
+
+// `log` crate logger forwarding the records of this library to the registered callback.
+struct LogFnLogger;
+
+impl LogFnLogger {
+    fn registered(&self, metadata: &log::Metadata) -> Option<LogFn> {
+        let ours = metadata.target().split("::").next() == Some(env!("CARGO_CRATE_NAME"));
+        let registered = REGISTERED.read().ok()?;
+
+        registered
+            .as_ref()
+            .filter(|(_, priority)| ours && LogPriority::from(metadata.level()) <= *priority)
+            .map(|(log_fn, _)| log_fn.clone())
+    }
+}
+
+impl log::Log for LogFnLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.registered(metadata).is_some()
+    }
+
+    fn log(&self, record: &log::Record) {
+        // the lock is released before the call, so the callback may register another one
+        if let Some(log_fn) = self.registered(record.metadata()) {
+            log_fn.call(record.level().into(), record.args().to_string().as_str());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Registers the callback for the `log` records of this library, up to the `priority`.
+///
+/// Installs the forwarding logger on first use, unless the application already installed a
+/// `log` logger, which then keeps receiving the records.
+pub(crate) fn register_log_fn(log_fn: LogFn, priority: LogPriority) {
+    if let Ok(mut registered) = REGISTERED.write() {
+        *registered = Some((log_fn, priority));
+    }
+
+    let installed = *INSTALLED.get_or_init(|| log::set_logger(&LOGGER).is_ok());
+    if installed {
+        log::set_max_level(log::Level::from(priority).to_level_filter());
+    } else {
+        log::debug!("a logger is already installed, library messages keep going there");
+    }
+}
+
+/// Unregisters the callback, if it is the registered one.
+pub(crate) fn unregister_log_fn(log_fn: &LogFn) {
+    if let Ok(mut registered) = REGISTERED.write() {
+        if registered.as_ref().is_some_and(|(cur, _)| cur == log_fn) {
+            *registered = None;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -117,6 +269,23 @@ mod tests {
         assert_eq!(i32::from(LogPriority::Info), LOG_INFO);
         assert_eq!(i32::from(LogPriority::Debug), LOG_DEBUG);
 
+        assert_eq!(LogPriority::try_from("3").ok(), Some(LogPriority::Error));
+        assert_eq!(
+            LogPriority::try_from(" err ").ok(),
+            Some(LogPriority::Error)
+        );
+        assert_eq!(
+            LogPriority::try_from("DEBUG").ok(),
+            Some(LogPriority::Debug)
+        );
+        assert!(LogPriority::try_from("8").is_err());
+        assert!(LogPriority::try_from("verbose").is_err());
+
+        assert_eq!(log::Level::from(LogPriority::Critical), log::Level::Error);
+        assert_eq!(log::Level::from(LogPriority::Notice), log::Level::Info);
+        assert_eq!(LogPriority::from(log::Level::Warn), LogPriority::Warning);
+        assert_eq!(LogPriority::from(log::Level::Trace), LogPriority::Debug);
+
         assert_eq!(LogPriority::new(), LogPriority::default());
     }
 }
@@ -0,0 +1,456 @@
+//! Runtime metrics for long-running device daemons.
+//!
+//! With the `metrics` feature enabled, the library records counters and latency histograms at
+//! key points (monitor receive, filter drops, parse failures, enumerate scans, HWDB lookups, and
+//! `sysfs` attribute reads) into a process-wide [UdevMetrics] registry.
+//!
+//! Operators can either read the registry directly (e.g. [UdevMetrics::to_prometheus]), or
+//! forward every recording to their own metrics backend by installing a [MetricsRecorder].
+//!
+//! Without the feature, all recording points compile to no-ops.
+
+#[cfg(feature = "metrics")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "metrics")]
+use std::sync::OnceLock;
+#[cfg(feature = "metrics")]
+use std::{fmt, time};
+
+#[cfg(feature = "metrics")]
+use crate::{Error, Result};
+
+/// Upper bounds (in microseconds) of the latency histogram buckets.
+#[cfg(feature = "metrics")]
+pub const LATENCY_BUCKETS_US: [u64; 12] = [
+    10, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 50_000, 100_000, 1_000_000,
+];
+/// Number of latency histogram buckets, excluding the implicit `+Inf` bucket.
+#[cfg(feature = "metrics")]
+pub const LATENCY_BUCKETS_LEN: usize = LATENCY_BUCKETS_US.len();
+
+/// Represents the metrics recorded by the library.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metric {
+    /// Devices received from a monitor socket.
+    MonitorReceived,
+    /// Devices received from a monitor socket, and dropped by the userspace filter.
+    MonitorFilterDropped,
+    /// Monitor messages that failed to parse into a device.
+    MonitorParseFailure,
+    /// Monitor receive buffer overflows, each losing an unknown number of events.
+    MonitorOverflow,
+    /// Duration of enumerate scans.
+    EnumerateDuration,
+    /// Duration of HWDB lookups.
+    HwdbLookupDuration,
+    /// Duration of `sysfs` attribute reads.
+    SysattrReadDuration,
+}
+
+#[cfg(feature = "metrics")]
+impl Metric {
+    /// Gets the exported name of the [Metric].
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::MonitorReceived => "udev_monitor_received_total",
+            Self::MonitorFilterDropped => "udev_monitor_filter_dropped_total",
+            Self::MonitorParseFailure => "udev_monitor_parse_failures_total",
+            Self::MonitorOverflow => "udev_monitor_overflows_total",
+            Self::EnumerateDuration => "udev_enumerate_duration_microseconds",
+            Self::HwdbLookupDuration => "udev_hwdb_lookup_duration_microseconds",
+            Self::SysattrReadDuration => "udev_sysattr_read_duration_microseconds",
+        }
+    }
+
+    /// Gets the help text of the [Metric].
+    pub const fn help(&self) -> &'static str {
+        match self {
+            Self::MonitorReceived => "Devices received from a monitor socket.",
+            Self::MonitorFilterDropped => "Received devices dropped by the monitor filter.",
+            Self::MonitorParseFailure => "Monitor messages that failed to parse.",
+            Self::MonitorOverflow => "Monitor receive buffer overflows.",
+            Self::EnumerateDuration => "Duration of enumerate scans.",
+            Self::HwdbLookupDuration => "Duration of HWDB lookups.",
+            Self::SysattrReadDuration => "Duration of sysfs attribute reads.",
+        }
+    }
+
+    /// Gets whether the [Metric] is a latency histogram.
+    pub const fn is_histogram(&self) -> bool {
+        matches!(
+            self,
+            Self::EnumerateDuration | Self::HwdbLookupDuration | Self::SysattrReadDuration
+        )
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Receives every metric recorded by the library.
+///
+/// Implement this trait to forward recordings to an external metrics backend (e.g. the
+/// `metrics` crate facade, or a Prometheus client), and install it with [set_recorder].
+#[cfg(feature = "metrics")]
+pub trait MetricsRecorder: Send + Sync {
+    /// Increments a counter [Metric] by one.
+    fn increment(&self, metric: Metric);
+
+    /// Records an elapsed duration for a histogram [Metric].
+    fn observe(&self, metric: Metric, elapsed: time::Duration);
+}
+
+/// Monotonic event counter.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Default)]
+pub struct Counter(AtomicU64);
+
+#[cfg(feature = "metrics")]
+impl Counter {
+    /// Creates a new [Counter].
+    pub const fn new() -> Self {
+        Self(AtomicU64::new(0))
+    }
+
+    /// Increments the [Counter] by one.
+    pub fn increment(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Gets the current [Counter] value.
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn reset(&self) {
+        self.0.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Latency histogram with fixed [buckets](LATENCY_BUCKETS_US).
+#[cfg(feature = "metrics")]
+#[derive(Debug, Default)]
+pub struct Histogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS_LEN],
+    count: AtomicU64,
+    sum_us: AtomicU64,
+}
+
+#[cfg(feature = "metrics")]
+impl Histogram {
+    /// Creates a new [Histogram].
+    pub const fn new() -> Self {
+        Self {
+            buckets: [const { AtomicU64::new(0) }; LATENCY_BUCKETS_LEN],
+            count: AtomicU64::new(0),
+            sum_us: AtomicU64::new(0),
+        }
+    }
+
+    /// Records an elapsed duration.
+    pub fn observe(&self, elapsed: time::Duration) {
+        let us = elapsed.as_micros().min(u64::MAX as u128) as u64;
+
+        if let Some(idx) = LATENCY_BUCKETS_US.iter().position(|&b| us <= b) {
+            self.buckets[idx].fetch_add(1, Ordering::Relaxed);
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_us.fetch_add(us, Ordering::Relaxed);
+    }
+
+    /// Gets the total number of observations.
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Gets the sum of all observations in microseconds.
+    pub fn sum_us(&self) -> u64 {
+        self.sum_us.load(Ordering::Relaxed)
+    }
+
+    /// Gets the cumulative bucket counts, paired with each bucket upper bound.
+    ///
+    /// Observations above the last bound are only reflected in [count](Self::count).
+    pub fn buckets(&self) -> [(u64, u64); LATENCY_BUCKETS_LEN] {
+        let mut acc = 0u64;
+        let mut ret = [(0u64, 0u64); LATENCY_BUCKETS_LEN];
+
+        for (i, (bound, bucket)) in LATENCY_BUCKETS_US
+            .iter()
+            .zip(self.buckets.iter())
+            .enumerate()
+        {
+            acc = acc.saturating_add(bucket.load(Ordering::Relaxed));
+            ret[i] = (*bound, acc);
+        }
+
+        ret
+    }
+
+    fn reset(&self) {
+        self.buckets
+            .iter()
+            .for_each(|b| b.store(0, Ordering::Relaxed));
+        self.count.store(0, Ordering::Relaxed);
+        self.sum_us.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Process-wide registry of library metrics.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Default)]
+pub struct UdevMetrics {
+    monitor_received: Counter,
+    monitor_filter_dropped: Counter,
+    monitor_parse_failures: Counter,
+    monitor_overflows: Counter,
+    enumerate_duration: Histogram,
+    hwdb_lookup_duration: Histogram,
+    sysattr_read_duration: Histogram,
+}
+
+#[cfg(feature = "metrics")]
+static METRICS: UdevMetrics = UdevMetrics::new();
+#[cfg(feature = "metrics")]
+static RECORDER: OnceLock<&'static dyn MetricsRecorder> = OnceLock::new();
+
+#[cfg(feature = "metrics")]
+impl UdevMetrics {
+    /// Creates a new [UdevMetrics].
+    pub const fn new() -> Self {
+        Self {
+            monitor_received: Counter::new(),
+            monitor_filter_dropped: Counter::new(),
+            monitor_parse_failures: Counter::new(),
+            monitor_overflows: Counter::new(),
+            enumerate_duration: Histogram::new(),
+            hwdb_lookup_duration: Histogram::new(),
+            sysattr_read_duration: Histogram::new(),
+        }
+    }
+
+    /// Gets a reference to the [Counter] for a [Metric].
+    ///
+    /// Returns `None` if the [Metric] is a histogram.
+    pub fn counter(&self, metric: Metric) -> Option<&Counter> {
+        match metric {
+            Metric::MonitorReceived => Some(&self.monitor_received),
+            Metric::MonitorFilterDropped => Some(&self.monitor_filter_dropped),
+            Metric::MonitorParseFailure => Some(&self.monitor_parse_failures),
+            Metric::MonitorOverflow => Some(&self.monitor_overflows),
+            _ => None,
+        }
+    }
+
+    /// Gets a reference to the [Histogram] for a [Metric].
+    ///
+    /// Returns `None` if the [Metric] is a counter.
+    pub fn histogram(&self, metric: Metric) -> Option<&Histogram> {
+        match metric {
+            Metric::EnumerateDuration => Some(&self.enumerate_duration),
+            Metric::HwdbLookupDuration => Some(&self.hwdb_lookup_duration),
+            Metric::SysattrReadDuration => Some(&self.sysattr_read_duration),
+            _ => None,
+        }
+    }
+
+    /// Resets all metrics to zero.
+    pub fn reset(&self) {
+        self.monitor_received.reset();
+        self.monitor_filter_dropped.reset();
+        self.monitor_parse_failures.reset();
+        self.monitor_overflows.reset();
+        self.enumerate_duration.reset();
+        self.hwdb_lookup_duration.reset();
+        self.sysattr_read_duration.reset();
+    }
+
+    /// Renders all metrics in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        self.to_string()
+    }
+
+    const fn all() -> [Metric; 7] {
+        [
+            Metric::MonitorReceived,
+            Metric::MonitorFilterDropped,
+            Metric::MonitorParseFailure,
+            Metric::MonitorOverflow,
+            Metric::EnumerateDuration,
+            Metric::HwdbLookupDuration,
+            Metric::SysattrReadDuration,
+        ]
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl MetricsRecorder for UdevMetrics {
+    fn increment(&self, metric: Metric) {
+        if let Some(counter) = self.counter(metric) {
+            counter.increment();
+        }
+    }
+
+    fn observe(&self, metric: Metric, elapsed: time::Duration) {
+        if let Some(histogram) = self.histogram(metric) {
+            histogram.observe(elapsed);
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl fmt::Display for UdevMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for metric in Self::all() {
+            let name = metric.name();
+
+            writeln!(f, "# HELP {name} {}", metric.help())?;
+
+            if let Some(counter) = self.counter(metric) {
+                writeln!(f, "# TYPE {name} counter")?;
+                writeln!(f, "{name} {}", counter.get())?;
+            } else if let Some(histogram) = self.histogram(metric) {
+                writeln!(f, "# TYPE {name} histogram")?;
+                for (bound, count) in histogram.buckets() {
+                    writeln!(f, r#"{name}_bucket{{le="{bound}"}} {count}"#)?;
+                }
+                writeln!(f, r#"{name}_bucket{{le="+Inf"}} {}"#, histogram.count())?;
+                writeln!(f, "{name}_sum {}", histogram.sum_us())?;
+                writeln!(f, "{name}_count {}", histogram.count())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Gets a reference to the process-wide [UdevMetrics] registry.
+#[cfg(feature = "metrics")]
+pub fn metrics() -> &'static UdevMetrics {
+    &METRICS
+}
+
+/// Installs a [MetricsRecorder] that receives every recorded metric.
+///
+/// Recordings are still stored in the [UdevMetrics] registry.
+///
+/// Returns: `Err(Error)` if a recorder is already installed.
+#[cfg(feature = "metrics")]
+pub fn set_recorder(recorder: &'static dyn MetricsRecorder) -> Result<()> {
+    RECORDER
+        .set(recorder)
+        .map_err(|_| Error::Udev("metrics: recorder already installed".into()))
+}
+
+/// Increments a counter [Metric].
+#[cfg(feature = "metrics")]
+pub(crate) fn increment(metric: Metric) {
+    METRICS.increment(metric);
+    if let Some(recorder) = RECORDER.get() {
+        recorder.increment(metric);
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+#[inline(always)]
+pub(crate) fn increment(_metric: Metric) {}
+
+/// Records the time elapsed since creation into a histogram [Metric] when dropped.
+pub(crate) struct MetricsTimer {
+    #[cfg(feature = "metrics")]
+    metric: Metric,
+    #[cfg(feature = "metrics")]
+    start: time::Instant,
+}
+
+impl MetricsTimer {
+    /// Starts a new [MetricsTimer] for a histogram [Metric].
+    #[cfg(feature = "metrics")]
+    pub(crate) fn start(metric: Metric) -> Self {
+        Self {
+            metric,
+            start: time::Instant::now(),
+        }
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    #[inline(always)]
+    pub(crate) fn start(_metric: Metric) -> Self {
+        Self {}
+    }
+
+    /// Stops the [MetricsTimer], recording the elapsed time.
+    pub(crate) fn stop(self) {}
+}
+
+#[cfg(feature = "metrics")]
+impl Drop for MetricsTimer {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+
+        METRICS.observe(self.metric, elapsed);
+        if let Some(recorder) = RECORDER.get() {
+            recorder.observe(self.metric, elapsed);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics() {
+        let reg = UdevMetrics::new();
+
+        reg.increment(Metric::MonitorReceived);
+        reg.increment(Metric::MonitorReceived);
+        reg.increment(Metric::MonitorFilterDropped);
+        // histograms ignore counter increments
+        reg.increment(Metric::HwdbLookupDuration);
+
+        assert_eq!(
+            reg.counter(Metric::MonitorReceived).map(|c| c.get()),
+            Some(2)
+        );
+        assert_eq!(
+            reg.counter(Metric::MonitorFilterDropped).map(|c| c.get()),
+            Some(1)
+        );
+        assert_eq!(
+            reg.counter(Metric::MonitorParseFailure).map(|c| c.get()),
+            Some(0)
+        );
+        assert!(reg.counter(Metric::HwdbLookupDuration).is_none());
+
+        reg.observe(Metric::HwdbLookupDuration, time::Duration::from_micros(5));
+        reg.observe(Metric::HwdbLookupDuration, time::Duration::from_micros(700));
+        reg.observe(Metric::HwdbLookupDuration, time::Duration::from_secs(5));
+
+        let hist = reg.histogram(Metric::HwdbLookupDuration).unwrap();
+        let buckets = hist.buckets();
+
+        assert_eq!(hist.count(), 3);
+        assert_eq!(hist.sum_us(), 5_000_705);
+        assert_eq!(buckets[0], (10, 1));
+        assert_eq!(buckets[5], (1_000, 2));
+        assert_eq!(buckets[LATENCY_BUCKETS_LEN - 1], (1_000_000, 2));
+
+        let text = reg.to_prometheus();
+        assert!(text.contains("udev_monitor_received_total 2\n"));
+        assert!(text.contains(r#"udev_hwdb_lookup_duration_microseconds_bucket{le="+Inf"} 3"#));
+        assert!(text.contains("udev_hwdb_lookup_duration_microseconds_count 3\n"));
+
+        reg.reset();
+        assert_eq!(
+            reg.counter(Metric::MonitorReceived).map(|c| c.get()),
+            Some(0)
+        );
+        assert_eq!(hist.count(), 0);
+    }
+}
@@ -1,19 +1,59 @@
 //! Connects to a device event source.
 
-use std::{cmp, fmt, fs, io, mem, sync::Arc};
+use std::collections::VecDeque;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
+use std::path::Path;
+use std::{cmp, fmt, fs, io, io::IoSlice, mem, ops, sync::Arc, time};
 
+use nix::errno::Errno;
+use nix::sys::socket::{self, MsgFlags};
+
+use crate::metrics::{self, Metric};
+use crate::pattern::MatchCache;
 use crate::{
-    util, Error, Result, Udev, UdevDevice, UdevEntry, UdevEntryList, UdevList, UdevSocket,
+    hash, Error, ErrorScope, Result, Udev, UdevDevice, UdevEntry, UdevEntryList, UdevList,
+    UdevSocket,
 };
 
+mod bpf;
+mod buffered;
+mod coalesce;
+mod device_ref;
+mod filter;
+mod loopback;
+mod netns;
+mod overflow;
+mod peek;
+mod policy;
+mod record;
+mod sender;
+mod split;
+mod stats;
+mod watcher;
+
+pub use bpf::*;
+pub use buffered::*;
+pub use coalesce::*;
+pub use device_ref::*;
+pub use overflow::*;
+pub use peek::*;
+pub use policy::*;
+pub use record::*;
+pub use split::*;
+pub use stats::*;
+pub use watcher::*;
+
 /// UDEV Monitor magic bytes
 pub const UDEV_MONITOR_MAGIC: u32 = u32::from_le_bytes([0xfe, 0xed, 0xca, 0xfe]);
-// FIXME: put behind a feature flag or conditional compilation for platforms with a different run
-// directory.
 /// Default filesystem path for the UDEV `run` directory.
-pub const UDEV_ROOT_RUN: &str = "/run";
+///
+/// Set with the `UDEVRS_RUN_DIR` environment variable at build time. Monitors use the run path
+/// of their [Udev] context, see [Udev::get_run_path].
+pub const UDEV_ROOT_RUN: &str = env!("UDEVRS_RUN_DIR");
 /// Maximum length of BPF socket filters.
 pub const BPF_FILTER_LEN: usize = 512;
+// Length of the `recvmsg` control buffer, in 8-byte words.
+const CONTROL_BUF_LEN: usize = 16;
 
 /// Collection of BPF socket filters for kernel events.
 #[repr(C)]
@@ -117,7 +157,7 @@ impl<const N: usize> Default for BpfFilters<N> {
 /// Handles device event sources.
 pub struct UdevMonitor {
     udev: Arc<Udev>,
-    sock: i32,
+    sock: Option<OwnedFd>,
     snl: UdevSocket,
     snl_group: UdevMonitorNetlinkGroup,
     snl_trusted_sender: UdevSocket,
@@ -126,19 +166,38 @@ pub struct UdevMonitor {
     addrlen: usize,
     filter_subsystem_list: UdevList,
     filter_tag_list: UdevList,
+    filter_sysattr_list: UdevList,
+    filter_devpath_list: UdevList,
+    filter_property_list: UdevList,
+    match_cache: MatchCache,
     bound: bool,
     filter: BpfFilters<BPF_FILTER_LEN>,
+    timeout_strategy: TimeoutStrategy,
+    buffered: VecDeque<UdevDevice>,
+    buffer_capacity: usize,
+    buffer_dropped: u64,
+    buffering: bool,
+    recorder: Option<fs::File>,
+    overflow_strategy: OverflowStrategy,
+    stats: MonitorStats,
+    rxq_ovfl: u32,
+    receive_policy: ReceivePolicy,
 }
 
 impl UdevMonitor {
     /// Creates a new [UdevMonitor].
     pub fn new(udev: Arc<Udev>) -> Result<Self> {
-        let filter_subsystem_list = UdevList::new(Arc::clone(&udev));
+        // subsystems can be matched with multiple devtypes, so allow duplicate names
+        let filter_subsystem_list = UdevList::new(Arc::clone(&udev)).with_unique(false);
         let filter_tag_list = UdevList::new(Arc::clone(&udev));
+        let filter_sysattr_list = UdevList::new(Arc::clone(&udev));
+        let filter_devpath_list = UdevList::new(Arc::clone(&udev));
+        // properties can be matched with multiple value patterns
+        let filter_property_list = UdevList::new(Arc::clone(&udev)).with_unique(false);
 
         Ok(Self {
             udev,
-            sock: 0,
+            sock: None,
             snl: UdevSocket::new_nl(libc::AF_NETLINK, 0, 2),
             snl_group: UdevMonitorNetlinkGroup::None,
             snl_trusted_sender: UdevSocket::new_nl(libc::AF_NETLINK, 0, 0),
@@ -147,24 +206,46 @@ impl UdevMonitor {
             addrlen: mem::size_of::<libc::sockaddr_nl>(),
             filter_subsystem_list,
             filter_tag_list,
+            filter_sysattr_list,
+            filter_devpath_list,
+            filter_property_list,
+            match_cache: MatchCache::default(),
             bound: false,
             filter: BpfFilters::new(),
+            timeout_strategy: TimeoutStrategy::Unknown,
+            buffered: VecDeque::new(),
+            buffer_capacity: DEFAULT_BUFFER_CAPACITY,
+            buffer_dropped: 0,
+            buffering: false,
+            recorder: None,
+            overflow_strategy: OverflowStrategy::Fail,
+            stats: MonitorStats::new(),
+            rxq_ovfl: 0,
+            receive_policy: ReceivePolicy::new(),
         })
     }
 
     /// Creates a [UdevMonitor] from group name and socket file descriptor.
+    ///
+    /// If `fd` is `None`, a new netlink socket is created. Otherwise, the [UdevMonitor] takes
+    /// ownership of the socket, and closes it on drop.
+    ///
+    /// A passed socket may belong to another network namespace, e.g. when handed over by a
+    /// container manager. Unbound sockets are bound to the `name` group by
+    /// [enable_receiving](Self::enable_receiving). Bound sockets keep their address, and join
+    /// the `name` group, if they are not a member yet.
+    ///
+    /// Like `libudev`, the `udev` group is disabled, i.e. [UdevMonitorNetlinkGroup::None], if the
+    /// `control` socket of the `udev` daemon does not exist in the run path.
     pub fn new_from_netlink_fd<N: Into<UdevMonitorNetlinkGroup> + fmt::Display + Copy>(
         udev: Arc<Udev>,
         name: N,
-        fd: i32,
+        fd: Option<OwnedFd>,
     ) -> Result<Self> {
-        let group = match name.into() {
+        let group = match UdevMonitorNetlinkGroup::from_id(name.into().id()) {
             UdevMonitorNetlinkGroup::Udev => {
-                if fs::OpenOptions::new()
-                    .read(true)
-                    .open(format!("{UDEV_ROOT_RUN}/udev/control"))
-                    .is_ok()
-                {
+                // like `libudev`, the control socket cannot be opened, only check it exists
+                if !Path::new(udev.get_run_path()).join("control").exists() {
                     let err_msg = "the udev service seems not to be active, disable the monitor";
                     log::debug!("{err_msg}");
                     Ok(UdevMonitorNetlinkGroup::None)
@@ -173,6 +254,7 @@ impl UdevMonitor {
                 }
             }
             UdevMonitorNetlinkGroup::Kernel => Ok(UdevMonitorNetlinkGroup::Kernel),
+            group @ UdevMonitorNetlinkGroup::Custom(_) => Ok(group),
 
             UdevMonitorNetlinkGroup::None => {
                 Err(Error::UdevMonitor(format!("invalid netlink group: {name}")))
@@ -181,34 +263,69 @@ impl UdevMonitor {
 
         let mut udev_monitor = Self::new(udev)?;
 
+        udev_monitor.set_snl(UdevSocket::new_nl(libc::AF_NETLINK, 0, group.mask()));
         udev_monitor.set_snl_group(group);
         udev_monitor.set_snl_destination_group(UdevMonitorNetlinkGroup::Udev);
 
-        if fd < 0 {
-            // SAFETY: all arguments are valid, and the return value is checked before use.
-            udev_monitor.set_sock(unsafe {
-                libc::socket(
-                    libc::PF_NETLINK,
-                    libc::SOCK_RAW | libc::SOCK_CLOEXEC | libc::SOCK_NONBLOCK,
-                    libc::NETLINK_KOBJECT_UEVENT,
-                )
-            });
-
-            if udev_monitor.sock() < 0 {
-                let errno = io::Error::last_os_error();
-                let err_msg = format!("error getting socket: {errno}");
-
-                log::error!("{err_msg}");
+        match fd {
+            Some(fd) => {
+                udev_monitor.set_sock(fd);
+                udev_monitor.adopt_nl_socket(group)?;
 
-                Err(Error::Io(err_msg))
-            } else {
                 Ok(udev_monitor)
             }
-        } else {
-            udev_monitor.set_sock(fd);
-            udev_monitor.set_nl_address()?;
+            None => Ok(udev_monitor.with_sock(Self::new_nl_socket()?)),
+        }
+    }
 
-            Ok(udev_monitor.with_bound(true))
+    // Takes over the state of a passed netlink socket, e.g. from a container manager.
+    //
+    // Unbound sockets are bound by `enable_receiving`, with the `group`. Bound sockets join the
+    // `group`, if they are not a member yet.
+    fn adopt_nl_socket(&mut self, group: UdevMonitorNetlinkGroup) -> Result<()> {
+        let requested = *self.snl.as_nl()?;
+        self.set_nl_address()?;
+
+        // the kernel assigns a port ID on bind
+        if self.snl.pid()? == 0 {
+            log::debug!("passed netlink socket is not bound");
+            self.set_snl(UdevSocket::Netlink(requested));
+            self.set_bound(false);
+            return Ok(());
+        }
+
+        self.set_bound(true);
+
+        let groups = self.snl.as_nl()?.nl_groups;
+        if group != UdevMonitorNetlinkGroup::None && groups & group.mask() == 0 {
+            log::debug!("passed netlink socket joins group: {group}");
+            self.join_group(group)?;
+        }
+
+        Ok(())
+    }
+
+    // Creates a new, unbound netlink socket for kernel object events.
+    pub(super) fn new_nl_socket() -> Result<OwnedFd> {
+        // SAFETY: all arguments are valid, and the return value is checked before use.
+        let sock = unsafe {
+            libc::socket(
+                libc::PF_NETLINK,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC | libc::SOCK_NONBLOCK,
+                libc::NETLINK_KOBJECT_UEVENT,
+            )
+        };
+
+        if sock < 0 {
+            let errno = io::Error::last_os_error();
+            let err_msg = format!("error getting socket: {errno}");
+
+            log::error!("{err_msg}");
+
+            Err(Error::os(ErrorScope::Io, err_msg, errno))
+        } else {
+            // SAFETY: `sock` is a valid, open file descriptor that nothing else owns.
+            Ok(unsafe { OwnedFd::from_raw_fd(sock) })
         }
     }
 
@@ -240,7 +357,37 @@ impl UdevMonitor {
         udev: Arc<Udev>,
         name: N,
     ) -> Result<Self> {
-        Self::new_from_netlink_fd(udev, name, -1)
+        Self::new_from_netlink_fd(udev, name, None)
+    }
+
+    /// Creates a new [UdevMonitor] for the netlink `group`.
+    ///
+    /// Like [new_from_netlink](Self::new_from_netlink), without parsing a group name, so typos
+    /// are caught at compile time. [Custom](UdevMonitorNetlinkGroup::Custom) groups select
+    /// other multicast groups, e.g. of an out-of-tree event forwarder:
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{Udev, UdevMonitor, UdevMonitorNetlinkGroup};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let udev = Arc::new(Udev::new());
+    ///
+    /// let kernel = UdevMonitor::new_from_netlink_group(udev.clone(), UdevMonitorNetlinkGroup::Kernel)?;
+    /// let custom = UdevMonitor::new_from_netlink_group(udev, UdevMonitorNetlinkGroup::Custom(4))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns: a new [UdevMonitor], or `Err(Error)` for the [None](UdevMonitorNetlinkGroup::None)
+    /// group, custom groups above `32`, or if creating the socket fails.
+    pub fn new_from_netlink_group(udev: Arc<Udev>, group: UdevMonitorNetlinkGroup) -> Result<Self> {
+        if group.mask() == 0 {
+            Err(Error::UdevMonitor(format!(
+                "invalid netlink group: {group}, select the kernel, udev, or a custom group from 3 to {NETLINK_GROUPS}"
+            )))
+        } else {
+            Self::new_from_netlink_fd(udev, group, None)
+        }
     }
 
     fn set_nl_address(&mut self) -> Result<()> {
@@ -255,7 +402,7 @@ impl UdevMonitor {
         // SAFETY: parameters are initialized properly, and pointers reference valid memory.
         match unsafe {
             libc::getsockname(
-                self.sock,
+                self.sock(),
                 &mut snl as *mut libc::sockaddr_nl as *mut _,
                 &mut snl_len as *mut _,
             )
@@ -268,9 +415,11 @@ impl UdevMonitor {
             }
             _ => {
                 let errno = io::Error::last_os_error();
-                Err(Error::UdevMonitor(format!(
-                    "unable to set SNL address: {errno}"
-                )))
+                Err(Error::os(
+                    ErrorScope::Monitor,
+                    format!("unable to set SNL address: {errno}"),
+                    errno,
+                ))
             }
         }
     }
@@ -281,21 +430,34 @@ impl UdevMonitor {
     }
 
     /// Gets the socket file descriptor.
-    pub const fn sock(&self) -> i32 {
-        self.sock
+    ///
+    /// Returns `-1` if the [UdevMonitor] does not have a socket.
+    pub fn sock(&self) -> i32 {
+        self.sock.as_ref().map(|s| s.as_raw_fd()).unwrap_or(-1)
     }
 
     /// Sets the socket file descriptor.
-    pub fn set_sock(&mut self, val: i32) {
-        self.sock = val;
+    ///
+    /// The [UdevMonitor] takes ownership of the socket, and closes any previous socket.
+    pub fn set_sock(&mut self, val: OwnedFd) {
+        self.sock = Some(val);
     }
 
     /// Builder function that sets the socket file descriptor.
-    pub fn with_sock(mut self, val: i32) -> Self {
+    pub fn with_sock(mut self, val: OwnedFd) -> Self {
         self.set_sock(val);
         self
     }
 
+    /// Consumes the [UdevMonitor], and releases ownership of the socket file descriptor.
+    ///
+    /// The caller is responsible for closing the returned file descriptor.
+    ///
+    /// Returns `-1` if the [UdevMonitor] does not have a socket.
+    pub fn into_raw_fd(mut self) -> RawFd {
+        self.sock.take().map(|s| s.into_raw_fd()).unwrap_or(-1)
+    }
+
     /// Gets a reference to the SNL [UdevSocket].
     pub const fn snl(&self) -> &UdevSocket {
         &self.snl
@@ -344,7 +506,7 @@ impl UdevMonitor {
     /// **NOTE**: the SNL socket is only set for [UdevSocket::Netlink] sockets.
     pub fn set_snl_trusted_sender(&mut self, val: UdevSocket) {
         if matches!(val, UdevSocket::Netlink(_)) {
-            self.snl = val;
+            self.snl_trusted_sender = val;
         }
     }
 
@@ -450,27 +612,130 @@ impl UdevMonitor {
         self.bound = val;
     }
 
-    fn with_bound(mut self, val: bool) -> Self {
-        self.set_bound(val);
+    /// Gets the [TimeoutStrategy] used by [receive_device_timeout](Self::receive_device_timeout).
+    ///
+    /// Returns [TimeoutStrategy::Unknown] until the strategy is probed, or set explicitly.
+    pub const fn timeout_strategy(&self) -> TimeoutStrategy {
+        self.timeout_strategy
+    }
+
+    /// Sets the [TimeoutStrategy] used by [receive_device_timeout](Self::receive_device_timeout).
+    ///
+    /// Setting [TimeoutStrategy::Unknown] probes the strategy again on the next receive.
+    pub fn set_timeout_strategy(&mut self, val: TimeoutStrategy) {
+        self.timeout_strategy = val;
+    }
+
+    /// Builder function that sets the [TimeoutStrategy].
+    pub fn with_timeout_strategy(mut self, val: TimeoutStrategy) -> Self {
+        self.set_timeout_strategy(val);
         self
     }
 
+    /// Probes whether the socket supports `SO_RCVTIMEO` timeouts.
+    ///
+    /// Some restricted runtimes disallow `setsockopt` on inherited sockets. If setting the
+    /// socket option, or switching the socket to blocking mode fails, the `ppoll` fallback is
+    /// used instead.
+    ///
+    /// Returns: the probed [TimeoutStrategy], which is also stored in the [UdevMonitor].
+    pub fn probe_timeout_strategy(&mut self) -> TimeoutStrategy {
+        let sock = self.sock();
+
+        // SAFETY: `timeval` is a plain C struct, so zeroed memory is a valid value.
+        let mut tv = unsafe { mem::zeroed::<libc::timeval>() };
+        let mut tv_len = mem::size_of::<libc::timeval>() as libc::socklen_t;
+
+        // write back the current value, to leave the socket unchanged
+        //
+        // SAFETY: all arguments are valid, and pointers reference valid memory.
+        let supported = unsafe {
+            libc::getsockopt(
+                sock,
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &mut tv as *mut libc::timeval as *mut _,
+                &mut tv_len,
+            ) == 0
+                && libc::setsockopt(
+                    sock,
+                    libc::SOL_SOCKET,
+                    libc::SO_RCVTIMEO,
+                    &tv as *const libc::timeval as *const _,
+                    tv_len,
+                ) == 0
+                && libc::fcntl(sock, libc::F_GETFL) >= 0
+        };
+
+        self.timeout_strategy = if supported {
+            TimeoutStrategy::SocketOption
+        } else {
+            let errno = io::Error::last_os_error();
+            log::debug!("SO_RCVTIMEO unavailable, using ppoll fallback, errno: {errno}");
+            TimeoutStrategy::Ppoll
+        };
+
+        self.timeout_strategy
+    }
+
+    /// Gets the effective subsystem filter entries.
+    ///
+    /// Each [UdevEntry] name is the subsystem, and the value is the devtype. An empty devtype
+    /// matches any devtype of the subsystem.
+    ///
+    /// Entries are matched with OR semantics: a device passes if it matches any entry.
+    ///
+    /// Duplicate entries, and entries covered by an any-devtype entry for the same subsystem,
+    /// are removed. Entries keep the order they were added in.
+    pub fn filter_entries(&self) -> Vec<&UdevEntry> {
+        bpf::subsystem_filter_entries(&self.filter_subsystem_list)
+    }
+
     /// Gets whether the [UdevDevice] passes the [UdevMonitor] filters.
+    ///
+    /// Includes the userspace sysattr, and devpath filters, see
+    /// [filter_add_match_sysattr](Self::filter_add_match_sysattr).
     pub fn passes_filter(&self, device: &mut UdevDevice) -> bool {
-        if self.filter_subsystem_list.is_empty() {
-            self.filter_tag_list().has_tag(device)
+        self.passes_kernel_filter(device) && self.passes_userspace_filter(device)
+    }
+
+    // Matches the filters also installed in the socket BPF program.
+    fn passes_kernel_filter(&self, device: &mut UdevDevice) -> bool {
+        let (subsystem, devtype) = if self.filter_subsystem_list.is_empty() {
+            // unused without subsystem filters, avoid reading the `uevent` file
+            (String::new(), String::new())
         } else {
-            for list_entry in self.filter_subsystem_list.iter() {
-                if list_entry.name() == device.get_subsystem() {
-                    let (devtype, ddevtype) = (list_entry.value(), device.devtype());
+            (
+                device.get_subsystem().to_owned(),
+                device.get_devtype().to_owned(),
+            )
+        };
 
-                    if !ddevtype.is_empty() && (devtype.is_empty() || devtype == ddevtype) {
-                        return self.filter_tag_list().has_tag(device);
-                    }
-                }
-            }
-            false
-        }
+        self.matches_kernel_filter(&subsystem, &devtype, |tag| {
+            device.tags_list().entry_by_name(tag).is_some()
+        })
+    }
+
+    // Matches the subsystem, devtype, and tag filters on the event values, shared by the owned,
+    // and borrowed events.
+    pub(super) fn matches_kernel_filter<F: Fn(&str) -> bool>(
+        &self,
+        subsystem: &str,
+        devtype: &str,
+        has_tag: F,
+    ) -> bool {
+        let subsystem_matched = self.filter_subsystem_list.is_empty()
+            || self.filter_subsystem_list.iter().any(|entry| {
+                entry.name() == subsystem && (entry.value().is_empty() || entry.value() == devtype)
+            });
+
+        let tag_matched = self.filter_tag_list.is_empty()
+            || self
+                .filter_tag_list
+                .iter()
+                .any(|entry| has_tag(entry.name()));
+
+        subsystem_matched && tag_matched
     }
 
     /// Updates the monitor socket filter.
@@ -482,172 +747,17 @@ impl UdevMonitor {
     /// if the filter was removed or changed.
     /// ```
     ///
+    /// The program is created by [build_filter], and installed with
+    /// [install_filter](Self::install_filter).
+    ///
     /// Returns: `Ok(())` on success, `Err(Error)` otherwise.
     pub fn filter_update(&mut self) -> Result<()> {
-        if self.filter_subsystem_list().entry().is_none()
-            && self.filter_tag_list().entry().is_none()
-        {
+        let filter = build_filter(&self.filter_subsystem_list, &self.filter_tag_list)?;
+
+        if filter.is_empty() {
             Ok(())
         } else {
-            let mut ins: BpfFilters<BPF_FILTER_LEN> = BpfFilters::new();
-            let mut i = 0usize;
-
-            // load magic in A
-            ins.bpf_stmt(
-                &mut i,
-                (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16,
-                UdevMonitorNetlinkHeader::magic_offset() as u32,
-            )?;
-            // jump if magic matches
-            ins.bpf_jmp(
-                &mut i,
-                (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16,
-                UDEV_MONITOR_MAGIC,
-                1,
-                0,
-            )?;
-            // wrong magic, pass packet
-            ins.bpf_stmt(&mut i, (libc::BPF_RET | libc::BPF_K) as u16, 0xffff_ffff)?;
-
-            if self.filter_tag_list.entry().is_some() {
-                let mut tag_matches = self.filter_tag_list.len();
-
-                for list_entry in self.filter_tag_list.iter() {
-                    let tag_bloom_bits = util::string_bloom64(list_entry.name());
-                    let tag_bloom_hi = (tag_bloom_bits >> 32) as u32;
-                    let tag_bloom_lo = tag_bloom_bits as u32;
-
-                    // load device bloom bits in A
-                    ins.bpf_stmt(
-                        &mut i,
-                        (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16,
-                        UdevMonitorNetlinkHeader::filter_tag_bloom_hi_offset() as u32,
-                    )?;
-                    // clear bits (tag bits & bloom bits)
-                    ins.bpf_stmt(
-                        &mut i,
-                        (libc::BPF_ALU | libc::BPF_AND | libc::BPF_K) as u16,
-                        UdevMonitorNetlinkHeader::filter_tag_bloom_hi_offset() as u32,
-                    )?;
-                    // jump to next tag if it does not match
-                    ins.bpf_jmp(
-                        &mut i,
-                        (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16,
-                        tag_bloom_hi,
-                        0,
-                        3,
-                    )?;
-
-                    // load device bloom bits in A
-                    ins.bpf_stmt(
-                        &mut i,
-                        (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16,
-                        UdevMonitorNetlinkHeader::filter_tag_bloom_lo_offset() as u32,
-                    )?;
-                    // clear bits (tag bits & bloom bits)
-                    ins.bpf_stmt(
-                        &mut i,
-                        (libc::BPF_ALU | libc::BPF_AND | libc::BPF_K) as u16,
-                        tag_bloom_lo,
-                    )?;
-                    // jump behind end of tag match block if tag matches
-                    tag_matches = tag_matches.saturating_sub(1);
-                    ins.bpf_jmp(
-                        &mut i,
-                        (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16,
-                        tag_bloom_lo,
-                        1usize.saturating_add(tag_matches.saturating_mul(6)) as u8,
-                        0,
-                    )?;
-                }
-
-                // nothing matched, drop packet
-                ins.bpf_stmt(&mut i, (libc::BPF_RET | libc::BPF_K) as u16, 0)?;
-            }
-
-            // add all subsystem matches
-            if self.filter_subsystem_list().entry().is_some() {
-                for list_entry in self.filter_subsystem_list().iter() {
-                    let mut hash = util::string_hash32(list_entry.name());
-
-                    // load device subsystem value in A
-                    ins.bpf_stmt(
-                        &mut i,
-                        (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16,
-                        UdevMonitorNetlinkHeader::filter_subsystem_hash_offset() as u32,
-                    )?;
-
-                    if list_entry.value().is_empty() {
-                        // jump if subsystem does not match
-                        ins.bpf_jmp(
-                            &mut i,
-                            (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16,
-                            hash,
-                            0,
-                            1,
-                        )?;
-                    } else {
-                        // jump if subsystem does not match
-                        ins.bpf_jmp(
-                            &mut i,
-                            (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16,
-                            hash,
-                            0,
-                            3,
-                        )?;
-
-                        // load device devtype value in A
-                        ins.bpf_stmt(
-                            &mut i,
-                            (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16,
-                            UdevMonitorNetlinkHeader::filter_devtype_hash_offset() as u32,
-                        )?;
-
-                        // jump if value does not match
-                        hash = util::string_hash32(list_entry.value());
-                        ins.bpf_jmp(
-                            &mut i,
-                            (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16,
-                            hash,
-                            0,
-                            1,
-                        )?;
-                    }
-
-                    // matched pass packet
-                    ins.bpf_stmt(&mut i, (libc::BPF_RET | libc::BPF_K) as u16, 0xffff_ffff)?;
-                }
-
-                // nothing matched, drop packet
-                ins.bpf_stmt(&mut i, (libc::BPF_RET | libc::BPF_K) as u16, 0)?;
-            }
-
-            // matched, pass packet
-            ins.bpf_stmt(&mut i, (libc::BPF_RET | libc::BPF_K) as u16, 0xffff_ffff)?;
-
-            // install filter
-            self.filter = ins;
-            let mut filter = self.filter.as_sock_fprog();
-
-            // SAFETY: arguments are valid, and pointer reference valid memory.
-            let err = unsafe {
-                libc::setsockopt(
-                    self.sock,
-                    libc::SOL_SOCKET,
-                    libc::SO_ATTACH_FILTER,
-                    &mut filter as *mut libc::sock_fprog as *mut _,
-                    mem::size_of::<libc::sock_fprog>() as u32,
-                )
-            };
-
-            if err < 0 {
-                let errno = io::Error::last_os_error();
-                Err(Error::UdevMonitor(format!(
-                    "error setting BPF filter, error: {err}, errno: {errno}"
-                )))
-            } else {
-                Ok(())
-            }
+            self.install_filter(filter)
         }
     }
 
@@ -659,7 +769,7 @@ impl UdevMonitor {
             // SAFETY: all arguments are valid, and pointers reference valid memory.
             unsafe {
                 libc::bind(
-                    self.sock,
+                    self.sock(),
                     self.snl.as_nl_ptr()? as *const _,
                     mem::size_of::<libc::sockaddr_nl>() as u32,
                 )
@@ -672,7 +782,7 @@ impl UdevMonitor {
             let errno = io::Error::last_os_error();
             let err_msg = format!("bind failed, error: {err}, errno: {errno}");
             log::error!("{err_msg}");
-            Err(Error::UdevMonitor(err_msg))
+            Err(Error::os(ErrorScope::Monitor, err_msg, errno))
         } else {
             self.bound = true;
             self.set_nl_address()?;
@@ -682,7 +792,7 @@ impl UdevMonitor {
             // SAFETY: all arguments are valid, and pointers reference valid memory.
             err = unsafe {
                 libc::setsockopt(
-                    self.sock,
+                    self.sock(),
                     libc::SOL_SOCKET,
                     libc::SO_PASSCRED,
                     &on as *const i32 as *const _,
@@ -694,10 +804,27 @@ impl UdevMonitor {
                 let errno = io::Error::last_os_error();
                 let err_msg = format!("setting SO_PASSCRED failed, error: {err}, errno: {errno}");
                 log::error!("{err_msg}");
-                Err(Error::UdevMonitor(err_msg))
-            } else {
-                Ok(())
+                return Err(Error::os(ErrorScope::Monitor, err_msg, errno));
+            }
+
+            // report kernel drops with every message, see `MonitorStats::kernel_dropped`
+            // SAFETY: all arguments are valid, and pointers reference valid memory.
+            err = unsafe {
+                libc::setsockopt(
+                    self.sock(),
+                    libc::SOL_SOCKET,
+                    libc::SO_RXQ_OVFL,
+                    &on as *const i32 as *const _,
+                    mem::size_of::<i32>() as u32,
+                )
+            };
+
+            if err < 0 {
+                let errno = io::Error::last_os_error();
+                log::debug!("setting SO_RXQ_OVFL failed, kernel drops are not counted: {errno}");
             }
+
+            Ok(())
         }
     }
 
@@ -716,7 +843,7 @@ impl UdevMonitor {
         // SAFETY: all arguments are valid, and pointers reference valid memory.
         let err = unsafe {
             libc::setsockopt(
-                self.sock,
+                self.sock(),
                 libc::SOL_SOCKET,
                 libc::SO_RCVBUFFORCE,
                 &int_size as *const i32 as *const _,
@@ -728,7 +855,92 @@ impl UdevMonitor {
             let err_msg =
                 format!("Error setting receive buffer size, error: {err}, errno: {errno}");
             log::error!("{err_msg}");
-            Err(Error::UdevMonitor(err_msg))
+            Err(Error::os(ErrorScope::Monitor, err_msg, errno))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Joins the netlink multicast `group` on the bound [UdevMonitor] socket.
+    ///
+    /// The socket receives events from every joined group. To switch a bound monitor between
+    /// the `kernel` and `udev` event sources, join the new group, then
+    /// [leave](Self::leave_group) the old one:
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{Udev, UdevMonitor, UdevMonitorNetlinkGroup};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let mut monitor = UdevMonitor::new_from_netlink(Arc::new(Udev::new()), "udev")?;
+    /// monitor.enable_receiving()?;
+    ///
+    /// monitor.join_group(UdevMonitorNetlinkGroup::Kernel)?;
+    /// monitor.leave_group(UdevMonitorNetlinkGroup::Udev)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Binding the socket overwrites its group memberships, so the socket must be bound with
+    /// [enable_receiving](Self::enable_receiving) first.
+    ///
+    /// Returns: `Ok(())` on success, `Err(Error)` otherwise.
+    pub fn join_group<G: Into<UdevMonitorNetlinkGroup>>(&mut self, group: G) -> Result<()> {
+        let group = group.into();
+
+        self.set_membership(group, libc::NETLINK_ADD_MEMBERSHIP)?;
+        self.snl_group = group;
+
+        Ok(())
+    }
+
+    /// Leaves the netlink multicast `group` on the bound [UdevMonitor] socket.
+    ///
+    /// Leaving a group the socket is not a member of succeeds.
+    ///
+    /// Returns: `Ok(())` on success, `Err(Error)` otherwise.
+    pub fn leave_group<G: Into<UdevMonitorNetlinkGroup>>(&mut self, group: G) -> Result<()> {
+        let group = group.into();
+
+        self.set_membership(group, libc::NETLINK_DROP_MEMBERSHIP)?;
+        if self.snl_group == group {
+            self.snl_group = UdevMonitorNetlinkGroup::None;
+        }
+
+        Ok(())
+    }
+
+    fn set_membership(&self, group: UdevMonitorNetlinkGroup, option: i32) -> Result<()> {
+        if group.mask() == 0 {
+            return Err(Error::UdevMonitor(format!(
+                "invalid netlink group: {group}"
+            )));
+        }
+
+        if !self.bound {
+            return Err(Error::UdevMonitor(format!(
+                "unable to change membership of netlink group: {group}, monitor is not bound"
+            )));
+        }
+
+        let group_id = group.id();
+        // SAFETY: all arguments are valid, and pointers reference valid memory.
+        let err = unsafe {
+            libc::setsockopt(
+                self.sock(),
+                libc::SOL_NETLINK,
+                option,
+                &group_id as *const u32 as *const _,
+                mem::size_of::<u32>() as u32,
+            )
+        };
+
+        if err < 0 {
+            let errno = io::Error::last_os_error();
+            let err_msg = format!(
+                "error changing membership of netlink group: {group}, error: {err}, errno: {errno}"
+            );
+            log::error!("{err_msg}");
+            Err(Error::os(ErrorScope::Monitor, err_msg, errno))
         } else {
             Ok(())
         }
@@ -750,260 +962,518 @@ impl UdevMonitor {
     /// switched into blocking mode.
     /// ```
     ///
+    /// Devices held by the event buffer, see [start_buffering](Self::start_buffering), are
+    /// returned first.
+    ///
+    /// Senders other than uid=0 can be accepted with [set_allowed_uids](Self::set_allowed_uids),
+    /// or [set_allow_unprivileged_sender](Self::set_allow_unprivileged_sender).
+    ///
     /// Returns: `Ok(UdevDevice)` on success, `Err(Error)` otherwise.
-    // FIXME: break this into smaller functions
     pub fn receive_device(&mut self) -> Result<UdevDevice> {
-        // avoid infinite loop, only retry up to a given limit of queued devices
-        // TODO: should this limit be higher? lower?
-        // the original implementation retries indefinitely, as long as there are queued devices
-        for _ in 0..1024 {
-            let mut buf = [0u8; 8192];
-            let mut iov = libc::iovec {
-                iov_base: buf.as_mut_ptr() as *mut _,
-                iov_len: 8192,
-            };
+        if let Some(device) = self.pop_buffered() {
+            return Ok(device);
+        }
+
+        self.receive_device_queued()?.ok_or(Error::Filtered(
+            "device did not pass filter, no queued devices".into(),
+        ))
+    }
 
-            let mut cred_msg = [0u8; mem::size_of::<libc::ucred>()];
+    /// Receives a device from the [UdevMonitor] socket, waiting up to `timeout` for one to arrive.
+    ///
+    /// The wait uses the [TimeoutStrategy] of the [UdevMonitor], which is probed on first use.
+    /// Devices that do not pass the filter do not reset the timeout. Devices held by the event
+    /// buffer are returned first, without waiting.
+    ///
+    /// Returns:
+    ///
+    /// - `Ok(Some(UdevDevice))` if a device was received
+    /// - `Ok(None)` if the timeout elapsed
+    /// - `Err(Error)` otherwise
+    pub fn receive_device_timeout(
+        &mut self,
+        timeout: time::Duration,
+    ) -> Result<Option<UdevDevice>> {
+        if let Some(device) = self.pop_buffered() {
+            return Ok(Some(device));
+        }
 
-            // SAFETY: `libc::sockaddr_nl` has a known-size, and is well-aligned.
-            // `snl` will also be initialized below by the syscall before being used.
-            let mut snl: libc::sockaddr_nl = unsafe { mem::zeroed() };
-            snl.nl_family = libc::AF_NETLINK as u16;
+        if self.timeout_strategy == TimeoutStrategy::Unknown {
+            self.probe_timeout_strategy();
+        }
 
-            // SAFETY: `libc::msghdr` has a known-size, and is well-aligned.
-            // `smsg` is properly initialized below before further use.
-            let mut smsg: libc::msghdr = unsafe { mem::zeroed() };
+        let deadline = time::Instant::now() + timeout;
 
-            smsg.msg_iov = &mut iov as *mut libc::iovec as *mut _;
-            smsg.msg_iovlen = 1;
-            smsg.msg_control = cred_msg.as_mut_ptr() as *mut _;
-            smsg.msg_controllen = cred_msg.len();
-            smsg.msg_name = &mut snl as *mut libc::sockaddr_nl as *mut _;
-            smsg.msg_namelen = mem::size_of::<libc::sockaddr_nl>() as u32;
+        loop {
+            let remaining = deadline.saturating_duration_since(time::Instant::now());
 
-            // SAFETY: all parameters are properly initialized, and point to valid memory.
-            let buflen = unsafe { libc::recvmsg(self.sock, &mut smsg as *mut _, 0) };
+            let ready = match self.timeout_strategy {
+                TimeoutStrategy::Ppoll => self.wait_ppoll(remaining),
+                _ => self.wait_socket_option(remaining).or_else(|err| {
+                    log::debug!("{err}, falling back to ppoll");
+                    self.timeout_strategy = TimeoutStrategy::Ppoll;
+                    self.wait_ppoll(remaining)
+                }),
+            }?;
 
-            let trusted_pid = self.snl_trusted_sender.pid().unwrap_or(0);
+            if !ready {
+                return Ok(None);
+            }
 
-            if buflen < 0 {
-                let errno = io::Error::last_os_error();
-                let err_msg = format!("unable to receive message: {errno}");
+            if let Some(device) = self.receive_device_queued()? {
+                return Ok(Some(device));
+            }
 
-                log::debug!("{err_msg}");
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+        }
+    }
 
-                Err(Error::UdevMonitor(err_msg))
-            } else if buflen < 32 || smsg.msg_flags & libc::MSG_TRUNC != 0 {
-                let err_msg = format!("invalid message length: {buflen}");
+    fn wait_ppoll(&self, timeout: time::Duration) -> Result<bool> {
+        let deadline = time::Instant::now() + timeout;
 
-                log::error!("{err_msg}");
+        loop {
+            let remaining = deadline.saturating_duration_since(time::Instant::now());
+            let ts = libc::timespec {
+                tv_sec: remaining.as_secs() as libc::time_t,
+                tv_nsec: remaining.subsec_nanos() as libc::c_long,
+            };
+            let mut pfd = [libc::pollfd {
+                fd: self.sock(),
+                events: libc::POLLIN,
+                revents: 0,
+            }];
+
+            // SAFETY: `pollfd` and `timespec` are properly initialized, and pointers reference
+            // valid memory. A null signal mask leaves the current mask unchanged.
+            let ret = unsafe {
+                libc::ppoll(
+                    pfd.as_mut_ptr(),
+                    pfd.len() as libc::nfds_t,
+                    &ts,
+                    std::ptr::null(),
+                )
+            };
 
-                Err(Error::UdevMonitor(err_msg))
-            } else if snl.nl_groups == 0 && (trusted_pid == 0 || snl.nl_pid != trusted_pid) {
-                // unicast message, check if we trust the sender
-                let err_msg = "unicast netlink message ignored".to_owned();
+            if ret >= 0 {
+                return Ok(ret > 0);
+            }
 
-                log::debug!("{err_msg}");
+            let errno = io::Error::last_os_error();
+            if errno.kind() != io::ErrorKind::Interrupted {
+                let err_msg = format!("error waiting for device, errno: {errno}");
+                log::error!("{err_msg}");
+                return Err(Error::os(ErrorScope::Monitor, err_msg, errno));
+            }
+        }
+    }
 
-                Err(Error::UdevMonitor(err_msg))
-            } else if snl.nl_groups == UdevMonitorNetlinkGroup::Kernel as u32 && snl.nl_pid > 0 {
-                let pid = snl.nl_pid;
-                let err_msg = format!("multicast kernel netlink message from PID {pid} ignored");
+    fn wait_socket_option(&self, timeout: time::Duration) -> Result<bool> {
+        let sock = self.sock();
 
-                log::debug!("{err_msg}");
+        // a zero SO_RCVTIMEO blocks forever, so use the smallest representable timeout instead
+        let timeout = cmp::max(timeout, time::Duration::from_micros(1));
+        let tv = libc::timeval {
+            tv_sec: timeout.as_secs() as libc::time_t,
+            tv_usec: timeout.subsec_micros() as libc::suseconds_t,
+        };
 
-                Err(Error::UdevMonitor(err_msg))
+        let set_timeout = |tv: &libc::timeval| -> Result<()> {
+            // SAFETY: all arguments are valid, and pointers reference valid memory.
+            let err = unsafe {
+                libc::setsockopt(
+                    sock,
+                    libc::SOL_SOCKET,
+                    libc::SO_RCVTIMEO,
+                    tv as *const libc::timeval as *const _,
+                    mem::size_of::<libc::timeval>() as libc::socklen_t,
+                )
+            };
+            if err < 0 {
+                let errno = io::Error::last_os_error();
+                Err(Error::os(
+                    ErrorScope::Monitor,
+                    format!("unable to set SO_RCVTIMEO, errno: {errno}"),
+                    errno,
+                ))
             } else {
                 Ok(())
-            }?;
-
-            let libc::ucred {
-                pid: _,
-                uid,
-                gid: _,
-            } = parse_cmsg(cred_msg.as_ref())?;
+            }
+        };
 
-            if uid != 0 {
-                let err_msg = format!("sender uid={uid}, message ignored");
+        // SAFETY: `fcntl` with `F_GETFL` only reads the file status flags.
+        let flags = unsafe { libc::fcntl(sock, libc::F_GETFL) };
+        if flags < 0 {
+            let errno = io::Error::last_os_error();
+            return Err(Error::os(
+                ErrorScope::Monitor,
+                format!("unable to get socket flags, errno: {errno}"),
+                errno,
+            ));
+        }
 
-                log::debug!("{err_msg}");
+        set_timeout(&tv)?;
 
-                Err(Error::UdevMonitor(err_msg))
-            } else {
-                Ok(())
-            }?;
+        // SO_RCVTIMEO only applies to blocking sockets, so clear O_NONBLOCK while waiting
+        //
+        // SAFETY: `fcntl` with `F_SETFL` only modifies the file status flags.
+        if unsafe { libc::fcntl(sock, libc::F_SETFL, flags & !libc::O_NONBLOCK) } < 0 {
+            let errno = io::Error::last_os_error();
+            set_timeout(&libc::timeval {
+                tv_sec: 0,
+                tv_usec: 0,
+            })?;
+            return Err(Error::os(
+                ErrorScope::Monitor,
+                format!("unable to set socket flags, errno: {errno}"),
+                errno,
+            ));
+        }
 
-            let (bufpos, is_initialized) = match UdevMonitorNetlinkHeader::try_from(buf.as_ref()) {
-                Ok(nlh) => {
-                    let prop_off = nlh.properties_off as usize;
-                    log::debug!("NetlinkHeader properties offset: {prop_off:#x}");
-                    (cmp::min(buf.len(), prop_off), true)
+        let mut peek = [0u8; 1];
+        let ret = loop {
+            // wait for a message without consuming it, so it can be decoded by the regular
+            // receive path
+            //
+            // SAFETY: the buffer references valid memory of the provided length.
+            let ret = unsafe {
+                libc::recv(
+                    sock,
+                    peek.as_mut_ptr() as *mut _,
+                    peek.len(),
+                    libc::MSG_PEEK | libc::MSG_TRUNC,
+                )
+            };
+            if ret >= 0 {
+                break Ok(true);
+            }
+            let errno = io::Error::last_os_error();
+            match errno.kind() {
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => break Ok(false),
+                io::ErrorKind::Interrupted => continue,
+                _ => {
+                    break Err(Error::os(
+                        ErrorScope::Monitor,
+                        format!("error waiting for device, errno: {errno}"),
+                        errno,
+                    ))
                 }
-                Err(_) => {
-                    // kernel message header
-                    let bufpos = buf
-                        .iter()
-                        .position(|&b| b == b'\0')
-                        .map(|b| b + 1)
-                        .unwrap_or(0);
+            }
+        };
 
-                    if bufpos < b"a@/d".len() || bufpos >= buflen as usize {
-                        let err_msg = format!("invalid message length :: buffer length: {buflen}, header length: {bufpos}, expected header: 4");
+        // restore the socket to its original state
+        //
+        // SAFETY: `fcntl` with `F_SETFL` only modifies the file status flags.
+        unsafe { libc::fcntl(sock, libc::F_SETFL, flags) };
+        set_timeout(&libc::timeval {
+            tv_sec: 0,
+            tv_usec: 0,
+        })?;
 
-                        log::debug!("{err_msg}");
-
-                        Err(Error::UdevMonitor(err_msg))
-                    } else if buf[..2].as_ref() != b"@/".as_ref() {
-                        let err_msg = "unrecognized message header".to_owned();
-
-                        log::debug!("{err_msg}");
+        ret
+    }
 
-                        Err(Error::UdevMonitor(err_msg))
-                    } else {
-                        Ok((bufpos, false))
-                    }?
-                }
-            };
+    // Returns `Ok(None)` if devices did not pass the filter, and no more devices are queued.
+    fn receive_device_queued(&mut self) -> Result<Option<UdevDevice>> {
+        let mut buf = [0u8; 8192];
 
-            let mut udev_device =
-                UdevDevice::new_from_nulstr(Arc::clone(&self.udev), buf[bufpos..].as_ref())
-                    .map_err(|e| {
-                        let err_msg = format!("could not create device: {e}");
-                        log::debug!("{err_msg}");
-                        Error::UdevMonitor(err_msg)
-                    })?;
+        let accepted = self.receive_accepted(&mut buf, |monitor, props, is_initialized| {
+            let mut udev_device = UdevDevice::new_from_nulstr(Arc::clone(&monitor.udev), props)
+                .map_err(|e| {
+                    let err_msg = format!("could not create device: {e}");
+                    log::debug!("{err_msg}");
+                    metrics::increment(Metric::MonitorParseFailure);
+                    Error::UdevMonitor(err_msg)
+                })?;
 
             if is_initialized {
                 udev_device.set_is_initialized(true);
             }
 
+            // invalidate before filtering, filtered events also make cached devices stale
+            if let Some(cache) = monitor.udev.device_cache() {
+                cache.invalidate(&udev_device);
+            }
+
+            if udev_device.action_type().is_unknown() {
+                log::debug!(
+                    "device {} has unknown action: {}",
+                    udev_device.devpath(),
+                    udev_device.action()
+                );
+            }
+
+            Ok(monitor
+                .passes_filter(&mut udev_device)
+                .then_some(udev_device))
+        })?;
+
+        Ok(accepted.map(|(udev_device, _)| udev_device))
+    }
+
+    // Receives the queued messages into `buf`, until `accept` returns an event passing the
+    // filters, called with the properties of each message, and whether it was sent by `udev`.
+    //
+    // Returns the accepted event, and the range of its properties in `buf`, or `Ok(None)` if
+    // events did not pass the filter, and no more devices are queued.
+    pub(super) fn receive_accepted<T, F>(
+        &mut self,
+        buf: &mut [u8],
+        mut accept: F,
+    ) -> Result<Option<(T, ops::Range<usize>)>>
+    where
+        F: FnMut(&Self, &[u8], bool) -> Result<Option<T>>,
+    {
+        // avoid infinite loop, only retry up to the policy limit of queued devices
+        // the original implementation retries indefinitely, as long as there are queued devices
+        for _ in 0..self.receive_policy.retry_limit() {
+            let (bufpos, buflen, is_initialized) = match self.receive_message(buf, 0) {
+                Err(Error::Overflow(msg)) => {
+                    self.handle_overflow(msg)?;
+                    if !self.has_pending()? {
+                        return Ok(None);
+                    }
+                    continue;
+                }
+                res => res?,
+            };
+
             // skip device, if it does not pass the current filter
-            if !self.passes_filter(&mut udev_device) {
-                // if somthing is queued, get next device
-                let mut pfd = [libc::pollfd {
-                    fd: self.sock,
-                    events: libc::POLLIN,
-                    revents: 0,
-                }];
-                let pfd_len = pfd.len() as libc::nfds_t;
-
-                // SAFETY: call to `poll` is safe because `pollfd` is properly initialized, and the
-                // resulting mutable pointer references valid memory.
-                if unsafe { libc::poll(pfd.as_mut_ptr(), pfd_len, 0) } > 0 {
+            match accept(self, &buf[bufpos..buflen], is_initialized)? {
+                Some(event) => {
+                    metrics::increment(Metric::MonitorReceived);
+                    return Ok(Some((event, bufpos..buflen)));
+                }
+                None => {
+                    metrics::increment(Metric::MonitorFilterDropped);
+                    self.stats_filter_rejected();
+
+                    // if somthing is queued, get next device
+                    if !self.has_pending()? {
+                        return Ok(None);
+                    }
                     // retry with the next device
-                    Ok(())
-                } else {
-                    Err(Error::UdevMonitor(
-                        "device did not pass filter, no queued devices".into(),
-                    ))
-                }?;
-            } else {
-                return Ok(udev_device);
+                }
             }
         }
 
         Err(Error::UdevMonitor("receive device retries exceeded".into()))
     }
 
-    /// Sends an [UdevDevice] from one [UdevMonitor] to another.
-    // FIXME: break this into smaller functions
-    pub fn send_device(
-        &mut self,
-        mut destination: Option<&mut Self>,
-        device: &mut UdevDevice,
-    ) -> Result<isize> {
-        let mut nlh = UdevMonitorNetlinkHeader::new();
-
-        let mut iov = [
-            libc::iovec {
-                iov_base: &mut nlh as *mut UdevMonitorNetlinkHeader as *mut _,
-                iov_len: mem::size_of::<UdevMonitorNetlinkHeader>(),
-            },
-            libc::iovec {
-                iov_base: core::ptr::null_mut(),
-                iov_len: 0,
-            },
-        ];
-
-        let mut smsg = libc::msghdr {
-            msg_iov: iov.as_mut_ptr() as *mut _,
-            msg_iovlen: iov.len(),
-            msg_control: core::ptr::null_mut(),
-            msg_controllen: 0,
-            msg_flags: 0,
-            msg_name: core::ptr::null_mut(),
-            msg_namelen: 0,
+    // Receives the next message from the socket, and validates the sender, and header.
+    //
+    // Returns the offset of the properties, the message length, and whether the message was sent
+    // by `udev`, or the kernel. With `MSG_PEEK` in `flags`, the message stays queued.
+    //
+    // Messages are counted in the [MonitorStats], and recorded before they are validated, unless
+    // peeked.
+    fn receive_message(&mut self, buf: &mut [u8], flags: i32) -> Result<(usize, usize, bool)> {
+        let mut iov = libc::iovec {
+            iov_base: buf.as_mut_ptr() as *mut _,
+            iov_len: buf.len(),
         };
 
-        if device.get_properties_monitor_buf().len() < 32 {
-            Err(Error::UdevMonitor(
-                "device buffer is too small to contain a valid device".into(),
-            ))
-        } else {
-            let mut buf = device.get_properties_monitor_buf().to_owned();
-            let blen = buf.len();
+        // aligned for `cmsghdr`, with room for the sender credentials, and the drop counter
+        let mut control = [0u64; CONTROL_BUF_LEN];
+
+        // SAFETY: `libc::sockaddr_nl` has a known-size, and is well-aligned.
+        // `snl` will also be initialized below by the syscall before being used.
+        let mut snl: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        snl.nl_family = libc::AF_NETLINK as u16;
 
-            // fill in versioned header
-            nlh.set_filter_subsystem_hash(util::string_hash32(device.get_subsystem()));
+        // SAFETY: `libc::msghdr` has a known-size, and is well-aligned.
+        // `smsg` is properly initialized below before further use.
+        let mut smsg: libc::msghdr = unsafe { mem::zeroed() };
 
-            if !device.devtype().is_empty() {
-                nlh.set_filter_devtype_hash(util::string_hash32(device.devtype()));
+        smsg.msg_iov = &mut iov as *mut libc::iovec as *mut _;
+        smsg.msg_iovlen = 1;
+        smsg.msg_control = control.as_mut_ptr() as *mut _;
+        smsg.msg_controllen = mem::size_of_val(&control);
+        smsg.msg_name = &mut snl as *mut libc::sockaddr_nl as *mut _;
+        smsg.msg_namelen = mem::size_of::<libc::sockaddr_nl>() as u32;
+
+        // SAFETY: all parameters are properly initialized, and point to valid memory.
+        let buflen = unsafe { libc::recvmsg(self.sock(), &mut smsg as *mut _, flags) };
+
+        let trusted_unicast = snl.nl_groups == 0 && self.unicast_trusted(snl.nl_pid);
+        let peek = flags & libc::MSG_PEEK != 0;
+
+        let cmsgs = if buflen >= 0 {
+            parse_cmsgs(&smsg)
+        } else {
+            ControlMessages::default()
+        };
+
+        if buflen >= 0 && !peek {
+            self.record_message(&buf[..cmp::min(buflen as usize, buf.len())]);
+            self.stats_received();
+            if let Some(counter) = cmsgs.dropped {
+                self.stats_kernel_dropped(counter);
             }
+        }
 
-            // add tag bloom filter
-            let mut tag_bloom_bits = 0u64;
-            device
-                .tags_list()
-                .iter()
-                .for_each(|list_entry| tag_bloom_bits |= util::string_bloom64(list_entry.name()));
+        if buflen < 0 {
+            let errno = io::Error::last_os_error();
+
+            if errno.raw_os_error() == Some(libc::ENOBUFS) {
+                metrics::increment(Metric::MonitorOverflow);
+                Err(Error::Overflow(format!(
+                    "receive buffer full, events were lost: {errno}"
+                )))
+            } else {
+                let err_msg = format!("unable to receive message: {errno}");
+
+                log::debug!("{err_msg}");
 
-            if tag_bloom_bits > 0 {
-                nlh.set_filter_tag_bloom_hi((tag_bloom_bits >> 32) as u32);
-                nlh.set_filter_tag_bloom_lo(tag_bloom_bits as u32);
+                Err(Error::os(ErrorScope::Monitor, err_msg, errno))
             }
+        } else if buflen < 32 || smsg.msg_flags & libc::MSG_TRUNC != 0 {
+            let err_msg = format!("invalid message length: {buflen}");
 
-            // add properties list
-            nlh.properties_off = iov[0].iov_len as u32;
-            nlh.properties_len = blen as u32;
+            log::error!("{err_msg}");
 
-            iov[1].iov_base = buf.as_mut_ptr() as *mut _;
-            iov[1].iov_len = blen;
+            Err(Error::UdevMonitor(err_msg))
+        } else if snl.nl_groups == 0 && !trusted_unicast {
+            // unicast message, check if we trust the sender
+            let err_msg = "unicast netlink message ignored".to_owned();
 
-            // Use custom address for target, or the default one.
-            //
-            // If we send to a multicast group, we will get
-            // ECONNREFUSED, which is expected.
-            if let Some(dest) = destination.as_mut() {
-                smsg.msg_name = &mut dest.snl as *mut UdevSocket as *mut _;
-            } else {
-                smsg.msg_name = &mut self.snl_destination as *mut UdevSocket as *mut _;
+            log::debug!("{err_msg}");
+            self.stats_sender_rejected();
+
+            Err(Error::UdevMonitor(err_msg))
+        } else if snl.nl_groups == UdevMonitorNetlinkGroup::Kernel.mask() && snl.nl_pid > 0 {
+            let pid = snl.nl_pid;
+            let err_msg = format!("multicast kernel netlink message from PID {pid} ignored");
+
+            log::debug!("{err_msg}");
+            self.stats_sender_rejected();
+
+            Err(Error::UdevMonitor(err_msg))
+        } else {
+            Ok(())
+        }?;
+
+        let Some(libc::ucred {
+            pid: _,
+            uid,
+            gid: _,
+        }) = cmsgs.cred
+        else {
+            let err_msg = "no sender credentials received, message ignored".to_owned();
+
+            log::debug!("{err_msg}");
+            self.stats_sender_rejected();
+
+            return Err(Error::UdevMonitor(err_msg));
+        };
+
+        if !self.receive_policy.sender_allowed(uid, trusted_unicast) {
+            let err_msg = format!("sender uid={uid}, message ignored");
+
+            log::debug!("{err_msg}");
+            self.stats_sender_rejected();
+
+            Err(Error::UdevMonitor(err_msg))
+        } else {
+            Ok(())
+        }?;
+
+        let (bufpos, buflen, is_udev) = parse_message(buf, buflen as usize)?;
+
+        if !self.receive_policy.format_allowed(is_udev) {
+            let source = if is_udev { "udev" } else { "kernel" };
+            let err_msg = format!("{source} message rejected by the receive policy");
+
+            log::debug!("{err_msg}");
+            self.stats_sender_rejected();
+
+            Err(Error::UdevMonitor(err_msg))
+        } else {
+            Ok((bufpos, buflen, is_udev))
+        }
+    }
+
+    // Checks whether a message is queued on the socket, without blocking.
+    fn has_pending(&self) -> Result<bool> {
+        let mut pfd = [libc::pollfd {
+            fd: self.sock(),
+            events: libc::POLLIN,
+            revents: 0,
+        }];
+        let pfd_len = pfd.len() as libc::nfds_t;
+
+        // SAFETY: call to `poll` is safe because `pollfd` is properly initialized, and the
+        // resulting mutable pointer references valid memory.
+        match unsafe { libc::poll(pfd.as_mut_ptr(), pfd_len, 0) } {
+            r if r < 0 => {
+                let errno = io::Error::last_os_error();
+                Err(Error::os(
+                    ErrorScope::Monitor,
+                    format!("unable to poll monitor socket: {errno}"),
+                    errno,
+                ))
             }
+            _ if pfd[0].revents & libc::POLLNVAL != 0 => Err(Error::UdevMonitor(format!(
+                "invalid monitor socket: {}",
+                self.sock()
+            ))),
+            _ => Ok(pfd[0].revents & libc::POLLIN != 0),
+        }
+    }
+
+    /// Sends an [UdevDevice] from one [UdevMonitor] to another.
+    ///
+    /// The message is sent to the `destination` monitor address, or the
+    /// [snl_destination](Self::snl_destination) address, if `destination` is `None`.
+    ///
+    /// Returns: `Ok(isize)` with the number of bytes sent on success, `Ok(0)` if nobody listens
+    /// on the default destination multicast group, `Err(Error)` otherwise.
+    pub fn send_device(
+        &mut self,
+        destination: Option<&mut Self>,
+        device: &mut UdevDevice,
+    ) -> Result<isize> {
+        if device.get_properties_monitor_buf().len() < 32 {
+            return Err(Error::UdevMonitor(
+                "device buffer is too small to contain a valid device".into(),
+            ));
+        }
 
-            smsg.msg_namelen = mem::size_of::<libc::sockaddr_nl>() as u32;
-            // SAFETY: call to `sendmsg` is safe because the parameters are properly initialized
-            // and the pointers reference valid memory.
-            let count = unsafe { libc::sendmsg(self.sock, &mut smsg as *mut _, 0) };
+        let nlh = UdevMonitorNetlinkHeader::for_device(device);
+        let header = nlh.to_bytes();
+        let buf = device.get_properties_monitor_buf();
 
-            let mon_pid = if let Some(dest) = destination.as_ref() {
-                dest.snl.pid()?
-            } else {
-                self.snl_destination.pid()?
-            };
+        // Use custom address for target, or the default one.
+        let has_destination = destination.is_some();
+        let addr = match destination {
+            Some(dest) => dest.snl.to_netlink_addr()?,
+            None => self.snl_destination.to_netlink_addr()?,
+        };
+        let mon_pid = addr.pid();
 
-            if count < 0
-                && destination.is_none()
-                && io::Error::last_os_error().raw_os_error() == Some(libc::ECONNREFUSED)
-            {
+        let iov = [IoSlice::new(header.as_ref()), IoSlice::new(buf.as_ref())];
+
+        match socket::sendmsg(self.sock(), &iov, &[], MsgFlags::empty(), Some(&addr)) {
+            // If we send to a multicast group, we will get
+            // ECONNREFUSED, which is expected.
+            Err(Errno::ECONNREFUSED) if !has_destination => {
                 log::debug!("passed device to netlink monitor: PID({mon_pid})");
                 Ok(0)
-            } else if count < 0 {
-                let errno = io::Error::last_os_error();
-                Err(Error::UdevMonitor(format!("sending device error: {errno}")))
-            } else {
+            }
+            Err(errno) => {
+                let errno = io::Error::from(errno);
+                Err(Error::os(
+                    ErrorScope::Monitor,
+                    format!("sending device error: {errno}"),
+                    errno,
+                ))
+            }
+            Ok(count) => {
                 log::debug!(
                     "monitor: passed {count} byte device to netlink monitor: PID({mon_pid})"
                 );
-                Ok(count)
+                Ok(count as isize)
             }
         }
     }
@@ -1025,6 +1495,12 @@ impl UdevMonitor {
     /// The filter must be installed before the monitor is switched to listening mode.
     /// ```
     ///
+    /// Multiple entries may be added for the same `subsystem` with different `devtype`s, e.g.
+    /// `("block", "disk")` and `("block", "partition")`. A device passes the filter if it matches
+    /// any entry (see [filter_entries](Self::filter_entries)).
+    ///
+    /// Adding an entry that already exists returns the existing entry.
+    ///
     /// Returns `Ok` on success, `Err` otherwise.
     pub fn filter_add_match_subsystem_devtype(
         &mut self,
@@ -1033,6 +1509,17 @@ impl UdevMonitor {
     ) -> Result<&UdevEntry> {
         if subsystem.is_empty() {
             Err(Error::UdevMonitor("empty subsystem filter".into()))
+        } else if let Some(pos) = self
+            .filter_subsystem_list
+            .iter()
+            .position(|e| e.name() == subsystem && e.value() == devtype)
+        {
+            self.filter_subsystem_list
+                .iter()
+                .nth(pos)
+                .ok_or(Error::UdevMonitor(
+                    "unable to get entry from filter subsystem list".into(),
+                ))
         } else {
             self.filter_subsystem_list
                 .add_entry(subsystem, devtype)
@@ -1079,11 +1566,15 @@ impl UdevMonitor {
         };
 
         self.filter_subsystem_list.clear();
+        self.filter_sysattr_list.clear();
+        self.filter_devpath_list.clear();
+        self.filter_property_list.clear();
+        self.match_cache.clear();
 
         // SAFETY: all arguments are valid, and pointers reference valid memory.
         let ret = unsafe {
             libc::setsockopt(
-                self.sock,
+                self.sock(),
                 libc::SOL_SOCKET,
                 libc::SO_ATTACH_FILTER,
                 &mut filter as *mut libc::sock_fprog as *mut _,
@@ -1093,113 +1584,213 @@ impl UdevMonitor {
 
         if ret != 0 {
             let errno = io::Error::last_os_error();
-            Err(Error::UdevMonitor(format!(
-                "unable to remove kernel `SO_ATTACH_FILTER`: {ret}, errno: {errno}"
-            )))
+            Err(Error::os(
+                ErrorScope::Monitor,
+                format!("unable to remove kernel `SO_ATTACH_FILTER`: {ret}, errno: {errno}"),
+                errno,
+            ))
         } else {
             Ok(())
         }
     }
 }
 
-fn parse_cmsg(msg_control: &[u8]) -> Result<libc::ucred> {
-    let controllen = msg_control.len();
-    let header_len = mem::size_of::<libc::cmsghdr>();
-    let ucred_len = mem::size_of::<libc::ucred>();
-    let msg_control_len = header_len + ucred_len;
-
-    let int_len = mem::size_of::<libc::c_int>();
-    let null_int = [0u8; 4];
-
-    match controllen {
-        l if l >= msg_control_len => {
-            // skip to the `cmsg_type` index of the `cmsghdr`
-            let mut idx = int_len * 3;
-
-            let cmsg_type = libc::c_int::from_ne_bytes(
-                msg_control[idx..idx + int_len]
-                    .as_ref()
-                    .try_into()
-                    .unwrap_or(null_int),
-            );
+impl AsRawFd for UdevMonitor {
+    fn as_raw_fd(&self) -> RawFd {
+        self.sock()
+    }
+}
 
-            idx += int_len;
+impl AsFd for UdevMonitor {
+    /// Borrows the socket file descriptor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the [UdevMonitor] does not have a socket, e.g. when created with
+    /// [UdevMonitor::new].
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.sock
+            .as_ref()
+            .map(|s| s.as_fd())
+            .expect("udev monitor has no socket")
+    }
+}
 
-            if cmsg_type != libc::SCM_CREDENTIALS {
-                let err_msg = "no sender credentials received, message ignored".to_owned();
+// Parses the header of a netlink message, from the `udev` daemon, or the kernel.
+//
+// Returns the offset of the properties, the message length, and whether the message was sent
+// by `udev`, or the kernel.
+pub(crate) fn parse_message(buf: &[u8], buflen: usize) -> Result<(usize, usize, bool)> {
+    match UdevMonitorNetlinkHeader::try_from(buf) {
+        Ok(nlh) => {
+            let prop_off = nlh.properties_off as usize;
+            log::debug!("NetlinkHeader properties offset: {prop_off:#x}");
+            Ok((cmp::min(buflen, prop_off), buflen, true))
+        }
+        Err(_) => {
+            // kernel message header
+            let bufpos = buf
+                .iter()
+                .position(|&b| b == b'\0')
+                .map(|b| b + 1)
+                .unwrap_or(0);
+
+            if bufpos < b"a@/d".len() || bufpos >= buflen {
+                let err_msg = format!("invalid message length :: buffer length: {buflen}, header length: {bufpos}, expected header: 4");
 
                 log::debug!("{err_msg}");
 
                 Err(Error::UdevMonitor(err_msg))
-            } else {
-                let pid = libc::pid_t::from_ne_bytes(
-                    msg_control[idx..idx + int_len]
-                        .as_ref()
-                        .try_into()
-                        .unwrap_or(null_int),
-                );
-                idx += int_len;
-
-                let uid = libc::uid_t::from_ne_bytes(
-                    msg_control[idx..idx + int_len]
-                        .as_ref()
-                        .try_into()
-                        .unwrap_or(null_int),
-                );
-                idx += int_len;
+            } else if !buf[..bufpos].windows(2).any(|w| w == b"@/") {
+                let err_msg = "unrecognized message header".to_owned();
 
-                let gid = libc::gid_t::from_ne_bytes(
-                    msg_control[idx..idx + int_len]
-                        .as_ref()
-                        .try_into()
-                        .unwrap_or(null_int),
-                );
+                log::debug!("{err_msg}");
 
-                Ok(libc::ucred { pid, uid, gid })
+                Err(Error::UdevMonitor(err_msg))
+            } else {
+                Ok((bufpos, buflen, false))
             }
+            .inspect_err(|_| metrics::increment(Metric::MonitorParseFailure))
         }
-        l if l >= ucred_len => {
-            let mut idx = 0;
+    }
+}
 
-            let pid = libc::pid_t::from_ne_bytes(
-                msg_control[idx..idx + int_len]
-                    .as_ref()
-                    .try_into()
-                    .unwrap_or(null_int),
-            );
-            idx += int_len;
+impl Drop for UdevMonitor {
+    fn drop(&mut self) {
+        if let Some(sock) = self.sock.take() {
+            log::trace!("closing udev monitor socket: {}", sock.as_raw_fd());
+        }
+    }
+}
 
-            let uid = libc::uid_t::from_ne_bytes(
-                msg_control[idx..idx + int_len]
-                    .as_ref()
-                    .try_into()
-                    .unwrap_or(null_int),
-            );
-            idx += int_len;
+#[cfg(feature = "mio")]
+impl mio::event::Source for UdevMonitor {
+    fn register(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> io::Result<()> {
+        mio::unix::SourceFd(&self.sock()).register(registry, token, interests)
+    }
 
-            let gid = libc::gid_t::from_ne_bytes(
-                msg_control[idx..idx + int_len]
-                    .as_ref()
-                    .try_into()
-                    .unwrap_or(null_int),
-            );
+    fn reregister(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> io::Result<()> {
+        mio::unix::SourceFd(&self.sock()).reregister(registry, token, interests)
+    }
 
-            Ok(libc::ucred { pid, uid, gid })
+    fn deregister(&mut self, registry: &mio::Registry) -> io::Result<()> {
+        mio::unix::SourceFd(&self.sock()).deregister(registry)
+    }
+}
+
+// Ancillary data received with a monitor message.
+#[derive(Clone, Copy, Default)]
+struct ControlMessages {
+    cred: Option<libc::ucred>,
+    dropped: Option<u32>,
+}
+
+// Reads the sender credentials, and the kernel drop counter, from the control messages.
+fn parse_cmsgs(smsg: &libc::msghdr) -> ControlMessages {
+    let mut cmsgs = ControlMessages::default();
+
+    // SAFETY: `smsg` was filled by `recvmsg`, and its control buffer is still valid.
+    let mut cmsg = unsafe { libc::CMSG_FIRSTHDR(smsg) };
+
+    while !cmsg.is_null() {
+        // SAFETY: non-null headers returned by `CMSG_FIRSTHDR`, and `CMSG_NXTHDR` are within the
+        // control buffer, and their data is read unaligned.
+        unsafe {
+            let hdr = &*cmsg;
+            let data = libc::CMSG_DATA(cmsg);
+            let data_len = (hdr.cmsg_len as usize)
+                .saturating_sub(data.offset_from(cmsg as *const u8) as usize);
+
+            match (hdr.cmsg_level, hdr.cmsg_type) {
+                (libc::SOL_SOCKET, libc::SCM_CREDENTIALS)
+                    if data_len >= mem::size_of::<libc::ucred>() =>
+                {
+                    cmsgs.cred = Some(std::ptr::read_unaligned(data as *const libc::ucred));
+                }
+                (libc::SOL_SOCKET, libc::SO_RXQ_OVFL) if data_len >= mem::size_of::<u32>() => {
+                    cmsgs.dropped = Some(std::ptr::read_unaligned(data as *const u32));
+                }
+                _ => (),
+            }
+
+            cmsg = libc::CMSG_NXTHDR(smsg, cmsg);
         }
-        _ => Err(Error::UdevMonitor(format!(
-            "msg_controllen ({controllen}) is too small for a cmsghdr"
-        ))),
     }
+
+    cmsgs
 }
 
+// Number of multicast groups of the `NETLINK_KOBJECT_UEVENT` protocol.
+const NETLINK_GROUPS: u32 = 32;
+
 /// Represents the netlink group for the [UdevMonitor].
+///
+/// Besides the `kernel`, and `udev` groups, [Custom](Self::Custom) selects the other multicast
+/// groups of the `NETLINK_KOBJECT_UEVENT` protocol, `3` through `32`, e.g. used by out-of-tree
+/// event forwarders.
+///
+/// Groups compare by their ID, so `Custom(1)` equals [Kernel](Self::Kernel).
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Default)]
 pub enum UdevMonitorNetlinkGroup {
     #[default]
     None,
     Kernel,
     Udev,
+    /// Netlink multicast group by numeric ID.
+    Custom(u32),
+}
+
+impl UdevMonitorNetlinkGroup {
+    /// Creates a [UdevMonitorNetlinkGroup] from the netlink multicast group `id`.
+    ///
+    /// IDs `1`, and `2` are the [Kernel](Self::Kernel), and [Udev](Self::Udev) groups. `0`, and
+    /// IDs above `32`, which the protocol does not have, are no group.
+    pub const fn from_id(id: u32) -> Self {
+        match id {
+            1 => Self::Kernel,
+            2 => Self::Udev,
+            3..=NETLINK_GROUPS => Self::Custom(id),
+            _ => Self::None,
+        }
+    }
+
+    /// Gets the netlink multicast group ID, used to join, and leave, the group.
+    pub const fn id(&self) -> u32 {
+        match self {
+            Self::None => 0,
+            Self::Kernel => 1,
+            Self::Udev => 2,
+            Self::Custom(id) => *id,
+        }
+    }
+
+    /// Gets the netlink multicast group bitmask, used in the socket address.
+    ///
+    /// The mask is `0` for no group, and invalid custom groups.
+    pub const fn mask(&self) -> u32 {
+        match self.id() {
+            id @ 1..=NETLINK_GROUPS => 1 << (id - 1),
+            _ => 0,
+        }
+    }
+}
+
+impl PartialEq for UdevMonitorNetlinkGroup {
+    fn eq(&self, oth: &Self) -> bool {
+        self.id() == oth.id()
+    }
 }
 
 impl From<&str> for UdevMonitorNetlinkGroup {
@@ -1207,18 +1798,14 @@ impl From<&str> for UdevMonitorNetlinkGroup {
         match val.to_lowercase().as_str() {
             "kernel" => Self::Kernel,
             "udev" => Self::Udev,
-            _ => Self::None,
+            id => id.parse::<u32>().map(Self::from_id).unwrap_or(Self::None),
         }
     }
 }
 
 impl From<u32> for UdevMonitorNetlinkGroup {
     fn from(val: u32) -> Self {
-        match val {
-            1 => Self::Kernel,
-            2 => Self::Udev,
-            _ => Self::None,
-        }
+        Self::from_id(val)
     }
 }
 
@@ -1228,10 +1815,63 @@ impl From<&UdevMonitorNetlinkGroup> for &'static str {
             UdevMonitorNetlinkGroup::None => "none",
             UdevMonitorNetlinkGroup::Kernel => "kernel",
             UdevMonitorNetlinkGroup::Udev => "udev",
+            UdevMonitorNetlinkGroup::Custom(_) => "custom",
         }
     }
 }
 
+impl From<UdevMonitorNetlinkGroup> for &'static str {
+    fn from(val: UdevMonitorNetlinkGroup) -> Self {
+        (&val).into()
+    }
+}
+
+impl fmt::Display for UdevMonitorNetlinkGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Custom(id) => write!(f, "{id}"),
+            _ => write!(f, "{}", <&str>::from(self)),
+        }
+    }
+}
+
+/// Represents the strategy used to wait for devices with a timeout.
+///
+/// See [UdevMonitor::receive_device_timeout].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TimeoutStrategy {
+    /// The strategy has not been probed yet.
+    #[default]
+    Unknown,
+    /// Blocking receive with the `SO_RCVTIMEO` socket option.
+    SocketOption,
+    /// `ppoll` on the socket, followed by a non-blocking receive.
+    Ppoll,
+}
+
+impl From<&TimeoutStrategy> for &'static str {
+    fn from(val: &TimeoutStrategy) -> Self {
+        match val {
+            TimeoutStrategy::Unknown => "unknown",
+            TimeoutStrategy::SocketOption => "socket option",
+            TimeoutStrategy::Ppoll => "ppoll",
+        }
+    }
+}
+
+impl From<TimeoutStrategy> for &'static str {
+    fn from(val: TimeoutStrategy) -> Self {
+        (&val).into()
+    }
+}
+
+impl fmt::Display for TimeoutStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", <&str>::from(self))
+    }
+}
+
 /// Represents a UDEV Netlink header.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
@@ -1248,11 +1888,15 @@ pub struct UdevMonitorNetlinkHeader {
 }
 
 impl UdevMonitorNetlinkHeader {
+    /// Length of the [UdevMonitorNetlinkHeader] on the wire.
+    pub const LEN: usize = mem::size_of::<Self>();
+
     /// Creates a new [UdevMonitorNetlinkHeader].
     pub const fn new() -> Self {
         Self {
             prefix: [b'l', b'i', b'b', b'u', b'd', b'e', b'v', 0],
-            magic: UDEV_MONITOR_MAGIC.to_be(),
+            // stored in the on-wire byte order, see `TryFrom<&[u8]>`
+            magic: UDEV_MONITOR_MAGIC,
             header_size: mem::size_of::<Self>() as u32,
             properties_off: 0,
             properties_len: 0,
@@ -1263,6 +1907,42 @@ impl UdevMonitorNetlinkHeader {
         }
     }
 
+    /// Creates a new [UdevMonitorNetlinkHeader] for sending the [UdevDevice].
+    ///
+    /// Fills in the subsystem, and devtype hashes, and the tag bloom filter bits, matched by the
+    /// socket filters of receiving monitors, and the offset, and length, of the device
+    /// properties buffer, which directly follows the header.
+    ///
+    /// The properties buffer is updated, if needed. Send the header, followed by the
+    /// [properties buffer](UdevDevice::get_properties_monitor_buf), e.g. to forward events over
+    /// other transports, like a Unix socket.
+    pub fn for_device(device: &mut UdevDevice) -> Self {
+        let properties_len = device.get_properties_monitor_buf().len();
+        let mut nlh = Self::new();
+
+        nlh.set_filter_subsystem_hash(hash::string_hash32(device.get_subsystem()));
+
+        if !device.devtype().is_empty() {
+            nlh.set_filter_devtype_hash(hash::string_hash32(device.devtype()));
+        }
+
+        // add tag bloom filter
+        let tag_bloom_bits = device.tags_list().iter().fold(0u64, |bits, list_entry| {
+            bits | hash::string_bloom64(list_entry.name())
+        });
+
+        if tag_bloom_bits > 0 {
+            nlh.set_filter_tag_bloom_hi((tag_bloom_bits >> 32) as u32);
+            nlh.set_filter_tag_bloom_lo(tag_bloom_bits as u32);
+        }
+
+        // add properties list
+        nlh.set_properties_off(Self::LEN as u32);
+        nlh.set_properties_len(properties_len as u32);
+
+        nlh
+    }
+
     /// Gets a string representation of the [UdevMonitorNetlinkHeader] prefix.
     pub fn prefix(&self) -> &str {
         std::str::from_utf8(self.prefix.as_ref()).unwrap_or("")
@@ -1316,6 +1996,9 @@ impl UdevMonitorNetlinkHeader {
     }
 
     /// Sets the filter subsystem hash.
+    ///
+    /// The value is in host byte order, and sent in network byte order, like the words loaded
+    /// by the socket filter, see [build_filter].
     pub fn set_filter_subsystem_hash(&mut self, val: u32) {
         self.filter_subsystem_hash = val;
     }
@@ -1332,6 +2015,9 @@ impl UdevMonitorNetlinkHeader {
     }
 
     /// Sets the filter devtype hash.
+    ///
+    /// The value is in host byte order, and sent in network byte order, like the words loaded
+    /// by the socket filter, see [build_filter].
     pub fn set_filter_devtype_hash(&mut self, val: u32) {
         self.filter_devtype_hash = val;
     }
@@ -1348,6 +2034,9 @@ impl UdevMonitorNetlinkHeader {
     }
 
     /// Sets the filter tag bloom hash high bits.
+    ///
+    /// The value is in host byte order, and sent in network byte order, like the words loaded
+    /// by the socket filter, see [build_filter].
     pub fn set_filter_tag_bloom_hi(&mut self, val: u32) {
         self.filter_tag_bloom_hi = val;
     }
@@ -1364,6 +2053,9 @@ impl UdevMonitorNetlinkHeader {
     }
 
     /// Sets the filter tag bloom hash low bits.
+    ///
+    /// The value is in host byte order, and sent in network byte order, like the words loaded
+    /// by the socket filter, see [build_filter].
     pub fn set_filter_tag_bloom_lo(&mut self, val: u32) {
         self.filter_tag_bloom_lo = val;
     }
@@ -1418,6 +2110,40 @@ impl UdevMonitorNetlinkHeader {
     pub const fn filter_tag_bloom_lo_offset() -> usize {
         36
     }
+
+    /// Gets the [UdevMonitorNetlinkHeader] as sent on the wire.
+    ///
+    /// The inverse of the `TryFrom<&[u8]>` conversion.
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+
+        buf[..8].copy_from_slice(self.prefix.as_ref());
+
+        let (sizes, filters) = buf[8..].split_at_mut(4 * mem::size_of::<u32>());
+
+        [
+            self.magic,
+            self.header_size,
+            self.properties_off,
+            self.properties_len,
+        ]
+        .iter()
+        .zip(sizes.chunks_exact_mut(mem::size_of::<u32>()))
+        .for_each(|(field, chunk)| chunk.copy_from_slice(field.to_le_bytes().as_ref()));
+
+        // filter fields are in network byte order, like the words loaded by the socket filter
+        [
+            self.filter_subsystem_hash,
+            self.filter_devtype_hash,
+            self.filter_tag_bloom_hi,
+            self.filter_tag_bloom_lo,
+        ]
+        .iter()
+        .zip(filters.chunks_exact_mut(mem::size_of::<u32>()))
+        .for_each(|(field, chunk)| chunk.copy_from_slice(field.to_be_bytes().as_ref()));
+
+        buf
+    }
 }
 
 impl TryFrom<&[u8]> for UdevMonitorNetlinkHeader {
@@ -1452,16 +2178,16 @@ impl TryFrom<&[u8]> for UdevMonitorNetlinkHeader {
             let properties_len = u32::from_le_bytes(val[idx..idx + 4].try_into()?);
             idx += mem::size_of::<u32>();
 
-            let filter_subsystem_hash = u32::from_le_bytes(val[idx..idx + 4].try_into()?);
+            let filter_subsystem_hash = u32::from_be_bytes(val[idx..idx + 4].try_into()?);
             idx += mem::size_of::<u32>();
 
-            let filter_devtype_hash = u32::from_le_bytes(val[idx..idx + 4].try_into()?);
+            let filter_devtype_hash = u32::from_be_bytes(val[idx..idx + 4].try_into()?);
             idx += mem::size_of::<u32>();
 
-            let filter_tag_bloom_hi = u32::from_le_bytes(val[idx..idx + 4].try_into()?);
+            let filter_tag_bloom_hi = u32::from_be_bytes(val[idx..idx + 4].try_into()?);
             idx += mem::size_of::<u32>();
 
-            let filter_tag_bloom_lo = u32::from_le_bytes(val[idx..idx + 4].try_into()?);
+            let filter_tag_bloom_lo = u32::from_be_bytes(val[idx..idx + 4].try_into()?);
 
             if magic != UDEV_MONITOR_MAGIC {
                 let err_msg = format!(
@@ -1485,3 +2211,390 @@ impl TryFrom<&[u8]> for UdevMonitorNetlinkHeader {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::SysfsFixture;
+
+    #[test]
+    fn test_udev_group_active() -> Result<()> {
+        let fixture = SysfsFixture::new()?;
+        let udev = Arc::new(Udev::new().with_run_path(fixture.run_path()));
+
+        // without the control socket, the `udev` daemon is not running
+        let monitor = UdevMonitor::new_from_netlink(Arc::clone(&udev), "udev")?;
+        assert_eq!(monitor.snl_group(), UdevMonitorNetlinkGroup::None);
+
+        // any file counts, the control socket itself cannot be opened
+        fs::write(Path::new(fixture.run_path()).join("control"), "")?;
+        let monitor = UdevMonitor::new_from_netlink(udev, "udev")?;
+        assert_eq!(monitor.snl_group(), UdevMonitorNetlinkGroup::Udev);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_subsystem_devtypes() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+        let mut monitor = UdevMonitor::new(Arc::clone(&udev))?;
+
+        monitor.filter_add_match_subsystem_devtype("block", "disk")?;
+        monitor.filter_add_match_subsystem_devtype("block", "partition")?;
+        // exact duplicates are not added twice
+        monitor.filter_add_match_subsystem_devtype("block", "disk")?;
+        monitor.filter_add_match_subsystem_devtype("usb", "usb_device")?;
+
+        assert_eq!(monitor.filter_subsystem_list().len(), 3);
+
+        let entries: Vec<(&str, &str)> = monitor
+            .filter_entries()
+            .iter()
+            .map(|e| (e.name(), e.value()))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                ("block", "disk"),
+                ("block", "partition"),
+                ("usb", "usb_device")
+            ]
+        );
+
+        // OR semantics across entries for the same subsystem
+        let mut disk = UdevDevice::new(Arc::clone(&udev))
+            .with_subsystem("block")
+            .with_devtype("disk");
+        let mut part = UdevDevice::new(Arc::clone(&udev))
+            .with_subsystem("block")
+            .with_devtype("partition");
+        let mut usb_iface = UdevDevice::new(Arc::clone(&udev))
+            .with_subsystem("usb")
+            .with_devtype("usb_interface");
+
+        assert!(monitor.passes_filter(&mut disk));
+        assert!(monitor.passes_filter(&mut part));
+        assert!(!monitor.passes_filter(&mut usb_iface));
+
+        // an any-devtype entry covers all devtypes of the subsystem
+        monitor.filter_add_match_subsystem_devtype("usb", "")?;
+
+        let entries: Vec<(&str, &str)> = monitor
+            .filter_entries()
+            .iter()
+            .map(|e| (e.name(), e.value()))
+            .collect();
+        assert_eq!(
+            entries,
+            [("block", "disk"), ("block", "partition"), ("usb", "")]
+        );
+
+        let mut usb_no_devtype = UdevDevice::new(Arc::clone(&udev)).with_subsystem("usb");
+
+        assert!(monitor.passes_filter(&mut usb_iface));
+        assert!(monitor.passes_filter(&mut usb_no_devtype));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_monitor_socket_ownership() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+
+        let monitor = UdevMonitor::new(Arc::clone(&udev))?;
+        assert_eq!(monitor.sock(), -1);
+        assert_eq!(monitor.into_raw_fd(), -1);
+
+        let monitor = UdevMonitor::new_from_netlink(Arc::clone(&udev), "kernel")?;
+        let fd = monitor.sock();
+        assert!(fd >= 0);
+        assert_eq!(monitor.as_raw_fd(), fd);
+
+        // ownership is released, so the socket stays open after the monitor is gone
+        let raw_fd = monitor.into_raw_fd();
+        assert_eq!(raw_fd, fd);
+        // SAFETY: `raw_fd` was released by the monitor, and is only owned here.
+        let sock = unsafe { OwnedFd::from_raw_fd(raw_fd) };
+
+        // the monitor takes back ownership of the socket
+        let monitor = UdevMonitor::new_from_netlink_fd(udev, "kernel", Some(sock))?;
+        assert_eq!(monitor.sock(), fd);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_monitor_group_membership() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+
+        // memberships can only change after binding
+        let mut monitor = UdevMonitor::new_from_netlink(Arc::clone(&udev), "kernel")?;
+        assert!(monitor.join_group(UdevMonitorNetlinkGroup::Udev).is_err());
+
+        monitor.enable_receiving()?;
+        assert!(monitor.join_group(UdevMonitorNetlinkGroup::None).is_err());
+        assert!(monitor.leave_group("none").is_err());
+
+        monitor.join_group(UdevMonitorNetlinkGroup::Udev)?;
+        assert_eq!(monitor.snl_group(), UdevMonitorNetlinkGroup::Udev);
+
+        monitor.leave_group(UdevMonitorNetlinkGroup::Kernel)?;
+        assert_eq!(monitor.snl_group(), UdevMonitorNetlinkGroup::Udev);
+
+        monitor.leave_group("udev")?;
+        assert_eq!(monitor.snl_group(), UdevMonitorNetlinkGroup::None);
+
+        // leaving twice succeeds
+        monitor.leave_group("udev")?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_monitor_netlink_group() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+
+        assert_eq!(
+            UdevMonitorNetlinkGroup::from("KERNEL"),
+            UdevMonitorNetlinkGroup::Kernel
+        );
+        assert_eq!(
+            UdevMonitorNetlinkGroup::from("kernal"),
+            UdevMonitorNetlinkGroup::None
+        );
+        assert_eq!(
+            UdevMonitorNetlinkGroup::from("2"),
+            UdevMonitorNetlinkGroup::Udev
+        );
+        assert_eq!(
+            UdevMonitorNetlinkGroup::from(5),
+            UdevMonitorNetlinkGroup::Custom(5)
+        );
+        assert_eq!(UdevMonitorNetlinkGroup::Custom(5).to_string(), "5");
+
+        assert_eq!(UdevMonitorNetlinkGroup::Kernel.mask(), 1);
+        assert_eq!(UdevMonitorNetlinkGroup::Udev.mask(), 2);
+        assert_eq!(UdevMonitorNetlinkGroup::Custom(4).mask(), 8);
+        assert_eq!(UdevMonitorNetlinkGroup::Custom(32).mask(), 1 << 31);
+        assert_eq!(UdevMonitorNetlinkGroup::Custom(33).mask(), 0);
+        assert_eq!(
+            UdevMonitorNetlinkGroup::Custom(1),
+            UdevMonitorNetlinkGroup::Kernel
+        );
+        assert_eq!(
+            UdevMonitorNetlinkGroup::from(33),
+            UdevMonitorNetlinkGroup::None
+        );
+        assert_eq!(
+            UdevMonitorNetlinkGroup::from("40"),
+            UdevMonitorNetlinkGroup::None
+        );
+
+        assert!(UdevMonitor::new_from_netlink_group(
+            Arc::clone(&udev),
+            UdevMonitorNetlinkGroup::None
+        )
+        .is_err());
+        assert!(UdevMonitor::new_from_netlink_group(
+            Arc::clone(&udev),
+            UdevMonitorNetlinkGroup::Custom(0)
+        )
+        .is_err());
+        assert!(UdevMonitor::new_from_netlink_group(
+            Arc::clone(&udev),
+            UdevMonitorNetlinkGroup::Custom(33)
+        )
+        .is_err());
+
+        // custom IDs of the predefined groups select them
+        let monitor = UdevMonitor::new_from_netlink_group(
+            Arc::clone(&udev),
+            UdevMonitorNetlinkGroup::Custom(1),
+        )?;
+        assert_eq!(monitor.snl_group(), UdevMonitorNetlinkGroup::Kernel);
+
+        for id in [4, 32] {
+            let mut monitor = UdevMonitor::new_from_netlink_group(
+                Arc::clone(&udev),
+                UdevMonitorNetlinkGroup::Custom(id),
+            )?;
+            assert_eq!(monitor.snl_group(), UdevMonitorNetlinkGroup::Custom(id));
+
+            match monitor.enable_receiving() {
+                Ok(()) => assert!(monitor.bound()),
+                Err(err) => {
+                    log::warn!("skipping custom group {id}, the sandbox has no netlink: {err}")
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_receive_device_timeout() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+
+        for strategy in [TimeoutStrategy::SocketOption, TimeoutStrategy::Ppoll] {
+            // the socket is not bound, so no devices arrive
+            let mut monitor = UdevMonitor::new_from_netlink(Arc::clone(&udev), "kernel")?
+                .with_timeout_strategy(strategy);
+
+            let start = time::Instant::now();
+            let timeout = time::Duration::from_millis(20);
+
+            assert!(monitor.receive_device_timeout(timeout)?.is_none());
+            assert!(start.elapsed() >= timeout);
+            assert_eq!(monitor.timeout_strategy(), strategy);
+        }
+
+        let mut monitor = UdevMonitor::new_from_netlink(udev, "kernel")?;
+        assert_eq!(monitor.timeout_strategy(), TimeoutStrategy::Unknown);
+        assert_ne!(monitor.probe_timeout_strategy(), TimeoutStrategy::Unknown);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_monitor_split() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+
+        assert!(UdevMonitor::new(Arc::clone(&udev))?.split().is_err());
+
+        let monitor = UdevMonitor::new_from_netlink(udev, "kernel")?;
+        let sock = monitor.sock();
+
+        let (fd, mut decoder) = monitor.split()?;
+        assert!(fd.as_raw_fd() >= 0);
+        assert_ne!(fd.as_raw_fd(), sock);
+        assert_eq!(decoder.monitor().sock(), sock);
+
+        // the socket is not bound, so nothing is pending
+        assert!(decoder.decode_pending()?.is_empty());
+
+        // the readiness handle stays valid after the monitor is dropped
+        drop(decoder.into_monitor());
+        assert!(fd.as_fd().try_clone_to_owned().is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_send_device() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+        let (mut sender, mut receiver) = UdevMonitor::new_loopback(Arc::clone(&udev))?;
+
+        let mut device = UdevDevice::new(Arc::clone(&udev));
+        device.add_property("ACTION", "add");
+        device.add_property("DEVPATH", "/devices/virtual/mock/mock0");
+        device.add_property("SUBSYSTEM", "mock");
+        device.add_property("SEQNUM", "1");
+
+        assert!(sender
+            .send_device(None, &mut UdevDevice::new(Arc::clone(&udev)))
+            .is_err());
+
+        let buf_len = device.get_properties_monitor_buf().len();
+        let nlh = UdevMonitorNetlinkHeader::for_device(&mut device);
+        assert_eq!(
+            UdevMonitorNetlinkHeader::try_from(nlh.to_bytes().as_ref())?,
+            nlh
+        );
+        assert_eq!(nlh.properties_off() as usize, UdevMonitorNetlinkHeader::LEN);
+        assert_eq!(nlh.properties_len() as usize, buf_len);
+        assert_eq!(nlh.filter_devtype_hash(), 0);
+        assert_eq!(nlh.filter_tag_bloom_hi(), 0);
+        assert_eq!(nlh.filter_tag_bloom_lo(), 0);
+
+        let mut tagged = UdevDevice::builder(Arc::clone(&udev))
+            .devpath("/devices/virtual/mock/mock1")
+            .subsystem("mock")
+            .devtype("mock_type")
+            .tag("seat")
+            .build()?;
+        let nlh = UdevMonitorNetlinkHeader::for_device(&mut tagged);
+        let bloom =
+            (u64::from(nlh.filter_tag_bloom_hi()) << 32) | u64::from(nlh.filter_tag_bloom_lo());
+        assert_eq!(bloom, hash::tag_bloom_bits(&["seat"]));
+        assert_eq!(nlh.filter_subsystem_hash(), hash::string_hash32("mock"));
+        assert_eq!(nlh.filter_devtype_hash(), hash::string_hash32("mock_type"));
+
+        // filter fields are sent in network byte order, on any host
+        let bytes = nlh.to_bytes();
+        let off = UdevMonitorNetlinkHeader::filter_subsystem_hash_offset();
+        assert_eq!(
+            bytes[off..off + 4],
+            hash::string_hash32("mock").to_be_bytes()
+        );
+        assert_eq!(UdevMonitorNetlinkHeader::try_from(bytes.as_ref())?, nlh);
+
+        // default destination
+        let count = sender.send_device(None, &mut device)?;
+        assert_eq!(count as usize, UdevMonitorNetlinkHeader::LEN + buf_len);
+
+        let received = receiver
+            .receive_device_timeout(time::Duration::from_secs(1))?
+            .expect("device from default destination");
+        assert_eq!(received.devpath(), "/devices/virtual/mock/mock0");
+        assert_eq!(received.seqnum(), 1);
+
+        // explicit destination
+        device.add_property("SEQNUM", "2");
+        sender.send_device(Some(&mut receiver), &mut device)?;
+
+        let received = receiver
+            .receive_device_timeout(time::Duration::from_secs(1))?
+            .expect("device from explicit destination");
+        assert_eq!(received.get_property_value("SUBSYSTEM"), Some("mock"));
+        assert_eq!(received.seqnum(), 2);
+
+        // only netlink destinations are supported
+        let addr = std::net::SocketAddrV4::new(std::net::Ipv4Addr::LOCALHOST, 0);
+        assert!(UdevSocket::SocketAddrV4(addr).to_netlink_addr().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_monitor_buffering() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+        let mut monitor =
+            UdevMonitor::new_from_netlink(Arc::clone(&udev), "kernel")?.with_buffer_capacity(0);
+
+        assert_eq!(monitor.buffer_capacity(), 1);
+        assert!(!monitor.is_buffering());
+        assert!(monitor.buffer_pending().is_err());
+        assert!(monitor.drain_buffered().is_err());
+
+        // loopback events, buffered in the order they arrived, and dropped once the buffer is full
+        let (mut sender, receiver) = UdevMonitor::new_loopback(Arc::clone(&udev))?;
+        let mut receiver = receiver.with_buffer_capacity(2);
+
+        receiver.start_buffering()?;
+        assert!(receiver.bound());
+        assert!(receiver.is_buffering());
+
+        for seqnum in 1..=3 {
+            let mut device = UdevDevice::new(Arc::clone(&udev));
+            device.add_property("ACTION", "add");
+            device.add_property("DEVPATH", &format!("/devices/virtual/mock/mock{seqnum}"));
+            device.add_property("SUBSYSTEM", "mock");
+            device.add_property("SEQNUM", &seqnum.to_string());
+            sender.send_device(None, &mut device)?;
+        }
+
+        assert_eq!(receiver.buffer_pending()?, 2);
+        assert_eq!(receiver.buffered_len(), 2);
+
+        let drained = receiver.drain_buffered()?;
+        assert!(!receiver.is_buffering());
+        assert_eq!(receiver.buffered_len(), 0);
+        assert_eq!(receiver.buffer_dropped(), 1);
+        assert_eq!(
+            drained.iter().map(|d| d.seqnum()).collect::<Vec<_>>(),
+            [2, 3]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,282 @@
+use std::{io, mem};
+
+use crate::{
+    hash, BpfFilters, Error, ErrorScope, Result, UdevEntry, UdevList, UdevMonitor,
+    UdevMonitorNetlinkHeader, BPF_FILTER_LEN,
+};
+
+/// [UDEV_MONITOR_MAGIC](crate::UDEV_MONITOR_MAGIC) as loaded by the socket filter.
+///
+/// `BPF_LD` loads words in network byte order, and the magic is sent as the bytes
+/// `fe ed ca fe`, like `libudev`.
+pub const BPF_MONITOR_MAGIC: u32 = 0xfeed_cafe;
+
+const BPF_LD_ABS: u16 = (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16;
+const BPF_AND_K: u16 = (libc::BPF_ALU | libc::BPF_AND | libc::BPF_K) as u16;
+const BPF_JEQ_K: u16 = (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16;
+const BPF_RET_K: u16 = (libc::BPF_RET | libc::BPF_K) as u16;
+
+const BPF_PASS: u32 = 0xffff_ffff;
+const BPF_DROP: u32 = 0;
+
+/// Builds the socket filter program for the subsystem, and tag, filter lists.
+///
+/// Generates the same instruction stream as `libudev`:
+///
+/// - messages without the `libudev` magic, e.g. kernel events, pass
+/// - messages must match any tag, if tags are set, using the tag bloom filter bits
+/// - messages must match any subsystem, and optional devtype, if subsystems are set, using the
+///   subsystem, and devtype hashes
+///
+/// The subsystem entries are reduced like [UdevMonitor::filter_entries].
+///
+/// The program only uses hashes, so it can pass messages that do not match the filters. Devices
+/// are matched again in userspace, see [UdevMonitor::passes_filter].
+///
+/// Returns: `Ok(BpfFilters)` on success, empty if both lists are empty, `Err(Error)` if the
+/// program exceeds [BPF_FILTER_LEN] instructions.
+pub fn build_filter(
+    subsystem_list: &UdevList,
+    tag_list: &UdevList,
+) -> Result<BpfFilters<BPF_FILTER_LEN>> {
+    let mut ins: BpfFilters<BPF_FILTER_LEN> = BpfFilters::new();
+
+    if subsystem_list.is_empty() && tag_list.is_empty() {
+        return Ok(ins);
+    }
+
+    let mut i = 0usize;
+
+    // load magic in A
+    ins.bpf_stmt(
+        &mut i,
+        BPF_LD_ABS,
+        UdevMonitorNetlinkHeader::magic_offset() as u32,
+    )?;
+    // jump if magic matches
+    ins.bpf_jmp(&mut i, BPF_JEQ_K, BPF_MONITOR_MAGIC, 1, 0)?;
+    // wrong magic, pass packet
+    ins.bpf_stmt(&mut i, BPF_RET_K, BPF_PASS)?;
+
+    if !tag_list.is_empty() {
+        let mut tag_matches = tag_list.len();
+
+        for list_entry in tag_list.iter() {
+            let tag_bloom_bits = hash::string_bloom64(list_entry.name());
+            let tag_bloom_hi = (tag_bloom_bits >> 32) as u32;
+            let tag_bloom_lo = tag_bloom_bits as u32;
+
+            // load device bloom bits in A
+            ins.bpf_stmt(
+                &mut i,
+                BPF_LD_ABS,
+                UdevMonitorNetlinkHeader::filter_tag_bloom_hi_offset() as u32,
+            )?;
+            // clear bits (tag bits & bloom bits)
+            ins.bpf_stmt(&mut i, BPF_AND_K, tag_bloom_hi)?;
+            // jump to next tag if it does not match
+            ins.bpf_jmp(&mut i, BPF_JEQ_K, tag_bloom_hi, 0, 3)?;
+
+            // load device bloom bits in A
+            ins.bpf_stmt(
+                &mut i,
+                BPF_LD_ABS,
+                UdevMonitorNetlinkHeader::filter_tag_bloom_lo_offset() as u32,
+            )?;
+            // clear bits (tag bits & bloom bits)
+            ins.bpf_stmt(&mut i, BPF_AND_K, tag_bloom_lo)?;
+            // jump behind end of tag match block if tag matches
+            tag_matches = tag_matches.saturating_sub(1);
+            let jt = u8::try_from(1usize.saturating_add(tag_matches.saturating_mul(6))).map_err(
+                |_| Error::UdevMonitor(format!("too many tag filters: {}", tag_list.len())),
+            )?;
+            ins.bpf_jmp(&mut i, BPF_JEQ_K, tag_bloom_lo, jt, 0)?;
+        }
+
+        // nothing matched, drop packet
+        ins.bpf_stmt(&mut i, BPF_RET_K, BPF_DROP)?;
+    }
+
+    // add all subsystem matches
+    if !subsystem_list.is_empty() {
+        for list_entry in subsystem_filter_entries(subsystem_list) {
+            // load device subsystem value in A
+            ins.bpf_stmt(
+                &mut i,
+                BPF_LD_ABS,
+                UdevMonitorNetlinkHeader::filter_subsystem_hash_offset() as u32,
+            )?;
+
+            let hash = hash::string_hash32(list_entry.name());
+
+            if list_entry.value().is_empty() {
+                // jump if subsystem does not match
+                ins.bpf_jmp(&mut i, BPF_JEQ_K, hash, 0, 1)?;
+            } else {
+                // jump if subsystem does not match
+                ins.bpf_jmp(&mut i, BPF_JEQ_K, hash, 0, 3)?;
+
+                // load device devtype value in A
+                ins.bpf_stmt(
+                    &mut i,
+                    BPF_LD_ABS,
+                    UdevMonitorNetlinkHeader::filter_devtype_hash_offset() as u32,
+                )?;
+
+                // jump if value does not match
+                let hash = hash::string_hash32(list_entry.value());
+                ins.bpf_jmp(&mut i, BPF_JEQ_K, hash, 0, 1)?;
+            }
+
+            // matched, pass packet
+            ins.bpf_stmt(&mut i, BPF_RET_K, BPF_PASS)?;
+        }
+
+        // nothing matched, drop packet
+        ins.bpf_stmt(&mut i, BPF_RET_K, BPF_DROP)?;
+    }
+
+    // matched, pass packet
+    ins.bpf_stmt(&mut i, BPF_RET_K, BPF_PASS)?;
+
+    Ok(ins)
+}
+
+// Removes duplicate entries, and entries covered by an any-devtype entry, keeping the order.
+pub(super) fn subsystem_filter_entries(list: &UdevList) -> Vec<&UdevEntry> {
+    let mut ret: Vec<&UdevEntry> = Vec::with_capacity(list.len());
+
+    for entry in list.iter() {
+        let (subsystem, devtype) = (entry.name(), entry.value());
+
+        let covered = list
+            .iter()
+            .any(|e| e.name() == subsystem && e.value().is_empty() && !devtype.is_empty());
+        let duplicate = ret
+            .iter()
+            .any(|e| e.name() == subsystem && e.value() == devtype);
+
+        if !covered && !duplicate {
+            ret.push(entry);
+        }
+    }
+
+    ret
+}
+
+impl UdevMonitor {
+    /// Installs a socket filter program on the [UdevMonitor] socket.
+    ///
+    /// The program is usually created by [build_filter], from the monitor filter lists.
+    ///
+    /// Returns: `Ok(())` on success, `Err(Error)` if the kernel rejects the program.
+    pub fn install_filter(&mut self, filter: BpfFilters<BPF_FILTER_LEN>) -> Result<()> {
+        self.filter = filter;
+        let mut filter = self.filter.as_sock_fprog();
+
+        // SAFETY: arguments are valid, and pointer reference valid memory.
+        let err = unsafe {
+            libc::setsockopt(
+                self.sock(),
+                libc::SOL_SOCKET,
+                libc::SO_ATTACH_FILTER,
+                &mut filter as *mut libc::sock_fprog as *mut _,
+                mem::size_of::<libc::sock_fprog>() as u32,
+            )
+        };
+
+        if err < 0 {
+            let errno = io::Error::last_os_error();
+            Err(Error::os(
+                ErrorScope::Monitor,
+                format!("error setting BPF filter, error: {err}, errno: {errno}"),
+                errno,
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Udev;
+    use std::sync::Arc;
+
+    fn stream(filter: &BpfFilters<BPF_FILTER_LEN>) -> Vec<(u16, u8, u8, u32)> {
+        filter.filters()[..filter.len()]
+            .iter()
+            .map(|f| (f.code, f.jt, f.jf, f.k))
+            .collect()
+    }
+
+    #[test]
+    fn test_build_filter() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+        let mut subsystems = UdevList::new(Arc::clone(&udev)).with_unique(false);
+        let mut tags = UdevList::new(Arc::clone(&udev));
+
+        assert!(build_filter(&subsystems, &tags)?.is_empty());
+
+        subsystems.add_entry("block", "disk");
+        subsystems.add_entry("net", "");
+        // covered by the any-devtype entry
+        subsystems.add_entry("net", "wlan");
+        tags.add_entry("systemd", "");
+        tags.add_entry("uaccess", "");
+
+        let bloom = |tag: &str| {
+            let bits = hash::string_bloom64(tag);
+            ((bits >> 32) as u32, bits as u32)
+        };
+        let (systemd_hi, systemd_lo) = bloom("systemd");
+        let (uaccess_hi, uaccess_lo) = bloom("uaccess");
+        let hash = hash::string_hash32;
+
+        // instruction stream of the `libudev` `udev_monitor_filter_update`
+        let expected = vec![
+            (BPF_LD_ABS, 0, 0, 8),
+            (BPF_JEQ_K, 1, 0, 0xfeed_cafe),
+            (BPF_RET_K, 0, 0, 0xffff_ffff),
+            // tag: systemd
+            (BPF_LD_ABS, 0, 0, 32),
+            (BPF_AND_K, 0, 0, systemd_hi),
+            (BPF_JEQ_K, 0, 3, systemd_hi),
+            (BPF_LD_ABS, 0, 0, 36),
+            (BPF_AND_K, 0, 0, systemd_lo),
+            (BPF_JEQ_K, 7, 0, systemd_lo),
+            // tag: uaccess
+            (BPF_LD_ABS, 0, 0, 32),
+            (BPF_AND_K, 0, 0, uaccess_hi),
+            (BPF_JEQ_K, 0, 3, uaccess_hi),
+            (BPF_LD_ABS, 0, 0, 36),
+            (BPF_AND_K, 0, 0, uaccess_lo),
+            (BPF_JEQ_K, 1, 0, uaccess_lo),
+            (BPF_RET_K, 0, 0, 0),
+            // subsystem: block/disk
+            (BPF_LD_ABS, 0, 0, 24),
+            (BPF_JEQ_K, 0, 3, hash("block")),
+            (BPF_LD_ABS, 0, 0, 28),
+            (BPF_JEQ_K, 0, 1, hash("disk")),
+            (BPF_RET_K, 0, 0, 0xffff_ffff),
+            // subsystem: net
+            (BPF_LD_ABS, 0, 0, 24),
+            (BPF_JEQ_K, 0, 1, hash("net")),
+            (BPF_RET_K, 0, 0, 0xffff_ffff),
+            (BPF_RET_K, 0, 0, 0),
+            (BPF_RET_K, 0, 0, 0xffff_ffff),
+        ];
+
+        assert_eq!(stream(&build_filter(&subsystems, &tags)?), expected);
+
+        // the magic matches the bytes sent in the header, loaded in network order
+        let header = UdevMonitorNetlinkHeader::new().to_bytes();
+        let offset = UdevMonitorNetlinkHeader::magic_offset();
+        let magic = &header[offset..offset + 4];
+        assert_eq!(magic, [0xfe, 0xed, 0xca, 0xfe]);
+        assert_eq!(u32::from_be_bytes(magic.try_into()?), BPF_MONITOR_MAGIC);
+
+        Ok(())
+    }
+}
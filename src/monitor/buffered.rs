@@ -0,0 +1,154 @@
+use crate::{Error, Result, UdevDevice, UdevMonitor};
+
+/// Default number of devices held by the [UdevMonitor] event buffer.
+pub const DEFAULT_BUFFER_CAPACITY: usize = 4096;
+/// Size of the kernel socket buffer requested while buffering, matches `udevadm monitor`.
+const BUFFER_RCVBUF_SIZE: usize = 128 * 1024 * 1024;
+
+impl UdevMonitor {
+    /// Binds the [UdevMonitor], and starts buffering device events.
+    ///
+    /// Call before enumerating existing devices, so devices added or removed during the scan are
+    /// not missed:
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{Udev, UdevEnumerate, UdevMonitor};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let udev = Arc::new(Udev::new());
+    /// let mut monitor = UdevMonitor::new_from_netlink(Arc::clone(&udev), "udev")?;
+    /// monitor.filter_add_match_subsystem_devtype("block", "")?;
+    ///
+    /// // 1. start listening, events are queued from now on
+    /// monitor.start_buffering()?;
+    ///
+    /// // 2. scan the existing devices
+    /// let mut enumerate = UdevEnumerate::new(udev);
+    /// enumerate.add_match_subsystem("block")?;
+    /// enumerate.scan_devices()?;
+    ///
+    /// // 3. process the events that arrived during the scan
+    /// for device in monitor.drain_buffered()? {
+    ///     println!("{} {}", device.action(), device.syspath());
+    /// }
+    ///
+    /// // 4. continue with `receive_device`, or an event loop
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Events are queued by the kernel socket buffer, which is enlarged if the process has the
+    /// privileges to do so. For long scans, call [buffer_pending](Self::buffer_pending)
+    /// periodically to move the queued events into the [UdevMonitor] buffer, before the socket
+    /// buffer overflows.
+    ///
+    /// Returns: `Ok(())` on success, `Err(Error)` if the monitor cannot be bound.
+    pub fn start_buffering(&mut self) -> Result<()> {
+        self.enable_receiving()?;
+
+        if let Err(err) = self.set_receive_buffer_size(BUFFER_RCVBUF_SIZE) {
+            log::debug!("unable to enlarge the monitor socket buffer: {err}");
+        }
+
+        self.buffering = true;
+
+        Ok(())
+    }
+
+    /// Gets whether the [UdevMonitor] is buffering device events.
+    pub const fn is_buffering(&self) -> bool {
+        self.buffering
+    }
+
+    /// Gets the maximum number of devices held by the event buffer.
+    pub const fn buffer_capacity(&self) -> usize {
+        self.buffer_capacity
+    }
+
+    /// Sets the maximum number of devices held by the event buffer.
+    ///
+    /// When the buffer is full, the oldest devices are dropped.
+    pub fn set_buffer_capacity(&mut self, val: usize) {
+        self.buffer_capacity = val.max(1);
+        while self.buffered.len() > self.buffer_capacity {
+            self.drop_oldest_buffered();
+        }
+    }
+
+    /// Builder function that sets the maximum number of devices held by the event buffer.
+    pub fn with_buffer_capacity(mut self, val: usize) -> Self {
+        self.set_buffer_capacity(val);
+        self
+    }
+
+    /// Gets the number of devices held by the event buffer.
+    pub fn buffered_len(&self) -> usize {
+        self.buffered.len()
+    }
+
+    /// Gets the number of devices dropped because the event buffer was full.
+    pub const fn buffer_dropped(&self) -> u64 {
+        self.buffer_dropped
+    }
+
+    /// Moves the events pending on the monitor socket into the event buffer.
+    ///
+    /// Does not block. Messages that are rejected, and devices that do not pass the monitor
+    /// filter are skipped.
+    ///
+    /// Returns: `Ok(usize)` with the number of buffered devices, `Err(Error)` if the
+    /// [UdevMonitor] is not buffering.
+    pub fn buffer_pending(&mut self) -> Result<usize> {
+        if !self.buffering {
+            return Err(Error::UdevMonitor("udev monitor is not buffering".into()));
+        }
+
+        let mut count = 0;
+
+        for _ in 0..self.buffer_capacity {
+            if !self.has_pending()? {
+                break;
+            }
+
+            match self.receive_device_queued() {
+                Ok(Some(device)) => {
+                    if self.buffered.len() >= self.buffer_capacity {
+                        self.drop_oldest_buffered();
+                    }
+                    self.buffered.push_back(device);
+                    count += 1;
+                }
+                Ok(None) => break,
+                Err(err) => log::debug!("skipping monitor message: {err}"),
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Stops buffering, and returns the buffered device events in the order they arrived.
+    ///
+    /// Includes the events still pending on the monitor socket. The [UdevMonitor] stays bound,
+    /// so later events are received as usual.
+    ///
+    /// Returns: `Ok(Vec<UdevDevice>)` with the buffered devices, `Err(Error)` if the
+    /// [UdevMonitor] is not buffering.
+    pub fn drain_buffered(&mut self) -> Result<Vec<UdevDevice>> {
+        self.buffer_pending()?;
+        self.buffering = false;
+
+        Ok(self.buffered.drain(..).collect())
+    }
+
+    // Gets the oldest buffered device, used by the receive functions to preserve event order.
+    pub(super) fn pop_buffered(&mut self) -> Option<UdevDevice> {
+        self.buffered.pop_front()
+    }
+
+    fn drop_oldest_buffered(&mut self) {
+        if let Some(device) = self.buffered.pop_front() {
+            self.buffer_dropped = self.buffer_dropped.saturating_add(1);
+            log::warn!("monitor event buffer full, dropping: {}", device.syspath());
+        }
+    }
+}
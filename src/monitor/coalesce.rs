@@ -0,0 +1,311 @@
+use std::collections::VecDeque;
+use std::os::fd::{AsRawFd, RawFd};
+use std::{cmp, time};
+
+use crate::{Result, UdevDevice, UdevMonitor};
+
+/// Default time window of an [EventCoalescer].
+pub const DEFAULT_COALESCE_WINDOW: time::Duration = time::Duration::from_millis(100);
+
+// Event held by an [EventCoalescer] until its window elapses.
+struct PendingEvent {
+    device: UdevDevice,
+    deadline: time::Instant,
+}
+
+/// Coalesces bursts of events for the same device, and action, e.g. the `change` events of a
+/// storage stack, reducing the churn for consumers like mount managers.
+///
+/// Events are held for the time window, starting at the first event of a burst. Later events
+/// with the same syspath, and action, replace the held event, so the latest device state is
+/// reported once the window elapses.
+///
+/// Events of different devices are reported in order of the first event of their burst. Events
+/// of one device keep their order: an event only replaces the latest held event of the device,
+/// e.g. `change`, `remove`, `change` are all reported.
+///
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use udevrs::{EventCoalescer, Udev, UdevMonitor, DEFAULT_COALESCE_WINDOW};
+/// # fn main() -> udevrs::Result<()> {
+/// let mut monitor = UdevMonitor::new_from_netlink(Arc::new(Udev::new()), "udev")?;
+/// monitor.filter_add_match_subsystem_devtype("block", "disk")?;
+/// monitor.enable_receiving()?;
+///
+/// for disk in EventCoalescer::new(monitor, DEFAULT_COALESCE_WINDOW) {
+///     let disk = disk?;
+///     println!("{}: {}", disk.action(), disk.syspath());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Held events come due on a timer, not on file descriptor readiness. Event loops polling the
+/// [AsRawFd] descriptor must also wake up at [next_deadline](Self::next_deadline), then call
+/// [try_next_event](Self::try_next_event) until it returns `Ok(None)`.
+pub struct EventCoalescer {
+    monitor: UdevMonitor,
+    window: time::Duration,
+    pending: VecDeque<PendingEvent>,
+    coalesced: u64,
+}
+
+impl EventCoalescer {
+    /// Creates a new [EventCoalescer], holding the events of the [UdevMonitor] for `window`.
+    pub fn new(monitor: UdevMonitor, window: time::Duration) -> Self {
+        Self {
+            monitor,
+            window,
+            pending: VecDeque::new(),
+            coalesced: 0,
+        }
+    }
+
+    /// Gets the time window events are held for.
+    pub const fn window(&self) -> time::Duration {
+        self.window
+    }
+
+    /// Sets the time window events are held for.
+    ///
+    /// Applies to events received after the change.
+    pub fn set_window(&mut self, window: time::Duration) {
+        self.window = window;
+    }
+
+    /// Builder function that sets the time window events are held for.
+    pub fn with_window(mut self, window: time::Duration) -> Self {
+        self.set_window(window);
+        self
+    }
+
+    /// Gets a reference to the [UdevMonitor].
+    pub const fn monitor(&self) -> &UdevMonitor {
+        &self.monitor
+    }
+
+    /// Gets a mutable reference to the [UdevMonitor].
+    pub fn monitor_mut(&mut self) -> &mut UdevMonitor {
+        &mut self.monitor
+    }
+
+    /// Converts the [EventCoalescer] into the [UdevMonitor].
+    ///
+    /// Held events are discarded, call [flush](Self::flush) first to keep them.
+    pub fn into_monitor(self) -> UdevMonitor {
+        self.monitor
+    }
+
+    /// Gets the number of held events.
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Gets the number of events replaced by a later event for the same device, and action.
+    pub const fn coalesced(&self) -> u64 {
+        self.coalesced
+    }
+
+    /// Gets the time the window of the first held event elapses.
+    ///
+    /// Returns: `Some(Instant)` while events are held, `None` otherwise.
+    pub fn next_deadline(&self) -> Option<time::Instant> {
+        self.pending.front().map(|event| event.deadline)
+    }
+
+    /// Gets the time left until the window of the first held event elapses, e.g. as the
+    /// timeout of a `poll` call on the [AsRawFd] descriptor.
+    ///
+    /// Returns: `Some(Duration)` while events are held, zero if an event is due, `None`
+    /// otherwise.
+    pub fn next_timeout(&self) -> Option<time::Duration> {
+        self.next_deadline()
+            .map(|deadline| deadline.saturating_duration_since(time::Instant::now()))
+    }
+
+    /// Gets the next device event, without waiting for its window to elapse.
+    ///
+    /// Receives the pending events of the monitor, without blocking.
+    ///
+    /// Returns: `Ok(Some(UdevDevice))` if an event window elapsed, `Ok(None)` if no event is
+    /// due, `Err(Error)` otherwise.
+    pub fn try_next_event(&mut self) -> Result<Option<UdevDevice>> {
+        self.next_event_timeout(time::Duration::ZERO)
+    }
+
+    /// Gets the next device event, waiting up to `timeout` for an event window to elapse.
+    ///
+    /// Returns: `Ok(Some(UdevDevice))` with the latest device state, if an event window elapsed,
+    /// `Ok(None)` if the timeout elapsed, `Err(Error)` otherwise.
+    pub fn next_event_timeout(&mut self, timeout: time::Duration) -> Result<Option<UdevDevice>> {
+        let deadline = time::Instant::now() + timeout;
+
+        loop {
+            let now = time::Instant::now();
+            if let Some(device) = self.pop_due(now) {
+                return Ok(Some(device));
+            }
+
+            let wait_until = self
+                .pending
+                .front()
+                .map_or(deadline, |event| cmp::min(event.deadline, deadline));
+
+            match self
+                .monitor
+                .receive_device_timeout(wait_until.saturating_duration_since(now))?
+            {
+                Some(device) => self.hold(device),
+                None if time::Instant::now() >= deadline => {
+                    return Ok(self.pop_due(time::Instant::now()))
+                }
+                None => (),
+            }
+        }
+    }
+
+    /// Reports all held events, without waiting for their windows to elapse, e.g. before
+    /// shutting down.
+    ///
+    /// Returns: the held devices, in report order.
+    pub fn flush(&mut self) -> Vec<UdevDevice> {
+        self.pending.drain(..).map(|event| event.device).collect()
+    }
+
+    // Gets the first held event, if its window elapsed.
+    fn pop_due(&mut self, now: time::Instant) -> Option<UdevDevice> {
+        if self.pending.front()?.deadline <= now {
+            self.pending.pop_front().map(|event| event.device)
+        } else {
+            None
+        }
+    }
+
+    // Replaces the latest held event of the device, if it has the same action, or holds the
+    // event for the window.
+    fn hold(&mut self, device: UdevDevice) {
+        let latest = self
+            .pending
+            .iter_mut()
+            .rev()
+            .find(|event| event.device.syspath() == device.syspath());
+
+        match latest {
+            Some(event) if event.device.action() == device.action() => {
+                log::trace!(
+                    "coalescing {} event, seqnum: {}, with seqnum: {}",
+                    device.action(),
+                    event.device.seqnum(),
+                    device.seqnum()
+                );
+                event.device = device;
+                self.coalesced = self.coalesced.saturating_add(1);
+            }
+            _ => self.pending.push_back(PendingEvent {
+                device,
+                deadline: time::Instant::now() + self.window,
+            }),
+        }
+    }
+}
+
+impl Iterator for EventCoalescer {
+    type Item = Result<UdevDevice>;
+
+    /// Gets the next device event, blocking until an event window elapses.
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.next_event_timeout(time::Duration::from_secs(60)) {
+                Ok(Some(device)) => return Some(Ok(device)),
+                Ok(None) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+impl AsRawFd for EventCoalescer {
+    /// Gets the [UdevMonitor] descriptor, readable when the monitor receives events.
+    ///
+    /// Held events do not make the descriptor readable, see
+    /// [next_deadline](EventCoalescer::next_deadline).
+    fn as_raw_fd(&self) -> RawFd {
+        self.monitor.as_raw_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Udev;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_event_coalescer() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+        let (mut sender, receiver) = UdevMonitor::new_loopback(Arc::clone(&udev))?;
+
+        let mut coalescer = EventCoalescer::new(receiver, time::Duration::from_millis(200));
+        assert!(coalescer.try_next_event()?.is_none());
+        assert!(coalescer.next_deadline().is_none());
+        assert!(coalescer.next_timeout().is_none());
+
+        for (action, name, seqnum) in [
+            ("change", "sda", 1),
+            ("change", "sda", 2),
+            ("add", "sdb", 3),
+            ("change", "sda", 4),
+            ("remove", "sda", 5),
+            // not coalesced with the `change` before the `remove`
+            ("change", "sda", 6),
+        ] {
+            let mut device = UdevDevice::builder(Arc::clone(&udev))
+                .devpath(format!("/devices/virtual/block/{name}"))
+                .subsystem("block")
+                .action(action)
+                .seqnum(seqnum)
+                .build()?;
+
+            sender.send_device(None, &mut device)?;
+        }
+
+        // the windows did not elapse yet
+        assert!(coalescer.try_next_event()?.is_none());
+        assert_eq!(coalescer.pending_len(), 4);
+        assert_eq!(coalescer.coalesced(), 2);
+
+        // held events do not make the descriptor readable, poll until the first deadline
+        let timeout = coalescer.next_timeout().unwrap_or_default();
+        assert!(timeout > time::Duration::ZERO && timeout <= coalescer.window());
+        let mut fds = [libc::pollfd {
+            fd: coalescer.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        }];
+        // SAFETY: the descriptor is valid, and `fds` references one valid `pollfd`.
+        let ready = unsafe { libc::poll(fds.as_mut_ptr(), 1, timeout.as_millis() as i32 + 1) };
+        assert_eq!(ready, 0);
+        assert!(coalescer
+            .next_deadline()
+            .is_some_and(|deadline| deadline <= time::Instant::now()));
+        assert_eq!(coalescer.try_next_event()?.map(|d| d.seqnum()), Some(4));
+
+        let mut events = Vec::new();
+        while let Some(device) = coalescer.next_event_timeout(time::Duration::from_secs(1))? {
+            events.push(format!(
+                "{} {} {}",
+                device.action(),
+                device.sysname(),
+                device.seqnum()
+            ));
+            if coalescer.pending_len() == 0 {
+                break;
+            }
+        }
+
+        assert_eq!(events, ["add sdb 3", "remove sda 5", "change sda 6"]);
+        assert!(coalescer.flush().is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,262 @@
+use std::sync::Arc;
+
+use crate::metrics::{self, Metric};
+use crate::{DeviceAction, Error, Result, Udev, UdevDevice, UdevMonitor};
+
+/// Borrowed view of a device event, over the nul-separated `KEY=value` properties of the message.
+///
+/// Created by [UdevMonitor::receive_device_into]. Properties are parsed on access, without
+/// allocating, so high event-rate consumers can inspect events, and only construct an
+/// [UdevDevice] for the ones they keep, with [to_device](Self::to_device).
+///
+/// Properties with values that are not valid UTF-8 are skipped.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct UdevDeviceRef<'b> {
+    buf: &'b [u8],
+    is_initialized: bool,
+}
+
+impl<'b> UdevDeviceRef<'b> {
+    /// Creates a new [UdevDeviceRef] from the nul-separated `KEY=value` properties of an event.
+    pub const fn new(buf: &'b [u8], is_initialized: bool) -> Self {
+        Self {
+            buf,
+            is_initialized,
+        }
+    }
+
+    /// Gets the raw nul-separated `KEY=value` properties.
+    pub const fn as_bytes(&self) -> &'b [u8] {
+        self.buf
+    }
+
+    /// Gets an iterator over the `(key, value)` pairs of the properties, in message order.
+    pub fn properties(&self) -> impl Iterator<Item = (&'b str, &'b str)> + 'b {
+        self.buf
+            .split(|&b| b == 0)
+            .take_while(|p| !p.is_empty())
+            .filter_map(|p| std::str::from_utf8(p).ok()?.split_once('='))
+    }
+
+    /// Gets the value of the property `key`.
+    ///
+    /// Returns: the property value, or `None` if the event has no such property.
+    pub fn property_value(&self, key: &str) -> Option<&'b str> {
+        self.properties().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+
+    /// Gets the kernel action, e.g. `add`, or `remove`.
+    pub fn action(&self) -> &'b str {
+        self.property_value("ACTION").unwrap_or_default()
+    }
+
+    /// Gets the kernel action as a [DeviceAction].
+    pub fn action_type(&self) -> DeviceAction {
+        self.action().into()
+    }
+
+    /// Gets the devpath, e.g. `/devices/virtual/block/loop0`.
+    pub fn devpath(&self) -> &'b str {
+        self.property_value("DEVPATH").unwrap_or_default()
+    }
+
+    /// Gets the subsystem.
+    pub fn subsystem(&self) -> &'b str {
+        self.property_value("SUBSYSTEM").unwrap_or_default()
+    }
+
+    /// Gets the devtype, e.g. `disk`, or `partition`.
+    pub fn devtype(&self) -> &'b str {
+        self.property_value("DEVTYPE").unwrap_or_default()
+    }
+
+    /// Gets the device node name, e.g. `sda`, or `/dev/sda`.
+    pub fn devname(&self) -> Option<&'b str> {
+        self.property_value("DEVNAME")
+    }
+
+    /// Gets the kernel event sequence number.
+    pub fn seqnum(&self) -> u64 {
+        self.property_value("SEQNUM")
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(0)
+    }
+
+    /// Gets whether the event was sent by `udev`, after processing the rules, instead of the
+    /// kernel.
+    pub const fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+
+    /// Gets whether the `TAGS` property of the event contains `tag`.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.property_value("TAGS")
+            .is_some_and(|tags| tags.split(':').any(|t| !t.is_empty() && t == tag))
+    }
+
+    /// Constructs the owned [UdevDevice] of the event.
+    ///
+    /// Returns: `Ok(UdevDevice)` on success, `Err(Error)` if the properties are invalid.
+    pub fn to_device(&self, udev: Arc<Udev>) -> Result<UdevDevice> {
+        let mut device = UdevDevice::new_from_nulstr(udev, self.buf)?;
+
+        if self.is_initialized {
+            device.set_is_initialized(true);
+        }
+
+        Ok(device)
+    }
+}
+
+impl UdevMonitor {
+    /// Receives a device event into the caller-provided `buf`, without allocating.
+    ///
+    /// Like [receive_device](Self::receive_device), but returns an [UdevDeviceRef] borrowing the
+    /// properties from `buf`, instead of an owned [UdevDevice]. Reusing one buffer avoids the
+    /// per-event allocations, e.g. when processing very high event rates:
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{Udev, UdevMonitor};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let mut monitor = UdevMonitor::new_from_netlink(Arc::new(Udev::new()), "udev")?;
+    /// monitor.enable_receiving()?;
+    ///
+    /// let mut buf = [0u8; 8192];
+    /// loop {
+    ///     let event = monitor.receive_device_into(&mut buf)?;
+    ///     println!("{} {}", event.action(), event.devpath());
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// Events larger than `buf` are rejected, `udev` messages are at most 8192 bytes. The
+    /// subsystem, devtype, and tag filters are matched on the borrowed properties. An
+    /// [UdevDevice] is only constructed for the userspace filters, see
+    /// [filter_add_match_sysattr](Self::filter_add_match_sysattr), for invalidating an attached
+    /// [DeviceCache](crate::DeviceCache), and for events held by the event buffer, see
+    /// [start_buffering](Self::start_buffering), which are copied to `buf`.
+    ///
+    /// Returns: `Ok(UdevDeviceRef)` on success, `Err(Error)` otherwise.
+    pub fn receive_device_into<'b>(&mut self, buf: &'b mut [u8]) -> Result<UdevDeviceRef<'b>> {
+        if let Some(mut device) = self.pop_buffered() {
+            let props = device.get_properties_monitor_buf();
+            let len = props.len();
+
+            let dst = buf.get_mut(..len).ok_or(Error::UdevMonitor(format!(
+                "buffer too small for device: {len}"
+            )))?;
+            dst.copy_from_slice(props.as_bytes());
+
+            return Ok(UdevDeviceRef::new(&buf[..len], device.is_initialized()));
+        }
+
+        let accepted = self.receive_accepted(buf, |monitor, props, is_initialized| {
+            let event = UdevDeviceRef::new(props, is_initialized);
+            Ok(monitor.passes_filter_ref(&event)?.then_some(is_initialized))
+        })?;
+
+        match accepted {
+            Some((is_initialized, range)) => Ok(UdevDeviceRef::new(&buf[range], is_initialized)),
+            None => Err(Error::Filtered(
+                "device did not pass filter, no queued devices".into(),
+            )),
+        }
+    }
+
+    // Matches the [UdevMonitor] filters on the borrowed event, and invalidates the device cache.
+    fn passes_filter_ref(&self, event: &UdevDeviceRef<'_>) -> Result<bool> {
+        let needs_device = self.udev.device_cache().is_some()
+            || !self.filter_devpath_list.is_empty()
+            || !self.filter_property_list.is_empty()
+            || !self.filter_sysattr_list.is_empty();
+
+        let mut device = if needs_device {
+            let device = event.to_device(Arc::clone(&self.udev)).map_err(|e| {
+                let err_msg = format!("could not create device: {e}");
+                log::debug!("{err_msg}");
+                metrics::increment(Metric::MonitorParseFailure);
+                Error::UdevMonitor(err_msg)
+            })?;
+
+            // invalidate before filtering, filtered events also make cached devices stale
+            if let Some(cache) = self.udev.device_cache() {
+                cache.invalidate(&device);
+            }
+
+            Some(device)
+        } else {
+            None
+        };
+
+        Ok(
+            self.matches_kernel_filter(event.subsystem(), event.devtype(), |tag| {
+                event.has_tag(tag)
+            }) && device
+                .as_mut()
+                .is_none_or(|device| self.passes_userspace_filter(device)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_receive_device_into() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+        let (mut sender, mut receiver) = UdevMonitor::new_loopback(Arc::clone(&udev))?;
+
+        let mut mock = UdevDevice::builder(Arc::clone(&udev))
+            .devpath("/devices/virtual/mock/mock0")
+            .subsystem("mock")
+            .action("add")
+            .seqnum(42)
+            .tag("seat")
+            .build()?;
+        let mut block = UdevDevice::builder(Arc::clone(&udev))
+            .devpath("/devices/virtual/block/loop0")
+            .subsystem("block")
+            .devtype("disk")
+            .action("change")
+            .build()?;
+
+        let mut buf = [0u8; 8192];
+
+        sender.send_device(None, &mut mock)?;
+        let event = receiver.receive_device_into(&mut buf)?;
+        assert_eq!(event.action_type(), DeviceAction::Add);
+        assert_eq!(event.devpath(), "/devices/virtual/mock/mock0");
+        assert_eq!(event.subsystem(), "mock");
+        assert_eq!(event.seqnum(), 42);
+        assert!(event.is_initialized());
+        assert!(event.has_tag("seat") && !event.has_tag("sea"));
+        assert!(event
+            .properties()
+            .any(|(k, v)| k == "SUBSYSTEM" && v == "mock"));
+        let mut received = event.to_device(Arc::clone(&udev))?;
+        assert_eq!(received, mock);
+        assert_eq!(
+            received.get_properties_monitor_buf(),
+            mock.get_properties_monitor_buf()
+        );
+        assert_eq!(received.get_tags_list_entry(), mock.get_tags_list_entry());
+
+        receiver.filter_add_match_subsystem_devtype("block", "disk")?;
+        sender.send_device(None, &mut mock)?;
+        sender.send_device(None, &mut block)?;
+
+        let event = receiver.receive_device_into(&mut buf)?;
+        assert_eq!(event.subsystem(), "block");
+        assert_eq!(event.devtype(), "disk");
+        assert_eq!(receiver.stats().filter_rejected(), 1);
+
+        // messages larger than the buffer are rejected
+        sender.send_device(None, &mut block)?;
+        assert!(receiver.receive_device_into(&mut buf[..64]).is_err());
+
+        Ok(())
+    }
+}
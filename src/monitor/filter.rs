@@ -0,0 +1,332 @@
+use crate::{Error, Result, UdevDevice, UdevEntry, UdevEntryList, UdevList, UdevMonitor};
+
+impl UdevMonitor {
+    /// Gets a reference to the filter sysattr [UdevList].
+    pub const fn filter_sysattr_list(&self) -> &UdevList {
+        &self.filter_sysattr_list
+    }
+
+    /// Gets a mutable reference to the filter sysattr [UdevList].
+    pub fn filter_sysattr_list_mut(&mut self) -> &mut UdevList {
+        &mut self.filter_sysattr_list
+    }
+
+    /// Sets the filter sysattr [UdevList].
+    pub fn set_filter_sysattr_list<L: Into<UdevEntryList>>(&mut self, list: L) {
+        self.filter_sysattr_list.set_list(list);
+
+        for entry in self.filter_sysattr_list.iter() {
+            // invalid comparisons never match
+            self.match_cache.add_sysattr(entry.value()).ok();
+        }
+    }
+
+    /// Builder function that sets the filter sysattr [UdevList].
+    pub fn with_filter_sysattr_list<L: Into<UdevEntryList>>(mut self, list: L) -> Self {
+        self.set_filter_sysattr_list(list);
+        self
+    }
+
+    /// Gets a reference to the filter devpath [UdevList].
+    pub const fn filter_devpath_list(&self) -> &UdevList {
+        &self.filter_devpath_list
+    }
+
+    /// Gets a mutable reference to the filter devpath [UdevList].
+    pub fn filter_devpath_list_mut(&mut self) -> &mut UdevList {
+        &mut self.filter_devpath_list
+    }
+
+    /// Sets the filter devpath [UdevList].
+    pub fn set_filter_devpath_list<L: Into<UdevEntryList>>(&mut self, list: L) {
+        self.filter_devpath_list.set_list(list);
+
+        for entry in self.filter_devpath_list.iter() {
+            self.match_cache.add_glob(entry.name());
+        }
+    }
+
+    /// Builder function that sets the filter devpath [UdevList].
+    pub fn with_filter_devpath_list<L: Into<UdevEntryList>>(mut self, list: L) -> Self {
+        self.set_filter_devpath_list(list);
+        self
+    }
+
+    /// Gets a reference to the filter property [UdevList].
+    pub const fn filter_property_list(&self) -> &UdevList {
+        &self.filter_property_list
+    }
+
+    /// Gets a mutable reference to the filter property [UdevList].
+    pub fn filter_property_list_mut(&mut self) -> &mut UdevList {
+        &mut self.filter_property_list
+    }
+
+    /// Sets the filter property [UdevList].
+    pub fn set_filter_property_list<L: Into<UdevEntryList>>(&mut self, list: L) {
+        self.filter_property_list.set_list(list);
+
+        for entry in self.filter_property_list.iter() {
+            self.match_cache.add_glob(entry.value());
+        }
+    }
+
+    /// Builder function that sets the filter property [UdevList].
+    pub fn with_filter_property_list<L: Into<UdevEntryList>>(mut self, list: L) -> Self {
+        self.set_filter_property_list(list);
+        self
+    }
+
+    /// Adds a filter matching devices by a sys attribute value.
+    ///
+    /// The kernel socket filter cannot match sys attributes, so the filter is evaluated in
+    /// userspace by [passes_filter](Self::passes_filter), after the subsystem, and tag filters.
+    ///
+    /// The `value` is a glob pattern, matched like
+    /// [UdevEnumerate::add_match_property](crate::UdevEnumerate::add_match_property). An empty
+    /// `value` matches any device with the attribute. A device passes if it matches all sysattr
+    /// filters.
+    ///
+    /// Attributes are read from `sys` when the event is received, so devices that were already
+    /// removed, e.g. on `remove` events, do not pass the filter.
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{Udev, UdevMonitor};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let udev = Arc::new(Udev::new());
+    /// let mut monitor = UdevMonitor::new_from_netlink(udev, "udev")?;
+    ///
+    /// monitor.filter_add_match_subsystem_devtype("block", "disk")?;
+    /// monitor.filter_add_match_sysattr("removable", "1")?;
+    /// monitor.enable_receiving()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns: `Ok(&UdevEntry)` on success, `Err(Error)` if the sysattr is empty, or the value
+    /// is an invalid pattern.
+    pub fn filter_add_match_sysattr(&mut self, sysattr: &str, value: &str) -> Result<&UdevEntry> {
+        if sysattr.is_empty() {
+            Err(Error::UdevMonitor("empty sysattr filter".into()))
+        } else {
+            validate_pattern(value)?;
+            // invalid comparisons never match
+            self.match_cache.add_sysattr(value).ok();
+
+            self.filter_sysattr_list
+                .add_entry(sysattr, value)
+                .ok_or(Error::UdevMonitor(
+                    "unable to add entry to filter sysattr list".into(),
+                ))
+        }
+    }
+
+    /// Adds a filter matching devices by a devpath glob pattern, e.g. `/devices/pci*/usb*`.
+    ///
+    /// Evaluated in userspace by [passes_filter](Self::passes_filter), after the subsystem, and
+    /// tag filters. Like enumerate matching, `*` also matches `/`. A device passes if it matches
+    /// any devpath filter.
+    ///
+    /// Returns: `Ok(&UdevEntry)` on success, `Err(Error)` if the pattern is empty, or invalid.
+    pub fn filter_add_match_devpath_glob(&mut self, pattern: &str) -> Result<&UdevEntry> {
+        if pattern.is_empty() {
+            Err(Error::UdevMonitor("empty devpath filter".into()))
+        } else {
+            validate_pattern(pattern)?;
+            self.match_cache.add_glob(pattern);
+
+            self.filter_devpath_list
+                .add_entry(pattern, "")
+                .ok_or(Error::UdevMonitor(
+                    "unable to add entry to filter devpath list".into(),
+                ))
+        }
+    }
+
+    /// Adds a filter matching devices by a property value, e.g. `ID_FS_TYPE` and `ext*`.
+    ///
+    /// Evaluated in userspace by [passes_filter](Self::passes_filter), on the properties of the
+    /// received event, after the subsystem, and tag filters. The `value` is a glob pattern, and an
+    /// empty `value` matches any device with the property. Like
+    /// [UdevEnumerate::add_match_property](crate::UdevEnumerate::add_match_property), a device
+    /// passes if it matches any property filter.
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{Udev, UdevMonitor};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let udev = Arc::new(Udev::new());
+    /// let mut monitor = UdevMonitor::new_from_netlink(udev, "udev")?;
+    ///
+    /// monitor.filter_add_match_subsystem_devtype("block", "partition")?;
+    /// monitor.filter_add_match_property("ID_FS_TYPE", "ext*")?;
+    /// monitor.enable_receiving()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns: `Ok(&UdevEntry)` on success, `Err(Error)` if the key is empty, or the value is
+    /// an invalid pattern.
+    pub fn filter_add_match_property(&mut self, key: &str, value: &str) -> Result<&UdevEntry> {
+        if key.is_empty() {
+            Err(Error::UdevMonitor("empty property filter".into()))
+        } else {
+            validate_pattern(value)?;
+            self.match_cache.add_glob(value);
+
+            self.filter_property_list
+                .add_entry(key, value)
+                .ok_or(Error::UdevMonitor(
+                    "unable to add entry to filter property list".into(),
+                ))
+        }
+    }
+
+    // Matches the filters the kernel socket filter cannot evaluate, with the patterns compiled
+    // when the filters were added.
+    pub(super) fn passes_userspace_filter(&self, device: &mut UdevDevice) -> bool {
+        let devpath_matched = self.filter_devpath_list.is_empty()
+            || self.filter_devpath_list.iter().any(|entry| {
+                self.match_cache
+                    .glob(entry.name())
+                    .matches(device.devpath())
+            });
+
+        let property_matched = self.filter_property_list.is_empty()
+            || self.filter_property_list.iter().any(|entry| {
+                device
+                    .get_property_value(entry.name())
+                    .is_some_and(|value| {
+                        entry.value().is_empty()
+                            || self.match_cache.glob(entry.value()).matches(value)
+                    })
+            });
+
+        devpath_matched
+            && property_matched
+            && self.filter_sysattr_list.iter().all(|entry| {
+                device.match_sysattr_value(entry.name(), &self.match_cache.sysattr(entry.value()))
+            })
+    }
+}
+
+fn validate_pattern(pattern: &str) -> Result<()> {
+    glob::Pattern::new(pattern)
+        .map(|_| ())
+        .map_err(|err| Error::UdevMonitor(format!("invalid filter pattern {pattern}: {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FixtureDevice, SysfsFixture};
+    use crate::Udev;
+    use std::borrow::Cow;
+    use std::sync::Arc;
+
+    // Removable, and fixed disks, with sys attributes for the userspace filters.
+    fn fixture() -> Result<SysfsFixture> {
+        SysfsFixture::new()?.with_devices([
+            FixtureDevice::new("/devices/virtual/block/sdz", "block")
+                .with_devtype("disk")
+                .with_sysattr("removable", "1\n")
+                .with_sysattr("size", "1024\n"),
+            FixtureDevice::new("/devices/virtual/block/sdy", "block")
+                .with_devtype("disk")
+                .with_sysattr("removable", "0\n"),
+        ])
+    }
+
+    #[test]
+    fn test_userspace_filter() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+        let mut monitor = UdevMonitor::new(Arc::clone(&udev))?;
+
+        let mut usb = UdevDevice::new(Arc::clone(&udev))
+            .with_devpath("/devices/pci0000:00/0000:00:14.0/usb1/1-2")
+            .with_subsystem("usb");
+        let mut mem = UdevDevice::new(Arc::clone(&udev))
+            .with_devpath("/devices/virtual/mem/null")
+            .with_subsystem("mem");
+
+        assert!(monitor.passes_filter(&mut usb));
+
+        monitor.filter_add_match_devpath_glob("/devices/pci*/usb*")?;
+        monitor.filter_add_match_devpath_glob("/devices/platform/*")?;
+        assert!(monitor.passes_filter(&mut usb));
+        assert!(!monitor.passes_filter(&mut mem));
+
+        assert!(monitor.filter_add_match_devpath_glob("").is_err());
+        assert!(monitor.filter_add_match_devpath_glob("/devices/[").is_err());
+        assert!(monitor.filter_add_match_sysattr("", "1").is_err());
+
+        // devices without a syspath have no sys attributes
+        monitor.filter_add_match_sysattr("removable", "")?;
+        assert!(!monitor.passes_filter(&mut usb));
+
+        monitor.filter_remove().ok();
+        assert!(monitor.filter_devpath_list().is_empty());
+        assert!(monitor.filter_property_list().is_empty());
+        assert!(monitor.filter_sysattr_list().is_empty());
+
+        // the compiled patterns are removed with the filters
+        let devpath = monitor.match_cache.glob("/devices/pci*/usb*");
+        assert!(matches!(devpath, Cow::Owned(_)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_property_filter() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+        let mut monitor = UdevMonitor::new(Arc::clone(&udev))?;
+
+        let partition = |fs_type: &str| {
+            let mut dev = UdevDevice::new(Arc::clone(&udev))
+                .with_devpath("/devices/virtual/block/sdz/sdz1")
+                .with_subsystem("block")
+                .with_devtype("partition");
+            if !fs_type.is_empty() {
+                dev.add_property("ID_FS_TYPE", fs_type);
+            }
+            dev
+        };
+
+        monitor.filter_add_match_subsystem_devtype("block", "partition")?;
+        monitor.filter_add_match_property("ID_FS_TYPE", "ext*")?;
+        monitor.filter_add_match_property("ID_FS_TYPE", "xfs")?;
+        assert_eq!(monitor.filter_property_list().len(), 2);
+
+        assert!(monitor.passes_filter(&mut partition("ext4")));
+        assert!(monitor.passes_filter(&mut partition("xfs")));
+        assert!(!monitor.passes_filter(&mut partition("vfat")));
+        assert!(!monitor.passes_filter(&mut partition("")));
+
+        // subsystem filters still apply
+        let mut disk = partition("ext4").with_devtype("disk");
+        assert!(!monitor.passes_filter(&mut disk));
+
+        assert!(monitor.filter_add_match_property("", "ext4").is_err());
+        assert!(monitor
+            .filter_add_match_property("ID_FS_TYPE", "[")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sysattr_filter() -> Result<()> {
+        let fixture = fixture()?;
+
+        let mut monitor = UdevMonitor::new(fixture.udev())?;
+        monitor.filter_add_match_subsystem_devtype("block", "disk")?;
+        monitor.filter_add_match_sysattr("removable", "1")?;
+        monitor.filter_add_match_sysattr("size", "1*")?;
+
+        assert!(monitor.passes_filter(&mut fixture.device("/devices/virtual/block/sdz")?));
+        assert!(!monitor.passes_filter(&mut fixture.device("/devices/virtual/block/sdy")?));
+
+        Ok(())
+    }
+}
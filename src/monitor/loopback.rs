@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use crate::{Result, Udev, UdevMonitor, UdevSocket};
+
+impl UdevMonitor {
+    /// Creates a connected pair of sender, and receiver, [UdevMonitor]s.
+    ///
+    /// Devices passed to [send_device](Self::send_device) on the sender are received by the
+    /// receiver, through the same serialization, and filter path as events from the `udev`
+    /// daemon. Useful to test monitor consumers with synthetic devices, without real hardware:
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{Udev, UdevDevice, UdevMonitor};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let udev = Arc::new(Udev::new());
+    /// let (mut sender, mut receiver) = UdevMonitor::new_loopback(Arc::clone(&udev))?;
+    ///
+    /// let mut device = UdevDevice::new(udev);
+    /// device.add_property("ACTION", "add");
+    /// device.add_property("DEVPATH", "/devices/virtual/mock/mock0");
+    /// device.add_property("SUBSYSTEM", "mock");
+    /// device.add_property("SEQNUM", "1");
+    ///
+    /// sender.send_device(None, &mut device)?;
+    /// let received = receiver.receive_device()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Both monitors use unicast netlink sockets, so no system events are received. The receiver
+    /// only accepts messages from the sender, also in unprivileged processes.
+    ///
+    /// Returns: `Ok((sender, receiver))` on success, `Err(Error)` if the sockets cannot be
+    /// created, or bound.
+    pub fn new_loopback(udev: Arc<Udev>) -> Result<(Self, Self)> {
+        let mut receiver = Self::new_loopback_end(Arc::clone(&udev))?;
+        let mut sender = Self::new_loopback_end(udev)?;
+
+        let receiver_pid = receiver.snl.pid()?;
+        let sender_pid = sender.snl.pid()?;
+
+        log::debug!("loopback monitor, sender PID: {sender_pid}, receiver PID: {receiver_pid}");
+
+        receiver.set_snl_trusted_sender(UdevSocket::new_nl(libc::AF_NETLINK, sender_pid, 0));
+        sender.set_snl_destination(UdevSocket::new_nl(libc::AF_NETLINK, receiver_pid, 0));
+
+        Ok((sender, receiver))
+    }
+
+    // Creates a monitor bound to a unicast-only netlink address.
+    fn new_loopback_end(udev: Arc<Udev>) -> Result<Self> {
+        let mut monitor = Self::new(udev)?
+            .with_sock(Self::new_nl_socket()?)
+            .with_snl(UdevSocket::new_nl(libc::AF_NETLINK, 0, 0));
+
+        monitor.enable_receiving()?;
+
+        Ok(monitor)
+    }
+}
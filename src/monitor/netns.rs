@@ -0,0 +1,181 @@
+use std::os::fd::{AsFd, AsRawFd};
+use std::{fs, io, mem};
+
+use crate::{Error, ErrorScope, Result, UdevMonitor};
+
+/// Path of the network namespace of the calling thread.
+const THREAD_NETNS_PATH: &str = "/proc/thread-self/ns/net";
+
+impl UdevMonitor {
+    /// Replaces the [UdevMonitor] socket with a netlink socket in the network namespace `netns`.
+    ///
+    /// Netlink sockets stay in the network namespace they were created in. Container device
+    /// managers can monitor the events of a container, without moving the whole process into it:
+    ///
+    /// ```no_run
+    /// # use std::{fs, sync::Arc};
+    /// # use udevrs::{Udev, UdevMonitor};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let netns = fs::File::open("/proc/1234/ns/net")?;
+    ///
+    /// let mut monitor = UdevMonitor::new_from_netlink(Arc::new(Udev::new()), "kernel")?;
+    /// monitor.set_netns(&netns)?;
+    /// monitor.enable_receiving()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// The calling thread enters `netns` with `setns` to create the socket, and returns to its
+    /// own network namespace afterwards. Entering a network namespace needs `CAP_SYS_ADMIN`.
+    ///
+    /// The socket must be replaced before [enable_receiving](Self::enable_receiving) binds it.
+    ///
+    /// Returns: `Ok(())` on success, `Err(Error)` if the monitor is bound, or entering the
+    /// network namespace fails.
+    pub fn set_netns<F: AsFd>(&mut self, netns: F) -> Result<()> {
+        if self.bound() {
+            return Err(Error::UdevMonitor(
+                "unable to change network namespace, monitor is bound".into(),
+            ));
+        }
+
+        let current = fs::File::open(THREAD_NETNS_PATH)?;
+
+        enter_netns(netns.as_fd().as_raw_fd())?;
+
+        let sock = Self::new_nl_socket();
+
+        if let Err(err) = enter_netns(current.as_raw_fd()) {
+            // the thread is stuck in the other namespace, sockets created later would be too
+            log::error!("unable to return to the original network namespace: {err}");
+            return Err(err);
+        }
+
+        self.set_sock(sock?);
+
+        Ok(())
+    }
+
+    /// Builder function that replaces the socket with a netlink socket in the network namespace
+    /// `netns`.
+    ///
+    /// See [set_netns](Self::set_netns).
+    pub fn with_netns<F: AsFd>(mut self, netns: F) -> Result<Self> {
+        self.set_netns(netns)?;
+        Ok(self)
+    }
+
+    /// Sets whether the bound [UdevMonitor] socket receives multicast messages from all network
+    /// namespaces, with `NETLINK_LISTEN_ALL_NSID`.
+    ///
+    /// Without it, the socket only receives messages from its own network namespace. Messages are
+    /// only received from namespaces that have an ID assigned in the namespace of the socket,
+    /// e.g. with `ip netns set`. Needs `CAP_NET_BROADCAST`.
+    ///
+    /// Returns: `Ok(())` on success, `Err(Error)` if the monitor is not bound, or the option
+    /// cannot be set.
+    pub fn set_listen_all_nsid(&mut self, enable: bool) -> Result<()> {
+        if !self.bound() {
+            return Err(Error::UdevMonitor(
+                "unable to set NETLINK_LISTEN_ALL_NSID, monitor is not bound".into(),
+            ));
+        }
+
+        let val = i32::from(enable);
+
+        // SAFETY: all arguments are valid, and pointers reference valid memory.
+        let err = unsafe {
+            libc::setsockopt(
+                self.sock(),
+                libc::SOL_NETLINK,
+                libc::NETLINK_LISTEN_ALL_NSID,
+                &val as *const i32 as *const _,
+                mem::size_of::<i32>() as u32,
+            )
+        };
+
+        if err < 0 {
+            let errno = io::Error::last_os_error();
+            let err_msg = format!("setting NETLINK_LISTEN_ALL_NSID failed, errno: {errno}");
+            log::error!("{err_msg}");
+            Err(Error::os(ErrorScope::Monitor, err_msg, errno))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn enter_netns(fd: i32) -> Result<()> {
+    // SAFETY: `setns` only reads the file descriptor, and the return value is checked.
+    if unsafe { libc::setns(fd, libc::CLONE_NEWNET) } < 0 {
+        let errno = io::Error::last_os_error();
+        Err(Error::os(
+            ErrorScope::Monitor,
+            format!("unable to enter network namespace: {errno}"),
+            errno,
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Udev, UdevMonitorNetlinkGroup, UdevSocket};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_monitor_netns() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+
+        // an unbound passed socket is bound by `enable_receiving`
+        let sock = UdevMonitor::new_nl_socket()?;
+        let mut monitor = UdevMonitor::new_from_netlink_fd(udev, "kernel", Some(sock))?;
+        assert!(!monitor.bound());
+        assert!(monitor.set_listen_all_nsid(true).is_err());
+
+        monitor.enable_receiving()?;
+        assert!(monitor.bound());
+
+        // the namespace is fixed once the socket is bound
+        let netns = fs::File::open(THREAD_NETNS_PATH)?;
+        assert!(monitor.set_netns(&netns).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "needs CAP_SYS_ADMIN, and CAP_NET_ADMIN"]
+    fn test_monitor_netns_privileged() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+
+        let sock = UdevMonitor::new_nl_socket()?;
+        let mut monitor =
+            UdevMonitor::new_from_netlink_fd(Arc::clone(&udev), "kernel", Some(sock))?;
+
+        let netns = fs::File::open(THREAD_NETNS_PATH)?;
+        monitor.set_netns(&netns)?;
+        monitor.enable_receiving()?;
+        monitor.set_listen_all_nsid(true)?;
+
+        // a bound passed socket keeps its address, and joins the requested group
+        let mut bound = UdevMonitor::new(Arc::clone(&udev))?
+            .with_sock(UdevMonitor::new_nl_socket()?)
+            .with_snl(UdevSocket::new_nl(libc::AF_NETLINK, 0, 0));
+        bound.enable_receiving()?;
+        let pid = bound.snl.pid()?;
+
+        let sock = bound
+            .sock
+            .take()
+            .ok_or(Error::UdevMonitor("no socket".into()))?;
+        let monitor = UdevMonitor::new_from_netlink_fd(udev, "kernel", Some(sock))?;
+
+        assert!(monitor.bound());
+        assert_eq!(monitor.snl.pid()?, pid);
+        assert_eq!(monitor.snl_group(), UdevMonitorNetlinkGroup::Kernel);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+use std::{fmt, sync::Arc};
+
+use crate::{Error, Result, UdevEnumerate, UdevMonitor};
+
+/// Callback run by [OverflowStrategy::Resync] after the monitor receive buffer overflowed.
+///
+/// The [UdevEnumerate] matches the subsystems of the monitor filter, and is not scanned yet.
+pub type OverflowCallback = Box<dyn FnMut(&mut UdevEnumerate) -> Result<()> + Send + Sync>;
+
+/// Represents how a [UdevMonitor] handles a receive buffer overflow (`ENOBUFS`).
+///
+/// The kernel drops events when the socket receive buffer is full, and reports the loss once on
+/// the next receive. The socket stays bound, and later events are received normally.
+#[derive(Default)]
+pub enum OverflowStrategy {
+    /// Return [Error::Overflow] from the receive call.
+    #[default]
+    Fail,
+    /// Log the overflow, and keep receiving devices.
+    Continue,
+    /// Run the callback to re-enumerate the current devices, then keep receiving devices.
+    ///
+    /// Errors returned by the callback are returned from the receive call.
+    Resync(OverflowCallback),
+}
+
+impl OverflowStrategy {
+    /// Creates an [OverflowStrategy::Resync] from the callback.
+    pub fn resync<F>(callback: F) -> Self
+    where
+        F: FnMut(&mut UdevEnumerate) -> Result<()> + Send + Sync + 'static,
+    {
+        Self::Resync(Box::new(callback))
+    }
+
+    /// Gets the [OverflowStrategy] name.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Fail => "fail",
+            Self::Continue => "continue",
+            Self::Resync(_) => "resync",
+        }
+    }
+}
+
+impl fmt::Debug for OverflowStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "OverflowStrategy::{}", self.as_str())
+    }
+}
+
+impl fmt::Display for OverflowStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl UdevMonitor {
+    /// Gets the [OverflowStrategy] of the [UdevMonitor].
+    pub const fn overflow_strategy(&self) -> &OverflowStrategy {
+        &self.overflow_strategy
+    }
+
+    /// Sets the [OverflowStrategy] of the [UdevMonitor].
+    ///
+    /// Resync after overflows instead of failing, e.g. to keep a device list consistent:
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{OverflowStrategy, Udev, UdevMonitor};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let udev = Arc::new(Udev::new());
+    /// let mut monitor = UdevMonitor::new_from_netlink(udev, "udev")?;
+    /// monitor.filter_add_match_subsystem_devtype("block", "")?;
+    ///
+    /// monitor.set_overflow_strategy(OverflowStrategy::resync(|enumerate| {
+    ///     enumerate.scan_devices()?;
+    ///     for entry in enumerate.devices_list().iter() {
+    ///         println!("resync: {}", entry.name());
+    ///     }
+    ///     Ok(())
+    /// }));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_overflow_strategy(&mut self, val: OverflowStrategy) {
+        self.overflow_strategy = val;
+    }
+
+    /// Builder function that sets the [OverflowStrategy] of the [UdevMonitor].
+    pub fn with_overflow_strategy(mut self, val: OverflowStrategy) -> Self {
+        self.set_overflow_strategy(val);
+        self
+    }
+
+    /// Gets the number of receive buffer overflows seen by the [UdevMonitor].
+    ///
+    /// Each overflow loses an unknown number of events.
+    pub const fn overflow_count(&self) -> u64 {
+        self.stats.overflows()
+    }
+
+    // Handles a receive buffer overflow with the current strategy.
+    //
+    // Returns `Err(Error::Overflow)` with the `Fail` strategy, or the resync callback error.
+    pub(super) fn handle_overflow(&mut self, msg: String) -> Result<()> {
+        self.stats_overflow();
+
+        log::warn!(
+            "udev monitor overflow, strategy: {}, {msg}",
+            self.overflow_strategy
+        );
+
+        match &self.overflow_strategy {
+            OverflowStrategy::Fail => Err(Error::Overflow(msg)),
+            OverflowStrategy::Continue => Ok(()),
+            OverflowStrategy::Resync(_) => {
+                let mut enumerate = self.filter_enumerate()?;
+                match &mut self.overflow_strategy {
+                    OverflowStrategy::Resync(callback) => callback(&mut enumerate),
+                    _ => Ok(()),
+                }
+            }
+        }
+    }
+
+    // Creates an enumerate matching the subsystems, and tags of the monitor filter.
+    pub(super) fn filter_enumerate(&self) -> Result<UdevEnumerate> {
+        let mut enumerate = UdevEnumerate::new(Arc::clone(&self.udev));
+
+        for entry in self.filter_subsystem_list.iter() {
+            enumerate.add_match_subsystem(entry.name())?;
+        }
+
+        for entry in self.filter_tag_list.iter() {
+            enumerate.add_match_tag(entry.name())?;
+        }
+
+        Ok(enumerate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Udev;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_overflow_strategy() -> Result<()> {
+        let mut monitor = UdevMonitor::new(Arc::new(Udev::new()))?;
+        monitor.filter_add_match_subsystem_devtype("block", "disk")?;
+
+        assert_eq!(monitor.overflow_strategy().as_str(), "fail");
+        assert!(matches!(
+            monitor.handle_overflow("lost".into()),
+            Err(Error::Overflow(_))
+        ));
+
+        monitor.set_overflow_strategy(OverflowStrategy::Continue);
+        monitor.handle_overflow("lost".into())?;
+
+        let resyncs = Arc::new(AtomicUsize::new(0));
+        let count = Arc::clone(&resyncs);
+        monitor.set_overflow_strategy(OverflowStrategy::resync(move |enumerate| {
+            let subsystems: Vec<&str> = enumerate
+                .subsystem_match_list()
+                .iter()
+                .map(|e| e.name())
+                .collect();
+            assert_eq!(subsystems, ["block"]);
+
+            count.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }));
+
+        monitor.handle_overflow("lost".into())?;
+        assert_eq!(resyncs.load(Ordering::Relaxed), 1);
+        assert_eq!(monitor.overflow_count(), 3);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use std::io;
+
+use crate::{DeviceAction, Error, ErrorScope, Result, UdevDevice, UdevMonitor};
+
+/// Summary of the next device event, without constructing the full [UdevDevice].
+///
+/// Created by [UdevMonitor::peek_next].
+#[repr(C)]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EventSummary {
+    action: String,
+    devpath: String,
+    subsystem: String,
+    seqnum: u64,
+    is_initialized: bool,
+}
+
+impl EventSummary {
+    /// Creates a new [EventSummary] from the nul-separated `KEY=value` properties of an event.
+    ///
+    /// Only the `ACTION`, `DEVPATH`, `SUBSYSTEM`, and `SEQNUM` properties are read.
+    pub fn from_nulstr(buf: &[u8], is_initialized: bool) -> Self {
+        let mut summary = Self {
+            is_initialized,
+            ..Default::default()
+        };
+
+        for prop in buf.split(|&b| b == 0).take_while(|p| !p.is_empty()) {
+            let prop = String::from_utf8_lossy(prop);
+
+            match prop.split_once('=') {
+                Some(("ACTION", val)) => summary.action = val.into(),
+                Some(("DEVPATH", val)) => summary.devpath = val.into(),
+                Some(("SUBSYSTEM", val)) => summary.subsystem = val.into(),
+                Some(("SEQNUM", val)) => summary.seqnum = val.parse::<u64>().unwrap_or(0),
+                _ => (),
+            }
+        }
+
+        summary
+    }
+
+    /// Gets the kernel action, e.g. `add`, or `remove`.
+    pub fn action(&self) -> &str {
+        self.action.as_str()
+    }
+
+    /// Gets the kernel action as a [DeviceAction].
+    pub fn action_type(&self) -> DeviceAction {
+        self.action().into()
+    }
+
+    /// Gets the devpath, e.g. `/devices/virtual/block/loop0`.
+    pub fn devpath(&self) -> &str {
+        self.devpath.as_str()
+    }
+
+    /// Gets the subsystem.
+    pub fn subsystem(&self) -> &str {
+        self.subsystem.as_str()
+    }
+
+    /// Gets the kernel event sequence number.
+    pub const fn seqnum(&self) -> u64 {
+        self.seqnum
+    }
+
+    /// Gets whether the event was sent by `udev`, after processing the rules, instead of the
+    /// kernel.
+    pub const fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl From<&UdevDevice> for EventSummary {
+    fn from(val: &UdevDevice) -> Self {
+        Self {
+            action: val.action().into(),
+            devpath: val.devpath().into(),
+            subsystem: val.subsystem().into(),
+            seqnum: val.seqnum(),
+            is_initialized: val.is_initialized(),
+        }
+    }
+}
+
+impl UdevMonitor {
+    /// Peeks at the next device event, without removing it from the [UdevMonitor].
+    ///
+    /// Only parses the action, devpath, subsystem, and sequence number, so consumers can decide
+    /// whether to construct the full device with [receive_device](Self::receive_device), or
+    /// drop the event with [skip_next](Self::skip_next):
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{Udev, UdevMonitor};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let mut monitor = UdevMonitor::new_from_netlink(Arc::new(Udev::new()), "udev")?;
+    /// monitor.enable_receiving()?;
+    ///
+    /// let event = monitor.peek_next()?;
+    /// if event.devpath().starts_with("/devices/virtual/") {
+    ///     monitor.skip_next()?;
+    /// } else {
+    ///     let device = monitor.receive_device()?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// The monitor filter is not applied to the peeked event, so
+    /// [receive_device](Self::receive_device) skips events that do not pass the filter.
+    /// Messages rejected by the sender checks are removed from the socket.
+    ///
+    /// Returns: `Ok(EventSummary)` on success, `Err(Error)` if no event is pending, or the message
+    /// is invalid.
+    pub fn peek_next(&mut self) -> Result<EventSummary> {
+        if let Some(device) = self.buffered.front() {
+            return Ok(device.into());
+        }
+
+        if !self.has_pending()? {
+            return Err(Error::UdevMonitor("no pending device events".into()));
+        }
+
+        let mut buf = [0u8; 8192];
+
+        match self.receive_message(&mut buf, libc::MSG_PEEK) {
+            Ok((bufpos, buflen, is_initialized)) => Ok(EventSummary::from_nulstr(
+                buf[bufpos..buflen].as_ref(),
+                is_initialized,
+            )),
+            // overflows are reported without a message, so there is nothing to drop
+            Err(err @ Error::Overflow(_)) => Err(err),
+            Err(err) => {
+                // drop the rejected message, so the next call does not peek it again
+                self.skip_next()?;
+                Err(err)
+            }
+        }
+    }
+
+    /// Removes the next device event from the [UdevMonitor], without parsing it.
+    ///
+    /// Returns: `Ok(())` on success, or if no event is pending, `Err(Error)` if the socket cannot
+    /// be read.
+    pub fn skip_next(&mut self) -> Result<()> {
+        if self.pop_buffered().is_some() {
+            return Ok(());
+        }
+
+        let mut byte = [0u8; 1];
+
+        // SAFETY: the buffer references valid memory of the passed length, and truncating the
+        // message discards the rest of it.
+        let ret = unsafe {
+            libc::recv(
+                self.sock(),
+                byte.as_mut_ptr() as *mut _,
+                byte.len(),
+                libc::MSG_DONTWAIT | libc::MSG_TRUNC,
+            )
+        };
+
+        match ret {
+            r if r >= 0 => Ok(()),
+            _ => match io::Error::last_os_error() {
+                err if err.kind() == io::ErrorKind::WouldBlock => Ok(()),
+                err => Err(Error::os(
+                    ErrorScope::Monitor,
+                    format!("unable to skip monitor message: {err}"),
+                    err,
+                )),
+            },
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use crate::UdevMonitor;
+
+/// Default limit of queued messages skipped by one receive call, e.g. devices rejected by the
+/// filter.
+pub const DEFAULT_RECEIVE_RETRY_LIMIT: usize = 1024;
+
+/// Represents the policy of a [UdevMonitor] for accepting received messages.
+///
+/// The default policy matches `libudev`:
+///
+/// - only messages sent by `uid=0` are accepted
+/// - unicast messages are only accepted from the trusted sender, see
+///   [set_snl_trusted_sender](UdevMonitor::set_snl_trusted_sender)
+/// - messages without a `udev` header, sent directly by the kernel, are accepted
+/// - one receive call skips at most [DEFAULT_RECEIVE_RETRY_LIMIT] queued messages
+///
+/// Embedded systems without a `udev` daemon can restrict a `kernel` monitor to kernel messages:
+///
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use udevrs::{ReceivePolicy, Udev, UdevMonitor};
+/// # fn main() -> udevrs::Result<()> {
+/// let mut monitor = UdevMonitor::new_from_netlink(Arc::new(Udev::new()), "kernel")?
+///     .with_receive_policy(ReceivePolicy::new().with_udev_messages(false));
+/// monitor.enable_receiving()?;
+/// # Ok(())
+/// # }
+/// ```
+#[repr(C)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReceivePolicy {
+    allowed_uids: Vec<libc::uid_t>,
+    allow_unprivileged_sender: bool,
+    trusted_senders: Vec<u32>,
+    retry_limit: usize,
+    kernel_messages: bool,
+    udev_messages: bool,
+}
+
+impl ReceivePolicy {
+    /// Creates a new [ReceivePolicy] with the default `libudev` policy.
+    pub const fn new() -> Self {
+        Self {
+            allowed_uids: Vec::new(),
+            allow_unprivileged_sender: false,
+            trusted_senders: Vec::new(),
+            retry_limit: DEFAULT_RECEIVE_RETRY_LIMIT,
+            kernel_messages: true,
+            udev_messages: true,
+        }
+    }
+
+    /// Gets the user IDs of senders accepted in addition to `root`.
+    pub fn allowed_uids(&self) -> &[libc::uid_t] {
+        self.allowed_uids.as_ref()
+    }
+
+    /// Sets the user IDs of senders accepted in addition to `root`.
+    ///
+    /// See [UdevMonitor::set_allowed_uids].
+    pub fn set_allowed_uids(&mut self, uids: &[libc::uid_t]) {
+        self.allowed_uids = uids.to_vec();
+    }
+
+    /// Builder function that sets the user IDs of senders accepted in addition to `root`.
+    pub fn with_allowed_uids(mut self, uids: &[libc::uid_t]) -> Self {
+        self.set_allowed_uids(uids);
+        self
+    }
+
+    /// Gets whether messages from any sender user are accepted.
+    pub const fn allow_unprivileged_sender(&self) -> bool {
+        self.allow_unprivileged_sender
+    }
+
+    /// Sets whether messages from any sender user are accepted.
+    ///
+    /// See [UdevMonitor::set_allow_unprivileged_sender].
+    pub fn set_allow_unprivileged_sender(&mut self, allow: bool) {
+        if allow {
+            log::warn!("accepting monitor messages from unprivileged senders");
+        }
+        self.allow_unprivileged_sender = allow;
+    }
+
+    /// Builder function that sets whether messages from any sender user are accepted.
+    pub fn with_allow_unprivileged_sender(mut self, allow: bool) -> Self {
+        self.set_allow_unprivileged_sender(allow);
+        self
+    }
+
+    /// Gets the netlink port IDs of senders trusted to send unicast messages.
+    pub fn trusted_senders(&self) -> &[u32] {
+        self.trusted_senders.as_ref()
+    }
+
+    /// Sets the netlink port IDs of senders trusted to send unicast messages, in addition to the
+    /// [trusted sender](UdevMonitor::snl_trusted_sender) of the monitor.
+    ///
+    /// Unicast messages from other senders are rejected. The sender user is still checked.
+    pub fn set_trusted_senders(&mut self, pids: &[u32]) {
+        self.trusted_senders = pids.iter().copied().filter(|&pid| pid != 0).collect();
+    }
+
+    /// Builder function that sets the netlink port IDs of trusted unicast senders.
+    pub fn with_trusted_senders(mut self, pids: &[u32]) -> Self {
+        self.set_trusted_senders(pids);
+        self
+    }
+
+    /// Gets the limit of queued messages skipped by one receive call.
+    pub const fn retry_limit(&self) -> usize {
+        self.retry_limit
+    }
+
+    /// Sets the limit of queued messages skipped by one receive call.
+    ///
+    /// Messages are skipped if they are rejected by the filter, or by the policy, while more
+    /// messages are queued. After the limit, the receive call returns an error, instead of
+    /// blocking the caller on a flood of unwanted messages. The limit is at least one.
+    pub fn set_retry_limit(&mut self, limit: usize) {
+        self.retry_limit = limit.max(1);
+    }
+
+    /// Builder function that sets the limit of queued messages skipped by one receive call.
+    pub fn with_retry_limit(mut self, limit: usize) -> Self {
+        self.set_retry_limit(limit);
+        self
+    }
+
+    /// Gets whether messages sent directly by the kernel, without a `udev` header, are accepted.
+    pub const fn kernel_messages(&self) -> bool {
+        self.kernel_messages
+    }
+
+    /// Sets whether messages sent directly by the kernel, without a `udev` header, are accepted.
+    pub fn set_kernel_messages(&mut self, accept: bool) {
+        self.kernel_messages = accept;
+    }
+
+    /// Builder function that sets whether messages sent directly by the kernel are accepted.
+    pub fn with_kernel_messages(mut self, accept: bool) -> Self {
+        self.set_kernel_messages(accept);
+        self
+    }
+
+    /// Gets whether messages with a `udev` header, sent by a `udev` daemon, are accepted.
+    pub const fn udev_messages(&self) -> bool {
+        self.udev_messages
+    }
+
+    /// Sets whether messages with a `udev` header, sent by a `udev` daemon, are accepted.
+    ///
+    /// Rejecting `udev` messages on a `kernel` monitor only accepts messages the kernel sent,
+    /// with the sender port ID `0`, which unprivileged processes cannot spoof.
+    pub fn set_udev_messages(&mut self, accept: bool) {
+        self.udev_messages = accept;
+    }
+
+    /// Builder function that sets whether messages with a `udev` header are accepted.
+    pub fn with_udev_messages(mut self, accept: bool) -> Self {
+        self.set_udev_messages(accept);
+        self
+    }
+
+    // Checks the sender user ID from the message credentials.
+    pub(super) fn sender_allowed(&self, uid: libc::uid_t, trusted_unicast: bool) -> bool {
+        // SAFETY: `geteuid` is always successful, and has no side effects.
+        let euid = unsafe { libc::geteuid() };
+
+        // unprivileged processes may also receive from a trusted sender of the same user,
+        // e.g. a loopback monitor pair
+        uid == 0
+            || self.allow_unprivileged_sender
+            || self.allowed_uids.contains(&uid)
+            || (trusted_unicast && uid == euid)
+    }
+
+    // Checks whether a message of the format is accepted, `is_udev` for messages with a header.
+    pub(super) fn format_allowed(&self, is_udev: bool) -> bool {
+        if is_udev {
+            self.udev_messages
+        } else {
+            self.kernel_messages
+        }
+    }
+}
+
+impl Default for ReceivePolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UdevMonitor {
+    /// Gets the [ReceivePolicy] of the [UdevMonitor].
+    pub const fn receive_policy(&self) -> &ReceivePolicy {
+        &self.receive_policy
+    }
+
+    /// Gets a mutable reference to the [ReceivePolicy] of the [UdevMonitor].
+    pub fn receive_policy_mut(&mut self) -> &mut ReceivePolicy {
+        &mut self.receive_policy
+    }
+
+    /// Sets the [ReceivePolicy] of the [UdevMonitor].
+    pub fn set_receive_policy(&mut self, policy: ReceivePolicy) {
+        self.receive_policy = policy;
+    }
+
+    /// Builder function that sets the [ReceivePolicy] of the [UdevMonitor].
+    pub fn with_receive_policy(mut self, policy: ReceivePolicy) -> Self {
+        self.set_receive_policy(policy);
+        self
+    }
+
+    // Checks whether the netlink sender of a unicast message is trusted.
+    pub(super) fn unicast_trusted(&self, pid: u32) -> bool {
+        let trusted_pid = self.snl_trusted_sender.pid().unwrap_or(0);
+
+        pid != 0 && (pid == trusted_pid || self.receive_policy.trusted_senders.contains(&pid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Result, Udev, UdevDevice, UdevSocket};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_receive_policy() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+
+        let policy = ReceivePolicy::new();
+        assert_eq!(policy, ReceivePolicy::default());
+        assert_eq!(policy.retry_limit(), DEFAULT_RECEIVE_RETRY_LIMIT);
+        assert!(policy.format_allowed(true));
+        assert!(policy.format_allowed(false));
+
+        let policy = ReceivePolicy::new()
+            .with_retry_limit(0)
+            .with_trusted_senders(&[0, 4242])
+            .with_udev_messages(false);
+        assert_eq!(policy.retry_limit(), 1);
+        assert_eq!(policy.trusted_senders(), [4242]);
+        assert!(!policy.format_allowed(true));
+        assert!(policy.format_allowed(false));
+
+        let monitor = UdevMonitor::new(Arc::clone(&udev))?
+            .with_snl_trusted_sender(UdevSocket::new_nl(libc::AF_NETLINK, 7, 0))
+            .with_receive_policy(policy);
+        assert!(monitor.unicast_trusted(7));
+        assert!(monitor.unicast_trusted(4242));
+        assert!(!monitor.unicast_trusted(8));
+        assert!(!monitor.unicast_trusted(0));
+
+        // a policy rejecting `udev` messages drops the events of a loopback pair
+        let (mut sender, mut receiver) = UdevMonitor::new_loopback(Arc::clone(&udev))?;
+        receiver.receive_policy_mut().set_udev_messages(false);
+
+        let mut device = UdevDevice::builder(udev)
+            .devpath("/devices/virtual/mock/mock0")
+            .subsystem("mock")
+            .action("add")
+            .build()?;
+        sender.send_device(None, &mut device)?;
+
+        assert!(receiver.receive_device().is_err());
+        assert_eq!(receiver.stats().sender_rejected(), 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,337 @@
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+use std::{fs, path, time};
+
+use crate::{Error, Result, Udev, UdevDevice, UdevMonitor};
+
+use super::parse_message;
+
+/// Length of the header preceding every message in a capture file.
+///
+/// The header holds the receive timestamp in microseconds since the UNIX epoch (`u64`), and the
+/// message length (`u32`), both little-endian.
+pub const RECORD_HEADER_LEN: usize = 12;
+/// Maximum length of a recorded message.
+const RECORD_MESSAGE_MAX: usize = 8192;
+
+impl UdevMonitor {
+    /// Appends every message received by the [UdevMonitor] to the capture file at `path`.
+    ///
+    /// Messages are recorded as received from the socket, before the sender checks, and the
+    /// monitor filter are applied, so the capture also holds rejected messages. Captures are read
+    /// back with [UdevEventReplay]:
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{Udev, UdevMonitor};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let udev = Arc::new(Udev::new());
+    /// let mut monitor = UdevMonitor::new_from_netlink(udev, "udev")?;
+    ///
+    /// monitor.record_to("/tmp/udev-events.cap")?;
+    /// monitor.enable_receiving()?;
+    ///
+    /// loop {
+    ///     let device = monitor.receive_device()?;
+    ///     println!("{} {}", device.action(), device.devpath());
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// Closes any previous capture file of the monitor. Failures to write a message are logged,
+    /// and do not affect receiving devices.
+    ///
+    /// Returns: `Ok(())` on success, `Err(Error)` if the file cannot be opened.
+    pub fn record_to<P: AsRef<path::Path>>(&mut self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|err| {
+                Error::UdevMonitor(format!(
+                    "unable to open capture file {}: {err}",
+                    path.display()
+                ))
+            })?;
+
+        self.recorder = Some(file);
+
+        Ok(())
+    }
+
+    /// Stops recording received messages, and closes the capture file.
+    pub fn stop_recording(&mut self) {
+        self.recorder = None;
+    }
+
+    /// Gets whether the [UdevMonitor] records received messages.
+    pub const fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    // Appends the raw message to the capture file, if recording.
+    pub(super) fn record_message(&mut self, msg: &[u8]) {
+        let Some(file) = self.recorder.as_mut() else {
+            return;
+        };
+
+        let record = UdevEventRecord::new(time::SystemTime::now(), msg.to_vec());
+
+        if let Err(err) = file.write_all(record.to_bytes().as_ref()) {
+            log::warn!("unable to record monitor message: {err}");
+        }
+    }
+}
+
+/// Raw netlink message captured by [UdevMonitor::record_to].
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct UdevEventRecord {
+    timestamp: time::SystemTime,
+    payload: Vec<u8>,
+}
+
+impl UdevEventRecord {
+    /// Creates a new [UdevEventRecord].
+    pub const fn new(timestamp: time::SystemTime, payload: Vec<u8>) -> Self {
+        Self { timestamp, payload }
+    }
+
+    /// Gets the time the message was received.
+    pub const fn timestamp(&self) -> time::SystemTime {
+        self.timestamp
+    }
+
+    /// Gets the raw message, including the `udev`, or kernel, header.
+    pub fn payload(&self) -> &[u8] {
+        self.payload.as_ref()
+    }
+
+    /// Parses the message into an [UdevDevice].
+    ///
+    /// Returns: `Ok(UdevDevice)` on success, `Err(Error)` if the message is invalid.
+    pub fn to_device(&self, udev: Arc<Udev>) -> Result<UdevDevice> {
+        let (bufpos, buflen, is_initialized) = parse_message(self.payload(), self.payload.len())?;
+
+        let mut device =
+            UdevDevice::new_from_nulstr(udev, self.payload[bufpos..buflen].as_ref())
+                .map_err(|err| Error::UdevMonitor(format!("could not create device: {err}")))?;
+
+        if is_initialized {
+            device.set_is_initialized(true);
+        }
+
+        Ok(device)
+    }
+
+    /// Encodes the [UdevEventRecord] in the capture file format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let usec = self
+            .timestamp
+            .duration_since(time::UNIX_EPOCH)
+            .map(|d| d.as_micros() as u64)
+            .unwrap_or(0);
+
+        let mut bytes = Vec::with_capacity(RECORD_HEADER_LEN + self.payload.len());
+        bytes.extend_from_slice(usec.to_le_bytes().as_ref());
+        bytes.extend_from_slice((self.payload.len() as u32).to_le_bytes().as_ref());
+        bytes.extend_from_slice(self.payload());
+
+        bytes
+    }
+
+    /// Reads the next [UdevEventRecord] from a capture file.
+    ///
+    /// Returns: `Ok(Some(UdevEventRecord))` on success, `Ok(None)` at the end of the file,
+    /// `Err(Error)` if the record is truncated, or invalid.
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Option<Self>> {
+        let mut header = [0u8; RECORD_HEADER_LEN];
+
+        match reader.read_exact(header.as_mut()) {
+            Ok(()) => (),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err.into()),
+        }
+
+        let usec = u64::from_le_bytes(header[..8].try_into()?);
+        let len = u32::from_le_bytes(header[8..].try_into()?) as usize;
+
+        if len > RECORD_MESSAGE_MAX {
+            return Err(Error::UdevMonitor(format!(
+                "invalid recorded message length: {len}"
+            )));
+        }
+
+        let mut payload = vec![0u8; len];
+        reader
+            .read_exact(payload.as_mut())
+            .map_err(|err| Error::UdevMonitor(format!("truncated recorded message: {err}")))?;
+
+        Ok(Some(Self::new(
+            time::UNIX_EPOCH + time::Duration::from_micros(usec),
+            payload,
+        )))
+    }
+}
+
+/// Reads the messages captured by [UdevMonitor::record_to] back as [UdevDevice]s.
+///
+/// The replay does not apply a monitor filter. To check a filter against captured events, pass
+/// the devices to [UdevMonitor::passes_filter]:
+///
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use udevrs::{Udev, UdevEventReplay, UdevMonitor};
+/// # fn main() -> udevrs::Result<()> {
+/// let udev = Arc::new(Udev::new());
+/// let mut monitor = UdevMonitor::new(Arc::clone(&udev))?;
+/// monitor.filter_add_match_subsystem_devtype("block", "disk")?;
+///
+/// for device in UdevEventReplay::open(udev, "/tmp/udev-events.cap")? {
+///     let mut device = device?;
+///     let passes = monitor.passes_filter(&mut device);
+///     println!("{} passes: {passes}", device.devpath());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct UdevEventReplay<R: Read = io::BufReader<fs::File>> {
+    udev: Arc<Udev>,
+    reader: R,
+    done: bool,
+}
+
+impl UdevEventReplay {
+    /// Opens the capture file at `path` for replay.
+    ///
+    /// Returns: `Ok(UdevEventReplay)` on success, `Err(Error)` if the file cannot be opened.
+    pub fn open<P: AsRef<path::Path>>(udev: Arc<Udev>, path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = fs::File::open(path).map_err(|err| {
+            Error::UdevMonitor(format!(
+                "unable to open capture file {}: {err}",
+                path.display()
+            ))
+        })?;
+
+        Ok(Self::new(udev, io::BufReader::new(file)))
+    }
+}
+
+impl<R: Read> UdevEventReplay<R> {
+    /// Creates a new [UdevEventReplay] reading captured messages from `reader`.
+    pub const fn new(udev: Arc<Udev>, reader: R) -> Self {
+        Self {
+            udev,
+            reader,
+            done: false,
+        }
+    }
+
+    /// Reads the next raw [UdevEventRecord], without parsing the message.
+    ///
+    /// Returns: `Ok(Some(UdevEventRecord))` on success, `Ok(None)` at the end of the capture,
+    /// `Err(Error)` if the capture is truncated, or invalid.
+    pub fn next_record(&mut self) -> Result<Option<UdevEventRecord>> {
+        if self.done {
+            return Ok(None);
+        }
+
+        UdevEventRecord::read_from(&mut self.reader).inspect(|record| {
+            self.done = record.is_none();
+        })
+    }
+}
+
+impl<R: Read> Iterator for UdevEventReplay<R> {
+    type Item = Result<UdevDevice>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_record() {
+            Ok(record) => record.map(|r| r.to_device(Arc::clone(&self.udev))),
+            Err(err) => {
+                // a damaged capture cannot be resynchronized, so stop after the error
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::SysfsFixture;
+    use crate::UdevSocket;
+
+    #[test]
+    fn test_event_record() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+
+        let msg = b"add@/devices/virtual/mock/mock0\0ACTION=add\0DEVPATH=/devices/virtual/mock/mock0\0SUBSYSTEM=mock\0SEQNUM=5\0";
+        let timestamp = time::UNIX_EPOCH + time::Duration::from_micros(1_700_000_000_123_456);
+        let record = UdevEventRecord::new(timestamp, msg.to_vec());
+
+        let mut capture = record.to_bytes();
+        assert_eq!(capture.len(), RECORD_HEADER_LEN + msg.len());
+        capture.extend_from_slice(record.to_bytes().as_ref());
+
+        let mut replay = UdevEventReplay::new(Arc::clone(&udev), capture.as_slice());
+        assert_eq!(replay.next_record()?, Some(record));
+
+        let device = replay.next().expect("replayed device")?;
+        assert_eq!(device.devpath(), "/devices/virtual/mock/mock0");
+        assert_eq!(device.subsystem(), "mock");
+        assert_eq!(device.seqnum(), 5);
+        assert!(!device.is_initialized());
+        assert!(replay.next().is_none());
+
+        // truncated captures end the replay with an error
+        capture.truncate(capture.len() - 4);
+        let mut replay = UdevEventReplay::new(udev, capture.as_slice());
+        assert!(replay.next().is_some_and(|d| d.is_ok()));
+        assert!(replay.next().is_some_and(|d| d.is_err()));
+        assert!(replay.next().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_rejected() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+        let (mut sender, mut receiver) = UdevMonitor::new_loopback(Arc::clone(&udev))?;
+
+        // a sender the receiver does not trust
+        let (mut untrusted, _) = UdevMonitor::new_loopback(Arc::clone(&udev))?;
+        untrusted.set_snl_destination(UdevSocket::new_nl(libc::AF_NETLINK, receiver.snl.pid()?, 0));
+
+        let fixture = SysfsFixture::new()?;
+        let path = fixture.root().join("record.cap");
+        receiver.record_to(&path)?;
+
+        let mut device = UdevDevice::new(Arc::clone(&udev));
+        device.add_property("ACTION", "add");
+        device.add_property("DEVPATH", "/devices/virtual/mock/mock0");
+        device.add_property("SUBSYSTEM", "mock");
+        device.add_property("SEQNUM", "1");
+
+        untrusted.send_device(None, &mut device)?;
+        assert!(receiver.receive_device().is_err());
+        assert_eq!(receiver.stats().sender_rejected(), 1);
+
+        device.add_property("SEQNUM", "2");
+        sender.send_device(None, &mut device)?;
+        assert_eq!(receiver.receive_device()?.seqnum(), 2);
+
+        receiver.stop_recording();
+
+        // the rejected message is recorded before the accepted one
+        let seqnums = UdevEventReplay::open(udev, &path)?
+            .map(|d| d.map(|d| d.seqnum()))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(seqnums, [1, 2]);
+
+        Ok(())
+    }
+}
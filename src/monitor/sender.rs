@@ -0,0 +1,93 @@
+use crate::UdevMonitor;
+
+impl UdevMonitor {
+    /// Gets the user IDs of senders accepted in addition to `root`.
+    pub fn allowed_uids(&self) -> &[libc::uid_t] {
+        self.receive_policy.allowed_uids()
+    }
+
+    /// Sets the user IDs of senders accepted in addition to `root`.
+    ///
+    /// By default, only messages sent by `uid=0` are accepted, like `libudev`. In user
+    /// namespaces, and test environments, the `udev` daemon may run as a different user, e.g. the
+    /// namespace owner mapped to `nobody`. Adding the daemon user accepts its messages, while
+    /// messages from other unprivileged users are still rejected.
+    ///
+    /// The sender user is checked against the `SCM_CREDENTIALS` of each message, which the
+    /// kernel verifies, so senders cannot claim another user ID.
+    ///
+    /// See also the [ReceivePolicy](crate::ReceivePolicy) of the monitor.
+    pub fn set_allowed_uids(&mut self, uids: &[libc::uid_t]) {
+        self.receive_policy.set_allowed_uids(uids);
+    }
+
+    /// Builder function that sets the user IDs of senders accepted in addition to `root`.
+    ///
+    /// See [set_allowed_uids](Self::set_allowed_uids).
+    pub fn with_allowed_uids(mut self, uids: &[libc::uid_t]) -> Self {
+        self.set_allowed_uids(uids);
+        self
+    }
+
+    /// Gets whether messages from any sender user are accepted.
+    pub const fn allow_unprivileged_sender(&self) -> bool {
+        self.receive_policy.allow_unprivileged_sender()
+    }
+
+    /// Sets whether messages from any sender user are accepted.
+    ///
+    /// **Warning**: any local process can send messages to a netlink multicast group, so enabling
+    /// this lets unprivileged processes inject fake device events. Prefer
+    /// [set_allowed_uids](Self::set_allowed_uids) to accept a known daemon user.
+    ///
+    /// Messages still need sender credentials, and netlink address checks are unchanged, e.g.
+    /// unicast messages are only accepted from the trusted sender.
+    pub fn set_allow_unprivileged_sender(&mut self, allow: bool) {
+        self.receive_policy.set_allow_unprivileged_sender(allow);
+    }
+
+    /// Builder function that sets whether messages from any sender user are accepted.
+    ///
+    /// See [set_allow_unprivileged_sender](Self::set_allow_unprivileged_sender).
+    pub fn with_allow_unprivileged_sender(mut self, allow: bool) -> Self {
+        self.set_allow_unprivileged_sender(allow);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Result, Udev};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_sender_policy() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+        let mut monitor = UdevMonitor::new(udev)?;
+
+        // SAFETY: `geteuid` is always successful, and has no side effects.
+        let euid = unsafe { libc::geteuid() };
+        let other = euid.wrapping_add(1000);
+
+        // secure default: only root, and trusted senders of the same user
+        assert!(monitor.receive_policy().sender_allowed(0, false));
+        assert!(!monitor.receive_policy().sender_allowed(other, false));
+        assert!(monitor.receive_policy().sender_allowed(euid, true));
+        assert!(!monitor.receive_policy().sender_allowed(other, true));
+
+        monitor.set_allowed_uids(&[other]);
+        assert_eq!(monitor.allowed_uids(), [other]);
+        assert!(monitor.receive_policy().sender_allowed(other, false));
+        assert!(!monitor
+            .receive_policy()
+            .sender_allowed(other.wrapping_add(1), false));
+
+        monitor.set_allowed_uids(&[]);
+        monitor.set_allow_unprivileged_sender(true);
+        assert!(monitor.allow_unprivileged_sender());
+        assert!(monitor.receive_policy().sender_allowed(other, false));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
+
+use crate::{Error, Result, UdevDevice, UdevMonitor};
+
+/// Limits the number of messages decoded in one call to [MonitorDecoder::decode_pending].
+const DECODE_PENDING_LIMIT: usize = 1024;
+
+impl UdevMonitor {
+    /// Splits the [UdevMonitor] into a readiness handle, and a decoder.
+    ///
+    /// The [MonitorFd] is a duplicate of the monitor socket, only used to wait for readiness,
+    /// e.g. by registering it with `calloop` or `mio`. The [MonitorDecoder] owns the monitor,
+    /// and decodes the pending messages once the socket is readable.
+    ///
+    /// Both handles refer to the same socket, so readiness is only cleared by decoding.
+    ///
+    /// Returns: `Ok((MonitorFd, MonitorDecoder))` on success, `Err(Error)` if the monitor has no
+    /// socket, or the socket cannot be duplicated.
+    pub fn split(self) -> Result<(MonitorFd, MonitorDecoder)> {
+        let fd = MonitorFd::new(&self)?;
+        Ok((fd, MonitorDecoder::new(self)))
+    }
+}
+
+/// Readiness handle for a [UdevMonitor] socket.
+///
+/// Created by [UdevMonitor::split].
+#[derive(Debug)]
+pub struct MonitorFd {
+    fd: OwnedFd,
+}
+
+impl MonitorFd {
+    fn new(monitor: &UdevMonitor) -> Result<Self> {
+        if monitor.sock() < 0 {
+            return Err(Error::UdevMonitor("udev monitor has no socket".into()));
+        }
+
+        let fd = monitor.as_fd().try_clone_to_owned().map_err(|err| {
+            Error::UdevMonitor(format!("unable to duplicate monitor socket: {err}"))
+        })?;
+
+        Ok(Self { fd })
+    }
+}
+
+impl AsFd for MonitorFd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl AsRawFd for MonitorFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+#[cfg(feature = "mio")]
+impl mio::event::Source for MonitorFd {
+    fn register(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> std::io::Result<()> {
+        mio::unix::SourceFd(&self.as_raw_fd()).register(registry, token, interests)
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> std::io::Result<()> {
+        mio::unix::SourceFd(&self.as_raw_fd()).reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &mio::Registry) -> std::io::Result<()> {
+        mio::unix::SourceFd(&self.as_raw_fd()).deregister(registry)
+    }
+}
+
+/// Decodes messages received by a [UdevMonitor].
+///
+/// Created by [UdevMonitor::split].
+pub struct MonitorDecoder {
+    monitor: UdevMonitor,
+}
+
+impl MonitorDecoder {
+    fn new(monitor: UdevMonitor) -> Self {
+        Self { monitor }
+    }
+
+    /// Gets a reference to the [UdevMonitor].
+    pub const fn monitor(&self) -> &UdevMonitor {
+        &self.monitor
+    }
+
+    /// Gets a mutable reference to the [UdevMonitor], e.g. to update the filters.
+    pub fn monitor_mut(&mut self) -> &mut UdevMonitor {
+        &mut self.monitor
+    }
+
+    /// Converts the [MonitorDecoder] back into the [UdevMonitor].
+    pub fn into_monitor(self) -> UdevMonitor {
+        self.monitor
+    }
+
+    /// Decodes all messages pending on the monitor socket.
+    ///
+    /// Does not block. Messages that are rejected, e.g. from untrusted senders, and devices that
+    /// do not pass the monitor filter are skipped.
+    ///
+    /// Returns: `Ok(Vec<UdevDevice>)` with the decoded devices, empty if nothing is pending.
+    pub fn decode_pending(&mut self) -> Result<Vec<UdevDevice>> {
+        let mut devices = Vec::new();
+
+        for _ in 0..DECODE_PENDING_LIMIT {
+            if !self.monitor.has_pending()? {
+                return Ok(devices);
+            }
+
+            match self.monitor.receive_device_queued() {
+                Ok(Some(device)) => devices.push(device),
+                Ok(None) => return Ok(devices),
+                Err(err) => log::debug!("skipping monitor message: {err}"),
+            }
+        }
+
+        log::debug!("decode limit reached, more messages may be pending");
+
+        Ok(devices)
+    }
+}
@@ -0,0 +1,150 @@
+use crate::UdevMonitor;
+
+/// Event counters of a [UdevMonitor].
+///
+/// Counters start at zero when the monitor is created, and after
+/// [reset_stats](UdevMonitor::reset_stats).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MonitorStats {
+    received: u64,
+    sender_rejected: u64,
+    filter_rejected: u64,
+    kernel_dropped: u64,
+    overflows: u64,
+}
+
+impl MonitorStats {
+    /// Creates a new [MonitorStats].
+    pub const fn new() -> Self {
+        Self {
+            received: 0,
+            sender_rejected: 0,
+            filter_rejected: 0,
+            kernel_dropped: 0,
+            overflows: 0,
+        }
+    }
+
+    /// Gets the number of messages read from the socket, including rejected messages.
+    pub const fn received(&self) -> u64 {
+        self.received
+    }
+
+    /// Gets the number of messages rejected by the sender checks, e.g. from untrusted senders.
+    pub const fn sender_rejected(&self) -> u64 {
+        self.sender_rejected
+    }
+
+    /// Gets the number of devices rejected by the monitor filter.
+    pub const fn filter_rejected(&self) -> u64 {
+        self.filter_rejected
+    }
+
+    /// Gets the number of messages dropped by the kernel, because the receive queue was full.
+    ///
+    /// Reported by the `SO_RXQ_OVFL` ancillary data, on kernels that support it for netlink
+    /// sockets. Stays zero otherwise, see [overflows](Self::overflows).
+    pub const fn kernel_dropped(&self) -> u64 {
+        self.kernel_dropped
+    }
+
+    /// Gets the number of receive buffer overflows (`ENOBUFS`), each losing an unknown number of
+    /// messages.
+    pub const fn overflows(&self) -> u64 {
+        self.overflows
+    }
+
+    /// Gets whether the monitor lost any events, from kernel drops, or overflows.
+    pub const fn has_lost_events(&self) -> bool {
+        self.kernel_dropped > 0 || self.overflows > 0
+    }
+}
+
+impl UdevMonitor {
+    /// Gets the [MonitorStats] of the [UdevMonitor].
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use udevrs::{Udev, UdevMonitor};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let udev = Arc::new(Udev::new());
+    /// let mut monitor = UdevMonitor::new_from_netlink(udev, "udev")?;
+    /// monitor.enable_receiving()?;
+    ///
+    /// let _device = monitor.receive_device()?;
+    ///
+    /// let stats = monitor.stats();
+    /// if stats.has_lost_events() {
+    ///     println!("lost events, dropped: {}, overflows: {}", stats.kernel_dropped(), stats.overflows());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub const fn stats(&self) -> MonitorStats {
+        self.stats
+    }
+
+    /// Resets the [MonitorStats] of the [UdevMonitor] to zero.
+    pub fn reset_stats(&mut self) {
+        self.stats = MonitorStats::new();
+    }
+
+    pub(super) fn stats_received(&mut self) {
+        self.stats.received = self.stats.received.saturating_add(1);
+    }
+
+    pub(super) fn stats_sender_rejected(&mut self) {
+        self.stats.sender_rejected = self.stats.sender_rejected.saturating_add(1);
+    }
+
+    pub(super) fn stats_filter_rejected(&mut self) {
+        self.stats.filter_rejected = self.stats.filter_rejected.saturating_add(1);
+    }
+
+    pub(super) fn stats_overflow(&mut self) {
+        self.stats.overflows = self.stats.overflows.saturating_add(1);
+    }
+
+    // Adds the drops since the last message, from the cumulative `SO_RXQ_OVFL` counter.
+    pub(super) fn stats_kernel_dropped(&mut self, counter: u32) {
+        let dropped = counter.wrapping_sub(self.rxq_ovfl);
+        self.rxq_ovfl = counter;
+        self.stats.kernel_dropped = self.stats.kernel_dropped.saturating_add(u64::from(dropped));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Result, Udev};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_monitor_stats() -> Result<()> {
+        let mut monitor = UdevMonitor::new(Arc::new(Udev::new()))?;
+        assert_eq!(monitor.stats(), MonitorStats::new());
+
+        monitor.stats_received();
+        monitor.stats_received();
+        monitor.stats_sender_rejected();
+        monitor.stats_filter_rejected();
+        monitor.stats_kernel_dropped(3);
+        monitor.stats_kernel_dropped(5);
+
+        let stats = monitor.stats();
+        assert_eq!(stats.received(), 2);
+        assert_eq!(stats.sender_rejected(), 1);
+        assert_eq!(stats.filter_rejected(), 1);
+        assert_eq!(stats.kernel_dropped(), 5);
+        assert_eq!(stats.overflows(), 0);
+        assert!(stats.has_lost_events());
+
+        // the kernel counter is cumulative, so drops before the reset are not counted again
+        monitor.reset_stats();
+        monitor.stats_kernel_dropped(5);
+        assert_eq!(monitor.stats(), MonitorStats::new());
+
+        Ok(())
+    }
+}
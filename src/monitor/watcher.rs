@@ -0,0 +1,330 @@
+use std::collections::{HashSet, VecDeque};
+use std::os::fd::{AsRawFd, RawFd};
+use std::sync::Arc;
+use std::time;
+
+use crate::{DeviceAction, LoadFlags, Result, SysPath, UdevDevice, UdevEnumerate, UdevMonitor};
+
+/// Number of recent event sequence numbers remembered to drop duplicate events.
+const SEEN_SEQNUM_LEN: usize = 256;
+
+/// Device reported by a [DeviceWatcher].
+#[derive(Clone, Debug, PartialEq)]
+pub enum WatchEvent {
+    /// Device that existed when the [DeviceWatcher] was created.
+    Existing(UdevDevice),
+    /// Device event received after the [DeviceWatcher] started listening.
+    Event(UdevDevice),
+}
+
+impl WatchEvent {
+    /// Gets a reference to the [UdevDevice].
+    pub const fn device(&self) -> &UdevDevice {
+        match self {
+            Self::Existing(device) | Self::Event(device) => device,
+        }
+    }
+
+    /// Converts the [WatchEvent] into the [UdevDevice].
+    pub fn into_device(self) -> UdevDevice {
+        match self {
+            Self::Existing(device) | Self::Event(device) => device,
+        }
+    }
+
+    /// Gets whether the device existed when the [DeviceWatcher] was created.
+    pub const fn is_existing(&self) -> bool {
+        matches!(self, Self::Existing(_))
+    }
+}
+
+/// Reports the existing devices, followed by device events, without missing devices that
+/// appear between the scan, and binding the monitor.
+///
+/// The [UdevMonitor] is bound, and starts buffering events, before the existing devices are
+/// enumerated. Events that arrived during the scan are reported after the existing devices.
+///
+/// Devices are tracked by devpath, so an `add` event for a device already reported is dropped,
+/// and so is a `remove` event for a device that was never reported. Events with a sequence
+/// number seen recently are dropped as duplicates.
+///
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use udevrs::{DeviceWatcher, Udev, UdevMonitor, WatchEvent};
+/// # fn main() -> udevrs::Result<()> {
+/// let udev = Arc::new(Udev::new());
+/// let mut monitor = UdevMonitor::new_from_netlink(udev, "udev")?;
+/// monitor.filter_add_match_subsystem_devtype("block", "disk")?;
+///
+/// for event in DeviceWatcher::new(monitor)? {
+///     match event? {
+///         WatchEvent::Existing(disk) => println!("existing: {}", disk.syspath()),
+///         WatchEvent::Event(disk) => println!("{}: {}", disk.action(), disk.syspath()),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct DeviceWatcher {
+    monitor: UdevMonitor,
+    existing: VecDeque<UdevDevice>,
+    pending: VecDeque<UdevDevice>,
+    known: HashSet<String>,
+    seen: VecDeque<u64>,
+}
+
+impl DeviceWatcher {
+    /// Creates a new [DeviceWatcher], enumerating the devices matching the monitor filters.
+    ///
+    /// The subsystem, and tag filters select the enumerated devices, the devtype, sysattr,
+    /// devpath, and property filters are applied to the enumerated devices.
+    ///
+    /// Returns: `Ok(DeviceWatcher)` on success, `Err(Error)` if the monitor cannot be bound, or
+    /// the devices cannot be enumerated.
+    pub fn new(monitor: UdevMonitor) -> Result<Self> {
+        let enumerate = monitor.filter_enumerate()?;
+        Self::with_enumerate(monitor, enumerate)
+    }
+
+    /// Creates a new [DeviceWatcher], reporting the devices found by `enumerate` as existing.
+    ///
+    /// The `enumerate` matches are used as-is, and should select the same devices as the
+    /// monitor filters.
+    ///
+    /// Returns: `Ok(DeviceWatcher)` on success, `Err(Error)` if the monitor cannot be bound, or
+    /// the devices cannot be enumerated.
+    pub fn with_enumerate(mut monitor: UdevMonitor, mut enumerate: UdevEnumerate) -> Result<Self> {
+        monitor.start_buffering()?;
+        enumerate.scan_devices()?;
+
+        let udev = Arc::clone(enumerate.udev());
+        let existing: VecDeque<UdevDevice> = enumerate
+            .devices()
+            .iter()
+            .filter_map(|dev| {
+                // devices can disappear between the scan, and creating the device
+                SysPath::new_in(udev.get_sys_path(), dev.syspath())
+                    .and_then(|path| UdevDevice::new_from_syspath(Arc::clone(&udev), &path))
+                    .map_err(|err| log::debug!("skipping enumerated device: {err}"))
+                    .ok()
+            })
+            .collect();
+
+        let pending = monitor.drain_buffered()?.into();
+
+        let mut watcher = Self {
+            monitor,
+            existing: VecDeque::with_capacity(existing.len()),
+            pending,
+            known: HashSet::with_capacity(existing.len()),
+            seen: VecDeque::with_capacity(SEEN_SEQNUM_LEN),
+        };
+
+        for mut device in existing {
+            if watcher.monitor.passes_existing_filter(&mut device)
+                && watcher.known.insert(device.devpath().to_owned())
+            {
+                watcher.existing.push_back(device);
+            }
+        }
+
+        Ok(watcher)
+    }
+
+    /// Gets a reference to the [UdevMonitor].
+    pub const fn monitor(&self) -> &UdevMonitor {
+        &self.monitor
+    }
+
+    /// Gets a mutable reference to the [UdevMonitor].
+    pub fn monitor_mut(&mut self) -> &mut UdevMonitor {
+        &mut self.monitor
+    }
+
+    /// Converts the [DeviceWatcher] into the [UdevMonitor].
+    ///
+    /// Existing devices, and events not reported yet are discarded.
+    pub fn into_monitor(self) -> UdevMonitor {
+        self.monitor
+    }
+
+    /// Gets the number of existing devices not reported yet.
+    pub fn existing_len(&self) -> usize {
+        self.existing.len()
+    }
+
+    /// Gets the next [WatchEvent], without waiting for device events.
+    ///
+    /// Returns: `Ok(Some(WatchEvent))` if a device is available, `Ok(None)` if no event is
+    /// pending, `Err(Error)` otherwise.
+    pub fn try_next_event(&mut self) -> Result<Option<WatchEvent>> {
+        self.next_event_timeout(time::Duration::ZERO)
+    }
+
+    /// Gets the next [WatchEvent], waiting up to `timeout` for a device event.
+    ///
+    /// Existing devices are returned first, without waiting.
+    ///
+    /// Returns: `Ok(Some(WatchEvent))` if a device is available, `Ok(None)` if the timeout
+    /// elapsed, `Err(Error)` otherwise.
+    pub fn next_event_timeout(&mut self, timeout: time::Duration) -> Result<Option<WatchEvent>> {
+        if let Some(device) = self.existing.pop_front() {
+            return Ok(Some(WatchEvent::Existing(device)));
+        }
+
+        while let Some(device) = self.pending.pop_front() {
+            if let Some(event) = self.track(device) {
+                return Ok(Some(event));
+            }
+        }
+
+        let deadline = time::Instant::now() + timeout;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(time::Instant::now());
+
+            match self.monitor.receive_device_timeout(remaining)? {
+                Some(device) => {
+                    if let Some(event) = self.track(device) {
+                        return Ok(Some(event));
+                    }
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+
+    // Updates the reported devices, and drops duplicate events.
+    fn track(&mut self, device: UdevDevice) -> Option<WatchEvent> {
+        let seqnum = device.seqnum();
+        if seqnum > 0 {
+            if self.seen.contains(&seqnum) {
+                log::debug!("dropping duplicate event, seqnum: {seqnum}");
+                return None;
+            }
+            if self.seen.len() >= SEEN_SEQNUM_LEN {
+                self.seen.pop_front();
+            }
+            self.seen.push_back(seqnum);
+        }
+
+        let devpath = device.devpath().to_owned();
+
+        let report = match device.action_type() {
+            DeviceAction::Add => self.known.insert(devpath),
+            DeviceAction::Remove => self.known.remove(devpath.as_str()),
+            DeviceAction::Move => {
+                self.known.remove(device.devpath_old());
+                self.known.insert(devpath);
+                true
+            }
+            _ => true,
+        };
+
+        if report {
+            Some(WatchEvent::Event(device))
+        } else {
+            log::debug!(
+                "dropping {} event, already reported: {}",
+                device.action(),
+                device.devpath()
+            );
+            None
+        }
+    }
+}
+
+impl Iterator for DeviceWatcher {
+    type Item = Result<WatchEvent>;
+
+    /// Gets the next [WatchEvent], blocking until a device event is received.
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.next_event_timeout(time::Duration::from_secs(60)) {
+                Ok(Some(event)) => return Some(Ok(event)),
+                Ok(None) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+impl AsRawFd for DeviceWatcher {
+    fn as_raw_fd(&self) -> RawFd {
+        self.monitor.as_raw_fd()
+    }
+}
+
+impl UdevMonitor {
+    // Matches enumerated devices against the filters not applied by the enumerate matches.
+    fn passes_existing_filter(&self, device: &mut UdevDevice) -> bool {
+        let entries = self.filter_entries();
+        let devtype_matched = entries.is_empty()
+            || entries.iter().any(|entry| {
+                entry.name() == device.get_subsystem()
+                    && (entry.value().is_empty() || entry.value() == device.get_devtype())
+            });
+
+        // enumerated devices only have the properties of the database entry once loaded
+        if devtype_matched && !self.filter_property_list().is_empty() {
+            device.load(LoadFlags::UEVENT | LoadFlags::DB);
+        }
+
+        devtype_matched && self.passes_userspace_filter(device)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Udev;
+
+    #[test]
+    fn test_device_watcher() -> Result<()> {
+        let udev = Arc::new(Udev::new());
+        let (mut sender, receiver) = UdevMonitor::new_loopback(Arc::clone(&udev))?;
+
+        // no subsystem matches, so there are no existing devices
+        let mut enumerate = UdevEnumerate::new(Arc::clone(&udev));
+        enumerate.add_match_subsystem("udevrs-none")?;
+
+        let mut watcher = DeviceWatcher::with_enumerate(receiver, enumerate)?;
+        assert_eq!(watcher.existing_len(), 0);
+        assert!(watcher.try_next_event()?.is_none());
+
+        for (action, name, seqnum) in [
+            ("add", "null", 1),
+            // already reported
+            ("add", "null", 2),
+            ("add", "zero", 3),
+            // duplicate seqnum
+            ("remove", "zero", 3),
+            // never reported
+            ("remove", "full", 4),
+            ("change", "zero", 5),
+            ("remove", "null", 6),
+        ] {
+            let mut device = UdevDevice::new(Arc::clone(&udev));
+            device.add_property("ACTION", action);
+            device.add_property("DEVPATH", format!("/devices/virtual/mem/{name}").as_str());
+            device.add_property("SUBSYSTEM", "mem");
+            device.add_property("SEQNUM", seqnum.to_string().as_str());
+
+            sender.send_device(None, &mut device)?;
+        }
+
+        let mut events = Vec::new();
+        while let Some(event) = watcher.next_event_timeout(time::Duration::from_millis(100))? {
+            assert!(!event.is_existing());
+            events.push(format!(
+                "{} {}",
+                event.device().action(),
+                event.device().seqnum()
+            ));
+        }
+
+        assert_eq!(events, ["add 1", "add 3", "change 5", "remove 6"]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,478 @@
+//! Management of device node symlinks and permissions.
+//!
+//! Device managers use the helpers to apply the results of rule processing to `/dev`:
+//!
+//! - [create_devlinks] creates the symlinks in the `devlinks_list` of a device
+//! - [remove_devlinks] removes the symlinks, or points them to the next claiming device
+//! - [apply_permissions] sets the mode, and ownership, of the device node
+//!
+//! Several devices may claim the same symlink, e.g. multipath disks. Claims are recorded in a
+//! stack directory for every symlink under `/run/udev/links`, and the symlink points to the
+//! device node with the highest `devlink_priority`.
+//!
+//! ```no_run
+//! # use std::sync::Arc;
+//! # use udevrs::{node, Udev, UdevDevice};
+//! # fn main() -> udevrs::Result<()> {
+//! let udev = Arc::new(Udev::new());
+//! let mut dev = UdevDevice::new_from_subsystem_sysname(udev, "block", "sda")?;
+//!
+//! dev.add_devlink("/dev/disk/by-label/root");
+//! node::apply_permissions(&mut dev)?;
+//! node::create_devlinks(&mut dev)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::os::unix::fs::{self as unix_fs, FileTypeExt, MetadataExt, PermissionsExt};
+use std::path::{Component, Path, PathBuf};
+use std::{fs, io};
+
+use crate::{util, Error, Result, UdevDevice};
+
+/// Default mode of device nodes without an assigned group.
+const DEFAULT_NODE_MODE: u32 = 0o600;
+/// Default mode of device nodes with an assigned group.
+const DEFAULT_GROUP_NODE_MODE: u32 = 0o660;
+
+/// Creates the symlinks in the `devlinks_list` of the [UdevDevice].
+///
+/// Records the claim of the device for every symlink, and points the symlink to the device node
+/// with the highest priority. On equal priorities, the device keeps, or takes over, the symlink.
+///
+/// Returns: `Ok(())` on success, `Err(Error)` if the device has no device node, or a symlink
+/// cannot be created.
+pub fn create_devlinks(dev: &mut UdevDevice) -> Result<()> {
+    update_devlinks(dev, true)
+}
+
+/// Removes the symlinks in the `devlinks_list` of the [UdevDevice].
+///
+/// Drops the claim of the device for every symlink. Symlinks still claimed by other devices
+/// point to the device node with the highest remaining priority, others are removed.
+///
+/// Returns: `Ok(())` on success, `Err(Error)` if the device has no device node, or a symlink
+/// cannot be updated.
+pub fn remove_devlinks(dev: &mut UdevDevice) -> Result<()> {
+    update_devlinks(dev, false)
+}
+
+/// Selects the device owning the symlink `link`, among the `devices` claiming it.
+///
+/// Mirrors the arbitration of the `/run/udev/links` stack directories: the device with the
+/// highest [link_priority](UdevDevice::link_priority) wins. Like `udevd`, equal priorities keep
+/// the device the symlink currently points to, and otherwise go to the first claiming device in
+/// `devices`, like the directory order of the stack directory. Only devices with a device node,
+/// and `link` in the `devlinks_list`, claim the symlink.
+///
+/// The `link` is an absolute path, or relative to the device directory, e.g. `disk/by-label/root`.
+/// The devices are not changed, load their database information first, e.g. with
+/// [link_priority](UdevDevice::link_priority).
+///
+/// Returns: the device owning the symlink, or `None` if no device claims it.
+pub fn select_highest_priority<'d>(
+    devices: &'d [UdevDevice],
+    link: &str,
+) -> Option<&'d UdevDevice> {
+    devices
+        .iter()
+        .filter(|dev| dev.get_devnode().is_some())
+        .filter(|dev| {
+            let dev_path = Path::new(dev.udev().get_dev_path());
+            let slink = dev_path.join(link);
+
+            dev.devlinks_list()
+                .iter()
+                .any(|entry| dev_path.join(entry.name()) == slink)
+        })
+        .fold(None, |best: Option<&UdevDevice>, dev| match best {
+            Some(best)
+                if dev.devlink_priority() < best.devlink_priority()
+                    || (dev.devlink_priority() == best.devlink_priority()
+                        && !holds_devlink(dev, link)) =>
+            {
+                Some(best)
+            }
+            _ => Some(dev),
+        })
+}
+
+/// Sets the mode, and ownership, of the [UdevDevice] device node.
+///
+/// Uses the `devnode_mode`, `devnode_uid`, and `devnode_gid` of the device. Without a mode, the
+/// node gets `0660` if a group is assigned, and `0600` otherwise.
+///
+/// Returns: `Ok(())` on success, `Err(Error)` if the device node is missing, does not match the
+/// device number, or cannot be changed.
+pub fn apply_permissions(dev: &mut UdevDevice) -> Result<()> {
+    let devnode = dev
+        .get_devnode()
+        .map(String::from)
+        .ok_or(Error::UdevNode("device has no devnode".into()))?;
+
+    let meta = fs::symlink_metadata(devnode.as_str())?;
+    let is_block = dev.get_subsystem() == "block";
+
+    let type_matches = if is_block {
+        meta.file_type().is_block_device()
+    } else {
+        meta.file_type().is_char_device()
+    };
+
+    if !type_matches || meta.rdev() != dev.get_devnum() {
+        return Err(Error::UdevNode(format!(
+            "found node with mismatching type, or devnum: {devnode}"
+        )));
+    }
+
+    let (uid, gid) = (dev.devnode_uid(), dev.devnode_gid());
+    let mode = match dev.devnode_mode().bits() {
+        0 if gid > 0 => DEFAULT_GROUP_NODE_MODE,
+        0 => DEFAULT_NODE_MODE,
+        mode => mode,
+    };
+
+    if meta.mode() & 0o7777 != mode {
+        log::debug!("set permissions {devnode}, mode: {mode:#o}");
+        fs::set_permissions(devnode.as_str(), fs::Permissions::from_mode(mode))?;
+    }
+
+    if meta.uid() != uid || meta.gid() != gid {
+        log::debug!("set ownership {devnode}, uid: {uid}, gid: {gid}");
+        unix_fs::lchown(devnode.as_str(), Some(uid), Some(gid))?;
+    }
+
+    Ok(())
+}
+
+fn update_devlinks(dev: &mut UdevDevice, add: bool) -> Result<()> {
+    // load the links from the database, if they have not been loaded yet
+    dev.get_devlinks_list_entry();
+
+    let devnode = dev
+        .get_devnode()
+        .map(PathBuf::from)
+        .ok_or(Error::UdevNode("device has no devnode".into()))?;
+
+    let id = dev.get_id_filename().to_owned();
+    if id.is_empty() {
+        return Err(Error::UdevNode("device has no id filename".into()));
+    }
+
+    let dev_path = dev.udev().get_dev_path();
+    let links_dir = Path::new(dev.udev().get_run_path()).join("links");
+    let links: Vec<String> = dev
+        .devlinks_list()
+        .iter()
+        .map(|e| e.name().into())
+        .collect();
+
+    let node = DevlinkNode {
+        dev_path: Path::new(dev_path),
+        links_dir: links_dir.as_path(),
+        id: id.as_str(),
+        devnode: devnode.as_path(),
+        priority: dev.devlink_priority(),
+    };
+
+    let mut res = Ok(());
+
+    for link in links.iter() {
+        let ret = if add {
+            node.add_link(link.as_str())
+        } else {
+            node.remove_link(link.as_str())
+        };
+
+        if let Err(err) = ret {
+            log::warn!("unable to update devlink {link}: {err}");
+            res = Err(err);
+        }
+    }
+
+    res
+}
+
+// Device node, and claim information, shared by the symlink updates of a device.
+struct DevlinkNode<'a> {
+    dev_path: &'a Path,
+    links_dir: &'a Path,
+    id: &'a str,
+    devnode: &'a Path,
+    priority: i32,
+}
+
+impl DevlinkNode<'_> {
+    fn add_link(&self, link: &str) -> Result<()> {
+        let slink = self.slink_path(link)?;
+        let stack = self.stack_dir(slink.as_path())?;
+
+        fs::create_dir_all(stack.as_path())?;
+
+        let entry = stack.join(self.id);
+        remove_if_exists(entry.as_path())?;
+        unix_fs::symlink(
+            format!("{}:{}", self.priority, self.devnode.display()),
+            entry.as_path(),
+        )?;
+
+        let target = self.find_prioritized(stack.as_path(), true);
+        self.symlink_node(target.as_path(), slink.as_path())
+    }
+
+    fn remove_link(&self, link: &str) -> Result<()> {
+        let slink = self.slink_path(link)?;
+        let stack = self.stack_dir(slink.as_path())?;
+
+        remove_if_exists(stack.join(self.id).as_path())?;
+
+        let target = self.find_prioritized(stack.as_path(), false);
+
+        if target.as_os_str().is_empty() {
+            log::debug!("no reference left, removing {}", slink.display());
+
+            if fs::symlink_metadata(slink.as_path()).is_ok_and(|m| m.file_type().is_symlink()) {
+                fs::remove_file(slink.as_path())?;
+            }
+
+            fs::remove_dir(stack.as_path()).ok();
+            self.remove_empty_parents(slink.as_path());
+
+            Ok(())
+        } else {
+            self.symlink_node(target.as_path(), slink.as_path())
+        }
+    }
+
+    // Resolves the link to an absolute path, and rejects links outside the device directory.
+    fn slink_path(&self, link: &str) -> Result<PathBuf> {
+        let slink = self.dev_path.join(link);
+        let rel = slink.strip_prefix(self.dev_path).unwrap_or(Path::new(""));
+
+        let valid = !rel.as_os_str().is_empty()
+            && rel.components().all(|c| matches!(c, Component::Normal(_)));
+
+        if valid {
+            Ok(slink)
+        } else {
+            Err(Error::UdevNode(format!("invalid devlink: {link}")))
+        }
+    }
+
+    // Gets the stack directory recording the claims on the symlink, e.g.
+    // `/run/udev/links/\x2fdisk\x2fby-label\x2froot`.
+    fn stack_dir(&self, slink: &Path) -> Result<PathBuf> {
+        let rel = slink
+            .strip_prefix(self.dev_path)
+            .map_err(|_| Error::UdevNode(format!("invalid devlink: {}", slink.display())))?;
+
+        let name = format!("/{}", rel.display());
+        Ok(self.links_dir.join(util::path_encode(name.as_str())))
+    }
+
+    // Gets the device node with the highest priority claiming the symlink.
+    //
+    // When adding, the device wins on equal priorities. Returns an empty path if no claims are
+    // left.
+    fn find_prioritized(&self, stack: &Path, add: bool) -> PathBuf {
+        let mut target = if add {
+            Some((self.priority, self.devnode.to_path_buf()))
+        } else {
+            None
+        };
+
+        let mut entries: Vec<(String, i32, PathBuf)> = fs::read_dir(stack)
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.ok())
+            .filter_map(|e| {
+                let name = e.file_name().to_str()?.to_owned();
+                let claim = fs::read_link(e.path()).ok()?;
+                let (prio, node) = claim.to_str()?.split_once(':')?;
+
+                Some((name, prio.parse::<i32>().ok()?, PathBuf::from(node)))
+            })
+            .filter(|(name, _, _)| name != self.id)
+            .collect();
+
+        // read order is unspecified, sort for stable results between updates
+        entries.sort();
+
+        for (_, prio, node) in entries {
+            if target.as_ref().is_none_or(|(best, _)| prio > *best) {
+                target = Some((prio, node));
+            }
+        }
+
+        target.map(|(_, node)| node).unwrap_or_default()
+    }
+
+    // Points the symlink to the device node, replacing an existing symlink atomically.
+    fn symlink_node(&self, node: &Path, slink: &Path) -> Result<()> {
+        let parent = slink.parent().unwrap_or(self.dev_path);
+        let target = relative_path(parent, node);
+
+        match fs::symlink_metadata(slink) {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                if fs::read_link(slink).is_ok_and(|cur| cur == target) {
+                    log::trace!("preserve already existing symlink {}", slink.display());
+                    return Ok(());
+                }
+            }
+            Ok(_) => {
+                return Err(Error::UdevNode(format!(
+                    "conflicting file already exists: {}",
+                    slink.display()
+                )))
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => return Err(err.into()),
+        }
+
+        fs::create_dir_all(parent)?;
+
+        let mut tmp = slink.as_os_str().to_owned();
+        tmp.push(format!(".tmp-{}", self.id));
+        let tmp = PathBuf::from(tmp);
+
+        log::debug!(
+            "creating symlink {} to {}",
+            slink.display(),
+            target.display()
+        );
+
+        remove_if_exists(tmp.as_path())?;
+        unix_fs::symlink(target.as_path(), tmp.as_path())?;
+
+        fs::rename(tmp.as_path(), slink).inspect_err(|_| {
+            fs::remove_file(tmp.as_path()).ok();
+        })?;
+
+        Ok(())
+    }
+
+    // Removes the directories left empty by removing the symlink, stopping at the device
+    // directory.
+    fn remove_empty_parents(&self, slink: &Path) {
+        for dir in slink.ancestors().skip(1) {
+            if dir == self.dev_path || !dir.starts_with(self.dev_path) {
+                break;
+            }
+            if fs::remove_dir(dir).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+// Gets whether the symlink `link` currently points to the device node of the device.
+fn holds_devlink(dev: &UdevDevice, link: &str) -> bool {
+    let slink = Path::new(dev.udev().get_dev_path()).join(link);
+    let (Some(parent), Some(devnode)) = (slink.parent(), dev.get_devnode()) else {
+        return false;
+    };
+
+    fs::read_link(slink.as_path()).is_ok_and(|cur| cur == relative_path(parent, Path::new(devnode)))
+}
+
+// Gets the path to `to`, relative to the `from` directory, e.g. `../../sda`.
+fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let common = from
+        .components()
+        .zip(to.components())
+        .take_while(|(f, t)| f == t)
+        .count();
+
+    let mut rel = PathBuf::new();
+    for _ in from.components().skip(common) {
+        rel.push("..");
+    }
+    rel.extend(to.components().skip(common));
+
+    rel
+}
+
+fn remove_if_exists(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::SysfsFixture;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_devlinks() -> Result<()> {
+        let fixture = SysfsFixture::new()?;
+        let dev_dir = Path::new(fixture.dev_path());
+        let udev = fixture.udev();
+
+        let link = dev_dir.join("disk/by-label/root");
+        let new_dev = |node: &str, id: &str, prio: i32| {
+            let mut dev = UdevDevice::new(Arc::clone(&udev))
+                .with_devnode(dev_dir.join(node).to_str().unwrap_or_default())
+                .with_id_filename(id)
+                .with_devlink_priority(prio)
+                .with_info_loaded(true);
+            dev.add_devlink(link.to_str().unwrap_or_default());
+            dev
+        };
+
+        let mut sda = new_dev("sda", "b8:0", 0);
+        let mut sdb = new_dev("sdb", "b8:16", 10);
+
+        create_devlinks(&mut sda)?;
+        assert_eq!(fs::read_link(link.as_path())?, Path::new("../../sda"));
+
+        // higher priority takes over, and lower priority does not
+        create_devlinks(&mut sdb)?;
+        create_devlinks(&mut sda)?;
+        assert_eq!(fs::read_link(link.as_path())?, Path::new("../../sdb"));
+
+        remove_devlinks(&mut sdb)?;
+        assert_eq!(fs::read_link(link.as_path())?, Path::new("../../sda"));
+
+        remove_devlinks(&mut sda)?;
+        assert!(fs::symlink_metadata(link.as_path()).is_err());
+        assert!(!dev_dir.join("disk").exists());
+
+        let mut escape = new_dev("sdc", "b8:32", 0);
+        escape.devlinks_list_mut().clear();
+        escape.add_devlink("../escape");
+        assert!(create_devlinks(&mut escape).is_err());
+
+        assert!(apply_permissions(&mut sda).is_err());
+
+        let sdc = new_dev("sdc", "b8:32", 10);
+        let devices = [sda, sdb, sdc];
+        let selected =
+            |link: &str| select_highest_priority(&devices, link).map(|d| d.get_id_filename());
+
+        // equal priorities go to the first claiming device
+        assert_eq!(selected("disk/by-label/root"), Some("b8:16"));
+        assert_eq!(selected(link.to_str().unwrap_or_default()), Some("b8:16"));
+        assert_eq!(selected("disk/by-label/other"), None);
+        assert_eq!(
+            select_highest_priority(&devices[..1], "disk/by-label/root")
+                .map(|d| d.get_id_filename()),
+            Some("b8:0")
+        );
+
+        // unless another device holds the symlink, like `udevd`
+        let reversed = [devices[2].clone(), devices[1].clone()];
+        let selected = || {
+            select_highest_priority(&reversed, "disk/by-label/root").map(|d| d.get_id_filename())
+        };
+        assert_eq!(selected(), Some("b8:32"));
+
+        fs::create_dir_all(dev_dir.join("disk/by-label"))?;
+        unix_fs::symlink("../../sdb", link.as_path())?;
+        assert_eq!(selected(), Some("b8:16"));
+
+        Ok(())
+    }
+}
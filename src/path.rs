@@ -0,0 +1,427 @@
+//! Validated `sysfs` device paths.
+//!
+//! [SysPath] is the absolute path of a device, including the `sys` mount point, e.g.
+//! `/sys/devices/virtual/net/lo`.
+//!
+//! [DevPath] is the path of a device relative to the `sys` mount point, e.g.
+//! `/devices/virtual/net/lo`.
+//!
+//! The `sys` mount point defaults to [SYS_MOUNT_POINT]. Paths in another `sysfs` tree, e.g. a
+//! fake tree in tests, are created with [SysPath::new_in], and [DevPath::syspath_in].
+
+use std::{fmt, fs, path};
+
+use crate::{Error, Result};
+
+/// Mount point of the `sysfs` filesystem.
+///
+/// Set with the `UDEVRS_SYS_DIR` environment variable at build time.
+pub const SYS_MOUNT_POINT: &str = env!("UDEVRS_SYS_DIR");
+
+/// Absolute path of a device in `sysfs`, including the `sys` mount point.
+#[repr(C)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SysPath {
+    path: String,
+    root_len: usize,
+}
+
+impl SysPath {
+    /// Creates a new [SysPath] in the [SYS_MOUNT_POINT].
+    ///
+    /// Trailing `/` separators are removed.
+    ///
+    /// Returns: `Ok(SysPath)` on success, `Err(Error)` if the path is empty, not in `/sys`, has no
+    /// subdirectory, or contains `..` components.
+    pub fn new<P: Into<String>>(path: P) -> Result<Self> {
+        Self::new_in(SYS_MOUNT_POINT, path)
+    }
+
+    /// Creates a new [SysPath] in the `sys` mount point `root`, e.g. a [Udev](crate::Udev)
+    /// context [sys path](crate::Udev::get_sys_path).
+    ///
+    /// Trailing `/` separators are removed.
+    ///
+    /// Returns: `Ok(SysPath)` on success, `Err(Error)` if `root` is not absolute, or the path is
+    /// empty, not in `root`, has no subdirectory, or contains `..` components.
+    pub fn new_in<P: Into<String>>(root: &str, path: P) -> Result<Self> {
+        let path = path.into();
+        let root = root.trim_end_matches('/');
+        let trimmed = path.trim_end_matches('/');
+
+        if !root.starts_with('/') {
+            Err(Error::UdevDevice(format!(
+                "sys mount point is not absolute: {root}"
+            )))
+        } else if path.is_empty() {
+            Err(Error::UdevDevice("empty syspath".into()))
+        } else if let Some(subdir) = strip_root(trimmed, root) {
+            validate_components(subdir, path.as_str())?;
+            Ok(Self {
+                path: trimmed.into(),
+                root_len: root.len(),
+            })
+        } else if trimmed == root {
+            Err(Error::UdevDevice("empty syspath subdir".into()))
+        } else {
+            Err(Error::UdevDevice(format!("not in {root}: {path}")))
+        }
+    }
+
+    /// Gets the [SysPath] as a string slice.
+    pub fn as_str(&self) -> &str {
+        self.path.as_str()
+    }
+
+    /// Gets the `sys` mount point of the [SysPath], e.g. `/sys`.
+    pub fn root(&self) -> &str {
+        &self.path[..self.root_len]
+    }
+
+    /// Gets the path relative to the `/sys/` mount point, e.g. `devices/virtual/net/lo`.
+    pub fn subdir(&self) -> &str {
+        &self.path[self.root_len + 1..]
+    }
+
+    /// Gets the [DevPath] of the device, i.e. the path with the `sys` mount point stripped.
+    pub fn devpath(&self) -> DevPath {
+        DevPath(self.path[self.root_len..].into())
+    }
+
+    /// Gets the last path component, e.g. `lo`.
+    pub fn sysname(&self) -> &str {
+        last_component(self.subdir())
+    }
+
+    /// Gets the parent directory.
+    ///
+    /// Like `libudev`, the walk stops below `/sys/devices`, which is not a device itself.
+    ///
+    /// Returns: `Some(SysPath)` for the parent, `None` if the parent is the `sys` mount point, or
+    /// `/sys/devices`.
+    pub fn parent(&self) -> Option<Self> {
+        self.path
+            .rfind('/')
+            .filter(|&pos| pos > self.root_len && &self.path[self.root_len..pos] != "/devices")
+            .map(|pos| Self {
+                path: self.path[..pos].into(),
+                root_len: self.root_len,
+            })
+    }
+
+    /// Resolves symlinks in the [SysPath], e.g. `/sys/class/net/lo` to
+    /// `/sys/devices/virtual/net/lo`.
+    ///
+    /// Devices are reachable through several symlink farms, e.g. `/sys/class`, `/sys/bus`, and
+    /// `/sys/dev`, the canonical path is the one under `/sys/devices`, or the subsystem
+    /// directories.
+    ///
+    /// The `sys` mount point is resolved too, so the canonical path may have a different
+    /// [root](Self::root) if the mount point is a symlink.
+    ///
+    /// Returns: `Ok(SysPath)` on success, `Err(Error)` if the path does not exist, or resolves
+    /// outside of `/sys`.
+    pub fn canonicalize(&self) -> Result<Self> {
+        let path = fs::canonicalize(self.as_str())
+            .map_err(|err| Error::UdevDevice(format!("unable to resolve syspath {self}: {err}")))?;
+        let root = fs::canonicalize(self.root()).unwrap_or(self.root().into());
+
+        match (path.to_str(), root.to_str()) {
+            (Some(path), Some(root)) => Self::new_in(root, path),
+            _ => Err(Error::UdevDevice(format!(
+                "syspath contains non-Unicode bytes: {}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Joins a relative path to the [SysPath].
+    ///
+    /// Returns: `Ok(SysPath)` on success, `Err(Error)` if `name` is empty, absolute, or contains
+    /// `..` components.
+    pub fn join(&self, name: &str) -> Result<Self> {
+        validate_relative(name)?;
+        Self::new_in(self.root(), format!("{}/{name}", self.path))
+    }
+}
+
+impl AsRef<str> for SysPath {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<path::Path> for SysPath {
+    fn as_ref(&self) -> &path::Path {
+        path::Path::new(self.as_str())
+    }
+}
+
+impl TryFrom<&str> for SysPath {
+    type Error = Error;
+
+    fn try_from(val: &str) -> Result<Self> {
+        Self::new(val)
+    }
+}
+
+impl TryFrom<String> for SysPath {
+    type Error = Error;
+
+    fn try_from(val: String) -> Result<Self> {
+        Self::new(val)
+    }
+}
+
+impl From<SysPath> for String {
+    fn from(val: SysPath) -> Self {
+        val.path
+    }
+}
+
+impl From<&DevPath> for SysPath {
+    fn from(val: &DevPath) -> Self {
+        val.syspath()
+    }
+}
+
+impl fmt::Display for SysPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path)
+    }
+}
+
+/// Path of a device relative to the `sys` mount point, e.g. `/devices/virtual/net/lo`.
+#[repr(C)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DevPath(String);
+
+impl DevPath {
+    /// Creates a new [DevPath].
+    ///
+    /// Trailing `/` separators are removed.
+    ///
+    /// Returns: `Ok(DevPath)` on success, `Err(Error)` if the path is empty, relative, includes
+    /// the `sys` mount point, or contains `..` components.
+    pub fn new<P: Into<String>>(path: P) -> Result<Self> {
+        let path = path.into();
+        let trimmed = path.trim_end_matches('/');
+
+        if path.is_empty() {
+            Err(Error::UdevDevice("empty devpath".into()))
+        } else if strip_root(trimmed, SYS_MOUNT_POINT).is_some() || trimmed == SYS_MOUNT_POINT {
+            Err(Error::UdevDevice(format!(
+                "devpath includes the sys mount point: {path}"
+            )))
+        } else if let Some(subdir) = trimmed.strip_prefix('/') {
+            validate_components(subdir, path.as_str())?;
+            Ok(Self(trimmed.into()))
+        } else {
+            Err(Error::UdevDevice(format!(
+                "devpath is not absolute: {path}"
+            )))
+        }
+    }
+
+    /// Gets the [DevPath] as a string slice.
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    /// Gets the [SysPath] of the device, i.e. the path with the `sys` mount point prepended.
+    pub fn syspath(&self) -> SysPath {
+        self.syspath_in(SYS_MOUNT_POINT)
+    }
+
+    /// Gets the [SysPath] of the device in the `sys` mount point `root`.
+    pub fn syspath_in(&self, root: &str) -> SysPath {
+        let root = root.trim_end_matches('/');
+
+        SysPath {
+            path: format!("{root}{}", self.0),
+            root_len: root.len(),
+        }
+    }
+
+    /// Gets the last path component, e.g. `lo`.
+    pub fn sysname(&self) -> &str {
+        last_component(self.0.as_str())
+    }
+
+    /// Gets the parent directory.
+    ///
+    /// Like `libudev`, the walk stops below `/devices`, which is not a device itself.
+    ///
+    /// Returns: `Some(DevPath)` for the parent, `None` for top-level paths, e.g.
+    /// `/devices/virtual`.
+    pub fn parent(&self) -> Option<Self> {
+        self.0
+            .rfind('/')
+            .filter(|&pos| pos > 0 && &self.0[..pos] != "/devices")
+            .map(|pos| Self(self.0[..pos].into()))
+    }
+
+    /// Joins a relative path to the [DevPath].
+    ///
+    /// Returns: `Ok(DevPath)` on success, `Err(Error)` if `name` is empty, absolute, or contains
+    /// `..` components.
+    pub fn join(&self, name: &str) -> Result<Self> {
+        validate_relative(name)?;
+        Self::new(format!("{}/{name}", self.0))
+    }
+}
+
+impl AsRef<str> for DevPath {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl TryFrom<&str> for DevPath {
+    type Error = Error;
+
+    fn try_from(val: &str) -> Result<Self> {
+        Self::new(val)
+    }
+}
+
+impl TryFrom<String> for DevPath {
+    type Error = Error;
+
+    fn try_from(val: String) -> Result<Self> {
+        Self::new(val)
+    }
+}
+
+impl From<DevPath> for String {
+    fn from(val: DevPath) -> Self {
+        val.0
+    }
+}
+
+impl From<&SysPath> for DevPath {
+    fn from(val: &SysPath) -> Self {
+        val.devpath()
+    }
+}
+
+impl fmt::Display for DevPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+fn last_component(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+// Gets the path relative to the `sys` mount point `root`, e.g. `devices/virtual/net/lo`.
+fn strip_root<'p>(path: &'p str, root: &str) -> Option<&'p str> {
+    path.strip_prefix(root).and_then(|p| p.strip_prefix('/'))
+}
+
+fn validate_components(subdir: &str, path: &str) -> Result<()> {
+    if subdir.is_empty() {
+        Err(Error::UdevDevice(format!("empty path subdir: {path}")))
+    } else if subdir
+        .split('/')
+        .any(|c| c.is_empty() || c == "." || c == "..")
+    {
+        Err(Error::UdevDevice(format!("invalid path component: {path}")))
+    } else {
+        Ok(())
+    }
+}
+
+fn validate_relative(name: &str) -> Result<()> {
+    if name.starts_with('/') {
+        Err(Error::UdevDevice(format!(
+            "joined path is absolute: {name}"
+        )))
+    } else {
+        validate_components(name.trim_end_matches('/'), name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sys_dev_path() {
+        let syspath = SysPath::new("/sys/devices/virtual/net/lo/").unwrap();
+
+        assert_eq!(syspath.as_str(), "/sys/devices/virtual/net/lo");
+        assert_eq!(syspath.subdir(), "devices/virtual/net/lo");
+        assert_eq!(syspath.sysname(), "lo");
+        assert_eq!(syspath.devpath().as_str(), "/devices/virtual/net/lo");
+        assert_eq!(syspath.devpath().syspath(), syspath);
+
+        let parent = syspath.parent().unwrap();
+        assert_eq!(parent.as_str(), "/sys/devices/virtual/net");
+        assert_eq!(parent.join("lo").unwrap(), syspath);
+        assert!(SysPath::new("/sys/devices").unwrap().parent().is_none());
+        assert!(SysPath::new("/sys/devices/virtual")
+            .unwrap()
+            .parent()
+            .is_none());
+        assert_eq!(
+            SysPath::new("/sys/class/net")
+                .unwrap()
+                .parent()
+                .unwrap()
+                .as_str(),
+            "/sys/class"
+        );
+
+        for invalid in [
+            "",
+            "/sys",
+            "/sys/",
+            "/dev/sda",
+            "sys/devices",
+            "/sys/devices/../class",
+        ] {
+            assert!(
+                SysPath::new(invalid).is_err(),
+                "expected error for: {invalid}"
+            );
+        }
+
+        for invalid in ["", "/abs", "../up", "a//b"] {
+            assert!(
+                parent.join(invalid).is_err(),
+                "expected error for: {invalid}"
+            );
+        }
+
+        let devpath = DevPath::new("/devices/virtual/net/lo").unwrap();
+        assert_eq!(devpath.sysname(), "lo");
+        assert_eq!(devpath.syspath(), syspath);
+        assert_eq!(devpath.parent().unwrap().as_str(), "/devices/virtual/net");
+        assert!(DevPath::new("/devices").unwrap().parent().is_none());
+        assert!(DevPath::new("/devices/virtual").unwrap().parent().is_none());
+
+        for invalid in ["", "devices/virtual", "/sys/devices", "/devices/./net"] {
+            assert!(
+                DevPath::new(invalid).is_err(),
+                "expected error for: {invalid}"
+            );
+        }
+
+        // paths in another sys mount point
+        let syspath = SysPath::new_in("/tmp/sys/", "/tmp/sys/devices/virtual/net/lo").unwrap();
+        assert_eq!(syspath.root(), "/tmp/sys");
+        assert_eq!(syspath.subdir(), "devices/virtual/net/lo");
+        assert_eq!(syspath.devpath(), devpath);
+        assert_eq!(devpath.syspath_in("/tmp/sys"), syspath);
+        assert_eq!(syspath.parent().unwrap().join("lo").unwrap(), syspath);
+        assert!(SysPath::new_in("/tmp/sys", "/tmp/sys/devices")
+            .unwrap()
+            .parent()
+            .is_none());
+
+        assert!(SysPath::new_in("/tmp/sys", "/sys/devices/virtual").is_err());
+        assert!(SysPath::new_in("tmp/sys", "tmp/sys/devices").is_err());
+    }
+}
@@ -0,0 +1,181 @@
+//! Match values compiled once, when a match is added to an enumerate, or monitor filter.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::{Error, Result};
+
+/// Compiled sys attribute match value.
+///
+/// Values are glob patterns, matched like `libudev`, see [GlobMatch]. As an extension, the
+/// numeric comparisons `>=N`, and `<=N` match integer attributes.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum SysattrMatch {
+    /// Empty values match any device with the attribute.
+    Any,
+    AtLeast(i64),
+    AtMost(i64),
+    Glob(GlobMatch),
+    /// Invalid numeric comparisons never match.
+    Never,
+}
+
+impl SysattrMatch {
+    /// Compiles the match value.
+    ///
+    /// Returns: `Ok(SysattrMatch)` on success, `Err(Error)` for invalid numeric comparisons.
+    pub(crate) fn new(match_val: &str) -> Result<Self> {
+        let (cmp, num): (fn(i64) -> Self, &str) = if match_val.is_empty() {
+            return Ok(Self::Any);
+        } else if let Some(num) = match_val.strip_prefix(">=") {
+            (Self::AtLeast, num)
+        } else if let Some(num) = match_val.strip_prefix("<=") {
+            (Self::AtMost, num)
+        } else {
+            return Ok(Self::Glob(GlobMatch::new(match_val)));
+        };
+
+        num.trim().parse::<i64>().map(cmp).map_err(|err| {
+            Error::UdevEnumerate(format!("invalid sysattr comparison {match_val}: {err}"))
+        })
+    }
+
+    /// Matches the attribute value, without the trailing newline.
+    ///
+    /// Non-integer attribute values never match a numeric comparison.
+    pub(crate) fn matches(&self, val: &str) -> bool {
+        match self {
+            Self::Any => true,
+            Self::AtLeast(num) => val.trim().parse::<i64>().is_ok_and(|val| val >= *num),
+            Self::AtMost(num) => val.trim().parse::<i64>().is_ok_and(|val| val <= *num),
+            Self::Glob(pattern) => pattern.matches(val),
+            Self::Never => false,
+        }
+    }
+}
+
+/// Compiled glob match value.
+///
+/// Invalid glob patterns, e.g. with an unclosed `[`, only match the value literally, like
+/// `fnmatch`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum GlobMatch {
+    Glob(glob::Pattern),
+    Literal(String),
+}
+
+impl GlobMatch {
+    /// Compiles the glob pattern.
+    pub(crate) fn new(pattern: &str) -> Self {
+        glob::Pattern::new(pattern)
+            .map(Self::Glob)
+            .unwrap_or_else(|_| Self::Literal(pattern.into()))
+    }
+
+    /// Matches the value against the pattern.
+    pub(crate) fn matches(&self, val: &str) -> bool {
+        match self {
+            Self::Glob(pattern) => pattern.matches(val),
+            Self::Literal(literal) => literal == val,
+        }
+    }
+}
+
+/// Match values compiled when a match is added, keyed by their source value.
+///
+/// Values missing from the cache, e.g. of entries added through a mutable list reference, are
+/// compiled on every match.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MatchCache {
+    sysattrs: HashMap<String, SysattrMatch>,
+    globs: HashMap<String, GlobMatch>,
+}
+
+impl MatchCache {
+    /// Compiles, and caches a sys attribute match value.
+    ///
+    /// Returns: `Ok(())` on success, `Err(Error)` for invalid numeric comparisons.
+    pub(crate) fn add_sysattr(&mut self, match_val: &str) -> Result<()> {
+        if !self.sysattrs.contains_key(match_val) {
+            let compiled = SysattrMatch::new(match_val)?;
+            self.sysattrs.insert(match_val.into(), compiled);
+        }
+
+        Ok(())
+    }
+
+    /// Gets the compiled sys attribute match value, invalid numeric comparisons never match.
+    pub(crate) fn sysattr(&self, match_val: &str) -> Cow<'_, SysattrMatch> {
+        match self.sysattrs.get(match_val) {
+            Some(compiled) => Cow::Borrowed(compiled),
+            None => Cow::Owned(SysattrMatch::new(match_val).unwrap_or(SysattrMatch::Never)),
+        }
+    }
+
+    /// Compiles, and caches a glob pattern.
+    pub(crate) fn add_glob(&mut self, pattern: &str) {
+        if !self.globs.contains_key(pattern) {
+            self.globs.insert(pattern.into(), GlobMatch::new(pattern));
+        }
+    }
+
+    /// Gets the compiled glob pattern.
+    pub(crate) fn glob(&self, pattern: &str) -> Cow<'_, GlobMatch> {
+        match self.globs.get(pattern) {
+            Some(compiled) => Cow::Borrowed(compiled),
+            None => Cow::Owned(GlobMatch::new(pattern)),
+        }
+    }
+
+    /// Removes all compiled match values.
+    pub(crate) fn clear(&mut self) {
+        self.sysattrs.clear();
+        self.globs.clear();
+    }
+}
+
+// caches are derived from the match lists, and do not change the filter identity
+impl PartialEq for MatchCache {
+    fn eq(&self, _oth: &Self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sysattr_match() -> Result<()> {
+        assert!(SysattrMatch::new("")?.matches("anything"));
+        assert!(SysattrMatch::new("1*")?.matches("1024"));
+        assert!(!SysattrMatch::new("1*")?.matches("0"));
+        assert!(SysattrMatch::new(">=10")?.matches("10"));
+        assert!(!SysattrMatch::new(">=10")?.matches("9"));
+        assert!(SysattrMatch::new("<= 10")?.matches(" 9 "));
+        assert!(!SysattrMatch::new("<=10")?.matches("small"));
+        assert!(SysattrMatch::new("[")?.matches("["));
+        assert!(!SysattrMatch::new("[")?.matches("1"));
+        assert!(SysattrMatch::new(">=big").is_err());
+
+        let mut cache = MatchCache::default();
+        cache.add_sysattr(">=4")?;
+        assert!(matches!(cache.sysattr(">=4"), Cow::Borrowed(_)));
+        assert!(matches!(cache.sysattr("<=4"), Cow::Owned(_)));
+        assert_eq!(*cache.sysattr(">=x"), SysattrMatch::Never);
+
+        assert!(GlobMatch::new("sd*").matches("sda"));
+        assert!(GlobMatch::new("[sd").matches("[sd"));
+        assert!(!GlobMatch::new("[sd").matches("sd"));
+
+        cache.add_glob("sd*");
+        assert!(matches!(cache.glob("sd*"), Cow::Borrowed(_)));
+        assert!(matches!(cache.glob("nvme*"), Cow::Owned(_)));
+
+        cache.clear();
+        assert!(matches!(cache.sysattr(">=4"), Cow::Owned(_)));
+        assert!(matches!(cache.glob("sd*"), Cow::Owned(_)));
+
+        Ok(())
+    }
+}
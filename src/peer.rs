@@ -0,0 +1,161 @@
+//! Credentials, and non-blocking message queues, of Unix socket peers.
+
+use std::collections::VecDeque;
+use std::io;
+use std::os::fd::AsRawFd;
+use std::os::unix::net::UnixStream;
+
+use nix::errno::Errno;
+use nix::sys::socket::{self, sockopt, MsgFlags};
+
+/// Gets the `(pid, uid)` credentials of the process connected to the `stream`.
+pub(crate) fn peer_cred(stream: &UnixStream) -> io::Result<(libc::pid_t, libc::uid_t)> {
+    let cred = socket::getsockopt(stream, sockopt::PeerCredentials)?;
+
+    Ok((cred.pid(), cred.uid()))
+}
+
+/// Checks whether a peer user is trusted: `root`, the user of this process, or one of the
+/// `allowed_uids`.
+pub(crate) fn peer_allowed(uid: libc::uid_t, allowed_uids: &[libc::uid_t]) -> bool {
+    // SAFETY: `geteuid` is always successful, and has no side effects.
+    let euid = unsafe { libc::geteuid() };
+
+    uid == 0 || uid == euid || allowed_uids.contains(&uid)
+}
+
+/// Messages queued for a peer, sent without blocking the sender.
+///
+/// Messages are queued whole, so a dropped message never leaves a partial frame on the stream.
+#[derive(Debug)]
+pub(crate) struct PeerQueue {
+    stream: UnixStream,
+    messages: VecDeque<Vec<u8>>,
+    // bytes of the front message already sent
+    sent: usize,
+    queued: usize,
+    limit: usize,
+}
+
+impl PeerQueue {
+    /// Creates a new [PeerQueue], switching the `stream` to non-blocking mode.
+    ///
+    /// Bounded pushes are dropped while `limit` bytes are queued.
+    pub(crate) fn new(stream: UnixStream, limit: usize) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+
+        Ok(Self {
+            stream,
+            messages: VecDeque::new(),
+            sent: 0,
+            queued: 0,
+            limit,
+        })
+    }
+
+    /// Gets a reference to the peer stream.
+    pub(crate) const fn stream(&self) -> &UnixStream {
+        &self.stream
+    }
+
+    /// Gets whether all messages were sent.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Queues the message, unless it would exceed the limit.
+    ///
+    /// Returns: `true` if the message was queued, `false` if it was dropped.
+    pub(crate) fn try_push(&mut self, msg: Vec<u8>) -> bool {
+        if self.queued.saturating_add(msg.len()) > self.limit {
+            false
+        } else {
+            self.push(msg);
+            true
+        }
+    }
+
+    /// Queues the message, regardless of the limit.
+    ///
+    /// Callers bound the unlimited messages themselves, e.g. one reply per call.
+    pub(crate) fn push(&mut self, msg: Vec<u8>) {
+        if !msg.is_empty() {
+            self.queued = self.queued.saturating_add(msg.len());
+            self.messages.push_back(msg);
+        }
+    }
+
+    /// Sends the queued messages, until the socket buffer is full.
+    ///
+    /// Returns: `Ok(())` on success, `Err(io::Error)` if the peer disconnected.
+    pub(crate) fn flush(&mut self) -> io::Result<()> {
+        while let Some(msg) = self.messages.front() {
+            let flags = MsgFlags::MSG_NOSIGNAL | MsgFlags::MSG_DONTWAIT;
+
+            match socket::send(self.stream.as_raw_fd(), &msg[self.sent..], flags) {
+                Ok(count) => {
+                    self.sent += count;
+                    self.queued = self.queued.saturating_sub(count);
+
+                    if self.sent == msg.len() {
+                        self.messages.pop_front();
+                        self.sent = 0;
+                    }
+                }
+                Err(Errno::EINTR) => (),
+                Err(Errno::EAGAIN) => return Ok(()),
+                Err(errno) => return Err(errno.into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gets the `poll` events to wait for, `POLLOUT` while messages are queued.
+    pub(crate) fn poll_events(&self) -> libc::c_short {
+        if self.is_empty() {
+            0
+        } else {
+            libc::POLLOUT
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_peer_queue() -> io::Result<()> {
+        let (local, mut remote) = UnixStream::pair()?;
+
+        let (pid, uid) = peer_cred(&local)?;
+        assert_eq!(pid, std::process::id() as libc::pid_t);
+        assert!(peer_allowed(uid, &[]));
+
+        let mut queue = PeerQueue::new(local, 8)?;
+        assert!(queue.is_empty());
+        assert_eq!(queue.poll_events(), 0);
+
+        assert!(queue.try_push(b"hello".to_vec()));
+        assert!(!queue.try_push(b"world".to_vec()));
+        assert_eq!(queue.poll_events(), libc::POLLOUT);
+
+        // replies bypass the limit
+        queue.push(b", reply".to_vec());
+
+        queue.flush()?;
+        assert!(queue.is_empty());
+
+        let mut buf = [0u8; 12];
+        remote.read_exact(buf.as_mut())?;
+        assert_eq!(buf.as_ref(), b"hello, reply");
+
+        drop(remote);
+        queue.push(b"gone".to_vec());
+        assert!(queue.flush().is_err());
+
+        Ok(())
+    }
+}
@@ -7,20 +7,28 @@
 //!
 //! From `libudev-queue` documentation.
 
-use std::io::{self, Write};
-use std::os::fd::FromRawFd;
-use std::{ffi, fs, sync::Arc};
+use std::collections::BTreeMap;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::{cmp, ffi, fmt, fs, io, mem, sync::Arc, time};
 
-use crate::UDEV_ROOT_RUN;
-use crate::{Error, Result, Udev, UdevEntryList, UdevList};
+use crate::inotify;
+use crate::{Error, ErrorContext, ErrorScope, Result, Udev, UdevEntry, UdevEntryList, UdevList};
+
+#[cfg(feature = "tokio")]
+mod stream;
+
+#[cfg(feature = "tokio")]
+pub use stream::*;
+
+/// Name of the event log written by the `udev` daemon to the run directory.
+const QUEUE_LOG_NAME: &str = "queue.bin";
 
 /// Represents the current event queue in the udev daemon.
-#[repr(C)]
-#[derive(Clone, Debug, Default, PartialEq)]
+#[derive(Debug)]
 pub struct UdevQueue {
     udev: Arc<Udev>,
     queue_list: UdevList,
-    fd: i32,
+    fd: Option<OwnedFd>,
 }
 
 impl UdevQueue {
@@ -30,12 +38,18 @@ impl UdevQueue {
         Self {
             udev,
             queue_list: UdevList::new(udev_arc),
-            fd: -1,
+            fd: None,
         }
     }
 
     /// Creates a new [UdevQueue] from the provided parameters.
-    pub fn create<Q: Into<UdevEntryList>>(udev: Arc<Udev>, queue_list: Q, fd: i32) -> Self {
+    ///
+    /// The [UdevQueue] takes ownership of the `fd` inotify file descriptor, if provided.
+    pub fn create<Q: Into<UdevEntryList>>(
+        udev: Arc<Udev>,
+        queue_list: Q,
+        fd: Option<OwnedFd>,
+    ) -> Self {
         let udev_arc = Arc::clone(&udev);
         Self {
             udev,
@@ -81,84 +95,211 @@ impl UdevQueue {
     }
 
     /// Gets the [UdevQueue] file descriptor.
-    pub const fn fd(&self) -> i32 {
-        self.fd
+    ///
+    /// Returns: the inotify file descriptor, or `-1` if the watch has not been created.
+    pub fn fd(&self) -> i32 {
+        self.fd.as_ref().map(|fd| fd.as_raw_fd()).unwrap_or(-1)
     }
 
     /// Sets the [UdevQueue] file descriptor.
-    pub fn set_fd(&mut self, val: i32) {
-        self.fd = val;
+    ///
+    /// The [UdevQueue] takes ownership of the file descriptor, and closes any previous one.
+    pub fn set_fd(&mut self, val: OwnedFd) {
+        self.fd = Some(val);
     }
 
     /// Builder function that sets the [UdevQueue] file descriptor.
-    pub fn with_fd(mut self, val: i32) -> Self {
+    pub fn with_fd(mut self, val: OwnedFd) -> Self {
         self.set_fd(val);
         self
     }
 
     /// Gets a file descriptor to watch for a queue to become empty.
+    ///
+    /// The inotify file descriptor is created on the first call, and is owned by the
+    /// [UdevQueue]. It is closed when the [UdevQueue] is dropped.
+    ///
+    /// The file descriptor is non-blocking, use [watch](Self::watch) to read pending events.
+    ///
+    /// **NOTE**: [UdevQueue] does not implement [AsFd](std::os::fd::AsFd), because the watch
+    /// may not exist yet. Use the file descriptor returned by this function instead.
     pub fn get_fd(&mut self) -> Result<i32> {
-        if self.fd < 0 {
-            // SAFETY: the argument is valid, and the return value is checked before use.
-            let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
-            if fd < 0 {
-                let errno = io::Error::last_os_error();
-                let err_msg =
-                    format!("unable to init inotify monitor, error: {fd}, errno: {errno}");
-                log::error!("{err_msg}");
-                Err(Error::UdevQueue(err_msg))
-            } else {
-                let udev_path = ffi::CString::new(format!("{UDEV_ROOT_RUN}/udev"))?;
-                // SAFETY: arguments are valid, and pointers reference valid memory.
-                let r = unsafe {
-                    libc::inotify_add_watch(fd, udev_path.as_ptr() as *const _, libc::IN_DELETE)
-                };
-                if r < 0 {
-                    let errno = io::Error::last_os_error();
-                    let err_msg =
-                        format!("unable to add inotify watch event, error: {r}, errno: {errno}");
-                    log::error!("{err_msg}");
-                    // SAFETY: argument is valid
-                    unsafe { libc::close(fd) };
-                    Err(Error::UdevQueue(err_msg))
-                } else {
-                    self.fd = fd;
-                    Ok(fd)
-                }
-            }
+        if let Some(fd) = self.fd.as_ref() {
+            return Ok(fd.as_raw_fd());
+        }
+
+        // SAFETY: the arguments are valid, and the return value is checked before use.
+        let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC | libc::IN_NONBLOCK) };
+        if fd < 0 {
+            let errno = io::Error::last_os_error();
+            let err_msg = format!("unable to init inotify monitor, error: {fd}, errno: {errno}");
+            log::error!("{err_msg}");
+            return Err(Error::os(ErrorScope::Queue, err_msg, errno));
+        }
+
+        // SAFETY: `inotify_init1` returned a new file descriptor, owned by nothing else.
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        let udev_path = ffi::CString::new(self.udev.get_run_path())?;
+        // SAFETY: arguments are valid, and pointers reference valid memory.
+        let r = unsafe {
+            libc::inotify_add_watch(
+                fd.as_raw_fd(),
+                udev_path.as_ptr() as *const _,
+                libc::IN_DELETE,
+            )
+        };
+        if r < 0 {
+            let errno = io::Error::last_os_error();
+            let err_msg = format!("unable to add inotify watch event, error: {r}, errno: {errno}");
+            log::error!("{err_msg}: {}", self.udev.get_run_path());
+            Err(Error::os(ErrorScope::Queue, err_msg, errno).with_context(
+                ErrorContext::new("inotify_add_watch").with_path(self.udev.get_run_path()),
+            ))
         } else {
-            Ok(self.fd)
+            let raw_fd = fd.as_raw_fd();
+            self.fd = Some(fd);
+            Ok(raw_fd)
         }
     }
 
-    /// Clears the watched file descriptor for queue changes.
+    /// Reads all pending events from the queue watch.
     ///
-    /// # Safety
+    /// Each event reports a file removed from the `udev` run directory. The removal of the
+    /// `queue` file means the `udev` daemon finished processing all events, see
+    /// [UdevQueueEvent::is_queue_empty].
     ///
-    /// Users must ensure that every [UdevQueue] has a unique file descriptor, if the descriptor is
-    /// non-negative.
+    /// Does not block, an empty list is returned when no events are pending.
+    ///
+    /// Returns: `Ok(Vec<UdevQueueEvent>)` on success, `Err(Error)` otherwise.
+    pub fn watch(&mut self) -> Result<Vec<UdevQueueEvent>> {
+        let fd = self
+            .fd
+            .as_ref()
+            .map(|fd| fd.as_raw_fd())
+            .ok_or(Error::UdevQueue(
+                "queue watch not created, call get_fd first".into(),
+            ))?;
+
+        let mut events = Vec::new();
+
+        inotify::read_events(fd, |event| {
+            events.push(UdevQueueEvent::new(event.mask, event.name));
+        })
+        .map_err(|errno| {
+            let err_msg = format!("unable to read queue watch events, errno: {errno}");
+            log::error!("{err_msg}");
+            Error::os(ErrorScope::Queue, err_msg, errno)
+        })?;
+
+        Ok(events)
+    }
+
+    /// Reads the pending notifications from the queue watch, as typed [QueueEvent]s.
+    ///
+    /// Creates the watch on the first call, see [get_fd](Self::get_fd), and consumes all
+    /// pending inotify events. Removals of other files in the run directory are discarded, see
+    /// [watch](Self::watch) for the raw events.
+    ///
+    /// Does not block, an empty list is returned when no notifications are pending.
+    ///
+    /// Returns: `Ok(Vec<QueueEvent>)` on success, `Err(Error)` otherwise.
+    pub fn read_events(&mut self) -> Result<Vec<QueueEvent>> {
+        self.get_fd()?;
+
+        Ok(self
+            .watch()?
+            .iter()
+            .filter_map(QueueEvent::from_watch_event)
+            .collect())
+    }
+
+    /// Clears the watched file descriptor for queue changes.
+    ///
+    /// Discards all pending events, see [watch](Self::watch) to read the events instead.
     ///
     /// Returns: `Ok(())` on success, `Err(Error)` otherwise.
     pub fn flush(&mut self) -> Result<()> {
-        let fd = self.fd;
-        if fd < 0 {
-            let err = libc::EINVAL;
-            Err(Error::UdevQueue(format!(
-                "invalid file descriptor, fd: {fd}, error: {err}"
-            )))
-        } else {
-            // SAFETY: argument is valid, and only one valid mutable reference to this UdevQueue
-            // can be held without further `unsafe` code.
-            //
-            // Users must ensure that every UdevQueue has a unique file descriptor.
-            let mut file = unsafe { fs::File::from_raw_fd(fd) };
-            file.flush().map_err(|err| {
+        let events = self.watch()?;
+        log::trace!("flushed {} queue watch events", events.len());
+        Ok(())
+    }
+
+    /// Waits for the `udev` daemon to finish processing all queued events, like `udevadm settle`.
+    ///
+    /// Watches the run directory for the removal of the `queue` file, see
+    /// [get_fd](Self::get_fd), and returns as soon as the queue is empty. Events triggered after
+    /// the call, e.g. with [UdevDevice::trigger](crate::UdevDevice::trigger), are included,
+    /// because the kernel queues them before the write returns.
+    ///
+    /// ```no_run
+    /// # use std::{sync::Arc, time::Duration};
+    /// # use udevrs::{Udev, UdevDevice, UdevQueue};
+    /// # fn main() -> udevrs::Result<()> {
+    /// let udev = Arc::new(Udev::new());
+    /// let mut disk = UdevDevice::new_from_subsystem_sysname(Arc::clone(&udev), "block", "sda")?;
+    /// disk.trigger("change")?;
+    ///
+    /// let mut queue = UdevQueue::new(udev);
+    /// if !queue.settle(Duration::from_secs(30))? {
+    ///     println!("timeout waiting for udev");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns:
+    ///
+    /// - `Ok(true)` if the queue is empty, or the `udev` daemon is not running
+    /// - `Ok(false)` if the timeout elapsed with events still queued
+    /// - `Err(Error)` if the queue cannot be watched
+    pub fn settle(&mut self, timeout: time::Duration) -> Result<bool> {
+        if !self.udev_is_active() {
+            log::debug!("udev daemon is not running, nothing to settle");
+            return Ok(true);
+        }
+
+        // create the watch, and drop events of earlier calls, before checking the queue, so a
+        // removal between the check, and the wait, always wakes the wait
+        let fd = self.get_fd()?;
+        self.flush()?;
+        let deadline = time::Instant::now() + timeout;
+
+        loop {
+            // re-checked after every wake up, the watch also reports other removed files
+            if self.queue_is_empty() {
+                return Ok(true);
+            }
+
+            let remaining = deadline.saturating_duration_since(time::Instant::now());
+            if remaining.is_zero() {
+                return Ok(false);
+            }
+
+            // round up, so short timeouts do not busy-loop with a zero `poll` timeout
+            let timeout_ms = cmp::min(
+                remaining.as_nanos().div_ceil(1_000_000),
+                libc::c_int::MAX as u128,
+            ) as libc::c_int;
+            let mut pfd = [libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            }];
+
+            // SAFETY: `pfd` is a valid, initialized array, and the length matches.
+            let ret =
+                unsafe { libc::poll(pfd.as_mut_ptr(), pfd.len() as libc::nfds_t, timeout_ms) };
+            if ret < 0 {
                 let errno = io::Error::last_os_error();
-                let err_msg =
-                    format!("unable to flush queue file descriptor, error: {err}, errno: {errno}");
-                log::error!("{err_msg}");
-                Error::UdevQueue(err_msg)
-            })
+                if errno.kind() != io::ErrorKind::Interrupted {
+                    let err_msg = format!("error waiting for the queue, errno: {errno}");
+                    log::error!("{err_msg}");
+                    return Err(Error::os(ErrorScope::Queue, err_msg, errno));
+                }
+            } else if ret > 0 {
+                self.flush()?;
+            }
         }
     }
 
@@ -166,23 +307,271 @@ impl UdevQueue {
     pub fn udev_is_active(&self) -> bool {
         fs::OpenOptions::new()
             .read(true)
-            .open(format!("{UDEV_ROOT_RUN}/udev/control"))
+            .open(format!("{}/control", self.udev.get_run_path()))
             .is_ok()
     }
 
     /// Gets whether [UdevQueue] is currently processing any events.
+    ///
+    /// The `udev` daemon creates the `queue` file in the run directory while events are
+    /// processed, and removes it when the queue is empty.
     pub fn queue_is_empty(&self) -> bool {
-        fs::OpenOptions::new()
-            .read(true)
-            .open(format!("{UDEV_ROOT_RUN}/udev/queue"))
-            .is_ok()
+        fs::metadata(format!("{}/queue", self.udev.get_run_path())).is_err()
+    }
+
+    /// Gets the current kernel event sequence number.
+    ///
+    /// Returns: `Ok(u64)` with the sequence number of the last kernel event, `Err(Error)` if the
+    /// `kernel/uevent_seqnum` sys file cannot be read.
+    pub fn get_kernel_seqnum(&self) -> Result<u64> {
+        let path = format!("{}/kernel/uevent_seqnum", self.udev.get_sys_path());
+        let seqnum = fs::read_to_string(path.as_str())
+            .map_err(|err| Error::UdevQueue(format!("unable to read {path}: {err}")))?;
+
+        seqnum
+            .trim()
+            .parse::<u64>()
+            .map_err(|err| Error::UdevQueue(format!("invalid kernel seqnum: {err}")))
+    }
+
+    /// Gets the last event sequence number queued by the `udev` daemon.
+    ///
+    /// Read from the `queue.bin` event log in the run directory. Daemons that do not write the
+    /// log, e.g. current `udevd` versions, report `0`, like `libudev`.
+    pub fn get_udev_seqnum(&self) -> u64 {
+        self.read_queue_log()
+            .map(|log| log.udev_seqnum)
+            .unwrap_or_default()
+    }
+
+    /// Gets whether the event with `seqnum` has been processed by the `udev` daemon.
+    ///
+    /// Events are finished when the queue is empty, or the event is older than the queued
+    /// events, or the event log reports the event as finished.
+    pub fn get_seqnum_is_finished(&self, seqnum: u64) -> bool {
+        if self.queue_is_empty() {
+            return true;
+        }
+
+        match self.read_queue_log() {
+            Some(log) => {
+                seqnum < log.seqnum_start
+                    || (seqnum <= log.udev_seqnum && !log.queued.contains_key(&seqnum))
+            }
+            None => true,
+        }
+    }
+
+    /// Gets whether all events in the range `start..=end` have been processed by the `udev`
+    /// daemon.
+    pub fn get_seqnum_sequence_is_finished(&self, start: u64, end: u64) -> bool {
+        let end = cmp::max(start, end);
+        (start..=end).all(|seqnum| self.get_seqnum_is_finished(seqnum))
+    }
+
+    /// Gets the list of events currently queued by the `udev` daemon.
+    ///
+    /// Entry names are the device syspaths, and entry values are the event sequence numbers.
+    /// The list is re-read from the event log on every call, and is empty if the daemon does not
+    /// write the log.
+    ///
+    /// Returns: the first entry of the queued events list, `None` if no events are queued.
+    pub fn get_queued_list_entry(&mut self) -> Option<&UdevEntry> {
+        let sys_path = self.udev.get_sys_path().to_owned();
+
+        self.queue_list.clear();
+
+        if let Some(log) = self.read_queue_log() {
+            for (seqnum, devpath) in log.queued {
+                self.queue_list.add_entry(
+                    format!("{sys_path}{devpath}").as_str(),
+                    seqnum.to_string().as_str(),
+                );
+            }
+        }
+
+        self.queue_list.entry()
+    }
+
+    fn read_queue_log(&self) -> Option<QueueLog> {
+        let path = format!("{}/{QUEUE_LOG_NAME}", self.udev.get_run_path());
+        fs::read(path.as_str())
+            .map_err(|err| log::trace!("unable to read queue log {path}: {err}"))
+            .ok()
+            .and_then(|buf| QueueLog::parse(buf.as_ref()))
+    }
+}
+
+// Contents of the `udev` daemon event log.
+//
+// The log starts with the native-endian `u64` sequence number of the first logged event,
+// followed by records of a `u64` sequence number, a `u16` devpath length, and the devpath.
+// Queued events have a devpath, finished events are logged again with an empty devpath.
+#[derive(Debug, Default, PartialEq)]
+struct QueueLog {
+    seqnum_start: u64,
+    udev_seqnum: u64,
+    queued: BTreeMap<u64, String>,
+}
+
+impl QueueLog {
+    fn parse(buf: &[u8]) -> Option<Self> {
+        let seqnum_len = mem::size_of::<u64>();
+        let devpath_len = mem::size_of::<u16>();
+
+        let seqnum_start = u64::from_ne_bytes(buf.get(..seqnum_len)?.try_into().ok()?);
+        let mut log = Self {
+            seqnum_start,
+            udev_seqnum: seqnum_start,
+            queued: BTreeMap::new(),
+        };
+
+        let mut idx = seqnum_len;
+        while let Some(header) = buf.get(idx..idx + seqnum_len + devpath_len) {
+            let seqnum = u64::from_ne_bytes(header[..seqnum_len].try_into().ok()?);
+            let len = u16::from_ne_bytes(header[seqnum_len..].try_into().ok()?) as usize;
+
+            idx += header.len();
+
+            // a truncated record means the daemon is still writing it
+            let Some(devpath) = buf.get(idx..idx + len) else {
+                break;
+            };
+            idx += len;
+
+            if len > 0 {
+                log.udev_seqnum = seqnum;
+                log.queued
+                    .insert(seqnum, String::from_utf8_lossy(devpath).into_owned());
+            } else {
+                log.queued.remove(&seqnum);
+            }
+        }
+
+        Some(log)
+    }
+}
+
+impl Default for UdevQueue {
+    fn default() -> Self {
+        Self::new(Arc::new(Udev::new()))
+    }
+}
+
+impl PartialEq for UdevQueue {
+    fn eq(&self, other: &Self) -> bool {
+        self.udev == other.udev && self.queue_list == other.queue_list && self.fd() == other.fd()
+    }
+}
+
+impl Drop for UdevQueue {
+    fn drop(&mut self) {
+        if let Some(fd) = self.fd.take() {
+            log::trace!("closing udev queue watch: {}", fd.as_raw_fd());
+        }
+    }
+}
+
+/// Registering the [UdevQueue] creates the inotify watch, if it does not exist yet.
+#[cfg(feature = "mio")]
+impl mio::event::Source for UdevQueue {
+    fn register(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> io::Result<()> {
+        let fd = self
+            .get_fd()
+            .map_err(|err| io::Error::other(format!("{err}")))?;
+        mio::unix::SourceFd(&fd).register(registry, token, interests)
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> io::Result<()> {
+        mio::unix::SourceFd(&self.fd()).reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &mio::Registry) -> io::Result<()> {
+        mio::unix::SourceFd(&self.fd()).deregister(registry)
+    }
+}
+
+/// Event read from the [UdevQueue] watch.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct UdevQueueEvent {
+    mask: u32,
+    name: String,
+}
+
+impl UdevQueueEvent {
+    /// Creates a new [UdevQueueEvent].
+    pub fn new<N: Into<String>>(mask: u32, name: N) -> Self {
+        Self {
+            mask,
+            name: name.into(),
+        }
+    }
+
+    /// Gets the inotify event mask.
+    pub const fn mask(&self) -> u32 {
+        self.mask
+    }
+
+    /// Gets the name of the file in the `udev` run directory the event refers to.
+    ///
+    /// Empty for events on the directory itself, e.g. `IN_IGNORED`.
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Gets whether the event reports the `udev` event queue becoming empty.
+    pub fn is_queue_empty(&self) -> bool {
+        self.mask & libc::IN_DELETE != 0 && self.name == "queue"
+    }
+}
+
+impl fmt::Display for UdevQueueEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{mask: {:#x}, name: {}}}", self.mask, self.name)
+    }
+}
+
+/// Typed notification read from the [UdevQueue] watch, see [UdevQueue::read_events].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum QueueEvent {
+    /// The `udev` daemon finished processing all queued events.
+    Emptied,
+}
+
+impl QueueEvent {
+    /// Converts a raw [UdevQueueEvent] into a [QueueEvent].
+    ///
+    /// Returns: the typed notification, or `None` for events without a notification.
+    pub fn from_watch_event(event: &UdevQueueEvent) -> Option<Self> {
+        event.is_queue_empty().then_some(Self::Emptied)
+    }
+}
+
+impl fmt::Display for QueueEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Emptied => write!(f, "emptied"),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::SysfsFixture;
     use crate::UdevEntry;
+    use std::path::Path;
 
     #[test]
     fn test_udev_queue() {
@@ -190,7 +579,7 @@ mod tests {
         let mut null_queue = UdevQueue::new(Arc::clone(&udev));
 
         let exp_list = [UdevEntry::new().with_name("test_list_entry")];
-        let exp_queue = UdevQueue::create(Arc::clone(&udev), exp_list.clone(), -1);
+        let exp_queue = UdevQueue::create(Arc::clone(&udev), exp_list.clone(), None);
 
         assert!(null_queue.queue_list().is_empty());
 
@@ -202,4 +591,154 @@ mod tests {
 
         assert_eq!(null_queue, exp_queue);
     }
+
+    #[test]
+    fn test_queue_watch() {
+        let udev = Arc::new(Udev::new());
+        let mut queue = UdevQueue::new(udev);
+
+        assert_eq!(queue.fd(), -1);
+        assert!(queue.watch().is_err());
+        assert!(queue.flush().is_err());
+
+        let fixture = SysfsFixture::new().unwrap();
+        let dir = Path::new(fixture.run_path());
+
+        // SAFETY: the arguments are valid, and the return value is checked before use.
+        let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC | libc::IN_NONBLOCK) };
+        assert!(fd >= 0);
+        // SAFETY: `inotify_init1` returned a new file descriptor, owned by nothing else.
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        let path = ffi::CString::new(dir.to_str().unwrap()).unwrap();
+        // SAFETY: arguments are valid, and pointers reference valid memory.
+        let wd = unsafe {
+            libc::inotify_add_watch(fd.as_raw_fd(), path.as_ptr() as *const _, libc::IN_DELETE)
+        };
+        assert!(wd >= 0);
+
+        let raw_fd = fd.as_raw_fd();
+        queue.set_fd(fd);
+        assert_eq!(queue.get_fd().unwrap(), raw_fd);
+
+        assert!(queue.watch().unwrap().is_empty());
+
+        fs::write(dir.join("queue"), "").unwrap();
+        fs::remove_file(dir.join("queue")).unwrap();
+
+        let events = queue.watch().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].name(), "queue");
+        assert!(events[0].is_queue_empty());
+
+        fs::write(dir.join("queue"), "").unwrap();
+        fs::write(dir.join("control"), "").unwrap();
+        fs::remove_file(dir.join("control")).unwrap();
+        fs::remove_file(dir.join("queue")).unwrap();
+
+        // only the queue removal is a typed notification
+        assert_eq!(queue.read_events().unwrap(), [QueueEvent::Emptied]);
+        assert!(queue.read_events().unwrap().is_empty());
+
+        fs::write(dir.join("control"), "").unwrap();
+        fs::remove_file(dir.join("control")).unwrap();
+
+        // flushing discards pending events, and keeps the watch open
+        queue.flush().unwrap();
+        assert!(queue.watch().unwrap().is_empty());
+        assert_eq!(queue.fd(), raw_fd);
+    }
+
+    #[test]
+    fn test_queue_seqnum() {
+        let fixture = SysfsFixture::new().unwrap();
+        let dir = Path::new(fixture.run_path());
+
+        let udev = Arc::new(Udev::new().with_run_path(fixture.run_path()));
+        let mut queue = UdevQueue::new(udev);
+
+        // no queue file, so every event is finished
+        assert!(queue.queue_is_empty());
+        assert!(queue.get_seqnum_is_finished(42));
+        assert_eq!(queue.get_udev_seqnum(), 0);
+        assert!(queue.get_queued_list_entry().is_none());
+
+        let record = |seqnum: u64, devpath: &str| {
+            let mut rec = seqnum.to_ne_bytes().to_vec();
+            rec.extend_from_slice(&(devpath.len() as u16).to_ne_bytes());
+            rec.extend_from_slice(devpath.as_bytes());
+            rec
+        };
+
+        let mut log = 10u64.to_ne_bytes().to_vec();
+        log.extend(record(10, "/devices/virtual/net/lo"));
+        log.extend(record(11, "/devices/virtual/block/loop0"));
+        log.extend(record(10, ""));
+        // truncated record
+        log.extend(&record(12, "/devices/virtual/block/loop1")[..12]);
+
+        fs::write(dir.join("queue"), "").unwrap();
+        fs::write(dir.join(QUEUE_LOG_NAME), log).unwrap();
+
+        assert!(!queue.queue_is_empty());
+        assert_eq!(queue.get_udev_seqnum(), 11);
+        assert!(queue.get_seqnum_is_finished(9));
+        assert!(queue.get_seqnum_is_finished(10));
+        assert!(!queue.get_seqnum_is_finished(11));
+        assert!(!queue.get_seqnum_is_finished(12));
+        assert!(!queue.get_seqnum_sequence_is_finished(9, 11));
+
+        let entry = queue.get_queued_list_entry().unwrap();
+        assert_eq!(entry.name(), "/sys/devices/virtual/block/loop0");
+        assert_eq!(entry.value(), "11");
+        assert_eq!(queue.len(), 1);
+
+        if fs::metadata("/sys/kernel/uevent_seqnum").is_ok() {
+            assert!(queue.get_kernel_seqnum().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_queue_settle() {
+        let fixture = SysfsFixture::new().unwrap();
+        let dir = Path::new(fixture.run_path());
+
+        let udev = Arc::new(Udev::new().with_run_path(fixture.run_path()));
+        let mut queue = UdevQueue::new(udev);
+
+        // no `control` socket, the daemon is not running
+        fs::write(dir.join("queue"), "").unwrap();
+        assert!(queue.settle(time::Duration::ZERO).unwrap());
+
+        fs::write(dir.join("control"), "").unwrap();
+        assert!(!queue.settle(time::Duration::from_millis(20)).unwrap());
+
+        let queue_file = dir.join("queue");
+        let remover = std::thread::spawn(move || {
+            std::thread::sleep(time::Duration::from_millis(50));
+            fs::remove_file(queue_file).unwrap();
+        });
+
+        let start = time::Instant::now();
+        assert!(queue.settle(time::Duration::from_secs(10)).unwrap());
+        assert!(start.elapsed() < time::Duration::from_secs(10));
+        remover.join().unwrap();
+
+        // stale events of the existing watch do not end the wait early
+        fs::write(dir.join("queue"), "").unwrap();
+        fs::write(dir.join("other"), "").unwrap();
+        fs::remove_file(dir.join("other")).unwrap();
+        assert!(!queue.settle(time::Duration::from_millis(20)).unwrap());
+
+        let queue_file = dir.join("queue");
+        let remover = std::thread::spawn(move || {
+            std::thread::sleep(time::Duration::from_millis(50));
+            fs::remove_file(queue_file).unwrap();
+        });
+
+        let start = time::Instant::now();
+        assert!(queue.settle(time::Duration::from_secs(10)).unwrap());
+        assert!(start.elapsed() < time::Duration::from_secs(10));
+        remover.join().unwrap();
+    }
 }
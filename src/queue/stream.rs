@@ -0,0 +1,158 @@
+use std::collections::VecDeque;
+use std::os::fd::{AsRawFd, RawFd};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use futures_core::Stream;
+use tokio::io::unix::AsyncFd;
+
+use super::{QueueEvent, UdevQueue};
+use crate::{Error, ErrorScope, Result};
+
+/// Asynchronous stream of [QueueEvent]s, read from the [UdevQueue] watch on a `tokio` runtime.
+///
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use udevrs::{QueueEventStream, Udev, UdevQueue};
+/// # async fn wait_emptied() -> udevrs::Result<()> {
+/// let mut events = QueueEventStream::new(UdevQueue::new(Arc::new(Udev::new())))?;
+///
+/// let event = events.next_event().await?;
+/// println!("udev queue {event}");
+/// # Ok(())
+/// # }
+/// ```
+pub struct QueueEventStream {
+    queue: AsyncFd<QueueWatch>,
+    pending: VecDeque<QueueEvent>,
+}
+
+// [UdevQueue] with the watch created, so it always has a file descriptor to register.
+struct QueueWatch(UdevQueue);
+
+impl AsRawFd for QueueWatch {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.fd()
+    }
+}
+
+impl QueueEventStream {
+    /// Creates a new [QueueEventStream], creating the queue watch if needed.
+    ///
+    /// Must be called from within a `tokio` runtime, with the IO driver enabled.
+    ///
+    /// Returns: `Ok(QueueEventStream)` on success, `Err(Error)` if the watch cannot be created,
+    /// or registered with the runtime.
+    pub fn new(mut queue: UdevQueue) -> Result<Self> {
+        queue.get_fd()?;
+
+        let queue = AsyncFd::new(QueueWatch(queue)).map_err(|err| {
+            let err_msg = format!("unable to register queue watch: {err}");
+            log::error!("{err_msg}");
+            Error::os(ErrorScope::Queue, err_msg, err)
+        })?;
+
+        Ok(Self {
+            queue,
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Gets a reference to the [UdevQueue].
+    pub fn queue(&self) -> &UdevQueue {
+        &self.queue.get_ref().0
+    }
+
+    /// Gets a mutable reference to the [UdevQueue].
+    pub fn queue_mut(&mut self) -> &mut UdevQueue {
+        &mut self.queue.get_mut().0
+    }
+
+    /// Converts the [QueueEventStream] into the [UdevQueue], deregistering it from the runtime.
+    pub fn into_queue(self) -> UdevQueue {
+        self.queue.into_inner().0
+    }
+
+    /// Waits for the next [QueueEvent].
+    ///
+    /// Returns: `Ok(QueueEvent)` on success, `Err(Error)` if the watch cannot be read.
+    pub async fn next_event(&mut self) -> Result<QueueEvent> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(event);
+            }
+
+            let mut guard = self.queue.readable_mut().await?;
+            let events = guard.get_inner_mut().0.read_events()?;
+            // the watch is read until it would block
+            guard.clear_ready();
+
+            self.pending.extend(events);
+        }
+    }
+}
+
+impl Stream for QueueEventStream {
+    type Item = Result<QueueEvent>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(event) = this.pending.pop_front() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+
+            let mut guard = match ready!(this.queue.poll_read_ready_mut(cx)) {
+                Ok(guard) => guard,
+                Err(err) => return Poll::Ready(Some(Err(err.into()))),
+            };
+            let events = guard.get_inner_mut().0.read_events();
+            // the watch is read until it would block
+            guard.clear_ready();
+
+            match events {
+                Ok(events) => this.pending.extend(events),
+                Err(err) => return Poll::Ready(Some(Err(err))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::SysfsFixture;
+    use crate::Udev;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::{fs, future, time};
+
+    #[tokio::test]
+    async fn test_queue_event_stream() -> Result<()> {
+        let fixture = SysfsFixture::new()?;
+        let dir = Path::new(fixture.run_path());
+
+        let udev = Arc::new(Udev::new().with_run_path(fixture.run_path()));
+        let mut events = QueueEventStream::new(UdevQueue::new(udev))?;
+        assert!(events.queue().fd() >= 0);
+
+        fs::write(dir.join("queue"), "")?;
+        fs::write(dir.join("control"), "")?;
+        fs::remove_file(dir.join("control"))?;
+        fs::remove_file(dir.join("queue"))?;
+
+        let event = tokio::time::timeout(time::Duration::from_secs(5), events.next_event())
+            .await
+            .map_err(|err| Error::UdevQueue(format!("{err}")))??;
+        assert_eq!(event, QueueEvent::Emptied);
+
+        fs::write(dir.join("queue"), "")?;
+        fs::remove_file(dir.join("queue"))?;
+
+        let event = future::poll_fn(|cx| Pin::new(&mut events).poll_next(cx)).await;
+        assert!(matches!(event, Some(Ok(QueueEvent::Emptied))));
+
+        Ok(())
+    }
+}
@@ -5,6 +5,7 @@ use std::{
 };
 
 use libc::{sockaddr_ll, sockaddr_nl};
+use nix::sys::socket::NetlinkAddr;
 
 use crate::{Error, Result};
 
@@ -78,4 +79,15 @@ impl UdevSocket {
             _ => Err(Error::Udev("socket: expected sockaddr_nl".into())),
         }
     }
+
+    /// Gets the [UdevSocket] as a [`NetlinkAddr`](nix::sys::socket::NetlinkAddr), e.g. for
+    /// [`sendmsg`](nix::sys::socket::sendmsg).
+    ///
+    /// Returns `Err(Error)` if not a [UdevSocket::Netlink] variant.
+    pub fn to_netlink_addr(&self) -> Result<NetlinkAddr> {
+        match self {
+            Self::Netlink(nl) => Ok(NetlinkAddr::new(nl.nl_pid, nl.nl_groups)),
+            _ => Err(Error::Udev("socket: expected sockaddr_nl".into())),
+        }
+    }
 }
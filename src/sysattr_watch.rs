@@ -0,0 +1,236 @@
+use std::io::{self, Read};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+use std::{ffi, fmt, fs};
+
+use crate::inotify;
+use crate::{Error, ErrorScope, Result, UdevDevice};
+
+/// Maximum length of a sys attribute value.
+const VALUE_LEN: usize = 4096;
+
+/// Watches a `sysfs` attribute file for modification events.
+///
+/// Useful for attributes that change without the kernel sending a uevent, e.g. `brightness`, or
+/// the `rfkill` `state`.
+///
+/// The watcher exposes a pollable file descriptor, so it can be added to the same event loop as
+/// a [UdevMonitor](crate::UdevMonitor).
+///
+/// **NOTE**: the kernel only reports changes for attributes that call `sysfs_notify`, or that
+/// are written from userspace.
+#[derive(Debug)]
+pub struct UdevSysattrWatch {
+    fd: OwnedFd,
+    wd: i32,
+    name: String,
+    path: String,
+}
+
+impl UdevSysattrWatch {
+    /// Creates a new [UdevSysattrWatch] for the `name` sys attribute of the [UdevDevice].
+    ///
+    /// The `inotify` file descriptor is non-blocking.
+    ///
+    /// Returns: `Ok(UdevSysattrWatch)` on success, `Err(Error)` otherwise.
+    pub fn new(device: &UdevDevice, name: &str) -> Result<Self> {
+        if name.is_empty() || name.split('/').any(|p| p == "..") {
+            return Err(Error::UdevDevice(format!("invalid sysattr name: {name}")));
+        }
+
+        let path = format!("{}/{name}", device.syspath());
+        let metadata = fs::metadata(path.as_str()).map_err(|err| {
+            let err_msg = format!("unable to watch sysattr: {path}, error: {err}");
+            log::debug!("{err_msg}");
+            Error::UdevDevice(err_msg)
+        })?;
+
+        if metadata.is_dir() {
+            return Err(Error::UdevDevice(format!(
+                "unable to watch sysattr: {path}, path is a directory"
+            )));
+        }
+
+        // SAFETY: the arguments are valid, and the return value is checked before use.
+        let raw_fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC | libc::IN_NONBLOCK) };
+        if raw_fd < 0 {
+            let errno = io::Error::last_os_error();
+            let err_msg = format!("unable to init inotify watch, error: {raw_fd}, errno: {errno}");
+            log::error!("{err_msg}");
+            return Err(Error::os(ErrorScope::Device, err_msg, errno));
+        }
+
+        // SAFETY: `raw_fd` is a valid, open file descriptor that is exclusively owned here.
+        let fd = unsafe { OwnedFd::from_raw_fd(raw_fd) };
+
+        let c_path = ffi::CString::new(path.as_str())?;
+        // SAFETY: arguments are valid, and pointers reference valid memory.
+        let wd = unsafe {
+            libc::inotify_add_watch(
+                fd.as_raw_fd(),
+                c_path.as_ptr(),
+                libc::IN_MODIFY | libc::IN_ATTRIB | libc::IN_DELETE_SELF,
+            )
+        };
+
+        if wd < 0 {
+            let errno = io::Error::last_os_error();
+            let err_msg =
+                format!("unable to add inotify watch: {path}, error: {wd}, errno: {errno}");
+            log::error!("{err_msg}");
+            Err(Error::os(ErrorScope::Device, err_msg, errno))
+        } else {
+            Ok(Self {
+                fd,
+                wd,
+                name: name.into(),
+                path,
+            })
+        }
+    }
+
+    /// Gets the name of the watched sys attribute.
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Gets the full `sysfs` path of the watched sys attribute.
+    pub fn path(&self) -> &str {
+        self.path.as_str()
+    }
+
+    /// Gets the `inotify` file descriptor.
+    ///
+    /// The descriptor becomes readable when the sys attribute changes.
+    pub fn get_fd(&self) -> i32 {
+        self.fd.as_raw_fd()
+    }
+
+    /// Drains pending events from the watch.
+    ///
+    /// Does not block, a return of `Ok(0)` means no changes occurred since the last call.
+    ///
+    /// Returns: `Ok(count)` with the number of modification events, `Err(Error)` otherwise.
+    pub fn receive_events(&self) -> Result<usize> {
+        let mut count = 0usize;
+
+        inotify::read_events(self.fd.as_raw_fd(), |event| {
+            if event.wd == self.wd {
+                if event.mask & libc::IN_DELETE_SELF != 0 {
+                    log::debug!("watched sysattr removed: {}", self.path);
+                }
+                if event.mask & (libc::IN_MODIFY | libc::IN_ATTRIB) != 0 {
+                    count = count.saturating_add(1);
+                }
+            }
+        })
+        .map_err(|errno| {
+            let err_msg = format!("unable to read sysattr watch events, errno: {errno}");
+            log::error!("{err_msg}");
+            Error::os(ErrorScope::Device, err_msg, errno)
+        })?;
+
+        Ok(count)
+    }
+
+    /// Reads the current value of the watched sys attribute.
+    ///
+    /// Unlike [UdevDevice::get_sysattr_value], the value is never cached.
+    ///
+    /// Returns: `Ok(String)` with the attribute value, `Err(Error)` otherwise.
+    pub fn read_value(&self) -> Result<String> {
+        let mut file = fs::File::open(self.path.as_str())?;
+        let mut value = [0u8; VALUE_LEN];
+        let read = file.read(&mut value)?;
+
+        Ok(std::str::from_utf8(value[..read].as_ref())
+            .unwrap_or("")
+            .trim_end_matches('\n')
+            .into())
+    }
+}
+
+impl AsRawFd for UdevSysattrWatch {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl AsFd for UdevSysattrWatch {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+#[cfg(feature = "mio")]
+impl mio::event::Source for UdevSysattrWatch {
+    fn register(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> io::Result<()> {
+        mio::unix::SourceFd(&self.as_raw_fd()).register(registry, token, interests)
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> io::Result<()> {
+        mio::unix::SourceFd(&self.as_raw_fd()).reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &mio::Registry) -> io::Result<()> {
+        mio::unix::SourceFd(&self.as_raw_fd()).deregister(registry)
+    }
+}
+
+impl fmt::Display for UdevSysattrWatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path)
+    }
+}
+
+impl UdevDevice {
+    /// Watches the `name` sys attribute for modification events.
+    ///
+    /// See [UdevSysattrWatch] for details.
+    ///
+    /// Returns: `Ok(UdevSysattrWatch)` on success, `Err(Error)` otherwise.
+    pub fn watch_sysattr(&self, name: &str) -> Result<UdevSysattrWatch> {
+        UdevSysattrWatch::new(self, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FixtureDevice, SysfsFixture};
+    use std::path::Path;
+
+    #[test]
+    fn test_watch_sysattr() -> Result<()> {
+        let devpath = "/devices/virtual/leds/led0";
+        let fixture = SysfsFixture::new()?
+            .with_device(FixtureDevice::new(devpath, "leds").with_sysattr("brightness", "1\n"))?;
+
+        let device = fixture.device(devpath)?;
+        let attr = Path::new(device.syspath()).join("brightness");
+
+        assert!(device.watch_sysattr("").is_err());
+        assert!(device.watch_sysattr("missing").is_err());
+
+        let watch = device.watch_sysattr("brightness")?;
+        assert_eq!(watch.name(), "brightness");
+        assert_eq!(watch.receive_events()?, 0);
+
+        fs::write(&attr, "42\n")?;
+
+        assert!(watch.receive_events()? > 0);
+        assert_eq!(watch.read_value()?, "42");
+        assert_eq!(watch.receive_events()?, 0);
+
+        Ok(())
+    }
+}
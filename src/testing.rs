@@ -0,0 +1,475 @@
+//! Fake `sysfs` trees for testing code that uses the library, without root or real hardware.
+//!
+//! [SysfsFixture] lays out a temporary directory with the `sys`, `dev`, and `udev` run paths,
+//! and a [Udev] context reading from it. Devices are described with [FixtureDevice]:
+//!
+//! ```
+//! # use udevrs::testing::{FixtureDevice, SysfsFixture};
+//! # use udevrs::UdevEnumerate;
+//! # fn main() -> udevrs::Result<()> {
+//! let fixture = SysfsFixture::new()?.with_device(
+//!     FixtureDevice::new("/devices/virtual/block/loop0", "block")
+//!         .with_devnum(7, 0)
+//!         .with_devtype("disk")
+//!         .with_sysattr("size", "0")
+//!         .with_db_property("ID_FS_TYPE", "ext4")
+//!         .with_tag("systemd"),
+//! )?;
+//!
+//! let mut enumerate = UdevEnumerate::new(fixture.udev());
+//! enumerate.add_match_subsystem("block")?;
+//! enumerate.scan_devices()?;
+//! assert_eq!(enumerate.devices().len(), 1);
+//!
+//! let dev = fixture.device("/devices/virtual/block/loop0")?;
+//! assert_eq!(dev.get_devnode(), Some("loop0"));
+//! assert_eq!(dev.get_sysattr_value("size").as_deref(), Some("0"));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The temporary directory is removed when the [SysfsFixture] is dropped.
+//!
+//! Requires the `testing` feature, outside of the library and integration tests.
+
+use std::fmt::Write;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::{env, fs, process};
+
+use crate::{DevPath, Result, SysPath, Udev, UdevDevice};
+
+static FIXTURE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Describes a device in a [SysfsFixture].
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FixtureDevice {
+    devpath: String,
+    subsystem: String,
+    bus: bool,
+    devtype: String,
+    driver: String,
+    devnum: Option<(u32, u32)>,
+    devname: String,
+    ifindex: i32,
+    properties: Vec<(String, String)>,
+    sysattrs: Vec<(String, String)>,
+    db_properties: Vec<(String, String)>,
+    devlinks: Vec<String>,
+    tags: Vec<String>,
+    initialized: bool,
+}
+
+impl FixtureDevice {
+    /// Creates a new [FixtureDevice] at the `devpath`, e.g. `/devices/virtual/net/lo`, in the
+    /// `subsystem`.
+    pub fn new<P: Into<String>, S: Into<String>>(devpath: P, subsystem: S) -> Self {
+        Self {
+            devpath: devpath.into(),
+            subsystem: subsystem.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Gets the devpath.
+    pub fn devpath(&self) -> &str {
+        self.devpath.as_str()
+    }
+
+    /// Gets the subsystem.
+    pub fn subsystem(&self) -> &str {
+        self.subsystem.as_str()
+    }
+
+    /// Gets whether the subsystem is a bus, e.g. `pci`, instead of a class, e.g. `net`.
+    pub const fn bus(&self) -> bool {
+        self.bus
+    }
+
+    /// Builder function that sets whether the subsystem is a bus.
+    ///
+    /// Bus devices are linked from `/sys/bus/<subsystem>/devices`, class devices from
+    /// `/sys/class/<subsystem>`.
+    pub fn with_bus(mut self, bus: bool) -> Self {
+        self.bus = bus;
+        self
+    }
+
+    /// Builder function that sets the `DEVTYPE` of the `uevent` file.
+    pub fn with_devtype<T: Into<String>>(mut self, devtype: T) -> Self {
+        self.devtype = devtype.into();
+        self
+    }
+
+    /// Builder function that sets the driver, linked from `/sys/bus/<subsystem>/drivers`.
+    pub fn with_driver<D: Into<String>>(mut self, driver: D) -> Self {
+        self.driver = driver.into();
+        self
+    }
+
+    /// Builder function that sets the device number.
+    ///
+    /// The device is linked from `/sys/dev/block`, for the `block` subsystem, or
+    /// `/sys/dev/char`. Devices with a device number get the sysname as default `DEVNAME`.
+    pub fn with_devnum(mut self, major: u32, minor: u32) -> Self {
+        self.devnum = Some((major, minor));
+        self
+    }
+
+    /// Builder function that sets the `DEVNAME` of the `uevent` file, relative to `/dev`.
+    pub fn with_devname<N: Into<String>>(mut self, devname: N) -> Self {
+        self.devname = devname.into();
+        self
+    }
+
+    /// Builder function that sets the `IFINDEX` of the `uevent` file.
+    pub fn with_ifindex(mut self, ifindex: i32) -> Self {
+        self.ifindex = ifindex;
+        self
+    }
+
+    /// Builder function that adds a property to the `uevent` file.
+    pub fn with_property<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.properties.push((key.into(), value.into()));
+        self
+    }
+
+    /// Builder function that adds a sys attribute file.
+    pub fn with_sysattr<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+        self.sysattrs.push((name.into(), value.into()));
+        self
+    }
+
+    /// Builder function that adds a property to the `udev` database entry.
+    pub fn with_db_property<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.db_properties.push((key.into(), value.into()));
+        self
+    }
+
+    /// Builder function that adds a device node symlink, relative to `/dev`, to the `udev`
+    /// database entry.
+    pub fn with_devlink<L: Into<String>>(mut self, devlink: L) -> Self {
+        self.devlinks.push(devlink.into());
+        self
+    }
+
+    /// Builder function that adds a tag to the `udev` database entry, and the tags index.
+    pub fn with_tag<T: Into<String>>(mut self, tag: T) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Builder function that sets whether the device has a `udev` database entry, even without
+    /// database properties, devlinks, or tags.
+    pub fn with_initialized(mut self, initialized: bool) -> Self {
+        self.initialized = initialized;
+        self
+    }
+
+    // Gets whether the device has a database entry.
+    fn has_db(&self) -> bool {
+        self.initialized
+            || !self.db_properties.is_empty()
+            || !self.devlinks.is_empty()
+            || !self.tags.is_empty()
+    }
+
+    fn uevent_string(&self, sysname: &str) -> String {
+        let mut uevent = String::new();
+
+        // writing to a `String` cannot fail
+        if let Some((major, minor)) = self.devnum {
+            writeln!(uevent, "MAJOR={major}\nMINOR={minor}").ok();
+        }
+        match (self.devname.as_str(), self.devnum) {
+            ("", Some(_)) => writeln!(uevent, "DEVNAME={sysname}").ok(),
+            ("", None) => None,
+            (devname, _) => writeln!(uevent, "DEVNAME={devname}").ok(),
+        };
+        if !self.devtype.is_empty() {
+            writeln!(uevent, "DEVTYPE={}", self.devtype).ok();
+        }
+        if !self.driver.is_empty() {
+            writeln!(uevent, "DRIVER={}", self.driver).ok();
+        }
+        if self.ifindex > 0 {
+            writeln!(uevent, "IFINDEX={}", self.ifindex).ok();
+        }
+        for (key, value) in self.properties.iter() {
+            writeln!(uevent, "{key}={value}").ok();
+        }
+
+        uevent
+    }
+
+    fn db_string(&self) -> String {
+        let mut db = String::new();
+
+        for devlink in self.devlinks.iter() {
+            writeln!(db, "S:{devlink}").ok();
+        }
+        writeln!(db, "I:1").ok();
+        for (key, value) in self.db_properties.iter() {
+            writeln!(db, "E:{key}={value}").ok();
+        }
+        for tag in self.tags.iter() {
+            writeln!(db, "G:{tag}").ok();
+        }
+
+        db
+    }
+}
+
+/// Temporary fake `sysfs` tree, with a [Udev] context reading from it.
+///
+/// See the [module documentation](self) for an example.
+#[derive(Debug)]
+pub struct SysfsFixture {
+    root: PathBuf,
+    udev: Arc<Udev>,
+}
+
+impl SysfsFixture {
+    /// Creates a new, empty [SysfsFixture] in the temporary directory.
+    ///
+    /// Returns: `Ok(SysfsFixture)` on success, `Err(Error)` if the directories cannot be created.
+    pub fn new() -> Result<Self> {
+        let count = FIXTURE_COUNT.fetch_add(1, Ordering::Relaxed);
+        let root = env::temp_dir().join(format!("udevrs-fixture-{}-{count}", process::id()));
+
+        for dir in [
+            "sys/devices",
+            "sys/bus",
+            "sys/class",
+            "sys/dev/block",
+            "sys/dev/char",
+            "dev",
+            "run/udev/data",
+            "run/udev/tags",
+        ] {
+            fs::create_dir_all(root.join(dir))?;
+        }
+
+        let udev = Udev::new()
+            .with_sys_path(path_str(&root.join("sys")))
+            .with_dev_path(path_str(&root.join("dev")))
+            .with_run_path(path_str(&root.join("run/udev")));
+
+        Ok(Self {
+            root,
+            udev: Arc::new(udev),
+        })
+    }
+
+    /// Gets the root directory of the fixture.
+    pub fn root(&self) -> &Path {
+        self.root.as_path()
+    }
+
+    /// Gets the `sys` path of the fixture.
+    pub fn sys_path(&self) -> &str {
+        self.udev.sys_path()
+    }
+
+    /// Gets the `dev` path of the fixture.
+    pub fn dev_path(&self) -> &str {
+        self.udev.dev_path()
+    }
+
+    /// Gets the `udev` run path of the fixture.
+    pub fn run_path(&self) -> &str {
+        self.udev.run_path()
+    }
+
+    /// Gets the [Udev] context reading from the fixture.
+    pub fn udev(&self) -> Arc<Udev> {
+        Arc::clone(&self.udev)
+    }
+
+    /// Adds a device to the fixture.
+    ///
+    /// Writes the device directory with the `uevent` file, and sys attributes, the `subsystem`,
+    /// `driver`, `class` or `bus`, and `dev` symlinks, and the `udev` database entry.
+    ///
+    /// Returns: `Ok(SysPath)` of the device on success, `Err(Error)` otherwise.
+    pub fn add_device(&self, device: &FixtureDevice) -> Result<SysPath> {
+        let devpath = DevPath::new(device.devpath())?;
+        let syspath = devpath.syspath_in(self.sys_path());
+        let sysname = devpath.sysname();
+        let subsystem = device.subsystem();
+
+        let dir = Path::new(syspath.as_str());
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join("uevent"), device.uevent_string(sysname))?;
+
+        for (name, value) in device.sysattrs.iter() {
+            let path = dir.join(name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, value)?;
+        }
+
+        // relative prefix from the device directory to the sys mount point
+        let up = "../".repeat(syspath.subdir().split('/').count());
+        let target = &devpath.as_str()[1..];
+
+        let subsystem_dir = if device.bus() {
+            let dir = format!("bus/{subsystem}");
+            self.link(
+                &format!("../../../{target}"),
+                &format!("{dir}/devices/{sysname}"),
+            )?;
+            dir
+        } else {
+            let dir = format!("class/{subsystem}");
+            self.link(&format!("../../{target}"), &format!("{dir}/{sysname}"))?;
+            dir
+        };
+        symlink(format!("{up}{subsystem_dir}"), dir.join("subsystem"))?;
+
+        if !device.driver.is_empty() {
+            let driver_dir = format!("bus/{subsystem}/drivers/{}", device.driver);
+            fs::create_dir_all(Path::new(self.sys_path()).join(driver_dir.as_str()))?;
+            symlink(format!("{up}{driver_dir}"), dir.join("driver"))?;
+        }
+
+        if let Some((major, minor)) = device.devnum {
+            let kind = if subsystem == "block" {
+                "block"
+            } else {
+                "char"
+            };
+            self.link(
+                &format!("../../{target}"),
+                &format!("dev/{kind}/{major}:{minor}"),
+            )?;
+        }
+
+        if device.has_db() {
+            let dev = UdevDevice::new_from_syspath(self.udev(), &syspath)?;
+            let id = dev.get_id_filename();
+
+            fs::write(
+                Path::new(self.run_path()).join("data").join(id),
+                device.db_string(),
+            )?;
+
+            for tag in device.tags.iter() {
+                let tag_dir = Path::new(self.run_path()).join("tags").join(tag);
+                fs::create_dir_all(tag_dir.as_path())?;
+                fs::write(tag_dir.join(id), "")?;
+            }
+        }
+
+        Ok(syspath)
+    }
+
+    /// Builder function that adds a device to the fixture.
+    ///
+    /// See [add_device](Self::add_device).
+    pub fn with_device(self, device: FixtureDevice) -> Result<Self> {
+        self.add_device(&device)?;
+        Ok(self)
+    }
+
+    /// Builder function that adds several devices to the fixture, in order.
+    ///
+    /// See [add_device](Self::add_device).
+    pub fn with_devices<I: IntoIterator<Item = FixtureDevice>>(self, devices: I) -> Result<Self> {
+        for device in devices {
+            self.add_device(&device)?;
+        }
+        Ok(self)
+    }
+
+    /// Creates the [UdevDevice] at the `devpath` in the fixture.
+    pub fn device(&self, devpath: &str) -> Result<UdevDevice> {
+        let syspath = DevPath::new(devpath)?.syspath_in(self.sys_path());
+        UdevDevice::new_from_syspath(self.udev(), &syspath)
+    }
+
+    // Creates a symlink to `target`, at `link` relative to the sys mount point.
+    fn link(&self, target: &str, link: &str) -> Result<()> {
+        let link = Path::new(self.sys_path()).join(link);
+        if let Some(parent) = link.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        symlink(target, link)?;
+        Ok(())
+    }
+}
+
+impl Drop for SysfsFixture {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_dir_all(self.root.as_path()) {
+            log::warn!("unable to remove fixture {}: {err}", self.root.display());
+        }
+    }
+}
+
+fn path_str(path: &Path) -> &str {
+    path.to_str().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UdevEnumerate;
+
+    #[test]
+    fn test_sysfs_fixture() -> Result<()> {
+        let fixture = SysfsFixture::new()?
+            .with_device(
+                FixtureDevice::new("/devices/pci0000:00/0000:00:1f.2", "pci")
+                    .with_bus(true)
+                    .with_driver("ahci")
+                    .with_property("PCI_ID", "8086:2922")
+                    .with_sysattr("vendor", "0x8086\n"),
+            )?
+            .with_device(
+                FixtureDevice::new("/devices/pci0000:00/0000:00:1f.2/block/sda", "block")
+                    .with_devnum(8, 0)
+                    .with_devtype("disk")
+                    .with_db_property("ID_SERIAL", "mock_disk")
+                    .with_devlink("disk/by-id/mock_disk")
+                    .with_tag("systemd"),
+            )?;
+        let root = fixture.root().to_owned();
+
+        let mut enumerate = UdevEnumerate::new(fixture.udev());
+        enumerate.scan_devices()?;
+        assert_eq!(enumerate.devices().len(), 2);
+
+        let mut disk = fixture.device("/devices/pci0000:00/0000:00:1f.2/block/sda")?;
+        assert_eq!(disk.get_subsystem(), "block");
+        assert_eq!(disk.get_devtype(), "disk");
+        assert_eq!(disk.get_devnum(), libc::makedev(8, 0));
+        assert_eq!(disk.get_id_filename(), "b8:0");
+        assert!(disk.has_db_entry());
+
+        let db = fs::read_to_string(format!("{}/data/b8:0", fixture.run_path()))?;
+        let mut db_dev = UdevDevice::from_db_string(fixture.udev(), db.as_str())?;
+        assert_eq!(db_dev.get_property_value("ID_SERIAL"), Some("mock_disk"));
+        assert!(db_dev.has_tag("systemd"));
+        assert!(db_dev
+            .devlinks_list()
+            .entry_by_name(&format!("{}/disk/by-id/mock_disk", fixture.dev_path()))
+            .is_some());
+
+        let pci = disk.parent()?;
+        assert!(pci.syspath().ends_with("/0000:00:1f.2"));
+        assert_eq!(pci.get_subsystem(), "pci");
+        assert_eq!(pci.get_driver(), Some("ahci"));
+        assert_eq!(pci.get_sysattr_value("vendor").as_deref(), Some("0x8086\n"));
+        assert!(disk.parent_with_subsystem_devtype("pci", "").is_ok());
+
+        drop(fixture);
+        assert!(fs::metadata(root).is_err());
+
+        Ok(())
+    }
+}
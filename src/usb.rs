@@ -0,0 +1,190 @@
+//! Lookup of USB devices opened by USB libraries.
+//!
+//! USB libraries, e.g. `nusb` or `rusb`, identify devices by the bus number, and either the
+//! device address, or the port path. The helpers map these identifiers to the [UdevDevice] of the
+//! `usb_device`, to access the serial numbers, persistent paths, and hardware database names.
+//!
+//! ```no_run
+//! # use std::sync::Arc;
+//! # use udevrs::{usb, Udev};
+//! # fn main() -> udevrs::Result<()> {
+//! let udev = Arc::new(Udev::new());
+//!
+//! // e.g. `nusb::DeviceInfo::bus_number`, and `nusb::DeviceInfo::device_address`
+//! let mut dev = usb::find_by_busnum_devnum(Arc::clone(&udev), 1, 4)?;
+//! println!("serial: {:?}", dev.get_sysattr_value("serial"));
+//!
+//! // e.g. `rusb::Device::bus_number`, and `rusb::Device::port_numbers`
+//! let dev = usb::find_by_port_path(udev, 1, &[2, 1])?;
+//! println!("syspath: {}", dev.syspath());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{fs, sync::Arc};
+
+use crate::{Error, Result, SysPath, Udev, UdevDevice};
+
+/// Character device major number of USB devices.
+pub const USB_DEVICE_MAJOR: u32 = 189;
+/// Maximum number of devices on a USB bus.
+const USB_BUS_DEVICES: u32 = 128;
+
+/// Finds the `usb_device` with the bus number and device address.
+///
+/// Uses the `/sys/dev/char` link of the USB device node, and falls back to scanning the USB
+/// devices if the link is missing, or does not match.
+///
+/// Returns: `Ok(UdevDevice)` on success, `Err(Error)` if no matching device is found.
+pub fn find_by_busnum_devnum(udev: Arc<Udev>, busnum: u8, devnum: u8) -> Result<UdevDevice> {
+    if busnum == 0 || devnum == 0 {
+        return Err(Error::UdevDevice(format!(
+            "invalid usb device, bus: {busnum}, address: {devnum}"
+        )));
+    }
+
+    let minor = (u32::from(busnum) - 1) * USB_BUS_DEVICES + (u32::from(devnum) - 1);
+    let link = format!(
+        "{}/dev/char/{USB_DEVICE_MAJOR}:{minor}",
+        udev.get_sys_path()
+    );
+
+    if let Ok(mut dev) = new_from_link(Arc::clone(&udev), link.as_str()) {
+        if matches_busnum_devnum(&mut dev, busnum, devnum) {
+            return Ok(dev);
+        }
+    }
+
+    usb_devices(udev.as_ref())
+        .filter_map(|path| new_from_link(Arc::clone(&udev), path.as_str()).ok())
+        .find_map(|mut dev| matches_busnum_devnum(&mut dev, busnum, devnum).then_some(dev))
+        .ok_or(Error::UdevDevice(format!(
+            "no usb device found, bus: {busnum}, address: {devnum}"
+        )))
+}
+
+/// Finds the `usb_device` with the bus number and port path.
+///
+/// The `ports` are the port numbers from the root hub to the device, e.g. `[2, 1]` for
+/// `1-2.1`. An empty port path refers to the root hub, e.g. `usb1`.
+///
+/// Returns: `Ok(UdevDevice)` on success, `Err(Error)` if no matching device is found.
+pub fn find_by_port_path(udev: Arc<Udev>, busnum: u8, ports: &[u8]) -> Result<UdevDevice> {
+    let sysname = usb_sysname(busnum, ports);
+    let link = format!("{}/bus/usb/devices/{sysname}", udev.get_sys_path());
+
+    new_from_link(udev, link.as_str())
+        .map_err(|err| Error::UdevDevice(format!("no usb device found: {sysname}, {err}")))
+}
+
+/// Gets the kernel name of the `usb_device` with the bus number and port path.
+///
+/// Returns: the sysname, e.g. `1-2.1`, or `usb1` for the root hub.
+pub fn usb_sysname(busnum: u8, ports: &[u8]) -> String {
+    if ports.is_empty() {
+        format!("usb{busnum}")
+    } else {
+        let ports: Vec<String> = ports.iter().map(|p| p.to_string()).collect();
+        format!("{busnum}-{}", ports.join("."))
+    }
+}
+
+// Creates the device from a sys link, resolved to the path under `/sys/devices`.
+fn new_from_link(udev: Arc<Udev>, link: &str) -> Result<UdevDevice> {
+    let syspath = SysPath::new_in(udev.get_sys_path(), link)?.canonicalize()?;
+
+    let mut dev = UdevDevice::new_from_syspath(udev, &syspath)?;
+    dev.set_sysname(syspath.sysname());
+
+    Ok(dev)
+}
+
+fn matches_busnum_devnum(dev: &mut UdevDevice, busnum: u8, devnum: u8) -> bool {
+    let sysattr_num = |dev: &mut UdevDevice, attr: &str| {
+        dev.get_sysattr_value(attr)
+            .and_then(|v| v.trim().parse::<u8>().ok())
+    };
+
+    dev.get_devtype() == "usb_device"
+        && sysattr_num(dev, "busnum") == Some(busnum)
+        && sysattr_num(dev, "devnum") == Some(devnum)
+}
+
+// Gets the paths of the USB devices, skipping interfaces, e.g. `1-2:1.0`.
+fn usb_devices(udev: &Udev) -> impl Iterator<Item = String> {
+    let dir = format!("{}/bus/usb/devices", udev.get_sys_path());
+
+    fs::read_dir(dir.as_str())
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.file_name().to_str().map(String::from))
+        .filter(|name| !name.contains(':'))
+        .map(move |name| format!("{dir}/{name}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FixtureDevice, SysfsFixture};
+
+    const ROOT_HUB: &str = "/devices/pci0000:00/0000:00:14.0/usb1";
+
+    // Root hub, a hub with a `/sys/dev/char` link, a device without one, and an interface.
+    fn fixture() -> Result<SysfsFixture> {
+        let device = |sysname: &str, devnum: u32| {
+            FixtureDevice::new(format!("{ROOT_HUB}/{sysname}"), "usb")
+                .with_bus(true)
+                .with_devtype("usb_device")
+                .with_sysattr("busnum", "1\n")
+                .with_sysattr("devnum", format!("{devnum}\n"))
+        };
+
+        SysfsFixture::new()?.with_devices([
+            FixtureDevice::new(ROOT_HUB, "usb")
+                .with_bus(true)
+                .with_devtype("usb_device")
+                .with_devnum(USB_DEVICE_MAJOR, 0)
+                .with_sysattr("busnum", "1\n")
+                .with_sysattr("devnum", "1\n"),
+            device("1-2", 2).with_devnum(USB_DEVICE_MAJOR, 1),
+            device("1-2/1-2.1", 3),
+            FixtureDevice::new(format!("{ROOT_HUB}/1-2/1-2.1/1-2.1:1.0"), "usb")
+                .with_bus(true)
+                .with_devtype("usb_interface"),
+        ])
+    }
+
+    #[test]
+    fn test_usb_lookup() -> Result<()> {
+        assert_eq!(usb_sysname(1, &[]), "usb1");
+        assert_eq!(usb_sysname(1, &[2]), "1-2");
+        assert_eq!(usb_sysname(3, &[2, 1, 4]), "3-2.1.4");
+
+        let fixture = fixture()?;
+        let udev = fixture.udev();
+
+        assert!(find_by_busnum_devnum(Arc::clone(&udev), 0, 1).is_err());
+
+        let root_hub = find_by_port_path(Arc::clone(&udev), 1, &[])?;
+        assert_eq!(root_hub.sysname(), "usb1");
+        assert!(root_hub.syspath().ends_with(ROOT_HUB));
+
+        let hub = find_by_port_path(Arc::clone(&udev), 1, &[2])?;
+        let device = find_by_port_path(Arc::clone(&udev), 1, &[2, 1])?;
+        assert_eq!(device.sysname(), "1-2.1");
+        assert!(find_by_port_path(Arc::clone(&udev), 1, &[3]).is_err());
+
+        // found by the `/sys/dev/char` link
+        let dev = find_by_busnum_devnum(Arc::clone(&udev), 1, 1)?;
+        assert_eq!(dev.syspath(), root_hub.syspath());
+        assert_eq!(dev.sysname(), "usb1");
+        assert_eq!(find_by_busnum_devnum(Arc::clone(&udev), 1, 2)?, hub);
+
+        // found by scanning the USB devices
+        assert_eq!(find_by_busnum_devnum(Arc::clone(&udev), 1, 3)?, device);
+        assert!(find_by_busnum_devnum(udev, 1, 4).is_err());
+
+        Ok(())
+    }
+}
@@ -1,46 +1,28 @@
-use std::fs;
+use std::io::BufRead;
 
-use crate::{murmur_hash2, Error, Result, Udev};
+use crate::Result;
 
-pub(crate) const LINE_SIZE: usize = 16384;
+/// Iterates over the lines of a `uevent`, or database file.
+///
+/// Invalid UTF-8 is replaced, instead of ending the iteration, so one bad value does not drop
+/// the following lines. Iteration ends at the end of the input, or at the first read error.
+pub(crate) fn read_lines<R: BufRead>(reader: R) -> impl Iterator<Item = String> {
+    reader
+        .split(b'\n')
+        .map_while(|line| line.ok())
+        .map(|mut line| {
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            String::from_utf8_lossy(&line).into_owned()
+        })
+}
 
 mod device_nodes;
+mod sys_link;
 
 pub use device_nodes::*;
-
-impl Udev {
-    pub(crate) fn get_sys_core_link_value(slink: &str, syspath: &str) -> Result<String> {
-        let path = format!("{syspath}/{slink}");
-        let link = fs::read_link(path)?;
-
-        // get the basename of the symlinked target
-        Ok(link
-            .components()
-            .last()
-            .ok_or(Error::Io("empty sys core link value".into()))?
-            .as_os_str()
-            .to_str()
-            .ok_or(Error::Io(
-                "sys core link OS string contains non-Unicode bytes".into(),
-            ))?
-            .to_owned())
-    }
-}
-
-/// Compute a MurMurHash over the provided string.
-pub fn string_hash32(s: &str) -> u32 {
-    murmur_hash2(s.as_bytes(), 0)
-}
-
-/// Gets a bunch of bit numbers out of the hash, and sets the bits into a bitfield.
-pub fn string_bloom64(s: &str) -> u64 {
-    let hash = string_hash32(s);
-
-    (1u64 << (hash & 63))
-        | (1u64 << ((hash >> 6) & 63))
-        | (1u64 << ((hash >> 12) & 63))
-        | (1u64 << ((hash >> 18) & 63))
-}
+pub use sys_link::*;
 
 /// Gets the major part of the device number.
 pub fn major(dev: libc::dev_t) -> u16 {
@@ -65,3 +47,62 @@ pub fn minor(dev: libc::dev_t) -> u16 {
 pub fn encode_string(arg: &str) -> Result<String> {
     encode_devnode_name(arg)
 }
+
+/// Encodes a path into a single file name.
+///
+/// From the `libudev` documentation:
+///
+/// ```no_build,no_run
+/// Encode all '/' and '\' characters of a path to the corresponding
+/// 2 char hex value prefixed by '\x'.
+/// ```
+pub fn path_encode(arg: &str) -> String {
+    let mut ret = String::with_capacity(arg.len());
+
+    for c in arg.chars() {
+        match c {
+            '/' => ret.push_str("\\x2f"),
+            '\\' => ret.push_str("\\x5c"),
+            _ => ret.push(c),
+        }
+    }
+
+    ret
+}
+
+/// Replaces whitespace in the provided string.
+///
+/// Leading and trailing whitespace is removed, and every run of inner whitespace is replaced
+/// with a single `_` character.
+pub fn replace_whitespace(arg: &str) -> String {
+    arg.split_whitespace().collect::<Vec<&str>>().join("_")
+}
+
+/// Replaces potentially unsafe characters in the provided string.
+///
+/// From the `libudev` documentation:
+///
+/// ```no_build,no_run
+/// Allow only whitelisted characters (plus the characters in `white`), hex encoding (`\x`),
+/// and valid UTF-8 sequences. Everything else is replaced with '_'.
+/// ```
+pub fn replace_chars(arg: &str, white: &str) -> String {
+    let mut ret = String::with_capacity(arg.len());
+    let mut chars = arg.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.len_utf8() > 1 || whitelisted_char_for_devnode(c, white) {
+            ret.push(c);
+        } else if c == '\\' && chars.peek() == Some(&'x') {
+            // allow hex encoding
+            ret.push(c);
+        } else if c.is_whitespace() && white.contains(' ') {
+            // if space is allowed, replace whitespace with ordinary space
+            ret.push(' ');
+        } else {
+            ret.push('_');
+        }
+    }
+
+    ret
+}
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::ffi::{self, OsStr};
+use std::os::fd::{AsFd, AsRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::{fs, io};
+
+use crate::{Error, ErrorScope, Result, Udev};
+
+impl Udev {
+    /// Gets the value of a core sys link, e.g. `subsystem`, or `driver`, of a sys device.
+    ///
+    /// The value is the basename of the link target, e.g. `block` for
+    /// `subsystem -> ../../../../class/block`.
+    ///
+    /// Returns: `Ok(String)` with the link value, `Err(Error)` with the link path, and the OS
+    /// error, if the link cannot be read.
+    pub fn get_sys_core_link_value(slink: &str, syspath: &str) -> Result<String> {
+        let path = format!("{syspath}/{slink}");
+        let link = fs::read_link(path.as_str())
+            .map_err(|err| Error::UdevUtil(format!("unable to read link {path}: {err}")))?;
+
+        link_basename(link.as_os_str(), path.as_str())
+    }
+
+    /// Gets the value of a core sys link, relative to an open sys device directory.
+    ///
+    /// Avoids resolving the full sys device path on every call, e.g. when reading several links
+    /// of the same device. See [SysDir] for a caching wrapper.
+    ///
+    /// Returns: `Ok(String)` with the link value, `Err(Error)` with the link name, and the OS
+    /// error, if the link cannot be read.
+    pub fn get_sys_core_link_value_at<D: AsFd>(dir: D, slink: &str) -> Result<String> {
+        let name = ffi::CString::new(slink)?;
+        let mut buf = [0u8; libc::PATH_MAX as usize];
+
+        // SAFETY: the directory file descriptor is valid for the lifetime of the borrow, `name`
+        // is a nul-terminated string, and `buf` references valid memory of the passed length.
+        let ret = unsafe {
+            libc::readlinkat(
+                dir.as_fd().as_raw_fd(),
+                name.as_ptr(),
+                buf.as_mut_ptr().cast(),
+                buf.len(),
+            )
+        };
+
+        if ret < 0 {
+            let errno = io::Error::last_os_error();
+            return Err(Error::os(
+                ErrorScope::Util,
+                format!("unable to read link {slink}: {errno}"),
+                errno,
+            ));
+        }
+
+        // a full buffer means the link target may be truncated
+        let len = ret as usize;
+        if len >= buf.len() {
+            return Err(Error::UdevUtil(format!("link target too long: {slink}")));
+        }
+
+        link_basename(OsStr::from_bytes(&buf[..len]), slink)
+    }
+}
+
+/// Open sys device directory, caching the values of core sys links.
+///
+/// Reads links relative to the directory file descriptor with
+/// [Udev::get_sys_core_link_value_at], and keeps the values until they are invalidated, e.g.
+/// after a `bind`, or `unbind`, event changes the `driver` link.
+#[derive(Debug)]
+pub struct SysDir {
+    path: String,
+    fd: OwnedFd,
+    links: HashMap<String, String>,
+}
+
+impl SysDir {
+    /// Opens the sys device directory at `syspath`.
+    ///
+    /// Returns: `Ok(SysDir)` on success, `Err(Error)` if the directory cannot be opened.
+    pub fn open(syspath: &str) -> Result<Self> {
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_PATH | libc::O_DIRECTORY | libc::O_CLOEXEC)
+            .open(syspath)
+            .map_err(|err| Error::UdevUtil(format!("unable to open {syspath}: {err}")))?;
+
+        Ok(Self {
+            path: syspath.into(),
+            fd: file.into(),
+            links: HashMap::new(),
+        })
+    }
+
+    /// Gets the path of the sys device directory.
+    pub fn path(&self) -> &str {
+        self.path.as_str()
+    }
+
+    /// Gets the value of a core sys link, reading it only on the first call.
+    ///
+    /// Failed reads are not cached.
+    ///
+    /// Returns: `Ok(&str)` with the link value, `Err(Error)` if the link cannot be read.
+    pub fn link_value(&mut self, slink: &str) -> Result<&str> {
+        if !self.links.contains_key(slink) {
+            let value = Udev::get_sys_core_link_value_at(&self.fd, slink)?;
+            self.links.insert(slink.into(), value);
+        }
+
+        Ok(self
+            .links
+            .get(slink)
+            .map(|v| v.as_str())
+            .unwrap_or_default())
+    }
+
+    /// Removes the cached value of a core sys link, so the next call reads it again.
+    pub fn invalidate(&mut self, slink: &str) {
+        self.links.remove(slink);
+    }
+
+    /// Removes all cached link values.
+    pub fn clear(&mut self) {
+        self.links.clear();
+    }
+}
+
+impl AsFd for SysDir {
+    fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+// Gets the basename of the symlinked target.
+fn link_basename(link: &OsStr, path: &str) -> Result<String> {
+    Path::new(link)
+        .file_name()
+        .ok_or(Error::UdevUtil(format!(
+            "empty sys core link value: {path}"
+        )))?
+        .to_str()
+        .map(String::from)
+        .ok_or(Error::UdevUtil(format!(
+            "sys core link contains non-Unicode bytes: {path}"
+        )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FixtureDevice, SysfsFixture};
+    use std::os::unix::fs as unix_fs;
+
+    #[test]
+    fn test_sys_core_link_value() -> Result<()> {
+        let fixture = SysfsFixture::new()?;
+        let syspath =
+            fixture.add_device(&FixtureDevice::new("/devices/virtual/block/loop0", "block"))?;
+        let syspath = syspath.as_str();
+        let dir = Path::new(syspath);
+
+        assert_eq!(
+            Udev::get_sys_core_link_value("subsystem", syspath)?,
+            "block"
+        );
+        assert!(Udev::get_sys_core_link_value("driver", syspath).is_err());
+
+        let mut sys_dir = SysDir::open(syspath)?;
+        assert_eq!(
+            Udev::get_sys_core_link_value_at(&sys_dir, "subsystem")?,
+            "block"
+        );
+        assert_eq!(sys_dir.link_value("subsystem")?, "block");
+
+        // cached values survive link changes until invalidated
+        fs::remove_file(dir.join("subsystem"))?;
+        unix_fs::symlink("../../../bus/usb", dir.join("subsystem"))?;
+        assert_eq!(sys_dir.link_value("subsystem")?, "block");
+
+        sys_dir.invalidate("subsystem");
+        assert_eq!(sys_dir.link_value("subsystem")?, "usb");
+        assert!(sys_dir.link_value("driver").is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,334 @@
+//! Device node watches, like the `udevd` `OPTIONS+="watch"` handling.
+//!
+//! `udevd` watches the device nodes of, e.g., block devices for `IN_CLOSE_WRITE`, and synthesizes
+//! a `change` event when a writer closes the node, e.g. after partitioning a disk.
+//!
+//! Watch descriptors are numbered per `inotify` instance, so the descriptors of a [UdevWatch]
+//! are mapped to their device IDs in memory. The `watch` links `udevd` stores in its run
+//! directory belong to the descriptors of the daemon, and are never touched.
+
+use std::collections::HashMap;
+use std::ffi;
+use std::io;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+use std::sync::{mpsc, Arc};
+
+use crate::inotify;
+use crate::{Error, ErrorScope, Result, Udev, UdevDevice};
+
+/// Watches device nodes, and emits synthetic `change` events when a writer closes a node.
+///
+/// Changed devices are sent on the channel passed to [new](Self::new). The watcher exposes a
+/// pollable file descriptor, so it can be added to the same event loop as a
+/// [UdevMonitor](crate::UdevMonitor).
+///
+/// ```no_run
+/// # use std::sync::{mpsc, Arc};
+/// # use udevrs::{watch::UdevWatch, Udev, UdevDevice};
+/// # fn main() -> udevrs::Result<()> {
+/// let udev = Arc::new(Udev::new());
+/// let (tx, rx) = mpsc::channel();
+/// let mut watch = UdevWatch::new(Arc::clone(&udev), tx)?;
+///
+/// let mut disk = UdevDevice::new_from_subsystem_sysname(udev, "block", "sda")?;
+/// watch.begin(&mut disk)?;
+///
+/// // after the watch file descriptor becomes readable
+/// watch.receive_events()?;
+/// for device in rx.try_iter() {
+///     println!("{}: {}", device.action(), device.syspath());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct UdevWatch {
+    udev: Arc<Udev>,
+    fd: OwnedFd,
+    sender: mpsc::Sender<UdevDevice>,
+    // device IDs of the watch descriptors
+    watches: HashMap<i32, String>,
+}
+
+impl UdevWatch {
+    /// Creates a new [UdevWatch], sending `change` events on the `sender` channel.
+    ///
+    /// The `inotify` file descriptor is non-blocking.
+    ///
+    /// Returns: `Ok(UdevWatch)` on success, `Err(Error)` otherwise.
+    pub fn new(udev: Arc<Udev>, sender: mpsc::Sender<UdevDevice>) -> Result<Self> {
+        // SAFETY: the arguments are valid, and the return value is checked before use.
+        let raw_fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC | libc::IN_NONBLOCK) };
+        if raw_fd < 0 {
+            let errno = io::Error::last_os_error();
+            let err_msg = format!("unable to init inotify watch, error: {raw_fd}, errno: {errno}");
+            log::error!("{err_msg}");
+            return Err(Error::os(ErrorScope::Device, err_msg, errno));
+        }
+
+        // SAFETY: `raw_fd` is a valid, open file descriptor that is exclusively owned here.
+        let fd = unsafe { OwnedFd::from_raw_fd(raw_fd) };
+
+        Ok(Self {
+            udev,
+            fd,
+            sender,
+            watches: HashMap::new(),
+        })
+    }
+
+    /// Gets the device ID watched by the watch descriptor `wd`, e.g. `b8:0`.
+    pub fn device_id(&self, wd: i32) -> Option<&str> {
+        self.watches.get(&wd).map(|id| id.as_str())
+    }
+
+    /// Gets the number of watched device nodes.
+    pub fn len(&self) -> usize {
+        self.watches.len()
+    }
+
+    /// Gets whether no device nodes are watched.
+    pub fn is_empty(&self) -> bool {
+        self.watches.is_empty()
+    }
+
+    /// Gets the `inotify` file descriptor.
+    ///
+    /// The descriptor becomes readable when a watched device node is closed after writing.
+    pub fn get_fd(&self) -> i32 {
+        self.fd.as_raw_fd()
+    }
+
+    /// Starts watching the device node of the [UdevDevice].
+    ///
+    /// Stores the watch descriptor in the [watch handle](UdevDevice::watch_handle), and maps it
+    /// to the device ID, see [device_id](Self::device_id).
+    ///
+    /// Returns: `Ok(i32)` with the watch descriptor on success, `Err(Error)` if the device has no
+    /// device node, or the watch cannot be added.
+    pub fn begin(&mut self, device: &mut UdevDevice) -> Result<i32> {
+        let devnode = device.get_devnode().unwrap_or_default().to_owned();
+        let id = device.get_id_filename().to_owned();
+
+        if devnode.is_empty() || id.is_empty() {
+            return Err(Error::UdevDevice(format!(
+                "unable to watch device without device node: {}",
+                device.syspath()
+            )));
+        }
+
+        let c_path = ffi::CString::new(devnode.as_str())?;
+        // SAFETY: arguments are valid, and pointers reference valid memory.
+        let wd = unsafe {
+            libc::inotify_add_watch(self.get_fd(), c_path.as_ptr(), libc::IN_CLOSE_WRITE)
+        };
+
+        if wd < 0 {
+            let errno = io::Error::last_os_error();
+            let err_msg =
+                format!("unable to add inotify watch: {devnode}, error: {wd}, errno: {errno}");
+            log::error!("{err_msg}");
+            return Err(Error::os(ErrorScope::Device, err_msg, errno));
+        }
+
+        // a reused watch descriptor replaces the stale mapping
+        self.watches.insert(wd, id.clone());
+
+        log::debug!("watching {devnode}, wd: {wd}, id: {id}");
+        device.set_watch_handle(wd);
+
+        Ok(wd)
+    }
+
+    /// Stops watching the device node of the [UdevDevice].
+    ///
+    /// Removes the watch descriptor mapping, and resets the
+    /// [watch handle](UdevDevice::watch_handle) to `-1`. Devices without a watch are ignored.
+    pub fn end(&mut self, device: &mut UdevDevice) {
+        let wd = device.watch_handle();
+        if wd <= 0 {
+            return;
+        }
+
+        log::debug!("removing watch, wd: {wd}, syspath: {}", device.syspath());
+
+        // SAFETY: arguments are valid, an already removed watch only returns an error.
+        unsafe { libc::inotify_rm_watch(self.get_fd(), wd) };
+        self.watches.remove(&wd);
+
+        device.set_watch_handle(-1);
+    }
+
+    /// Looks up the [UdevDevice] of the watch descriptor `wd`.
+    ///
+    /// Returns: `Ok(UdevDevice)` on success, `Err(Error)` if the watch descriptor is unknown,
+    /// or the device no longer exists.
+    pub fn lookup(&self, wd: i32) -> Result<UdevDevice> {
+        let id = self
+            .device_id(wd)
+            .ok_or(Error::UdevDevice(format!("unknown watch descriptor: {wd}")))?;
+
+        UdevDevice::new_from_device_id(Arc::clone(&self.udev), id)
+    }
+
+    /// Drains pending events from the watch.
+    ///
+    /// Sends a synthetic `change` [UdevDevice] for each device node closed after writing.
+    /// Watches removed by the kernel, e.g. for removed device nodes, are forgotten.
+    ///
+    /// Does not block, a return of `Ok(0)` means no device nodes changed since the last call.
+    ///
+    /// Returns: `Ok(count)` with the number of sent devices, `Err(Error)` otherwise.
+    pub fn receive_events(&mut self) -> Result<usize> {
+        let mut count = 0usize;
+        let mut ignored = Vec::new();
+
+        inotify::read_events(self.get_fd(), |event| {
+            if event.mask & libc::IN_IGNORED != 0 {
+                ignored.push(event.wd);
+            } else if event.mask & libc::IN_CLOSE_WRITE != 0 && self.send_change(event.wd) {
+                count = count.saturating_add(1);
+            }
+        })
+        .map_err(|errno| {
+            let err_msg = format!("unable to read watch events, errno: {errno}");
+            log::error!("{err_msg}");
+            Error::os(ErrorScope::Device, err_msg, errno)
+        })?;
+
+        for wd in ignored {
+            self.watches.remove(&wd);
+        }
+
+        Ok(count)
+    }
+
+    fn send_change(&self, wd: i32) -> bool {
+        let mut device = match self.lookup(wd) {
+            Ok(d) => d,
+            Err(err) => {
+                log::debug!("unable to look up device for watch, wd: {wd}, error: {err}");
+                return false;
+            }
+        };
+
+        log::debug!("device node closed after writing: {}", device.syspath());
+
+        device.set_action("change");
+        device.add_property("ACTION", "change");
+        device.set_watch_handle(wd);
+
+        self.sender.send(device).is_ok()
+    }
+}
+
+impl AsRawFd for UdevWatch {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl AsFd for UdevWatch {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+#[cfg(feature = "mio")]
+impl mio::event::Source for UdevWatch {
+    fn register(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> io::Result<()> {
+        mio::unix::SourceFd(&self.as_raw_fd()).register(registry, token, interests)
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> io::Result<()> {
+        mio::unix::SourceFd(&self.as_raw_fd()).reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &mio::Registry) -> io::Result<()> {
+        mio::unix::SourceFd(&self.as_raw_fd()).deregister(registry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FixtureDevice, SysfsFixture};
+    use std::fs;
+    use std::path::Path;
+
+    // memory device of the `/dev/null` device node
+    fn fixture() -> Result<SysfsFixture> {
+        SysfsFixture::new()?
+            .with_device(FixtureDevice::new("/devices/virtual/mem/null", "mem").with_devnum(1, 3))
+    }
+
+    #[test]
+    fn test_udev_watch() -> Result<()> {
+        let fixture = fixture()?;
+        let run = Path::new(fixture.run_path());
+        let node = run.join("mock0");
+        fs::write(&node, "")?;
+
+        let udev = fixture.udev();
+        let (tx, rx) = mpsc::channel();
+        let mut watch = UdevWatch::new(Arc::clone(&udev), tx)?;
+
+        assert!(watch
+            .begin(&mut UdevDevice::new(Arc::clone(&udev)))
+            .is_err());
+
+        let mut device = UdevDevice::new(Arc::clone(&udev))
+            .with_subsystem("mock")
+            .with_devpath("/devices/virtual/mock/mock0")
+            .with_devnode(node.to_str().unwrap_or_default())
+            .with_info_loaded(true);
+
+        let wd = watch.begin(&mut device)?;
+        assert_eq!(device.watch_handle(), wd);
+        assert_eq!(watch.device_id(wd), Some("+mock:mock0"));
+        assert_eq!(watch.len(), 1);
+
+        // the watch descriptors never touch the `udevd` watch links
+        assert!(fs::symlink_metadata(run.join("watch")).is_err());
+
+        // the mock device does not exist in `sysfs`, so no event is sent
+        fs::write(&node, "changed")?;
+        assert_eq!(watch.receive_events()?, 0);
+        assert!(rx.try_recv().is_err());
+
+        watch.end(&mut device);
+        assert_eq!(device.watch_handle(), -1);
+        assert_eq!(watch.device_id(wd), None);
+        assert!(watch.is_empty());
+        assert!(watch.lookup(wd).is_err());
+        watch.receive_events()?;
+
+        let mut null = UdevDevice::new_from_device_id(Arc::clone(&udev), "c1:3")?;
+        null.set_devnode("/dev/null");
+
+        let wd = watch.begin(&mut null)?;
+        fs::write("/dev/null", "")?;
+
+        assert_eq!(watch.receive_events()?, 1);
+        let changed = rx
+            .try_recv()
+            .map_err(|e| Error::UdevDevice(e.to_string()))?;
+        assert_eq!(changed.action(), "change");
+        assert_eq!(changed.watch_handle(), wd);
+        assert_eq!(changed.get_devnum(), libc::makedev(1, 3));
+
+        watch.end(&mut null);
+
+        Ok(())
+    }
+}
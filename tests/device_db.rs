@@ -0,0 +1,330 @@
+use std::sync::Arc;
+
+use udevrs::testing::SysfsFixture;
+use udevrs::{Result, Udev, UdevDevice};
+
+mod common;
+
+#[test]
+fn db_string_roundtrip() -> Result<()> {
+    common::init();
+
+    let udev = Arc::new(Udev::new());
+
+    let db = "S:disk/by-id/ata-TEST_1234\n\
+              S:disk/by-path/pci-0000:00:1f.2-ata-1\n\
+              L:-100\n\
+              W:7\n\
+              E:ID_BUS=ata\n\
+              E:ID_FS_LABEL=a=b\n\
+              G:systemd\n\
+              G:uaccess\n\
+              I:12345678\n";
+
+    let device = UdevDevice::from_db_string(Arc::clone(&udev), db)?;
+
+    let devlinks: Vec<&str> = device.devlinks_list().iter().map(|e| e.name()).collect();
+    assert_eq!(
+        devlinks,
+        [
+            "/dev/disk/by-id/ata-TEST_1234",
+            "/dev/disk/by-path/pci-0000:00:1f.2-ata-1"
+        ]
+    );
+    assert_eq!(device.devlink_priority(), -100);
+    assert_eq!(device.watch_handle(), 7);
+    assert_eq!(device.get_property_value("ID_BUS"), Some("ata"));
+    assert_eq!(device.get_property_value("ID_FS_LABEL"), Some("a=b"));
+
+    let tags: Vec<&str> = device.tags_list().iter().map(|e| e.name()).collect();
+    assert_eq!(tags, ["systemd", "uaccess"]);
+
+    assert_eq!(device.usec_initialized(), 12345678);
+    assert!(device.is_initialized());
+
+    assert_eq!(device.to_db_string(), db);
+
+    // re-parsing the written record gives the same record
+    let reparsed = UdevDevice::from_db_string(udev, device.to_db_string().as_str())?;
+    assert_eq!(reparsed.to_db_string(), db);
+
+    Ok(())
+}
+
+#[test]
+fn db_string_defaults() -> Result<()> {
+    let udev = Arc::new(Udev::new());
+
+    // empty records produce an empty device, and default values are not written
+    let device = UdevDevice::from_db_string(Arc::clone(&udev), "")?;
+    assert!(device.devlinks_list().is_empty());
+    assert!(device.tags_list().is_empty());
+    assert_eq!(device.to_db_string(), "");
+
+    // unknown record types and empty lines are ignored
+    let device = UdevDevice::from_db_string(Arc::clone(&udev), "X:unknown\n\nG:seat\n")?;
+    assert_eq!(device.to_db_string(), "G:seat\n");
+
+    // non-persistent properties are not written
+    let mut device = UdevDevice::new(Arc::clone(&udev));
+    device.add_property_from_string("DEVNAME=/dev/sda");
+    device.add_devlink("/dev/disk/by-label/root");
+    assert_eq!(device.to_db_string(), "S:disk/by-label/root\n");
+
+    Ok(())
+}
+
+#[test]
+fn db_string_invalid() {
+    let udev = Arc::new(Udev::new());
+
+    for db in [
+        "no separator",
+        "SS:two character type",
+        "L:not-a-number",
+        "W:1.5",
+        "I:-1",
+        "E:MISSING_VALUE",
+        "G:invalid tag",
+    ] {
+        assert!(
+            UdevDevice::from_db_string(Arc::clone(&udev), db).is_err(),
+            "expected error for: {db}"
+        );
+    }
+}
+
+#[test]
+fn db_update_delete() -> Result<()> {
+    common::init();
+
+    let fixture = SysfsFixture::new()?;
+    let udev = Arc::new(Udev::new().with_run_path(fixture.run_path()));
+
+    let mut device = UdevDevice::new(Arc::clone(&udev))
+        .with_subsystem("block")
+        .with_devpath("/devices/virtual/block/loop0")
+        .with_devnum(libc::makedev(7, 0));
+    device.add_devlink("/dev/disk/by-id/loop-test");
+    device.add_tag("systemd")?;
+    device.set_usec_initialized(42);
+
+    let filename = device.db_filename();
+    assert_eq!(filename, format!("{}/data/b7:0", fixture.run_path()));
+
+    device.update_db()?;
+
+    let db = std::fs::read_to_string(filename.as_str())?;
+    assert_eq!(db, "S:disk/by-id/loop-test\nG:systemd\nI:42\n");
+    assert_eq!(
+        UdevDevice::from_db_string(Arc::clone(&udev), db.as_str())?.to_db_string(),
+        db
+    );
+
+    device.delete_db()?;
+    assert!(!std::path::Path::new(filename.as_str()).exists());
+
+    // deleting a missing file is not an error
+    device.delete_db()?;
+
+    // empty devices without a device number or interface index do not get a DB file
+    let mut empty = UdevDevice::new(udev)
+        .with_subsystem("pci")
+        .with_devpath("/devices/pci0000:00/0000:00:1f.2");
+    empty.update_db()?;
+    assert!(!std::path::Path::new(empty.db_filename().as_str()).exists());
+
+    Ok(())
+}
+
+#[test]
+fn tag_index_update() -> Result<()> {
+    common::init();
+
+    let fixture = SysfsFixture::new()?;
+    let run_str = fixture.run_path();
+    let udev = Arc::new(Udev::new().with_run_path(run_str));
+
+    let tag_file = |tag: &str| std::path::Path::new(run_str).join(format!("tags/{tag}/n1"));
+
+    let mut old = UdevDevice::new(Arc::clone(&udev))
+        .with_subsystem("net")
+        .with_ifindex(1);
+    old.add_tag("systemd")?;
+    old.add_tag("seat")?;
+    old.tag_index_update(None, true)?;
+
+    assert!(tag_file("systemd").exists());
+    assert!(tag_file("seat").exists());
+
+    // tags missing from the new device are removed
+    let mut new = UdevDevice::new(Arc::clone(&udev))
+        .with_subsystem("net")
+        .with_ifindex(1);
+    new.add_tag("systemd")?;
+    new.add_tag("uaccess")?;
+    new.tag_index_update(Some(&old), true)?;
+
+    assert!(tag_file("systemd").exists());
+    assert!(tag_file("uaccess").exists());
+    assert!(!tag_file("seat").exists());
+
+    // removing the device removes all its tags
+    new.tag_index_update(None, false)?;
+
+    assert!(!tag_file("systemd").exists());
+    assert!(!tag_file("uaccess").exists());
+
+    // devices without an ID filename cannot be indexed
+    assert!(UdevDevice::new(udev).tag_index_update(None, true).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn db_path_diagnostics() -> Result<()> {
+    common::init();
+
+    let fixture = SysfsFixture::new()?;
+    let run_path = std::path::Path::new(fixture.run_path());
+    let udev = Arc::new(Udev::new().with_run_path(fixture.run_path()));
+
+    let mut device = UdevDevice::new(Arc::clone(&udev))
+        .with_subsystem("block")
+        .with_devpath("/devices/virtual/block/loop1")
+        .with_devnum(libc::makedev(7, 1));
+    device.set_usec_initialized(42);
+
+    assert_eq!(device.db_path(), Some(run_path.join("data").join("b7:1")));
+    assert!(!device.has_db_entry());
+
+    device.update_db()?;
+    assert!(device.has_db_entry());
+
+    device.delete_db()?;
+    assert!(!device.has_db_entry());
+
+    // devices without a subsystem have no ID filename
+    let mut unknown = UdevDevice::new(udev).with_devpath("/devices/unknown");
+    assert_eq!(unknown.db_path(), None);
+    assert!(!unknown.has_db_entry());
+
+    Ok(())
+}
+
+#[test]
+fn read_db_fixture() -> Result<()> {
+    common::init();
+
+    let fixture = SysfsFixture::new()?;
+    let run_path = std::path::Path::new(fixture.run_path());
+    std::fs::copy("tests/fixtures/db/b8:0", run_path.join("data").join("b8:0"))?;
+
+    let udev = Arc::new(Udev::new().with_run_path(fixture.run_path()));
+    let mut device = UdevDevice::new(Arc::clone(&udev))
+        .with_subsystem("block")
+        .with_devpath("/devices/pci0000:00/0000:00:17.0/ata1/host0/target0:0:0/0:0:0:0/block/sda")
+        .with_devnum(libc::makedev(8, 0));
+
+    device.read_db()?;
+
+    // every record is read, not only the first one
+    let devlinks: Vec<&str> = device.devlinks_list().iter().map(|e| e.name()).collect();
+    assert_eq!(
+        devlinks,
+        [
+            "/dev/disk/by-id/ata-MOCK_SSD_0001",
+            "/dev/disk/by-path/pci-0000:00:17.0-ata-1",
+            "/dev/disk/by-diskseq/1"
+        ]
+    );
+    assert_eq!(device.watch_handle(), 3);
+    assert_eq!(device.usec_initialized(), 1_700_000_000_000_000);
+    assert!(device.is_initialized());
+
+    for (key, value) in [
+        ("ID_ATA", "1"),
+        ("ID_TYPE", "disk"),
+        ("ID_BUS", "ata"),
+        ("ID_MODEL", "MOCK_SSD"),
+        ("ID_SERIAL", "MOCK_SSD_0001"),
+        ("ID_PART_TABLE_TYPE", "gpt"),
+        ("ID_VENDOR_ENC", "Mock\\x20\u{fffd}"),
+    ] {
+        assert_eq!(device.get_property_value(key), Some(value), "{key}");
+    }
+
+    // malformed records are skipped, and do not reset the valid ones
+    assert_eq!(device.devlink_priority(), 0);
+
+    let tags: Vec<&str> = device.tags_list().iter().map(|e| e.name()).collect();
+    assert_eq!(tags, ["systemd", "uaccess"]);
+    assert_eq!(device.db_version(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn read_uevent_fixture() -> Result<()> {
+    common::init();
+
+    let fixture = SysfsFixture::new()?;
+    let syspath = fixture.root().join("sda");
+    std::fs::create_dir_all(&syspath)?;
+    std::fs::copy("tests/fixtures/uevent/sda", syspath.join("uevent"))?;
+
+    let mut device =
+        UdevDevice::new(Arc::new(Udev::new())).with_syspath(syspath.to_str().unwrap_or_default());
+
+    device.read_uevent_file()?;
+
+    assert_eq!(device.devnum(), libc::makedev(8, 0));
+    assert_eq!(device.devtype(), "disk");
+    assert_eq!(device.get_property_value("DISKSEQ"), Some("1"));
+    assert_eq!(
+        device.get_property_value("ID_MOCK_BAD"),
+        Some("\u{fffd}\u{fffd}")
+    );
+
+    // lines after an invalid UTF-8 value are still read
+    assert_eq!(device.get_property_value("DEVMODE"), Some("0640"));
+    assert_eq!(device.devnode_mode().bits(), 0o640);
+
+    Ok(())
+}
+
+#[test]
+fn db_string_current_tags() -> Result<()> {
+    let db = "G:systemd\nG:seat\nQ:seat\nV:1\n";
+    let device = UdevDevice::from_db_string(Arc::new(Udev::new()), db)?;
+
+    let tags: Vec<&str> = device.tags_list().iter().map(|e| e.name()).collect();
+    assert_eq!(tags, ["systemd", "seat"]);
+    assert_eq!(device.db_version(), 1);
+
+    let mut device = device.with_info_loaded(true);
+    assert!(device.has_tag("systemd"));
+    assert!(!device.has_current_tag("systemd"));
+    assert!(device.has_current_tag("seat"));
+    assert_eq!(
+        device.get_current_tags_list_entry().map(|e| e.name()),
+        Some("seat")
+    );
+    assert_eq!(device.to_db_string(), db);
+
+    // without a version, all tags are current
+    let mut device =
+        UdevDevice::from_db_string(Arc::new(Udev::new()), "G:systemd\n")?.with_info_loaded(true);
+    assert!(device.has_current_tag("systemd"));
+    assert_eq!(device.to_db_string(), "G:systemd\n");
+
+    // version 1 records without current tags have none
+    let mut device = UdevDevice::from_db_string(Arc::new(Udev::new()), "G:systemd\nV:1\n")?
+        .with_info_loaded(true);
+    assert!(!device.has_current_tag("systemd"));
+    assert!(device.get_current_tags_list_entry().is_none());
+
+    assert!(UdevDevice::from_db_string(Arc::new(Udev::new()), "V:one").is_err());
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use udevrs::testing::{FixtureDevice, SysfsFixture};
+use udevrs::{Result, SysPath, Udev, UdevDevice, UdevEnumerate};
+
+mod common;
+
+#[test]
+fn subsystem_enumerators() -> Result<()> {
+    common::init();
+
+    let udev = Arc::new(Udev::new());
+
+    let net = udev.net_interfaces()?;
+    assert!(net.iter().all(|d| d.subsystem() == "net"));
+    if std::fs::metadata("/sys/class/net/lo").is_ok() {
+        assert!(net.iter().any(|d| d.syspath().ends_with("/lo")));
+    }
+
+    let block = udev.block_devices()?;
+    assert!(block.iter().all(|d| d.subsystem() == "block"));
+
+    let input = udev.input_devices()?;
+    assert!(input.iter().all(|d| d.subsystem() == "input"));
+
+    Ok(())
+}
+
+#[test]
+fn canonical_syspaths() -> Result<()> {
+    common::init();
+
+    // fake tree with the loopback interface, reached through its class symlink
+    let fixture = SysfsFixture::new()?.with_device(
+        FixtureDevice::new("/devices/virtual/net/lo", "net")
+            .with_property("INTERFACE", "lo")
+            .with_ifindex(1),
+    )?;
+
+    let sys_path = fixture.sys_path();
+    let udev = fixture.udev();
+
+    let class_path = SysPath::new_in(sys_path, format!("{sys_path}/class/net/lo"))?;
+    let class_dev = UdevDevice::new_from_syspath(Arc::clone(&udev), &class_path)?;
+    assert_eq!(
+        class_dev.syspath(),
+        format!("{sys_path}/devices/virtual/net/lo")
+    );
+    assert_eq!(class_dev.canonical_syspath()?.as_str(), class_dev.syspath());
+
+    let canonical = SysPath::new_in(sys_path, class_dev.syspath())?;
+    let dev = UdevDevice::new_from_syspath(Arc::clone(&udev), &canonical)?;
+
+    assert_eq!(dev.devpath(), class_dev.devpath());
+    assert_eq!(dev.get_id_filename(), class_dev.get_id_filename());
+    assert_eq!(dev, class_dev);
+
+    // both paths add the same canonical syspath
+    let mut enumerate = UdevEnumerate::new(Arc::clone(&udev));
+    enumerate.add_syspath(class_dev.syspath())?;
+    enumerate.add_syspath(dev.syspath())?;
+    assert!(enumerate
+        .devices()
+        .iter()
+        .all(|d| d.syspath() == dev.syspath()));
+
+    let net = udev.net_interfaces()?;
+    assert_eq!(
+        net.iter().filter(|d| d.syspath() == dev.syspath()).count(),
+        1
+    );
+
+    Ok(())
+}
+
+#[test]
+fn fake_sys_tree() -> Result<()> {
+    common::init();
+
+    let fixture = SysfsFixture::new()?.with_device(
+        FixtureDevice::new("/devices/virtual/mock/mock0", "mock").with_devnum(240, 0),
+    )?;
+    let udev = fixture.udev();
+
+    let mut enumerate = UdevEnumerate::new(Arc::clone(&udev));
+    enumerate.add_match_subsystem("mock")?;
+    enumerate.scan_devices()?;
+
+    let syspaths: Vec<&str> = enumerate.devices().iter().map(|d| d.syspath()).collect();
+    assert_eq!(syspaths.len(), 1);
+    assert!(syspaths[0].ends_with("/sys/devices/virtual/mock/mock0"));
+
+    let dev = UdevDevice::new_from_subsystem_sysname(Arc::clone(&udev), "mock", "mock0")?;
+    assert_eq!(dev.devpath(), "/devices/virtual/mock/mock0");
+    assert_eq!(dev.get_subsystem(), "mock");
+    assert_eq!(dev.get_devnum(), libc::makedev(240, 0));
+
+    let by_devnum = UdevDevice::new_from_device_id(Arc::clone(&udev), "c240:0")?;
+    assert_eq!(by_devnum.syspath(), dev.syspath());
+
+    // paths outside of the context sys path are not devices of the fake tree
+    assert!(udevrs::udev_device_new_from_syspath(Arc::clone(&udev), "/sys/devices").is_err());
+
+    Ok(())
+}
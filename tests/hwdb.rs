@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
-use udevrs::{Error, Result, Udev, UdevHwdb};
+use udevrs::testing::SysfsFixture;
+use udevrs::{Error, Result, Udev, UdevDevice, UdevHwdb};
 
 mod common;
 
@@ -8,7 +9,7 @@ mod common;
 fn parse_hwdb() -> Result<()> {
     common::init();
 
-    std::env::set_var("UDEV_HWDB_BIN", "./hwdb.bin");
+    std::env::set_var("UDEV_HWDB_BIN", "./tests/hwdb.bin");
     let udev = Arc::new(Udev::new());
 
     let mut hwdb = UdevHwdb::new(udev)?;
@@ -21,7 +22,7 @@ fn parse_hwdb() -> Result<()> {
         ))?
         .iter()
         .find(|e| e.name() == "ID_VENDOR_FROM_DATABASE")
-        .map(|e| (e.value()));
+        .map(|e| e.value());
 
     assert_eq!(root_hub, Some("Linux Foundation"));
 
@@ -33,7 +34,7 @@ fn parse_hwdb() -> Result<()> {
         ))?
         .iter()
         .find(|e| e.name() == "ID_VENDOR_FROM_DATABASE")
-        .map(|e| (e.value()));
+        .map(|e| e.value());
 
     assert_eq!(pci, Some("Intel Corporation"));
 
@@ -45,7 +46,7 @@ fn parse_hwdb() -> Result<()> {
         ))?
         .iter()
         .find(|e| e.name() == "ID_MODEL_FROM_DATABASE")
-        .map(|e| (e.value()));
+        .map(|e| e.value());
 
     assert_eq!(root_hub_30, Some("3.0 root hub"));
 
@@ -57,7 +58,7 @@ fn parse_hwdb() -> Result<()> {
         ))?
         .iter()
         .find(|e| e.name() == "ID_USB_CLASS_FROM_DATABASE")
-        .map(|e| (e.value()));
+        .map(|e| e.value());
 
     assert_eq!(hid, Some("Human Interface Device"));
 
@@ -70,11 +71,11 @@ fn parse_hwdb() -> Result<()> {
     let subclass = query
         .iter()
         .find(|e| e.name() == "ID_USB_SUBCLASS_FROM_DATABASE")
-        .map(|e| (e.value()));
+        .map(|e| e.value());
     let protocol = query
         .iter()
         .find(|e| e.name() == "ID_USB_PROTOCOL_FROM_DATABASE")
-        .map(|e| (e.value()));
+        .map(|e| e.value());
 
     assert_eq!(subclass, Some("Boot Interface Subclass"));
     assert_eq!(protocol, Some("Keyboard"));
@@ -87,7 +88,7 @@ fn parse_hwdb() -> Result<()> {
         ))?
         .iter()
         .find(|e| e.name() == "ID_USB_PROTOCOL_FROM_DATABASE")
-        .map(|e| (e.value()));
+        .map(|e| e.value());
 
     assert_eq!(at, Some("AT-commands (3G)"));
 
@@ -98,7 +99,7 @@ fn parse_hwdb() -> Result<()> {
 fn invalid_queries() -> Result<()> {
     common::init();
 
-    std::env::set_var("UDEV_HWDB_BIN", "./hwdb.bin");
+    std::env::set_var("UDEV_HWDB_BIN", "./tests/hwdb.bin");
     let udev = Arc::new(Udev::new());
 
     let mut hwdb = UdevHwdb::new(udev)?;
@@ -113,3 +114,228 @@ fn invalid_queries() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn mapped_database() -> Result<()> {
+    common::init();
+
+    std::env::set_var("UDEV_HWDB_BIN", "./tests/hwdb.bin");
+    let udev = Arc::new(Udev::new());
+
+    let hwdb = UdevHwdb::new(udev)?;
+    let file_len = std::fs::metadata(hwdb.hwdb_path())?.len() as usize;
+
+    assert_eq!(hwdb.as_bytes().len(), file_len);
+    assert_eq!(hwdb.header().file_size() as usize, file_len);
+    assert!(UdevHwdb::parse_nodes(hwdb.header(), hwdb.as_bytes())
+        .next()
+        .is_some());
+
+    Ok(())
+}
+
+#[test]
+fn query_semantics() -> Result<()> {
+    common::init();
+
+    std::env::set_var("UDEV_HWDB_BIN", "./tests/hwdb.bin");
+    let udev = Arc::new(Udev::new());
+
+    let mut hwdb = UdevHwdb::new(udev)?;
+
+    // full modalias matches exact vendor/product, and class wildcard patterns
+    let query = hwdb
+        .query("usb:v1D6Bp0002d0606dc09dsc00dp01ic09isc00ip00in00")
+        .ok_or(Error::UdevHwdb(
+            "no matching entry found for usb:v1D6Bp0002".into(),
+        ))?;
+
+    let mut keys: Vec<&str> = query.iter().map(|e| e.name()).collect();
+    let find = |key: &str| query.iter().find(|e| e.name() == key).map(|e| e.value());
+
+    assert_eq!(find("ID_VENDOR_FROM_DATABASE"), Some("Linux Foundation"));
+    assert_eq!(find("ID_MODEL_FROM_DATABASE"), Some("2.0 root hub"));
+    assert_eq!(find("ID_USB_CLASS_FROM_DATABASE"), Some("Hub"));
+    assert_eq!(find("ID_USB_PROTOCOL_FROM_DATABASE"), Some("Single TT"));
+
+    // duplicate keys are filtered
+    let keys_len = keys.len();
+    keys.sort_unstable();
+    keys.dedup();
+    assert_eq!(keys.len(), keys_len);
+
+    // model keys are not added for a vendor-only query
+    let query = hwdb.query("usb:v1D6B").ok_or(Error::UdevHwdb(
+        "no matching entry found for usb:v1D6B".into(),
+    ))?;
+    assert!(query.iter().all(|e| e.name() != "ID_MODEL_FROM_DATABASE"));
+
+    Ok(())
+}
+
+#[test]
+fn query_by_device() -> Result<()> {
+    common::init();
+
+    std::env::set_var("UDEV_HWDB_BIN", "./tests/hwdb.bin");
+    let udev = Arc::new(Udev::new());
+
+    let mut hwdb = UdevHwdb::new(Arc::clone(&udev))?;
+
+    // modalias from the device properties
+    let mut dev = UdevDevice::new(Arc::clone(&udev));
+    dev.add_property_from_string("MODALIAS=usb:v1D6Bp0003d0606dc09dsc00dp03ic09isc00ip00in00");
+
+    let model = hwdb
+        .get_properties_for_device(&mut dev)
+        .ok_or(Error::UdevHwdb("no matching entry found for device".into()))?
+        .iter()
+        .find(|e| e.name() == "ID_MODEL_FROM_DATABASE")
+        .map(|e| e.value().to_owned());
+
+    assert_eq!(model.as_deref(), Some("3.0 root hub"));
+
+    // modalias composed from USB sys attributes
+    let fixture = SysfsFixture::new()?;
+    let dir = fixture.root().join("usb1");
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(dir.join("idVendor"), "1d6b\n")?;
+    std::fs::write(dir.join("idProduct"), "0002\n")?;
+    std::fs::write(dir.join("product"), "EHCI Host Controller\n")?;
+
+    let mut dev = UdevDevice::new(Arc::clone(&udev))
+        .with_syspath(dir.to_str().unwrap_or_default())
+        .with_subsystem("usb")
+        .with_devtype("usb_device");
+
+    assert_eq!(
+        UdevHwdb::compose_modalias(&mut dev).as_deref(),
+        Some("usb:v1D6Bp0002:EHCI Host Controller")
+    );
+
+    let vendor = hwdb
+        .get_properties_for_device(&mut dev)
+        .ok_or(Error::UdevHwdb("no matching entry found for device".into()))?
+        .iter()
+        .find(|e| e.name() == "ID_VENDOR_FROM_DATABASE")
+        .map(|e| e.value().to_owned());
+
+    assert_eq!(vendor.as_deref(), Some("Linux Foundation"));
+
+    // no modalias
+    let mut dev = UdevDevice::new(udev);
+    assert!(hwdb.get_properties_for_device(&mut dev).is_none());
+
+    Ok(())
+}
+
+#[test]
+fn open_and_load() -> Result<()> {
+    common::init();
+
+    let udev = Arc::new(Udev::new());
+
+    let file = UdevHwdb::open_path(Arc::clone(&udev), "./tests/hwdb.bin")?;
+    assert_eq!(file.path(), "./tests/hwdb.bin");
+    assert_eq!(file.header().file_size() as usize, file.len());
+
+    let mut owned = file.load()?;
+    let mut mapped = UdevHwdb::new_from_path(Arc::clone(&udev), "./tests/hwdb.bin")?;
+    assert_eq!(owned.as_bytes(), mapped.as_bytes());
+
+    let owned_vendor = owned
+        .query("usb:v1D6B")
+        .and_then(|l| l.entry_by_name("ID_VENDOR_FROM_DATABASE"))
+        .map(|e| e.value().to_owned());
+    let mapped_vendor = mapped
+        .query("usb:v1D6B")
+        .and_then(|l| l.entry_by_name("ID_VENDOR_FROM_DATABASE"))
+        .map(|e| e.value().to_owned());
+    assert_eq!(owned_vendor.as_deref(), Some("Linux Foundation"));
+    assert_eq!(owned_vendor, mapped_vendor);
+
+    assert!(UdevHwdb::open_path(Arc::clone(&udev), "./tests/missing.bin").is_err());
+
+    // truncated databases are rejected when opening, without panicking
+    let hwdb_bin = std::fs::read("./tests/hwdb.bin")?;
+    let fixture = SysfsFixture::new()?;
+    let truncated = fixture.root().join("hwdb.bin");
+
+    for len in [16, 80, hwdb_bin.len() / 2] {
+        std::fs::write(&truncated, &hwdb_bin[..len])?;
+        assert!(UdevHwdb::open_path(Arc::clone(&udev), truncated.to_str().unwrap()).is_err());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn corrupt_database() -> Result<()> {
+    common::init();
+
+    let udev = Arc::new(Udev::new());
+    let hwdb_bin = std::fs::read("./tests/hwdb.bin")?;
+
+    let mut hwdb = UdevHwdb::new_from_bytes(Arc::clone(&udev), hwdb_bin.clone())?;
+    assert!(hwdb.query("usb:v1D6B").is_some());
+
+    // overwrite trie offsets in the nodes section with out-of-range values, lookups must fail
+    // with errors, or return no properties, instead of panicking
+    let nodes_start = hwdb.header().header_size() as usize;
+    let nodes_end = nodes_start + hwdb.header().nodes_len() as usize;
+
+    let step = (nodes_end - nodes_start) / 32;
+
+    for (i, off) in (nodes_start..nodes_end).step_by(step.max(1)).enumerate() {
+        let mut corrupt = hwdb_bin.clone();
+        let garbage = match i % 3 {
+            0 => u64::MAX,
+            1 => corrupt.len() as u64 - 1,
+            _ => 0x0101_0101,
+        };
+        let end = (off + 8).min(nodes_end);
+        corrupt[off..end].copy_from_slice(&garbage.to_le_bytes()[..end - off]);
+
+        let mut hwdb = UdevHwdb::new_from_bytes(Arc::clone(&udev), corrupt)?;
+        hwdb.query("usb:v1D6Bp0002d0606dc09dsc00dp01ic09isc00ip00in00");
+        hwdb.query("pci:v00008086d00001C2D*");
+        UdevHwdb::parse_nodes(hwdb.header(), hwdb.as_bytes())
+            .take(1024)
+            .count();
+    }
+
+    assert!(UdevHwdb::new_from_bytes(udev, hwdb_bin[..80].to_vec()).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn validate_database() -> Result<()> {
+    common::init();
+
+    let udev = Arc::new(Udev::new());
+    let hwdb = UdevHwdb::new_from_path(Arc::clone(&udev), "./tests/hwdb.bin")?;
+
+    hwdb.validate()?;
+
+    let stats = hwdb.stats()?;
+    assert!(stats.node_count() > 0);
+    assert!(stats.value_count() > 0);
+    assert!(stats.max_depth() > 0);
+    assert_eq!(stats.nodes_used(), stats.nodes_len());
+    assert!(stats.strings_utilization() > 0.5 && stats.strings_utilization() <= 1.0);
+
+    // swap the first two children of the root node
+    let mut unsorted = hwdb.as_bytes().to_vec();
+    let children = (hwdb.header().nodes_root_off() + hwdb.header().node_size()) as usize;
+    let child_size = hwdb.header().child_entry_size() as usize;
+    unsorted.swap(children, children + child_size);
+
+    let err = UdevHwdb::new_from_bytes(udev, unsorted)?
+        .validate()
+        .err()
+        .map(|err| err.to_string());
+    assert!(err.is_some_and(|err| err.contains("not sorted")));
+
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use udevrs::testing::SysfsFixture;
+use udevrs::{DeviceAction, Result, Udev, UdevDevice, UdevEventReplay, UdevMonitor};
+
+mod common;
+
+fn mock_device(udev: &Arc<Udev>, action: &str, subsystem: &str, seqnum: u64) -> Result<UdevDevice> {
+    let mut device = UdevDevice::new(Arc::clone(udev));
+
+    device.add_property("ACTION", action);
+    device.add_property(
+        "DEVPATH",
+        format!("/devices/virtual/{subsystem}/mock0").as_str(),
+    );
+    device.add_property("SUBSYSTEM", subsystem);
+    // the header hashes use the subsystem field, like real devices read from `sys`
+    device.set_subsystem(subsystem);
+    device.add_property("SEQNUM", seqnum.to_string().as_str());
+    device.add_property("ID_MOCK", "1");
+    device.add_tag("mock")?;
+
+    Ok(device)
+}
+
+#[test]
+fn loopback_send_receive() -> Result<()> {
+    common::init();
+
+    let udev = Arc::new(Udev::new());
+    let (mut sender, mut receiver) = UdevMonitor::new_loopback(Arc::clone(&udev))?;
+
+    sender.send_device(None, &mut mock_device(&udev, "add", "mock", 1)?)?;
+
+    let device = receiver
+        .receive_device_timeout(Duration::from_secs(1))?
+        .expect("loopback device");
+    assert_eq!(device.action(), "add");
+    assert_eq!(device.action_type(), DeviceAction::Add);
+    assert_eq!(device.devpath(), "/devices/virtual/mock/mock0");
+    assert_eq!(device.subsystem(), "mock");
+    assert_eq!(device.seqnum(), 1);
+    assert_eq!(device.get_property_value("ID_MOCK"), Some("1"));
+    assert!(device.is_initialized());
+
+    // messages go through the receiver socket filter, and are matched again in userspace
+    receiver.filter_add_match_subsystem_devtype("mock", "")?;
+    receiver.filter_update()?;
+
+    sender.send_device(None, &mut mock_device(&udev, "add", "other", 2)?)?;
+    sender.send_device(None, &mut mock_device(&udev, "remove", "mock", 3)?)?;
+
+    let device = receiver
+        .receive_device_timeout(Duration::from_secs(1))?
+        .expect("filtered loopback device");
+    assert_eq!(device.action(), "remove");
+    assert_eq!(device.seqnum(), 3);
+
+    let stats = receiver.stats();
+    // the socket filter drops the other subsystem in the kernel
+    assert_eq!(stats.received(), 2);
+    assert_eq!(stats.filter_rejected(), 0);
+    assert_eq!(stats.sender_rejected(), 0);
+
+    // unrelated monitors are not trusted senders
+    let (mut other, _) = UdevMonitor::new_loopback(Arc::clone(&udev))?;
+    let mut device = mock_device(&udev, "add", "mock", 4)?;
+    other.send_device(Some(&mut receiver), &mut device)?;
+    assert!(!matches!(
+        receiver.receive_device_timeout(Duration::from_millis(100)),
+        Ok(Some(_))
+    ));
+    assert_eq!(receiver.stats().sender_rejected(), 1);
+    assert!(!receiver.stats().has_lost_events());
+
+    Ok(())
+}
+
+#[test]
+fn loopback_peek_next() -> Result<()> {
+    common::init();
+
+    let udev = Arc::new(Udev::new());
+    let (mut sender, mut receiver) = UdevMonitor::new_loopback(Arc::clone(&udev))?;
+
+    assert!(receiver.peek_next().is_err());
+
+    sender.send_device(None, &mut mock_device(&udev, "change", "mock", 7)?)?;
+    sender.send_device(None, &mut mock_device(&udev, "remove", "mock", 8)?)?;
+
+    // peeking leaves the event queued
+    let event = receiver.peek_next()?;
+    assert_eq!(event, receiver.peek_next()?);
+    assert_eq!(event.action(), "change");
+    assert_eq!(event.action_type(), DeviceAction::Change);
+    assert_eq!(event.devpath(), "/devices/virtual/mock/mock0");
+    assert_eq!(event.subsystem(), "mock");
+    assert_eq!(event.seqnum(), 7);
+    assert!(event.is_initialized());
+
+    let device = receiver.receive_device()?;
+    assert_eq!(device.seqnum(), event.seqnum());
+
+    assert_eq!(receiver.peek_next()?.seqnum(), 8);
+    receiver.skip_next()?;
+    assert!(receiver.peek_next().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn loopback_record_replay() -> Result<()> {
+    common::init();
+
+    let fixture = SysfsFixture::new()?;
+    let capture = fixture.root().join("capture.cap");
+
+    let udev = Arc::new(Udev::new());
+    let (mut sender, mut receiver) = UdevMonitor::new_loopback(Arc::clone(&udev))?;
+
+    receiver.record_to(capture.as_path())?;
+    assert!(receiver.is_recording());
+
+    // messages filtered in userspace are recorded as well
+    receiver.filter_add_match_devpath_glob("/devices/virtual/mock/*")?;
+    receiver.filter_update()?;
+
+    sender.send_device(None, &mut mock_device(&udev, "add", "other", 1)?)?;
+    sender.send_device(None, &mut mock_device(&udev, "add", "mock", 2)?)?;
+
+    let device = receiver
+        .receive_device_timeout(Duration::from_secs(1))?
+        .expect("recorded device");
+    assert_eq!(device.seqnum(), 2);
+
+    receiver.stop_recording();
+    sender.send_device(None, &mut mock_device(&udev, "remove", "mock", 3)?)?;
+    receiver.receive_device_timeout(Duration::from_secs(1))?;
+
+    let replayed: Vec<UdevDevice> =
+        UdevEventReplay::open(Arc::clone(&udev), capture.as_path())?.collect::<Result<_>>()?;
+
+    assert_eq!(replayed.len(), 2);
+    assert_eq!(replayed[0].subsystem(), "other");
+    assert_eq!(replayed[1].seqnum(), 2);
+    assert_eq!(replayed[1].get_property_value("ID_MOCK"), Some("1"));
+    assert!(replayed[1].is_initialized());
+
+    // the replayed devices go through the same filter
+    let mut replayed = replayed;
+    assert!(!receiver.passes_filter(&mut replayed[0]));
+    assert!(receiver.passes_filter(&mut replayed[1]));
+
+    Ok(())
+}